@@ -242,7 +242,7 @@ mod tests {
 		construct_block(
 			1,
 			[69u8; 32].into(),
-			hex!("786071057714fdd6ea4595eecd4a0f327908d65f462ff5bca0f700fafce588c9").into(),
+			hex!("a696c1e23ee14fd64ce011d9ba551038dddf4b4fdadeab542f808144b8a55855").into(),
 			vec![BareExtrinsic {
 				signed: alice(),
 				index: 0,
@@ -255,7 +255,7 @@ mod tests {
 		construct_block(
 			2,
 			block1().1,
-			hex!("a7f1259cc6b2fa758542f2996e737f8f0de9dec3a9d32641da348178f48b9fc2").into(),
+			hex!("cbf0fb97c30aea8f0039618134481c7e7e756cbc022dbd29bd80fea218a788fa").into(),
 			vec![
 				BareExtrinsic {
 					signed: bob(),
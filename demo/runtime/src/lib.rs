@@ -74,6 +74,9 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 /// Version module for this concrete runtime.
 pub type Version = version::Module<Concrete>;
 
+/// Storage migrations of the modules, run once the spec version is bumped.
+pub type RuntimeUpgrade = version::Upgrade<Concrete, ()>;
+
 impl version::Trait for Concrete {
 	const VERSION: RuntimeVersion = VERSION;
 }
@@ -83,6 +86,7 @@ impl HasPublicAux for Concrete {
 }
 
 impl system::Trait for Concrete {
+	const MAX_BLOCK_WEIGHT: u32 = 4 * 1024 * 1024;
 	type Index = Index;
 	type BlockNumber = BlockNumber;
 	type Hash = Hash;
@@ -90,6 +94,7 @@ impl system::Trait for Concrete {
 	type Digest = generic::Digest<Vec<u8>>;
 	type AccountId = AccountId;
 	type Header = generic::Header<BlockNumber, BlakeTwo256, Vec<u8>>;
+	type Event = Event;
 }
 
 /// System module for this concrete runtime.
@@ -98,6 +103,7 @@ pub type System = system::Module<Concrete>;
 impl consensus::Trait for Concrete {
 	type PublicAux = <Self as HasPublicAux>::PublicAux;
 	type SessionKey = SessionKey;
+	type OnMisbehaviorReported = ();
 }
 
 /// Consensus module for this concrete runtime.
@@ -132,6 +138,7 @@ impl staking::Trait for Concrete {
 	type Balance = Balance;
 	type DetermineContractAddress = BlakeTwo256;
 	type AccountIndex = AccountIndex;
+	type Event = Event;
 }
 
 /// Staking module for this concrete runtime.
@@ -151,6 +158,14 @@ pub type Council = council::Module<Concrete>;
 /// Council voting module for this concrete runtime.
 pub type CouncilVoting = council::voting::Module<Concrete>;
 
+impl_outer_event! {
+	#[derive(Clone, PartialEq, Eq)]
+	#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+	pub enum Event for Concrete {
+		staking = 2,
+	}
+}
+
 impl_outer_dispatch! {
 	#[derive(Clone, PartialEq, Eq)]
 	#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
@@ -192,7 +207,8 @@ pub type Extrinsic = generic::Extrinsic<Address, Index, Call>;
 pub type BareExtrinsic = generic::Extrinsic<AccountId, Index, Call>;
 /// Executive: handles dispatch to the various modules.
 pub type Executive = executive::Executive<Concrete, Block, Staking, Staking,
-	(((((), Council), Democracy), Staking), Session)>;
+	(((((), Council), Democracy), Staking), Session),
+	RuntimeUpgrade>;
 
 impl_outer_config! {
 	pub struct GenesisConfig for Concrete {
@@ -214,6 +230,7 @@ pub mod api {
 		apply_extrinsic => |extrinsic| super::Executive::apply_extrinsic(extrinsic),
 		execute_block => |block| super::Executive::execute_block(block),
 		finalise_block => |()| super::Executive::finalise_block(),
+		runtime_upgrade => |()| super::RuntimeUpgrade::apply(),
 		validator_count => |()| super::Session::validator_count(),
 		validators => |()| super::Session::validators()
 	);
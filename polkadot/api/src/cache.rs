@@ -0,0 +1,283 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Memoization of per-block API calls.
+//!
+//! Calls such as `session_keys` and `duty_roster` are made at the same block by
+//! several subsystems each round. Results are cached per block hash; calls at
+//! a block number are never cached, since the block a number refers to may change.
+//!
+//! Only a bounded number of the most recently queried blocks is kept, so states
+//! which have since been pruned drop out of the cache. A failed call at a block
+//! also discards everything cached for it.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use primitives::{AccountId, Balance, Block, BlockId, BlockNumber, Hash, Heartbeat, Index, MisbehaviorReport, SessionKey,
+	Timestamp, UncheckedExtrinsic};
+use primitives::parachain::{AgreementMisbehaviorReport, CandidateReceipt, DutyRoster, EgressLimits, EgressRoot, Id as ParaId};
+use runtime::{Address, EventRecord};
+use runtime_primitives::generic::BlockId as GenericBlockId;
+
+use {AgreementTiming, CollationInputs, PolkadotApi, LocalPolkadotApi, RemotePolkadotApi, Result};
+
+/// Default number of blocks to keep results for.
+pub const DEFAULT_CACHED_BLOCKS: usize = 16;
+
+// results of the memoized calls at a single block.
+#[derive(Default)]
+struct BlockResults {
+	session_keys: Option<Vec<SessionKey>>,
+	validators: Option<Vec<AccountId>>,
+	session_index: Option<BlockNumber>,
+	random_seed: Option<Hash>,
+	duty_roster: Option<DutyRoster>,
+	timestamp: Option<Timestamp>,
+	active_parachains: Option<Vec<ParaId>>,
+	agreement_timing: Option<AgreementTiming>,
+	egress_limits: Option<EgressLimits>,
+}
+
+struct ResultCache {
+	max_blocks: usize,
+	order: VecDeque<Hash>,
+	blocks: HashMap<Hash, BlockResults>,
+}
+
+impl ResultCache {
+	fn new(max_blocks: usize) -> Self {
+		ResultCache {
+			max_blocks,
+			order: VecDeque::new(),
+			blocks: HashMap::new(),
+		}
+	}
+
+	fn get(&self, hash: &Hash) -> Option<&BlockResults> {
+		self.blocks.get(hash)
+	}
+
+	// get the results at a block, making room for it if necessary.
+	fn entry(&mut self, hash: Hash) -> &mut BlockResults {
+		if !self.blocks.contains_key(&hash) {
+			while self.order.len() >= self.max_blocks {
+				match self.order.pop_front() {
+					Some(oldest) => { self.blocks.remove(&oldest); }
+					None => break,
+				}
+			}
+
+			self.order.push_back(hash);
+		}
+
+		self.blocks.entry(hash).or_insert_with(Default::default)
+	}
+
+	fn forget(&mut self, hash: &Hash) {
+		if self.blocks.remove(hash).is_some() {
+			self.order.retain(|h| h != hash);
+		}
+	}
+}
+
+// call through to the inner API, memoizing the result if the block is given by hash.
+macro_rules! cached {
+	($self: ident, $at: expr, $field: ident, $call: expr) => {{
+		let hash = match *$at {
+			GenericBlockId::Hash(hash) => hash,
+			GenericBlockId::Number(_) => return $call,
+		};
+
+		if let Some(result) = $self.cache.lock().get(&hash).and_then(|r| r.$field.clone()) {
+			return Ok(result);
+		}
+
+		match $call {
+			Ok(result) => {
+				$self.cache.lock().entry(hash).$field = Some(result.clone());
+				Ok(result)
+			}
+			Err(e) => {
+				$self.cache.lock().forget(&hash);
+				Err(e)
+			}
+		}
+	}}
+}
+
+/// Wraps a `PolkadotApi`, memoizing the results of calls which are made at
+/// the same block by many subsystems. See the module documentation.
+pub struct CachedApi<A> {
+	inner: Arc<A>,
+	cache: Mutex<ResultCache>,
+}
+
+impl<A> CachedApi<A> {
+	/// Wrap an API, keeping results for up to `max_blocks` blocks.
+	pub fn new(inner: Arc<A>, max_blocks: usize) -> Self {
+		CachedApi {
+			inner,
+			cache: Mutex::new(ResultCache::new(max_blocks)),
+		}
+	}
+
+	/// The wrapped API.
+	pub fn inner(&self) -> &Arc<A> {
+		&self.inner
+	}
+}
+
+impl<A: PolkadotApi> PolkadotApi for CachedApi<A> {
+	type BlockBuilder = A::BlockBuilder;
+
+	fn session_keys(&self, at: &BlockId) -> Result<Vec<SessionKey>> {
+		cached!(self, at, session_keys, self.inner.session_keys(at))
+	}
+
+	fn validators(&self, at: &BlockId) -> Result<Vec<AccountId>> {
+		cached!(self, at, validators, self.inner.validators(at))
+	}
+
+	fn session_index(&self, at: &BlockId) -> Result<BlockNumber> {
+		cached!(self, at, session_index, self.inner.session_index(at))
+	}
+
+	fn session_key_owner(&self, at: &BlockId, key: SessionKey) -> Result<Option<AccountId>> {
+		self.inner.session_key_owner(at, key)
+	}
+
+	fn random_seed(&self, at: &BlockId) -> Result<Hash> {
+		cached!(self, at, random_seed, self.inner.random_seed(at))
+	}
+
+	fn duty_roster(&self, at: &BlockId) -> Result<DutyRoster> {
+		cached!(self, at, duty_roster, self.inner.duty_roster(at))
+	}
+
+	fn timestamp(&self, at: &BlockId) -> Result<Timestamp> {
+		cached!(self, at, timestamp, self.inner.timestamp(at))
+	}
+
+	fn index(&self, at: &BlockId, account: AccountId) -> Result<Index> {
+		self.inner.index(at, account)
+	}
+
+	fn free_balance(&self, at: &BlockId, account: AccountId) -> Result<Balance> {
+		self.inner.free_balance(at, account)
+	}
+
+	fn lookup(&self, at: &BlockId, address: Address) -> Result<Option<AccountId>> {
+		self.inner.lookup(at, address)
+	}
+
+	fn active_parachains(&self, at: &BlockId) -> Result<Vec<ParaId>> {
+		cached!(self, at, active_parachains, self.inner.active_parachains(at))
+	}
+
+	fn parachain_code(&self, at: &BlockId, parachain: ParaId) -> Result<Option<Vec<u8>>> {
+		self.inner.parachain_code(at, parachain)
+	}
+
+	fn parachain_head(&self, at: &BlockId, parachain: ParaId) -> Result<Option<Vec<u8>>> {
+		self.inner.parachain_head(at, parachain)
+	}
+
+	fn collation_inputs(&self, at: &BlockId, parachain: ParaId) -> Result<CollationInputs> {
+		self.inner.collation_inputs(at, parachain)
+	}
+
+	fn agreement_timing(&self, at: &BlockId) -> Result<AgreementTiming> {
+		cached!(self, at, agreement_timing, self.inner.agreement_timing(at))
+	}
+
+	fn egress_limits(&self, at: &BlockId) -> Result<EgressLimits> {
+		cached!(self, at, egress_limits, self.inner.egress_limits(at))
+	}
+
+	fn egress_roots(&self, at: &BlockId) -> Result<Vec<(ParaId, Vec<(ParaId, Vec<EgressRoot>)>)>> {
+		self.inner.egress_roots(at)
+	}
+
+	fn misbehavior_reported(&self, at: &BlockId, report: &MisbehaviorReport) -> Result<bool> {
+		self.inner.misbehavior_reported(at, report)
+	}
+
+	fn agreement_misbehavior_reported(&self, at: &BlockId, report: &AgreementMisbehaviorReport) -> Result<bool> {
+		self.inner.agreement_misbehavior_reported(at, report)
+	}
+
+	fn events(&self, at: &BlockId) -> Result<Vec<EventRecord>> {
+		self.inner.events(at)
+	}
+
+	fn transaction_fee(&self, at: &BlockId, extrinsic: &UncheckedExtrinsic) -> Result<Balance> {
+		self.inner.transaction_fee(at, extrinsic)
+	}
+
+	fn evaluate_block(&self, at: &BlockId, block: Block) -> Result<bool> {
+		self.inner.evaluate_block(at, block)
+	}
+
+	fn build_block(&self, at: &BlockId, timestamp: Timestamp, new_heads: Vec<CandidateReceipt>, heartbeats: Vec<Heartbeat>) -> Result<Self::BlockBuilder> {
+		self.inner.build_block(at, timestamp, new_heads, heartbeats)
+	}
+
+	fn inherent_extrinsics(&self, at: &BlockId, timestamp: Timestamp, new_heads: Vec<CandidateReceipt>, heartbeats: Vec<Heartbeat>) -> Result<Vec<UncheckedExtrinsic>> {
+		self.inner.inherent_extrinsics(at, timestamp, new_heads, heartbeats)
+	}
+}
+
+impl<A: LocalPolkadotApi> LocalPolkadotApi for CachedApi<A> {}
+
+impl<A: RemotePolkadotApi> RemotePolkadotApi for CachedApi<A> {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn oldest_blocks_are_evicted() {
+		let mut cache = ResultCache::new(2);
+		let (a, b, c) = ([1; 32].into(), [2; 32].into(), [3; 32].into());
+
+		cache.entry(a).timestamp = Some(1);
+		cache.entry(b).timestamp = Some(2);
+		cache.entry(a).random_seed = Some(a);
+		assert_eq!(cache.order.len(), 2);
+
+		cache.entry(c).timestamp = Some(3);
+		assert!(cache.get(&a).is_none());
+		assert_eq!(cache.get(&b).and_then(|r| r.timestamp), Some(2));
+		assert_eq!(cache.get(&c).and_then(|r| r.timestamp), Some(3));
+	}
+
+	#[test]
+	fn forgotten_blocks_free_their_slot() {
+		let mut cache = ResultCache::new(2);
+		let (a, b, c) = ([1; 32].into(), [2; 32].into(), [3; 32].into());
+
+		cache.entry(a).timestamp = Some(1);
+		cache.entry(b).timestamp = Some(2);
+		cache.forget(&a);
+		assert!(cache.get(&a).is_none());
+
+		cache.entry(c).timestamp = Some(3);
+		assert!(cache.get(&b).is_some());
+		assert!(cache.get(&c).is_some());
+	}
+}
@@ -23,12 +23,13 @@ use polkadot_executor::Executor as LocalDispatch;
 use substrate_executor::NativeExecutor;
 use state_machine;
 
-use runtime::Address;
+use runtime::{Address, EventRecord};
 use runtime_primitives::traits::AuxLookup;
-use primitives::{AccountId, Block, Header, BlockId, Hash, Index, SessionKey, Timestamp, UncheckedExtrinsic};
-use primitives::parachain::{CandidateReceipt, DutyRoster, Id as ParaId};
+use primitives::{AccountId, Balance, Block, Header, BlockId, BlockNumber, Hash, Heartbeat, Index, MisbehaviorReport, SessionKey,
+	Timestamp, UncheckedExtrinsic};
+use primitives::parachain::{AgreementMisbehaviorReport, CandidateReceipt, DutyRoster, EgressLimits, EgressRoot, Id as ParaId};
 
-use {BlockBuilder, PolkadotApi, LocalPolkadotApi, ErrorKind, Error, Result};
+use {AgreementTiming, BlockBuilder, CollationInputs, PolkadotApi, LocalPolkadotApi, ErrorKind, Error, Result};
 
 // set up the necessary scaffolding to execute a set of calls to the runtime.
 // this creates a new block on top of the given ID and initialises it.
@@ -83,6 +84,14 @@ impl<B: LocalBackend<Block>> PolkadotApi for Client<B, LocalCallExecutor<B, Nati
 		with_runtime!(self, at, ::runtime::Session::validators)
 	}
 
+	fn session_index(&self, at: &BlockId) -> Result<BlockNumber> {
+		with_runtime!(self, at, ::runtime::Session::current_index)
+	}
+
+	fn session_key_owner(&self, at: &BlockId, key: SessionKey) -> Result<Option<AccountId>> {
+		with_runtime!(self, at, || ::runtime::Session::key_owner(key))
+	}
+
 	fn random_seed(&self, at: &BlockId) -> Result<Hash> {
 		with_runtime!(self, at, ::runtime::System::random_seed)
 	}
@@ -120,6 +129,10 @@ impl<B: LocalBackend<Block>> PolkadotApi for Client<B, LocalCallExecutor<B, Nati
 		with_runtime!(self, at, || ::runtime::System::account_nonce(account))
 	}
 
+	fn free_balance(&self, at: &BlockId, account: AccountId) -> Result<Balance> {
+		with_runtime!(self, at, || ::runtime::Staking::free_balance(account))
+	}
+
 	fn lookup(&self, at: &BlockId, address: Address) -> Result<Option<AccountId>> {
 		with_runtime!(self, at, || <::runtime::Staking as AuxLookup>::lookup(address).ok())
 	}
@@ -136,20 +149,75 @@ impl<B: LocalBackend<Block>> PolkadotApi for Client<B, LocalCallExecutor<B, Nati
 		with_runtime!(self, at, || ::runtime::Parachains::parachain_head(parachain))
 	}
 
-	fn build_block(&self, at: &BlockId, timestamp: Timestamp, new_heads: Vec<CandidateReceipt>) -> Result<Self::BlockBuilder> {
+	fn collation_inputs(&self, at: &BlockId, parachain: ParaId) -> Result<CollationInputs> {
+		with_runtime!(self, at, || CollationInputs {
+			active_parachains: ::runtime::Parachains::active_parachains(),
+			code: ::runtime::Parachains::parachain_code(parachain),
+			head: ::runtime::Parachains::parachain_head(parachain),
+		})
+	}
+
+	fn agreement_timing(&self, at: &BlockId) -> Result<AgreementTiming> {
+		use std::time::Duration;
+
+		with_runtime!(self, at, || {
+			let default = AgreementTiming::default();
+			AgreementTiming {
+				proposal_delay: ::runtime::Parachains::proposal_delay()
+					.map_or(default.proposal_delay, Duration::from_millis),
+				parachain_empty_duration: ::runtime::Parachains::parachain_empty_delay()
+					.map_or(default.parachain_empty_duration, Duration::from_millis),
+				round_timeout_multiplier: ::runtime::Parachains::round_timeout_multiplier()
+					.unwrap_or(default.round_timeout_multiplier),
+			}
+		})
+	}
+
+	fn egress_limits(&self, at: &BlockId) -> Result<EgressLimits> {
+		with_runtime!(self, at, ::runtime::Parachains::egress_limits)
+	}
+
+	fn egress_roots(&self, at: &BlockId) -> Result<Vec<(ParaId, Vec<(ParaId, Vec<EgressRoot>)>)>> {
+		with_runtime!(self, at, || ::runtime::Parachains::active_parachains()
+			.into_iter()
+			.map(|id| (id, ::runtime::Parachains::egress_roots(id)))
+			.filter(|&(_, ref egress)| !egress.is_empty())
+			.collect())
+	}
+
+	fn misbehavior_reported(&self, at: &BlockId, report: &MisbehaviorReport) -> Result<bool> {
+		with_runtime!(self, at, || ::runtime::Consensus::is_reported(report))
+	}
+
+	fn agreement_misbehavior_reported(&self, at: &BlockId, report: &AgreementMisbehaviorReport) -> Result<bool> {
+		with_runtime!(self, at, || ::runtime::Slashing::is_reported(report))
+	}
+
+	fn events(&self, at: &BlockId) -> Result<Vec<EventRecord>> {
+		with_runtime!(self, at, ::runtime::System::events)
+	}
+
+	fn transaction_fee(&self, at: &BlockId, extrinsic: &UncheckedExtrinsic) -> Result<Balance> {
+		use codec::Slicable;
+
+		let encoded_len = extrinsic.encode().len();
+		with_runtime!(self, at, || ::runtime::Staking::transaction_fee(encoded_len))
+	}
+
+	fn build_block(&self, at: &BlockId, timestamp: Timestamp, new_heads: Vec<CandidateReceipt>, heartbeats: Vec<Heartbeat>) -> Result<Self::BlockBuilder> {
 		let mut block_builder = self.new_block_at(at)?;
-		for inherent in self.inherent_extrinsics(at, timestamp, new_heads)? {
+		for inherent in self.inherent_extrinsics(at, timestamp, new_heads, heartbeats)? {
 			block_builder.push(inherent)?;
 		}
 
 		Ok(block_builder)
 	}
 
-	fn inherent_extrinsics(&self, at: &BlockId, timestamp: Timestamp, new_heads: Vec<CandidateReceipt>) -> Result<Vec<UncheckedExtrinsic>> {
+	fn inherent_extrinsics(&self, at: &BlockId, timestamp: Timestamp, new_heads: Vec<CandidateReceipt>, heartbeats: Vec<Heartbeat>) -> Result<Vec<UncheckedExtrinsic>> {
 		use codec::Slicable;
 
 		with_runtime!(self, at, || {
-			let extrinsics = ::runtime::inherent_extrinsics(timestamp, new_heads);
+			let extrinsics = ::runtime::inherent_extrinsics(timestamp, new_heads, heartbeats);
 			extrinsics.into_iter()
 				.map(|x| x.encode()) // get encoded representation
 				.map(|x| Slicable::decode(&mut &x[..])) // get byte-vec equivalent to extrinsic
@@ -170,7 +238,7 @@ mod tests {
 	use client::LocalCallExecutor;
 	use client::in_mem::Backend as InMemory;
 	use substrate_executor::NativeExecutionDispatch;
-	use runtime::{GenesisConfig, ConsensusConfig, SessionConfig};
+	use runtime::{GenesisConfig, ConsensusConfig, SessionConfig, ParachainsConfig};
 
 	fn validators() -> Vec<AccountId> {
 		vec![
@@ -200,9 +268,20 @@ mod tests {
 			}),
 			council: Some(Default::default()),
 			democracy: Some(Default::default()),
-			parachains: Some(Default::default()),
+			parachains: Some(ParachainsConfig {
+				parachains: vec![(5.into(), vec![1, 2, 3])],
+				proposal_delay: Some(2000),
+				parachain_empty_delay: None,
+				round_timeout_multiplier: None,
+				max_egress_messages: Some(100),
+				max_egress_bytes: None,
+				deposit: None,
+				phantom: Default::default(),
+			}),
 			staking: Some(Default::default()),
 			timestamp: Some(Default::default()),
+			heartbeats: Some(Default::default()),
+			slashing: Some(Default::default()),
 		};
 
 		::client::new_in_mem(LocalDispatch::new(), genesis_config).unwrap()
@@ -216,12 +295,24 @@ mod tests {
 		assert_eq!(client.validators(&id).unwrap(), validators());
 	}
 
+	#[test]
+	fn gets_session_key_owners() {
+		let client = client();
+		let id = BlockId::number(0);
+		for (key, validator) in session_keys().into_iter().zip(validators()) {
+			assert_eq!(client.session_key_owner(&id, key).unwrap(), Some(validator));
+		}
+
+		let unknown = Keyring::Alice.to_raw_public().into();
+		assert_eq!(client.session_key_owner(&id, unknown).unwrap(), None);
+	}
+
 	#[test]
 	fn build_block_implicit_succeeds() {
 		let client = client();
 
 		let id = BlockId::number(0);
-		let block_builder = client.build_block(&id, 1_000_000, Vec::new()).unwrap();
+		let block_builder = client.build_block(&id, 1_000_000, Vec::new(), Vec::new()).unwrap();
 		let block = block_builder.bake().unwrap();
 
 		assert_eq!(block.header.number, 1);
@@ -233,7 +324,7 @@ mod tests {
 		let client = client();
 
 		let id = BlockId::number(0);
-		let inherent = client.inherent_extrinsics(&id, 1_000_000, Vec::new()).unwrap();
+		let inherent = client.inherent_extrinsics(&id, 1_000_000, Vec::new(), Vec::new()).unwrap();
 
 		let mut block_builder = client.new_block_at(&id).unwrap();
 		for extrinsic in inherent {
@@ -253,4 +344,91 @@ mod tests {
 		let id = BlockId::number(0);
 		assert!(client.random_seed(&id).is_ok());
 	}
+
+	#[test]
+	fn gets_collation_inputs_in_one_call() {
+		let client = client();
+		let id = BlockId::number(0);
+
+		let inputs = client.collation_inputs(&id, 5.into()).unwrap();
+		assert_eq!(inputs.active_parachains, vec![5.into()]);
+		assert_eq!(inputs.code, Some(vec![1, 2, 3]));
+		assert_eq!(inputs.head, client.parachain_head(&id, 5.into()).unwrap());
+
+		let inactive = client.collation_inputs(&id, 6.into()).unwrap();
+		assert_eq!(inactive.code, None);
+		assert_eq!(inactive.head, None);
+	}
+
+	#[test]
+	fn gets_agreement_timing_with_defaults() {
+		use std::time::Duration;
+
+		let client = client();
+		let timing = client.agreement_timing(&BlockId::number(0)).unwrap();
+		let default = AgreementTiming::default();
+
+		assert_eq!(timing.proposal_delay, Duration::from_millis(2000));
+		assert_eq!(timing.parachain_empty_duration, default.parachain_empty_duration);
+		assert_eq!(timing.round_timeout_multiplier, default.round_timeout_multiplier);
+	}
+
+	#[test]
+	fn gets_egress_limits() {
+		let client = client();
+		let limits = client.egress_limits(&BlockId::number(0)).unwrap();
+
+		assert_eq!(limits.max_messages, Some(100));
+		assert_eq!(limits.max_bytes, None);
+	}
+
+	#[test]
+	fn no_egress_roots_at_genesis() {
+		let client = client();
+		assert!(client.egress_roots(&BlockId::number(0)).unwrap().is_empty());
+	}
+
+	#[test]
+	fn no_events_at_genesis() {
+		let client = client();
+		assert!(client.events(&BlockId::number(0)).unwrap().is_empty());
+	}
+
+	#[test]
+	fn no_transaction_fee_at_genesis() {
+		let client = client();
+		assert_eq!(client.transaction_fee(&BlockId::number(0), &vec![0u8; 100]).unwrap(), 0);
+	}
+
+	#[test]
+	fn no_misbehavior_reported_at_genesis() {
+		use runtime_primitives::bft::MisbehaviorKind;
+		use primitives::parachain::AgreementMisbehavior;
+
+		let client = client();
+		let report = MisbehaviorReport {
+			parent_hash: [1; 32].into(),
+			parent_number: 0,
+			target: Keyring::Alice.to_raw_public().into(),
+			misbehavior: MisbehaviorKind::BftDoubleCommit(
+				0,
+				([2; 32].into(), [3; 64].into()),
+				([4; 32].into(), [5; 64].into()),
+			),
+		};
+
+		assert!(!client.misbehavior_reported(&BlockId::number(0), &report).unwrap());
+
+		let report = AgreementMisbehaviorReport {
+			parent_hash: [1; 32].into(),
+			target: Keyring::Alice.to_raw_public().into(),
+			misbehavior: AgreementMisbehavior::ValidityAndInvalidity(
+				[2; 32].into(),
+				::substrate_primitives::H512::from([3; 64]).into(),
+				::substrate_primitives::H512::from([4; 64]).into(),
+			),
+		};
+
+		assert!(!client.agreement_misbehavior_reported(&BlockId::number(0), &report).unwrap());
+	}
 }
@@ -17,6 +17,7 @@
 //! Strongly typed API for Polkadot based around the locally-compiled native
 //! runtime.
 
+extern crate parking_lot;
 extern crate polkadot_executor;
 extern crate polkadot_primitives as primitives;
 extern crate polkadot_runtime as runtime;
@@ -35,13 +36,18 @@ extern crate error_chain;
 #[cfg(test)]
 extern crate substrate_keyring as keyring;
 
+pub mod cache;
 pub mod full;
 pub mod light;
 
-use primitives::{AccountId, Block, BlockId, Hash, Index, SessionKey, Timestamp,
-	UncheckedExtrinsic};
-use runtime::Address;
-use primitives::parachain::{CandidateReceipt, DutyRoster, Id as ParaId};
+use std::time::Duration;
+
+pub use cache::CachedApi;
+
+use primitives::{AccountId, Balance, Block, BlockId, BlockNumber, Hash, Heartbeat, Index, MisbehaviorReport, SessionKey,
+	Timestamp, UncheckedExtrinsic};
+use runtime::{Address, EventRecord};
+use primitives::parachain::{AgreementMisbehaviorReport, CandidateReceipt, DutyRoster, EgressLimits, EgressRoot, Id as ParaId};
 
 error_chain! {
 	errors {
@@ -77,6 +83,41 @@ impl From<client::error::Error> for Error {
 	}
 }
 
+/// Per-block inputs to collation and candidate validation for a single parachain.
+///
+/// These are gathered in a single pass over the state, rather than with one
+/// runtime call per item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollationInputs {
+	/// The parachains active at the block.
+	pub active_parachains: Vec<ParaId>,
+	/// The validation code of the parachain. `None` if it is not active.
+	pub code: Option<Vec<u8>>,
+	/// The chain head of the parachain. `None` if it is not active.
+	pub head: Option<Vec<u8>>,
+}
+
+/// Timing parameters of candidate agreement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AgreementTiming {
+	/// Delay from the start of agreement before proposing or voting.
+	pub proposal_delay: Duration,
+	/// Delay after which a proposal without any parachain candidates is acceptable.
+	pub parachain_empty_duration: Duration,
+	/// Multiplier, in seconds, of the exponentially-increasing BFT round timeout.
+	pub round_timeout_multiplier: u64,
+}
+
+impl Default for AgreementTiming {
+	fn default() -> Self {
+		AgreementTiming {
+			proposal_delay: Duration::from_millis(5000),
+			parachain_empty_duration: Duration::from_millis(4000),
+			round_timeout_multiplier: 4,
+		}
+	}
+}
+
 /// Build new blocks.
 pub trait BlockBuilder {
 	/// Push an extrinsic onto the block. Fails if the extrinsic is invalid.
@@ -99,6 +140,13 @@ pub trait PolkadotApi {
 	/// Get validators at a given block.
 	fn validators(&self, at: &BlockId) -> Result<Vec<AccountId>>;
 
+	/// Get the index of the session at a given block.
+	fn session_index(&self, at: &BlockId) -> Result<BlockNumber>;
+
+	/// Get the staking account of the validator holding a session key at a block,
+	/// to attribute misbehavior and authorship observed under the key.
+	fn session_key_owner(&self, at: &BlockId, key: SessionKey) -> Result<Option<AccountId>>;
+
 	/// Get the value of the randomness beacon at a given block.
 	fn random_seed(&self, at: &BlockId) -> Result<Hash>;
 
@@ -111,6 +159,9 @@ pub trait PolkadotApi {
 	/// Get the nonce (né index) of an account at a block.
 	fn index(&self, at: &BlockId, account: AccountId) -> Result<Index>;
 
+	/// Get the free balance of an account at a block.
+	fn free_balance(&self, at: &BlockId, account: AccountId) -> Result<Balance>;
+
 	/// Get the account id of an address at a block.
 	fn lookup(&self, at: &BlockId, address: Address) -> Result<Option<AccountId>>;
 
@@ -123,16 +174,46 @@ pub trait PolkadotApi {
 	/// Get the chain head of a parachain. If the parachain is active, this will always return `Some`.
 	fn parachain_head(&self, at: &BlockId, parachain: ParaId) -> Result<Option<Vec<u8>>>;
 
+	/// Get all per-block inputs to collation on a parachain at a block in one call.
+	fn collation_inputs(&self, at: &BlockId, parachain: ParaId) -> Result<CollationInputs>;
+
+	/// Get the candidate agreement timing configured at a block. Parameters which
+	/// are not configured on-chain take their default values.
+	fn agreement_timing(&self, at: &BlockId) -> Result<AgreementTiming>;
+
+	/// Get the limits on egress a parachain candidate may post to each destination
+	/// at a block. Candidates exceeding them are rejected by the runtime.
+	fn egress_limits(&self, at: &BlockId) -> Result<EgressLimits>;
+
+	/// Get the egress roots of each parachain at a block which their destinations
+	/// haven't routed, or routed in that block, by destination. Parachains without
+	/// any are left out.
+	fn egress_roots(&self, at: &BlockId) -> Result<Vec<(ParaId, Vec<(ParaId, Vec<EgressRoot>)>)>>;
+
+	/// Whether the offence proven by a misbehavior report has already been
+	/// reported on-chain as of a block. Further reports of it would be rejected.
+	fn misbehavior_reported(&self, at: &BlockId, report: &MisbehaviorReport) -> Result<bool>;
+
+	/// Whether the offence proven by a report of misbehavior during agreement on
+	/// parachain candidates has already been reported on-chain as of a block.
+	fn agreement_misbehavior_reported(&self, at: &BlockId, report: &AgreementMisbehaviorReport) -> Result<bool>;
+
+	/// Get the events deposited in a block, in the order they were deposited.
+	fn events(&self, at: &BlockId) -> Result<Vec<EventRecord>>;
+
+	/// Get the fee a signed extrinsic would pay if included in a block built on the given.
+	fn transaction_fee(&self, at: &BlockId, extrinsic: &UncheckedExtrinsic) -> Result<Balance>;
+
 	/// Evaluate a block. Returns true if the block is good, false if it is known to be bad,
 	/// and an error if we can't evaluate for some reason.
 	fn evaluate_block(&self, at: &BlockId, block: Block) -> Result<bool>;
 
 	/// Build a block on top of the given, with inherent extrinsics pre-pushed.
-	fn build_block(&self, at: &BlockId, timestamp: Timestamp, new_heads: Vec<CandidateReceipt>) -> Result<Self::BlockBuilder>;
+	fn build_block(&self, at: &BlockId, timestamp: Timestamp, new_heads: Vec<CandidateReceipt>, heartbeats: Vec<Heartbeat>) -> Result<Self::BlockBuilder>;
 
 	/// Attempt to produce the (encoded) inherent extrinsics for a block being built upon the given.
 	/// This may vary by runtime and will fail if a runtime doesn't follow the same API.
-	fn inherent_extrinsics(&self, at: &BlockId, timestamp: Timestamp, new_heads: Vec<CandidateReceipt>) -> Result<Vec<UncheckedExtrinsic>>;
+	fn inherent_extrinsics(&self, at: &BlockId, timestamp: Timestamp, new_heads: Vec<CandidateReceipt>, heartbeats: Vec<Heartbeat>) -> Result<Vec<UncheckedExtrinsic>>;
 }
 
 /// Mark for all Polkadot API implementations, that are making use of state data, stored locally.
@@ -21,10 +21,11 @@ use client::backend::{Backend, RemoteBackend};
 use client::{Client, CallExecutor};
 use codec::Slicable;
 use state_machine;
-use primitives::{AccountId, Block, BlockId, Hash, Index, SessionKey, Timestamp, UncheckedExtrinsic};
-use runtime::Address;
-use primitives::parachain::{CandidateReceipt, DutyRoster, Id as ParaId};
-use {PolkadotApi, BlockBuilder, RemotePolkadotApi, Result, ErrorKind};
+use primitives::{AccountId, Balance, Block, BlockId, BlockNumber, Hash, Heartbeat, Index, MisbehaviorReport, SessionKey, Timestamp,
+	UncheckedExtrinsic};
+use runtime::{Address, EventRecord};
+use primitives::parachain::{AgreementMisbehaviorReport, CandidateReceipt, DutyRoster, EgressLimits, EgressRoot, Id as ParaId};
+use {AgreementTiming, PolkadotApi, BlockBuilder, CollationInputs, RemotePolkadotApi, Result, ErrorKind};
 
 /// Light block builder. TODO: make this work (efficiently)
 #[derive(Clone, Copy)]
@@ -49,7 +50,7 @@ impl<B: Backend<Block>, E: CallExecutor<Block>> PolkadotApi for RemotePolkadotAp
 	type BlockBuilder = LightBlockBuilder;
 
 	fn session_keys(&self, at: &BlockId) -> Result<Vec<SessionKey>> {
-		self.0.executor().call(at, "authorities", &[])
+		self.0.call(at, "authorities", &[])
 			.and_then(|r| Vec::<SessionKey>::decode(&mut &r.return_data[..])
 				.ok_or("error decoding session keys".into()))
 			.map_err(Into::into)
@@ -59,6 +60,14 @@ impl<B: Backend<Block>, E: CallExecutor<Block>> PolkadotApi for RemotePolkadotAp
 		Err(ErrorKind::UnknownRuntime.into())
 	}
 
+	fn session_index(&self, _at: &BlockId) -> Result<BlockNumber> {
+		Err(ErrorKind::UnknownRuntime.into())
+	}
+
+	fn session_key_owner(&self, _at: &BlockId, _key: SessionKey) -> Result<Option<AccountId>> {
+		Err(ErrorKind::UnknownRuntime.into())
+	}
+
 	fn random_seed(&self, _at: &BlockId) -> Result<Hash> {
 		Err(ErrorKind::UnknownRuntime.into())
 	}
@@ -79,6 +88,10 @@ impl<B: Backend<Block>, E: CallExecutor<Block>> PolkadotApi for RemotePolkadotAp
 		Err(ErrorKind::UnknownRuntime.into())
 	}
 
+	fn free_balance(&self, _at: &BlockId, _account: AccountId) -> Result<Balance> {
+		Err(ErrorKind::UnknownRuntime.into())
+	}
+
 	fn lookup(&self, _at: &BlockId, _address: Address) -> Result<Option<AccountId>> {
 		Err(ErrorKind::UnknownRuntime.into())
 	}
@@ -95,11 +108,43 @@ impl<B: Backend<Block>, E: CallExecutor<Block>> PolkadotApi for RemotePolkadotAp
 		Err(ErrorKind::UnknownRuntime.into())
 	}
 
-	fn build_block(&self, _at: &BlockId, _timestamp: Timestamp, _new_heads: Vec<CandidateReceipt>) -> Result<Self::BlockBuilder> {
+	fn collation_inputs(&self, _at: &BlockId, _parachain: ParaId) -> Result<CollationInputs> {
+		Err(ErrorKind::UnknownRuntime.into())
+	}
+
+	fn agreement_timing(&self, _at: &BlockId) -> Result<AgreementTiming> {
+		Err(ErrorKind::UnknownRuntime.into())
+	}
+
+	fn egress_limits(&self, _at: &BlockId) -> Result<EgressLimits> {
+		Err(ErrorKind::UnknownRuntime.into())
+	}
+
+	fn egress_roots(&self, _at: &BlockId) -> Result<Vec<(ParaId, Vec<(ParaId, Vec<EgressRoot>)>)>> {
+		Err(ErrorKind::UnknownRuntime.into())
+	}
+
+	fn misbehavior_reported(&self, _at: &BlockId, _report: &MisbehaviorReport) -> Result<bool> {
+		Err(ErrorKind::UnknownRuntime.into())
+	}
+
+	fn agreement_misbehavior_reported(&self, _at: &BlockId, _report: &AgreementMisbehaviorReport) -> Result<bool> {
+		Err(ErrorKind::UnknownRuntime.into())
+	}
+
+	fn events(&self, _at: &BlockId) -> Result<Vec<EventRecord>> {
+		Err(ErrorKind::UnknownRuntime.into())
+	}
+
+	fn transaction_fee(&self, _at: &BlockId, _extrinsic: &UncheckedExtrinsic) -> Result<Balance> {
+		Err(ErrorKind::UnknownRuntime.into())
+	}
+
+	fn build_block(&self, _at: &BlockId, _timestamp: Timestamp, _new_heads: Vec<CandidateReceipt>, _heartbeats: Vec<Heartbeat>) -> Result<Self::BlockBuilder> {
 		Err(ErrorKind::UnknownRuntime.into())
 	}
 
-	fn inherent_extrinsics(&self, _at: &BlockId, _timestamp: Timestamp, _new_heads: Vec<CandidateReceipt>) -> Result<Vec<Vec<u8>>> {
+	fn inherent_extrinsics(&self, _at: &BlockId, _timestamp: Timestamp, _new_heads: Vec<CandidateReceipt>, _heartbeats: Vec<Heartbeat>) -> Result<Vec<Vec<u8>>> {
 		Err(ErrorKind::UnknownRuntime.into())
 	}
 }
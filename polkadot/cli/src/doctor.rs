@@ -0,0 +1,311 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Self-test of the setup of a validator node, run by the `doctor` subcommand.
+//!
+//! Checks the chain spec against the database, the keystore against the
+//! session keys at the best block, the node key, the bootnodes and the clock,
+//! and prints a summary. The database is only opened if it already exists.
+
+use std::fs::File;
+use std::io::Read;
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap;
+use client;
+use keystore::Store as Keystore;
+use network::NetworkConfiguration;
+use polkadot_primitives::{Block, BlockId, Hash};
+use runtime_primitives::BuildStorage;
+use runtime_primitives::traits::Header as HeaderT;
+use service;
+use substrate_primitives::AuthorityId;
+
+use error;
+
+/// Time to wait for a connection to a bootnode.
+const DIAL_TIMEOUT_SECS: u64 = 5;
+
+/// Time to wait for the NTP server.
+const NTP_TIMEOUT_SECS: u64 = 5;
+
+/// NTP server to check the clock against, unless given.
+const DEFAULT_NTP_SERVER: &str = "pool.ntp.org:123";
+
+/// Largest offset of the local clock from NTP time accepted, in seconds.
+const MAX_CLOCK_DRIFT_SECS: f64 = 2.0;
+
+// seconds from the NTP epoch, 1900, to the Unix epoch.
+const NTP_UNIX_OFFSET_SECS: f64 = 2_208_988_800.0;
+
+enum Outcome {
+	Pass(String),
+	Fail(String),
+	Skip(String),
+}
+
+type Client = client::Client<
+	<service::FullComponents as service::Components>::Backend,
+	<service::FullComponents as service::Components>::Executor,
+	Block,
+>;
+
+/// Run all checks and print a summary. Fails if any check failed.
+pub fn run(matches: &clap::ArgMatches) -> error::Result<()> {
+	let base_path = super::base_path(matches);
+	let mut checks = Vec::new();
+
+	let mut boot_nodes: Vec<String> = matches.values_of("bootnodes")
+		.map_or(Vec::new(), |v| v.map(str::to_owned).collect());
+
+	let client = match super::load_spec(matches) {
+		Ok(spec) => {
+			boot_nodes.extend(spec.boot_nodes().iter().cloned());
+			let (outcome, genesis) = check_chain_spec(&spec);
+			checks.push(("chain spec", outcome));
+
+			let (outcome, client) = match genesis {
+				Some(genesis) => check_database(spec, &base_path, genesis),
+				None => (Outcome::Skip("chain spec has no genesis".into()), None),
+			};
+			checks.push(("database", outcome));
+			client
+		}
+		Err(e) => {
+			checks.push(("chain spec", Outcome::Fail(e)));
+			checks.push(("database", Outcome::Skip("no chain spec".into())));
+			None
+		}
+	};
+
+	checks.push(("session key", check_session_key(matches, &base_path, client.as_ref())));
+	checks.push(("node key", check_node_key(matches, &base_path)));
+	checks.push(("bootnodes", check_boot_nodes(&boot_nodes)));
+	checks.push(("clock", check_clock(matches.value_of("ntp-server").unwrap_or(DEFAULT_NTP_SERVER))));
+
+	let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+	for (name, outcome) in checks {
+		match outcome {
+			Outcome::Pass(msg) => { passed += 1; println!("[PASS] {}: {}", name, msg) }
+			Outcome::Fail(msg) => { failed += 1; println!("[FAIL] {}: {}", name, msg) }
+			Outcome::Skip(msg) => { skipped += 1; println!("[SKIP] {}: {}", name, msg) }
+		}
+	}
+	println!("{} passed, {} failed, {} skipped", passed, failed, skipped);
+
+	if failed > 0 {
+		bail!(error::ErrorKind::Input(format!("{} checks failed", failed)));
+	}
+	Ok(())
+}
+
+// the chain spec yields genesis storage; returns the hash of its genesis block.
+fn check_chain_spec(spec: &service::ChainSpec) -> (Outcome, Option<Hash>) {
+	match spec.build_storage() {
+		Ok(storage) => {
+			let genesis = client::genesis::construct_genesis_block::<Block>(&storage).header.hash();
+			(Outcome::Pass(format!("{}, genesis {}", spec.name(), genesis)), Some(genesis))
+		}
+		Err(e) => (Outcome::Fail(format!("invalid genesis: {}", e)), None),
+	}
+}
+
+// the database, if any, was initialised from the same genesis.
+fn check_database(spec: service::ChainSpec, base_path: &Path, genesis: Hash) -> (Outcome, Option<Arc<Client>>) {
+	let path = super::db_path(base_path);
+	if !path.exists() {
+		return (Outcome::Skip(format!("no database at {}; created on first start", path.display())), None);
+	}
+
+	let mut config = service::Configuration::default_with_spec(spec);
+	config.database_path = path.to_string_lossy().into();
+
+	let client = match service::new_client(config) {
+		Ok(client) => client,
+		Err(e) => return (Outcome::Fail(format!("can't open {}: {}", path.display(), e)), None),
+	};
+
+	match client.block_hash(0) {
+		Ok(Some(ref hash)) if *hash == genesis => {
+			let best = client.info().map(|info| info.chain.best_number).unwrap_or(0);
+			(Outcome::Pass(format!("matches the chain spec, best block #{}", best)), Some(client))
+		}
+		Ok(Some(hash)) => (Outcome::Fail(format!("genesis {} doesn't match the chain spec", hash)), None),
+		Ok(None) => (Outcome::Fail("no genesis block".into()), None),
+		Err(e) => (Outcome::Fail(format!("can't read genesis: {}", e)), None),
+	}
+}
+
+// the keystore holds the key the node would validate with, and it is among the
+// session keys at the best block.
+fn check_session_key(matches: &clap::ArgMatches, base_path: &Path, client: Option<&Arc<Client>>) -> Outcome {
+	let path = matches.value_of("keystore-path")
+		.map(|x| Path::new(x).to_owned())
+		.unwrap_or_else(|| super::keystore_path(base_path));
+	let seeds: Vec<_> = matches.values_of("key").map_or(Vec::new(), |v| v.collect());
+
+	if !path.exists() && seeds.is_empty() {
+		return Outcome::Fail(format!("no keystore at {}", path.display()));
+	}
+
+	let key = Keystore::open(path.clone())
+		.and_then(|mut keystore| {
+			for seed in &seeds {
+				keystore.generate_from_seed(seed)?;
+			}
+			match keystore.contents()?.first() {
+				Some(public) => keystore.load(public, "").map(Some),
+				None => Ok(None),
+			}
+		});
+
+	let key = match key {
+		Ok(Some(key)) => key,
+		Ok(None) => return Outcome::Fail(format!("no keys in {}", path.display())),
+		Err(e) => return Outcome::Fail(format!("can't load key from {}: {}", path.display(), e)),
+	};
+
+	let client = match client {
+		Some(client) => client,
+		None => return Outcome::Skip(format!("key {} loads, but there is no database to check it against", key.public())),
+	};
+
+	let authority: AuthorityId = key.public().into();
+	let best = match client.info() {
+		Ok(info) => info.chain,
+		Err(e) => return Outcome::Fail(format!("can't read best block: {}", e)),
+	};
+
+	match client.authorities_at(&BlockId::hash(best.best_hash)) {
+		Ok(ref authorities) if authorities.contains(&authority) =>
+			Outcome::Pass(format!("{} is a session key at #{}", key.public(), best.best_number)),
+		Ok(authorities) => Outcome::Fail(format!(
+			"{} is not among the {} session keys at #{}",
+			key.public(), authorities.len(), best.best_number
+		)),
+		Err(e) => Outcome::Fail(format!("can't read session keys: {}", e)),
+	}
+}
+
+// the node key given, or the one stored by the network, parses.
+fn check_node_key(matches: &clap::ArgMatches, base_path: &Path) -> Outcome {
+	let (key, source) = match super::node_key(matches) {
+		Ok(Some(key)) => key,
+		Err(e) => return Outcome::Fail(e.to_string()),
+		Ok(None) => {
+			let path = super::network_path(base_path).join("key");
+			let mut key = String::new();
+			match File::open(&path).and_then(|mut file| file.read_to_string(&mut key)) {
+				Ok(_) => (key, path.display().to_string()),
+				Err(_) => return Outcome::Skip("no node key; a new one is generated on start".into()),
+			}
+		}
+	};
+
+	let mut network = NetworkConfiguration::new();
+	match key.trim().parse() {
+		Ok(secret) => {
+			network.use_secret = Some(secret);
+			Outcome::Pass(format!("loaded from {}", source))
+		}
+		Err(e) => Outcome::Fail(format!("invalid key in {}: {}", source, e)),
+	}
+}
+
+// at least one bootnode accepts connections.
+fn check_boot_nodes(boot_nodes: &[String]) -> Outcome {
+	if boot_nodes.is_empty() {
+		return Outcome::Skip("no bootnodes configured".into());
+	}
+
+	let unreachable: Vec<_> = boot_nodes.iter()
+		.filter_map(|node| dial(node).err().map(|e| format!("{} ({})", node, e)))
+		.collect();
+
+	let reachable = boot_nodes.len() - unreachable.len();
+	match (reachable, unreachable.is_empty()) {
+		(_, true) => Outcome::Pass(format!("all {} reachable", reachable)),
+		(0, _) => Outcome::Fail(format!("none reachable: {}", unreachable.join(", "))),
+		_ => Outcome::Pass(format!("{} of {} reachable; unreachable: {}", reachable, boot_nodes.len(), unreachable.join(", "))),
+	}
+}
+
+// connect to the address of an `enode://<id>@<address>` URL.
+fn dial(node: &str) -> Result<(), String> {
+	let address = match node.splitn(2, '@').nth(1) {
+		Some(address) => address.split('?').next().unwrap_or(address),
+		None => return Err("not an enode URL".into()),
+	};
+
+	let address = address.to_socket_addrs()
+		.map_err(|e| e.to_string())?
+		.next()
+		.ok_or_else(|| "no address".to_owned())?;
+
+	TcpStream::connect_timeout(&address, Duration::from_secs(DIAL_TIMEOUT_SECS))
+		.map(|_| ())
+		.map_err(|e| e.to_string())
+}
+
+// the local clock is close to the time of an NTP server.
+fn check_clock(server: &str) -> Outcome {
+	match clock_offset(server) {
+		Ok(offset) if offset.abs() <= MAX_CLOCK_DRIFT_SECS =>
+			Outcome::Pass(format!("{:+.3}s from {}", offset, server)),
+		Ok(offset) => Outcome::Fail(format!(
+			"{:+.3}s from {}, more than {}s",
+			offset, server, MAX_CLOCK_DRIFT_SECS
+		)),
+		Err(e) => Outcome::Fail(format!("can't query {}: {}", server, e)),
+	}
+}
+
+// offset of the local clock from an NTP server in seconds, using a single SNTP request.
+fn clock_offset(server: &str) -> Result<f64, String> {
+	let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?;
+	socket.set_read_timeout(Some(Duration::from_secs(NTP_TIMEOUT_SECS))).map_err(|e| e.to_string())?;
+
+	// version 3, client mode.
+	let mut request = [0u8; 48];
+	request[0] = 0x1b;
+
+	let sent = unix_time(SystemTime::now());
+	socket.send_to(&request, server).map_err(|e| e.to_string())?;
+
+	let mut response = [0u8; 48];
+	let (len, _) = socket.recv_from(&mut response).map_err(|e| e.to_string())?;
+	let received = unix_time(SystemTime::now());
+	if len < response.len() {
+		return Err("short response".into());
+	}
+
+	let (server_received, server_sent) = (ntp_time(&response[32..40]), ntp_time(&response[40..48]));
+	Ok(((server_received - sent) + (server_sent - received)) / 2.0)
+}
+
+fn unix_time(time: SystemTime) -> f64 {
+	let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+	since_epoch.as_secs() as f64 + since_epoch.subsec_nanos() as f64 / 1_000_000_000.0
+}
+
+// an NTP timestamp: big-endian seconds since 1900 and fraction of a second.
+fn ntp_time(bytes: &[u8]) -> f64 {
+	let word = |b: &[u8]| b.iter().fold(0u64, |acc, &byte| (acc << 8) | byte as u64);
+	word(&bytes[..4]) as f64 - NTP_UNIX_OFFSET_SECS + word(&bytes[4..8]) as f64 / 4_294_967_296.0
+}
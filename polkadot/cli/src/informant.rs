@@ -27,6 +27,14 @@ use client::{self, BlockchainEvents};
 
 const TIMER_INTERVAL_MS: u64 = 5000;
 
+fn format_duration(duration: Duration) -> String {
+	match duration.as_secs() {
+		secs if secs >= 3600 => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+		secs if secs >= 60 => format!("{}m{:02}s", secs / 60, secs % 60),
+		secs => format!("{}s", secs),
+	}
+}
+
 /// Spawn informant on the event loop
 pub fn start<C>(service: &Service<C>, handle: reactor::Handle)
 	where
@@ -46,14 +54,26 @@ pub fn start<C>(service: &Service<C>, handle: reactor::Handle)
 		if let Ok(best_block) = client.best_block_header() {
 			let hash = best_block.hash();
 			let num_peers = sync_status.num_peers;
-			let status = match (sync_status.sync.state, sync_status.sync.best_seen_block) {
+			let sync = sync_status.sync;
+			let status = match (sync.state, sync.best_seen_block) {
 				(SyncState::Idle, _) => "Idle".into(),
-				(SyncState::Downloading, None) => "Syncing".into(),
-				(SyncState::Downloading, Some(n)) => format!("Syncing, target=#{}", n),
+				(SyncState::Downloading, None) => format!("Syncing ({:.1} bps)", sync.blocks_per_second),
+				(SyncState::Downloading, Some(n)) => match sync.eta {
+					Some(eta) => format!("Syncing, target=#{} ({:.1} bps, {} left)", n, sync.blocks_per_second, format_duration(eta)),
+					None => format!("Syncing, target=#{} ({:.1} bps)", n, sync.blocks_per_second),
+				},
 			};
 			let txpool_status = txpool.light_status();
 			info!(target: "polkadot", "{} ({} peers), best: #{} ({})", status, sync_status.num_peers, best_block.number, hash);
 			telemetry!("system.interval"; "status" => status, "peers" => num_peers, "height" => best_block.number, "best" => ?hash, "txcount" => txpool_status.transaction_count);
+
+			let noisiest = network.peers().into_iter()
+				.filter_map(|peer| peer.dot_info.map(|info| (peer.id, info.bandwidth)))
+				.max_by_key(|&(_, ref bandwidth)| bandwidth.bytes_received);
+			if let Some((id, bandwidth)) = noisiest {
+				debug!(target: "polkadot", "Noisiest peer {}: {} bytes received, {} bytes sent",
+					id.unwrap_or_default(), bandwidth.bytes_received, bandwidth.bytes_sent);
+			}
 		} else {
 			warn!("Error getting best block information");
 		}
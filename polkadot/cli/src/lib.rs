@@ -33,8 +33,10 @@ extern crate triehash;
 extern crate parking_lot;
 extern crate serde;
 extern crate serde_json;
+extern crate rand;
 
 extern crate substrate_client as client;
+extern crate substrate_keystore as keystore;
 extern crate substrate_network as network;
 extern crate substrate_codec as codec;
 extern crate substrate_primitives;
@@ -63,6 +65,7 @@ extern crate log;
 pub mod error;
 mod informant;
 mod chain_spec;
+mod doctor;
 
 pub use chain_spec::ChainSpec;
 
@@ -70,8 +73,11 @@ use std::io::{self, Write, Read, stdin, stdout};
 use std::fs::File;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use substrate_telemetry::{init_telemetry, TelemetryConfig};
-use polkadot_primitives::{Block, BlockId};
+use polkadot_primitives::{AccountId, Balance, Block, BlockId, Header};
+use polkadot_primitives::parachain::{Id as ParaId, DutyRoster};
 use codec::Slicable;
 use client::BlockOrigin;
 use runtime_primitives::generic::SignedBlock;
@@ -79,10 +85,13 @@ use runtime_primitives::generic::SignedBlock;
 use futures::sync::mpsc;
 use futures::{Sink, Future, Stream};
 use tokio_core::reactor;
-use service::PruningMode;
+use service::{PolkadotApi, PruningMode};
 
 const DEFAULT_TELEMETRY_URL: &str = "ws://telemetry.polkadot.io:1024";
 
+/// Environment variable holding the node key, unless given on the command line.
+const NODE_KEY_ENV: &str = "POLKADOT_NODE_KEY";
+
 #[derive(Clone)]
 struct SystemConfiguration {
 	chain_name: String,
@@ -102,6 +111,82 @@ impl substrate_rpc::system::SystemApi for SystemConfiguration {
 	}
 }
 
+#[derive(Clone)]
+struct NetworkIntrospection {
+	network: Arc<network::Service<Block>>,
+}
+
+impl substrate_rpc::network::NetworkApi<network::NetworkState> for NetworkIntrospection {
+	fn network_state(&self) -> substrate_rpc::network::error::Result<network::NetworkState> {
+		Ok(self.network.network_state())
+	}
+}
+
+#[derive(Clone)]
+struct ConsensusIntrospection {
+	live_agreement: Option<service::LiveAgreement>,
+}
+
+impl substrate_rpc::consensus::ConsensusApi<ParaId, DutyRoster, service::GroupState, service::AgreementSummary>
+	for ConsensusIntrospection
+{
+	fn duty_roster(&self) -> substrate_rpc::consensus::error::Result<Option<DutyRoster>> {
+		Ok(self.live_agreement.as_ref().and_then(|a| a.duty_roster()))
+	}
+
+	fn group_state(&self, para_id: ParaId) -> substrate_rpc::consensus::error::Result<Option<service::GroupState>> {
+		Ok(self.live_agreement.as_ref().and_then(|a| a.group_state(para_id)))
+	}
+
+	fn table_summary(&self) -> substrate_rpc::consensus::error::Result<Option<service::AgreementSummary>> {
+		Ok(self.live_agreement.as_ref().and_then(|a| a.summary()))
+	}
+}
+
+struct RelayData<C: service::Components> {
+	client: Arc<client::Client<C::Backend, C::Executor, Block>>,
+	api: Arc<C::Api>,
+}
+
+impl<C> RelayData<C> where
+	C: service::Components,
+	client::error::Error: From<<<<C as service::Components>::Backend as client::backend::Backend<Block>>::State as state_machine::Backend>::Error>,
+{
+	fn best_block_id(&self) -> substrate_rpc::relay::error::Result<BlockId> {
+		self.client.info()
+			.map(|info| BlockId::Hash(info.chain.best_hash))
+			.map_err(|e| substrate_rpc::relay::error::ErrorKind::Unavailable(e.to_string()).into())
+	}
+}
+
+impl<C> substrate_rpc::relay::RelayApi<Header, AccountId, Balance, ParaId> for RelayData<C> where
+	C: service::Components + 'static,
+	client::error::Error: From<<<<C as service::Components>::Backend as client::backend::Backend<Block>>::State as state_machine::Backend>::Error>,
+{
+	fn best_head(&self) -> substrate_rpc::relay::error::Result<Header> {
+		self.client.best_block_header()
+			.map_err(|e| substrate_rpc::relay::error::ErrorKind::Unavailable(e.to_string()).into())
+	}
+
+	fn finalized_head(&self) -> substrate_rpc::relay::error::Result<Header> {
+		// imported relay chain blocks are final.
+		self.best_head()
+	}
+
+	fn balance(&self, account: AccountId) -> substrate_rpc::relay::error::Result<Balance> {
+		let at = self.best_block_id()?;
+		self.api.free_balance(&at, account)
+			.map_err(|e| substrate_rpc::relay::error::ErrorKind::Unavailable(e.to_string()).into())
+	}
+
+	fn parachain_head(&self, para_id: ParaId) -> substrate_rpc::relay::error::Result<Option<substrate_rpc::relay::Bytes>> {
+		let at = self.best_block_id()?;
+		self.api.parachain_head(&at, para_id)
+			.map(|head| head.map(Into::into))
+			.map_err(|e| substrate_rpc::relay::error::ErrorKind::Unavailable(e.to_string()).into())
+	}
+}
+
 fn load_spec(matches: &clap::ArgMatches) -> Result<service::ChainSpec, String> {
 	let chain_spec = matches.value_of("chain")
 		.map(ChainSpec::from)
@@ -111,6 +196,21 @@ fn load_spec(matches: &clap::ArgMatches) -> Result<service::ChainSpec, String> {
 	Ok(spec)
 }
 
+// the node key given with --node-key or --node-key-file, or else in the
+// environment, and where it comes from.
+fn node_key(matches: &clap::ArgMatches) -> error::Result<Option<(String, String)>> {
+	if let Some(key) = matches.value_of("node-key") {
+		return Ok(Some((key.to_owned(), "--node-key".to_owned())));
+	}
+	if let Some(path) = matches.value_of("node-key-file") {
+		let mut key = String::new();
+		File::open(path).and_then(|mut file| file.read_to_string(&mut key))
+			.map_err(|e| error::ErrorKind::Input(format!("Error reading node key from {}: {}", path, e)))?;
+		return Ok(Some((key.trim().to_owned(), path.to_owned())));
+	}
+	Ok(std::env::var(NODE_KEY_ENV).ok().map(|key| (key.trim().to_owned(), NODE_KEY_ENV.to_owned())))
+}
+
 fn base_path(matches: &clap::ArgMatches) -> PathBuf {
 	matches.value_of("base-path")
 		.map(|x| Path::new(x).to_owned())
@@ -153,6 +253,10 @@ pub fn run<I, T>(args: I) -> error::Result<()> where
 		return build_spec(matches);
 	}
 
+	if let Some(matches) = matches.subcommand_matches("generate-node-key") {
+		return generate_node_key(matches);
+	}
+
 	if let Some(matches) = matches.subcommand_matches("export-blocks") {
 		return export_blocks(matches);
 	}
@@ -161,6 +265,10 @@ pub fn run<I, T>(args: I) -> error::Result<()> where
 		return import_blocks(matches);
 	}
 
+	if let Some(matches) = matches.subcommand_matches("doctor") {
+		return doctor::run(matches);
+	}
+
 	let spec = load_spec(&matches)?;
 	let mut config = service::Configuration::default_with_spec(spec);
 
@@ -170,13 +278,15 @@ pub fn run<I, T>(args: I) -> error::Result<()> where
 	}
 
 	let base_path = base_path(&matches);
-	config.keystore_path = matches.value_of("keystore")
+	config.keystore_path = matches.value_of("keystore-path")
 		.map(|x| Path::new(x).to_owned())
 		.unwrap_or_else(|| keystore_path(&base_path))
 		.to_string_lossy()
 		.into();
 
 	config.database_path = db_path(&base_path).to_string_lossy().into();
+	config.statement_log_path = statement_log_path(&base_path).to_string_lossy().into();
+	config.availability_store_path = availability_store_path(&base_path).to_string_lossy().into();
 
 	config.pruning = match matches.value_of("pruning") {
 		Some("archive") => PruningMode::ArchiveAll,
@@ -185,6 +295,17 @@ pub fn run<I, T>(args: I) -> error::Result<()> where
 			.map_err(|_| error::ErrorKind::Input("Invalid pruning mode specified".to_owned()))?),
 	};
 
+	if let Some(s) = matches.value_of("availability-retention") {
+		config.availability_retention = s.parse()
+			.map_err(|_| error::ErrorKind::Input("Invalid availability retention specified".to_owned()))?;
+	}
+
+	config.retained_finalized_states = match matches.value_of("retain-states") {
+		None => 16,
+		Some(s) => s.parse()
+			.map_err(|_| error::ErrorKind::Input("Invalid number of retained states specified".to_owned()))?,
+	};
+
 	let role =
 		if matches.is_present("collator") {
 			info!("Starting collator");
@@ -200,7 +321,14 @@ pub fn run<I, T>(args: I) -> error::Result<()> where
 			service::Role::FULL
 		};
 
+	config.execution_strategies = service::ExecutionStrategies {
+		importing: parse_execution_strategy("import-execution", &matches)?,
+		block_construction: parse_execution_strategy("authoring-execution", &matches)?,
+		other: parse_execution_strategy("other-execution", &matches)?,
+	};
+
 	config.roles = role;
+	config.refuse_on_clock_skew = matches.is_present("refuse-skewed-authoring");
 	{
 		config.network.boot_nodes.extend(matches
 			.values_of("bootnodes")
@@ -212,16 +340,104 @@ pub fn run<I, T>(args: I) -> error::Result<()> where
 			Some(port) => port.parse().expect("Invalid p2p port value specified."),
 			None => 30333,
 		};
-		config.network.listen_address = Some(SocketAddr::new("0.0.0.0".parse().unwrap(), port));
-		config.network.public_address = None;
+		let listen_ip = match matches.value_of("listen-addr") {
+			Some(ip) => ip.parse()
+				.map_err(|_| error::ErrorKind::Input("Invalid listen address specified".to_owned()))?,
+			None => "0.0.0.0".parse().unwrap(),
+		};
+		config.network.listen_address = Some(SocketAddr::new(listen_ip, port));
+		config.network.public_address = match matches.value_of("public-addr") {
+			Some(address) => Some(address.parse()
+				.map_err(|_| error::ErrorKind::Input("Invalid public address specified".to_owned()))?),
+			None => None,
+		};
+		config.network.nat_enabled = !matches.is_present("no-nat");
 		config.network.client_version = format!("parity-polkadot/{}", crate_version!());
-		config.network.use_secret = match matches.value_of("node-key").map(|s| s.parse()) {
-			Some(Ok(secret)) => Some(secret),
-			Some(Err(err)) => return Err(format!("Error parsing node key: {}", err).into()),
+		config.network.use_secret = match node_key(&matches)? {
+			Some((key, source)) => match key.parse() {
+				Ok(secret) => Some(secret),
+				Err(err) => return Err(format!("Error parsing node key from {}: {}", source, err).into()),
+			},
 			None => None,
 		};
 	}
 
+	config.remote_signer = match matches.value_of("remote-signer") {
+		None => None,
+		Some(s) => Some(s.parse()
+			.map_err(|_| error::ErrorKind::Input("Invalid remote signer address specified".to_owned()))?),
+	};
+	if let Some(s) = matches.value_of("signing-budget") {
+		config.signing_budget = Duration::from_millis(s.parse()
+			.map_err(|_| error::ErrorKind::Input("Invalid signing budget specified".to_owned()))?);
+	}
+
+	config.state_sync_target = match matches.value_of("state-sync") {
+		None => None,
+		Some(s) => Some(s.trim_left_matches("0x").parse()
+			.map_err(|_| error::ErrorKind::Input("Invalid state sync block hash specified".to_owned()))?),
+	};
+
+	for checkpoint in matches.values_of("checkpoint").unwrap_or_default() {
+		let invalid = || error::ErrorKind::Input(format!("Invalid checkpoint specified: {}", checkpoint));
+		let mut parts = checkpoint.splitn(2, ':');
+		let number = parts.next().and_then(|n| n.parse().ok()).ok_or_else(&invalid)?;
+		let hash = parts.next().and_then(|h| h.trim_left_matches("0x").parse().ok()).ok_or_else(&invalid)?;
+		config.checkpoints.push((number, hash));
+	}
+
+	if matches.is_present("announce-hashes") {
+		config.announce_mode = service::AnnounceMode::Hash;
+	}
+
+	if let Some(s) = matches.value_of("peer-in-rate") {
+		let kib: u64 = s.parse().map_err(|_| error::ErrorKind::Input("Invalid peer inbound rate specified".to_owned()))?;
+		config.peer_bandwidth.inbound_rate = Some(kib * 1024);
+	}
+	if let Some(s) = matches.value_of("peer-out-rate") {
+		let kib: u64 = s.parse().map_err(|_| error::ErrorKind::Input("Invalid peer outbound rate specified".to_owned()))?;
+		config.peer_bandwidth.outbound_rate = Some(kib * 1024);
+	}
+
+	config.peer_set.reserved_nodes = matches.values_of("reserved-nodes").unwrap_or_default().map(str::to_owned).collect();
+	config.peer_set.reserved_only = matches.is_present("reserved-only");
+	if let Some(s) = matches.value_of("light-slots") {
+		config.peer_set.slots.light = Some(s.parse().map_err(|_| error::ErrorKind::Input("Invalid number of light client slots specified".to_owned()))?);
+	}
+	if let Some(s) = matches.value_of("collator-slots") {
+		config.peer_set.slots.collator = Some(s.parse().map_err(|_| error::ErrorKind::Input("Invalid number of collator slots specified".to_owned()))?);
+	}
+	if let Some(s) = matches.value_of("max-incoming-connections") {
+		config.peer_set.limits.max_incoming = Some(s.parse().map_err(|_| error::ErrorKind::Input("Invalid maximum number of incoming connections specified".to_owned()))?);
+	}
+	if let Some(s) = matches.value_of("max-connections-per-ip") {
+		config.peer_set.limits.max_per_ip = Some(s.parse().map_err(|_| error::ErrorKind::Input("Invalid maximum number of connections per IP specified".to_owned()))?);
+	}
+	if let Some(s) = matches.value_of("max-pending-handshakes") {
+		config.peer_set.limits.max_handshaking = Some(s.parse().map_err(|_| error::ErrorKind::Input("Invalid maximum number of pending handshakes specified".to_owned()))?);
+	}
+	if matches.is_present("no-listen") {
+		// the port stays bound: devp2p discovery runs over it.
+		config.peer_set.limits.max_incoming = Some(0);
+		config.network.nat_enabled = false;
+	}
+
+	if let Some(s) = matches.value_of("max-blocks-per-response") {
+		config.response_limits.max_blocks = s.parse().map_err(|_| error::ErrorKind::Input("Invalid maximum number of blocks per response specified".to_owned()))?;
+	}
+	if let Some(s) = matches.value_of("max-response-size") {
+		let kib: u64 = s.parse().map_err(|_| error::ErrorKind::Input("Invalid maximum response size specified".to_owned()))?;
+		config.response_limits.max_bytes = kib * 1024;
+	}
+	if let Some(s) = matches.value_of("handshake-timeout") {
+		let secs = s.parse().map_err(|_| error::ErrorKind::Input("Invalid handshake timeout specified".to_owned()))?;
+		config.handshake_timeout = Duration::from_secs(secs);
+	}
+	if let Some(s) = matches.value_of("keep-alive-timeout") {
+		let secs = s.parse().map_err(|_| error::ErrorKind::Input("Invalid keep-alive timeout specified".to_owned()))?;
+		config.keep_alive_timeout = Duration::from_secs(secs);
+	}
+
 	config.keys = matches.values_of("key").unwrap_or_default().map(str::to_owned).collect();
 	if matches.is_present("dev") {
 		config.keys.push("Alice".into());
@@ -265,6 +481,26 @@ fn build_spec(matches: &clap::ArgMatches) -> error::Result<()> {
 	Ok(())
 }
 
+fn generate_node_key(matches: &clap::ArgMatches) -> error::Result<()> {
+	use rand::{OsRng, Rng};
+
+	let mut secret = [0u8; 32];
+	OsRng::new()?.fill_bytes(&mut secret);
+	let key: String = secret.iter().map(|b| format!("{:02x}", b)).collect();
+
+	match matches.value_of("file") {
+		Some(path) => {
+			if Path::new(path).exists() {
+				return Err(error::ErrorKind::Input(format!("{} already exists", path)).into());
+			}
+			File::create(path)?.write_all(key.as_bytes())?;
+			info!("Node key written to {}", path);
+		}
+		None => println!("{}", key),
+	}
+	Ok(())
+}
+
 fn export_blocks(matches: &clap::ArgMatches) -> error::Result<()> {
 	let base_path = base_path(matches);
 	let spec = load_spec(&matches)?;
@@ -372,7 +608,7 @@ fn import_blocks(matches: &clap::ArgMatches) -> error::Result<()> {
 
 fn run_until_exit<C>(mut core: reactor::Core, service: service::Service<C>, matches: &clap::ArgMatches, sys_conf: SystemConfiguration) -> error::Result<()>
 	where
-		C: service::Components,
+		C: service::Components + 'static,
 		client::error::Error: From<<<<C as service::Components>::Backend as client::backend::Backend<Block>>::State as state_machine::Backend>::Error>,
 {
 	let exit = {
@@ -391,15 +627,20 @@ fn run_until_exit<C>(mut core: reactor::Core, service: service::Service<C>, matc
 		let http_address = parse_address("127.0.0.1:9933", "rpc-port", matches)?;
 		let ws_address = parse_address("127.0.0.1:9944", "ws-port", matches)?;
 
+		let consensus = ConsensusIntrospection { live_agreement: service.live_agreement() };
+		let network = NetworkIntrospection { network: service.network() };
 		let handler = || {
 			let chain = rpc::apis::chain::Chain::new(service.client(), core.remote());
 			let author = rpc::apis::author::Author::new(service.client(), service.transaction_pool());
-			rpc::rpc_handler::<Block, _, _, _, _>(
+			let mut io = rpc::rpc_handler::<Block, _, _, _, _>(
 				service.client(),
 				chain,
 				author,
 				sys_conf.clone(),
-			)
+			);
+			io.extend_with(substrate_rpc::consensus::ConsensusApi::to_delegate(consensus.clone()));
+			io.extend_with(substrate_rpc::network::NetworkApi::to_delegate(network.clone()));
+			io
 		};
 		(
 			start_server(http_address, |address| rpc::start_http(address, handler())),
@@ -407,6 +648,18 @@ fn run_until_exit<C>(mut core: reactor::Core, service: service::Service<C>, matc
 		)
 	};
 
+	let _relay_server = match matches.value_of("relay-port") {
+		Some(_) => {
+			let address = parse_address("127.0.0.1:9955", "relay-port", matches)?;
+			let handler = || {
+				let relay = RelayData::<C> { client: service.client(), api: service.api() };
+				rpc::relay_handler(rpc::relay::CachedRelay::new(relay, Duration::from_secs(rpc::relay::DEFAULT_CACHE_SECS)))
+			};
+			Some(start_server(address, |address| rpc::start_http(address, handler())))
+		},
+		None => None,
+	};
+
 	core.run(exit.into_future()).expect("Error running informant event loop");
 	Ok(())
 }
@@ -436,6 +689,14 @@ fn parse_address(default: &str, port_param: &str, matches: &clap::ArgMatches) ->
 	Ok(address)
 }
 
+fn parse_execution_strategy(param: &str, matches: &clap::ArgMatches) -> error::Result<service::ExecutionStrategy> {
+	match matches.value_of(param) {
+		None | Some("native") => Ok(service::ExecutionStrategy::NativeWhenPossible),
+		Some("wasm") => Ok(service::ExecutionStrategy::AlwaysWasm),
+		Some(s) => bail!(error::ErrorKind::Input(format!("Invalid execution strategy for --{}: {}", param, s))),
+	}
+}
+
 fn keystore_path(base_path: &Path) -> PathBuf {
 	let mut path = base_path.to_owned();
 	path.push("keystore");
@@ -448,6 +709,18 @@ fn db_path(base_path: &Path) -> PathBuf {
 	path
 }
 
+fn statement_log_path(base_path: &Path) -> PathBuf {
+	let mut path = base_path.to_owned();
+	path.push("statements");
+	path
+}
+
+fn availability_store_path(base_path: &Path) -> PathBuf {
+	let mut path = base_path.to_owned();
+	path.push("availability");
+	path
+}
+
 fn network_path(base_path: &Path) -> PathBuf {
 	let mut path = base_path.to_owned();
 	path.push("network");
@@ -0,0 +1,36 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Errors that can occur while gathering the inputs to a collation.
+
+use polkadot_primitives::parachain::Id as ParaId;
+
+error_chain! {
+	errors {
+		Api(e: String) {
+			description("Relay chain API call failed"),
+			display("Relay chain API call failed: {}", e),
+		}
+		Timeout(id: ParaId) {
+			description("Timed out fetching egress queues"),
+			display("Timed out fetching egress queues of parachain {:?}", id),
+		}
+		Decode(what: &'static str) {
+			description("Failed to decode collation input"),
+			display("Failed to decode {}", what),
+		}
+	}
+}
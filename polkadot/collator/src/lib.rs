@@ -42,19 +42,44 @@
 //! chain history.
 //!
 //! This crate defines traits which provide context necessary for collation logic
-//! to be performed, as the collation logic itself.
+//! to be performed, as the collation logic itself. It also provides a watch-only
+//! mode, which checks the candidates other collators got included instead.
 
 extern crate futures;
 extern crate substrate_codec as codec;
 extern crate substrate_primitives as primitives;
+extern crate substrate_runtime_primitives as runtime_primitives;
+extern crate polkadot_api;
+extern crate polkadot_parachain;
 extern crate polkadot_runtime;
 extern crate polkadot_primitives;
+extern crate serde;
+
+#[macro_use]
+extern crate serde_derive;
+
+#[cfg(test)]
+extern crate serde_json;
+
+#[macro_use]
+extern crate error_chain;
+
+#[macro_use]
+extern crate log;
 
 use std::collections::{BTreeSet, BTreeMap};
 
 use futures::{stream, Stream, Future, IntoFuture};
 use polkadot_primitives::parachain::{self, CandidateSignature, ConsolidatedIngress, Message, Id as ParaId};
 
+pub use self::error::{Error, ErrorKind};
+pub use self::watch::{BlockDataSource, CandidateCheck, Verdict, Watcher, check_candidate};
+pub use self::spec::{ParachainSpec, CHAIN_SPEC_EXTENSION};
+
+mod error;
+mod spec;
+mod watch;
+
 /// Parachain context needed for collation.
 ///
 /// This can be implemented through an externally attached service or a stub.
@@ -70,7 +95,8 @@ pub trait ParachainContext {
 /// This encapsulates a network and local database which may store
 /// some of the input.
 pub trait RelayChainContext {
-	type Error;
+	/// Errors when fetching collation inputs.
+	type Error: Into<Error>;
 
 	/// Future that resolves to the un-routed egress queues of a parachain.
 	/// The first item is the oldest.
@@ -85,7 +111,7 @@ pub trait RelayChainContext {
 
 /// Collate the necessary ingress queue using the given context.
 pub fn collate_ingress<'a, R>(relay_context: R)
-	-> Box<Future<Item=ConsolidatedIngress, Error=R::Error> + 'a>
+	-> Box<Future<Item=ConsolidatedIngress, Error=Error> + 'a>
 	where
 		R: RelayChainContext,
 		R::Error: 'a,
@@ -97,7 +123,12 @@ pub fn collate_ingress<'a, R>(relay_context: R)
 		let fetch = relay_context
 			.unrouted_egress(routing_parachain)
 			.into_future()
-			.map(move |egresses| (routing_parachain, egresses));
+			.map(move |egresses| (routing_parachain, egresses))
+			.map_err(move |e| {
+				let e = e.into();
+				warn!(target: "collator", "Unable to fetch egress queues of parachain {:?}: {}", routing_parachain, e);
+				e
+			});
 
 		egress_fetch.push(fetch);
 	}
@@ -122,7 +153,7 @@ pub fn collate_ingress<'a, R>(relay_context: R)
 
 /// Produce a candidate for the parachain.
 pub fn collate<'a, R, P>(local_id: ParaId, relay_context: R, para_context: P)
-	-> Box<Future<Item=parachain::Candidate, Error=R::Error> + 'a>
+	-> Box<Future<Item=parachain::Candidate, Error=Error> + 'a>
 	where
 		R: RelayChainContext,
 	    R::Error: 'a,
@@ -134,6 +165,14 @@ pub fn collate<'a, R, P>(local_id: ParaId, relay_context: R, para_context: P)
 			ingress.0.iter().flat_map(|&(id, ref msgs)| msgs.iter().cloned().map(move |msg| (id, msg)))
 		);
 
+		if polkadot_parachain::pov_size_near_limit(block_data.0.len()) {
+			warn!(
+				target: "collator",
+				"Proof of validity of the candidate is {} bytes, close to the limit of {}",
+				block_data.0.len(), polkadot_parachain::MAX_POV_SIZE
+			);
+		}
+
 		parachain::Candidate {
 			parachain_index: local_id,
 			collator_signature: signature,
@@ -155,17 +194,22 @@ mod tests {
 	pub struct DummyRelayChainCtx {
 		egresses: HashMap<ParaId, Vec<Vec<Message>>>,
 		currently_routing: BTreeSet<ParaId>,
+		timing_out: BTreeSet<ParaId>,
 	}
 
 	impl RelayChainContext for DummyRelayChainCtx {
-		type Error = ();
-		type FutureEgress = Result<Vec<Vec<Message>>, ()>;
+		type Error = Error;
+		type FutureEgress = Result<Vec<Vec<Message>>, Error>;
 
 		fn routing_parachains(&self) -> BTreeSet<ParaId> {
 			self.currently_routing.clone()
 		}
 
-		fn unrouted_egress(&self, id: ParaId) -> Result<Vec<Vec<Message>>, ()> {
+		fn unrouted_egress(&self, id: ParaId) -> Result<Vec<Vec<Message>>, Error> {
+			if self.timing_out.contains(&id) {
+				return Err(ErrorKind::Timeout(id).into());
+			}
+
 			Ok(self.egresses.get(&id).cloned().unwrap_or_default())
 		}
 	}
@@ -199,6 +243,7 @@ mod tests {
 					message(vec![13]),
 				]),
 			].into_iter().collect(),
+			timing_out: BTreeSet::new(),
 		};
 
 		assert_eq!(
@@ -215,4 +260,18 @@ mod tests {
 			]
 		))
 	}
+
+	#[test]
+	fn egress_failure_is_reported() {
+		let dummy_ctx = DummyRelayChainCtx {
+			currently_routing: vec![2.into(), 3.into()].into_iter().collect(),
+			egresses: HashMap::new(),
+			timing_out: vec![3.into()].into_iter().collect(),
+		};
+
+		match collate_ingress(dummy_ctx).wait() {
+			Err(Error(ErrorKind::Timeout(id), _)) => assert_eq!(id, 3.into()),
+			_ => panic!("fetching egress of parachain 3 times out"),
+		}
+	}
 }
@@ -0,0 +1,56 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Chain spec fields of a parachain.
+//!
+//! A node embedding the collator runs with the spec of the relay chain. The
+//! fields of its parachain are shipped in the same spec, as an extension stored
+//! under `CHAIN_SPEC_EXTENSION` next to the relay chain's own fields.
+
+use polkadot_primitives::parachain::{HeadData, Id as ParaId};
+
+/// Name of the chain spec extension holding the `ParachainSpec`.
+pub const CHAIN_SPEC_EXTENSION: &str = "parachain";
+
+/// Parachain-specific fields of a chain spec.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParachainSpec {
+	/// Id of the parachain.
+	pub para_id: ParaId,
+	/// Head data of the parachain at genesis.
+	pub genesis_head: HeadData,
+	/// Relay chain nodes to connect to, in addition to the boot nodes of the
+	/// relay chain spec.
+	#[serde(default)]
+	pub relay_boot_nodes: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json;
+
+	#[test]
+	fn boot_nodes_are_optional() {
+		let spec: ParachainSpec = serde_json::from_str(r#"{"paraId":5,"genesisHead":"0x0102"}"#).unwrap();
+		assert_eq!(spec, ParachainSpec {
+			para_id: 5.into(),
+			genesis_head: HeadData(vec![1, 2]),
+			relay_boot_nodes: Vec::new(),
+		});
+	}
+}
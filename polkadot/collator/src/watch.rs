@@ -0,0 +1,269 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Watch-only collation.
+//!
+//! Rather than producing candidates, a watching collator follows the relay
+//! chain and re-executes the candidates other collators got included for its
+//! parachain, on top of the head at the relay parent. This lets parachain teams
+//! running redundant collators catch a candidate which doesn't validate under
+//! the parachain's validation code, such as one produced by a faulty collator.
+//!
+//! The block data of candidates isn't kept by the relay chain, so it is fetched
+//! from a `BlockDataSource`, typically a full node of the parachain.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use codec::Slicable;
+use futures::{future, Future, IntoFuture};
+use polkadot_api::PolkadotApi;
+use polkadot_parachain::{wasm, ValidationParams, MAX_POV_SIZE, pov_size_near_limit};
+use polkadot_primitives::{AccountId, Block, BlockId, Hash};
+use polkadot_primitives::parachain::{BlockData, CandidateReceipt, Id as ParaId};
+use polkadot_runtime::{Block as RuntimeBlock, CheckedBlock};
+use runtime_primitives::traits::Header as HeaderT;
+
+use {Error, ErrorKind};
+
+/// Source of the block data of included candidates.
+pub trait BlockDataSource {
+	/// Errors when fetching block data.
+	type Error: Into<Error>;
+
+	/// Future that resolves to the block data of a candidate, if it can be found.
+	type FutureBlockData: IntoFuture<Item=Option<BlockData>, Error=Self::Error>;
+
+	/// Fetch the block data of a candidate.
+	fn block_data(&self, receipt: &CandidateReceipt) -> Self::FutureBlockData;
+}
+
+/// Outcome of re-executing an included candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+	/// The candidate produces the head data it committed to.
+	Valid,
+	/// The candidate failed validation, for the given reason.
+	Invalid(String),
+	/// The block data of the candidate couldn't be found.
+	Unavailable,
+}
+
+/// The check of a candidate included in a relay chain block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateCheck {
+	/// Hash of the relay chain block which included the candidate.
+	pub relay_block: Hash,
+	/// Hash of the candidate receipt.
+	pub candidate: Hash,
+	/// The collator of the candidate.
+	pub collator: AccountId,
+	/// Size of the proof of validity of the candidate, if its block data was found.
+	pub pov_size: Option<usize>,
+	/// The outcome.
+	pub verdict: Verdict,
+}
+
+/// Re-execute a candidate on top of `parent_head` under `validation_code`.
+pub fn check_candidate(
+	validation_code: &[u8],
+	parent_head: Vec<u8>,
+	block_data: &BlockData,
+	receipt: &CandidateReceipt,
+) -> Verdict {
+	let params = ValidationParams {
+		parent_head,
+		block_data: block_data.0.clone(),
+	};
+
+	match wasm::validate_candidate(validation_code, params) {
+		Ok(ref result) if result.head_data == receipt.head_data.0 => Verdict::Valid,
+		Ok(_) => Verdict::Invalid("validation produced different head data".into()),
+		Err(e) => Verdict::Invalid(e.to_string()),
+	}
+}
+
+/// Checks the candidates of a parachain included in relay chain blocks.
+///
+/// Invalid candidates are logged as warnings and counted, so they can be
+/// reported by whatever embeds the watcher.
+pub struct Watcher<P, S> {
+	para_id: ParaId,
+	client: Arc<P>,
+	source: S,
+	invalid: Arc<AtomicUsize>,
+	unavailable: Arc<AtomicUsize>,
+}
+
+impl<P: PolkadotApi, S: BlockDataSource> Watcher<P, S> {
+	/// Create a watcher of the candidates of `para_id`.
+	pub fn new(para_id: ParaId, client: Arc<P>, source: S) -> Self {
+		Watcher {
+			para_id,
+			client,
+			source,
+			invalid: Arc::new(AtomicUsize::new(0)),
+			unavailable: Arc::new(AtomicUsize::new(0)),
+		}
+	}
+
+	/// Number of included candidates which failed validation so far.
+	pub fn invalid_candidates(&self) -> usize {
+		self.invalid.load(Ordering::SeqCst)
+	}
+
+	/// Number of included candidates whose block data couldn't be found so far.
+	pub fn unavailable_candidates(&self) -> usize {
+		self.unavailable.load(Ordering::SeqCst)
+	}
+
+	/// Check the candidate for the watched parachain included in a relay chain
+	/// block, if any.
+	pub fn check_block<'a>(&self, block: &Block) -> Box<Future<Item=Option<CandidateCheck>, Error=Error> + 'a>
+		where S::FutureBlockData: 'a, S::Error: 'a
+	{
+		let relay_block = block.header.hash();
+		let relay_parent = BlockId::hash(block.header.parent_hash);
+
+		let encoded = block.encode();
+		let block = match RuntimeBlock::decode(&mut &encoded[..]).and_then(|b| CheckedBlock::new(b).ok()) {
+			Some(block) => block,
+			None => return Box::new(future::err(ErrorKind::Decode("relay chain block").into())),
+		};
+
+		let receipt = match block.parachain_heads().iter().find(|r| r.parachain_index == self.para_id) {
+			Some(receipt) => receipt.clone(),
+			None => return Box::new(future::ok(None)),
+		};
+
+		let inputs = match self.client.collation_inputs(&relay_parent, self.para_id) {
+			Ok(inputs) => inputs,
+			Err(e) => return Box::new(future::err(ErrorKind::Api(e.to_string()).into())),
+		};
+
+		let (invalid, unavailable) = (self.invalid.clone(), self.unavailable.clone());
+		let fetch = self.source.block_data(&receipt).into_future().map_err(Into::into);
+
+		Box::new(fetch.map(move |block_data| {
+			let pov_size = block_data.as_ref().map(|data| data.0.len());
+			let verdict = match (inputs.code, inputs.head, block_data) {
+				(Some(code), Some(head), Some(block_data)) => check_candidate(&code, head, &block_data, &receipt),
+				(None, _, _) | (_, None, _) => Verdict::Invalid("parachain inactive at the relay parent".into()),
+				(_, _, None) => Verdict::Unavailable,
+			};
+
+			let check = CandidateCheck {
+				relay_block,
+				candidate: receipt.hash(),
+				collator: receipt.collator,
+				pov_size,
+				verdict,
+			};
+
+			match pov_size {
+				Some(size) if pov_size_near_limit(size) => warn!(
+					target: "collator",
+					"Proof of validity of candidate {} included in {} is {} bytes, close to the limit of {}",
+					check.candidate, relay_block, size, MAX_POV_SIZE
+				),
+				_ => {}
+			}
+
+			match check.verdict {
+				Verdict::Valid => {
+					debug!(target: "collator", "Candidate {} included in {} is valid", check.candidate, relay_block);
+				}
+				Verdict::Invalid(ref reason) => {
+					invalid.fetch_add(1, Ordering::SeqCst);
+					warn!(
+						target: "collator",
+						"Candidate {} of collator {} included in relay chain block {} is invalid: {}",
+						check.candidate, check.collator, relay_block, reason
+					);
+				}
+				Verdict::Unavailable => {
+					unavailable.fetch_add(1, Ordering::SeqCst);
+					warn!(target: "collator", "No block data for candidate {} included in {}", check.candidate, relay_block);
+				}
+			}
+
+			Some(check)
+		}))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use polkadot_primitives::parachain::HeadData;
+
+	const TEST_CODE: &[u8] = include_bytes!("../../parachain/tests/res/basic_add.wasm");
+
+	// keccak-256 of the encoded state `0` of the `basic_add` test parachain.
+	const ZERO_STATE_HASH: [u8; 32] = [
+		1, 27, 77, 3, 221, 140, 1, 241, 4, 145, 67, 207, 156, 76, 129, 126,
+		75, 22, 127, 29, 27, 131, 229, 198, 240, 241, 13, 137, 186, 30, 123, 206,
+	];
+
+	// head data of the `basic_add` test parachain at genesis: number, parent hash and state hash.
+	fn genesis_head() -> Vec<u8> {
+		let mut head = Vec::new();
+		0u64.using_encoded(|s| head.extend(s));
+		[0u8; 32].using_encoded(|s| head.extend(s));
+		ZERO_STATE_HASH.using_encoded(|s| head.extend(s));
+		head
+	}
+
+	// block data of the `basic_add` test parachain: starting state and amount to add.
+	fn block_data(state: u64, add: u64) -> BlockData {
+		let mut data = Vec::new();
+		state.using_encoded(|s| data.extend(s));
+		add.using_encoded(|s| data.extend(s));
+		BlockData(data)
+	}
+
+	fn receipt(head_data: Vec<u8>) -> CandidateReceipt {
+		CandidateReceipt {
+			parachain_index: 5.into(),
+			collator: Default::default(),
+			head_data: HeadData(head_data),
+			balance_uploads: Vec::new(),
+			commitments: Default::default(),
+		}
+	}
+
+	#[test]
+	fn checks_head_data_of_candidates() {
+		let expected = wasm::validate_candidate(TEST_CODE, ValidationParams {
+			parent_head: genesis_head(),
+			block_data: block_data(0, 512).0,
+		}).unwrap().head_data;
+
+		let good = receipt(expected.clone());
+		assert_eq!(check_candidate(TEST_CODE, genesis_head(), &block_data(0, 512), &good), Verdict::Valid);
+
+		// the block data produces a different head than the collator committed to.
+		match check_candidate(TEST_CODE, genesis_head(), &block_data(0, 513), &good) {
+			Verdict::Invalid(_) => {}
+			v => panic!("expected an invalid verdict, got {:?}", v),
+		}
+
+		match check_candidate(TEST_CODE, genesis_head(), &BlockData(vec![1, 2, 3]), &good) {
+			Verdict::Invalid(_) => {}
+			v => panic!("expected an invalid verdict, got {:?}", v),
+		}
+	}
+}
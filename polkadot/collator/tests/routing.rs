@@ -0,0 +1,158 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Routing of messages between two parachains through the relay chain.
+//!
+//! The relay chain is simulated in memory, following the rules of the runtime:
+//! the egress a parachain posts to a destination stays unrouted until the
+//! destination gets a candidate included, which consumes everything posted
+//! before that block. Parachain A posts messages to B in the blocks its
+//! candidates are included in, and B's collator gathers them with
+//! `collate_ingress`. Not every candidate is included, so messages must
+//! survive being collated into a candidate which is dropped.
+
+extern crate futures;
+extern crate polkadot_collator as collator;
+extern crate polkadot_primitives;
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use collator::{Error, ParachainContext, RelayChainContext};
+use futures::Future;
+use polkadot_primitives::AccountId;
+use polkadot_primitives::parachain::{BlockData, Candidate, CandidateSignature, Id as ParaId, Message};
+
+const PARA_A: u32 = 1;
+const PARA_B: u32 = 2;
+
+struct Batch {
+	messages: Vec<Message>,
+	consumed: bool,
+}
+
+// the egress posted by A to B, one batch per relay chain block A posted in.
+#[derive(Default)]
+struct RelayChain {
+	egress_a_to_b: Vec<Batch>,
+}
+
+impl RelayChain {
+	// import a block including the candidates of the given parachains, with
+	// the messages A posts to B if its candidate is included.
+	fn import_block(&mut self, included: &[u32], posted: Vec<Message>) {
+		if included.contains(&PARA_B) {
+			self.egress_a_to_b.retain(|batch| !batch.consumed);
+			for batch in &mut self.egress_a_to_b {
+				batch.consumed = true;
+			}
+		}
+
+		if included.contains(&PARA_A) && !posted.is_empty() {
+			self.egress_a_to_b.push(Batch { messages: posted, consumed: false });
+		}
+	}
+}
+
+// B's view of the relay chain at the latest block.
+struct RelayContext(Rc<RefCell<RelayChain>>);
+
+impl RelayChainContext for RelayContext {
+	type Error = Error;
+	type FutureEgress = Result<Vec<Vec<Message>>, Error>;
+
+	fn routing_parachains(&self) -> BTreeSet<ParaId> {
+		let mut routing = BTreeSet::new();
+		routing.insert(PARA_A.into());
+		routing
+	}
+
+	fn unrouted_egress(&self, id: ParaId) -> Result<Vec<Vec<Message>>, Error> {
+		assert_eq!(id, PARA_A.into());
+		Ok(self.0.borrow().egress_a_to_b.iter()
+			.filter(|batch| !batch.consumed)
+			.map(|batch| batch.messages.clone())
+			.collect())
+	}
+}
+
+// B processes its ingress into the block data.
+struct ParachainB;
+
+impl ParachainContext for ParachainB {
+	fn produce_candidate<I: IntoIterator<Item=(ParaId, Message)>>(&self, ingress: I)
+		-> (BlockData, AccountId, CandidateSignature)
+	{
+		let data = ingress.into_iter()
+			.flat_map(|(id, message)| {
+				assert_eq!(id, PARA_A.into());
+				message.0
+			})
+			.collect();
+		(BlockData(data), Default::default(), Default::default())
+	}
+}
+
+fn message(n: u8) -> Message {
+	Message(vec![n])
+}
+
+#[test]
+fn messages_arrive_once_and_in_order() {
+	let relay = Rc::new(RefCell::new(RelayChain::default()));
+	let mut posted = Vec::new();
+	let mut delivered = Vec::new();
+
+	// relay chain blocks: whether A's and B's candidates are included.
+	let blocks = [
+		(true, true),
+		(true, false),
+		(false, true),
+		(true, true),
+		(true, false),
+		(true, false),
+		(false, false),
+		(true, true),
+		(false, true),
+		(false, true),
+	];
+
+	for (number, &(include_a, include_b)) in blocks.iter().enumerate() {
+		let candidate: Candidate = collator::collate(PARA_B.into(), RelayContext(relay.clone()), ParachainB)
+			.wait()
+			.unwrap();
+		assert_eq!(candidate.parachain_index, PARA_B.into());
+
+		let outgoing = vec![message(number as u8 * 2), message(number as u8 * 2 + 1)];
+		let mut included = Vec::new();
+		if include_a {
+			included.push(PARA_A);
+			posted.extend(outgoing.iter().cloned());
+		}
+		if include_b {
+			included.push(PARA_B);
+			delivered.extend(candidate.block.0.into_iter().map(message));
+		}
+
+		relay.borrow_mut().import_block(&included, outgoing);
+	}
+
+	// B's candidates were included after A's last post, so everything was delivered.
+	assert!(!posted.is_empty());
+	assert_eq!(delivered, posted);
+	assert!(relay.borrow().egress_a_to_b.iter().all(|batch| batch.consumed));
+}
@@ -0,0 +1,247 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Store of the block data of parachain candidates which the local validator
+//! makes available to others.
+//!
+//! The data must be kept until the relay chain block including the candidate
+//! is finalized, plus a safety margin for validators which are still catching
+//! up. Relay chain blocks are final once imported, so the data noted while
+//! building on a relay parent is pruned once the block after that parent is
+//! more than a retention window of blocks behind the latest imported one.
+//!
+//! Each candidate is kept in a file of its own, so that a restarted node still
+//! serves what it made available before.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use codec::Slicable;
+use polkadot_primitives::Hash;
+use polkadot_primitives::parachain::BlockData;
+use parking_lot::Mutex;
+
+use error::Error;
+
+/// Default number of relay chain blocks past finality to keep candidate data for.
+pub const DEFAULT_AVAILABILITY_RETENTION: u64 = 64;
+
+struct Entry {
+	relay_number: u64,
+	size: usize,
+	// the data itself, when not kept on disk.
+	block_data: Option<BlockData>,
+}
+
+struct StoreInner {
+	path: Option<PathBuf>,
+	retention: u64,
+	entries: HashMap<Hash, Entry>,
+	// candidates by the number of the relay parent they were noted under.
+	by_relay_number: BTreeMap<u64, Vec<Hash>>,
+	stored_bytes: usize,
+}
+
+impl StoreInner {
+	fn file_path(&self, candidate: &Hash) -> Option<PathBuf> {
+		self.path.as_ref().map(|path| {
+			let name: String = candidate.0.iter().map(|b| format!("{:02x}", b)).collect();
+			path.join(name)
+		})
+	}
+
+	fn insert(&mut self, candidate: Hash, entry: Entry) {
+		self.stored_bytes += entry.size;
+		self.by_relay_number.entry(entry.relay_number).or_insert_with(Vec::new).push(candidate);
+		self.entries.insert(candidate, entry);
+	}
+}
+
+/// Statistics of an availability store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AvailabilityStats {
+	/// Number of candidates whose data is stored.
+	pub candidates: usize,
+	/// Total size of the stored block data, in bytes.
+	pub stored_bytes: usize,
+}
+
+/// Block data of candidates made available by the local validator.
+///
+/// Cheaply cloneable; all clones share the same store.
+#[derive(Clone)]
+pub struct AvailabilityStore {
+	inner: Arc<Mutex<StoreInner>>,
+}
+
+impl AvailabilityStore {
+	/// Create a store which is kept in memory only.
+	pub fn in_memory(retention: u64) -> Self {
+		AvailabilityStore {
+			inner: Arc::new(Mutex::new(StoreInner {
+				path: None,
+				retention,
+				entries: HashMap::new(),
+				by_relay_number: BTreeMap::new(),
+				stored_bytes: 0,
+			})),
+		}
+	}
+
+	/// Open the store in the given directory, loading the index of previously
+	/// stored data. Files which can't be decoded are removed.
+	pub fn open(path: PathBuf, retention: u64) -> Result<Self, Error> {
+		fs::create_dir_all(&path)?;
+
+		let mut inner = StoreInner {
+			path: Some(path.clone()),
+			retention,
+			entries: HashMap::new(),
+			by_relay_number: BTreeMap::new(),
+			stored_bytes: 0,
+		};
+
+		for file in fs::read_dir(&path)? {
+			let file_path = file?.path();
+			let mut contents = Vec::new();
+			File::open(&file_path)?.read_to_end(&mut contents)?;
+
+			match <(Hash, u64, Vec<u8>)>::decode(&mut &contents[..]) {
+				Some((candidate, relay_number, block_data)) => inner.insert(candidate, Entry {
+					relay_number,
+					size: block_data.len(),
+					block_data: None,
+				}),
+				None => {
+					warn!(target: "consensus", "Removing undecodable availability data at {}", file_path.display());
+					fs::remove_file(&file_path)?;
+				}
+			}
+		}
+
+		Ok(AvailabilityStore { inner: Arc::new(Mutex::new(inner)) })
+	}
+
+	/// Store the block data of a candidate made available while building on
+	/// top of the relay chain block with the given number.
+	pub fn note(&self, relay_number: u64, candidate: Hash, block_data: BlockData) -> Result<(), Error> {
+		let mut inner = self.inner.lock();
+		if inner.entries.contains_key(&candidate) {
+			return Ok(());
+		}
+
+		let size = block_data.0.len();
+		let block_data = match inner.file_path(&candidate) {
+			Some(file_path) => {
+				let mut file = File::create(&file_path)?;
+				(candidate, relay_number, block_data.0).using_encoded(|s| file.write_all(s))?;
+				file.sync_data()?;
+				None
+			}
+			None => Some(block_data),
+		};
+
+		inner.insert(candidate, Entry { relay_number, size, block_data });
+		Ok(())
+	}
+
+	/// Get the block data of a candidate, if stored.
+	pub fn block_data(&self, candidate: &Hash) -> Option<BlockData> {
+		let inner = self.inner.lock();
+		let entry = inner.entries.get(candidate)?;
+		if let Some(ref block_data) = entry.block_data {
+			return Some(block_data.clone());
+		}
+
+		let file_path = inner.file_path(candidate)?;
+		let mut contents = Vec::new();
+		if let Err(e) = File::open(&file_path).and_then(|mut f| f.read_to_end(&mut contents)) {
+			warn!(target: "consensus", "Unable to read availability data at {}: {}", file_path.display(), e);
+			return None;
+		}
+
+		<(Hash, u64, Vec<u8>)>::decode(&mut &contents[..]).map(|(_, _, data)| BlockData(data))
+	}
+
+	/// Note that the relay chain block with the given number is finalized,
+	/// pruning the data of candidates which could be included more than the
+	/// retention window of blocks before it.
+	pub fn note_finalized(&self, number: u64) -> Result<AvailabilityStats, Error> {
+		let mut inner = self.inner.lock();
+		let keep_from = match number.checked_sub(inner.retention + 1) {
+			Some(n) => n,
+			None => return Ok(Self::stats_of(&inner)),
+		};
+
+		let retained = inner.by_relay_number.split_off(&keep_from);
+		let pruned = ::std::mem::replace(&mut inner.by_relay_number, retained);
+		for candidate in pruned.into_iter().flat_map(|(_, candidates)| candidates) {
+			if let Some(entry) = inner.entries.remove(&candidate) {
+				inner.stored_bytes -= entry.size;
+			}
+			if let Some(file_path) = inner.file_path(&candidate) {
+				fs::remove_file(&file_path)?;
+			}
+			trace!(target: "consensus", "Pruned availability data of candidate {:?}", candidate);
+		}
+
+		Ok(Self::stats_of(&inner))
+	}
+
+	/// Number of candidates and bytes currently stored.
+	pub fn stats(&self) -> AvailabilityStats {
+		Self::stats_of(&self.inner.lock())
+	}
+
+	fn stats_of(inner: &StoreInner) -> AvailabilityStats {
+		AvailabilityStats {
+			candidates: inner.entries.len(),
+			stored_bytes: inner.stored_bytes,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tempdir::TempDir;
+
+	#[test]
+	fn data_is_pruned_past_the_retention_window() {
+		let temp_dir = TempDir::new("availability").unwrap();
+		let path = temp_dir.path().join("availability");
+
+		{
+			let store = AvailabilityStore::open(path.clone(), 2).unwrap();
+			store.note(1, [1; 32].into(), BlockData(vec![1; 10])).unwrap();
+			store.note(2, [2; 32].into(), BlockData(vec![2; 20])).unwrap();
+			assert_eq!(store.stats(), AvailabilityStats { candidates: 2, stored_bytes: 30 });
+		}
+
+		// the data survives a restart.
+		let store = AvailabilityStore::open(path, 2).unwrap();
+		assert_eq!(store.block_data(&[1; 32].into()), Some(BlockData(vec![1; 10])));
+
+		// included at #2 at the earliest, so kept while finalizing up to #4.
+		assert_eq!(store.note_finalized(4).unwrap(), AvailabilityStats { candidates: 2, stored_bytes: 30 });
+		assert_eq!(store.note_finalized(5).unwrap(), AvailabilityStats { candidates: 1, stored_bytes: 20 });
+		assert_eq!(store.block_data(&[1; 32].into()), None);
+		assert_eq!(store.block_data(&[2; 32].into()), Some(BlockData(vec![2; 20])));
+	}
+}
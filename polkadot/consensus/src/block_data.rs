@@ -0,0 +1,167 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Distribution of the block data of parachain candidates.
+//!
+//! Block data is gossiped among the validators assigned to a parachain, under
+//! a topic derived from the relay parent and the parachain ID, so that it
+//! doesn't reach the rest of the validator set. The validator which collated
+//! a candidate sends its block data to the group right away. A validator which
+//! is missing the data of a candidate, to check its validity or to guarantee
+//! its availability, asks the group for it by candidate digest, and any
+//! validity guarantor holding it answers.
+//!
+//! Candidate receipts don't commit to the block data, so data is signed by the
+//! validity guarantor sending it and only accepted from the validity
+//! guarantors of the group. Invalid data is then attributable to its sender.
+
+use bft::{self, Signer};
+use codec::{Input, Slicable};
+use ed25519;
+use polkadot_primitives::Hash;
+use polkadot_primitives::parachain::{BlockData, CandidateSignature, Id as ParaId};
+use primitives::AuthorityId;
+use runtime_support::Hashable;
+
+// distinguishes block data topics and signatures from any other.
+const BLOCK_DATA_PREFIX: &[u8] = b"parachain-block-data";
+
+const REQUEST_TAG: u8 = 0;
+const DATA_TAG: u8 = 1;
+
+/// The gossip topic of the validators assigned to a parachain while building
+/// on top of the given relay chain parent.
+pub fn group_topic(parent_hash: &Hash, para_id: ParaId) -> Hash {
+	(BLOCK_DATA_PREFIX.to_vec(), *parent_hash, para_id).blake2_256().into()
+}
+
+/// Block data of a candidate, signed by the validator sending it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedBlockData {
+	/// Digest of the candidate receipt.
+	pub candidate: Hash,
+	/// The block data.
+	pub block_data: BlockData,
+	/// Signature of the sender on the candidate digest, the hash of the block
+	/// data and the relay parent.
+	pub signature: CandidateSignature,
+	/// The sender.
+	pub sender: AuthorityId,
+}
+
+/// A message gossiped on the topic of a group.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockDataMessage {
+	/// Request for the block data of the candidate with the given digest.
+	Request(Hash),
+	/// Block data of a candidate.
+	Data(SignedBlockData),
+}
+
+impl BlockDataMessage {
+	/// Encode for gossip.
+	pub fn encode(&self) -> Vec<u8> {
+		match *self {
+			BlockDataMessage::Request(ref candidate) => {
+				let mut encoded = vec![REQUEST_TAG];
+				encoded.extend(candidate.encode());
+				encoded
+			}
+			BlockDataMessage::Data(ref data) => {
+				let mut encoded = vec![DATA_TAG];
+				encoded.extend((data.candidate, data.block_data.0.clone(), data.signature.clone(), data.sender).encode());
+				encoded
+			}
+		}
+	}
+
+	/// Decode a gossiped message. Signatures aren't checked.
+	pub fn decode(mut data: &[u8]) -> Option<Self> {
+		match data.read_byte()? {
+			REQUEST_TAG => Hash::decode(&mut data).map(BlockDataMessage::Request),
+			DATA_TAG => {
+				let (candidate, block_data, signature, sender) =
+					<(Hash, Vec<u8>, CandidateSignature, AuthorityId)>::decode(&mut data)?;
+				Some(BlockDataMessage::Data(SignedBlockData {
+					candidate,
+					block_data: BlockData(block_data),
+					signature,
+					sender,
+				}))
+			}
+			_ => None,
+		}
+	}
+}
+
+// the message signed for block data: the hash of the block data, with the
+// candidate digest and the relay parent.
+fn block_data_payload(candidate: &Hash, block_data: &BlockData, parent_hash: &Hash) -> Vec<u8> {
+	(BLOCK_DATA_PREFIX.to_vec(), *candidate, block_data.0.blake2_256(), *parent_hash).encode()
+}
+
+/// Sign the block data of a candidate against a parent hash.
+pub fn sign_block_data(key: &Signer, parent_hash: &Hash, candidate: Hash, block_data: BlockData) -> Result<SignedBlockData, bft::Error> {
+	let signature = key.sign(&block_data_payload(&candidate, &block_data, parent_hash))?;
+	Ok(SignedBlockData {
+		candidate,
+		block_data,
+		signature: signature.into(),
+		sender: key.public().into(),
+	})
+}
+
+/// Check the signature on block data made against a parent hash.
+pub fn check_block_data(data: &SignedBlockData, parent_hash: &Hash) -> bool {
+	let payload = block_data_payload(&data.candidate, &data.block_data, parent_hash);
+	ed25519::verify_strong(&data.signature.0, &payload, ed25519::Public::from(data.sender))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_keyring::Keyring;
+
+	#[test]
+	fn messages_round_trip() {
+		let parent_hash = [1; 32].into();
+		let request = BlockDataMessage::Request([2; 32].into());
+		assert_eq!(BlockDataMessage::decode(&request.encode()), Some(request));
+
+		let data = sign_block_data(&Keyring::Alice.pair(), &parent_hash, [2; 32].into(), BlockData(vec![1, 2, 3])).unwrap();
+		let message = BlockDataMessage::Data(data);
+		assert_eq!(BlockDataMessage::decode(&message.encode()), Some(message));
+
+		assert_eq!(BlockDataMessage::decode(&[]), None);
+		assert_eq!(BlockDataMessage::decode(&[7, 0, 0]), None);
+	}
+
+	#[test]
+	fn signatures_cover_data_and_parent() {
+		let parent_hash = [1; 32].into();
+		let data = sign_block_data(&Keyring::Alice.pair(), &parent_hash, [2; 32].into(), BlockData(vec![1, 2, 3])).unwrap();
+		assert_eq!(data.sender, Keyring::Alice.to_raw_public().into());
+		assert!(check_block_data(&data, &parent_hash));
+		assert!(!check_block_data(&data, &[3; 32].into()));
+
+		let mut tampered = data.clone();
+		tampered.block_data = BlockData(vec![1, 2, 4]);
+		assert!(!check_block_data(&tampered, &parent_hash));
+
+		assert_ne!(group_topic(&parent_hash, 1.into()), group_topic(&parent_hash, 2.into()));
+		assert_ne!(group_topic(&parent_hash, 1.into()), parent_hash);
+	}
+}
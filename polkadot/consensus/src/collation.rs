@@ -18,14 +18,24 @@
 //!
 //! This module contains type definitions, a trait for a batch of collators, and a trait for
 //! attempting to fetch a collation repeatedly until a valid one is obtained.
+//!
+//! Validation outcomes are kept in a `ValidationCache`, shared between the fetching of
+//! local collations and the checking of candidates from other validators, so that a
+//! candidate is executed at most once per relay parent.
 
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 
 use polkadot_api::PolkadotApi;
 use polkadot_primitives::{Hash, AccountId, BlockId};
 use polkadot_primitives::parachain::{Id as ParaId, Chain, BlockData, Extrinsic, CandidateReceipt};
+use runtime_primitives::traits::{BlakeTwo256, Hashing};
 
 use futures::prelude::*;
+use parking_lot::Mutex;
+
+/// Default number of validation outcomes to keep per relay parent.
+pub const DEFAULT_CACHED_OUTCOMES: usize = 256;
 
 /// A full collation.
 pub struct Collation {
@@ -61,16 +71,25 @@ pub struct CollationFetch<C: Collators, P: PolkadotApi> {
 	collators: C,
 	live_fetch: Option<<C::Collation as IntoFuture>::Future>,
 	client: Arc<P>,
+	validation_cache: ValidationCache,
 }
 
 impl<C: Collators, P: PolkadotApi> CollationFetch<C, P> {
 	/// Create a new collation fetcher for the given chain.
-	pub fn new(parachain: Chain, relay_parent: BlockId, relay_parent_hash: Hash, collators: C, client: Arc<P>) -> Self {
+	pub fn new(
+		parachain: Chain,
+		relay_parent: BlockId,
+		relay_parent_hash: Hash,
+		collators: C,
+		client: Arc<P>,
+		validation_cache: ValidationCache,
+	) -> Self {
 		CollationFetch {
 			relay_parent_hash,
 			relay_parent,
 			collators,
 			client,
+			validation_cache,
 			parachain: match parachain {
 				Chain::Parachain(id) => Some(id),
 				Chain::Relay => None,
@@ -101,7 +120,7 @@ impl<C: Collators, P: PolkadotApi> Future for CollationFetch<C, P> {
 				try_ready!(poll)
 			};
 
-			match validate_collation(&*self.client, &self.relay_parent, &x) {
+			match self.validation_cache.validate(&*self.client, &self.relay_parent, &x) {
 				Ok(()) => {
 					self.parachain = None;
 
@@ -137,6 +156,10 @@ error_chain! {
 			description("Parachain validation produced wrong head data."),
 			display("Parachain validation produced wrong head data (expected: {:?}, got {:?}", expected, got),
 		}
+		PreviouslyInvalid {
+			description("Parachain candidate previously failed validation."),
+			display("Parachain candidate previously failed validation."),
+		}
 	}
 
 	links {
@@ -149,11 +172,10 @@ pub fn validate_collation<P: PolkadotApi>(client: &P, relay_parent: &BlockId, co
 	use parachain::{self, ValidationParams};
 
 	let para_id = collation.receipt.parachain_index;
-	let validation_code = client.parachain_code(relay_parent, para_id)?
-		.ok_or_else(|| ErrorKind::InactiveParachain(para_id))?;
+	let inputs = client.collation_inputs(relay_parent, para_id)?;
 
-	let chain_head = client.parachain_head(relay_parent, para_id)?
-		.ok_or_else(|| ErrorKind::InactiveParachain(para_id))?;
+	let validation_code = inputs.code.ok_or_else(|| ErrorKind::InactiveParachain(para_id))?;
+	let chain_head = inputs.head.ok_or_else(|| ErrorKind::InactiveParachain(para_id))?;
 
 	let params = ValidationParams {
 		parent_head: chain_head,
@@ -174,3 +196,126 @@ pub fn validate_collation<P: PolkadotApi>(client: &P, relay_parent: &BlockId, co
 		Err(_) => Err(ErrorKind::ValidationFailure.into())
 	}
 }
+
+// the receipt doesn't commit to the block data, so outcomes are keyed by both.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct OutcomeKey {
+	candidate: Hash,
+	block_data: Hash,
+}
+
+impl OutcomeKey {
+	fn new(candidate: Hash, block_data: &BlockData) -> Self {
+		OutcomeKey {
+			candidate,
+			block_data: BlakeTwo256::hash(&block_data.0),
+		}
+	}
+}
+
+struct Outcomes {
+	max_outcomes: usize,
+	order: VecDeque<OutcomeKey>,
+	valid: HashMap<OutcomeKey, bool>,
+}
+
+/// Bounded record of parachain validation outcomes at a single relay parent.
+///
+/// This is cheaply cloneable and all clones share the same outcomes.
+#[derive(Clone)]
+pub struct ValidationCache {
+	inner: Arc<Mutex<Outcomes>>,
+}
+
+impl Default for ValidationCache {
+	fn default() -> Self {
+		ValidationCache::new(DEFAULT_CACHED_OUTCOMES)
+	}
+}
+
+impl ValidationCache {
+	/// Create a cache keeping up to `max_outcomes` outcomes, dropping the oldest first.
+	pub fn new(max_outcomes: usize) -> Self {
+		ValidationCache {
+			inner: Arc::new(Mutex::new(Outcomes {
+				max_outcomes,
+				order: VecDeque::new(),
+				valid: HashMap::new(),
+			})),
+		}
+	}
+
+	/// Get the validity of a candidate with the given block data, if known.
+	pub fn outcome(&self, candidate: Hash, block_data: &BlockData) -> Option<bool> {
+		self.inner.lock().valid.get(&OutcomeKey::new(candidate, block_data)).cloned()
+	}
+
+	/// Note the validity of a candidate with the given block data.
+	pub fn note_outcome(&self, candidate: Hash, block_data: &BlockData, valid: bool) {
+		let key = OutcomeKey::new(candidate, block_data);
+		let mut inner = self.inner.lock();
+
+		if inner.valid.insert(key, valid).is_some() { return }
+
+		inner.order.push_back(key);
+		while inner.order.len() > inner.max_outcomes {
+			if let Some(oldest) = inner.order.pop_front() {
+				inner.valid.remove(&oldest);
+			}
+		}
+	}
+
+	/// Validate a collation, unless its outcome is already known.
+	///
+	/// Failures to query the API are not cached.
+	pub fn validate<P: PolkadotApi>(&self, client: &P, relay_parent: &BlockId, collation: &Collation) -> Result<(), Error> {
+		let candidate = collation.receipt.hash();
+		match self.outcome(candidate, &collation.block_data) {
+			Some(true) => return Ok(()),
+			Some(false) => return Err(ErrorKind::PreviouslyInvalid.into()),
+			None => {}
+		}
+
+		let res = validate_collation(client, relay_parent, collation);
+		match res {
+			Ok(()) => self.note_outcome(candidate, &collation.block_data, true),
+			Err(Error(ErrorKind::PolkadotApi(_), _)) => {}
+			Err(_) => self.note_outcome(candidate, &collation.block_data, false),
+		}
+
+		res
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn outcomes_are_keyed_by_block_data() {
+		let cache = ValidationCache::default();
+		let candidate = [1; 32].into();
+
+		cache.note_outcome(candidate, &BlockData(vec![1, 2, 3]), false);
+
+		assert_eq!(cache.outcome(candidate, &BlockData(vec![1, 2, 3])), Some(false));
+		assert_eq!(cache.outcome(candidate, &BlockData(vec![4, 5, 6])), None);
+		assert_eq!(cache.outcome([2; 32].into(), &BlockData(vec![1, 2, 3])), None);
+	}
+
+	#[test]
+	fn oldest_outcomes_are_dropped() {
+		let cache = ValidationCache::new(2);
+		let block_data = BlockData(vec![1, 2, 3]);
+		let (a, b, c) = ([1; 32].into(), [2; 32].into(), [3; 32].into());
+
+		cache.note_outcome(a, &block_data, true);
+		cache.note_outcome(b, &block_data, false);
+		cache.note_outcome(a, &block_data, true);
+		cache.note_outcome(c, &block_data, true);
+
+		assert_eq!(cache.outcome(a, &block_data), None);
+		assert_eq!(cache.outcome(b, &block_data), Some(false));
+		assert_eq!(cache.outcome(c, &block_data), Some(true));
+	}
+}
@@ -16,6 +16,7 @@
 
 //! Errors that can occur during the consensus process.
 
+use polkadot_primitives::Hash;
 use primitives::AuthorityId;
 
 error_chain! {
@@ -24,6 +25,10 @@ error_chain! {
 		Bft(::bft::Error, ::bft::ErrorKind);
 	}
 
+	foreign_links {
+		Io(::std::io::Error);
+	}
+
 	errors {
 		InvalidDutyRosterLength(expected: usize, got: usize) {
 			description("Duty Roster had invalid length"),
@@ -41,6 +46,10 @@ error_chain! {
 			description("Failed to register or resolve async timer."),
 			display("Timer failed: {}", e),
 		}
+		ConflictingStatement(parent_hash: Hash) {
+			description("Refusing to sign a statement conflicting with one already issued"),
+			display("Refusing to sign a statement conflicting with one already issued on parent {:?}", parent_hash),
+		}
 		Executor(e: ::futures::future::ExecuteErrorKind) {
 			description("Unable to dispatch agreement future"),
 			display("Unable to dispatch agreement future: {:?}", e),
@@ -0,0 +1,285 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Suppression of redundant statement gossip.
+//!
+//! Statements about a popular candidate reach a node from many peers at once.
+//! The filter here remembers every statement seen in a round by what it says
+//! about which candidate digest, and who signed it, so that each is relayed at
+//! most once no matter how many peers send it or with which signature. It is
+//! independent of the statement table, and so cheap enough to run before any
+//! statement is imported.
+//!
+//! Each peer also has a budget of statements per round, which grows with the
+//! number of candidates seen. Honest peers relay each statement once, so the
+//! traffic accepted from a peer scales with the candidates rather than with the
+//! square of the number of validators.
+//!
+//! Statements are gossiped under the relay chain parent as topic, encoded by
+//! `encode_statement`.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash as StdHash;
+
+use codec::Slicable;
+use table::SignedStatement;
+use table::generic::Statement as GenericStatement;
+use polkadot_primitives::Hash;
+use polkadot_primitives::parachain::{CandidateSignature, Statement as RawStatement};
+use primitives::AuthorityId;
+use substrate_network::Misbehavior;
+use statement_log::{to_raw, from_raw};
+
+/// Default number of statements a peer may send per round before any candidate is known.
+pub const DEFAULT_BASE_BUDGET: usize = 64;
+
+/// Default number of statements a peer may send per round for every known candidate.
+pub const DEFAULT_BUDGET_PER_CANDIDATE: usize = 32;
+
+/// Configuration of the per-peer statement budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GossipConfig {
+	/// Statements a peer may send per round before any candidate is known.
+	pub base_budget: usize,
+	/// Additional statements a peer may send per round for each known candidate.
+	pub budget_per_candidate: usize,
+}
+
+impl Default for GossipConfig {
+	fn default() -> Self {
+		GossipConfig {
+			base_budget: DEFAULT_BASE_BUDGET,
+			budget_per_candidate: DEFAULT_BUDGET_PER_CANDIDATE,
+		}
+	}
+}
+
+/// What to do with a statement received from a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+	/// The statement is new: import it and relay it to other peers.
+	Relay,
+	/// The statement was seen before; drop it.
+	Duplicate,
+	/// The peer has exceeded its budget for the round; drop it.
+	OverBudget,
+}
+
+impl Verdict {
+	/// The misbehavior of the peer which sent the statement, if any.
+	pub fn misbehavior(&self) -> Option<Misbehavior> {
+		match *self {
+			Verdict::OverBudget => Some(Misbehavior::Spam),
+			Verdict::Relay | Verdict::Duplicate => None,
+		}
+	}
+}
+
+// a statement, without its signature.
+#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+enum StatementKey {
+	Candidate(Hash, AuthorityId),
+	Valid(Hash, AuthorityId),
+	Invalid(Hash, AuthorityId),
+	Available(Hash, AuthorityId),
+	Withdraw(Hash, AuthorityId),
+}
+
+impl StatementKey {
+	fn of(statement: &SignedStatement) -> Self {
+		let sender = statement.sender;
+		match statement.statement {
+			GenericStatement::Candidate(ref c) => StatementKey::Candidate(c.hash(), sender),
+			GenericStatement::Valid(h) => StatementKey::Valid(h, sender),
+			GenericStatement::Invalid(h) => StatementKey::Invalid(h, sender),
+			GenericStatement::Available(h) => StatementKey::Available(h, sender),
+			GenericStatement::Withdraw(h) => StatementKey::Withdraw(h, sender),
+		}
+	}
+}
+
+/// Encode a signed statement for gossip.
+pub fn encode_statement(statement: &SignedStatement) -> Vec<u8> {
+	(to_raw(&statement.statement), statement.signature.clone(), statement.sender).encode()
+}
+
+/// Decode a gossiped statement. The signature isn't checked.
+pub fn decode_statement(mut data: &[u8]) -> Option<SignedStatement> {
+	let (statement, signature, sender) = <(RawStatement, CandidateSignature, AuthorityId)>::decode(&mut data)?;
+	Some(SignedStatement { statement: from_raw(statement), signature, sender })
+}
+
+/// Filters the statements gossiped during a single round of candidate agreement,
+/// i.e. under a single relay chain parent. Peers are identified by `P`.
+///
+/// Signatures should be checked before statements are passed in, since any
+/// statement which is let through is remembered for the rest of the round.
+pub struct StatementGossip<P> {
+	config: GossipConfig,
+	parent_hash: Hash,
+	seen: HashSet<StatementKey>,
+	candidates: HashSet<Hash>,
+	received: HashMap<P, usize>,
+}
+
+impl<P: StdHash + Eq> StatementGossip<P> {
+	/// Create a filter for the round building on `parent_hash`.
+	pub fn new(parent_hash: Hash, config: GossipConfig) -> Self {
+		StatementGossip {
+			config,
+			parent_hash,
+			seen: HashSet::new(),
+			candidates: HashSet::new(),
+			received: HashMap::new(),
+		}
+	}
+
+	/// The relay chain parent of the round.
+	pub fn parent_hash(&self) -> &Hash {
+		&self.parent_hash
+	}
+
+	/// The number of statements each peer may send in this round at present.
+	pub fn budget(&self) -> usize {
+		self.config.base_budget + self.config.budget_per_candidate * self.candidates.len()
+	}
+
+	/// Handle a statement received from a peer. Duplicates count against the
+	/// peer's budget as well.
+	pub fn import(&mut self, peer: P, statement: &SignedStatement) -> Verdict {
+		let budget = self.budget();
+		let received = self.received.entry(peer).or_insert(0);
+		if *received >= budget {
+			return Verdict::OverBudget;
+		}
+
+		*received += 1;
+		if self.note(statement) {
+			Verdict::Relay
+		} else {
+			Verdict::Duplicate
+		}
+	}
+
+	/// Note a statement produced locally. Returns `true` if it wasn't seen
+	/// before and so should be broadcast.
+	pub fn note_local(&mut self, statement: &SignedStatement) -> bool {
+		self.note(statement)
+	}
+
+	/// Forget a peer, e.g. once it disconnects.
+	pub fn peer_disconnected(&mut self, peer: &P) {
+		self.received.remove(peer);
+	}
+
+	fn note(&mut self, statement: &SignedStatement) -> bool {
+		let key = StatementKey::of(statement);
+		if !self.seen.insert(key) {
+			return false;
+		}
+
+		if let StatementKey::Candidate(digest, _) = key {
+			self.candidates.insert(digest);
+		}
+
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_keyring::Keyring;
+	use polkadot_primitives::parachain::{CandidateReceipt, HeadData, Id as ParaId};
+
+	fn candidate(head: u8) -> CandidateReceipt {
+		CandidateReceipt {
+			parachain_index: ParaId::from(1),
+			collator: [1; 32].into(),
+			head_data: HeadData(vec![head]),
+			balance_uploads: Vec::new(),
+			commitments: Default::default(),
+		}
+	}
+
+	fn sign(statement: ::table::Statement, key: Keyring, parent_hash: &Hash) -> SignedStatement {
+		let signature = ::sign_table_statement(&statement, &key.pair(), parent_hash).unwrap();
+		SignedStatement {
+			statement,
+			signature: signature.into(),
+			sender: key.to_raw_public().into(),
+		}
+	}
+
+	#[test]
+	fn duplicates_are_suppressed_across_peers() {
+		let parent_hash = [1; 32].into();
+		let other_parent = [2; 32].into();
+		let mut gossip = StatementGossip::new(parent_hash, GossipConfig::default());
+
+		let digest = candidate(1).hash();
+		let valid = sign(GenericStatement::Valid(digest), Keyring::Alice, &parent_hash);
+
+		// same statement, different signature.
+		let resigned = sign(GenericStatement::Valid(digest), Keyring::Alice, &other_parent);
+		assert!(valid.signature != resigned.signature);
+
+		assert_eq!(gossip.import(1, &valid), Verdict::Relay);
+		assert_eq!(gossip.import(2, &valid), Verdict::Duplicate);
+		assert_eq!(gossip.import(3, &resigned), Verdict::Duplicate);
+
+		// the same vote by someone else is a different statement.
+		let other_valid = sign(GenericStatement::Valid(digest), Keyring::Bob, &parent_hash);
+		assert_eq!(gossip.import(2, &other_valid), Verdict::Relay);
+		assert!(!gossip.note_local(&other_valid));
+	}
+
+	#[test]
+	fn budget_grows_with_candidates() {
+		let parent_hash = [1; 32].into();
+		let config = GossipConfig { base_budget: 2, budget_per_candidate: 1 };
+		let mut gossip = StatementGossip::new(parent_hash, config);
+
+		let votes: Vec<_> = (0..4)
+			.map(|i| sign(GenericStatement::Valid(candidate(i).hash()), Keyring::Alice, &parent_hash))
+			.collect();
+
+		assert_eq!(gossip.import(1, &votes[0]), Verdict::Relay);
+		assert_eq!(gossip.import(1, &votes[0]), Verdict::Duplicate);
+		assert_eq!(gossip.import(1, &votes[1]), Verdict::OverBudget);
+		assert_eq!(gossip.import(1, &votes[1]).misbehavior(), Some(Misbehavior::Spam));
+
+		// other peers are unaffected.
+		assert_eq!(gossip.import(2, &votes[1]), Verdict::Relay);
+
+		// a new candidate raises everyone's budget.
+		let issued = sign(GenericStatement::Candidate(candidate(0)), Keyring::Bob, &parent_hash);
+		assert!(gossip.note_local(&issued));
+		assert_eq!(gossip.budget(), 3);
+		assert_eq!(gossip.import(1, &votes[2]), Verdict::Relay);
+		assert_eq!(gossip.import(1, &votes[3]), Verdict::OverBudget);
+
+		gossip.peer_disconnected(&1);
+		assert_eq!(gossip.import(1, &votes[3]), Verdict::Relay);
+	}
+
+	#[test]
+	fn statements_survive_encoding() {
+		let statement = sign(GenericStatement::Candidate(candidate(1)), Keyring::Alice, &[1; 32].into());
+		assert_eq!(decode_statement(&encode_statement(&statement)), Some(statement));
+		assert_eq!(decode_statement(&[1, 2, 3]), None);
+	}
+}
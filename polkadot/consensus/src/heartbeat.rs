@@ -0,0 +1,133 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Validator heartbeats.
+//!
+//! Validators periodically sign and gossip a heartbeat for the current session.
+//! Heartbeats received from the network are pooled until this node proposes a
+//! block, at which point they're noted on-chain.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+
+use bft::{self, Signer};
+use ed25519;
+use parking_lot::Mutex;
+use polkadot_primitives::{BlockNumber, Heartbeat, SessionKey};
+
+/// Sign a heartbeat for the given session.
+pub fn sign_heartbeat(key: &Signer, session_index: BlockNumber) -> Result<Heartbeat, bft::Error> {
+	Ok(Heartbeat {
+		session_index,
+		authority: key.public().into(),
+		signature: key.sign(&Heartbeat::payload(session_index))?.into(),
+	})
+}
+
+/// Check the signature on a heartbeat.
+pub fn check_heartbeat(heartbeat: &Heartbeat) -> bool {
+	let payload = Heartbeat::payload(heartbeat.session_index);
+	ed25519::verify_strong(&heartbeat.signature.0, &payload, ed25519::Public::from(heartbeat.authority))
+}
+
+/// Heartbeats waiting to be noted in a block, at most one per authority.
+#[derive(Clone, Default)]
+pub struct HeartbeatPool {
+	inner: Arc<Mutex<HashMap<SessionKey, Heartbeat>>>,
+}
+
+impl HeartbeatPool {
+	/// Import a heartbeat. Returns false if it has a bad signature or is no
+	/// newer than a heartbeat already pooled for the same authority.
+	pub fn import(&self, heartbeat: Heartbeat) -> bool {
+		if !check_heartbeat(&heartbeat) {
+			return false;
+		}
+
+		match self.inner.lock().entry(heartbeat.authority) {
+			Entry::Occupied(mut occ) => {
+				if occ.get().session_index >= heartbeat.session_index {
+					return false;
+				}
+
+				occ.insert(heartbeat);
+			}
+			Entry::Vacant(vacant) => {
+				vacant.insert(heartbeat);
+			}
+		}
+
+		true
+	}
+
+	/// Take all pooled heartbeats for the given session from the given authorities,
+	/// to be noted in a block. Heartbeats for other sessions are dropped.
+	///
+	/// Heartbeats taken for a block which doesn't make it into the chain will be
+	/// replaced by the next ones gossiped.
+	pub fn take(&self, session_index: BlockNumber, authorities: &[SessionKey]) -> Vec<Heartbeat> {
+		let mut inner = self.inner.lock();
+		let mut heartbeats: Vec<_> = inner.drain()
+			.map(|(_, heartbeat)| heartbeat)
+			.filter(|h| h.session_index == session_index && authorities.contains(&h.authority))
+			.collect();
+
+		heartbeats.sort_by_key(|h| h.authority.0);
+		heartbeats
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_keyring::Keyring;
+
+	#[test]
+	fn pool_only_keeps_valid_and_newest_heartbeats() {
+		let pool = HeartbeatPool::default();
+		let alice = Keyring::Alice.pair();
+		let bob = Keyring::Bob.pair();
+
+		let mut forged = sign_heartbeat(&bob, 1).unwrap();
+		forged.authority = alice.public().into();
+
+		assert!(!pool.import(forged));
+		assert!(pool.import(sign_heartbeat(&alice, 1).unwrap()));
+		assert!(!pool.import(sign_heartbeat(&alice, 1).unwrap()));
+		assert!(!pool.import(sign_heartbeat(&alice, 0).unwrap()));
+		assert!(pool.import(sign_heartbeat(&alice, 2).unwrap()));
+		assert!(pool.import(sign_heartbeat(&bob, 1).unwrap()));
+
+		let authorities = [alice.public().into(), bob.public().into()];
+		assert_eq!(pool.take(2, &authorities), vec![sign_heartbeat(&alice, 2).unwrap()]);
+
+		// bob's heartbeat was for an old session and has been dropped.
+		assert!(pool.take(1, &authorities).is_empty());
+	}
+
+	#[test]
+	fn take_skips_unknown_authorities() {
+		let pool = HeartbeatPool::default();
+		let alice = Keyring::Alice.pair();
+		let bob = Keyring::Bob.pair();
+
+		assert!(pool.import(sign_heartbeat(&alice, 3).unwrap()));
+		assert!(pool.import(sign_heartbeat(&bob, 3).unwrap()));
+
+		assert_eq!(pool.take(3, &[bob.public().into()]), vec![sign_heartbeat(&bob, 3).unwrap()]);
+	}
+}
@@ -0,0 +1,151 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Introspection of the ongoing candidate agreement.
+//!
+//! This is meant for diagnosing why a parachain isn't getting candidates included,
+//! and is exposed to operators over RPC.
+
+use std::sync::Arc;
+
+use polkadot_primitives::Hash;
+use polkadot_primitives::parachain::{Id as ParaId, DutyRoster};
+use primitives::AuthorityId;
+use parking_lot::Mutex;
+use table::TableSummary;
+
+use SharedTable;
+
+/// State of a single candidate in the statement table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CandidateState {
+	/// The hash of the candidate receipt.
+	pub digest: Hash,
+	/// The authority which issued the candidate.
+	pub issuer: Option<AuthorityId>,
+	/// Authorities which attested to validity, including the issuer.
+	pub validity_votes: Vec<AuthorityId>,
+	/// Authorities which attested to invalidity.
+	pub invalidity_votes: Vec<AuthorityId>,
+	/// Authorities which attested to availability.
+	pub availability_votes: Vec<AuthorityId>,
+	/// Whether the candidate currently has enough votes to be included.
+	pub includable: bool,
+}
+
+/// State of a parachain's group in the statement table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupState {
+	/// Authorities meant to check validity of candidates.
+	pub validity_guarantors: Vec<AuthorityId>,
+	/// Authorities meant to check availability of candidate data.
+	pub availability_guarantors: Vec<AuthorityId>,
+	/// Number of votes needed for validity.
+	pub needed_validity: usize,
+	/// Number of votes needed for availability.
+	pub needed_availability: usize,
+	/// All candidates submitted for the parachain so far.
+	pub candidates: Vec<CandidateState>,
+}
+
+/// Votes witnessed on a single candidate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CandidateSummary {
+	/// The hash of the candidate receipt.
+	pub digest: Hash,
+	/// The parachain the candidate is for.
+	pub parachain_index: ParaId,
+	/// Number of validity votes.
+	pub validity_votes: usize,
+	/// Number of availability votes.
+	pub availability_votes: usize,
+	/// Whether anyone attested to invalidity.
+	pub signalled_bad: bool,
+}
+
+/// Progress of the ongoing agreement, across all groups.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgreementSummary {
+	/// Number of candidates submitted for each parachain.
+	pub candidates_per_group: Vec<(ParaId, usize)>,
+	/// Votes witnessed on every candidate.
+	pub candidates: Vec<CandidateSummary>,
+	/// Number of parachains with an includable candidate.
+	pub includable_groups: usize,
+	/// Number of statements which were imported without effect.
+	pub dropped_statements: usize,
+	/// Number of distinct offenses witnessed.
+	pub misbehavior_count: usize,
+	/// Number of authorities which committed at least one offense.
+	pub misbehaving_authorities: usize,
+}
+
+impl From<TableSummary> for AgreementSummary {
+	fn from(summary: TableSummary) -> Self {
+		AgreementSummary {
+			candidates_per_group: summary.candidates_per_group.into_iter().collect(),
+			candidates: summary.candidates.into_iter()
+				.map(|c| CandidateSummary {
+					digest: c.candidate,
+					parachain_index: c.group_id,
+					validity_votes: c.validity_votes,
+					availability_votes: c.availability_votes,
+					signalled_bad: c.signalled_bad,
+				})
+				.collect(),
+			includable_groups: summary.includable_groups,
+			dropped_statements: summary.dropped_statements,
+			misbehavior_count: summary.misbehavior_count,
+			misbehaving_authorities: summary.misbehaving_authorities,
+		}
+	}
+}
+
+/// A shared handle to the ongoing candidate agreement, if any.
+///
+/// The proposer factory updates this whenever agreement starts on top of a new block.
+#[derive(Clone, Default)]
+pub struct LiveAgreement {
+	inner: Arc<Mutex<Option<(DutyRoster, Arc<SharedTable>)>>>,
+}
+
+impl LiveAgreement {
+	/// Note the start of agreement with the given duty roster and statement table.
+	pub fn set(&self, roster: DutyRoster, table: Arc<SharedTable>) {
+		*self.inner.lock() = Some((roster, table));
+	}
+
+	/// The duty roster of the ongoing agreement.
+	pub fn duty_roster(&self) -> Option<DutyRoster> {
+		self.inner.lock().as_ref().map(|&(ref roster, _)| roster.clone())
+	}
+
+	/// The state of a parachain's group in the ongoing agreement.
+	/// `None` if there is no agreement or the parachain has no group.
+	pub fn group_state(&self, para_id: ParaId) -> Option<GroupState> {
+		let table = self.inner.lock().as_ref().map(|&(_, ref table)| table.clone());
+		table.and_then(|table| table.group_state(para_id))
+	}
+
+	/// A summary of the progress of the ongoing agreement.
+	pub fn summary(&self) -> Option<AgreementSummary> {
+		self.inner.lock().as_ref().map(|&(_, ref table)| table.summary().into())
+	}
+}
@@ -47,6 +47,7 @@ extern crate substrate_runtime_primitives as runtime_primitives;
 extern crate substrate_network;
 
 extern crate exit_future;
+extern crate serde;
 extern crate tokio_core;
 extern crate substrate_client as client;
 
@@ -59,9 +60,21 @@ extern crate futures;
 #[macro_use]
 extern crate log;
 
+#[macro_use]
+extern crate serde_derive;
+
+#[macro_use]
+extern crate substrate_telemetry;
+
+#[macro_use]
+extern crate slog;	// needed until we can reexport `slog_info` from `substrate_telemetry`
+
 #[cfg(test)]
 extern crate substrate_keyring;
 
+#[cfg(test)]
+extern crate tempdir;
+
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -72,6 +85,7 @@ use runtime_support::Hashable;
 use polkadot_api::PolkadotApi;
 use polkadot_primitives::{Hash, Block, BlockId, BlockNumber, Header, Timestamp};
 use polkadot_primitives::parachain::{Id as ParaId, Chain, DutyRoster, BlockData, Extrinsic as ParachainExtrinsic, CandidateReceipt};
+use polkadot_primitives::parachain::{AgreementMisbehavior, AgreementMisbehaviorReport};
 use polkadot_runtime::BareExtrinsic;
 use primitives::AuthorityId;
 use transaction_pool::{TransactionPool};
@@ -82,17 +96,34 @@ use futures::future::{self, Shared};
 use collation::CollationFetch;
 use dynamic_inclusion::DynamicInclusion;
 
-pub use self::collation::{Collators, Collation};
+pub use self::availability_store::{AvailabilityStore, AvailabilityStats, DEFAULT_AVAILABILITY_RETENTION};
+pub use self::block_data::{BlockDataMessage, SignedBlockData};
+pub use self::collation::{Collators, Collation, ValidationCache};
 pub use self::error::{ErrorKind, Error};
+pub use self::gossip::{StatementGossip, GossipConfig, Verdict};
+pub use self::heartbeat::HeartbeatPool;
+pub use self::introspection::{LiveAgreement, GroupState, CandidateState, AgreementSummary, CandidateSummary};
+pub use self::misbehavior::{MisbehaviorQueue, SubmissionConfig};
+pub use self::remote_signer::{RemoteSigner, DEFAULT_SIGNING_BUDGET_MS};
 pub use self::shared_table::{SharedTable, StatementSource, StatementProducer, ProducedStatements};
+pub use self::statement_log::{StatementLog, SignedStatementProducer};
 pub use service::Service;
+pub use bft::Signer;
 
+mod availability_store;
+mod block_data;
 mod collation;
 mod dynamic_inclusion;
 mod evaluation;
 mod error;
+mod gossip;
+mod heartbeat;
+mod introspection;
+mod misbehavior;
+mod remote_signer;
 mod service;
 mod shared_table;
+mod statement_log;
 
 // block size limit.
 const MAX_TRANSACTIONS_SIZE: usize = 4 * 1024 * 1024;
@@ -109,7 +140,10 @@ pub trait TableRouter: Clone {
 	type FetchExtrinsic: IntoFuture<Item=ParachainExtrinsic,Error=Self::Error>;
 
 	/// Note local candidate data, making it available on the network to other validators.
-	fn local_candidate_data(&self, hash: Hash, block_data: BlockData, extrinsic: ParachainExtrinsic);
+	fn local_candidate_data(&self, candidate: &CandidateReceipt, block_data: BlockData, extrinsic: ParachainExtrinsic);
+
+	/// Broadcast a statement signed by the local validator to other validators.
+	fn local_statement(&self, statement: table::SignedStatement);
 
 	/// Fetch block data for a specific candidate.
 	fn fetch_block_data(&self, candidate: &CandidateReceipt) -> Self::FetchCandidate;
@@ -124,8 +158,13 @@ pub trait Network {
 	/// routing statements to peers, and driving completion of any `StatementProducers`.
 	type TableRouter: TableRouter;
 
-	/// Instantiate a table router using the given shared table.
-	fn table_router(&self, table: Arc<SharedTable>) -> Self::TableRouter;
+	/// Instantiate a table router using the given shared table, for agreement
+	/// on top of the relay chain block with the given hash and number.
+	fn table_router(&self, table: Arc<SharedTable>, parent_hash: Hash, parent_number: BlockNumber) -> Self::TableRouter;
+
+	/// Median offset of the clocks of peers from the local clock in
+	/// milliseconds, positive when the local clock is behind, if known.
+	fn clock_skew(&self) -> Option<i64>;
 }
 
 /// Information about a specific group.
@@ -141,10 +180,10 @@ pub struct GroupInfo {
 	pub needed_availability: usize,
 }
 
-/// Sign a table statement against a parent hash.
-/// The actual message signed is the encoded statement concatenated with the
-/// parent hash.
-pub fn sign_table_statement(statement: &table::Statement, key: &ed25519::Pair, parent_hash: &Hash) -> ed25519::Signature {
+// the message signed for a table statement: the encoded statement
+// concatenated with the parent hash. The runtime checks signatures in
+// misbehavior reports against the same payload.
+fn table_statement_payload(statement: &table::Statement, parent_hash: &Hash) -> Vec<u8> {
 	use polkadot_primitives::parachain::Statement as RawStatement;
 
 	let raw = match *statement {
@@ -152,12 +191,23 @@ pub fn sign_table_statement(statement: &table::Statement, key: &ed25519::Pair, p
 		GenericStatement::Valid(h) => RawStatement::Valid(h),
 		GenericStatement::Invalid(h) => RawStatement::Invalid(h),
 		GenericStatement::Available(h) => RawStatement::Available(h),
+		GenericStatement::Withdraw(h) => RawStatement::Withdraw(h),
 	};
 
-	let mut encoded = raw.encode();
-	encoded.extend(&parent_hash.0);
+	raw.signing_payload(parent_hash)
+}
 
-	key.sign(&encoded)
+/// Sign a table statement against a parent hash.
+/// The actual message signed is the encoded statement concatenated with the
+/// parent hash.
+pub fn sign_table_statement(statement: &table::Statement, key: &Signer, parent_hash: &Hash) -> Result<ed25519::Signature, bft::Error> {
+	key.sign(&table_statement_payload(statement, parent_hash))
+}
+
+/// Check the signature on a table statement made against a parent hash.
+pub fn check_table_statement(statement: &table::SignedStatement, parent_hash: &Hash) -> bool {
+	let payload = table_statement_payload(&statement.statement, parent_hash);
+	ed25519::verify_strong(&statement.signature.0, &payload, ed25519::Public::from(statement.sender))
 }
 
 fn make_group_info(roster: DutyRoster, authorities: &[AuthorityId], local_id: AuthorityId) -> Result<(HashMap<ParaId, GroupInfo>, LocalDuty), Error> {
@@ -233,8 +283,20 @@ pub struct ProposerFactory<C, N, P> {
 	pub collators: P,
 	/// The timer used to schedule proposal intervals.
 	pub handle: Handle,
-	/// The duration after which parachain-empty blocks will be allowed.
-	pub parachain_empty_duration: Duration,
+	/// Handle to expose the ongoing agreement through.
+	pub live_agreement: LiveAgreement,
+	/// Heartbeats to note in proposed blocks.
+	pub heartbeats: HeartbeatPool,
+	/// Record of statements signed by the local validator.
+	pub statement_log: StatementLog,
+	/// Misbehavior reports waiting to be included.
+	pub misbehavior: MisbehaviorQueue,
+	/// Reports of misbehavior during agreement on parachain candidates waiting
+	/// to be included.
+	pub agreement_misbehavior: MisbehaviorQueue<AgreementMisbehaviorReport>,
+	/// Whether to refuse proposing while the local clock is off from the
+	/// peers' by more than the proposal delay.
+	pub refuse_on_clock_skew: bool,
 }
 
 impl<C, N, P> bft::ProposerFactory<Block> for ProposerFactory<C, N, P>
@@ -246,19 +308,16 @@ impl<C, N, P> bft::ProposerFactory<Block> for ProposerFactory<C, N, P>
 	type Proposer = Proposer<C, N::TableRouter, P>;
 	type Error = Error;
 
-	fn init(&self, parent_header: &Header, authorities: &[AuthorityId], sign_with: Arc<ed25519::Pair>) -> Result<Self::Proposer, Error> {
-		use std::time::Duration;
-
-		const DELAY_UNTIL: Duration = Duration::from_millis(5000);
-
+	fn init(&self, parent_header: &Header, authorities: &[AuthorityId], sign_with: Arc<Signer>) -> Result<Self::Proposer, Error> {
 		let parent_hash = parent_header.blake2_256().into();
 
 		let id = BlockId::hash(parent_hash);
 		let duty_roster = self.client.duty_roster(&id)?;
 		let random_seed = self.client.random_seed(&id)?;
+		let timing = self.client.agreement_timing(&id)?;
 
 		let (group_info, local_duty) = make_group_info(
-			duty_roster,
+			duty_roster.clone(),
 			authorities,
 			sign_with.public().into(),
 		)?;
@@ -266,37 +325,65 @@ impl<C, N, P> bft::ProposerFactory<Block> for ProposerFactory<C, N, P>
 		let active_parachains = self.client.active_parachains(&id)?;
 
 		let n_parachains = active_parachains.len();
-		let table = Arc::new(SharedTable::new(group_info, sign_with.clone(), parent_hash));
-		let router = self.network.table_router(table.clone());
+		let signer = SignedStatementProducer::new(sign_with.clone(), parent_hash, self.statement_log.clone());
+		let validation_cache = ValidationCache::default();
+		let table = Arc::new(SharedTable::new(group_info, signer, validation_cache.clone()));
+		let router = self.network.table_router(table.clone(), parent_hash, parent_header.number);
+		self.live_agreement.set(duty_roster, table.clone());
 		let dynamic_inclusion = DynamicInclusion::new(
 			n_parachains,
 			Instant::now(),
-			self.parachain_empty_duration.clone(),
+			timing.parachain_empty_duration,
 		);
 
-		let timeout = Timeout::new(DELAY_UNTIL, &self.handle)
+		let timeout = Timeout::new(timing.proposal_delay, &self.handle)
 			.map_err(|e| timer_error(&e))?;
 
 		debug!(target: "bft", "Initialising consensus proposer. Refusing to evaluate for {:?} from now.",
-			DELAY_UNTIL);
+			timing.proposal_delay);
+
+		// a proposal timestamped with a skewed clock is likely to be rejected, so
+		// leave the round to time out and another authority to propose. a skew
+		// larger than the proposal delay moves the proposal outside its slot.
+		let max_skew = (timing.proposal_delay.as_secs() * 1000
+			+ (timing.proposal_delay.subsec_nanos() / 1_000_000) as u64) as i64;
+		let clock_skewed = match self.network.clock_skew() {
+			Some(skew) if skew.abs() > max_skew => {
+				warn!(target: "bft", "Local clock is {}ms off from peers, more than the {}ms proposal delay.{}",
+					skew, max_skew,
+					if self.refuse_on_clock_skew { " Not proposing on this block." } else { "" });
+				self.refuse_on_clock_skew
+			}
+			_ => false,
+		};
 
 		// TODO [PoC-2]: kick off collation process.
-		Ok(Proposer {
+		let proposer = Proposer {
 			client: self.client.clone(),
 			collators: self.collators.clone(),
 			delay: timeout.shared(),
 			handle: self.handle.clone(),
 			dynamic_inclusion,
+			heartbeats: self.heartbeats.clone(),
+			clock_skewed,
 			local_duty,
 			local_key: sign_with,
+			misbehavior: self.misbehavior.clone(),
+			agreement_misbehavior: self.agreement_misbehavior.clone(),
 			parent_hash,
 			parent_id: id,
 			parent_number: parent_header.number,
 			random_seed,
+			round_timeout_multiplier: timing.round_timeout_multiplier,
 			router,
 			table,
 			transaction_pool: self.transaction_pool.clone(),
-		})
+			validation_cache,
+		};
+
+		// retry any reports which haven't made it into the chain yet.
+		proposer.submit_misbehavior();
+		Ok(proposer)
 	}
 }
 
@@ -307,19 +394,25 @@ struct LocalDuty {
 /// The Polkadot proposer logic.
 pub struct Proposer<C: PolkadotApi, R, P> {
 	client: Arc<C>,
+	clock_skewed: bool,
 	collators: P,
 	delay: Shared<Timeout>,
 	dynamic_inclusion: DynamicInclusion,
 	handle: Handle,
+	heartbeats: HeartbeatPool,
 	local_duty: LocalDuty,
-	local_key: Arc<ed25519::Pair>,
+	local_key: Arc<Signer>,
+	misbehavior: MisbehaviorQueue,
+	agreement_misbehavior: MisbehaviorQueue<AgreementMisbehaviorReport>,
 	parent_hash: Hash,
 	parent_id: BlockId,
 	parent_number: BlockNumber,
 	random_seed: Hash,
+	round_timeout_multiplier: u64,
 	router: R,
 	table: Arc<SharedTable>,
 	transaction_pool: Arc<TransactionPool<C>>,
+	validation_cache: ValidationCache,
 }
 
 impl<C, R, P> bft::Proposer<Block> for Proposer<C, R, P>
@@ -373,16 +466,19 @@ impl<C, R, P> bft::Proposer<Block> for Proposer<C, R, P>
 			parent_id: self.parent_id.clone(),
 			client: self.client.clone(),
 			transaction_pool: self.transaction_pool.clone(),
+			heartbeats: self.heartbeats.clone(),
 			collation: CollationFetch::new(
 				self.local_duty.validation,
 				self.parent_id.clone(),
 				self.parent_hash.clone(),
 				self.collators.clone(),
-				self.client.clone()
+				self.client.clone(),
+				self.validation_cache.clone(),
 			),
 			table: self.table.clone(),
 			router: self.router.clone(),
 			timing,
+			clock_skewed: self.clock_skewed,
 		})
 	}
 
@@ -424,6 +520,11 @@ impl<C, R, P> bft::Proposer<Block> for Proposer<C, R, P>
 				.iter()
 				.map(|candidate| candidate.hash());
 
+			let check = self.table.check_proposal(proposal.parachain_heads());
+			if !check.is_backed() {
+				debug!(target: "bft", "Waiting on backing for proposed candidates: {:?}", check.unbacked);
+			}
+
 			// delay casting vote until we have proof that all candidates are
 			// includable.
 			let includability_tracker = self.table.track_includability(included_candidate_hashes)
@@ -493,11 +594,75 @@ impl<C, R, P> bft::Proposer<Block> for Proposer<C, R, P>
 		proposer
 	}
 
+	fn round_timeout_multiplier(&self) -> Option<u64> {
+		Some(self.round_timeout_multiplier)
+	}
+
 	fn import_misbehavior(&self, misbehavior: Vec<(AuthorityId, bft::Misbehavior<Hash>)>) {
 		use bft::generic::Misbehavior as GenericMisbehavior;
 		use runtime_primitives::bft::{MisbehaviorKind, MisbehaviorReport};
+
+		let now = Instant::now();
+		for (target, misbehavior) in misbehavior {
+			let report = MisbehaviorReport {
+				parent_hash: self.parent_hash,
+				parent_number: self.parent_number,
+				target,
+				misbehavior: match misbehavior {
+					GenericMisbehavior::ProposeOutOfTurn(_, _, _) => continue,
+					GenericMisbehavior::DoublePropose(_, _, _) => continue,
+					GenericMisbehavior::DoublePrepare(round, (h1, s1), (h2, s2))
+						=> MisbehaviorKind::BftDoublePrepare(round as u32, (h1, s1.signature), (h2, s2.signature)),
+					GenericMisbehavior::DoubleCommit(round, (h1, s1), (h2, s2))
+						=> MisbehaviorKind::BftDoubleCommit(round as u32, (h1, s1.signature), (h2, s2.signature)),
+				}
+			};
+
+			if !self.misbehavior.push(report, now) {
+				debug!(target: "consensus", "Not queueing misbehavior report of {}: already queued or queue full", target);
+			}
+		}
+
+		self.submit_misbehavior();
+	}
+}
+
+impl<C, R, P> Proposer<C, R, P>
+	where
+		C: PolkadotApi + Send + Sync,
+{
+	// submit the queued misbehavior reports which are due and whose offences
+	// aren't yet reported as of the parent block.
+	fn submit_misbehavior(&self) {
 		use runtime_primitives::MaybeUnsigned;
-		use polkadot_runtime::{Call, Extrinsic, UncheckedExtrinsic, ConsensusCall};
+		use polkadot_runtime::{Call, Extrinsic, UncheckedExtrinsic, ConsensusCall, SlashingCall};
+
+		let now = Instant::now();
+		let check_reported = |reported: ::polkadot_api::Result<bool>| match reported {
+			Ok(reported) => reported,
+			Err(e) => {
+				warn!(target: "consensus", "Unable to check whether misbehavior was reported: {}", e);
+				false
+			}
+		};
+
+		let bft_reports = self.misbehavior.due(now, |report|
+			check_reported(self.client.misbehavior_reported(&self.parent_id, report))
+		);
+		let agreement_reports = self.agreement_misbehavior.due(now, |report|
+			check_reported(self.client.agreement_misbehavior_reported(&self.parent_id, report))
+		);
+
+		let reports: Vec<_> = bft_reports.into_iter()
+			.map(|report| (report.target, Call::Consensus(ConsensusCall::report_misbehavior(report))))
+			.chain(agreement_reports.into_iter().map(|report|
+				(report.target, Call::Slashing(SlashingCall::report_agreement_misbehavior(report)))
+			))
+			.collect();
+
+		if reports.is_empty() {
+			return;
+		}
 
 		let local_id = self.local_key.public().0.into();
 		let mut next_index = {
@@ -521,29 +686,31 @@ impl<C, R, P> bft::Proposer<Block> for Proposer<C, R, P>
 			}
 		};
 
-		for (target, misbehavior) in misbehavior {
-			let report = MisbehaviorReport {
-				parent_hash: self.parent_hash,
-				parent_number: self.parent_number,
-				target,
-				misbehavior: match misbehavior {
-					GenericMisbehavior::ProposeOutOfTurn(_, _, _) => continue,
-					GenericMisbehavior::DoublePropose(_, _, _) => continue,
-					GenericMisbehavior::DoublePrepare(round, (h1, s1), (h2, s2))
-						=> MisbehaviorKind::BftDoublePrepare(round as u32, (h1, s1.signature), (h2, s2.signature)),
-					GenericMisbehavior::DoubleCommit(round, (h1, s1), (h2, s2))
-						=> MisbehaviorKind::BftDoubleCommit(round as u32, (h1, s1.signature), (h2, s2.signature)),
-				}
-			};
+		for (target, function) in reports {
+			// attribute the offence to the staking account behind the session key.
+			let offender = self.client.session_key_owner(&self.parent_id, target).ok().and_then(|o| o);
+			info!(target: "consensus", "Reporting misbehavior by {} (validator {:?})", target, offender);
+			telemetry!("bft.misbehavior_report";
+				"offender" => ?target,
+				"validator" => ?offender,
+				"parent" => ?self.parent_hash
+			);
+
 			let extrinsic = BareExtrinsic {
 				signed: local_id,
 				index: next_index,
-				function: Call::Consensus(ConsensusCall::report_misbehavior(report)),
+				function,
 			};
 
 			next_index += 1;
 
-			let signature = MaybeUnsigned(self.local_key.sign(&extrinsic.encode()).into());
+			let signature = match self.local_key.sign(&extrinsic.encode()) {
+				Ok(signature) => MaybeUnsigned(signature.into()),
+				Err(e) => {
+					warn!(target: "consensus", "Unable to sign misbehavior report: {}", e);
+					return;
+				}
+			};
 
 			let extrinsic = Extrinsic {
 				signed: extrinsic.signed.into(),
@@ -558,6 +725,44 @@ impl<C, R, P> bft::Proposer<Block> for Proposer<C, R, P>
 	}
 }
 
+impl<C: PolkadotApi, R, P> Proposer<C, R, P> {
+	// queue reports of the misbehavior witnessed by the statement table so far,
+	// to be submitted along with the other misbehavior reports.
+	fn queue_table_misbehavior(&self) {
+		use table::generic::{Misbehavior, ValidityDoubleVote};
+
+		let now = Instant::now();
+		for (target, misbehavior) in self.table.drain_misbehavior() {
+			let misbehavior = match misbehavior {
+				Misbehavior::ValidityDoubleVote(ValidityDoubleVote::IssuedAndInvalidity(issued, (_, invalidity)))
+					=> AgreementMisbehavior::IssuedAndInvalidity(issued, invalidity),
+				Misbehavior::ValidityDoubleVote(ValidityDoubleVote::ValidityAndInvalidity(digest, validity, invalidity))
+					=> AgreementMisbehavior::ValidityAndInvalidity(digest, validity, invalidity),
+				Misbehavior::MultipleCandidates(multiple)
+					=> AgreementMisbehavior::MultipleCandidates(multiple.first, multiple.second),
+				// issuing a candidate is a vote for its validity, so voting valid
+				// again is redundant rather than contradictory.
+				Misbehavior::ValidityDoubleVote(ValidityDoubleVote::IssuedAndValidity(_, _)) => continue,
+				// the runtime can't tell whether a statement was authorized.
+				Misbehavior::UnauthorizedStatement(_) => continue,
+			};
+
+			let report = AgreementMisbehaviorReport { parent_hash: self.parent_hash, target, misbehavior };
+			if !self.agreement_misbehavior.push(report, now) {
+				debug!(target: "consensus", "Not queueing misbehavior report of {}: already queued or queue full", target);
+			}
+		}
+	}
+}
+
+impl<C: PolkadotApi, R, P> Drop for Proposer<C, R, P> {
+	fn drop(&mut self) {
+		// the table is done with once agreement ends. its misbehavior is
+		// submitted by the proposer of a later block.
+		self.queue_table_misbehavior();
+	}
+}
+
 fn current_timestamp() -> Timestamp {
 	use std::time;
 
@@ -622,10 +827,12 @@ pub struct CreateProposal<C: PolkadotApi, R, P: Collators>  {
 	parent_id: BlockId,
 	client: Arc<C>,
 	transaction_pool: Arc<TransactionPool<C>>,
+	heartbeats: HeartbeatPool,
 	collation: CollationFetch<P, C>,
 	router: R,
 	table: Arc<SharedTable>,
 	timing: ProposalTiming,
+	clock_skewed: bool,
 }
 
 impl<C, R, P> CreateProposal<C, R, P>
@@ -640,7 +847,11 @@ impl<C, R, P> CreateProposal<C, R, P>
 
 		// TODO: handle case when current timestamp behind that in state.
 		let timestamp = current_timestamp();
-		let mut block_builder = self.client.build_block(&self.parent_id, timestamp, candidates)?;
+		let session_index = self.client.session_index(&self.parent_id)?;
+		let authorities = self.client.session_keys(&self.parent_id)?;
+		let heartbeats = self.heartbeats.take(session_index, &authorities);
+
+		let mut block_builder = self.client.build_block(&self.parent_id, timestamp, candidates, heartbeats)?;
 
 		{
 			let mut unqueue_invalid = Vec::new();
@@ -716,10 +927,12 @@ impl<C, R, P> Future for CreateProposal<C, R, P>
 		match self.collation.poll() {
 			Ok(Async::Ready((collation, extrinsic))) => {
 				let hash = collation.receipt.hash();
-				self.router.local_candidate_data(hash, collation.block_data, extrinsic);
+				self.router.local_candidate_data(&collation.receipt, collation.block_data, extrinsic);
 
 				// TODO: if we are an availability guarantor also, we should produce an availability statement.
-				self.table.sign_and_import(&self.router, GenericStatement::Candidate(collation.receipt));
+				if let Err(e) = self.table.sign_and_import(&self.router, GenericStatement::Candidate(collation.receipt)) {
+					warn!(target: "bft", "Not proposing local candidate {:?}: {}", hash, e);
+				}
 			}
 			Ok(Async::NotReady) => {},
 			Err(_) => {}, // TODO: handle this failure to collate.
@@ -730,7 +943,20 @@ impl<C, R, P> Future for CreateProposal<C, R, P>
 		let included = self.table.includable_count();
 		try_ready!(self.timing.poll(included));
 
+		// never resolves, so the round times out.
+		if self.clock_skewed {
+			return Ok(Async::NotReady);
+		}
+
 		// 3. propose
+		if log_enabled!(target: "bft", ::log::LogLevel::Debug) {
+			let summary = self.table.summary();
+			debug!(target: "bft", "Proposing with {} candidates in {} groups, {} includable; \
+				{} statements dropped, {} offenses by {} authorities",
+				summary.candidates.len(), summary.candidates_per_group.len(), summary.includable_groups,
+				summary.dropped_statements, summary.misbehavior_count, summary.misbehaving_authorities);
+		}
+
 		let proposed_candidates = self.table.with_proposal(|proposed_set| {
 			proposed_set.into_iter().cloned().collect()
 		});
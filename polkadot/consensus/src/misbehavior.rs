@@ -0,0 +1,287 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Submission of misbehavior reports.
+//!
+//! Misbehavior during block agreement is reported to the consensus module, and
+//! misbehavior during agreement on parachain candidates to the slashing module.
+//!
+//! Every validator observes the same misbehavior, and so would report the same
+//! offence. The runtime only accepts the first report of an offence, so reports
+//! are queued here and only submitted while the offence is not yet reported
+//! on-chain. Each offence is queued once, however many times it is observed.
+//!
+//! A submitted report may be dropped before it is included. Reports are
+//! resubmitted with exponential backoff until the offence shows up on-chain or
+//! the attempts run out.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use polkadot_primitives::MisbehaviorReport;
+use polkadot_primitives::parachain::AgreementMisbehaviorReport;
+
+/// Default maximum number of reports waiting to be included.
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// Default delay in seconds before a submitted report is submitted again.
+pub const DEFAULT_INITIAL_BACKOFF_SECS: u64 = 6;
+
+/// Default number of times a report is submitted before giving up.
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+/// Configuration of the misbehavior submission queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubmissionConfig {
+	/// Maximum number of reports waiting to be included. Further reports are
+	/// rejected until some are.
+	pub capacity: usize,
+	/// Delay before a report is first submitted again. Doubles with every attempt.
+	pub initial_backoff: Duration,
+	/// Number of times a report is submitted before it is given up on.
+	pub max_attempts: u32,
+}
+
+impl Default for SubmissionConfig {
+	fn default() -> Self {
+		SubmissionConfig {
+			capacity: DEFAULT_CAPACITY,
+			initial_backoff: Duration::from_secs(DEFAULT_INITIAL_BACKOFF_SECS),
+			max_attempts: DEFAULT_MAX_ATTEMPTS,
+		}
+	}
+}
+
+/// A report proving an offence, which the runtime accepts once per offence.
+pub trait Report: Clone {
+	/// An encoding of the offence proven by the report. All reports of the same
+	/// offence share it.
+	fn offence(&self) -> Vec<u8>;
+}
+
+impl Report for MisbehaviorReport {
+	fn offence(&self) -> Vec<u8> {
+		MisbehaviorReport::offence(self)
+	}
+}
+
+impl Report for AgreementMisbehaviorReport {
+	fn offence(&self) -> Vec<u8> {
+		AgreementMisbehaviorReport::offence(self)
+	}
+}
+
+struct Pending<R> {
+	report: R,
+	attempts: u32,
+	next_attempt: Instant,
+}
+
+struct Inner<R> {
+	config: SubmissionConfig,
+	pending: HashMap<Vec<u8>, Pending<R>>,
+	// offences no longer pending, and the order in which they were settled.
+	settled: HashSet<Vec<u8>>,
+	settled_order: VecDeque<Vec<u8>>,
+}
+
+impl<R> Inner<R> {
+	fn new(config: SubmissionConfig) -> Self {
+		Inner {
+			config,
+			pending: HashMap::new(),
+			settled: HashSet::new(),
+			settled_order: VecDeque::new(),
+		}
+	}
+
+	fn settle(&mut self, offence: Vec<u8>) {
+		// remember as many settled offences as may be pending.
+		while self.settled_order.len() >= self.config.capacity {
+			match self.settled_order.pop_front() {
+				Some(old) => { self.settled.remove(&old); }
+				None => break,
+			}
+		}
+
+		if self.settled.insert(offence.clone()) {
+			self.settled_order.push_back(offence);
+		}
+	}
+}
+
+/// Misbehavior reports waiting to be included in the chain, deduplicated by
+/// the offence they prove.
+pub struct MisbehaviorQueue<R = MisbehaviorReport> {
+	inner: Arc<Mutex<Inner<R>>>,
+}
+
+impl<R> Clone for MisbehaviorQueue<R> {
+	fn clone(&self) -> Self {
+		MisbehaviorQueue { inner: self.inner.clone() }
+	}
+}
+
+impl<R: Report> Default for MisbehaviorQueue<R> {
+	fn default() -> Self {
+		MisbehaviorQueue::new(SubmissionConfig::default())
+	}
+}
+
+impl<R: Report> MisbehaviorQueue<R> {
+	/// Create a queue with the given configuration.
+	pub fn new(config: SubmissionConfig) -> Self {
+		MisbehaviorQueue {
+			inner: Arc::new(Mutex::new(Inner::new(config))),
+		}
+	}
+
+	/// Queue a report for submission. Returns false if the offence it proves was
+	/// already queued or the queue is full.
+	pub fn push(&self, report: R, now: Instant) -> bool {
+		let mut inner = self.inner.lock();
+		let offence = report.offence();
+
+		if inner.pending.contains_key(&offence) || inner.settled.contains(&offence) {
+			return false;
+		}
+
+		if inner.pending.len() >= inner.config.capacity {
+			return false;
+		}
+
+		inner.pending.insert(offence, Pending { report, attempts: 0, next_attempt: now });
+		true
+	}
+
+	/// Take the reports which are due to be submitted at `now`, noting an
+	/// attempt for each. `is_reported` is asked about every due report first,
+	/// and offences already reported are forgotten instead.
+	pub fn due<F>(&self, now: Instant, mut is_reported: F) -> Vec<R>
+		where F: FnMut(&R) -> bool
+	{
+		let mut inner = self.inner.lock();
+		let config = inner.config;
+
+		let due: Vec<_> = inner.pending.iter()
+			.filter(|&(_, p)| p.next_attempt <= now)
+			.map(|(offence, _)| offence.clone())
+			.collect();
+
+		let mut reports = Vec::new();
+		for offence in due {
+			let done = {
+				let pending = inner.pending.get(&offence).expect("collected from pending above; qed");
+				pending.attempts >= config.max_attempts || is_reported(&pending.report)
+			};
+
+			if done {
+				inner.pending.remove(&offence);
+				inner.settle(offence);
+				continue;
+			}
+
+			let pending = inner.pending.get_mut(&offence).expect("collected from pending above; qed");
+			pending.next_attempt = now + config.initial_backoff * 2u32.pow(pending.attempts);
+			pending.attempts += 1;
+			reports.push(pending.report.clone());
+		}
+
+		reports
+	}
+
+	/// The number of reports waiting to be included.
+	pub fn len(&self) -> usize {
+		self.inner.lock().pending.len()
+	}
+
+	/// Whether no reports are waiting to be included.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use runtime_primitives::bft::MisbehaviorKind;
+
+	fn report(round: u32, signature: u8) -> MisbehaviorReport {
+		MisbehaviorReport {
+			parent_hash: [1; 32].into(),
+			parent_number: 1,
+			target: [2; 32].into(),
+			misbehavior: MisbehaviorKind::BftDoubleCommit(
+				round,
+				([3; 32].into(), [signature; 64].into()),
+				([4; 32].into(), [5; 64].into()),
+			),
+		}
+	}
+
+	#[test]
+	fn offences_are_queued_once() {
+		let config = SubmissionConfig { capacity: 2, ..Default::default() };
+		let queue = MisbehaviorQueue::new(config);
+		let now = Instant::now();
+
+		assert!(queue.push(report(1, 6), now));
+		assert!(!queue.push(report(1, 7), now));
+		assert!(queue.push(report(2, 6), now));
+		assert!(!queue.push(report(3, 6), now));
+		assert_eq!(queue.len(), 2);
+
+		// reported on-chain by someone else.
+		assert_eq!(queue.due(now, |r| r == &report(1, 6)), vec![report(2, 6)]);
+		assert_eq!(queue.len(), 1);
+
+		// settled offences aren't queued again.
+		assert!(!queue.push(report(1, 6), now));
+		assert!(queue.push(report(3, 6), now));
+	}
+
+	#[test]
+	fn resubmits_with_backoff_until_reported() {
+		let config = SubmissionConfig {
+			capacity: 4,
+			initial_backoff: Duration::from_secs(1),
+			max_attempts: 3,
+		};
+		let queue = MisbehaviorQueue::new(config);
+		let start = Instant::now();
+		let at = |secs| start + Duration::from_secs(secs);
+
+		assert!(queue.push(report(1, 6), start));
+		assert_eq!(queue.due(start, |_| false).len(), 1);
+		assert!(queue.due(start, |_| false).is_empty());
+
+		// backoff of one second, then two.
+		assert_eq!(queue.due(at(1), |_| false).len(), 1);
+		assert!(queue.due(at(2), |_| false).is_empty());
+		assert_eq!(queue.due(at(3), |_| false).len(), 1);
+
+		// out of attempts.
+		assert!(queue.due(at(100), |_| false).is_empty());
+		assert!(queue.is_empty());
+
+		assert!(queue.push(report(2, 6), start));
+		assert_eq!(queue.due(start, |_| false).len(), 1);
+		assert!(queue.due(at(1), |_| true).is_empty());
+		assert!(queue.is_empty());
+	}
+}
@@ -0,0 +1,217 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Signing with an authority key held by a separate signer process.
+//!
+//! The signer listens on a local TCP socket. Each request is a kind byte
+//! (`0` for the public key, `1` to sign), the length of the payload as a
+//! little-endian `u32` and the payload. Each response is a status byte (`0` on
+//! success), the length of the payload and the payload: the 32-byte public key,
+//! the 64-byte signature, or an error message.
+//!
+//! Signing blocks the consensus thread, so every request is bounded by a
+//! latency budget. After a failure the signer isn't contacted for a while, so
+//! that a signer which is down fails fast rather than stalling every round on
+//! its timeout.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+
+use bft::{self, Signer};
+use codec::Slicable;
+use ed25519;
+use parking_lot::Mutex;
+
+/// Default time a signing request may take, in milliseconds.
+pub const DEFAULT_SIGNING_BUDGET_MS: u64 = 200;
+
+/// Time the signer isn't contacted after a failed request, in milliseconds.
+const FAILURE_BACKOFF_MS: u64 = 2000;
+
+const REQUEST_PUBLIC: u8 = 0;
+const REQUEST_SIGN: u8 = 1;
+const MAX_RESPONSE_LEN: usize = 1024;
+
+struct Connection {
+	stream: Option<TcpStream>,
+	failed_at: Option<Instant>,
+}
+
+/// Signer delegating to a separate process.
+pub struct RemoteSigner {
+	address: SocketAddr,
+	budget: Duration,
+	public: ed25519::Public,
+	connection: Mutex<Connection>,
+}
+
+impl RemoteSigner {
+	/// Connect to the signer at `address` and fetch its public key. Every
+	/// request may take at most `budget`.
+	pub fn connect(address: SocketAddr, budget: Duration) -> Result<Self, bft::Error> {
+		let mut signer = RemoteSigner {
+			address,
+			budget,
+			public: ed25519::Public::from_raw([0; 32]),
+			connection: Mutex::new(Connection { stream: None, failed_at: None }),
+		};
+
+		let public = signer.request(REQUEST_PUBLIC, &[])?;
+		if public.len() != 32 {
+			bail!(bft::ErrorKind::Signing(format!("signer sent a public key of {} bytes", public.len())));
+		}
+		signer.public = ed25519::Public::from_slice(&public);
+		Ok(signer)
+	}
+
+	// Send a request, reconnecting once if the connection was lost and the
+	// budget allows.
+	fn request(&self, kind: u8, payload: &[u8]) -> Result<Vec<u8>, bft::Error> {
+		let deadline = Instant::now() + self.budget;
+		let mut connection = self.connection.lock();
+
+		if let Some(failed_at) = connection.failed_at {
+			if failed_at.elapsed() < Duration::from_millis(FAILURE_BACKOFF_MS) {
+				bail!(bft::ErrorKind::Signing("signer failed recently".into()));
+			}
+		}
+
+		let mut result = Err(bft::ErrorKind::Signing("no time left to contact the signer".into()).into());
+		for _ in 0..2 {
+			let now = Instant::now();
+			if now >= deadline {
+				break;
+			}
+
+			result = self.exchange(&mut connection.stream, deadline - now, kind, payload);
+			match result {
+				Ok(_) => break,
+				Err(_) => connection.stream = None,
+			}
+		}
+
+		connection.failed_at = match result {
+			Ok(_) => None,
+			Err(_) => Some(Instant::now()),
+		};
+		result
+	}
+
+	fn exchange(&self, stream: &mut Option<TcpStream>, timeout: Duration, kind: u8, payload: &[u8]) -> Result<Vec<u8>, bft::Error> {
+		let io_error = |e: ::std::io::Error| bft::Error::from(bft::ErrorKind::Signing(e.to_string()));
+
+		if stream.is_none() {
+			let connected = TcpStream::connect_timeout(&self.address, timeout).map_err(io_error)?;
+			connected.set_nodelay(true).map_err(io_error)?;
+			*stream = Some(connected);
+		}
+		let stream = stream.as_mut().expect("connected above; qed");
+		stream.set_read_timeout(Some(timeout)).map_err(io_error)?;
+		stream.set_write_timeout(Some(timeout)).map_err(io_error)?;
+
+		let mut request = Vec::with_capacity(5 + payload.len());
+		request.push(kind);
+		request.extend((payload.len() as u32).encode());
+		request.extend(payload);
+		stream.write_all(&request).map_err(io_error)?;
+
+		let mut head = [0u8; 5];
+		stream.read_exact(&mut head).map_err(io_error)?;
+		let len = u32::decode(&mut &head[1..]).expect("four bytes always decode to u32; qed") as usize;
+		if len > MAX_RESPONSE_LEN {
+			bail!(bft::ErrorKind::Signing(format!("signer sent a response of {} bytes", len)));
+		}
+
+		let mut response = vec![0u8; len];
+		stream.read_exact(&mut response).map_err(io_error)?;
+		if head[0] != 0 {
+			bail!(bft::ErrorKind::Signing(String::from_utf8_lossy(&response).into_owned()));
+		}
+		Ok(response)
+	}
+}
+
+impl Signer for RemoteSigner {
+	fn public(&self) -> ed25519::Public {
+		self.public.clone()
+	}
+
+	fn sign(&self, message: &[u8]) -> Result<ed25519::Signature, bft::Error> {
+		let signature = self.request(REQUEST_SIGN, message)?;
+		if signature.len() != 64 {
+			bail!(bft::ErrorKind::Signing(format!("signer sent a signature of {} bytes", signature.len())));
+		}
+
+		let signature = ed25519::Signature::from_slice(&signature);
+		if !ed25519::verify_strong(&signature, message, &self.public) {
+			bail!(bft::ErrorKind::Signing("signer sent an invalid signature".into()));
+		}
+		Ok(signature)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::net::TcpListener;
+	use std::thread;
+	use substrate_keyring::Keyring;
+
+	// serve `requests` requests of one connection with the given key.
+	fn serve(pair: ed25519::Pair, requests: usize) -> SocketAddr {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let address = listener.local_addr().unwrap();
+
+		thread::spawn(move || {
+			let (mut stream, _) = listener.accept().unwrap();
+			for _ in 0..requests {
+				let mut head = [0u8; 5];
+				stream.read_exact(&mut head).unwrap();
+				let mut payload = vec![0u8; u32::decode(&mut &head[1..]).unwrap() as usize];
+				stream.read_exact(&mut payload).unwrap();
+
+				let response = match head[0] {
+					REQUEST_PUBLIC => pair.public().to_raw_vec(),
+					_ => pair.sign(&payload).to_vec(),
+				};
+				stream.write_all(&[0]).unwrap();
+				stream.write_all(&(response.len() as u32).encode()).unwrap();
+				stream.write_all(&response).unwrap();
+			}
+		});
+
+		address
+	}
+
+	#[test]
+	fn signs_with_the_remote_key() {
+		let address = serve(Keyring::Alice.pair(), 2);
+		let signer = RemoteSigner::connect(address, Duration::from_secs(5)).unwrap();
+		assert_eq!(signer.public(), Keyring::Alice.pair().public());
+
+		let signature = signer.sign(b"hello").unwrap();
+		assert!(ed25519::verify_strong(&signature, b"hello", Keyring::Alice.pair().public()));
+
+		// the signer went away: fail within the budget, then fail fast.
+		let budget = Duration::from_millis(500);
+		let signer = RemoteSigner { budget, ..signer };
+		let started = Instant::now();
+		assert!(signer.sign(b"hello").is_err());
+		assert!(started.elapsed() < 2 * budget + Duration::from_millis(500));
+		assert!(signer.sign(b"hello").is_err());
+	}
+}
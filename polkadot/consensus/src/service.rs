@@ -19,17 +19,20 @@
 /// Consensus service. A long runnung service that manages BFT agreement and parachain
 /// candidate agreement over the network.
 
+use std::collections::{HashMap, HashSet};
 use std::thread;
 use std::time::{Duration, Instant};
 use std::sync::Arc;
 
-use bft::{self, BftService};
+use bft::{self, BftService, Signer};
 use client::{BlockchainEvents, ChainHead};
 use ed25519;
 use futures::prelude::*;
 use futures::{future, Canceled};
-use polkadot_api::LocalPolkadotApi;
-use polkadot_primitives::{BlockId, Block, Header, Hash, AccountId};
+use futures::sync::oneshot;
+use parking_lot::Mutex;
+use polkadot_api::{LocalPolkadotApi, PolkadotApi};
+use polkadot_primitives::{BlockId, Block, BlockNumber, Header, Hash, AccountId, Heartbeat};
 use polkadot_primitives::parachain::{Id as ParaId, BlockData, Extrinsic, CandidateReceipt};
 use primitives::AuthorityId;
 use runtime_support::Hashable;
@@ -37,11 +40,15 @@ use substrate_network as net;
 use tokio_core::reactor;
 use transaction_pool::TransactionPool;
 
-use super::{TableRouter, SharedTable, ProposerFactory};
+use block_data::{self, BlockDataMessage, SignedBlockData};
+use codec::Slicable;
+use heartbeat::sign_heartbeat;
+use super::{TableRouter, SharedTable, StatementSource, ProposerFactory, LiveAgreement, HeartbeatPool, StatementLog, MisbehaviorQueue, AvailabilityStore};
 use error;
 
 const TIMER_DELAY_MS: u64 = 5000;
 const TIMER_INTERVAL_MS: u64 = 500;
+const HEARTBEAT_INTERVAL_MS: u64 = 30000;
 
 struct BftSink<E> {
 	network: Arc<net::ConsensusService<Block>>,
@@ -174,14 +181,85 @@ impl<E> Sink for BftSink<E> {
 	}
 }
 
-struct Network(Arc<net::ConsensusService<Block>>);
+struct Network {
+	network: Arc<net::ConsensusService<Block>>,
+	availability_store: AvailabilityStore,
+	handle: reactor::Handle,
+}
 
 impl super::Network for Network {
 	type TableRouter = Router;
-	fn table_router(&self, _table: Arc<SharedTable>) -> Self::TableRouter {
-		Router {
-			network: self.0.clone()
+	fn table_router(&self, table: Arc<SharedTable>, parent_hash: Hash, parent_number: BlockNumber) -> Self::TableRouter {
+		let router = Router {
+			network: self.network.clone(),
+			availability_store: self.availability_store.clone(),
+			table: table.clone(),
+			parent_hash,
+			parent_number,
+			fetches: Arc::new(Mutex::new(HashMap::new())),
+		};
+
+		// exchange block data with the groups the local validator is assigned
+		// to, for as long as statements are gossiped under the parent.
+		let (expired_tx, expired_rx) = oneshot::channel::<()>();
+		let expired = expired_rx.shared();
+		let local_id = table.local_id();
+		for (para_id, group) in table.group_info() {
+			if !group.validity_guarantors.contains(&local_id) && !group.availability_guarantors.contains(&local_id) {
+				continue;
+			}
+
+			let para_id = *para_id;
+			let block_data_router = router.clone();
+			let mut answered = HashSet::new();
+			let exchange = self.network.gossip_messages(block_data::group_topic(&parent_hash, para_id))
+				.for_each(move |data| {
+					block_data_router.on_block_data_message(para_id, &data, &mut answered);
+					Ok(())
+				})
+				.select(expired.clone().map(|_| ()).map_err(|_| ()))
+				.then(|_| Ok(()));
+			self.handle.spawn(exchange);
 		}
+
+		// import the statements gossiped under the parent until the topic expires.
+		let handle = self.handle.clone();
+		let import_router = router.clone();
+		let import = self.network.gossip_messages(parent_hash).for_each(move |data| {
+			let statement = match ::gossip::decode_statement(&data) {
+				Some(statement) => statement,
+				None => {
+					debug!("Ignoring undecodable statement gossiped on {}", parent_hash);
+					return Ok(());
+				}
+			};
+			if let Err(m) = table.check_remote_statement(&statement) {
+				debug!("Ignoring gossiped statement from {:?}: {}", statement.sender, m);
+				return Ok(());
+			}
+
+			let sender = statement.sender;
+			let producer = table.import_statement(&import_router, statement, StatementSource::Remote(Some(sender)), |_| true);
+			let (table, router) = (table.clone(), import_router.clone());
+			handle.spawn(producer.map(move |produced| {
+				for statement in produced.validity.into_iter().chain(produced.availability) {
+					if let Err(e) = table.sign_and_import(&router, statement) {
+						warn!("Unable to sign statement: {:?}", e);
+					}
+				}
+			}).map_err(|_| ()));
+			Ok(())
+		}).then(move |_| {
+			let _ = expired_tx.send(());
+			Ok(())
+		});
+		self.handle.spawn(import);
+
+		router
+	}
+
+	fn clock_skew(&self) -> Option<i64> {
+		self.network.clock_skew()
 	}
 }
 
@@ -227,6 +305,7 @@ fn start_bft<F, C>(
 pub struct Service {
 	thread: Option<thread::JoinHandle<()>>,
 	exit_signal: Option<::exit_future::Signal>,
+	live_agreement: LiveAgreement,
 }
 
 impl Service {
@@ -236,27 +315,40 @@ impl Service {
 		api: Arc<A>,
 		network: Arc<net::ConsensusService<Block>>,
 		transaction_pool: Arc<TransactionPool<A>>,
-		parachain_empty_duration: Duration,
-		key: ed25519::Pair,
+		key: Arc<Signer>,
+		statement_log: StatementLog,
+		availability_store: AvailabilityStore,
+		refuse_on_clock_skew: bool,
 	) -> Service
 		where
 			A: LocalPolkadotApi + Send + Sync + 'static,
 			C: BlockchainEvents<Block> + ChainHead<Block> + bft::BlockImport<Block> + bft::Authorities<Block> + Send + Sync + 'static,
 	{
 		let (signal, exit) = ::exit_future::signal();
+		let live_agreement = LiveAgreement::default();
+		let factory_live_agreement = live_agreement.clone();
 		let thread = thread::spawn(move || {
 			let mut core = reactor::Core::new().expect("tokio::Core could not be created");
-			let key = Arc::new(key);
+			let heartbeats = HeartbeatPool::default();
 
 			let factory = ProposerFactory {
 				client: api.clone(),
 				transaction_pool: transaction_pool.clone(),
-				network: Network(network.clone()),
+				network: Network {
+					network: network.clone(),
+					availability_store: availability_store.clone(),
+					handle: core.handle(),
+				},
 				collators: NoCollators,
-				parachain_empty_duration,
 				handle: core.handle(),
+				live_agreement: factory_live_agreement,
+				heartbeats: heartbeats.clone(),
+				statement_log,
+				misbehavior: MisbehaviorQueue::default(),
+				agreement_misbehavior: MisbehaviorQueue::default(),
+				refuse_on_clock_skew,
 			};
-			let bft_service = Arc::new(BftService::new(client.clone(), key, factory));
+			let bft_service = Arc::new(BftService::new(client.clone(), key.clone(), factory));
 
 			let notifications = {
 				let handle = core.handle();
@@ -268,6 +360,19 @@ impl Service {
 					if notification.is_new_best {
 						start_bft(&notification.header, handle.clone(), &*client, network.clone(), &*bft_service);
 					}
+
+					// imported blocks are final, so candidate data can be pruned behind them.
+					let stored = availability_store.stats();
+					match availability_store.note_finalized(notification.header.number) {
+						Ok(stats) => if stats != stored {
+							debug!("Pruned availability data of {} candidates", stored.candidates - stats.candidates);
+							telemetry!("availability.prune";
+								"candidates" => stats.candidates,
+								"stored_bytes" => stats.stored_bytes
+							);
+						},
+						Err(e) => warn!("Error pruning availability data: {:?}", e),
+					}
 					Ok(())
 				})
 			};
@@ -304,8 +409,59 @@ impl Service {
 				})
 			};
 
+			let incoming_heartbeats = {
+				let heartbeats = heartbeats.clone();
+				network.heartbeats().for_each(move |encoded| {
+					match Heartbeat::decode(&mut &encoded[..]) {
+						Some(heartbeat) => { heartbeats.import(heartbeat); }
+						None => debug!("Received undecodable heartbeat"),
+					}
+					Ok(())
+				})
+			};
+
+			let heartbeat_interval = reactor::Interval::new(
+				Duration::from_millis(HEARTBEAT_INTERVAL_MS),
+				&core.handle(),
+			).expect("it is always possible to create an interval with valid params");
+
+			let local_heartbeats = {
+				let c = client.clone();
+				let n = network.clone();
+
+				heartbeat_interval.map_err(|e| debug!("Timer error: {:?}", e)).for_each(move |_| {
+					let session_index = c.best_block_header()
+						.map_err(|e| e.to_string())
+						.and_then(|header| api.session_index(&BlockId::hash(header.blake2_256().into()))
+							.map_err(|e| e.to_string()));
+
+					match session_index.and_then(|index| sign_heartbeat(&*key, index).map_err(|e| e.to_string())) {
+						Ok(heartbeat) => {
+							n.send_heartbeat(heartbeat.encode());
+							heartbeats.import(heartbeat);
+						}
+						Err(e) => debug!("Unable to produce heartbeat: {}", e),
+					}
+					Ok(())
+				})
+			};
+
+			let round_changes = bft_service.round_change_stream().for_each(|change| {
+				info!(target: "bft", "Agreement on top of {} stalled, moving to round {} with a timeout of {}s",
+					change.parent_hash, change.round, change.timeout.as_secs());
+				telemetry!("bft.round_change";
+					"parent" => ?change.parent_hash,
+					"round" => change.round,
+					"timeout" => change.timeout.as_secs()
+				);
+				Ok(())
+			});
+
 			core.handle().spawn(notifications);
 			core.handle().spawn(timed);
+			core.handle().spawn(round_changes);
+			core.handle().spawn(incoming_heartbeats);
+			core.handle().spawn(local_heartbeats);
 			if let Err(e) = core.run(exit) {
 				debug!("BFT event loop error {:?}", e);
 			}
@@ -313,8 +469,14 @@ impl Service {
 		Service {
 			thread: Some(thread),
 			exit_signal: Some(signal),
+			live_agreement,
 		}
 	}
+
+	/// Get a handle to the ongoing agreement, for introspection.
+	pub fn live_agreement(&self) -> LiveAgreement {
+		self.live_agreement.clone()
+	}
 }
 
 impl Drop for Service {
@@ -348,19 +510,99 @@ impl ::collation::Collators for NoCollators {
 #[derive(Clone)]
 struct Router {
 	network: Arc<net::ConsensusService<Block>>,
+	availability_store: AvailabilityStore,
+	table: Arc<SharedTable>,
+	parent_hash: Hash,
+	parent_number: BlockNumber,
+	// block data awaited from the network, by candidate digest.
+	fetches: Arc<Mutex<HashMap<Hash, Vec<oneshot::Sender<BlockData>>>>>,
+}
+
+impl Router {
+	fn is_validity_guarantor(&self, para_id: ParaId, authority: &AuthorityId) -> bool {
+		self.table.group_info().get(&para_id).map_or(false, |g| g.validity_guarantors.contains(authority))
+	}
+
+	// sign the block data of a candidate and send it to the group.
+	fn send_block_data(&self, para_id: ParaId, candidate: Hash, block_data: BlockData) {
+		match self.table.sign_block_data(candidate, block_data) {
+			Ok(data) => self.network.send_gossip(
+				block_data::group_topic(&self.parent_hash, para_id),
+				BlockDataMessage::Data(data).encode(),
+			),
+			Err(e) => warn!("Unable to sign block data of candidate {:?}: {:?}", candidate, e),
+		}
+	}
+
+	// handle a message gossiped on the topic of the group of `para_id`.
+	// `answered` holds the candidates whose data was sent on request already.
+	fn on_block_data_message(&self, para_id: ParaId, data: &[u8], answered: &mut HashSet<Hash>) {
+		match BlockDataMessage::decode(data) {
+			Some(BlockDataMessage::Request(candidate)) => {
+				if !self.is_validity_guarantor(para_id, &self.table.local_id()) || answered.contains(&candidate) {
+					return;
+				}
+				if let Some(block_data) = self.availability_store.block_data(&candidate) {
+					answered.insert(candidate);
+					self.send_block_data(para_id, candidate, block_data);
+				}
+			}
+			Some(BlockDataMessage::Data(data)) => self.import_block_data(para_id, data),
+			None => debug!("Ignoring undecodable block data message for parachain {:?}", para_id),
+		}
+	}
+
+	fn import_block_data(&self, para_id: ParaId, data: SignedBlockData) {
+		if !self.is_validity_guarantor(para_id, &data.sender) || !block_data::check_block_data(&data, &self.parent_hash) {
+			debug!("Ignoring block data of candidate {:?} from {:?}", data.candidate, data.sender);
+			return;
+		}
+
+		// noted under the lock so that no fetch starts waiting in between.
+		let mut fetches = self.fetches.lock();
+		if let Err(e) = self.availability_store.note(self.parent_number, data.candidate, data.block_data.clone()) {
+			warn!("Unable to store block data of candidate {:?}: {:?}", data.candidate, e);
+		}
+		for waiting in fetches.remove(&data.candidate).into_iter().flat_map(|w| w) {
+			let _ = waiting.send(data.block_data.clone());
+		}
+	}
 }
 
 impl TableRouter for Router {
 	type Error = Canceled;
-	type FetchCandidate =  future::Empty<BlockData, Self::Error>;
+	type FetchCandidate = future::Either<future::FutureResult<BlockData, Self::Error>, oneshot::Receiver<BlockData>>;
 	type FetchExtrinsic = future::FutureResult<Extrinsic, Self::Error>;
 
-	fn local_candidate_data(&self, _hash: Hash, _block_data: BlockData, _extrinsic: Extrinsic) {
-		// TODO
+	fn local_candidate_data(&self, candidate: &CandidateReceipt, block_data: BlockData, _extrinsic: Extrinsic) {
+		let hash = candidate.hash();
+		if let Err(e) = self.availability_store.note(self.parent_number, hash, block_data.clone()) {
+			warn!("Unable to store block data of candidate {:?}: {:?}", hash, e);
+		}
+		self.send_block_data(candidate.parachain_index, hash, block_data);
 	}
 
-	fn fetch_block_data(&self, _candidate: &CandidateReceipt) -> Self::FetchCandidate {
-		future::empty()
+	fn local_statement(&self, statement: ::table::SignedStatement) {
+		self.network.send_gossip(self.parent_hash, ::gossip::encode_statement(&statement));
+	}
+
+	fn fetch_block_data(&self, candidate: &CandidateReceipt) -> Self::FetchCandidate {
+		let hash = candidate.hash();
+		let mut fetches = self.fetches.lock();
+		if let Some(block_data) = self.availability_store.block_data(&hash) {
+			return future::Either::A(future::ok(block_data));
+		}
+
+		let (tx, rx) = oneshot::channel();
+		let waiting = fetches.entry(hash).or_insert_with(Vec::new);
+		if waiting.is_empty() {
+			self.network.send_gossip(
+				block_data::group_topic(&self.parent_hash, candidate.parachain_index),
+				BlockDataMessage::Request(hash).encode(),
+			);
+		}
+		waiting.push(tx);
+		future::Either::B(rx)
 	}
 
 	fn fetch_extrinsic_data(&self, _candidate: &CandidateReceipt) -> Self::FetchExtrinsic {
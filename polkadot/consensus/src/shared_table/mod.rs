@@ -22,15 +22,19 @@ use std::sync::Arc;
 
 use table::{self, Table, Context as TableContextTrait};
 use table::generic::Statement as GenericStatement;
-use collation::Collation;
+use collation::{Collation, ValidationCache};
 use polkadot_primitives::Hash;
 use polkadot_primitives::parachain::{Id as ParaId, BlockData, Extrinsic, CandidateReceipt};
 use primitives::AuthorityId;
+use substrate_network::Misbehavior;
 
 use parking_lot::Mutex;
 use futures::{future, prelude::*};
 
-use super::{GroupInfo, TableRouter};
+use super::{GroupInfo, TableRouter, Error};
+use block_data::SignedBlockData;
+use introspection::{GroupState, CandidateState};
+use statement_log::SignedStatementProducer;
 use self::includable::IncludabilitySender;
 
 mod includable;
@@ -38,8 +42,7 @@ mod includable;
 pub use self::includable::Includable;
 
 struct TableContext {
-	parent_hash: Hash,
-	key: Arc<::ed25519::Pair>,
+	signer: SignedStatementProducer,
 	groups: HashMap<ParaId, GroupInfo>,
 }
 
@@ -52,27 +55,32 @@ impl table::Context for TableContext {
 		self.groups.get(group).map_or(false, |g| g.availability_guarantors.contains(authority))
 	}
 
+	fn group_authorities(&self, group: &ParaId) -> (Vec<AuthorityId>, Vec<AuthorityId>) {
+		self.groups.get(group).map_or((Vec::new(), Vec::new()), |g| (
+			g.validity_guarantors.iter().cloned().collect(),
+			g.availability_guarantors.iter().cloned().collect(),
+		))
+	}
+
 	fn requisite_votes(&self, group: &ParaId) -> (usize, usize) {
 		self.groups.get(group).map_or(
 			(usize::max_value(), usize::max_value()),
 			|g| (g.needed_validity, g.needed_availability),
 		)
 	}
+
+	fn verify_statement(&self, statement: &table::SignedStatement) -> bool {
+		::check_table_statement(statement, self.signer.parent_hash())
+	}
 }
 
 impl TableContext {
 	fn local_id(&self) -> AuthorityId {
-		self.key.public().into()
+		self.signer.local_id()
 	}
 
-	fn sign_statement(&self, statement: table::Statement) -> table::SignedStatement {
-		let signature = ::sign_table_statement(&statement, &self.key, &self.parent_hash).into();
-
-		table::SignedStatement {
-			statement,
-			signature,
-			sender: self.local_id(),
-		}
+	fn sign_statement(&self, statement: table::Statement) -> Result<table::SignedStatement, Error> {
+		self.signer.produce(statement)
 	}
 }
 
@@ -91,6 +99,7 @@ struct SharedTableInner {
 	checked_validity: HashSet<Hash>,
 	checked_availability: HashSet<Hash>,
 	trackers: Vec<IncludabilitySender>,
+	validation_cache: ValidationCache,
 }
 
 impl SharedTableInner {
@@ -160,6 +169,7 @@ impl SharedTableInner {
 						fetch_extrinsic,
 						evaluate: checking_validity,
 						check_candidate,
+						validation_cache: self.validation_cache.clone(),
 					})
 				}
 			}
@@ -209,7 +219,8 @@ struct Work<D: Future, E: Future, C> {
 	fetch_block_data: future::Fuse<D>,
 	fetch_extrinsic: Option<future::Fuse<E>>,
 	evaluate: bool,
-	check_candidate: C
+	check_candidate: C,
+	validation_cache: ValidationCache,
 }
 
 impl<D: Future, E: Future, C> Default for StatementProducer<D, E, C> {
@@ -239,12 +250,22 @@ impl<D, E, C, Err> Future for StatementProducer<D, E, C>
 		if let Async::Ready(block_data) = work.fetch_block_data.poll()? {
 			self.produced_statements.block_data = Some(block_data.clone());
 			if work.evaluate {
-				let is_good = (work.check_candidate)(Collation {
-					block_data,
-					receipt: work.candidate_receipt.clone(),
-				});
-
 				let hash = work.candidate_receipt.hash();
+
+				// the candidate may have been validated already, e.g. when fetching it locally.
+				let is_good = match work.validation_cache.outcome(hash, &block_data) {
+					Some(is_good) => is_good,
+					None => {
+						let is_good = (work.check_candidate)(Collation {
+							block_data: block_data.clone(),
+							receipt: work.candidate_receipt.clone(),
+						});
+
+						work.validation_cache.note_outcome(hash, &block_data, is_good);
+						is_good
+					}
+				};
+
 				self.produced_statements.validity = Some(if is_good {
 					GenericStatement::Valid(hash)
 				} else {
@@ -298,17 +319,23 @@ impl Clone for SharedTable {
 impl SharedTable {
 	/// Create a new shared table.
 	///
-	/// Provide the key to sign with, and the parent hash of the relay chain
-	/// block being built.
-	pub fn new(groups: HashMap<ParaId, GroupInfo>, key: Arc<::ed25519::Pair>, parent_hash: Hash) -> Self {
+	/// Provide the producer to sign local statements with, which is bound to
+	/// the parent hash of the relay chain block being built, and the cache of
+	/// validation outcomes at that block.
+	pub fn new(
+		groups: HashMap<ParaId, GroupInfo>,
+		signer: SignedStatementProducer,
+		validation_cache: ValidationCache,
+	) -> Self {
 		SharedTable {
-			context: Arc::new(TableContext { groups, key, parent_hash }),
+			context: Arc::new(TableContext { groups, signer }),
 			inner: Arc::new(Mutex::new(SharedTableInner {
 				table: Table::default(),
 				proposed_digest: None,
 				checked_validity: HashSet::new(),
 				checked_availability: HashSet::new(),
 				trackers: Vec::new(),
+				validation_cache,
 			}))
 		}
 	}
@@ -318,6 +345,41 @@ impl SharedTable {
 		&self.context.groups
 	}
 
+	/// The authority ID of the local validator.
+	pub fn local_id(&self) -> AuthorityId {
+		self.context.local_id()
+	}
+
+	/// Sign the block data of a candidate for distribution to its group.
+	pub fn sign_block_data(&self, candidate: Hash, block_data: BlockData) -> Result<SignedBlockData, Error> {
+		self.context.signer.sign_block_data(candidate, block_data)
+	}
+
+	/// Get the membership and all statements about candidates of a group.
+	pub fn group_state(&self, para_id: ParaId) -> Option<GroupState> {
+		let group = self.context.groups.get(&para_id)?;
+		let inner = self.inner.lock();
+
+		let candidates = inner.table.group_candidates(&para_id)
+			.map(|(digest, data)| CandidateState {
+				digest: digest.clone(),
+				issuer: data.issuer().cloned(),
+				validity_votes: data.validity_voters().cloned().collect(),
+				invalidity_votes: data.invalidity_voters().to_vec(),
+				availability_votes: data.availability_voters().cloned().collect(),
+				includable: inner.table.candidate_includable(digest, &*self.context),
+			})
+			.collect();
+
+		Some(GroupState {
+			validity_guarantors: group.validity_guarantors.iter().cloned().collect(),
+			availability_guarantors: group.availability_guarantors.iter().cloned().collect(),
+			needed_validity: group.needed_validity,
+			needed_availability: group.needed_availability,
+			candidates,
+		})
+	}
+
 	/// Import a single statement. Provide a handle to a table router
 	/// for dispatching any other requests which come up.
 	pub fn import_statement<R: TableRouter, C: FnMut(Collation) -> bool>(
@@ -330,18 +392,33 @@ impl SharedTable {
 		self.inner.lock().import_statement(&*self.context, router, statement, received_from, check_candidate)
 	}
 
+	/// Check a statement received from a peer before importing or relaying it.
+	/// Statements with a bad signature are the peer's misbehavior, as honest
+	/// peers check signatures before relaying.
+	pub fn check_remote_statement(&self, statement: &table::SignedStatement) -> Result<(), Misbehavior> {
+		if self.context.verify_statement(statement) {
+			Ok(())
+		} else {
+			Err(Misbehavior::InvalidSignature)
+		}
+	}
+
 	/// Sign and import a local statement.
+	///
+	/// Fails without importing if the statement conflicts with one the
+	/// local validator has already signed.
 	pub fn sign_and_import<R: TableRouter>(
 		&self,
 		router: &R,
 		statement: table::Statement,
-	) {
+	) -> Result<(), Error> {
 		let proposed_digest = match statement {
 			GenericStatement::Candidate(ref c) => Some(c.hash()),
 			_ => None,
 		};
 
-		let signed_statement = self.context.sign_statement(statement);
+		let signed_statement = self.context.sign_statement(statement)?;
+		router.local_statement(signed_statement.clone());
 
 		let mut inner = self.inner.lock();
 		if proposed_digest.is_some() {
@@ -357,6 +434,7 @@ impl SharedTable {
 		);
 
 		assert!(producer.work.is_none(), "local statement import never leads to additional work; qed");
+		Ok(())
 	}
 
 	/// Import many statements at once.
@@ -400,16 +478,38 @@ impl SharedTable {
 		f(inner.table.proposed_candidates(&*self.context))
 	}
 
+	/// Check the candidates of a proposal against the votes known locally,
+	/// reporting any which lack backing.
+	pub fn check_proposal(&self, candidates: &[CandidateReceipt]) -> table::ProposalCheck {
+		self.inner.lock().table.check_proposal(&*self.context, candidates)
+	}
+
 	/// Get the number of parachains which have available candidates.
 	pub fn includable_count(&self) -> usize {
 		self.inner.lock().table.includable_count()
 	}
 
-	/// Get all witnessed misbehavior.
-	pub fn get_misbehavior(&self) -> HashMap<AuthorityId, table::Misbehavior> {
+	/// Get the authorities which have yet to vote on a candidate, so statements
+	/// can be requested from them directly.
+	pub fn missing_voters(&self, digest: &Hash) -> Option<table::MissingVoters> {
+		self.inner.lock().table.missing_voters(digest, &*self.context)
+	}
+
+	/// Get a snapshot of the candidates, votes, and misbehavior in the table.
+	pub fn summary(&self) -> table::TableSummary {
+		self.inner.lock().table.summary()
+	}
+
+	/// Get all witnessed misbehavior, grouped by offender.
+	pub fn get_misbehavior(&self) -> HashMap<AuthorityId, Vec<table::Misbehavior>> {
 		self.inner.lock().table.get_misbehavior().clone()
 	}
 
+	/// Drain all witnessed misbehavior, yielding one entry per distinct offense.
+	pub fn drain_misbehavior(&self) -> Vec<(AuthorityId, table::Misbehavior)> {
+		self.inner.lock().table.drain_misbehavior()
+	}
+
 	/// Fill a statement batch.
 	pub fn fill_batch<B: table::StatementBatch>(&self, batch: &mut B) {
 		self.inner.lock().table.fill_batch(batch);
@@ -437,7 +537,9 @@ impl SharedTable {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use statement_log::StatementLog;
 	use substrate_keyring::Keyring;
+	use polkadot_primitives::parachain::CandidateCommitments;
 
 	#[derive(Clone)]
 	struct DummyRouter;
@@ -447,7 +549,12 @@ mod tests {
 		type FetchExtrinsic = ::futures::future::Empty<Extrinsic,()>;
 
 		/// Note local candidate data, making it available on the network to other validators.
-		fn local_candidate_data(&self, _hash: Hash, _block_data: BlockData, _extrinsic: Extrinsic) {
+		fn local_candidate_data(&self, _candidate: &CandidateReceipt, _block_data: BlockData, _extrinsic: Extrinsic) {
+
+		}
+
+		/// Broadcast a local statement.
+		fn local_statement(&self, _statement: table::SignedStatement) {
 
 		}
 
@@ -481,26 +588,34 @@ mod tests {
 			needed_availability: 0,
 		});
 
-		let shared_table = SharedTable::new(groups, local_key.clone(), parent_hash);
+		let signer = SignedStatementProducer::new(local_key.clone(), parent_hash, StatementLog::in_memory());
+		let shared_table = SharedTable::new(groups, signer, ValidationCache::default());
 
 		let candidate = CandidateReceipt {
 			parachain_index: para_id,
 			collator: [1; 32].into(),
 			head_data: ::polkadot_primitives::parachain::HeadData(vec![1, 2, 3, 4]),
 			balance_uploads: Vec::new(),
-			egress_queue_roots: Vec::new(),
-			fees: 1_000_000,
+			commitments: CandidateCommitments {
+				fees: 1_000_000,
+				..Default::default()
+			},
 		};
 
 		let candidate_statement = GenericStatement::Candidate(candidate);
 
-		let signature = ::sign_table_statement(&candidate_statement, &validity_other_key, &parent_hash);
+		let signature = ::sign_table_statement(&candidate_statement, &validity_other_key, &parent_hash).unwrap();
 		let signed_statement = ::table::generic::SignedStatement {
 			statement: candidate_statement,
 			signature: signature.into(),
 			sender: validity_other,
 		};
 
+		let mut forged = signed_statement.clone();
+		forged.sender = local_id;
+		assert_eq!(shared_table.check_remote_statement(&forged), Err(Misbehavior::InvalidSignature));
+		assert_eq!(shared_table.check_remote_statement(&signed_statement), Ok(()));
+
 		let producer = shared_table.import_statement(
 			&DummyRouter,
 			signed_statement,
@@ -510,6 +625,16 @@ mod tests {
 
 		assert!(producer.work.is_some(), "candidate and local validity group are same");
 		assert!(producer.work.as_ref().unwrap().evaluate, "should evaluate validity");
+
+		let state = shared_table.group_state(para_id).unwrap();
+		assert_eq!(state.needed_validity, 2);
+		assert_eq!(state.candidates.len(), 1);
+		assert_eq!(state.candidates[0].issuer, Some(validity_other));
+		assert!(!state.candidates[0].includable);
+		assert!(shared_table.group_state(ParaId::from(2)).is_none());
+
+		let missing = shared_table.missing_voters(&state.candidates[0].digest).unwrap();
+		assert_eq!(missing.validity, vec![local_id]);
 	}
 
 	#[test]
@@ -531,20 +656,23 @@ mod tests {
 			needed_availability: 1,
 		});
 
-		let shared_table = SharedTable::new(groups, local_key.clone(), parent_hash);
+		let signer = SignedStatementProducer::new(local_key.clone(), parent_hash, StatementLog::in_memory());
+		let shared_table = SharedTable::new(groups, signer, ValidationCache::default());
 
 		let candidate = CandidateReceipt {
 			parachain_index: para_id,
 			collator: [1; 32].into(),
 			head_data: ::polkadot_primitives::parachain::HeadData(vec![1, 2, 3, 4]),
 			balance_uploads: Vec::new(),
-			egress_queue_roots: Vec::new(),
-			fees: 1_000_000,
+			commitments: CandidateCommitments {
+				fees: 1_000_000,
+				..Default::default()
+			},
 		};
 
 		let candidate_statement = GenericStatement::Candidate(candidate);
 
-		let signature = ::sign_table_statement(&candidate_statement, &validity_other_key, &parent_hash);
+		let signature = ::sign_table_statement(&candidate_statement, &validity_other_key, &parent_hash).unwrap();
 		let signed_statement = ::table::generic::SignedStatement {
 			statement: candidate_statement,
 			signature: signature.into(),
@@ -562,4 +690,124 @@ mod tests {
 		assert!(producer.work.as_ref().unwrap().fetch_extrinsic.is_some(), "should fetch extrinsic when guaranteeing availability");
 		assert!(!producer.work.as_ref().unwrap().evaluate, "should not evaluate validity");
 	}
+
+	#[test]
+	fn sign_and_import_refuses_conflicting_candidate() {
+		let mut groups = HashMap::new();
+
+		let para_id = ParaId::from(1);
+		let local_id = Keyring::Alice.to_raw_public().into();
+		let local_key = Arc::new(Keyring::Alice.pair());
+		let parent_hash = Default::default();
+
+		groups.insert(para_id, GroupInfo {
+			validity_guarantors: [local_id].iter().cloned().collect(),
+			availability_guarantors: Default::default(),
+			needed_validity: 1,
+			needed_availability: 0,
+		});
+
+		let signer = SignedStatementProducer::new(local_key, parent_hash, StatementLog::in_memory());
+		let shared_table = SharedTable::new(groups, signer, ValidationCache::default());
+
+		let candidate = |head: u8| CandidateReceipt {
+			parachain_index: para_id,
+			collator: [1; 32].into(),
+			head_data: ::polkadot_primitives::parachain::HeadData(vec![head]),
+			balance_uploads: Vec::new(),
+			commitments: CandidateCommitments {
+				fees: 1_000_000,
+				..Default::default()
+			},
+		};
+
+		assert!(shared_table.sign_and_import(&DummyRouter, GenericStatement::Candidate(candidate(1))).is_ok());
+		assert!(shared_table.sign_and_import(&DummyRouter, GenericStatement::Candidate(candidate(2))).is_err());
+
+		assert_eq!(shared_table.inner.lock().proposed_digest, Some(candidate(1).hash()));
+	}
+
+	#[test]
+	fn statements_signed_against_other_parent_are_dropped() {
+		let mut groups = HashMap::new();
+
+		let para_id = ParaId::from(1);
+		let local_id = Keyring::Alice.to_raw_public().into();
+		let local_key = Arc::new(Keyring::Alice.pair());
+
+		let validity_other = Keyring::Bob.to_raw_public().into();
+		let validity_other_key = Keyring::Bob.pair();
+		let parent_hash = Default::default();
+
+		groups.insert(para_id, GroupInfo {
+			validity_guarantors: [local_id, validity_other].iter().cloned().collect(),
+			availability_guarantors: Default::default(),
+			needed_validity: 2,
+			needed_availability: 0,
+		});
+
+		let signer = SignedStatementProducer::new(local_key, parent_hash, StatementLog::in_memory());
+		let shared_table = SharedTable::new(groups, signer, ValidationCache::default());
+
+		let candidate_statement = GenericStatement::Candidate(CandidateReceipt {
+			parachain_index: para_id,
+			collator: [1; 32].into(),
+			head_data: ::polkadot_primitives::parachain::HeadData(vec![1, 2, 3, 4]),
+			balance_uploads: Vec::new(),
+			commitments: Default::default(),
+		});
+
+		let signature = ::sign_table_statement(&candidate_statement, &validity_other_key, &[1; 32].into()).unwrap();
+		let signed_statement = ::table::generic::SignedStatement {
+			statement: candidate_statement,
+			signature: signature.into(),
+			sender: validity_other,
+		};
+
+		let producer = shared_table.import_statement(
+			&DummyRouter,
+			signed_statement,
+			StatementSource::Remote(None),
+			|_| true,
+		);
+
+		assert!(producer.work.is_none());
+		assert!(shared_table.group_state(para_id).unwrap().candidates.is_empty());
+	}
+
+	#[test]
+	fn evaluation_uses_cached_outcomes() {
+		let candidate = CandidateReceipt {
+			parachain_index: ParaId::from(1),
+			collator: [1; 32].into(),
+			head_data: ::polkadot_primitives::parachain::HeadData(vec![1, 2, 3, 4]),
+			balance_uploads: Vec::new(),
+			commitments: CandidateCommitments {
+				fees: 1_000_000,
+				..Default::default()
+			},
+		};
+
+		let block_data = BlockData(vec![5, 6, 7, 8]);
+		let validation_cache = ValidationCache::default();
+
+		let producer = |check_candidate: fn(Collation) -> bool| StatementProducer {
+			produced_statements: Default::default(),
+			work: Some(Work {
+				candidate_receipt: candidate.clone(),
+				fetch_block_data: future::ok::<_, ()>(block_data.clone()).fuse(),
+				fetch_extrinsic: None::<future::Fuse<future::FutureResult<Extrinsic, ()>>>,
+				evaluate: true,
+				check_candidate,
+				validation_cache: validation_cache.clone(),
+			}),
+		};
+
+		let produced = producer(|_| false).wait().unwrap();
+		assert_eq!(produced.validity, Some(GenericStatement::Invalid(candidate.hash())));
+		assert_eq!(validation_cache.outcome(candidate.hash(), &block_data), Some(false));
+
+		let produced = producer(|_| panic!("outcome is already known")).wait().unwrap();
+		assert_eq!(produced.validity, Some(GenericStatement::Invalid(candidate.hash())));
+	}
 }
@@ -0,0 +1,400 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Record of the statements signed by the local validator.
+//!
+//! Signing two conflicting statements under the same parent is slashable.
+//! Every statement is written to disk before it is signed, so a node which
+//! crashed, or a second node accidentally started with the same key and data
+//! directory, will refuse to contradict what was already issued.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use codec::Slicable;
+use table;
+use table::generic::Statement as GenericStatement;
+use polkadot_primitives::Hash;
+use polkadot_primitives::parachain::{BlockData, Statement as RawStatement};
+use primitives::AuthorityId;
+use bft::Signer;
+use parking_lot::Mutex;
+
+use block_data::SignedBlockData;
+use error::{Error, ErrorKind};
+
+/// Number of relay chain parents to keep issued statements for.
+const MAX_RECORDED_PARENTS: usize = 64;
+
+pub(crate) fn to_raw(statement: &table::Statement) -> RawStatement {
+	match *statement {
+		GenericStatement::Candidate(ref c) => RawStatement::Candidate(c.clone()),
+		GenericStatement::Valid(h) => RawStatement::Valid(h),
+		GenericStatement::Invalid(h) => RawStatement::Invalid(h),
+		GenericStatement::Available(h) => RawStatement::Available(h),
+		GenericStatement::Withdraw(h) => RawStatement::Withdraw(h),
+	}
+}
+
+pub(crate) fn from_raw(statement: RawStatement) -> table::Statement {
+	match statement {
+		RawStatement::Candidate(c) => GenericStatement::Candidate(c),
+		RawStatement::Valid(h) => GenericStatement::Valid(h),
+		RawStatement::Invalid(h) => GenericStatement::Invalid(h),
+		RawStatement::Available(h) => GenericStatement::Available(h),
+		RawStatement::Withdraw(h) => GenericStatement::Withdraw(h),
+	}
+}
+
+// Statements issued under a single parent.
+#[derive(Default)]
+struct Issued {
+	candidate: Option<Hash>,
+	valid: HashSet<Hash>,
+	invalid: HashSet<Hash>,
+	statements: Vec<table::Statement>,
+}
+
+impl Issued {
+	fn conflicts_with(&self, statement: &table::Statement) -> bool {
+		match *statement {
+			GenericStatement::Candidate(ref c) => {
+				let digest = c.hash();
+				self.candidate.map_or(false, |d| d != digest) || self.invalid.contains(&digest)
+			}
+			GenericStatement::Valid(ref digest) => self.invalid.contains(digest),
+			GenericStatement::Invalid(ref digest) =>
+				self.valid.contains(digest) || self.candidate.as_ref() == Some(digest),
+			GenericStatement::Available(_) | GenericStatement::Withdraw(_) => false,
+		}
+	}
+
+	fn note(&mut self, statement: table::Statement) {
+		if self.statements.contains(&statement) {
+			return;
+		}
+
+		match statement {
+			GenericStatement::Candidate(ref c) => { self.candidate = Some(c.hash()); }
+			GenericStatement::Valid(digest) => { self.valid.insert(digest); }
+			GenericStatement::Invalid(digest) => { self.invalid.insert(digest); }
+			GenericStatement::Available(_) => {}
+			// a withdrawn candidate may be superseded by another.
+			GenericStatement::Withdraw(digest) => if self.candidate == Some(digest) {
+				self.candidate = None;
+			},
+		}
+
+		self.statements.push(statement);
+	}
+}
+
+struct LogInner {
+	path: Option<PathBuf>,
+	file: Option<File>,
+	parents: VecDeque<Hash>,
+	issued: HashMap<Hash, Issued>,
+}
+
+impl LogInner {
+	fn note(&mut self, parent_hash: Hash, statement: table::Statement) {
+		if !self.issued.contains_key(&parent_hash) {
+			self.parents.push_back(parent_hash);
+		}
+
+		self.issued.entry(parent_hash).or_insert_with(Default::default).note(statement);
+	}
+
+	// drop the oldest parents if there are too many, returning whether
+	// anything was removed.
+	fn prune(&mut self) -> bool {
+		let mut pruned = false;
+		while self.parents.len() > MAX_RECORDED_PARENTS {
+			if let Some(parent) = self.parents.pop_front() {
+				self.issued.remove(&parent);
+				pruned = true;
+			}
+		}
+
+		pruned
+	}
+
+	fn encoded_records(&self) -> Vec<u8> {
+		let mut encoded = Vec::new();
+		for parent in &self.parents {
+			for statement in self.issued.get(parent).into_iter().flat_map(|i| &i.statements) {
+				(parent.clone(), to_raw(statement)).using_encoded(|s| encoded.extend(s));
+			}
+		}
+
+		encoded
+	}
+
+	// rewrite the file with only the retained records.
+	fn rewrite(&mut self) -> Result<(), Error> {
+		let path = match self.path {
+			Some(ref path) => path.clone(),
+			None => return Ok(()),
+		};
+
+		let mut tmp_path = path.clone();
+		tmp_path.set_extension("tmp");
+
+		{
+			let mut tmp = File::create(&tmp_path)?;
+			tmp.write_all(&self.encoded_records())?;
+			tmp.sync_all()?;
+		}
+
+		fs::rename(&tmp_path, &path)?;
+		self.file = Some(OpenOptions::new().append(true).open(&path)?);
+		Ok(())
+	}
+}
+
+/// A persistent record of every statement signed by the local validator.
+///
+/// Cheaply cloneable; all clones share the same record.
+#[derive(Clone)]
+pub struct StatementLog {
+	inner: Arc<Mutex<LogInner>>,
+}
+
+impl StatementLog {
+	/// Create a log which is kept in memory only.
+	pub fn in_memory() -> Self {
+		StatementLog {
+			inner: Arc::new(Mutex::new(LogInner {
+				path: None,
+				file: None,
+				parents: VecDeque::new(),
+				issued: HashMap::new(),
+			})),
+		}
+	}
+
+	/// Open the log at the given path, loading any previously recorded statements.
+	///
+	/// A truncated trailing record, as left by a crash mid-write, is discarded.
+	pub fn open(path: PathBuf) -> Result<Self, Error> {
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		let mut inner = LogInner {
+			path: Some(path.clone()),
+			file: None,
+			parents: VecDeque::new(),
+			issued: HashMap::new(),
+		};
+
+		if path.exists() {
+			let mut contents = Vec::new();
+			File::open(&path)?.read_to_end(&mut contents)?;
+
+			let mut input = &contents[..];
+			while !input.is_empty() {
+				match <(Hash, RawStatement)>::decode(&mut input) {
+					Some((parent, statement)) => inner.note(parent, from_raw(statement)),
+					None => {
+						warn!(target: "consensus", "Discarding {} undecodable bytes at the end of the statement log",
+							input.len());
+						break;
+					}
+				}
+			}
+		}
+
+		inner.prune();
+		inner.rewrite()?;
+
+		Ok(StatementLog { inner: Arc::new(Mutex::new(inner)) })
+	}
+
+	/// Record a statement about to be signed under the given parent.
+	///
+	/// Fails if it conflicts with a statement already issued under that parent.
+	/// Recording an already-issued statement again is allowed.
+	pub fn note_issued(&self, parent_hash: &Hash, statement: &table::Statement) -> Result<(), Error> {
+		let mut inner = self.inner.lock();
+
+		let is_new = {
+			let issued = inner.issued.get(parent_hash);
+			if issued.map_or(false, |i| i.conflicts_with(statement)) {
+				bail!(ErrorKind::ConflictingStatement(parent_hash.clone()));
+			}
+
+			issued.map_or(true, |i| !i.statements.contains(statement))
+		};
+
+		if !is_new {
+			return Ok(());
+		}
+
+		inner.note(parent_hash.clone(), statement.clone());
+		if inner.prune() {
+			inner.rewrite()?;
+		} else if let Some(ref mut file) = inner.file {
+			(parent_hash.clone(), to_raw(statement)).using_encoded(|s| file.write_all(s))?;
+			file.sync_data()?;
+		}
+
+		Ok(())
+	}
+
+	/// Get all statements issued under the given parent.
+	pub fn issued(&self, parent_hash: &Hash) -> Vec<table::Statement> {
+		self.inner.lock().issued.get(parent_hash).map_or_else(Vec::new, |i| i.statements.clone())
+	}
+}
+
+/// Signs statements on behalf of the local validator, refusing any which
+/// would conflict with one signed earlier under the same parent.
+pub struct SignedStatementProducer {
+	key: Arc<Signer>,
+	parent_hash: Hash,
+	log: StatementLog,
+}
+
+impl SignedStatementProducer {
+	/// Create a new producer signing with the given key for statements
+	/// made while building on top of `parent_hash`.
+	pub fn new(key: Arc<Signer>, parent_hash: Hash, log: StatementLog) -> Self {
+		SignedStatementProducer { key, parent_hash, log }
+	}
+
+	/// The authority ID of the signing key.
+	pub fn local_id(&self) -> AuthorityId {
+		self.key.public().into()
+	}
+
+	/// The parent hash statements are signed under.
+	pub fn parent_hash(&self) -> &Hash {
+		&self.parent_hash
+	}
+
+	/// Record and sign a statement.
+	pub fn produce(&self, statement: table::Statement) -> Result<table::SignedStatement, Error> {
+		self.log.note_issued(&self.parent_hash, &statement)?;
+		let signature = ::sign_table_statement(&statement, &*self.key, &self.parent_hash)?.into();
+
+		Ok(table::SignedStatement {
+			statement,
+			signature,
+			sender: self.local_id(),
+		})
+	}
+
+	/// Sign the block data of a candidate for distribution to its group.
+	/// Block data isn't recorded: sending it twice contradicts nothing.
+	pub fn sign_block_data(&self, candidate: Hash, block_data: BlockData) -> Result<SignedBlockData, Error> {
+		Ok(::block_data::sign_block_data(&*self.key, &self.parent_hash, candidate, block_data)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_keyring::Keyring;
+	use polkadot_primitives::parachain::{CandidateCommitments, CandidateReceipt, HeadData, Id as ParaId};
+	use tempdir::TempDir;
+
+	fn candidate(head: u8) -> CandidateReceipt {
+		CandidateReceipt {
+			parachain_index: ParaId::from(1),
+			collator: [1; 32].into(),
+			head_data: HeadData(vec![head]),
+			balance_uploads: Vec::new(),
+			commitments: CandidateCommitments {
+				fees: 1_000_000,
+				..Default::default()
+			},
+		}
+	}
+
+	#[test]
+	fn refuses_conflicting_statements() {
+		let parent_hash = [0xff; 32].into();
+		let producer = SignedStatementProducer::new(
+			Arc::new(Keyring::Alice.pair()),
+			parent_hash,
+			StatementLog::in_memory(),
+		);
+
+		let first = candidate(1);
+		let digest = first.hash();
+
+		assert!(producer.produce(GenericStatement::Candidate(first.clone())).is_ok());
+		assert!(producer.produce(GenericStatement::Candidate(first)).is_ok());
+		assert!(producer.produce(GenericStatement::Candidate(candidate(2))).is_err());
+		assert!(producer.produce(GenericStatement::Invalid(digest)).is_err());
+
+		let other = candidate(3).hash();
+		assert!(producer.produce(GenericStatement::Valid(other)).is_ok());
+		assert!(producer.produce(GenericStatement::Invalid(other)).is_err());
+		assert!(producer.produce(GenericStatement::Available(other)).is_ok());
+
+		// statements under another parent are independent.
+		let producer = SignedStatementProducer::new(
+			Arc::new(Keyring::Alice.pair()),
+			[0xee; 32].into(),
+			producer.log.clone(),
+		);
+		assert!(producer.produce(GenericStatement::Invalid(other)).is_ok());
+	}
+
+	#[test]
+	fn withdrawn_candidate_can_be_superseded() {
+		let producer = SignedStatementProducer::new(
+			Arc::new(Keyring::Alice.pair()),
+			[0xff; 32].into(),
+			StatementLog::in_memory(),
+		);
+
+		let digest = candidate(1).hash();
+		assert!(producer.produce(GenericStatement::Candidate(candidate(1))).is_ok());
+		assert!(producer.produce(GenericStatement::Withdraw(digest)).is_ok());
+		assert!(producer.produce(GenericStatement::Candidate(candidate(2))).is_ok());
+	}
+
+	#[test]
+	fn issued_statements_survive_restart() {
+		let temp_dir = TempDir::new("statement-log").unwrap();
+		let path = temp_dir.path().join("statements");
+		let parent_hash = [0xff; 32].into();
+		let digest = candidate(1).hash();
+
+		{
+			let log = StatementLog::open(path.clone()).unwrap();
+			let producer = SignedStatementProducer::new(Arc::new(Keyring::Alice.pair()), parent_hash, log);
+			assert!(producer.produce(GenericStatement::Candidate(candidate(1))).is_ok());
+			assert!(producer.produce(GenericStatement::Valid(digest)).is_ok());
+		}
+
+		let log = StatementLog::open(path).unwrap();
+		assert_eq!(log.issued(&parent_hash), vec![
+			GenericStatement::Candidate(candidate(1)),
+			GenericStatement::Valid(digest),
+		]);
+
+		let producer = SignedStatementProducer::new(Arc::new(Keyring::Alice.pair()), parent_hash, log);
+		assert!(producer.produce(GenericStatement::Candidate(candidate(2))).is_err());
+		assert!(producer.produce(GenericStatement::Invalid(digest)).is_err());
+	}
+}
@@ -0,0 +1,116 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Blocking JSON-RPC over HTTP.
+//!
+//! Just enough HTTP/1.1 to talk to the RPC server of a node: one request per
+//! connection, with the response body delimited by the connection closing.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use serde_json::{self, Value};
+
+use {ErrorKind, Result, Transport, response_result};
+
+/// Default time to wait for a response.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Makes requests to the HTTP RPC server of a node.
+pub struct HttpTransport {
+	addr: SocketAddr,
+	timeout: Duration,
+	next_id: AtomicUsize,
+}
+
+impl HttpTransport {
+	/// Create a transport for the RPC server at `addr`.
+	pub fn new(addr: SocketAddr) -> Self {
+		HttpTransport {
+			addr,
+			timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+			next_id: AtomicUsize::new(1),
+		}
+	}
+
+	/// Set the time to wait for a response.
+	pub fn with_timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = timeout;
+		self
+	}
+}
+
+impl Transport for HttpTransport {
+	fn request(&self, method: &str, params: Vec<Value>) -> Result<Value> {
+		let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+		let body = json!({
+			"jsonrpc": "2.0",
+			"id": id,
+			"method": method,
+			"params": params,
+		}).to_string();
+
+		let mut stream = TcpStream::connect(&self.addr)?;
+		stream.set_read_timeout(Some(self.timeout))?;
+		stream.set_write_timeout(Some(self.timeout))?;
+
+		write!(
+			stream,
+			"POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+			self.addr, body.len(), body
+		)?;
+		stream.flush()?;
+
+		let mut response = Vec::new();
+		stream.read_to_end(&mut response)?;
+
+		trace!(target: "follower", "Response to {}: {}", method, String::from_utf8_lossy(&response));
+		response_result(parse_response(&response)?)
+	}
+}
+
+// split an HTTP response into status and body, and parse the body.
+fn parse_response(response: &[u8]) -> Result<Value> {
+	let header_end = match response.windows(4).position(|w| w == b"\r\n\r\n") {
+		Some(pos) => pos,
+		None => bail!(ErrorKind::BadResponse("incomplete HTTP response".into())),
+	};
+
+	let head = String::from_utf8_lossy(&response[..header_end]);
+	let status = head.lines().next().unwrap_or("");
+	if status.split_whitespace().nth(1) != Some("200") {
+		bail!(ErrorKind::BadResponse(format!("HTTP status {}", status)));
+	}
+
+	Ok(serde_json::from_slice(&response[header_end + 4..])?)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_responses() {
+		let ok = b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\r\n{\"jsonrpc\":\"2.0\",\"result\":5,\"id\":1}";
+		assert_eq!(parse_response(ok).unwrap()["result"], json!(5));
+
+		let not_found = b"HTTP/1.1 404 Not Found\r\n\r\n";
+		assert!(parse_response(not_found).is_err());
+		assert!(parse_response(b"HTTP/1.1 200 OK\r\n").is_err());
+	}
+}
@@ -0,0 +1,429 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A light-weight follower of the Polkadot relay chain.
+//!
+//! Connects to the RPC of a node, follows its finalized head and reports the
+//! balance transfers in every new block, without linking any of the node.
+//! Blocks are final as soon as they are imported, so the head of the node is
+//! finalized.
+//!
+//! The follower starts from a trusted checkpoint header. Every block it yields
+//! is checked to be a descendant of the checkpoint, to have the hash it was
+//! requested by, and to have a body matching the extrinsics root of its header,
+//! so a transfer reported for a block is included in it. The justifications
+//! of the blocks are not checked, so the node itself is trusted to have
+//! imported only final blocks.
+
+extern crate polkadot_primitives as primitives;
+extern crate polkadot_runtime as runtime;
+extern crate serde;
+extern crate substrate_codec as codec;
+extern crate substrate_runtime_primitives as runtime_primitives;
+
+#[macro_use]
+extern crate serde_json;
+
+#[macro_use]
+extern crate error_chain;
+
+#[macro_use]
+extern crate log;
+
+mod http;
+
+use codec::Slicable;
+use primitives::{Balance, BlockNumber, Hash, Header, UncheckedExtrinsic};
+use runtime::{Address, Call, StakingCall, UncheckedExtrinsic as RuntimeExtrinsic};
+use runtime_primitives::generic;
+use runtime_primitives::traits::{BlakeTwo256, Hashing};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+pub use http::HttpTransport;
+
+/// A block of the relay chain, as returned by `chain_getBlock`.
+pub type SignedBlock = generic::SignedBlock<Header, UncheckedExtrinsic, Hash>;
+
+/// Default number of blocks the head may be ahead of the follower.
+pub const DEFAULT_MAX_GAP: BlockNumber = 1024;
+
+error_chain! {
+	foreign_links {
+		Io(::std::io::Error) #[doc = "Error communicating with the node."];
+		Json(::serde_json::Error) #[doc = "Malformed JSON."];
+	}
+
+	errors {
+		/// The node responded with an error.
+		Rpc(code: i64, message: String) {
+			description("RPC error"),
+			display("RPC error {}: {}", code, message),
+		}
+		/// The node responded to the request, but not with JSON-RPC.
+		BadResponse(msg: String) {
+			description("Bad response"),
+			display("Bad response: {}", msg),
+		}
+		/// The node doesn't know a block.
+		UnknownBlock(hash: Hash) {
+			description("Unknown block"),
+			display("Unknown block {:?}", hash),
+		}
+		/// A block returned by the node doesn't check out.
+		InvalidBlock(hash: Hash, reason: &'static str) {
+			description("Invalid block"),
+			display("Invalid block {:?}: {}", hash, reason),
+		}
+		/// The chain of the node doesn't descend from the last followed block.
+		Diverged(hash: Hash) {
+			description("Chain diverged from the followed blocks"),
+			display("Block {:?} doesn't descend from the followed blocks", hash),
+		}
+		/// The head is too far ahead of the last followed block.
+		TooFarBehind(gap: BlockNumber) {
+			description("Too far behind the head"),
+			display("Head is {} blocks ahead of the followed blocks", gap),
+		}
+	}
+}
+
+/// A way of making JSON-RPC requests to a node.
+pub trait Transport {
+	/// Make a request, returning the `result` of the response.
+	fn request(&self, method: &str, params: Vec<Value>) -> Result<Value>;
+}
+
+/// Get the result of a JSON-RPC response.
+pub fn response_result(mut response: Value) -> Result<Value> {
+	if let Some(error) = response.get("error") {
+		let code = error.get("code").and_then(Value::as_i64).unwrap_or(0);
+		let message = error.get("message").and_then(Value::as_str).unwrap_or("").to_owned();
+		bail!(ErrorKind::Rpc(code, message));
+	}
+
+	match response.get_mut("result") {
+		Some(result) => Ok(result.take()),
+		None => bail!(ErrorKind::BadResponse("no result".into())),
+	}
+}
+
+/// A balance transfer made in a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transfer {
+	/// Position of the extrinsic in the block.
+	pub index: u32,
+	/// The sender.
+	pub from: Address,
+	/// The recipient.
+	pub to: Address,
+	/// The amount transferred.
+	pub value: Balance,
+}
+
+/// A block followed.
+#[derive(Debug, Clone)]
+pub struct FollowedBlock {
+	/// The hash of the block.
+	pub hash: Hash,
+	/// The block.
+	pub block: SignedBlock,
+	/// The balance transfers in the block.
+	pub transfers: Vec<Transfer>,
+}
+
+/// Whether `extrinsics` are the body of the block with `header`, i.e. match
+/// its extrinsics root.
+pub fn verify_body(header: &Header, extrinsics: &[UncheckedExtrinsic]) -> bool {
+	let root = BlakeTwo256::ordered_trie_root(extrinsics.iter().map(Slicable::encode));
+	root == header.extrinsics_root
+}
+
+/// Decode a transfer from an extrinsic at position `index` in a block.
+pub fn decode_transfer(index: u32, extrinsic: &UncheckedExtrinsic) -> Option<Transfer> {
+	let encoded = extrinsic.encode();
+	let extrinsic = RuntimeExtrinsic::decode(&mut &encoded[..])?;
+	if !extrinsic.is_signed() {
+		return None;
+	}
+
+	match extrinsic.extrinsic.function {
+		Call::Staking(StakingCall::transfer(to, value)) => Some(Transfer {
+			index,
+			from: extrinsic.extrinsic.signed,
+			to,
+			value,
+		}),
+		_ => None,
+	}
+}
+
+/// Follows the relay chain of a node from a trusted checkpoint.
+pub struct Follower<T> {
+	transport: T,
+	last_hash: Hash,
+	last_number: BlockNumber,
+	max_gap: BlockNumber,
+}
+
+impl<T: Transport> Follower<T> {
+	/// Follow the blocks after the trusted `checkpoint`.
+	pub fn new(transport: T, checkpoint: &Header) -> Self {
+		Follower {
+			transport,
+			last_hash: BlakeTwo256::hash_of(checkpoint),
+			last_number: checkpoint.number,
+			max_gap: DEFAULT_MAX_GAP,
+		}
+	}
+
+	/// Set the number of blocks the head may be ahead of the last followed
+	/// block before `poll` gives up.
+	pub fn with_max_gap(mut self, max_gap: BlockNumber) -> Self {
+		self.max_gap = max_gap;
+		self
+	}
+
+	/// The hash and number of the last block followed.
+	pub fn last(&self) -> (Hash, BlockNumber) {
+		(self.last_hash, self.last_number)
+	}
+
+	/// The transport used.
+	pub fn transport(&self) -> &T {
+		&self.transport
+	}
+
+	/// Fetch the blocks imported by the node since the last call, in order.
+	pub fn poll(&mut self) -> Result<Vec<FollowedBlock>> {
+		let head: Hash = self.call("chain_getHead", vec![])?;
+		if head == self.last_hash {
+			return Ok(Vec::new());
+		}
+
+		let mut followed = Vec::new();
+		let mut hash = head;
+		loop {
+			let block = self.block(hash)?;
+			let number = block.block.header.number;
+			let parent_hash = block.block.header.parent_hash;
+
+			if number <= self.last_number {
+				bail!(ErrorKind::Diverged(hash));
+			}
+
+			if followed.is_empty() && number - self.last_number > self.max_gap {
+				bail!(ErrorKind::TooFarBehind(number - self.last_number));
+			}
+
+			let transfers = block.block.extrinsics.iter()
+				.enumerate()
+				.filter_map(|(i, xt)| decode_transfer(i as u32, xt))
+				.collect();
+
+			followed.push(FollowedBlock { hash, block, transfers });
+
+			if number == self.last_number + 1 {
+				if parent_hash != self.last_hash {
+					bail!(ErrorKind::Diverged(hash));
+				}
+				break;
+			}
+
+			hash = parent_hash;
+		}
+
+		followed.reverse();
+		self.last_hash = head;
+		self.last_number += followed.len() as BlockNumber;
+
+		debug!(target: "follower", "Followed {} blocks to #{} ({:?})", followed.len(), self.last_number, head);
+		Ok(followed)
+	}
+
+	// fetch a block and check it against the hash it was requested by.
+	fn block(&self, hash: Hash) -> Result<SignedBlock> {
+		let block: Option<SignedBlock> = self.call("chain_getBlock", vec![json!(hash)])?;
+		let block = block.ok_or_else(|| ErrorKind::UnknownBlock(hash))?;
+
+		if BlakeTwo256::hash_of(&block.block.header) != hash {
+			bail!(ErrorKind::InvalidBlock(hash, "header hash mismatch"));
+		}
+
+		if !verify_body(&block.block.header, &block.block.extrinsics) {
+			bail!(ErrorKind::InvalidBlock(hash, "extrinsics root mismatch"));
+		}
+
+		if block.justification.hash != hash {
+			bail!(ErrorKind::InvalidBlock(hash, "justification of another block"));
+		}
+
+		Ok(block)
+	}
+
+	fn call<R: DeserializeOwned>(&self, method: &str, params: Vec<Value>) -> Result<R> {
+		let result = self.transport.request(method, params)?;
+		Ok(serde_json::from_value(result)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::cell::RefCell;
+	use std::collections::HashMap;
+	use primitives::AccountId;
+	use runtime::Extrinsic;
+	use runtime_primitives::{bft::Justification, Ed25519Signature, MaybeUnsigned};
+
+	#[derive(Default)]
+	struct TestNode {
+		head: RefCell<Hash>,
+		blocks: RefCell<HashMap<Hash, SignedBlock>>,
+	}
+
+	impl TestNode {
+		fn import(&self, header: Header, extrinsics: Vec<UncheckedExtrinsic>) -> Hash {
+			let hash = BlakeTwo256::hash_of(&header);
+			let justification = Justification { round_number: 0, hash, signatures: Vec::new() };
+			let block = SignedBlock { block: generic::Block { header, extrinsics }, justification };
+			self.blocks.borrow_mut().insert(hash, block);
+			*self.head.borrow_mut() = hash;
+			hash
+		}
+	}
+
+	impl<'a> Transport for &'a TestNode {
+		fn request(&self, method: &str, params: Vec<Value>) -> Result<Value> {
+			match method {
+				"chain_getHead" => Ok(json!(*self.head.borrow())),
+				"chain_getBlock" => {
+					let hash: Hash = serde_json::from_value(params[0].clone())?;
+					Ok(json!(self.blocks.borrow().get(&hash)))
+				}
+				_ => bail!(ErrorKind::Rpc(-32601, "Method not found".into())),
+			}
+		}
+	}
+
+	fn account(n: u8) -> AccountId {
+		[n; 32].into()
+	}
+
+	fn transfer(from: u8, to: u8, value: Balance) -> UncheckedExtrinsic {
+		let extrinsic = RuntimeExtrinsic::new(Extrinsic {
+			signed: Address::Id(account(from)),
+			index: 0,
+			function: Call::Staking(StakingCall::transfer(Address::Id(account(to)), value)),
+		}, MaybeUnsigned(Ed25519Signature([1; 64].into())));
+
+		let encoded = extrinsic.encode();
+		UncheckedExtrinsic::decode(&mut &encoded[..]).unwrap()
+	}
+
+	fn header(parent: &Header, extrinsics: &[UncheckedExtrinsic]) -> Header {
+		Header {
+			parent_hash: BlakeTwo256::hash_of(parent),
+			number: parent.number + 1,
+			state_root: Default::default(),
+			extrinsics_root: BlakeTwo256::ordered_trie_root(extrinsics.iter().map(Slicable::encode)),
+			digest: Default::default(),
+		}
+	}
+
+	fn genesis() -> Header {
+		Header {
+			parent_hash: Default::default(),
+			number: 0,
+			state_root: Default::default(),
+			extrinsics_root: BlakeTwo256::ordered_trie_root(Vec::<Vec<u8>>::new()),
+			digest: Default::default(),
+		}
+	}
+
+	#[test]
+	fn follows_blocks_in_order() {
+		let node = TestNode::default();
+		let genesis = genesis();
+		node.import(genesis.clone(), Vec::new());
+
+		let mut follower = Follower::new(&node, &genesis);
+		assert!(follower.poll().unwrap().is_empty());
+
+		let body_1 = vec![transfer(1, 2, 100)];
+		let header_1 = header(&genesis, &body_1);
+		node.import(header_1.clone(), body_1);
+
+		let body_2 = vec![vec![1, 2, 3], transfer(2, 3, 50)];
+		let header_2 = header(&header_1, &body_2);
+		let hash_2 = node.import(header_2, body_2);
+
+		let followed = follower.poll().unwrap();
+		assert_eq!(followed.iter().map(|b| b.block.block.header.number).collect::<Vec<_>>(), vec![1, 2]);
+		assert_eq!(followed[0].transfers, vec![Transfer {
+			index: 0,
+			from: Address::Id(account(1)),
+			to: Address::Id(account(2)),
+			value: 100,
+		}]);
+		assert_eq!(followed[1].transfers.len(), 1);
+		assert_eq!(followed[1].transfers[0].index, 1);
+		assert_eq!(follower.last(), (hash_2, 2));
+
+		assert!(follower.poll().unwrap().is_empty());
+	}
+
+	#[test]
+	fn rejects_bodies_not_matching_the_header() {
+		let node = TestNode::default();
+		let genesis = genesis();
+
+		let header_1 = header(&genesis, &[transfer(1, 2, 100)]);
+		node.import(header_1, vec![transfer(1, 2, 1_000_000)]);
+
+		let mut follower = Follower::new(&node, &genesis);
+		match follower.poll() {
+			Err(Error(ErrorKind::InvalidBlock(_, _), _)) => {}
+			other => panic!("unexpected result: {:?}", other.map(|b| b.len())),
+		}
+		assert_eq!(follower.last().1, 0);
+	}
+
+	#[test]
+	fn rejects_chains_not_descending_from_the_checkpoint() {
+		let node = TestNode::default();
+		let genesis = genesis();
+		let mut other_genesis = genesis.clone();
+		other_genesis.state_root = [1; 32].into();
+
+		node.import(header(&other_genesis, &[]), Vec::new());
+
+		let mut follower = Follower::new(&node, &genesis);
+		match follower.poll() {
+			Err(Error(ErrorKind::Diverged(_), _)) => {}
+			other => panic!("unexpected result: {:?}", other.map(|b| b.len())),
+		}
+	}
+
+	#[test]
+	fn rpc_errors_are_reported() {
+		assert_eq!(response_result(json!({"jsonrpc": "2.0", "id": 1, "result": 5})).unwrap(), json!(5));
+		match response_result(json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32601, "message": "Method not found"}})) {
+			Err(Error(ErrorKind::Rpc(-32601, _), _)) => {}
+			other => panic!("unexpected result: {:?}", other),
+		}
+	}
+}
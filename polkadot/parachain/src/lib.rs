@@ -66,6 +66,17 @@ use codec::Slicable;
 #[cfg(feature = "std")]
 pub mod wasm;
 
+/// Maximum size of the proof of validity of a candidate, in bytes: its block
+/// data, including any state proof it carries. Not enforced yet; collators are
+/// warned as their candidates approach it.
+pub const MAX_POV_SIZE: usize = 5 * 1024 * 1024;
+
+/// Whether a proof of validity of the given size is close enough to
+/// `MAX_POV_SIZE` to warn about.
+pub fn pov_size_near_limit(size: usize) -> bool {
+	size > MAX_POV_SIZE / 10 * 8
+}
+
 /// Validation parameters for evaluating the parachain validity function.
 // TODO: consolidated ingress and balance downloads
 #[derive(PartialEq, Eq)]
@@ -104,6 +104,52 @@ pub type Balance = u128;
 // TODO: parameterize blockid only as necessary.
 pub type BlockId = generic::BlockId<Block>;
 
+/// A report of misbehavior by an authority during agreement on a relay chain block.
+pub type MisbehaviorReport = runtime_primitives::bft::MisbehaviorReport<Hash, BlockNumber>;
+
+/// A signed statement that a validator was online during a session.
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", serde(deny_unknown_fields))]
+pub struct Heartbeat {
+	/// The index of the session the validator was online in.
+	pub session_index: BlockNumber,
+	/// The session key of the validator.
+	pub authority: SessionKey,
+	/// Signature by the session key on the payload given by `Heartbeat::payload`.
+	pub signature: runtime_primitives::Ed25519Signature,
+}
+
+impl Heartbeat {
+	/// The message which is signed to produce a heartbeat for the given session.
+	pub fn payload(session_index: BlockNumber) -> Vec<u8> {
+		let mut v = b"heartbeat".to_vec();
+		session_index.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+impl Slicable for Heartbeat {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		self.session_index.using_encoded(|s| v.extend(s));
+		self.authority.using_encoded(|s| v.extend(s));
+		self.signature.using_encoded(|s| v.extend(s));
+
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(Heartbeat {
+			session_index: Slicable::decode(input)?,
+			authority: Slicable::decode(input)?,
+			signature: Slicable::decode(input)?,
+		})
+	}
+}
+
 /// A log entry in the block.
 #[derive(PartialEq, Eq, Clone, Default)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
@@ -59,7 +59,7 @@ impl Slicable for Id {
 
 /// Identifier for a chain, either one of a number of parachains or the relay chain.
 #[derive(Copy, Clone, PartialEq)]
-#[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
 pub enum Chain {
 	/// The relay chain.
 	Relay,
@@ -96,7 +96,8 @@ impl Slicable for Chain {
 
 /// The duty roster specifying what jobs each validator must do.
 #[derive(Clone, PartialEq)]
-#[cfg_attr(feature = "std", derive(Default, Debug))]
+#[cfg_attr(feature = "std", derive(Default, Serialize, Deserialize, Debug))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct DutyRoster {
 	/// Lookup from validator index to chain on which that validator has a duty to validate.
 	pub validator_duty: Vec<Chain>,
@@ -154,6 +155,103 @@ pub struct Candidate {
 	pub block: BlockData,
 }
 
+/// Version of the candidate commitments encoding produced by this node.
+pub const COMMITMENTS_VERSION: u8 = 1;
+
+/// Commitments made by a candidate to the relay chain.
+///
+/// These are encoded as a version byte followed by the length-prefixed fields,
+/// so that later versions may append fields without breaking older nodes.
+/// Fields appended by a version newer than `COMMITMENTS_VERSION` can't be
+/// interpreted, but are kept verbatim so the commitments re-encode, and so hash,
+/// exactly as received.
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", serde(deny_unknown_fields))]
+pub struct CandidateCommitments {
+	/// Version of the encoding these commitments were produced with.
+	pub version: u8,
+	/// Egress queue roots.
+	pub egress_queue_roots: Vec<(Id, Hash)>,
+	/// Amount of egress posted to each destination, in ascending order by ID.
+	pub egress_sizes: Vec<(Id, EgressSize)>,
+	/// Fees paid from the chain to the relay chain validators
+	pub fees: u64,
+	/// Root of the erasure-coding of the candidate's block data.
+	pub erasure_root: Hash,
+	/// Messages sent from the parachain to the relay chain.
+	pub upward_messages: Vec<Message>,
+	/// Signal bits raised by the parachain.
+	pub signals: u32,
+	/// Encoded fields of a newer version, which aren't understood by this node.
+	#[cfg_attr(feature = "std", serde(default, with="bytes"))]
+	pub unknown_fields: Vec<u8>,
+}
+
+impl Default for CandidateCommitments {
+	fn default() -> Self {
+		CandidateCommitments {
+			version: COMMITMENTS_VERSION,
+			egress_queue_roots: Vec::new(),
+			egress_sizes: Vec::new(),
+			fees: 0,
+			erasure_root: Default::default(),
+			upward_messages: Vec::new(),
+			signals: 0,
+			unknown_fields: Vec::new(),
+		}
+	}
+}
+
+impl Slicable for CandidateCommitments {
+	fn encode(&self) -> Vec<u8> {
+		let mut fields = Vec::new();
+
+		self.egress_queue_roots.using_encoded(|s| fields.extend(s));
+		self.egress_sizes.using_encoded(|s| fields.extend(s));
+		self.fees.using_encoded(|s| fields.extend(s));
+		self.erasure_root.using_encoded(|s| fields.extend(s));
+		self.upward_messages.using_encoded(|s| fields.extend(s));
+		self.signals.using_encoded(|s| fields.extend(s));
+		fields.extend(&self.unknown_fields);
+
+		let mut v = Vec::new();
+		v.push(self.version);
+		fields.using_encoded(|s| v.extend(s));
+
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		let version = input.read_byte()?;
+		if version < COMMITMENTS_VERSION {
+			return None;
+		}
+
+		let fields: Vec<u8> = Slicable::decode(input)?;
+		let mut fields = &fields[..];
+
+		let commitments = CandidateCommitments {
+			version,
+			egress_queue_roots: Slicable::decode(&mut fields)?,
+			egress_sizes: Slicable::decode(&mut fields)?,
+			fees: Slicable::decode(&mut fields)?,
+			erasure_root: Slicable::decode(&mut fields)?,
+			upward_messages: Slicable::decode(&mut fields)?,
+			signals: Slicable::decode(&mut fields)?,
+			unknown_fields: fields.to_vec(),
+		};
+
+		// only a newer version may carry fields we don't know about.
+		if version == COMMITMENTS_VERSION && !commitments.unknown_fields.is_empty() {
+			return None;
+		}
+
+		Some(commitments)
+	}
+}
+
 /// Candidate receipt type.
 #[derive(PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
@@ -168,10 +266,8 @@ pub struct CandidateReceipt {
 	pub head_data: HeadData,
 	/// Balance uploads to the relay chain.
 	pub balance_uploads: Vec<(super::AccountId, u64)>,
-	/// Egress queue roots.
-	pub egress_queue_roots: Vec<(Id, Hash)>,
-	/// Fees paid from the chain to the relay chain validators
-	pub fees: u64,
+	/// Commitments made by the candidate to the relay chain.
+	pub commitments: CandidateCommitments,
 }
 
 impl Slicable for CandidateReceipt {
@@ -182,8 +278,7 @@ impl Slicable for CandidateReceipt {
 		self.collator.using_encoded(|s| v.extend(s));
 		self.head_data.0.using_encoded(|s| v.extend(s));
 		self.balance_uploads.using_encoded(|s| v.extend(s));
-		self.egress_queue_roots.using_encoded(|s| v.extend(s));
-		self.fees.using_encoded(|s| v.extend(s));
+		self.commitments.using_encoded(|s| v.extend(s));
 
 		v
 	}
@@ -194,8 +289,7 @@ impl Slicable for CandidateReceipt {
 			collator: Slicable::decode(input)?,
 			head_data: Slicable::decode(input).map(HeadData)?,
 			balance_uploads: Slicable::decode(input)?,
-			egress_queue_roots: Slicable::decode(input)?,
-			fees: Slicable::decode(input)?,
+			commitments: Slicable::decode(input)?,
 		})
 	}
 }
@@ -223,11 +317,108 @@ impl Ord for CandidateReceipt {
 	}
 }
 
+/// Amount of egress posted by a candidate to a single destination.
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", serde(deny_unknown_fields))]
+pub struct EgressSize {
+	/// Number of messages.
+	pub messages: u32,
+	/// Total length of the messages, in bytes.
+	pub bytes: u32,
+}
+
+impl Slicable for EgressSize {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(EgressSize {
+			messages: Slicable::decode(input)?,
+			bytes: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		self.messages.using_encoded(|s| v.extend(s));
+		self.bytes.using_encoded(|s| v.extend(s));
+
+		v
+	}
+}
+
+/// Limits on the egress a candidate may post to a single destination in a block.
+/// `None` means unlimited.
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct EgressLimits {
+	/// Maximum number of messages.
+	pub max_messages: Option<u32>,
+	/// Maximum total length of the messages, in bytes.
+	pub max_bytes: Option<u32>,
+}
+
+impl EgressLimits {
+	/// Whether egress of the given size is within the limits.
+	pub fn admits(&self, size: &EgressSize) -> bool {
+		self.max_messages.map_or(true, |max| size.messages <= max)
+			&& self.max_bytes.map_or(true, |max| size.bytes <= max)
+	}
+}
+
+/// An egress queue root posted by a parachain to a single destination.
+///
+/// The relay chain keeps these until the destination has routed them, which it
+/// does for all egress posted to it before the next block including one of its
+/// candidates. Roots consumed by a block are still listed at that block.
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", serde(deny_unknown_fields))]
+pub struct EgressRoot {
+	/// Root of the egress queue.
+	pub root: Hash,
+	/// Number of the relay chain block which included the queue.
+	pub posted_at: super::BlockNumber,
+	/// Whether the destination has routed the queue.
+	pub consumed: bool,
+}
+
+impl Slicable for EgressRoot {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(EgressRoot {
+			root: Slicable::decode(input)?,
+			posted_at: Slicable::decode(input)?,
+			consumed: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		self.root.using_encoded(|s| v.extend(s));
+		self.posted_at.using_encoded(|s| v.extend(s));
+		self.consumed.using_encoded(|s| v.extend(s));
+
+		v
+	}
+}
+
 /// Parachain ingress queue message.
 #[derive(PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
 pub struct Message(#[cfg_attr(feature = "std", serde(with="bytes"))] pub Vec<u8>);
 
+impl Slicable for Message {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Slicable::decode(input).map(Message)
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		self.0.encode()
+	}
+}
+
 /// Consolidated ingress queue data.
 ///
 /// This is just an ordered vector of other parachains' egress queues,
@@ -281,6 +472,7 @@ enum StatementKind {
 	Valid = 2,
 	Invalid = 3,
 	Available = 4,
+	Withdraw = 5,
 }
 
 /// Statements which can be made about parachain candidates.
@@ -295,6 +487,8 @@ pub enum Statement {
 	Invalid(Hash),
 	/// Vote to advance round after inactive primary.
 	Available(Hash),
+	/// Retraction of a parachain candidate by its issuer.
+	Withdraw(Hash),
 }
 
 impl Slicable for Statement {
@@ -317,6 +511,10 @@ impl Slicable for Statement {
 				v.push(StatementKind::Available as u8);
 				hash.using_encoded(|s| v.extend(s));
 			}
+			Statement::Withdraw(ref hash) => {
+				v.push(StatementKind::Withdraw as u8);
+				hash.using_encoded(|s| v.extend(s));
+			}
 		}
 
 		v
@@ -336,7 +534,237 @@ impl Slicable for Statement {
 			Some(x) if x == StatementKind::Available as u8 => {
 				Slicable::decode(value).map(Statement::Available)
 			}
+			Some(x) if x == StatementKind::Withdraw as u8 => {
+				Slicable::decode(value).map(Statement::Withdraw)
+			}
 			_ => None,
 		}
 	}
 }
+
+impl Statement {
+	/// The message signed by a validator making this statement on top of the
+	/// given relay chain parent.
+	pub fn signing_payload(&self, parent_hash: &Hash) -> Vec<u8> {
+		let mut v = self.encode();
+		parent_hash.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+// single-byte code to represent candidate agreement misbehavior kind.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[repr(u8)]
+enum AgreementMisbehaviorCode {
+	IssuedAndInvalidity = 1,
+	ValidityAndInvalidity = 2,
+	MultipleCandidates = 3,
+}
+
+/// Provable misbehavior of a validator during agreement on parachain
+/// candidates. Signatures are on the `signing_payload` of the statements.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+pub enum AgreementMisbehavior {
+	/// Issued a candidate and voted it invalid: the candidate with the
+	/// signature issuing it, and the signature of the invalidity vote.
+	IssuedAndInvalidity((CandidateReceipt, CandidateSignature), CandidateSignature),
+	/// Voted a candidate both valid and invalid: its digest, and the
+	/// signatures of the validity and invalidity votes.
+	ValidityAndInvalidity(Hash, CandidateSignature, CandidateSignature),
+	/// Issued two different candidates, with their signatures.
+	MultipleCandidates((CandidateReceipt, CandidateSignature), (CandidateReceipt, CandidateSignature)),
+}
+
+/// A report of misbehavior by a validator during agreement on parachain
+/// candidates.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", serde(deny_unknown_fields))]
+pub struct AgreementMisbehaviorReport {
+	/// The relay chain parent the statements were made on top of.
+	pub parent_hash: Hash,
+	/// The session key of the validator which misbehaved.
+	pub target: super::SessionKey,
+	/// The misbehavior.
+	pub misbehavior: AgreementMisbehavior,
+}
+
+impl AgreementMisbehaviorReport {
+	/// An encoding of the offence proven by this report: who misbehaved, in
+	/// which way, on top of which parent and, for contradicting votes, about
+	/// which candidate. All reports of the same offence share it.
+	pub fn offence(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		self.parent_hash.using_encoded(|s| v.extend(s));
+		self.target.using_encoded(|s| v.extend(s));
+
+		match self.misbehavior {
+			AgreementMisbehavior::IssuedAndInvalidity((ref candidate, _), _) => {
+				use runtime_primitives::traits::{BlakeTwo256, Hashing};
+
+				v.push(AgreementMisbehaviorCode::IssuedAndInvalidity as u8);
+				BlakeTwo256::hash_of(candidate).using_encoded(|s| v.extend(s));
+			}
+			AgreementMisbehavior::ValidityAndInvalidity(ref digest, _, _) => {
+				v.push(AgreementMisbehaviorCode::ValidityAndInvalidity as u8);
+				digest.using_encoded(|s| v.extend(s));
+			}
+			AgreementMisbehavior::MultipleCandidates(_, _) => {
+				v.push(AgreementMisbehaviorCode::MultipleCandidates as u8);
+			}
+		}
+
+		v
+	}
+}
+
+impl Slicable for AgreementMisbehaviorReport {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		self.parent_hash.using_encoded(|s| v.extend(s));
+		self.target.using_encoded(|s| v.extend(s));
+
+		match self.misbehavior {
+			AgreementMisbehavior::IssuedAndInvalidity(ref issued, ref invalidity) => {
+				v.push(AgreementMisbehaviorCode::IssuedAndInvalidity as u8);
+				issued.using_encoded(|s| v.extend(s));
+				invalidity.using_encoded(|s| v.extend(s));
+			}
+			AgreementMisbehavior::ValidityAndInvalidity(ref digest, ref validity, ref invalidity) => {
+				v.push(AgreementMisbehaviorCode::ValidityAndInvalidity as u8);
+				digest.using_encoded(|s| v.extend(s));
+				validity.using_encoded(|s| v.extend(s));
+				invalidity.using_encoded(|s| v.extend(s));
+			}
+			AgreementMisbehavior::MultipleCandidates(ref first, ref second) => {
+				v.push(AgreementMisbehaviorCode::MultipleCandidates as u8);
+				first.using_encoded(|s| v.extend(s));
+				second.using_encoded(|s| v.extend(s));
+			}
+		}
+
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		let parent_hash = Slicable::decode(input)?;
+		let target = Slicable::decode(input)?;
+
+		let misbehavior = match input.read_byte() {
+			Some(x) if x == AgreementMisbehaviorCode::IssuedAndInvalidity as u8 => {
+				AgreementMisbehavior::IssuedAndInvalidity(Slicable::decode(input)?, Slicable::decode(input)?)
+			}
+			Some(x) if x == AgreementMisbehaviorCode::ValidityAndInvalidity as u8 => {
+				AgreementMisbehavior::ValidityAndInvalidity(
+					Slicable::decode(input)?,
+					Slicable::decode(input)?,
+					Slicable::decode(input)?,
+				)
+			}
+			Some(x) if x == AgreementMisbehaviorCode::MultipleCandidates as u8 => {
+				AgreementMisbehavior::MultipleCandidates(Slicable::decode(input)?, Slicable::decode(input)?)
+			}
+			_ => return None,
+		};
+
+		Some(AgreementMisbehaviorReport {
+			parent_hash,
+			target,
+			misbehavior,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn commitments() -> CandidateCommitments {
+		CandidateCommitments {
+			egress_queue_roots: vec![(1.into(), [1; 32].into())],
+			egress_sizes: vec![(1.into(), EgressSize { messages: 2, bytes: 64 })],
+			fees: 1_000_000,
+			upward_messages: vec![Message(vec![1, 2, 3])],
+			signals: 0b101,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn commitments_roundtrip() {
+		let commitments = commitments();
+		let encoded = commitments.encode();
+		assert_eq!(CandidateCommitments::decode(&mut &encoded[..]), Some(commitments));
+	}
+
+	#[test]
+	fn commitments_of_newer_version_are_tolerated() {
+		let mut newer = commitments();
+		newer.version = COMMITMENTS_VERSION + 1;
+		newer.unknown_fields = vec![9, 9, 9];
+
+		let encoded = newer.encode();
+		let decoded = CandidateCommitments::decode(&mut &encoded[..]).unwrap();
+		assert_eq!(decoded, newer);
+		assert_eq!(decoded.fees, 1_000_000);
+		assert_eq!(decoded.encode(), encoded);
+	}
+
+	#[test]
+	fn unknown_fields_rejected_at_current_version() {
+		let mut malformed = commitments();
+		malformed.unknown_fields = vec![9];
+
+		let encoded = malformed.encode();
+		assert!(CandidateCommitments::decode(&mut &encoded[..]).is_none());
+
+		let mut legacy = commitments();
+		legacy.version = 0;
+
+		let encoded = legacy.encode();
+		assert!(CandidateCommitments::decode(&mut &encoded[..]).is_none());
+	}
+
+	#[test]
+	fn agreement_misbehavior_offence_ignores_evidence() {
+		let signature = |byte: u8| CandidateSignature::from(::primitives::H512::from([byte; 64]));
+		let candidate = |head| CandidateReceipt {
+			parachain_index: 1.into(),
+			collator: Default::default(),
+			head_data: HeadData(vec![head]),
+			balance_uploads: Vec::new(),
+			commitments: commitments(),
+		};
+		let report = |misbehavior| AgreementMisbehaviorReport {
+			parent_hash: [1; 32].into(),
+			target: [2; 32].into(),
+			misbehavior,
+		};
+
+		let multiple = |head, sig| report(AgreementMisbehavior::MultipleCandidates(
+			(candidate(1), signature(3)),
+			(candidate(head), signature(sig)),
+		));
+		let encoded = multiple(2, 4).encode();
+		assert_eq!(AgreementMisbehaviorReport::decode(&mut &encoded[..]), Some(multiple(2, 4)));
+		assert_eq!(multiple(2, 4).offence(), multiple(3, 5).offence());
+
+		let double_vote = |digest, sig| report(AgreementMisbehavior::ValidityAndInvalidity(
+			[digest; 32].into(),
+			signature(3),
+			signature(sig),
+		));
+		let encoded = double_vote(4, 5).encode();
+		assert_eq!(AgreementMisbehaviorReport::decode(&mut &encoded[..]), Some(double_vote(4, 5)));
+		assert_eq!(double_vote(4, 5).offence(), double_vote(4, 6).offence());
+		assert!(double_vote(4, 5).offence() != double_vote(7, 5).offence());
+		assert!(double_vote(4, 5).offence() != multiple(2, 4).offence());
+
+		let issued = report(AgreementMisbehavior::IssuedAndInvalidity((candidate(1), signature(3)), signature(4)));
+		let encoded = issued.encode();
+		assert_eq!(AgreementMisbehaviorReport::decode(&mut &encoded[..]), Some(issued));
+	}
+}
@@ -0,0 +1,112 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Records how the runtime is built, for `BUILD_INFO` and the build info
+//! section of the Wasm runtime. Shared by the native and the Wasm build.
+//!
+//! Nothing depending on the time or place of the build is recorded, so that
+//! building the same source with the same compiler and features yields the same
+//! code.
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+const UNKNOWN: &str = "unknown";
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+	let output = Command::new(program)
+		.args(args)
+		.current_dir(env::var("CARGO_MANIFEST_DIR").ok()?)
+		.output()
+		.ok()?;
+
+	if output.status.success() {
+		String::from_utf8(output.stdout).ok().map(|s| s.trim().to_owned())
+	} else {
+		None
+	}
+}
+
+fn rustc_version() -> String {
+	let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+	command_output(&rustc, &["--version"]).unwrap_or_else(|| UNKNOWN.into())
+}
+
+fn features() -> String {
+	let mut features: Vec<_> = env::vars()
+		.filter_map(|(k, _)| if k.starts_with("CARGO_FEATURE_") {
+			Some(k["CARGO_FEATURE_".len()..].to_lowercase().replace('_', "-"))
+		} else {
+			None
+		})
+		.collect();
+
+	features.sort();
+	features.join(",")
+}
+
+fn source_hash() -> String {
+	let tree = match command_output("git", &["rev-parse", "HEAD^{tree}"]) {
+		Some(tree) => tree,
+		None => return UNKNOWN.into(),
+	};
+
+	match command_output("git", &["status", "--porcelain", "--untracked-files=no"]) {
+		Some(ref changes) if changes.is_empty() => tree,
+		_ => format!("{}-dirty", tree),
+	}
+}
+
+// same encoding as `BuildInfo`.
+fn encode(fields: &[&str]) -> Vec<u8> {
+	let mut v = Vec::new();
+	for field in fields {
+		let len = field.len() as u32;
+		v.extend_from_slice(&[len as u8, (len >> 8) as u8, (len >> 16) as u8, (len >> 24) as u8]);
+		v.extend_from_slice(field.as_bytes());
+	}
+	v
+}
+
+fn main() {
+	let (rustc_version, features, source_hash) = (rustc_version(), features(), source_hash());
+
+	println!("cargo:rustc-env=RUNTIME_BUILD_RUSTC_VERSION={}", rustc_version);
+	println!("cargo:rustc-env=RUNTIME_BUILD_FEATURES={}", features);
+	println!("cargo:rustc-env=RUNTIME_BUILD_SOURCE_HASH={}", source_hash);
+
+	if let Some(git_dir) = command_output("git", &["rev-parse", "--absolute-git-dir"]) {
+		println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+		println!("cargo:rerun-if-changed={}/index", git_dir);
+	}
+
+	let section = encode(&[&rustc_version, &features, &source_hash]);
+	let out_dir = env::var("OUT_DIR").expect("set by cargo for build scripts; qed");
+	let mut file = File::create(Path::new(&out_dir).join("build_info_section.rs"))
+		.expect("OUT_DIR is writable; qed");
+
+	writeln!(
+		file,
+		"/// The encoded `BuildInfo`, in a custom section of the Wasm runtime.\n\
+		#[used]\n\
+		#[link_section = \"runtime_build_info\"]\n\
+		pub static BUILD_INFO_SECTION: [u8; {}] = {:?};",
+		section.len(), section
+	).expect("OUT_DIR is writable; qed");
+}
@@ -0,0 +1,244 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Validator liveness tracking.
+//!
+//! Validators gossip signed heartbeats, which block authors collect and note in a
+//! single unsigned extrinsic. At the end of every session each validator which
+//! wasn't heard from has a missed session counted against it, and those which
+//! missed too many in a row are flagged as offline, to be removed.
+
+use rstd::prelude::*;
+use primitives::{BlockNumber, Heartbeat, SessionKey};
+use runtime_primitives::traits::{RefInto, MaybeEmpty};
+use {runtime_io, consensus, session, system};
+
+use substrate_runtime_support::{StorageValue, StorageMap};
+use substrate_runtime_support::dispatch::Result;
+
+#[cfg(any(feature = "std", test))]
+use rstd::marker::PhantomData;
+
+#[cfg(any(feature = "std", test))]
+use runtime_primitives;
+
+pub trait Trait: session::Trait<SessionKey = SessionKey, BlockNumber = BlockNumber> {
+	/// The position of the note_heartbeats call in the block.
+	const SET_POSITION: u32;
+
+	type PublicAux: RefInto<Self::AccountId> + MaybeEmpty;
+}
+
+decl_module! {
+	/// Heartbeats module.
+	pub struct Module<T: Trait>;
+
+	/// Call type for heartbeats.
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub enum Call where aux: <T as Trait>::PublicAux {
+		// note heartbeats of authorities for the current session.
+		fn note_heartbeats(aux, heartbeats: Vec<Heartbeat>) -> Result = 0;
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait>;
+	// The last session in which a heartbeat was noted for each authority.
+	pub LastHeartbeat get(last_heartbeat): b"hbt:last" => map [ SessionKey => BlockNumber ];
+	// The number of consecutive sessions each validator has missed.
+	pub MissedSessions get(missed_sessions): b"hbt:missed" => default map [ T::AccountId => u32 ];
+	// Consecutive missed sessions after which a validator is flagged offline. Zero disables flagging.
+	pub MaxMissedSessions get(max_missed_sessions): b"hbt:max_missed" => default u32;
+	// Validators flagged as offline at the end of the last session.
+	pub OfflineValidators get(offline_validators): b"hbt:offline" => default Vec<T::AccountId>;
+}
+
+impl<T: Trait> Module<T> {
+	fn note_heartbeats(aux: &<T as Trait>::PublicAux, heartbeats: Vec<Heartbeat>) -> Result {
+		ensure!(aux.is_empty(), "note_heartbeats must not be signed");
+		ensure!(
+			<system::Module<T>>::extrinsic_index() == T::SET_POSITION,
+			"Heartbeats extrinsic must be at position {} in the block"
+//			, T::SET_POSITION
+		);
+
+		let session_index = <session::Module<T>>::current_index();
+		let authorities = <consensus::Module<T>>::authorities();
+		let payload = Heartbeat::payload(session_index);
+
+		// perform these checks before writing to storage.
+		for heartbeat in &heartbeats {
+			ensure!(heartbeat.session_index == session_index, "Heartbeat is not for the current session");
+			ensure!(authorities.contains(&heartbeat.authority), "Heartbeat from unknown authority");
+			ensure!(
+				runtime_io::ed25519_verify(&(heartbeat.signature.0).0, &payload, &heartbeat.authority.0),
+				"Heartbeat signature invalid"
+			);
+		}
+
+		for heartbeat in heartbeats {
+			<LastHeartbeat<T>>::insert(heartbeat.authority, session_index);
+		}
+
+		Ok(())
+	}
+}
+
+impl<T: Trait> session::OnSessionChange<T::Moment> for Module<T> {
+	fn on_session_change(_normal_rotation: bool, _time_elapsed: T::Moment) {
+		// the session index is incremented before this is called.
+		let ended_session = <session::Module<T>>::current_index() - 1;
+		let max_missed = Self::max_missed_sessions();
+
+		// authorities are still those of the ended session, and line up with validators.
+		let authorities = <consensus::Module<T>>::authorities();
+		let mut offline = Vec::new();
+
+		for (validator, authority) in <session::Module<T>>::validators().into_iter().zip(authorities) {
+			if Self::last_heartbeat(&authority) == Some(ended_session) {
+				<MissedSessions<T>>::remove(&validator);
+			} else {
+				let missed = Self::missed_sessions(&validator) + 1;
+				<MissedSessions<T>>::insert(&validator, missed);
+
+				if max_missed != 0 && missed >= max_missed {
+					offline.push(validator);
+				}
+			}
+		}
+
+		<OfflineValidators<T>>::put(offline);
+	}
+}
+
+/// Heartbeats module genesis configuration.
+#[cfg(any(feature = "std", test))]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct GenesisConfig<T: Trait> {
+	/// Consecutive missed sessions after which a validator is flagged offline.
+	/// Zero disables flagging.
+	#[serde(default)]
+	pub max_missed_sessions: u32,
+	/// Phantom data.
+	#[serde(skip)]
+	pub phantom: PhantomData<T>,
+}
+
+#[cfg(any(feature = "std", test))]
+impl<T: Trait> Default for GenesisConfig<T> {
+	fn default() -> Self {
+		GenesisConfig {
+			max_missed_sessions: 0,
+			phantom: PhantomData,
+		}
+	}
+}
+
+#[cfg(any(feature = "std", test))]
+impl<T: Trait> runtime_primitives::BuildStorage for GenesisConfig<T>
+{
+	fn build_storage(self) -> ::std::result::Result<runtime_io::TestExternalities, String> {
+		use runtime_io::twox_128;
+		use codec::Slicable;
+
+		Ok(map![
+			twox_128(<MaxMissedSessions<T>>::key()).to_vec() => self.max_missed_sessions.encode()
+		])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use runtime_io::with_externalities;
+	use substrate_keyring::Keyring;
+	use mock::{Test, Session, Heartbeats, keyring_for, authority_for};
+
+	fn heartbeat(validator: u64, session_index: BlockNumber) -> Heartbeat {
+		Heartbeat {
+			session_index,
+			authority: authority_for(validator),
+			signature: keyring_for(validator).sign(&Heartbeat::payload(session_index)).into(),
+		}
+	}
+
+	fn new_test_ext() -> runtime_io::TestExternalities {
+		::mock::new_test_ext(vec![1, 2, 3], vec![], vec![])
+	}
+
+	#[test]
+	fn notes_valid_heartbeats() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Heartbeats::note_heartbeats(&0, vec![heartbeat(1, 0), heartbeat(2, 0)]));
+			assert_eq!(Heartbeats::last_heartbeat(&authority_for(1)), Some(0));
+			assert_eq!(Heartbeats::last_heartbeat(&authority_for(2)), Some(0));
+			assert_eq!(Heartbeats::last_heartbeat(&authority_for(3)), None);
+		});
+	}
+
+	#[test]
+	fn rejects_invalid_heartbeats() {
+		with_externalities(&mut new_test_ext(), || {
+			let mut forged = heartbeat(1, 0);
+			forged.authority = authority_for(2);
+
+			assert_noop!(Heartbeats::note_heartbeats(&1, vec![heartbeat(1, 0)]), "note_heartbeats must not be signed");
+			assert_noop!(
+				Heartbeats::note_heartbeats(&0, vec![heartbeat(1, 0), heartbeat(2, 1)]),
+				"Heartbeat is not for the current session"
+			);
+			assert_noop!(Heartbeats::note_heartbeats(&0, vec![forged]), "Heartbeat signature invalid");
+
+			let mut stranger = heartbeat(1, 0);
+			stranger.authority = Keyring::Dave.to_raw_public().into();
+			assert_noop!(Heartbeats::note_heartbeats(&0, vec![stranger]), "Heartbeat from unknown authority");
+
+			<system::ExtrinsicIndex<Test>>::put(1);
+			assert_noop!(
+				Heartbeats::note_heartbeats(&0, vec![heartbeat(1, 0)]),
+				"Heartbeats extrinsic must be at position {} in the block"
+			);
+		});
+	}
+
+	#[test]
+	fn flags_chronically_offline_validators() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Heartbeats::note_heartbeats(&0, vec![heartbeat(1, 0), heartbeat(2, 0)]));
+			Session::rotate_session(true);
+
+			assert_eq!(Heartbeats::missed_sessions(&1), 0);
+			assert_eq!(Heartbeats::missed_sessions(&3), 1);
+			assert!(Heartbeats::offline_validators().is_empty());
+
+			assert_ok!(Heartbeats::note_heartbeats(&0, vec![heartbeat(1, 1)]));
+			Session::rotate_session(true);
+
+			assert_eq!(Heartbeats::missed_sessions(&1), 0);
+			assert_eq!(Heartbeats::missed_sessions(&2), 1);
+			assert_eq!(Heartbeats::missed_sessions(&3), 2);
+			assert_eq!(Heartbeats::offline_validators(), vec![3]);
+
+			assert_ok!(Heartbeats::note_heartbeats(&0, vec![heartbeat(1, 2), heartbeat(2, 2), heartbeat(3, 2)]));
+			Session::rotate_session(true);
+
+			assert_eq!(Heartbeats::missed_sessions(&3), 0);
+			assert!(Heartbeats::offline_validators().is_empty());
+		});
+	}
+}
@@ -41,6 +41,9 @@ extern crate hex_literal;
 #[cfg(test)]
 extern crate substrate_serializer;
 
+#[cfg(test)]
+extern crate substrate_keyring;
+
 extern crate substrate_primitives;
 
 #[macro_use]
@@ -61,7 +64,10 @@ extern crate substrate_runtime_version as version;
 
 #[cfg(feature = "std")]
 mod checked_block;
+mod heartbeats;
+mod mock;
 mod parachains;
+mod slashing;
 mod utils;
 
 #[cfg(feature = "std")]
@@ -71,20 +77,29 @@ pub use staking::address::Address as RawAddress;
 
 use primitives::{AccountId, AccountIndex, Balance, BlockNumber, Hash, Index, Log, SessionKey, Signature};
 use runtime_primitives::{generic, traits::{HasPublicAux, BlakeTwo256, Convert}};
-use version::RuntimeVersion;
+use version::{BuildInfo, RuntimeVersion};
 
 #[cfg(feature = "std")]
 pub use runtime_primitives::BuildStorage;
 
 pub use consensus::Call as ConsensusCall;
+pub use session::Call as SessionCall;
+pub use staking::Call as StakingCall;
 pub use timestamp::Call as TimestampCall;
 pub use parachains::Call as ParachainsCall;
+pub use heartbeats::Call as HeartbeatsCall;
+pub use slashing::Call as SlashingCall;
 pub use primitives::Header;
 
 /// The position of the timestamp set extrinsic.
 pub const TIMESTAMP_SET_POSITION: u32 = 0;
 /// The position of the parachains set extrinsic.
 pub const PARACHAINS_SET_POSITION: u32 = 1;
+/// The position of the heartbeats note extrinsic, when there is one.
+pub const HEARTBEATS_SET_POSITION: u32 = 2;
+/// The total encoded length of the extrinsics a block may hold. Leaves room for the
+/// transactions the proposer includes as well as the inherent extrinsics.
+pub const MAX_BLOCK_WEIGHT: u32 = 8 * 1024 * 1024;
 
 /// The address format for describing accounts.
 pub type Address = staking::Address<Concrete>;
@@ -110,10 +125,22 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: ver_str!("polkadot"),
 	impl_name: ver_str!("parity-polkadot"),
 	authoring_version: 0,
-	spec_version: 0,
+	spec_version: 1,
 	impl_version: 0,
 };
 
+/// How this runtime was built. See `build.rs`.
+pub const BUILD_INFO: BuildInfo = BuildInfo {
+	rustc_version: ver_str!(env!("RUNTIME_BUILD_RUSTC_VERSION")),
+	features: ver_str!(env!("RUNTIME_BUILD_FEATURES")),
+	source_hash: ver_str!(env!("RUNTIME_BUILD_SOURCE_HASH")),
+};
+
+// `BUILD_INFO` again, where it can be read from the Wasm runtime without
+// executing it.
+#[cfg(target_arch = "wasm32")]
+include!(concat!(env!("OUT_DIR"), "/build_info_section.rs"));
+
 impl version::Trait for Concrete {
 	const VERSION: RuntimeVersion = VERSION;
 }
@@ -121,11 +148,15 @@ impl version::Trait for Concrete {
 /// Version module for this concrete runtime.
 pub type Version = version::Module<Concrete>;
 
+/// Storage migrations of the modules, run once the spec version is bumped.
+pub type RuntimeUpgrade = version::Upgrade<Concrete, ()>;
+
 impl HasPublicAux for Concrete {
 	type PublicAux = AccountId;	// TODO: Option<AccountId>
 }
 
 impl system::Trait for Concrete {
+	const MAX_BLOCK_WEIGHT: u32 = MAX_BLOCK_WEIGHT;
 	type Index = Index;
 	type BlockNumber = BlockNumber;
 	type Hash = Hash;
@@ -133,6 +164,7 @@ impl system::Trait for Concrete {
 	type Digest = generic::Digest<Log>;
 	type AccountId = AccountId;
 	type Header = Header;
+	type Event = Event;
 }
 /// System module for this concrete runtime.
 pub type System = system::Module<Concrete>;
@@ -140,6 +172,7 @@ pub type System = system::Module<Concrete>;
 impl consensus::Trait for Concrete {
 	type PublicAux = <Concrete as HasPublicAux>::PublicAux;
 	type SessionKey = SessionKey;
+	type OnMisbehaviorReported = Slashing;
 }
 /// Consensus module for this concrete runtime.
 pub type Consensus = consensus::Module<Concrete>;
@@ -161,7 +194,7 @@ impl Convert<AccountId, SessionKey> for SessionKeyConversion {
 
 impl session::Trait for Concrete {
 	type ConvertAccountIdToSessionKey = SessionKeyConversion;
-	type OnSessionChange = Staking;
+	type OnSessionChange = (Heartbeats, Staking);
 }
 /// Session module for this concrete runtime.
 pub type Session = session::Module<Concrete>;
@@ -170,6 +203,7 @@ impl staking::Trait for Concrete {
 	type Balance = Balance;
 	type DetermineContractAddress = BlakeTwo256;
 	type AccountIndex = AccountIndex;
+	type Event = Event;
 }
 /// Staking module for this concrete runtime.
 pub type Staking = staking::Module<Concrete>;
@@ -190,9 +224,25 @@ impl parachains::Trait for Concrete {
 	const SET_POSITION: u32 = PARACHAINS_SET_POSITION;
 
 	type PublicAux = <Concrete as HasPublicAux>::PublicAux;
+	type Event = Event;
 }
 pub type Parachains = parachains::Module<Concrete>;
 
+impl heartbeats::Trait for Concrete {
+	const SET_POSITION: u32 = HEARTBEATS_SET_POSITION;
+
+	type PublicAux = <Concrete as HasPublicAux>::PublicAux;
+}
+/// Heartbeats module for this concrete runtime.
+pub type Heartbeats = heartbeats::Module<Concrete>;
+
+impl slashing::Trait for Concrete {
+	type PublicAux = <Concrete as HasPublicAux>::PublicAux;
+	type Event = Event;
+}
+/// Slashing module for this concrete runtime.
+pub type Slashing = slashing::Module<Concrete>;
+
 impl_outer_dispatch! {
 	/// Call type for polkadot transactions.
 	#[derive(Clone, PartialEq, Eq)]
@@ -206,6 +256,8 @@ impl_outer_dispatch! {
 		Council = 6,
 		CouncilVoting = 7,
 		Parachains = 8,
+		Heartbeats = 9,
+		Slashing = 10,
 	}
 
 	/// Internal calls.
@@ -218,12 +270,28 @@ impl_outer_dispatch! {
 		Democracy = 5,
 		Council = 6,
 		CouncilVoting = 7,
+		Parachains = 8,
 	}
 }
 
+impl_outer_event! {
+	/// Event type for the polkadot runtime, deposited in the system module.
+	#[derive(Clone, PartialEq, Eq)]
+	#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+	pub enum Event for Concrete {
+		staking = 2,
+		parachains = 8,
+		slashing = 10,
+	}
+}
+
+/// An event deposited in a block, with the phase of the block's execution it was deposited in.
+pub type EventRecord = system::EventRecord<Event>;
+
 /// Executive: handles dispatch to the various modules.
 pub type Executive = executive::Executive<Concrete, Block, Staking, Staking,
-	(((((((), Parachains), Council), Democracy), Staking), Session), Timestamp)>;
+	(((((((), Parachains), Council), Democracy), Staking), Session), Timestamp),
+	RuntimeUpgrade>;
 
 impl_outer_config! {
 	pub struct GenesisConfig for Concrete {
@@ -235,20 +303,26 @@ impl_outer_config! {
 		CouncilConfig => council,
 		TimestampConfig => timestamp,
 		ParachainsConfig => parachains,
+		HeartbeatsConfig => heartbeats,
+		SlashingConfig => slashing,
 	}
 }
 
 pub mod api {
 	impl_stubs!(
 		version => |()| super::Version::version(),
+		build_info => |()| super::BUILD_INFO,
 		authorities => |()| super::Consensus::authorities(),
 		initialise_block => |header| super::Executive::initialise_block(&header),
 		apply_extrinsic => |extrinsic| super::Executive::apply_extrinsic(extrinsic),
 		execute_block => |block| super::Executive::execute_block(block),
 		finalise_block => |()| super::Executive::finalise_block(),
-		inherent_extrinsics => |(timestamp, heads)| super::inherent_extrinsics(timestamp, heads),
+		runtime_upgrade => |()| super::RuntimeUpgrade::apply(),
+		inherent_extrinsics => |(timestamp, heads, heartbeats)| super::inherent_extrinsics(timestamp, heads, heartbeats),
 		validator_count => |()| super::Session::validator_count(),
-		validators => |()| super::Session::validators()
+		validators => |()| super::Session::validators(),
+		events => |()| super::System::events(),
+		transaction_fee => |encoded_len: u64| super::Staking::transaction_fee(encoded_len as usize)
 	);
 }
 
@@ -395,4 +469,30 @@ mod tests {
 		let v = Slicable::encode(&xt);
 		assert_eq!(Extrinsic::decode(&mut &v[..]).unwrap(), xt);
 	}
+
+	#[test]
+	fn serialize_event_record() {
+		let record = system::EventRecord {
+			phase: system::Phase::ApplyExtrinsic(1),
+			event: Event::from(staking::RawEvent::Transfer(AccountId::from([1; 32]), AccountId::from([2; 32]), 69)),
+		};
+
+		// 00 01000000
+		// 02 00
+		// 0101010101010101010101010101010101010101010101010101010101010101
+		// 0202020202020202020202020202020202020202020202020202020202020202
+		// 45000000000000000000000000000000
+
+		let v = Slicable::encode(&record);
+		assert_eq!(&v[..], &hex!["000100000002000101010101010101010101010101010101010101010101010101010101010101020202020202020202020202020202020202020202020202020202020202020245000000000000000000000000000000"][..]);
+		assert_eq!(system::EventRecord::<Event>::decode(&mut &v[..]).unwrap(), record);
+
+		let record = system::EventRecord {
+			phase: system::Phase::Finalization,
+			event: Event::from(parachains::RawEvent::Registered(5u32.into())),
+		};
+		let v = Slicable::encode(&record);
+		assert_eq!(&v[..], &hex!["01080005000000"][..]);
+		assert_eq!(system::EventRecord::<Event>::decode(&mut &v[..]).unwrap(), record);
+	}
 }
@@ -0,0 +1,155 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Test utilities
+
+#![cfg(test)]
+
+use rstd::marker::PhantomData;
+use primitives::SessionKey;
+use primitives::parachain::Id;
+use runtime_io;
+use runtime_primitives::BuildStorage;
+use runtime_primitives::traits::{HasPublicAux, Convert, BlakeTwo256};
+use runtime_primitives::testing::{Digest, Header};
+use substrate_keyring::Keyring;
+use substrate_primitives::H256;
+use {consensus, heartbeats, parachains, session, slashing, staking, system, timestamp};
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+impl HasPublicAux for Test {
+	type PublicAux = u64;
+}
+impl consensus::Trait for Test {
+	type PublicAux = <Self as HasPublicAux>::PublicAux;
+	type SessionKey = SessionKey;
+	type OnMisbehaviorReported = Slashing;
+}
+impl system::Trait for Test {
+	const MAX_BLOCK_WEIGHT: u32 = 1024 * 1024;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type Digest = Digest;
+	type AccountId = u64;
+	type Header = Header;
+	type Event = ();
+}
+pub struct KeyFor;
+impl Convert<u64, SessionKey> for KeyFor {
+	fn convert(a: u64) -> SessionKey {
+		authority_for(a)
+	}
+}
+impl session::Trait for Test {
+	type ConvertAccountIdToSessionKey = KeyFor;
+	type OnSessionChange = Heartbeats;
+}
+impl timestamp::Trait for Test {
+	const TIMESTAMP_SET_POSITION: u32 = 0;
+	type Moment = u64;
+}
+impl staking::Trait for Test {
+	type Balance = u64;
+	type DetermineContractAddress = staking::DummyContractAddressFor;
+	type AccountIndex = u64;
+	type Event = ();
+}
+impl parachains::Trait for Test {
+	const SET_POSITION: u32 = 0;
+
+	type PublicAux = <Self as HasPublicAux>::PublicAux;
+	type Event = ();
+}
+impl heartbeats::Trait for Test {
+	const SET_POSITION: u32 = 0;
+
+	type PublicAux = <Self as HasPublicAux>::PublicAux;
+}
+impl slashing::Trait for Test {
+	type PublicAux = <Self as HasPublicAux>::PublicAux;
+	type Event = ();
+}
+
+pub type Session = session::Module<Test>;
+pub type Staking = staking::Module<Test>;
+pub type Parachains = parachains::Module<Test>;
+pub type Heartbeats = heartbeats::Module<Test>;
+pub type Slashing = slashing::Module<Test>;
+
+pub fn keyring_for(validator: u64) -> Keyring {
+	match validator {
+		1 => Keyring::Alice,
+		2 => Keyring::Bob,
+		_ => Keyring::Charlie,
+	}
+}
+
+pub fn authority_for(validator: u64) -> SessionKey {
+	keyring_for(validator).to_raw_public().into()
+}
+
+pub fn new_test_ext(validators: Vec<u64>, balances: Vec<(u64, u64)>, parachains: Vec<(Id, Vec<u8>)>) -> runtime_io::TestExternalities {
+	let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	t.extend(consensus::GenesisConfig::<Test>{
+		code: vec![],
+		authorities: validators.iter().map(|&v| authority_for(v)).collect(),
+	}.build_storage().unwrap());
+	t.extend(timestamp::GenesisConfig::<Test>::default().build_storage().unwrap());
+	t.extend(session::GenesisConfig::<Test>{
+		session_length: 1000,
+		validators,
+		broken_percent_late: 100,
+	}.build_storage().unwrap());
+	t.extend(staking::GenesisConfig::<Test>{
+		sessions_per_era: 1,
+		current_era: 0,
+		balances,
+		intentions: vec![],
+		validator_count: 2,
+		bonding_duration: 3,
+		transaction_base_fee: 0,
+		transaction_byte_fee: 0,
+		existential_deposit: 0,
+		transfer_fee: 0,
+		creation_fee: 0,
+		contract_fee: 0,
+		reclaim_rebate: 0,
+		early_era_slash: 0,
+		session_reward: 0,
+	}.build_storage().unwrap());
+	t.extend(parachains::GenesisConfig::<Test>{
+		parachains,
+		proposal_delay: Some(6000),
+		parachain_empty_delay: None,
+		round_timeout_multiplier: Some(2),
+		max_egress_messages: Some(2),
+		max_egress_bytes: None,
+		deposit: Some(10),
+		phantom: PhantomData,
+	}.build_storage().unwrap());
+	t.extend(heartbeats::GenesisConfig::<Test>{
+		max_missed_sessions: 2,
+		phantom: PhantomData,
+	}.build_storage().unwrap());
+	t.extend(slashing::GenesisConfig::<Test>{
+		misbehavior_slash: 30,
+		phantom: PhantomData,
+	}.build_storage().unwrap());
+	t
+}
@@ -14,15 +14,20 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Main parachains logic. For now this is just the determination of which validators do what.
+//! Main parachains logic: the registered parachains, their heads and egress, and the
+//! determination of which validators do what.
+//!
+//! Parachains are registered at genesis, by an account which reserves a deposit
+//! until it deregisters the parachain, or by governance. Registration by accounts
+//! is disabled unless a deposit is configured.
 
 use primitives;
 use rstd::prelude::*;
-use codec::{Slicable, Joiner};
+use codec::{Slicable, Input, Joiner};
 
-use runtime_primitives::traits::{Executable, RefInto, MaybeEmpty};
-use primitives::parachain::{Id, Chain, DutyRoster, CandidateReceipt};
-use {system, session};
+use runtime_primitives::traits::{Executable, RefInto, MaybeEmpty, Zero};
+use primitives::parachain::{Id, Chain, DutyRoster, CandidateReceipt, EgressLimits, EgressRoot};
+use {system, session, staking};
 
 use substrate_runtime_support::{Hashable, StorageValue, StorageMap};
 use substrate_runtime_support::dispatch::Result;
@@ -30,14 +35,71 @@ use substrate_runtime_support::dispatch::Result;
 #[cfg(any(feature = "std", test))]
 use rstd::marker::PhantomData;
 
+/// Maximum size in bytes of the validation code of a parachain registered by an account.
+pub const MAX_CODE_SIZE: usize = 1024 * 1024;
+/// Maximum size in bytes of the initial head data of a parachain registered by an account.
+pub const MAX_HEAD_DATA_SIZE: usize = 32 * 1024;
+
 #[cfg(any(feature = "std", test))]
 use {runtime_io, runtime_primitives};
 
-pub trait Trait: session::Trait<Hash = primitives::Hash> {
+pub trait Trait: session::Trait<Hash = primitives::Hash, BlockNumber = primitives::BlockNumber> + staking::Trait {
 	/// The position of the set_heads call in the block.
 	const SET_POSITION: u32;
 
 	type PublicAux: RefInto<Self::AccountId> + MaybeEmpty;
+	/// The overarching event type, which the events of this module convert into.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+/// An event of this module.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+pub enum RawEvent<Hash> {
+	/// A parachain was registered.
+	Registered(Id),
+	/// A parachain was deregistered.
+	Deregistered(Id),
+	/// A candidate of a parachain was included: the parachain and the hash of the candidate.
+	Included(Id, Hash),
+}
+
+/// The event of this module for a runtime.
+pub type Event<T> = RawEvent<<T as system::Trait>::Hash>;
+
+impl<Hash> From<RawEvent<Hash>> for () {
+	fn from(_: RawEvent<Hash>) {}
+}
+
+impl<Hash: Slicable> Slicable for RawEvent<Hash> {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		match *self {
+			RawEvent::Registered(ref id) => {
+				v.push(0);
+				id.using_encoded(|s| v.extend(s));
+			}
+			RawEvent::Deregistered(ref id) => {
+				v.push(1);
+				id.using_encoded(|s| v.extend(s));
+			}
+			RawEvent::Included(ref id, ref hash) => {
+				v.push(2);
+				id.using_encoded(|s| v.extend(s));
+				hash.using_encoded(|s| v.extend(s));
+			}
+		}
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(match input.read_byte()? {
+			0 => RawEvent::Registered(Slicable::decode(input)?),
+			1 => RawEvent::Deregistered(Slicable::decode(input)?),
+			2 => RawEvent::Included(Slicable::decode(input)?, Slicable::decode(input)?),
+			_ => return None,
+		})
+	}
 }
 
 decl_module! {
@@ -49,6 +111,17 @@ decl_module! {
 	pub enum Call where aux: <T as Trait>::PublicAux {
 		// provide candidate receipts for parachains, in ascending order by id.
 		fn set_heads(aux, heads: Vec<CandidateReceipt>) -> Result = 0;
+		// register a parachain, reserving the deposit from the sender.
+		fn register(aux, id: Id, code: Vec<u8>, initial_head_data: Vec<u8>) -> Result = 1;
+		// deregister a parachain registered by the sender, returning the deposit.
+		fn deregister(aux, id: Id) -> Result = 2;
+	}
+
+	/// Privileged call type for parachains.
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub enum PrivCall {
+		fn force_register(id: Id, code: Vec<u8>, initial_head_data: Vec<u8>) -> Result = 0;
+		fn force_deregister(id: Id) -> Result = 1;
 	}
 }
 
@@ -60,6 +133,26 @@ decl_storage! {
 	pub Code get(parachain_code): b"para:code" => map [ Id => Vec<u8> ];
 	// The heads of the parachains registered at present. these are kept sorted.
 	pub Heads get(parachain_head): b"para:head" => map [ Id => Vec<u8> ];
+	// The egress roots posted by each parachain which its destinations haven't routed,
+	// by destination in ascending order, oldest first. Roots routed in this block are kept
+	// until the next.
+	pub Egress get(egress_roots): b"para:egress" => default map [ Id => Vec<(Id, Vec<EgressRoot>)> ];
+	// The account which registered each parachain and the deposit it reserved.
+	pub Registrant get(registrant): b"para:registrant" => map [ Id => (T::AccountId, T::Balance) ];
+	// Deposit reserved from an account registering a parachain.
+	pub Deposit get(deposit): b"para:deposit" => default T::Balance;
+
+	// Milliseconds from the start of candidate agreement before proposing or voting.
+	pub ProposalDelay get(proposal_delay): b"para:proposal_delay" => u64;
+	// Milliseconds after which a proposal without any parachain candidates is acceptable.
+	pub ParachainEmptyDelay get(parachain_empty_delay): b"para:empty_delay" => u64;
+	// Multiplier, in seconds, of the exponentially-increasing BFT round timeout.
+	pub RoundTimeoutMultiplier get(round_timeout_multiplier): b"para:round_timeout" => u64;
+
+	// Maximum number of messages a candidate may post to a single egress queue in a block.
+	pub MaxEgressMessages get(max_egress_messages): b"para:max_egress_messages" => u32;
+	// Maximum number of bytes a candidate may post to a single egress queue in a block.
+	pub MaxEgressBytes get(max_egress_bytes): b"para:max_egress_bytes" => u32;
 
 	// Did the parachain heads get updated in this block?
 	DidUpdate: b"para:did" => default bool;
@@ -114,6 +207,14 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// The limits on egress a candidate may post to each destination in a block.
+	pub fn egress_limits() -> EgressLimits {
+		EgressLimits {
+			max_messages: Self::max_egress_messages(),
+			max_bytes: Self::max_egress_bytes(),
+		}
+	}
+
 	/// Register a parachain with given code.
 	/// Fails if given ID is already used.
 	pub fn register_parachain(id: Id, code: Vec<u8>, initial_head_data: Vec<u8>) {
@@ -126,6 +227,7 @@ impl<T: Trait> Module<T> {
 		<Code<T>>::insert(id, code);
 		<Parachains<T>>::put(parachains);
 		<Heads<T>>::insert(id, initial_head_data);
+		Self::deposit_event(RawEvent::Registered(id));
 	}
 
 	/// Deregister a parachain with given id
@@ -138,7 +240,62 @@ impl<T: Trait> Module<T> {
 
 		<Code<T>>::remove(id);
 		<Heads<T>>::remove(id);
+		<Egress<T>>::remove(id);
+
+		// nothing will route the roots posted to it by the others.
+		for source in &parachains {
+			let mut egress = Self::egress_roots(source);
+			if let Ok(idx) = egress.binary_search_by_key(&id, |&(destination, _)| destination) {
+				egress.remove(idx);
+				if egress.is_empty() {
+					<Egress<T>>::remove(source);
+				} else {
+					<Egress<T>>::insert(source, egress);
+				}
+			}
+		}
+
 		<Parachains<T>>::put(parachains);
+		Self::deposit_event(RawEvent::Deregistered(id));
+	}
+
+	fn register(aux: &<T as Trait>::PublicAux, id: Id, code: Vec<u8>, initial_head_data: Vec<u8>) -> Result {
+		ensure!(Self::active_parachains().binary_search(&id).is_err(), "Parachain ID already in use");
+		ensure!(code.len() <= MAX_CODE_SIZE, "Parachain validation code too large");
+		ensure!(initial_head_data.len() <= MAX_HEAD_DATA_SIZE, "Parachain head data too large");
+
+		let deposit = Self::deposit();
+		ensure!(!deposit.is_zero(), "Parachain registration by accounts is disabled");
+		<staking::Module<T>>::reserve(aux.ref_into(), deposit)
+			.map_err(|_| "registrant's balance too low")?;
+		<Registrant<T>>::insert(id, (aux.ref_into().clone(), deposit));
+
+		Self::register_parachain(id, code, initial_head_data);
+		Ok(())
+	}
+
+	fn deregister(aux: &<T as Trait>::PublicAux, id: Id) -> Result {
+		let registrant = Self::registrant(&id).ok_or("Parachain wasn't registered by an account")?;
+		ensure!(&registrant.0 == aux.ref_into(), "Only the registrant may deregister a parachain");
+
+		Self::force_deregister(id)
+	}
+
+	fn force_register(id: Id, code: Vec<u8>, initial_head_data: Vec<u8>) -> Result {
+		ensure!(Self::active_parachains().binary_search(&id).is_err(), "Parachain ID already in use");
+
+		Self::register_parachain(id, code, initial_head_data);
+		Ok(())
+	}
+
+	fn force_deregister(id: Id) -> Result {
+		ensure!(Self::active_parachains().binary_search(&id).is_ok(), "No parachain with this ID");
+
+		if let Some((registrant, deposit)) = <Registrant<T>>::take(id) {
+			<staking::Module<T>>::unreserve(&registrant, deposit);
+		}
+		Self::deregister_parachain(id);
+		Ok(())
 	}
 
 	fn set_heads(aux: &<T as Trait>::PublicAux, heads: Vec<CandidateReceipt>) -> Result {
@@ -151,26 +308,102 @@ impl<T: Trait> Module<T> {
 		);
 
 		let active_parachains = Self::active_parachains();
+		let egress_limits = Self::egress_limits();
 		let mut iter = active_parachains.iter();
 
-		// perform this check before writing to storage.
+		// perform these checks before writing to storage.
 		for head in &heads {
 			ensure!(
 				iter.find(|&p| p == &head.parachain_index).is_some(),
 				"Submitted candidate for unregistered or out-of-order parachain {}"
 //				, head.parachain_index.into_inner()
 			);
+
+			// destinations are strictly ascending so egress can't be split
+			// across several entries to evade the limits.
+			ensure!(
+				head.commitments.egress_sizes.windows(2).all(|w| w[0].0 < w[1].0),
+				"Submitted candidate with duplicate or out-of-order egress destinations"
+			);
+			ensure!(
+				head.commitments.egress_sizes.iter().all(|&(_, ref size)| egress_limits.admits(size)),
+				"Submitted candidate exceeds egress limits"
+			);
+		}
+
+		let now = <system::Module<T>>::block_number();
+		let routed: Vec<_> = heads.iter().map(|head| head.parachain_index).collect();
+		for source in &active_parachains {
+			let posted = heads.iter()
+				.find(|head| &head.parachain_index == source)
+				.map_or(&[][..], |head| &head.commitments.egress_queue_roots[..]);
+
+			Self::update_egress(*source, &routed, posted, &active_parachains, now);
 		}
 
 		for head in heads {
 			let id = head.parachain_index.clone();
+			let hash: primitives::Hash = head.blake2_256().into();
 			<Heads<T>>::insert(id, head.head_data.0);
+			Self::deposit_event(RawEvent::Included(id, hash));
 		}
 
 		<DidUpdate<T>>::put(true);
 
 		Ok(())
 	}
+
+	// mark the egress of a parachain to the destinations which included a candidate,
+	// `routed`, as consumed and add the roots it posted in this block to `active` ones.
+	fn update_egress(
+		source: Id,
+		routed: &[Id],
+		posted: &[(Id, primitives::Hash)],
+		active: &[Id],
+		now: primitives::BlockNumber,
+	) {
+		let mut egress = Self::egress_roots(&source);
+		if egress.is_empty() && posted.is_empty() {
+			return;
+		}
+
+		for &mut (ref destination, ref mut roots) in egress.iter_mut() {
+			roots.retain(|root| !root.consumed);
+			if routed.contains(destination) {
+				for root in roots.iter_mut() {
+					root.consumed = true;
+				}
+			}
+		}
+
+		for &(destination, root) in posted {
+			if active.binary_search(&destination).is_err() {
+				continue;
+			}
+
+			let root = EgressRoot { root, posted_at: now, consumed: false };
+			match egress.binary_search_by_key(&destination, |&(id, _)| id) {
+				Ok(idx) => egress[idx].1.push(root),
+				Err(idx) => {
+					let mut roots = Vec::new();
+					roots.push(root);
+					egress.insert(idx, (destination, roots));
+				}
+			}
+		}
+
+		egress.retain(|&(_, ref roots)| !roots.is_empty());
+		if egress.is_empty() {
+			<Egress<T>>::remove(source);
+		} else {
+			<Egress<T>>::insert(source, egress);
+		}
+	}
+
+	/// Deposit one of this module's events.
+	fn deposit_event(event: Event<T>) {
+		<system::Module<T>>::deposit_event(<T as Trait>::Event::from(event).into());
+	}
 }
 
 impl<T: Trait> Executable for Module<T> {
@@ -187,6 +420,29 @@ impl<T: Trait> Executable for Module<T> {
 pub struct GenesisConfig<T: Trait> {
 	/// The initial parachains, mapped to code.
 	pub parachains: Vec<(Id, Vec<u8>)>,
+	/// Milliseconds from the start of candidate agreement before proposing or voting.
+	/// Nodes fall back to their own default when unset.
+	#[serde(default)]
+	pub proposal_delay: Option<u64>,
+	/// Milliseconds after which a proposal without any parachain candidates is acceptable.
+	/// Nodes fall back to their own default when unset.
+	#[serde(default)]
+	pub parachain_empty_delay: Option<u64>,
+	/// Multiplier, in seconds, of the exponentially-increasing BFT round timeout.
+	/// Nodes fall back to their own default when unset.
+	#[serde(default)]
+	pub round_timeout_multiplier: Option<u64>,
+	/// Maximum number of messages a candidate may post to a single egress queue in a block.
+	/// Unlimited when unset.
+	#[serde(default)]
+	pub max_egress_messages: Option<u32>,
+	/// Maximum number of bytes a candidate may post to a single egress queue in a block.
+	/// Unlimited when unset.
+	#[serde(default)]
+	pub max_egress_bytes: Option<u32>,
+	/// Deposit reserved from an account registering a parachain. Zero when unset.
+	#[serde(default)]
+	pub deposit: Option<T::Balance>,
 	/// Phantom data.
 	#[serde(skip)]
 	pub phantom: PhantomData<T>,
@@ -197,6 +453,12 @@ impl<T: Trait> Default for GenesisConfig<T> {
 	fn default() -> Self {
 		GenesisConfig {
 			parachains: Vec::new(),
+			proposal_delay: None,
+			parachain_empty_delay: None,
+			round_timeout_multiplier: None,
+			max_egress_messages: None,
+			max_egress_bytes: None,
+			deposit: None,
 			phantom: PhantomData,
 		}
 	}
@@ -224,6 +486,30 @@ impl<T: Trait> runtime_primitives::BuildStorage for GenesisConfig<T>
 			map.insert(key, code.encode());
 		}
 
+		if let Some(delay) = self.proposal_delay {
+			map.insert(twox_128(<ProposalDelay<T>>::key()).to_vec(), delay.encode());
+		}
+
+		if let Some(delay) = self.parachain_empty_delay {
+			map.insert(twox_128(<ParachainEmptyDelay<T>>::key()).to_vec(), delay.encode());
+		}
+
+		if let Some(multiplier) = self.round_timeout_multiplier {
+			map.insert(twox_128(<RoundTimeoutMultiplier<T>>::key()).to_vec(), multiplier.encode());
+		}
+
+		if let Some(max) = self.max_egress_messages {
+			map.insert(twox_128(<MaxEgressMessages<T>>::key()).to_vec(), max.encode());
+		}
+
+		if let Some(max) = self.max_egress_bytes {
+			map.insert(twox_128(<MaxEgressBytes<T>>::key()).to_vec(), max.encode());
+		}
+
+		if let Some(deposit) = self.deposit {
+			map.insert(twox_128(<Deposit<T>>::key()).to_vec(), deposit.encode());
+		}
+
 		Ok(map.into())
 	}
 }
@@ -232,62 +518,10 @@ impl<T: Trait> runtime_primitives::BuildStorage for GenesisConfig<T>
 mod tests {
 	use super::*;
 	use runtime_io::with_externalities;
-	use substrate_primitives::H256;
-	use runtime_primitives::BuildStorage;
-	use runtime_primitives::traits::{HasPublicAux, Identity, BlakeTwo256};
-	use runtime_primitives::testing::{Digest, Header};
-	use {consensus, timestamp};
-
-	#[derive(Clone, Eq, PartialEq)]
-	pub struct Test;
-	impl HasPublicAux for Test {
-		type PublicAux = u64;
-	}
-	impl consensus::Trait for Test {
-		type PublicAux = <Self as HasPublicAux>::PublicAux;
-		type SessionKey = u64;
-	}
-	impl system::Trait for Test {
-		type Index = u64;
-		type BlockNumber = u64;
-		type Hash = H256;
-		type Hashing = BlakeTwo256;
-		type Digest = Digest;
-		type AccountId = u64;
-		type Header = Header;
-	}
-	impl session::Trait for Test {
-		type ConvertAccountIdToSessionKey = Identity;
-		type OnSessionChange = ();
-	}
-	impl timestamp::Trait for Test {
-		const TIMESTAMP_SET_POSITION: u32 = 0;
-		type Moment = u64;
-	}
-	impl Trait for Test {
-		const SET_POSITION: u32 = 0;
-
-		type PublicAux = <Self as HasPublicAux>::PublicAux;
-	}
-
-	type Parachains = Module<Test>;
+	use mock::{Test, Parachains, Staking};
 
 	fn new_test_ext(parachains: Vec<(Id, Vec<u8>)>) -> runtime_io::TestExternalities {
-		let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap();
-		t.extend(consensus::GenesisConfig::<Test>{
-			code: vec![],
-			authorities: vec![1, 2, 3],
-		}.build_storage().unwrap());
-		t.extend(session::GenesisConfig::<Test>{
-			session_length: 1000,
-			validators: vec![1, 2, 3, 4, 5, 6, 7, 8],
-			broken_percent_late: 100,
-		}.build_storage().unwrap());
-		t.extend(GenesisConfig::<Test>{
-			parachains: parachains,
-			phantom: PhantomData,
-		}.build_storage().unwrap());
-		t
+		::mock::new_test_ext(vec![1, 2, 3, 4, 5, 6, 7, 8], vec![(1, 100), (2, 5)], parachains)
 	}
 
 	#[test]
@@ -304,6 +538,152 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn agreement_timing_from_genesis() {
+		with_externalities(&mut new_test_ext(vec![]), || {
+			assert_eq!(Parachains::proposal_delay(), Some(6000));
+			assert_eq!(Parachains::parachain_empty_delay(), None);
+			assert_eq!(Parachains::round_timeout_multiplier(), Some(2));
+		});
+	}
+
+	#[test]
+	fn set_heads_enforces_egress_limits() {
+		use primitives::parachain::{CandidateCommitments, EgressSize, HeadData};
+
+		let parachains = vec![
+			(5u32.into(), vec![1,2,3]),
+		];
+
+		let candidate = |egress_sizes| CandidateReceipt {
+			parachain_index: 5.into(),
+			collator: Default::default(),
+			head_data: HeadData(vec![4, 5, 6]),
+			balance_uploads: vec![],
+			commitments: CandidateCommitments {
+				egress_sizes,
+				..Default::default()
+			},
+		};
+
+		let size = |messages| EgressSize { messages, bytes: 1000 };
+
+		with_externalities(&mut new_test_ext(parachains), || {
+			assert_eq!(Parachains::egress_limits(), EgressLimits { max_messages: Some(2), max_bytes: None });
+
+			let over_limit = vec![(1.into(), size(3))];
+			assert!(Parachains::set_heads(&0, vec![candidate(over_limit)]).is_err());
+
+			let split = vec![(1.into(), size(2)), (1.into(), size(1))];
+			assert!(Parachains::set_heads(&0, vec![candidate(split)]).is_err());
+			assert_eq!(Parachains::parachain_head(&5u32.into()), None);
+
+			let within_limit = vec![(1.into(), size(2)), (2.into(), size(2))];
+			assert!(Parachains::set_heads(&0, vec![candidate(within_limit)]).is_ok());
+			assert_eq!(Parachains::parachain_head(&5u32.into()), Some(vec![4, 5, 6]));
+		});
+	}
+
+	#[test]
+	fn egress_roots_are_kept_until_routed() {
+		use primitives::parachain::{CandidateCommitments, HeadData};
+
+		let parachains = vec![
+			(1u32.into(), vec![]),
+			(2u32.into(), vec![]),
+			(3u32.into(), vec![]),
+		];
+
+		let candidate = |id: u32, egress_queue_roots| CandidateReceipt {
+			parachain_index: id.into(),
+			collator: Default::default(),
+			head_data: HeadData(vec![id as u8]),
+			balance_uploads: vec![],
+			commitments: CandidateCommitments {
+				egress_queue_roots,
+				..Default::default()
+			},
+		};
+
+		let root = |posted_at, consumed| EgressRoot { root: [posted_at as u8; 32].into(), posted_at, consumed };
+
+		let set_heads_at = |number, heads| {
+			system::Module::<Test>::set_block_number(number);
+			assert!(Parachains::set_heads(&0, heads).is_ok());
+			Parachains::execute();
+		};
+
+		with_externalities(&mut new_test_ext(parachains), || {
+			set_heads_at(1, vec![candidate(1, vec![(3.into(), [1; 32].into()), (2.into(), [1; 32].into())])]);
+			assert_eq!(Parachains::egress_roots(&1u32.into()), vec![
+				(2.into(), vec![root(1, false)]),
+				(3.into(), vec![root(1, false)]),
+			]);
+
+			// 2 routes what was posted before, but not what's posted alongside it.
+			set_heads_at(2, vec![
+				candidate(1, vec![(2.into(), [2; 32].into())]),
+				candidate(2, vec![]),
+			]);
+			assert_eq!(Parachains::egress_roots(&1u32.into()), vec![
+				(2.into(), vec![root(1, true), root(2, false)]),
+				(3.into(), vec![root(1, false)]),
+			]);
+
+			set_heads_at(3, vec![candidate(3, vec![])]);
+			assert_eq!(Parachains::egress_roots(&1u32.into()), vec![
+				(2.into(), vec![root(2, false)]),
+				(3.into(), vec![root(1, true)]),
+			]);
+
+			set_heads_at(4, vec![candidate(2, vec![])]);
+			set_heads_at(5, vec![]);
+			assert!(Parachains::egress_roots(&1u32.into()).is_empty());
+		});
+	}
+
+	#[test]
+	fn egress_roots_to_deregistered_parachain_are_removed() {
+		use primitives::parachain::{CandidateCommitments, HeadData};
+
+		let parachains = vec![
+			(1u32.into(), vec![]),
+			(2u32.into(), vec![]),
+			(3u32.into(), vec![]),
+		];
+
+		let candidate = |id: u32, egress_queue_roots| CandidateReceipt {
+			parachain_index: id.into(),
+			collator: Default::default(),
+			head_data: HeadData(vec![id as u8]),
+			balance_uploads: vec![],
+			commitments: CandidateCommitments {
+				egress_queue_roots,
+				..Default::default()
+			},
+		};
+
+		with_externalities(&mut new_test_ext(parachains), || {
+			system::Module::<Test>::set_block_number(1);
+			assert!(Parachains::set_heads(&0, vec![
+				candidate(1, vec![(3.into(), [1; 32].into())]),
+				candidate(2, vec![(1.into(), [2; 32].into()), (3.into(), [2; 32].into())]),
+			]).is_ok());
+			Parachains::execute();
+
+			Parachains::deregister_parachain(3u32.into());
+			assert!(Parachains::egress_roots(&1u32.into()).is_empty());
+			assert_eq!(Parachains::egress_roots(&2u32.into()), vec![
+				(1.into(), vec![EgressRoot { root: [2; 32].into(), posted_at: 1, consumed: false }]),
+			]);
+
+			// roots can't be posted to it any more.
+			system::Module::<Test>::set_block_number(2);
+			assert!(Parachains::set_heads(&0, vec![candidate(1, vec![(3.into(), [3; 32].into())])]).is_ok());
+			assert!(Parachains::egress_roots(&1u32.into()).is_empty());
+		});
+	}
+
 	#[test]
 	fn register_deregister() {
 		let parachains = vec![
@@ -329,6 +709,54 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn registration_reserves_a_deposit() {
+		let parachains = vec![
+			(5u32.into(), vec![1,2,3]),
+		];
+
+		with_externalities(&mut new_test_ext(parachains), || {
+			assert!(Parachains::register(&1, 5u32.into(), vec![], vec![]).is_err());
+			assert!(Parachains::register(&2, 6u32.into(), vec![], vec![]).is_err());
+			assert_noop!(
+				Parachains::register(&1, 6u32.into(), vec![0; MAX_CODE_SIZE + 1], vec![]),
+				"Parachain validation code too large"
+			);
+			assert_noop!(
+				Parachains::register(&1, 6u32.into(), vec![], vec![0; MAX_HEAD_DATA_SIZE + 1]),
+				"Parachain head data too large"
+			);
+
+			assert_eq!(Parachains::register(&1, 6u32.into(), vec![7,8,9], vec![1]), Ok(()));
+			assert_eq!(Parachains::active_parachains(), vec![5u32.into(), 6u32.into()]);
+			assert_eq!(Parachains::parachain_head(&6u32.into()), Some(vec![1]));
+			assert_eq!(Parachains::registrant(&6u32.into()), Some((1, 10)));
+			assert_eq!(Staking::free_balance(&1), 90);
+			assert_eq!(Staking::reserved_balance(&1), 10);
+
+			// only the registrant may deregister, and genesis parachains need governance.
+			assert!(Parachains::deregister(&2, 6u32.into()).is_err());
+			assert!(Parachains::deregister(&1, 5u32.into()).is_err());
+
+			assert_eq!(Parachains::deregister(&1, 6u32.into()), Ok(()));
+			assert_eq!(Parachains::active_parachains(), vec![5u32.into()]);
+			assert_eq!(Parachains::registrant(&6u32.into()), None);
+			assert_eq!(Staking::free_balance(&1), 100);
+			assert_eq!(Staking::reserved_balance(&1), 0);
+
+			assert_eq!(Parachains::force_deregister(5u32.into()), Ok(()));
+			assert!(Parachains::force_deregister(5u32.into()).is_err());
+			assert_eq!(Parachains::force_register(7u32.into(), vec![], vec![]), Ok(()));
+			assert_eq!(Parachains::active_parachains(), vec![7u32.into()]);
+
+			<Deposit<Test>>::put(0);
+			assert_noop!(
+				Parachains::register(&1, 8u32.into(), vec![], vec![]),
+				"Parachain registration by accounts is disabled"
+			);
+		});
+	}
+
 	#[test]
 	fn duty_roster_works() {
 		let parachains = vec![
@@ -0,0 +1,318 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Slashing of validators for misbehavior during agreement on parachain candidates.
+//!
+//! Anyone may report statements of a validator which contradict each other: a
+//! candidate issued and voted invalid, a candidate voted both valid and invalid,
+//! or two candidates issued on top of the same relay chain parent. The signatures
+//! are checked again here, and the validator owning the session key which made
+//! them is slashed once per offence.
+//!
+//! Misbehavior during block agreement proven to the consensus module is slashed
+//! in the same way.
+//!
+//! Statements made without authority can't be proven here: the duty roster of a
+//! past relay chain parent depends on its random seed, which isn't kept.
+
+use rstd::prelude::*;
+use primitives::SessionKey;
+use primitives::parachain::{AgreementMisbehavior, AgreementMisbehaviorReport, CandidateSignature, Statement};
+use runtime_primitives::traits::{RefInto, MaybeEmpty};
+use {runtime_io, consensus, session, staking, system};
+use codec::{Slicable, Input};
+
+use substrate_runtime_support::{Hashable, StorageMap};
+use substrate_runtime_support::dispatch::Result;
+
+#[cfg(any(feature = "std", test))]
+use rstd::marker::PhantomData;
+
+#[cfg(any(feature = "std", test))]
+use runtime_primitives;
+
+pub trait Trait: session::Trait<SessionKey = SessionKey> + staking::Trait {
+	type PublicAux: RefInto<Self::AccountId> + MaybeEmpty;
+	/// The overarching event type, which the events of this module convert into.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+/// An event of this module.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+pub enum RawEvent<AccountId, Balance> {
+	/// A validator was slashed for misbehavior: the validator and the balance slashed.
+	Slashed(AccountId, Balance),
+}
+
+/// The event of this module for a runtime.
+pub type Event<T> = RawEvent<<T as system::Trait>::AccountId, <T as staking::Trait>::Balance>;
+
+impl<AccountId, Balance> From<RawEvent<AccountId, Balance>> for () {
+	fn from(_: RawEvent<AccountId, Balance>) {}
+}
+
+impl<AccountId: Slicable, Balance: Slicable> Slicable for RawEvent<AccountId, Balance> {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		match *self {
+			RawEvent::Slashed(ref who, ref amount) => {
+				v.push(0);
+				who.using_encoded(|s| v.extend(s));
+				amount.using_encoded(|s| v.extend(s));
+			}
+		}
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => Some(RawEvent::Slashed(Slicable::decode(input)?, Slicable::decode(input)?)),
+			_ => None,
+		}
+	}
+}
+
+decl_module! {
+	/// Slashing module.
+	pub struct Module<T: Trait>;
+
+	/// Call type for slashing.
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub enum Call where aux: <T as Trait>::PublicAux {
+		// report misbehavior of a validator during candidate agreement.
+		fn report_agreement_misbehavior(aux, report: AgreementMisbehaviorReport) -> Result = 0;
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait>;
+	// Offences the offender was slashed for already.
+	pub ReportedOffences get(offence_reported): b"slash:rep:" => default map [ Vec<u8> => bool ];
+	// Balance slashed from a validator for each offence.
+	pub MisbehaviorSlash get(misbehavior_slash): b"slash:amount" => default T::Balance;
+}
+
+impl<T: Trait> Module<T> {
+	/// Whether the offence proven by a misbehavior report has already been reported.
+	pub fn is_reported(report: &AgreementMisbehaviorReport) -> bool {
+		Self::offence_reported(report.offence())
+	}
+
+	fn report_agreement_misbehavior(_aux: &<T as Trait>::PublicAux, report: AgreementMisbehaviorReport) -> Result {
+		let offence = report.offence();
+		ensure!(!Self::offence_reported(&offence), "offence already reported");
+		ensure!(Self::proves_misbehavior(&report), "misbehavior not proven");
+
+		Self::slash_validator(&report.target)?;
+		<ReportedOffences<T>>::insert(offence, true);
+
+		Ok(())
+	}
+
+	// slash the validator owning a session key for an offence.
+	fn slash_validator(key: &SessionKey) -> Result {
+		let offender = <session::Module<T>>::key_owner(key)
+			.ok_or("misbehavior of an unknown validator")?;
+
+		let amount = Self::misbehavior_slash();
+		<staking::Module<T>>::slash(&offender, amount);
+		Self::deposit_event(RawEvent::Slashed(offender, amount));
+
+		Ok(())
+	}
+
+	// whether the statements in the report were signed by its target, and contradict each other.
+	fn proves_misbehavior(report: &AgreementMisbehaviorReport) -> bool {
+		let signed = |statement: Statement, signature: &CandidateSignature| runtime_io::ed25519_verify(
+			&(signature.0).0,
+			&statement.signing_payload(&report.parent_hash),
+			&report.target.0,
+		);
+
+		match report.misbehavior {
+			AgreementMisbehavior::IssuedAndInvalidity((ref candidate, ref issued), ref invalidity) => {
+				let digest = candidate.blake2_256().into();
+				signed(Statement::Candidate(candidate.clone()), issued)
+					&& signed(Statement::Invalid(digest), invalidity)
+			}
+			AgreementMisbehavior::ValidityAndInvalidity(digest, ref validity, ref invalidity) => {
+				signed(Statement::Valid(digest), validity)
+					&& signed(Statement::Invalid(digest), invalidity)
+			}
+			AgreementMisbehavior::MultipleCandidates((ref first, ref first_sig), (ref second, ref second_sig)) => {
+				first != second
+					&& signed(Statement::Candidate(first.clone()), first_sig)
+					&& signed(Statement::Candidate(second.clone()), second_sig)
+			}
+		}
+	}
+
+	/// Deposit one of this module's events.
+	fn deposit_event(event: Event<T>) {
+		<system::Module<T>>::deposit_event(<T as Trait>::Event::from(event).into());
+	}
+}
+
+/// Slashes the owners of authorities whose misbehavior during block agreement
+/// was proven to the consensus module.
+impl<T: Trait> consensus::OnMisbehaviorReported for Module<T> {
+	fn on_misbehavior_reported(target: &SessionKey) -> Result {
+		Self::slash_validator(target)
+	}
+}
+
+/// Slashing module genesis configuration.
+#[cfg(any(feature = "std", test))]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct GenesisConfig<T: Trait> {
+	/// Balance slashed from a validator for each offence.
+	#[serde(default)]
+	pub misbehavior_slash: T::Balance,
+	/// Phantom data.
+	#[serde(skip)]
+	pub phantom: PhantomData<T>,
+}
+
+#[cfg(any(feature = "std", test))]
+impl<T: Trait> Default for GenesisConfig<T> {
+	fn default() -> Self {
+		GenesisConfig {
+			misbehavior_slash: Default::default(),
+			phantom: PhantomData,
+		}
+	}
+}
+
+#[cfg(any(feature = "std", test))]
+impl<T: Trait> runtime_primitives::BuildStorage for GenesisConfig<T>
+{
+	fn build_storage(self) -> ::std::result::Result<runtime_io::TestExternalities, String> {
+		use runtime_io::twox_128;
+		use substrate_runtime_support::StorageValue;
+
+		Ok(map![
+			twox_128(<MisbehaviorSlash<T>>::key()).to_vec() => self.misbehavior_slash.encode()
+		])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use runtime_io::with_externalities;
+	use primitives::Hash;
+	use primitives::parachain::{CandidateReceipt, HeadData};
+	use mock::{Slashing, Staking, keyring_for, authority_for};
+
+	fn sign(validator: u64, statement: Statement, parent_hash: &Hash) -> CandidateSignature {
+		keyring_for(validator).sign(&statement.signing_payload(parent_hash)).into()
+	}
+
+	fn candidate(head: u8) -> CandidateReceipt {
+		CandidateReceipt {
+			parachain_index: 1.into(),
+			collator: Default::default(),
+			head_data: HeadData(vec![head]),
+			balance_uploads: Vec::new(),
+			commitments: Default::default(),
+		}
+	}
+
+	fn multiple_candidates(validator: u64, signer: u64) -> AgreementMisbehaviorReport {
+		let parent_hash = [1; 32].into();
+		let issued = |head| (candidate(head), sign(signer, Statement::Candidate(candidate(head)), &parent_hash));
+		AgreementMisbehaviorReport {
+			parent_hash,
+			target: authority_for(validator),
+			misbehavior: AgreementMisbehavior::MultipleCandidates(issued(1), issued(2)),
+		}
+	}
+
+	fn new_test_ext() -> runtime_io::TestExternalities {
+		::mock::new_test_ext(vec![1, 2], vec![(1, 100), (2, 100)], vec![])
+	}
+
+	#[test]
+	fn proven_misbehavior_is_slashed_once() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Slashing::report_agreement_misbehavior(&0, multiple_candidates(1, 1)));
+			assert_eq!(Staking::free_balance(&1), 70);
+			assert_eq!(Staking::free_balance(&2), 100);
+
+			assert_noop!(
+				Slashing::report_agreement_misbehavior(&0, multiple_candidates(1, 1)),
+				"offence already reported"
+			);
+
+			let parent_hash = [1; 32].into();
+			let digest: Hash = candidate(1).blake2_256().into();
+			let double_vote = AgreementMisbehaviorReport {
+				parent_hash,
+				target: authority_for(2),
+				misbehavior: AgreementMisbehavior::ValidityAndInvalidity(
+					digest,
+					sign(2, Statement::Valid(digest), &parent_hash),
+					sign(2, Statement::Invalid(digest), &parent_hash),
+				),
+			};
+			assert_ok!(Slashing::report_agreement_misbehavior(&0, double_vote));
+			assert_eq!(Staking::free_balance(&2), 70);
+		});
+	}
+
+	#[test]
+	fn misbehavior_proven_to_consensus_is_slashed() {
+		use consensus::OnMisbehaviorReported;
+
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Slashing::on_misbehavior_reported(&authority_for(2)));
+			assert_eq!(Staking::free_balance(&1), 100);
+			assert_eq!(Staking::free_balance(&2), 70);
+
+			assert_noop!(
+				Slashing::on_misbehavior_reported(&authority_for(3)),
+				"misbehavior of an unknown validator"
+			);
+		});
+	}
+
+	#[test]
+	fn unproven_misbehavior_is_rejected() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(
+				Slashing::report_agreement_misbehavior(&0, multiple_candidates(1, 2)),
+				"misbehavior not proven"
+			);
+
+			let parent_hash = [1; 32].into();
+			let issued = (candidate(1), sign(1, Statement::Candidate(candidate(1)), &parent_hash));
+			let same_candidate = AgreementMisbehaviorReport {
+				parent_hash,
+				target: authority_for(1),
+				misbehavior: AgreementMisbehavior::MultipleCandidates(issued.clone(), issued),
+			};
+			assert_noop!(Slashing::report_agreement_misbehavior(&0, same_candidate), "misbehavior not proven");
+
+			assert_noop!(
+				Slashing::report_agreement_misbehavior(&0, multiple_candidates(3, 3)),
+				"misbehavior of an unknown validator"
+			);
+		});
+	}
+}
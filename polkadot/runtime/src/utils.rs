@@ -19,13 +19,21 @@
 use rstd::prelude::*;
 use super::{Call, UncheckedExtrinsic, Extrinsic, Staking};
 use runtime_primitives::traits::{Checkable, AuxLookup};
+use primitives::Heartbeat;
 use primitives::parachain::CandidateReceipt;
 use timestamp::Call as TimestampCall;
 use parachains::Call as ParachainsCall;
+use heartbeats::Call as HeartbeatsCall;
 
 /// Produces the list of inherent extrinsics.
-pub fn inherent_extrinsics(timestamp: ::primitives::Timestamp, parachain_heads: Vec<CandidateReceipt>) -> Vec<UncheckedExtrinsic> {
-	vec![
+///
+/// Heartbeats are only noted when there are any.
+pub fn inherent_extrinsics(
+	timestamp: ::primitives::Timestamp,
+	parachain_heads: Vec<CandidateReceipt>,
+	heartbeats: Vec<Heartbeat>,
+) -> Vec<UncheckedExtrinsic> {
+	let mut extrinsics = vec![
 		UncheckedExtrinsic::new(
 			Extrinsic {
 				signed: Default::default(),
@@ -42,7 +50,20 @@ pub fn inherent_extrinsics(timestamp: ::primitives::Timestamp, parachain_heads:
 			},
 			Default::default()
 		)
-	]
+	];
+
+	if !heartbeats.is_empty() {
+		extrinsics.push(UncheckedExtrinsic::new(
+			Extrinsic {
+				signed: Default::default(),
+				function: Call::Heartbeats(HeartbeatsCall::note_heartbeats(heartbeats)),
+				index: 0,
+			},
+			Default::default()
+		));
+	}
+
+	extrinsics
 }
 
 /// Checks an unchecked extrinsic for validity.
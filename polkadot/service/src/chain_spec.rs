@@ -17,14 +17,18 @@
 //! Polkadot chain configurations.
 
 use ed25519;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::path::PathBuf;
 use primitives::{AuthorityId, storage::{StorageKey, StorageData}};
+use polkadot_primitives::{BlockNumber, Hash};
 use runtime_primitives::{BuildStorage, StorageMap};
 use polkadot_runtime::{GenesisConfig, ConsensusConfig, CouncilConfig, DemocracyConfig,
-	SessionConfig, StakingConfig, TimestampConfig};
+	SessionConfig, StakingConfig, TimestampConfig, ParachainsConfig, HeartbeatsConfig, SlashingConfig};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 use serde_json as json;
+use collator;
 
 enum GenesisSource {
 	File(PathBuf),
@@ -76,6 +80,23 @@ enum Genesis {
 struct ChainSpecFile {
 	pub name: String,
 	pub boot_nodes: Vec<String>,
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub checkpoints: BTreeMap<BlockNumber, Hash>,
+	#[serde(default, skip_serializing_if = "json::Map::is_empty")]
+	pub extensions: json::Map<String, json::Value>,
+}
+
+/// Custom fields of a chain spec, stored under a name in its `extensions`.
+///
+/// Lets nodes embedding the service, such as parachain collators, ship fields
+/// of their own in the spec without changing its format.
+pub trait Extension: Serialize + DeserializeOwned {
+	/// Name the fields are stored under.
+	const NAME: &'static str;
+}
+
+impl Extension for collator::ParachainSpec {
+	const NAME: &'static str = collator::CHAIN_SPEC_EXTENSION;
 }
 
 /// A configuration of a chain. Can be used to build a genesis block.
@@ -89,10 +110,32 @@ impl ChainSpec {
 		&self.spec.boot_nodes
 	}
 
+	/// Hashes of trusted blocks by number. Chains conflicting with them are refused.
+	pub fn checkpoints(&self) -> &BTreeMap<BlockNumber, Hash> {
+		&self.spec.checkpoints
+	}
+
 	pub fn name(&self) -> &str {
 		&self.spec.name
 	}
 
+	/// Get the extension of the given type, if the spec has it.
+	pub fn extension<E: Extension>(&self) -> Result<Option<E>, String> {
+		match self.spec.extensions.get(E::NAME) {
+			Some(value) => json::from_value(value.clone())
+				.map(Some)
+				.map_err(|e| format!("Error parsing {} extension: {}", E::NAME, e)),
+			None => Ok(None),
+		}
+	}
+
+	/// Set the extension of the given type, replacing any previous one.
+	pub fn set_extension<E: Extension>(&mut self, extension: &E) -> Result<(), String> {
+		let value = json::to_value(extension).map_err(|e| format!("Error generating {} extension json: {}", E::NAME, e))?;
+		self.spec.extensions.insert(E::NAME.to_owned(), value);
+		Ok(())
+	}
+
 	/// Parse json content into a `ChainSpec`
 	pub fn from_embedded(json: &'static [u8]) -> Result<Self, String> {
 		let spec = json::from_slice(json).map_err(|e| format!("Error parsing spec file: {}", e))?;
@@ -194,10 +237,21 @@ impl ChainSpec {
 				cooloff_period: 12 * 60 * 24 * 4, // 4 day cooling off period if council member vetoes a proposal.
 				voting_period: 12 * 60 * 24, // 1 day voting period for council members.
 			}),
-			parachains: Some(Default::default()),
+			parachains: Some(ParachainsConfig {
+				deposit: Some(1_000_000),	// reserved from an account registering a parachain.
+				..Default::default()
+			}),
 			timestamp: Some(TimestampConfig {
 				period: 5,					// 5 second block time.
 			}),
+			heartbeats: Some(HeartbeatsConfig {
+				max_missed_sessions: 12,	// flag validators offline for a whole era.
+				phantom: Default::default(),
+			}),
+			slashing: Some(SlashingConfig {
+				misbehavior_slash: 10000,	// as much as an early era end.
+				phantom: Default::default(),
+			}),
 		})
 	}
 	/// PoC-2 testnet config.
@@ -208,7 +262,8 @@ impl ChainSpec {
 			"enode://c831ec9011d2c02d2c4620fc88db6d897a40d2f88fd75f47b9e4cf3b243999acb6f01b7b7343474650b34eeb1363041a422a91f1fc3850e43482983ee15aa582@104.211.48.247:30333".into(),
 		];
 		ChainSpec {
-			spec: ChainSpecFile { name: "PoC-2 Testnet".to_owned(), boot_nodes },
+			spec: ChainSpecFile { name: "PoC-2 Testnet".to_owned(), boot_nodes, checkpoints: Default::default(),
+				extensions: Default::default() },
 			genesis: GenesisSource::Factory(Self::poc_2_testnet_config_genesis),
 		}
 	}
@@ -270,10 +325,18 @@ impl ChainSpec {
 				cooloff_period: 75,
 				voting_period: 20,
 			}),
-			parachains: Some(Default::default()),
+			parachains: Some(ParachainsConfig {
+				deposit: Some(100),
+				..Default::default()
+			}),
 			timestamp: Some(TimestampConfig {
 				period: 5,					// 5 second block time.
 			}),
+			heartbeats: Some(HeartbeatsConfig {
+				max_missed_sessions: 5,	// flag validators offline for a whole era.
+				phantom: Default::default(),
+			}),
+			slashing: Some(Default::default()),
 		})
 	}
 
@@ -286,7 +349,8 @@ impl ChainSpec {
 	/// Development config (single validator Alice)
 	pub fn development_config() -> Self {
 		ChainSpec {
-			spec: ChainSpecFile { name: "Development".to_owned(), boot_nodes: vec![] },
+			spec: ChainSpecFile { name: "Development".to_owned(), boot_nodes: vec![], checkpoints: Default::default(),
+				extensions: Default::default() },
 			genesis: GenesisSource::Factory(Self::development_config_genesis),
 		}
 	}
@@ -301,7 +365,8 @@ impl ChainSpec {
 	/// Local testnet config (multivalidator Alice + Bob)
 	pub fn local_testnet_config() -> Self {
 		ChainSpec {
-			spec: ChainSpecFile { name: "Local Testnet".to_owned(), boot_nodes: vec![] },
+			spec: ChainSpecFile { name: "Local Testnet".to_owned(), boot_nodes: vec![], checkpoints: Default::default(),
+				extensions: Default::default() },
 			genesis: GenesisSource::Factory(Self::local_testnet_genesis),
 		}
 	}
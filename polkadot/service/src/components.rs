@@ -16,17 +16,23 @@
 
 //! Polkadot service components.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use client::{self, Client};
 use client_db;
 use codec::{self, Slicable};
-use consensus;
+use consensus::{self, Signer};
 use keystore::Store as Keystore;
 use network;
 use polkadot_api;
 use polkadot_executor::Executor as LocalDispatch;
-use polkadot_primitives::{Block, BlockId, Hash};
+use polkadot_primitives::{Block, BlockId, Hash, Header, UncheckedExtrinsic};
+use polkadot_runtime::{Block as RuntimeBlock, CheckedBlock};
+use runtime_primitives::bft::Justification;
+use runtime_primitives::traits::Header as HeaderT;
 use state_machine;
 use substrate_executor::NativeExecutor;
 use transaction_pool::{self, TransactionPool};
@@ -49,7 +55,7 @@ pub trait Components {
 
 	/// Create client.
 	fn build_client(&self, settings: client_db::DatabaseSettings, executor: CodeExecutor, chain_spec: &ChainSpec)
-		-> Result<(Arc<Client<Self::Backend, Self::Executor, Block>>, Option<Arc<network::OnDemand<Block, network::Service<Block>>>>), error::Error>;
+		-> Result<(Arc<Client<Self::Backend, Self::Executor, Block>>, Option<Arc<network::OnDemand<Block>>>), error::Error>;
 
 	/// Create api.
 	fn build_api(&self, client: Arc<Client<Self::Backend, Self::Executor, Block>>) -> Arc<Self::Api>;
@@ -59,7 +65,7 @@ pub trait Components {
 		-> Arc<network::TransactionPool<Block>>;
 
 	/// Create consensus service.
-	fn build_consensus(&self, client: Arc<Client<Self::Backend, Self::Executor, Block>>, network: Arc<network::Service<Block>>, tx_pool: Arc<TransactionPool<Self::Api>>, keystore: &Keystore)
+	fn build_consensus(&self, client: Arc<Client<Self::Backend, Self::Executor, Block>>, api: Arc<Self::Api>, network: Arc<network::Service<Block>>, tx_pool: Arc<TransactionPool<Self::Api>>, keystore: &Keystore, statement_log_path: PathBuf, availability_store_path: PathBuf)
 		-> Result<Option<consensus::Service>, error::Error>;
 }
 
@@ -67,20 +73,31 @@ pub trait Components {
 pub struct FullComponents {
 	/// Is this a validator node?
 	pub is_validator: bool,
+	/// Refuse to propose blocks while the local clock is skewed from the peers'.
+	pub refuse_on_clock_skew: bool,
+	/// Strategies for executing the runtime.
+	pub execution_strategies: client::ExecutionStrategies,
+	/// Address of the signer holding the authority key and the time a request
+	/// to it may take, if the key isn't in the keystore.
+	pub remote_signer: Option<(SocketAddr, Duration)>,
+	/// Number of relay chain blocks past finality to keep candidate data for.
+	pub availability_retention: u64,
 }
 
 impl Components for FullComponents {
 	type Backend = client_db::Backend<Block>;
-	type Api = Client<Self::Backend, Self::Executor, Block>;
+	type Api = polkadot_api::CachedApi<Client<Self::Backend, Self::Executor, Block>>;
 	type Executor = client::LocalCallExecutor<client_db::Backend<Block>, NativeExecutor<LocalDispatch>>;
 
 	fn build_client(&self, db_settings: client_db::DatabaseSettings, executor: CodeExecutor, chain_spec: &ChainSpec)
-		-> Result<(Arc<client::Client<Self::Backend, Self::Executor, Block>>, Option<Arc<network::OnDemand<Block, network::Service<Block>>>>), error::Error> {
-		Ok((Arc::new(client_db::new_client(db_settings, executor, chain_spec)?), None))
+		-> Result<(Arc<client::Client<Self::Backend, Self::Executor, Block>>, Option<Arc<network::OnDemand<Block>>>), error::Error> {
+		let client = client_db::new_client(db_settings, executor, chain_spec)?
+			.with_execution_strategies(self.execution_strategies);
+		Ok((Arc::new(client), None))
 	}
 
 	fn build_api(&self, client: Arc<client::Client<Self::Backend, Self::Executor, Block>>) -> Arc<Self::Api> {
-		client
+		Arc::new(polkadot_api::CachedApi::new(client, polkadot_api::cache::DEFAULT_CACHED_BLOCKS))
 	}
 
 	fn build_network_tx_pool(&self, client: Arc<client::Client<Self::Backend, Self::Executor, Block>>, pool: Arc<TransactionPool<Self::Api>>)
@@ -92,22 +109,37 @@ impl Components for FullComponents {
 		})
 	}
 
-	fn build_consensus(&self, client: Arc<client::Client<Self::Backend, Self::Executor, Block>>, network: Arc<network::Service<Block>>, tx_pool: Arc<TransactionPool<Self::Api>>, keystore: &Keystore)
+	fn build_consensus(&self, client: Arc<client::Client<Self::Backend, Self::Executor, Block>>, api: Arc<Self::Api>, network: Arc<network::Service<Block>>, tx_pool: Arc<TransactionPool<Self::Api>>, keystore: &Keystore, statement_log_path: PathBuf, availability_store_path: PathBuf)
 		-> Result<Option<consensus::Service>, error::Error> {
 		if !self.is_validator {
 			return Ok(None);
 		}
 
-		// Load the first available key
-		let key = keystore.load(&keystore.contents()?[0], "")?;
-		info!("Using authority key: {}", key.public());
+		let key: Arc<Signer> = match self.remote_signer {
+			Some((address, budget)) => {
+				let signer = consensus::RemoteSigner::connect(address, budget)
+					.map_err(consensus::Error::from)?;
+				info!("Using authority key {} of signer at {}", signer.public(), address);
+				Arc::new(signer)
+			}
+			None => {
+				// Load the first available key
+				let key = keystore.load(&keystore.contents()?[0], "")?;
+				info!("Using authority key: {}", key.public());
+				Arc::new(key)
+			}
+		};
+		let statement_log = consensus::StatementLog::open(statement_log_path)?;
+		let availability_store = consensus::AvailabilityStore::open(availability_store_path, self.availability_retention)?;
 		Ok(Some(consensus::Service::new(
 			client.clone(),
-			client.clone(),
+			api,
 			network.clone(),
 			tx_pool.clone(),
-			::std::time::Duration::from_millis(4000), // TODO: dynamic
 			key,
+			statement_log,
+			availability_store,
+			self.refuse_on_clock_skew,
 		)))
 	}
 }
@@ -116,14 +148,14 @@ impl Components for FullComponents {
 pub struct LightComponents;
 
 impl Components for LightComponents {
-	type Backend = client::light::backend::Backend<client_db::light::LightStorage<Block>, network::OnDemand<Block, network::Service<Block>>>;
+	type Backend = client::light::backend::Backend<client_db::light::LightStorage<Block>, network::OnDemand<Block>>;
 	type Api = polkadot_api::light::RemotePolkadotApiWrapper<Self::Backend, Self::Executor>;
 	type Executor = client::light::call_executor::RemoteCallExecutor<
-		client::light::blockchain::Blockchain<client_db::light::LightStorage<Block>, network::OnDemand<Block, network::Service<Block>>>,
-		network::OnDemand<Block, network::Service<Block>>>;
+		client::light::blockchain::Blockchain<client_db::light::LightStorage<Block>, network::OnDemand<Block>>,
+		network::OnDemand<Block>>;
 
 	fn build_client(&self, db_settings: client_db::DatabaseSettings, executor: CodeExecutor, spec: &ChainSpec)
-		-> Result<(Arc<client::Client<Self::Backend, Self::Executor, Block>>, Option<Arc<network::OnDemand<Block, network::Service<Block>>>>), error::Error> {
+		-> Result<(Arc<client::Client<Self::Backend, Self::Executor, Block>>, Option<Arc<network::OnDemand<Block>>>), error::Error> {
 		let db_storage = client_db::light::LightStorage::new(db_settings)?;
 		let light_blockchain = client::light::new_light_blockchain(db_storage);
 		let fetch_checker = Arc::new(client::light::new_fetch_checker(light_blockchain.clone(), executor));
@@ -146,12 +178,83 @@ impl Components for LightComponents {
 		})
 	}
 
-	fn build_consensus(&self, _client: Arc<client::Client<Self::Backend, Self::Executor, Block>>, _network: Arc<network::Service<Block>>, _tx_pool: Arc<TransactionPool<Self::Api>>, _keystore: &Keystore)
+	fn build_consensus(&self, _client: Arc<client::Client<Self::Backend, Self::Executor, Block>>, _api: Arc<Self::Api>, _network: Arc<network::Service<Block>>, _tx_pool: Arc<TransactionPool<Self::Api>>, _keystore: &Keystore, _statement_log_path: PathBuf, _availability_store_path: PathBuf)
 		-> Result<Option<consensus::Service>, error::Error> {
 		Ok(None)
 	}
 }
 
+/// Validator of block announcements.
+///
+/// Relay chain blocks are final once imported, so a block announced at or
+/// below the best block is on a fork which can never be imported and isn't
+/// downloaded.
+pub struct BlockAnnounceAdapter<B, E> {
+	client: Arc<Client<B, E, Block>>,
+}
+
+impl<B, E> BlockAnnounceAdapter<B, E> {
+	/// Create a validator checking announcements against the client's chain.
+	pub fn new(client: Arc<Client<B, E, Block>>) -> Self {
+		BlockAnnounceAdapter { client }
+	}
+}
+
+impl<B, E> network::BlockAnnounceValidator<Block> for BlockAnnounceAdapter<B, E>
+	where
+		B: client::backend::Backend<Block> + Send + Sync,
+		E: client::CallExecutor<Block> + Send + Sync,
+		client::error::Error: From<<<B as client::backend::Backend<Block>>::State as state_machine::backend::Backend>::Error>,
+{
+	fn validate(&self, header: &Header) -> network::AnnounceValidity {
+		let best_number = match self.client.info() {
+			Ok(info) => info.chain.best_number,
+			Err(e) => {
+				debug!("Error getting best block: {:?}", e);
+				return network::AnnounceValidity::Valid;
+			}
+		};
+
+		if header.number <= best_number {
+			network::AnnounceValidity::Ignore
+		} else {
+			network::AnnounceValidity::Valid
+		}
+	}
+}
+
+/// Verifier of downloaded relay chain blocks.
+///
+/// A justification must be for the block it comes with and list every signer
+/// once, and the body must have the timestamp and parachain heads inherents
+/// in place. The signatures themselves are checked by the client on import.
+pub struct BlockVerifierAdapter;
+
+impl network::BlockVerifier<Block> for BlockVerifierAdapter {
+	fn verify(&self, header: &Header, justification: &Justification<Hash>, body: Option<&[UncheckedExtrinsic]>)
+		-> Result<(), String>
+	{
+		if justification.hash != header.hash() {
+			return Err(format!("justification is for block {}", justification.hash));
+		}
+
+		let signers: HashSet<_> = justification.signatures.iter().map(|&(ref signer, _)| signer).collect();
+		if signers.len() != justification.signatures.len() {
+			return Err("justification lists a signer more than once".into());
+		}
+
+		if let Some(extrinsics) = body {
+			let encoded = Block { header: header.clone(), extrinsics: extrinsics.to_vec() }.encode();
+			let checked = RuntimeBlock::decode(&mut &encoded[..]).and_then(|block| CheckedBlock::new(block).ok());
+			if checked.is_none() {
+				return Err("block lacks the timestamp or parachain heads inherent".into());
+			}
+		}
+
+		Ok(())
+	}
+}
+
 /// Transaction pool adapter.
 pub struct TransactionPoolAdapter<B, E, A> where A: Send + Sync, E: Send + Sync {
 	imports_external_transactions: bool,
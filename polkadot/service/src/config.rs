@@ -16,11 +16,22 @@
 
 //! Service configuration.
 
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use consensus;
+use network;
+use polkadot_primitives::{BlockNumber, Hash};
 use transaction_pool;
 use chain_spec::ChainSpec;
 pub use network::Role;
 pub use network::NetworkConfiguration;
+pub use network::ProtocolRegistry;
+pub use network::AnnounceMode;
+pub use network::BandwidthConfig;
+pub use network::{PeerSetConfig, PeerSlots, ConnectionLimits, ResponseLimits};
 pub use client_db::PruningMode;
+pub use client::{ExecutionStrategies, ExecutionStrategy};
 
 /// Service configuration.
 pub struct Configuration {
@@ -30,12 +41,22 @@ pub struct Configuration {
 	pub transaction_pool: transaction_pool::Options,
 	/// Network configuration.
 	pub network: NetworkConfiguration,
+	/// Application protocols to run alongside the core network protocol.
+	pub application_protocols: ProtocolRegistry,
 	/// Path to key files.
 	pub keystore_path: String,
 	/// Path to the database.
 	pub database_path: String,
+	/// Path to the record of statements signed by the local validator.
+	pub statement_log_path: String,
+	/// Path to the block data of candidates made available by the local validator.
+	pub availability_store_path: String,
+	/// Number of relay chain blocks past finality to keep candidate data for.
+	pub availability_retention: u64,
 	/// Pruning settings.
 	pub pruning: PruningMode,
+	/// Number of recently finalized states kept for RPC queries regardless of pruning.
+	pub retained_finalized_states: u32,
 	/// Additional key seeds.
 	pub keys: Vec<String>,
 	/// Chain configuration.
@@ -44,6 +65,35 @@ pub struct Configuration {
 	pub telemetry: Option<String>,
 	/// Node name.
 	pub name: String,
+	/// Refuse to propose blocks while the local clock is off from the peers'
+	/// by more than the proposal delay.
+	pub refuse_on_clock_skew: bool,
+	/// Strategies for executing the runtime when importing, authoring and
+	/// answering RPC calls.
+	pub execution_strategies: ExecutionStrategies,
+	/// Address of a signer process holding the authority key, used instead of
+	/// the keystore.
+	pub remote_signer: Option<SocketAddr>,
+	/// Time a request to the remote signer may take.
+	pub signing_budget: Duration,
+	/// Trusted block to download the state of and sync from when the database
+	/// is empty, instead of syncing from genesis.
+	pub state_sync_target: Option<Hash>,
+	/// Hashes of trusted blocks by number, besides the checkpoints of the
+	/// chain spec. Chains conflicting with them are refused.
+	pub checkpoints: Vec<(BlockNumber, Hash)>,
+	/// How imported blocks are announced to peers.
+	pub announce_mode: AnnounceMode,
+	/// Limits on the bandwidth used by each peer.
+	pub peer_bandwidth: BandwidthConfig,
+	/// Reserved nodes and connection slots by role.
+	pub peer_set: PeerSetConfig,
+	/// Limits on the block responses sent to and accepted from peers.
+	pub response_limits: ResponseLimits,
+	/// Time a connected peer has to send its status before it is disconnected.
+	pub handshake_timeout: Duration,
+	/// Time a pinged peer has to answer before it is disconnected.
+	pub keep_alive_timeout: Duration,
 }
 
 impl Configuration {
@@ -55,11 +105,28 @@ impl Configuration {
 			roles: Role::FULL,
 			transaction_pool: Default::default(),
 			network: Default::default(),
+			application_protocols: Default::default(),
 			keystore_path: Default::default(),
 			database_path: Default::default(),
+			statement_log_path: Default::default(),
+			availability_store_path: Default::default(),
+			availability_retention: consensus::DEFAULT_AVAILABILITY_RETENTION,
 			keys: Default::default(),
 			telemetry: Default::default(),
 			pruning: PruningMode::ArchiveAll,
+			retained_finalized_states: 0,
+			refuse_on_clock_skew: false,
+			execution_strategies: Default::default(),
+			remote_signer: None,
+			signing_budget: Duration::from_millis(consensus::DEFAULT_SIGNING_BUDGET_MS),
+			state_sync_target: None,
+			checkpoints: Vec::new(),
+			announce_mode: AnnounceMode::Header,
+			peer_bandwidth: Default::default(),
+			peer_set: Default::default(),
+			response_limits: Default::default(),
+			handshake_timeout: Duration::from_secs(network::DEFAULT_HANDSHAKE_TIMEOUT_SECS),
+			keep_alive_timeout: Duration::from_secs(network::DEFAULT_KEEP_ALIVE_TIMEOUT_SECS),
 		};
 		configuration.network.boot_nodes = configuration.chain_spec.boot_nodes().to_vec();
 		configuration
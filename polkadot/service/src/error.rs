@@ -19,12 +19,14 @@
 use client;
 use network;
 use keystore;
+use consensus;
 
 error_chain! {
 	links {
 		Client(client::error::Error, client::error::ErrorKind) #[doc="Client error"];
 		Network(network::error::Error, network::error::ErrorKind) #[doc="Network error"];
 		Keystore(keystore::Error, keystore::ErrorKind) #[doc="Keystore error"];
+		Consensus(consensus::Error, consensus::ErrorKind) #[doc="Consensus error"];
 	}
 
 	errors {
@@ -29,6 +29,7 @@ extern crate polkadot_runtime;
 extern crate polkadot_executor;
 extern crate polkadot_api;
 extern crate polkadot_consensus as consensus;
+extern crate polkadot_collator as collator;
 extern crate polkadot_transaction_pool as transaction_pool;
 extern crate substrate_keystore as keystore;
 extern crate substrate_runtime_io as runtime_io;
@@ -69,23 +70,28 @@ use transaction_pool::TransactionPool;
 use keystore::Store as Keystore;
 use polkadot_api::PolkadotApi;
 use polkadot_primitives::{Block, BlockId, Hash};
+use codec::Slicable;
 use client::{Client, BlockchainEvents};
 use network::ManageNetwork;
 use exit_future::Signal;
 
 pub use self::error::{ErrorKind, Error};
 pub use self::components::{Components, FullComponents, LightComponents};
-pub use config::{Configuration, Role, PruningMode};
-pub use chain_spec::ChainSpec;
+pub use config::{Configuration, Role, PruningMode, ProtocolRegistry, ExecutionStrategies, ExecutionStrategy, AnnounceMode,
+	BandwidthConfig, PeerSetConfig, PeerSlots, ConnectionLimits, ResponseLimits};
+pub use chain_spec::{ChainSpec, Extension as ChainSpecExtension};
+pub use consensus::{LiveAgreement, GroupState, AgreementSummary};
+pub use polkadot_api::PolkadotApi;
 
 /// Polkadot service.
 pub struct Service<Components: components::Components> {
 	thread: Option<thread::JoinHandle<()>>,
 	client: Arc<Client<Components::Backend, Components::Executor, Block>>,
+	api: Arc<Components::Api>,
 	network: Arc<network::Service<Block>>,
 	transaction_pool: Arc<TransactionPool<Components::Api>>,
 	signal: Option<Signal>,
-	_consensus: Option<consensus::Service>,
+	consensus: Option<consensus::Service>,
 }
 
 /// Creates light client and register protocol with the network service
@@ -96,7 +102,14 @@ pub fn new_light(config: Configuration) -> Result<Service<components::LightCompo
 /// Creates full client and register protocol with the network service
 pub fn new_full(config: Configuration) -> Result<Service<components::FullComponents>, error::Error> {
 	let is_validator = (config.roles & Role::VALIDATOR) == Role::VALIDATOR;
-	Service::new(components::FullComponents { is_validator }, config)
+	let components = components::FullComponents {
+		is_validator,
+		refuse_on_clock_skew: config.refuse_on_clock_skew,
+		execution_strategies: config.execution_strategies,
+		remote_signer: config.remote_signer.map(|address| (address, config.signing_budget)),
+		availability_retention: config.availability_retention,
+	};
+	Service::new(components, config)
 }
 
 /// Creates bare client without any networking.
@@ -110,10 +123,17 @@ pub fn new_client(config: Configuration) -> Result<Arc<Client<
 		cache_size: None,
 		path: config.database_path.into(),
 		pruning: config.pruning,
+		retained_finalized_states: config.retained_finalized_states,
 	};
 	let executor = polkadot_executor::Executor::new();
 	let is_validator = (config.roles & Role::VALIDATOR) == Role::VALIDATOR;
-	let components = components::FullComponents { is_validator };
+	let components = components::FullComponents {
+		is_validator,
+		refuse_on_clock_skew: false,
+		execution_strategies: config.execution_strategies,
+		remote_signer: None,
+		availability_retention: config.availability_retention,
+	};
 	let (client, _) = components.build_client(db_settings, executor, &config.chain_spec)?;
 	Ok(client)
 }
@@ -146,6 +166,7 @@ impl<Components> Service<Components>
 			cache_size: None,
 			path: config.database_path.into(),
 			pruning: config.pruning,
+			retained_finalized_states: config.retained_finalized_states,
 		};
 
 		let (client, on_demand) = components.build_client(db_settings, executor, &config.chain_spec)?;
@@ -160,15 +181,31 @@ impl<Components> Service<Components>
 		let network_params = network::Params {
 			config: network::ProtocolConfig {
 				roles: config.roles,
+				state_sync_target: config.state_sync_target.map(|hash| hash.encode()),
+				checkpoints: config.chain_spec.checkpoints().iter()
+					.map(|(number, hash)| (*number, *hash))
+					.chain(config.checkpoints)
+					.map(|(number, hash)| (number, hash.encode()))
+					.collect(),
+				announce_mode: config.announce_mode,
+				bandwidth: config.peer_bandwidth,
+				peer_set: config.peer_set,
+				response_limits: config.response_limits,
+				handshake_timeout: config.handshake_timeout,
+				keep_alive_timeout: config.keep_alive_timeout,
+				..Default::default()
 			},
 			network_config: config.network,
 			chain: client.clone(),
 			on_demand: on_demand.clone().map(|d| d as Arc<network::OnDemandService<Block>>),
 			transaction_pool: transaction_pool_adapter,
+			block_announce_validator: Some(Arc::new(components::BlockAnnounceAdapter::new(client.clone()))),
+			block_verifier: Some(Arc::new(components::BlockVerifierAdapter)),
+			application_protocols: config.application_protocols,
 		};
 		let network = network::Service::new(network_params)?;
 		let barrier = ::std::sync::Arc::new(Barrier::new(2));
-		on_demand.map(|on_demand| on_demand.set_service_link(Arc::downgrade(&network)));
+		on_demand.map(|on_demand| on_demand.set_network_chan(network.network_chan()));
 
 		let thread = {
 			let client = client.clone();
@@ -195,6 +232,22 @@ impl<Components> Service<Components>
 					});
 				core.handle().spawn(events);
 
+				// best chain reorganizations
+				let network1 = network.clone();
+				let txpool1 = txpool.clone();
+				let client1 = client.clone();
+
+				let events = client.reorg_notification_stream()
+					.for_each(move |notification| {
+						let requeued = requeue_retracted(&*client1, &*txpool1, &notification.retracted);
+						if requeued > 0 {
+							network1.trigger_repropagate();
+						}
+
+						Ok(())
+					});
+				core.handle().spawn(events);
+
 				// transaction notifications
 				let events = txpool.import_notification_stream()
 					// TODO [ToDr] Consider throttling?
@@ -216,15 +269,24 @@ impl<Components> Service<Components>
 		barrier.wait();
 
 		// Spin consensus service if configured
-		let consensus_service = components.build_consensus(client.clone(), network.clone(), transaction_pool.clone(), &keystore)?;
+		let consensus_service = components.build_consensus(
+			client.clone(),
+			api.clone(),
+			network.clone(),
+			transaction_pool.clone(),
+			&keystore,
+			config.statement_log_path.into(),
+			config.availability_store_path.into(),
+		)?;
 
 		Ok(Service {
 			thread: Some(thread),
 			client: client,
+			api: api,
 			network: network,
 			transaction_pool: transaction_pool,
 			signal: Some(signal),
-			_consensus: consensus_service,
+			consensus: consensus_service,
 		})
 	}
 
@@ -233,6 +295,11 @@ impl<Components> Service<Components>
 		self.client.clone()
 	}
 
+	/// Get shared Polkadot API instance.
+	pub fn api(&self) -> Arc<Components::Api> {
+		self.api.clone()
+	}
+
 	/// Get shared network instance.
 	pub fn network(&self) -> Arc<network::Service<Block>> {
 		self.network.clone()
@@ -242,6 +309,11 @@ impl<Components> Service<Components>
 	pub fn transaction_pool(&self) -> Arc<TransactionPool<Components::Api>> {
 		self.transaction_pool.clone()
 	}
+
+	/// Get a handle to the ongoing candidate agreement, if this node is a validator.
+	pub fn live_agreement(&self) -> Option<LiveAgreement> {
+		self.consensus.as_ref().map(|c| c.live_agreement())
+	}
 }
 
 /// Produce a task which prunes any finalized transactions from the pool.
@@ -258,6 +330,33 @@ pub fn prune_imported<A>(pool: &TransactionPool<A>, hash: Hash)
 	}
 }
 
+/// Re-queue the extrinsics of blocks retracted from the best chain, so that
+/// they may be included in the new one. Returns the number of extrinsics re-queued.
+pub fn requeue_retracted<B, E, A>(client: &Client<B, E, Block>, pool: &TransactionPool<A>, retracted: &[Hash]) -> usize
+	where
+		B: client::backend::Backend<Block>,
+		E: client::CallExecutor<Block>,
+		A: PolkadotApi,
+		client::error::Error: From<<<B as client::backend::Backend<Block>>::State as state_machine::backend::Backend>::Error>,
+{
+	let best = match client.info() {
+		Ok(info) => BlockId::hash(info.chain.best_hash),
+		Err(e) => {
+			warn!("Unable to re-queue retracted extrinsics: {:?}", e);
+			return 0;
+		}
+	};
+
+	retracted.iter().map(|hash| match client.body(&BlockId::hash(*hash)) {
+		Ok(Some(body)) => pool.requeue(best, body),
+		Ok(None) => 0,
+		Err(e) => {
+			warn!("Unable to read retracted block {}: {:?}", hash, e);
+			0
+		}
+	}).sum()
+}
+
 impl<Components> Drop for Service<Components> where Components: components::Components {
 	fn drop(&mut self) {
 		self.network.stop_network();
@@ -0,0 +1,329 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Property tests of the generic statement table.
+//!
+//! Random sequences of statements are imported from a fixed set of authorities,
+//! some of which are malicious and may sign anything. Honest authorities only
+//! issue and withdraw their own candidate and vote where they are entitled to.
+//! After every statement the table is checked against a simple model:
+//!
+//!   - only malicious authorities are ever reported for misbehavior.
+//!   - an authority is reported for a double vote exactly when it voted in two
+//!     different ways on a candidate known to the table.
+//!   - an includable candidate only stops being includable through an invalidity
+//!     vote or a withdrawal.
+//!   - the count of includable groups matches the candidates.
+//!
+//! Only built with the `fuzz` feature: `cargo test --features fuzz`.
+
+use std::collections::{HashMap, HashSet};
+
+use quickcheck::{Arbitrary, Gen, QuickCheck};
+
+use generic::{Context, Misbehavior, SignedStatement, Statement, Table};
+
+const AUTHORITIES: usize = 12;
+const GROUPS: usize = 3;
+const MALICIOUS_CANDIDATES: usize = 6;
+const TESTS: u64 = 500;
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+struct AuthorityId(usize);
+
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord, PartialEq, Eq)]
+struct GroupId(usize);
+
+// group, body
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord, PartialEq, Eq)]
+struct Candidate(usize, usize);
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+struct Signature(usize);
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+struct Digest(usize);
+
+// every fourth authority is malicious.
+fn is_malicious(authority: AuthorityId) -> bool {
+	authority.0 % 4 == 3
+}
+
+struct FuzzContext;
+
+impl Context for FuzzContext {
+	type AuthorityId = AuthorityId;
+	type Digest = Digest;
+	type Candidate = Candidate;
+	type GroupId = GroupId;
+	type Signature = Signature;
+
+	fn candidate_digest(candidate: &Candidate) -> Digest {
+		Digest(candidate.1)
+	}
+
+	fn candidate_group(candidate: &Candidate) -> GroupId {
+		GroupId(candidate.0)
+	}
+
+	fn is_member_of(&self, authority: &AuthorityId, group: &GroupId) -> bool {
+		authority.0 % GROUPS == group.0
+	}
+
+	// some authorities guarantee availability for the group they are a member of.
+	fn is_availability_guarantor_of(&self, authority: &AuthorityId, group: &GroupId) -> bool {
+		(authority.0 / GROUPS) % GROUPS == group.0
+	}
+
+	fn group_authorities(&self, group: &GroupId) -> (Vec<AuthorityId>, Vec<AuthorityId>) {
+		let authorities = (0..AUTHORITIES).map(AuthorityId);
+		(
+			authorities.clone().filter(|a| self.is_member_of(a, group)).collect(),
+			authorities.filter(|a| self.is_availability_guarantor_of(a, group)).collect(),
+		)
+	}
+
+	fn requisite_votes(&self, _group: &GroupId) -> (usize, usize) {
+		(2, 2)
+	}
+
+	fn verify_statement(&self, statement: &SignedStatement<Candidate, Digest, AuthorityId, Signature>) -> bool {
+		statement.signature.0 == statement.sender.0
+	}
+
+	fn issuer_guarantees_availability(&self, group: &GroupId) -> bool {
+		group.0 == 0
+	}
+}
+
+// all candidates which may be referenced: one for each authority, plus
+// some only ever issued by malicious authorities.
+fn candidates() -> Vec<Candidate> {
+	let own = (0..AUTHORITIES).map(own_candidate);
+	let extra = (0..MALICIOUS_CANDIDATES).map(|i| Candidate(i % GROUPS, AUTHORITIES + i));
+	own.chain(extra).collect()
+}
+
+fn own_candidate(authority: usize) -> Candidate {
+	Candidate(authority % GROUPS, authority)
+}
+
+// what honest authorities think of a candidate.
+fn honestly_valid(candidate: &Candidate) -> bool {
+	candidate.1 % 5 != 0
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+	Candidate,
+	Valid,
+	Invalid,
+	Available,
+	Withdraw,
+}
+
+#[derive(Debug, Clone)]
+struct Action {
+	sender: usize,
+	kind: Kind,
+	target: usize,
+}
+
+impl Arbitrary for Action {
+	fn arbitrary<G: Gen>(g: &mut G) -> Self {
+		let kind = match usize::arbitrary(g) % 5 {
+			0 => Kind::Candidate,
+			1 => Kind::Valid,
+			2 => Kind::Invalid,
+			3 => Kind::Available,
+			_ => Kind::Withdraw,
+		};
+
+		Action {
+			sender: usize::arbitrary(g) % AUTHORITIES,
+			kind,
+			target: usize::arbitrary(g) % (AUTHORITIES + MALICIOUS_CANDIDATES),
+		}
+	}
+}
+
+impl Action {
+	// the statement signed for this action, if the sender would sign it at all.
+	fn statement(&self, context: &FuzzContext, model: &Model) -> Option<SignedStatement<Candidate, Digest, AuthorityId, Signature>> {
+		let sender = AuthorityId(self.sender);
+		let target = candidates()[self.target];
+		let digest = FuzzContext::candidate_digest(&target);
+		let group = FuzzContext::candidate_group(&target);
+
+		let statement = if is_malicious(sender) {
+			match self.kind {
+				Kind::Candidate => Statement::Candidate(target),
+				Kind::Valid => Statement::Valid(digest),
+				Kind::Invalid => Statement::Invalid(digest),
+				Kind::Available => Statement::Available(digest),
+				Kind::Withdraw => Statement::Withdraw(digest),
+			}
+		} else {
+			let own = own_candidate(self.sender);
+			let own_digest = FuzzContext::candidate_digest(&own);
+			let may_vote = context.is_member_of(&sender, &group) && target != own;
+			let issued = model.proposals.get(&sender) == Some(&own_digest);
+
+			match self.kind {
+				Kind::Candidate => Statement::Candidate(own),
+				Kind::Withdraw if issued => Statement::Withdraw(own_digest),
+				Kind::Valid | Kind::Invalid if may_vote => if honestly_valid(&target) {
+					Statement::Valid(digest)
+				} else {
+					Statement::Invalid(digest)
+				},
+				Kind::Available if context.is_availability_guarantor_of(&sender, &group) =>
+					Statement::Available(digest),
+				_ => return None,
+			}
+		};
+
+		Some(SignedStatement { statement, signature: Signature(self.sender), sender })
+	}
+}
+
+// ways of voting on validity which may not be combined.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+enum Vote {
+	Issued,
+	Valid,
+	Invalid,
+}
+
+#[derive(Default)]
+struct Model {
+	withdrawn: HashSet<Digest>,
+	proposals: HashMap<AuthorityId, Digest>,
+	votes: HashMap<(AuthorityId, Digest), HashSet<Vote>>,
+}
+
+impl Model {
+	// note the validity vote implied by a statement, before importing it.
+	fn note(&mut self, context: &FuzzContext, table: &Table<FuzzContext>, statement: &SignedStatement<Candidate, Digest, AuthorityId, Signature>) {
+		let sender = statement.sender;
+		let (digest, vote) = match statement.statement {
+			Statement::Candidate(ref c) => {
+				let digest = FuzzContext::candidate_digest(c);
+				let accepted = context.is_member_of(&sender, &FuzzContext::candidate_group(c))
+					&& !self.withdrawn.contains(&digest)
+					&& self.proposals.get(&sender).map_or(true, |p| p == &digest);
+
+				if !accepted { return }
+				self.proposals.insert(sender, digest);
+				(digest, Vote::Issued)
+			}
+			Statement::Valid(digest) => (digest, Vote::Valid),
+			Statement::Invalid(digest) => (digest, Vote::Invalid),
+			Statement::Available(_) | Statement::Withdraw(_) => return,
+		};
+
+		if let Vote::Issued = vote {} else {
+			let known_member = table.get_candidate(&digest)
+				.map_or(false, |c| context.is_member_of(&sender, &FuzzContext::candidate_group(c)));
+
+			if !known_member { return }
+		}
+
+		self.votes.entry((sender, digest)).or_insert_with(HashSet::new).insert(vote);
+	}
+
+	fn double_voted(&self, authority: AuthorityId) -> bool {
+		self.votes.iter().any(|(&(a, _), votes)| a == authority && votes.len() > 1)
+	}
+}
+
+fn includable(context: &FuzzContext, table: &Table<FuzzContext>) -> HashSet<Digest> {
+	candidates().iter()
+		.map(FuzzContext::candidate_digest)
+		.filter(|d| table.candidate_includable(d, context))
+		.collect()
+}
+
+fn check_sequence(actions: Vec<Action>) -> bool {
+	let context = FuzzContext;
+	let mut table = Table::default();
+	let mut model = Model::default();
+
+	for action in actions {
+		let statement = match action.statement(&context, &model) {
+			Some(statement) => statement,
+			None => continue,
+		};
+
+		let includable_before = includable(&context, &table);
+		let known_before = match statement.statement {
+			Statement::Withdraw(ref d) => table.get_candidate(d).is_some(),
+			_ => false,
+		};
+
+		model.note(&context, &table, &statement);
+		table.import_statement(&context, statement.clone(), None);
+
+		if let Statement::Withdraw(digest) = statement.statement {
+			if known_before && table.get_candidate(&digest).is_none() {
+				model.withdrawn.insert(digest);
+				model.proposals.remove(&statement.sender);
+			}
+		}
+
+		for (authority, offenses) in table.get_misbehavior() {
+			assert!(is_malicious(*authority), "honest authority {:?} reported for {:?}", authority, offenses);
+
+			let double_vote = offenses.iter().any(|o| match *o {
+				Misbehavior::ValidityDoubleVote(_) => true,
+				_ => false,
+			});
+			assert_eq!(double_vote, model.double_voted(*authority), "double votes of {:?}", authority);
+		}
+
+		for authority in (0..AUTHORITIES).map(AuthorityId) {
+			if model.double_voted(authority) {
+				assert!(table.get_misbehavior().contains_key(&authority), "double vote of {:?} undetected", authority);
+			}
+		}
+
+		let includable_after = includable(&context, &table);
+		for digest in includable_before.difference(&includable_after) {
+			let explained = match statement.statement {
+				Statement::Invalid(ref d) | Statement::Withdraw(ref d) => d == digest,
+				_ => false,
+			};
+			assert!(explained, "{:?} stopped being includable after {:?}", digest, statement);
+		}
+
+		let includable_groups: HashSet<_> = includable_after.iter()
+			.filter_map(|d| table.get_candidate(d))
+			.map(FuzzContext::candidate_group)
+			.collect();
+		assert_eq!(table.includable_count(), includable_groups.len());
+
+		for candidate in table.proposed_candidates(&context) {
+			assert!(includable_after.contains(&FuzzContext::candidate_digest(candidate)));
+		}
+	}
+
+	true
+}
+
+#[test]
+fn statement_sequences_uphold_invariants() {
+	QuickCheck::new().tests(TESTS).quickcheck(check_sequence as fn(Vec<Action>) -> bool);
+}
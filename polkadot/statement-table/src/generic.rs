@@ -27,7 +27,7 @@
 //! propose and attest to validity of candidates, and those who can only attest
 //! to availability.
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::collections::hash_map::{HashMap, Entry};
 use std::hash::Hash;
 use std::fmt::Debug;
@@ -80,8 +80,73 @@ pub trait Context {
 		group: &Self::GroupId,
 	) -> bool;
 
-	// requisite number of votes for validity and availability respectively from a group.
+	/// All members and availability guarantors of a group, respectively.
+	fn group_authorities(&self, group: &Self::GroupId) -> (Vec<Self::AuthorityId>, Vec<Self::AuthorityId>);
+
+	/// The weight of an authority's votes.
+	///
+	/// Defaults to 1 for every authority, which makes `requisite_votes` a plain
+	/// count of votes.
+	fn authority_weight(&self, _authority: &Self::AuthorityId) -> usize { 1 }
+
+	// requisite weight of votes for validity and availability respectively from a group.
 	fn requisite_votes(&self, group: &Self::GroupId) -> (usize, usize);
+
+	/// Whether the signature on a statement is valid for its sender.
+	///
+	/// Checked for every statement imported through `Table::import_statement`.
+	fn verify_statement(
+		&self,
+		statement: &SignedStatement<Self::Candidate, Self::Digest, Self::AuthorityId, Self::Signature>,
+	) -> bool;
+
+	/// Whether issuers of candidates in a group also guarantee their availability.
+	///
+	/// If so, the issuance of a candidate counts as an availability vote of its
+	/// issuer, provided the issuer is an availability guarantor of the group.
+	/// The issuer's validity vote is always seeded by the issuance.
+	fn issuer_guarantees_availability(&self, _group: &Self::GroupId) -> bool { false }
+
+	/// The value of a candidate to the relay chain, e.g. fees paid.
+	/// Only consulted by `SelectionStrategy::HighestValue`.
+	fn candidate_value(_candidate: &Self::Candidate) -> u64 { 0 }
+
+	/// How to choose which candidate of a group to propose when several are
+	/// includable. Defaults to the least candidate by `Ord`.
+	fn selection_strategy(&self) -> SelectionStrategy { SelectionStrategy::LeastByOrd }
+}
+
+/// Strategies for choosing which of several includable candidates of a group
+/// to propose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionStrategy {
+	/// The least candidate by `Ord`.
+	LeastByOrd,
+	/// The candidate with the greatest weight of validity votes.
+	MostVotes,
+	/// The candidate which was imported into the table first.
+	EarliestSeen,
+	/// The candidate with the highest `Context::candidate_value`.
+	HighestValue,
+}
+
+impl SelectionStrategy {
+	/// Whether candidate `a` should be proposed over `b` from the same group.
+	/// Ties are broken in favor of the lesser candidate by `Ord`.
+	pub fn prefer<C: Context>(&self, context: &C, a: &CandidateData<C>, b: &CandidateData<C>) -> bool {
+		use std::cmp::Ordering;
+
+		let ordering = match *self {
+			SelectionStrategy::LeastByOrd => Ordering::Equal,
+			SelectionStrategy::MostVotes =>
+				a.validity_weight(context).cmp(&b.validity_weight(context)),
+			SelectionStrategy::EarliestSeen => b.seen_index.cmp(&a.seen_index),
+			SelectionStrategy::HighestValue =>
+				C::candidate_value(&a.candidate).cmp(&C::candidate_value(&b.candidate)),
+		};
+
+		ordering.then_with(|| b.candidate.cmp(&a.candidate)) == Ordering::Greater
+	}
 }
 
 /// Statements circulated among peers.
@@ -101,6 +166,12 @@ pub enum Statement<C, D> {
 	/// Broadcast by a authority to attest that the candidate with given digest
 	/// is invalid.
 	Invalid(D),
+	/// Broadcast by the issuer of a candidate to retract it before inclusion,
+	/// e.g. after a collator re-org. Only takes effect while no other authority
+	/// has voted on the candidate's validity.
+	///
+	/// Withdrawing another authority's candidate is not allowed.
+	Withdraw(D),
 }
 
 /// A signed statement.
@@ -125,13 +196,15 @@ pub struct SignedStatement<C, D, V, S> {
 #[derive(Hash, PartialEq, Eq, Clone)]
 enum StatementTrace<V, D> {
 	/// The candidate proposed by the authority.
-	Candidate(V),
+	Candidate(V, D),
 	/// A validity statement from that authority about the given digest.
 	Valid(V, D),
 	/// An invalidity statement from that authority about the given digest.
 	Invalid(V, D),
 	/// An availability statement from that authority about the given digest.
 	Available(V, D),
+	/// A withdrawal by that authority of the candidate with the given digest.
+	Withdraw(V, D),
 }
 
 /// Misbehavior: voting more than one way on candidate validity.
@@ -213,6 +286,82 @@ pub struct Summary<D, G> {
 	pub signalled_bad: bool,
 }
 
+/// A snapshot of the state of the whole table, for reporting agreement progress.
+#[derive(Clone, PartialEq, Eq)]
+pub struct TableSummary<D, G> {
+	/// Number of candidates submitted in each group.
+	pub candidates_per_group: BTreeMap<G, usize>,
+	/// Summaries of votes on every candidate.
+	pub candidates: Vec<Summary<D, G>>,
+	/// Number of groups with at least one includable candidate.
+	pub includable_groups: usize,
+	/// Number of statements imported without effect, e.g. duplicates or
+	/// votes on unknown candidates.
+	pub dropped_statements: usize,
+	/// Number of distinct offenses witnessed.
+	pub misbehavior_count: usize,
+	/// Number of authorities which committed at least one offense.
+	pub misbehaving_authorities: usize,
+}
+
+/// Authorities which have not yet voted on a candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingVoters<A> {
+	/// Group members which have not submitted a validity or invalidity vote.
+	pub validity: Vec<A>,
+	/// Availability guarantors which have not attested availability.
+	pub availability: Vec<A>,
+}
+
+/// Why a candidate in a proposal lacks the backing needed for inclusion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MissingBacking {
+	/// The candidate is not known to the table.
+	Unknown,
+	/// The candidate was withdrawn by its issuer.
+	Withdrawn,
+	/// At least one authority attested to the candidate's invalidity.
+	IndicatedBad,
+	/// The weight of validity or availability votes is below the group's threshold.
+	InsufficientVotes {
+		/// Accumulated weight of validity votes.
+		validity_weight: usize,
+		/// Validity weight required for inclusion.
+		validity_threshold: usize,
+		/// Accumulated weight of availability votes.
+		availability_weight: usize,
+		/// Availability weight required for inclusion.
+		availability_threshold: usize,
+	},
+	/// An earlier candidate in the proposal is from the same group.
+	DuplicateGroup,
+}
+
+/// A candidate in a proposal which lacks backing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnbackedCandidate<D> {
+	/// The position of the candidate in the proposal.
+	pub index: usize,
+	/// The digest of the candidate.
+	pub digest: D,
+	/// Why the candidate can't be included.
+	pub reason: MissingBacking,
+}
+
+/// The result of checking a proposed set of candidates against the table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProposalCheck<D> {
+	/// Candidates which lack backing, in the order they were proposed.
+	pub unbacked: Vec<UnbackedCandidate<D>>,
+}
+
+impl<D> ProposalCheck<D> {
+	/// Whether every proposed candidate is backed.
+	pub fn is_backed(&self) -> bool {
+		self.unbacked.is_empty()
+	}
+}
+
 /// Stores votes and data about a candidate.
 pub struct CandidateData<C: Context> {
 	group_id: C::GroupId,
@@ -220,6 +369,7 @@ pub struct CandidateData<C: Context> {
 	validity_votes: HashMap<C::AuthorityId, ValidityVote<C::Signature>>,
 	availability_votes: HashMap<C::AuthorityId, C::Signature>,
 	indicated_bad_by: Vec<C::AuthorityId>,
+	seen_index: usize,
 }
 
 impl<C: Context> CandidateData<C> {
@@ -228,13 +378,98 @@ impl<C: Context> CandidateData<C> {
 		!self.indicated_bad_by.is_empty()
 	}
 
+	/// The candidate itself.
+	pub fn candidate(&self) -> &C::Candidate {
+		&self.candidate
+	}
+
+	/// The group the candidate is in.
+	pub fn group_id(&self) -> &C::GroupId {
+		&self.group_id
+	}
+
+	/// The order in which the candidate was first imported into the table.
+	pub fn seen_index(&self) -> usize {
+		self.seen_index
+	}
+
+	/// The authority which issued the candidate.
+	pub fn issuer(&self) -> Option<&C::AuthorityId> {
+		self.validity_votes.iter()
+			.find(|&(_, v)| if let ValidityVote::Issued(_) = *v { true } else { false })
+			.map(|(a, _)| a)
+	}
+
+	/// Authorities which have attested to validity, including the issuer.
+	pub fn validity_voters<'a>(&'a self) -> impl Iterator<Item=&'a C::AuthorityId> + 'a {
+		self.validity_votes.iter()
+			.filter(|&(_, v)| if let ValidityVote::Invalid(_) = *v { false } else { true })
+			.map(|(a, _)| a)
+	}
+
+	/// Authorities which have attested to invalidity.
+	pub fn invalidity_voters(&self) -> &[C::AuthorityId] {
+		&self.indicated_bad_by[..]
+	}
+
+	/// Authorities which have attested to availability.
+	pub fn availability_voters<'a>(&'a self) -> impl Iterator<Item=&'a C::AuthorityId> + 'a {
+		self.availability_votes.keys()
+	}
+
 	// Candidate data can be included in a proposal
-	// if it has enough validity and availability votes
+	// if it has enough weight of validity and availability votes
 	// and no authorities have called it bad.
-	fn can_be_included(&self, validity_threshold: usize, availability_threshold: usize) -> bool {
+	fn can_be_included(&self, context: &C, validity_threshold: usize, availability_threshold: usize) -> bool {
 		self.indicated_bad_by.is_empty()
-			&& self.validity_votes.len() >= validity_threshold
-			&& self.availability_votes.len() >= availability_threshold
+			&& self.validity_weight(context) >= validity_threshold
+			&& self.availability_weight(context) >= availability_threshold
+	}
+
+	// why the candidate can't be included, if it can't.
+	fn missing_backing(&self, context: &C) -> Option<MissingBacking> {
+		let (validity_threshold, availability_threshold) = context.requisite_votes(&self.group_id);
+		if self.indicated_bad() {
+			return Some(MissingBacking::IndicatedBad);
+		}
+
+		let validity_weight = self.validity_weight(context);
+		let availability_weight = self.availability_weight(context);
+		if validity_weight >= validity_threshold && availability_weight >= availability_threshold {
+			return None;
+		}
+
+		Some(MissingBacking::InsufficientVotes {
+			validity_weight,
+			validity_threshold,
+			availability_weight,
+			availability_threshold,
+		})
+	}
+
+	// accumulated weight of all validity votes.
+	fn validity_weight(&self, context: &C) -> usize {
+		self.validity_votes.keys().map(|a| context.authority_weight(a)).sum()
+	}
+
+	// accumulated weight of all availability votes, including the issuer's implicit one.
+	fn availability_weight(&self, context: &C) -> usize {
+		let implicit = self.implicit_availability_voter(context)
+			.map_or(0, |issuer| context.authority_weight(issuer));
+
+		implicit + self.availability_votes.keys().map(|a| context.authority_weight(a)).sum::<usize>()
+	}
+
+	// the issuer, if its issuance counts as an availability vote it hasn't also cast explicitly.
+	fn implicit_availability_voter(&self, context: &C) -> Option<&C::AuthorityId> {
+		if !context.issuer_guarantees_availability(&self.group_id) { return None }
+
+		self.issuer().and_then(|issuer| {
+			let counts = context.is_availability_guarantor_of(issuer, &self.group_id)
+				&& !self.availability_votes.contains_key(issuer);
+
+			if counts { Some(issuer) } else { None }
+		})
 	}
 
 	fn summary(&self, digest: C::Digest) -> Summary<C::Digest, C::GroupId> {
@@ -264,11 +499,18 @@ impl<C: Context> Default for AuthorityData<C> {
 }
 
 /// Stores votes
+///
+/// Candidates are indexed by group, so that queries about a single group only
+/// touch that group's candidates and whole groups can be pruned at once.
 pub struct Table<C: Context> {
 	authority_data: HashMap<C::AuthorityId, AuthorityData<C>>,
-	detected_misbehavior: HashMap<C::AuthorityId, <C as ResolveMisbehavior>::Misbehavior>,
+	detected_misbehavior: HashMap<C::AuthorityId, Vec<<C as ResolveMisbehavior>::Misbehavior>>,
 	candidate_votes: HashMap<C::Digest, CandidateData<C>>,
+	group_candidates: HashMap<C::GroupId, HashSet<C::Digest>>,
 	includable_count: HashMap<C::GroupId, usize>,
+	dropped_statements: usize,
+	candidates_seen: usize,
+	withdrawn: HashMap<C::GroupId, HashMap<C::Digest, (C::AuthorityId, C::Signature)>>,
 }
 
 impl<C: Context> Default for Table<C> {
@@ -277,7 +519,11 @@ impl<C: Context> Default for Table<C> {
 			authority_data: HashMap::new(),
 			detected_misbehavior: HashMap::new(),
 			candidate_votes: HashMap::new(),
+			group_candidates: HashMap::new(),
 			includable_count: HashMap::new(),
+			dropped_statements: 0,
+			candidates_seen: 0,
+			withdrawn: HashMap::new(),
 		}
 	}
 }
@@ -286,49 +532,162 @@ impl<C: Context> Table<C> {
 	/// Produce a set of proposed candidates.
 	///
 	/// This will be at most one per group, consisting of the
-	/// best candidate for each group with requisite votes for inclusion.
+	/// best candidate for each group with requisite votes for inclusion,
+	/// as chosen by the context's `SelectionStrategy`.
 	///
 	/// The vector is sorted in ascending order by group id.
 	pub fn proposed_candidates<'a>(&'a self, context: &C) -> Vec<&'a C::Candidate> {
-		use std::collections::BTreeMap;
 		use std::collections::btree_map::Entry as BTreeEntry;
 
+		let strategy = context.selection_strategy();
 		let mut best_candidates = BTreeMap::new();
-		for candidate_data in self.candidate_votes.values() {
-			let group_id = &candidate_data.group_id;
-
-			if !self.includable_count.contains_key(group_id) {
-				continue
-			}
 
+		// only groups with an includable candidate need to be looked at.
+		for group_id in self.includable_count.keys() {
 			let (validity_t, availability_t) = context.requisite_votes(group_id);
 
-			if !candidate_data.can_be_included(validity_t, availability_t) { continue }
-			let candidate = &candidate_data.candidate;
-			match best_candidates.entry(group_id.clone()) {
-				BTreeEntry::Occupied(mut occ) => {
-					let candidate_ref = occ.get_mut();
-					if *candidate_ref > candidate {
-						*candidate_ref = candidate;
+			for (_, candidate_data) in self.group_candidates(group_id) {
+				if !candidate_data.can_be_included(context, validity_t, availability_t) { continue }
+				match best_candidates.entry(group_id.clone()) {
+					BTreeEntry::Occupied(mut occ) => {
+						let best_ref = occ.get_mut();
+						if strategy.prefer(context, candidate_data, *best_ref) {
+							*best_ref = candidate_data;
+						}
 					}
+					BTreeEntry::Vacant(vacant) => { vacant.insert(candidate_data); },
 				}
-				BTreeEntry::Vacant(vacant) => { vacant.insert(candidate); },
 			}
 		}
 
-		best_candidates.values().cloned().collect::<Vec<_>>()
+		best_candidates.values().map(|data| &data.candidate).collect::<Vec<_>>()
 	}
 
 	/// Whether a candidate can be included.
 	pub fn candidate_includable(&self, digest: &C::Digest, context: &C) -> bool {
 		self.candidate_votes.get(digest).map_or(false, |data| {
 			let (v_threshold, a_threshold) = context.requisite_votes(&data.group_id);
-			data.can_be_included(v_threshold, a_threshold)
+			data.can_be_included(context, v_threshold, a_threshold)
+		})
+	}
+
+	/// Check a proposed set of candidates, e.g. from a peer's block proposal,
+	/// against the votes known locally. Every candidate must be includable, and
+	/// at most one may be proposed per group.
+	pub fn check_proposal(&self, context: &C, candidates: &[C::Candidate]) -> ProposalCheck<C::Digest> {
+		let mut groups = HashSet::new();
+		let mut unbacked = Vec::new();
+
+		for (index, candidate) in candidates.iter().enumerate() {
+			let digest = C::candidate_digest(candidate);
+			let group = C::candidate_group(candidate);
+			let reason = if !groups.insert(group.clone()) {
+				Some(MissingBacking::DuplicateGroup)
+			} else if self.is_withdrawn(&group, &digest) {
+				Some(MissingBacking::Withdrawn)
+			} else {
+				match self.candidate_votes.get(&digest) {
+					None => Some(MissingBacking::Unknown),
+					Some(data) => data.missing_backing(context),
+				}
+			};
+
+			if let Some(reason) = reason {
+				unbacked.push(UnbackedCandidate { index, digest, reason });
+			}
+		}
+
+		ProposalCheck { unbacked }
+	}
+
+	/// Export the fewest signed statements needed to reconstruct every includable
+	/// candidate in the groups passing `filter`, e.g. to bring a validator which
+	/// joined mid-round up to speed without replaying all gossip.
+	///
+	/// For each candidate this is its issuance followed by the heaviest validity
+	/// and availability votes until the group's thresholds are met. Importing the
+	/// statements in order into an empty table makes the same candidates includable.
+	pub fn export_statements<F>(&self, context: &C, filter: F)
+		-> Vec<SignedStatement<C::Candidate, C::Digest, C::AuthorityId, C::Signature>>
+		where F: Fn(&C::GroupId) -> bool
+	{
+		let mut statements = Vec::new();
+		let groups = self.group_candidates.keys().filter(|group| filter(group));
+		for (digest, data) in groups.flat_map(|group| self.group_candidates(group)) {
+
+			let (validity_t, availability_t) = context.requisite_votes(&data.group_id);
+			if !data.can_be_included(context, validity_t, availability_t) { continue }
+
+			let mut validity_weight = 0;
+			let mut valid = Vec::new();
+			for (authority, vote) in data.validity_votes.iter() {
+				match *vote {
+					ValidityVote::Issued(ref sig) => {
+						validity_weight += context.authority_weight(authority);
+						statements.push(SignedStatement {
+							statement: Statement::Candidate(data.candidate.clone()),
+							signature: sig.clone(),
+							sender: authority.clone(),
+						});
+					}
+					ValidityVote::Valid(ref sig) => valid.push((authority, sig)),
+					ValidityVote::Invalid(_) => {}
+				}
+			}
+
+			valid.sort_by_key(|&(a, _)| ::std::cmp::Reverse(context.authority_weight(a)));
+			for (authority, sig) in valid {
+				if validity_weight >= validity_t { break }
+
+				validity_weight += context.authority_weight(authority);
+				statements.push(SignedStatement {
+					statement: Statement::Valid(digest.clone()),
+					signature: sig.clone(),
+					sender: authority.clone(),
+				});
+			}
+
+			let mut available: Vec<_> = data.availability_votes.iter().collect();
+			available.sort_by_key(|&(a, _)| ::std::cmp::Reverse(context.authority_weight(a)));
+
+			// the issuance exported above carries the issuer's implicit vote.
+			let mut availability_weight = data.implicit_availability_voter(context)
+				.map_or(0, |issuer| context.authority_weight(issuer));
+			for (authority, sig) in available {
+				if availability_weight >= availability_t { break }
+
+				availability_weight += context.authority_weight(authority);
+				statements.push(SignedStatement {
+					statement: Statement::Available(digest.clone()),
+					signature: sig.clone(),
+					sender: authority.clone(),
+				});
+			}
+		}
+
+		statements
+	}
+
+	/// Get the authorities which have yet to vote on validity or availability of a candidate.
+	/// `None` if the candidate is unknown.
+	pub fn missing_voters(&self, digest: &C::Digest, context: &C) -> Option<MissingVoters<C::AuthorityId>> {
+		self.candidate_votes.get(digest).map(|data| {
+			let (members, guarantors) = context.group_authorities(&data.group_id);
+			let implicit = data.implicit_availability_voter(context);
+
+			MissingVoters {
+				validity: members.into_iter()
+					.filter(|a| !data.validity_votes.contains_key(a))
+					.collect(),
+				availability: guarantors.into_iter()
+					.filter(|a| !data.availability_votes.contains_key(a) && implicit != Some(a))
+					.collect(),
+			}
 		})
 	}
 
-	/// Import a signed statement. Signatures should be checked for validity, and the
-	/// sender should be checked to actually be a authority.
+	/// Import a signed statement. Statements whose signature the context doesn't
+	/// verify are dropped.
 	///
 	/// This can note the origin of the statement to indicate that he has
 	/// seen it already.
@@ -337,14 +696,31 @@ impl<C: Context> Table<C> {
 		context: &C,
 		statement: SignedStatement<C::Candidate, C::Digest, C::AuthorityId, C::Signature>,
 		from: Option<C::AuthorityId>
+	) -> Option<Summary<C::Digest, C::GroupId>> {
+		if !context.verify_statement(&statement) {
+			self.dropped_statements += 1;
+			return None;
+		}
+
+		self.import_verified_statement(context, statement, from)
+	}
+
+	/// Import a signed statement without verifying its signature, e.g. as part
+	/// of a batch which has already been verified.
+	pub fn import_verified_statement(
+		&mut self,
+		context: &C,
+		statement: SignedStatement<C::Candidate, C::Digest, C::AuthorityId, C::Signature>,
+		from: Option<C::AuthorityId>
 	) -> Option<Summary<C::Digest, C::GroupId>> {
 		let SignedStatement { statement, signature, sender: signer } = statement;
 
 		let trace = match statement {
-			Statement::Candidate(_) => StatementTrace::Candidate(signer.clone()),
+			Statement::Candidate(ref c) => StatementTrace::Candidate(signer.clone(), C::candidate_digest(c)),
 			Statement::Valid(ref d) => StatementTrace::Valid(signer.clone(), d.clone()),
 			Statement::Invalid(ref d) => StatementTrace::Invalid(signer.clone(), d.clone()),
 			Statement::Available(ref d) => StatementTrace::Available(signer.clone(), d.clone()),
+			Statement::Withdraw(ref d) => StatementTrace::Withdraw(signer.clone(), d.clone()),
 		};
 
 		let (maybe_misbehavior, maybe_summary) = match statement {
@@ -372,13 +748,26 @@ impl<C: Context> Table<C> {
 				digest,
 				signature,
 			),
+			Statement::Withdraw(digest) => self.withdraw_candidate(
+				context,
+				signer.clone(),
+				digest,
+				signature,
+			),
 		};
 
 		if let Some(misbehavior) = maybe_misbehavior {
 			// all misbehavior in agreement is provable and actively malicious.
-			// punishments are not cumulative.
-			self.detected_misbehavior.insert(signer, misbehavior);
+			// each distinct offense is kept so it can be reported separately.
+			let offenses = self.detected_misbehavior.entry(signer).or_insert_with(Vec::new);
+			if !offenses.contains(&misbehavior) {
+				offenses.push(misbehavior);
+			}
 		} else {
+			if maybe_summary.is_none() {
+				self.dropped_statements += 1;
+			}
+
 			if let Some(from) = from {
 				self.note_trace_seen(trace.clone(), from);
 			}
@@ -394,18 +783,88 @@ impl<C: Context> Table<C> {
 		self.candidate_votes.get(digest).map(|d| &d.candidate)
 	}
 
-	/// Access all witnessed misbehavior.
+	/// Get the data of all candidates submitted in a group, along with their digests.
+	pub fn group_candidates<'a>(&'a self, group: &'a C::GroupId)
+		-> impl Iterator<Item=(&'a C::Digest, &'a CandidateData<C>)> + 'a
+	{
+		let candidate_votes = &self.candidate_votes;
+		self.group_candidates.get(group)
+			.into_iter()
+			.flat_map(|digests| digests.iter())
+			.filter_map(move |digest| candidate_votes.get(digest).map(|data| (digest, data)))
+	}
+
+	/// Drop all candidates of a group and the votes on them, e.g. once the
+	/// group is no longer of interest. Returns the number of candidates dropped.
+	///
+	/// Statements about the dropped candidates imported afterwards are treated
+	/// as if the candidates had never been seen.
+	pub fn prune_group(&mut self, group: &C::GroupId) -> usize {
+		// withdrawn candidates are no longer in `group_candidates`.
+		self.withdrawn.remove(group);
+
+		let digests = match self.group_candidates.remove(group) {
+			Some(digests) => digests,
+			None => return 0,
+		};
+
+		for digest in &digests {
+			self.candidate_votes.remove(digest);
+		}
+
+		// the existence of a proposal implies the existence of its candidate.
+		for data in self.authority_data.values_mut() {
+			if data.proposal.as_ref().map_or(false, |&(ref d, _)| digests.contains(d)) {
+				data.proposal = None;
+			}
+		}
+
+		self.includable_count.remove(group);
+		digests.len()
+	}
+
+	/// Access all witnessed misbehavior, grouped by offender.
 	pub fn get_misbehavior(&self)
-		-> &HashMap<C::AuthorityId, <C as ResolveMisbehavior>::Misbehavior>
+		-> &HashMap<C::AuthorityId, Vec<<C as ResolveMisbehavior>::Misbehavior>>
 	{
 		&self.detected_misbehavior
 	}
 
+	/// Drain all witnessed misbehavior, yielding one entry per distinct offense.
+	///
+	/// An authority which committed several offenses will appear once for each.
+	pub fn drain_misbehavior(&mut self) -> Vec<(C::AuthorityId, <C as ResolveMisbehavior>::Misbehavior)> {
+		self.detected_misbehavior.drain()
+			.flat_map(|(authority, offenses)| {
+				offenses.into_iter().map(move |offense| (authority.clone(), offense))
+			})
+			.collect()
+	}
+
 	/// Get the current number of parachains with includable candidates.
 	pub fn includable_count(&self) -> usize {
 		self.includable_count.len()
 	}
 
+	/// Get a snapshot of the candidates, votes, and misbehavior witnessed so far.
+	pub fn summary(&self) -> TableSummary<C::Digest, C::GroupId> {
+		let mut candidates_per_group = BTreeMap::new();
+		for data in self.candidate_votes.values() {
+			*candidates_per_group.entry(data.group_id.clone()).or_insert(0) += 1;
+		}
+
+		TableSummary {
+			candidates_per_group,
+			candidates: self.candidate_votes.iter()
+				.map(|(digest, data)| data.summary(digest.clone()))
+				.collect(),
+			includable_groups: self.includable_count.len(),
+			dropped_statements: self.dropped_statements,
+			misbehavior_count: self.detected_misbehavior.values().map(|o| o.len()).sum(),
+			misbehaving_authorities: self.detected_misbehavior.len(),
+		}
+	}
+
 	/// Fill a statement batch and note messages as seen by the targets.
 	pub fn fill_batch<B>(&mut self, batch: &mut B)
 		where B: StatementBatch<
@@ -490,7 +949,7 @@ impl<C: Context> Table<C> {
 				match *vote {
 					ValidityVote::Issued(ref sig) => {
 						attempt_send!(
-							StatementTrace::Candidate(sender.clone()),
+							StatementTrace::Candidate(sender.clone(), digest.clone()),
 							sender = sender.clone(),
 							sig = sig.clone(),
 							statement = Statement::Candidate(candidate.candidate.clone())
@@ -527,6 +986,20 @@ impl<C: Context> Table<C> {
 			}
 		}
 
+		// withdrawals, so peers which saw the candidate drop it as well.
+		for (digest, &(ref sender, ref sig)) in self.withdrawn.values().flat_map(|w| w.iter()) {
+			attempt_send!(
+				StatementTrace::Withdraw(sender.clone(), digest.clone()),
+				sender = sender.clone(),
+				sig = sig.clone(),
+				statement = Statement::Withdraw(digest.clone())
+			)
+		}
+
+	}
+
+	fn is_withdrawn(&self, group: &C::GroupId, digest: &C::Digest) -> bool {
+		self.withdrawn.get(group).map_or(false, |w| w.contains_key(digest))
 	}
 
 	fn note_trace_seen(&mut self, trace: StatementTrace<C::AuthorityId, C::Digest>, known_by: C::AuthorityId) {
@@ -560,6 +1033,11 @@ impl<C: Context> Table<C> {
 		// check that authority hasn't already specified another candidate.
 		let digest = C::candidate_digest(&candidate);
 
+		// withdrawn candidates stay withdrawn, even if their issuance is seen again.
+		if self.is_withdrawn(&group, &digest) {
+			return (None, None);
+		}
+
 		let new_proposal = match self.authority_data.entry(from.clone()) {
 			Entry::Occupied(mut occ) => {
 				// if digest is different, fetch candidate and
@@ -605,12 +1083,21 @@ impl<C: Context> Table<C> {
 		// NOTE: altering this code may affect the existence proof above. ensure it remains
 		// valid.
 		if new_proposal {
-			self.candidate_votes.entry(digest.clone()).or_insert_with(move || CandidateData {
-				group_id: group,
-				candidate: candidate,
-				validity_votes: HashMap::new(),
-				availability_votes: HashMap::new(),
-				indicated_bad_by: Vec::new(),
+			self.group_candidates.entry(group.clone())
+				.or_insert_with(HashSet::new)
+				.insert(digest.clone());
+
+			let seen_index = &mut self.candidates_seen;
+			self.candidate_votes.entry(digest.clone()).or_insert_with(move || {
+				*seen_index += 1;
+				CandidateData {
+					group_id: group,
+					candidate: candidate,
+					validity_votes: HashMap::new(),
+					availability_votes: HashMap::new(),
+					indicated_bad_by: Vec::new(),
+					seen_index: *seen_index - 1,
+				}
 			});
 		}
 
@@ -635,7 +1122,7 @@ impl<C: Context> Table<C> {
 		};
 
 		let (v_threshold, a_threshold) = context.requisite_votes(&votes.group_id);
-		let was_includable = votes.can_be_included(v_threshold, a_threshold);
+		let was_includable = votes.can_be_included(context, v_threshold, a_threshold);
 
 		// check that this authority actually can vote in this group.
 		if !context.is_member_of(&from, &votes.group_id) {
@@ -701,7 +1188,7 @@ impl<C: Context> Table<C> {
 			}
 		}
 
-		let is_includable = votes.can_be_included(v_threshold, a_threshold);
+		let is_includable = votes.can_be_included(context, v_threshold, a_threshold);
 		update_includable_count(&mut self.includable_count, &votes.group_id, was_includable, is_includable);
 
 		(None, Some(votes.summary(digest)))
@@ -720,7 +1207,7 @@ impl<C: Context> Table<C> {
 		};
 
 		let (v_threshold, a_threshold) = context.requisite_votes(&votes.group_id);
-		let was_includable = votes.can_be_included(v_threshold, a_threshold);
+		let was_includable = votes.can_be_included(context, v_threshold, a_threshold);
 
 		// check that this authority actually can vote in this group.
 		if !context.is_availability_guarantor_of(&from, &votes.group_id) {
@@ -738,11 +1225,74 @@ impl<C: Context> Table<C> {
 
 		votes.availability_votes.insert(from, signature);
 
-		let is_includable = votes.can_be_included(v_threshold, a_threshold);
+		let is_includable = votes.can_be_included(context, v_threshold, a_threshold);
 		update_includable_count(&mut self.includable_count, &votes.group_id, was_includable, is_includable);
 
 		(None, Some(votes.summary(digest)))
 	}
+
+	fn withdraw_candidate(
+		&mut self,
+		context: &C,
+		from: C::AuthorityId,
+		digest: C::Digest,
+		signature: C::Signature,
+	) -> (Option<<C as ResolveMisbehavior>::Misbehavior>, Option<Summary<C::Digest, C::GroupId>>) {
+		let (summary, was_includable) = match self.candidate_votes.get(&digest) {
+			None => return (None, None),
+			Some(votes) => {
+				// the candidate may have been issued by several authorities,
+				// any of which may withdraw it.
+				let issued_by_sender = match votes.validity_votes.get(&from) {
+					Some(&ValidityVote::Issued(_)) => true,
+					_ => false,
+				};
+
+				if !issued_by_sender {
+					return (
+						Some(Misbehavior::UnauthorizedStatement(UnauthorizedStatement {
+							statement: SignedStatement {
+								signature,
+								statement: Statement::Withdraw(digest),
+								sender: from,
+							}
+						})),
+						None,
+					);
+				}
+
+				// once others have vouched for or against the candidate, it
+				// can no longer be retracted.
+				if votes.validity_votes.len() > 1 {
+					return (None, None);
+				}
+
+				let (v_threshold, a_threshold) = context.requisite_votes(&votes.group_id);
+				(votes.summary(digest.clone()), votes.can_be_included(context, v_threshold, a_threshold))
+			}
+		};
+
+		self.candidate_votes.remove(&digest);
+		if let Entry::Occupied(mut digests) = self.group_candidates.entry(summary.group_id.clone()) {
+			digests.get_mut().remove(&digest);
+			if digests.get().is_empty() {
+				digests.remove();
+			}
+		}
+		update_includable_count(&mut self.includable_count, &summary.group_id, was_includable, false);
+
+		// free the issuer to propose a superseding candidate.
+		if let Some(data) = self.authority_data.get_mut(&from) {
+			if data.proposal.as_ref().map_or(false, |&(ref d, _)| d == &digest) {
+				data.proposal = None;
+			}
+		}
+
+		self.withdrawn.entry(summary.group_id.clone())
+			.or_insert_with(HashMap::new)
+			.insert(digest, (from, signature));
+		(None, Some(summary))
+	}
 }
 
 fn update_includable_count<G: Hash + Eq + Clone>(map: &mut HashMap<G, usize>, group_id: &G, was_includable: bool, is_includable: bool) {
@@ -842,6 +1392,25 @@ mod tests {
 			self.authorities.get(authority).map(|v| &v.1 == group).unwrap_or(false)
 		}
 
+		fn group_authorities(&self, group: &GroupId) -> (Vec<AuthorityId>, Vec<AuthorityId>) {
+			let mut members = Vec::new();
+			let mut guarantors = Vec::new();
+
+			for (authority, &(ref validity, ref availability)) in &self.authorities {
+				if validity == group { members.push(*authority) }
+				if availability == group { guarantors.push(*authority) }
+			}
+
+			members.sort_by_key(|a| a.0);
+			guarantors.sort_by_key(|a| a.0);
+			(members, guarantors)
+		}
+
+		// test signatures are valid if they carry the sender's index.
+		fn verify_statement(&self, statement: &SignedStatement<Candidate, Digest, AuthorityId, Signature>) -> bool {
+			statement.signature.0 == statement.sender.0
+		}
+
 		fn requisite_votes(&self, id: &GroupId) -> (usize, usize) {
 			let mut total_validity = 0;
 			let mut total_availability = 0;
@@ -883,7 +1452,7 @@ mod tests {
 
 		table.import_statement(&context, statement_b, None);
 		assert_eq!(
-			table.detected_misbehavior.get(&AuthorityId(1)).unwrap(),
+			&table.detected_misbehavior[&AuthorityId(1)][0],
 			&Misbehavior::MultipleCandidates(MultipleCandidates {
 				first: (Candidate(2, 100), Signature(1)),
 				second: (Candidate(2, 999), Signature(1)),
@@ -892,109 +1461,394 @@ mod tests {
 	}
 
 	#[test]
-	fn submitting_candidate_from_wrong_group_is_misbehavior() {
+	fn issuer_can_withdraw_and_supersede_candidate() {
 		let context = TestContext {
 			authorities: {
 				let mut map = HashMap::new();
-				map.insert(AuthorityId(1), (GroupId(3), GroupId(455)));
+				map.insert(AuthorityId(1), (GroupId(2), GroupId(455)));
 				map
 			}
 		};
 
 		let mut table = create();
-		let statement = SignedStatement {
-			statement: Statement::Candidate(Candidate(2, 100)),
+		let candidate_a = Candidate(2, 100);
+		let digest_a = Digest(100);
+
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Candidate(candidate_a.clone()),
 			signature: Signature(1),
 			sender: AuthorityId(1),
-		};
+		}, None);
 
-		table.import_statement(&context, statement, None);
+		let summary = table.import_statement(&context, SignedStatement {
+			statement: Statement::Withdraw(digest_a.clone()),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		}, None).expect("withdrawal of own candidate succeeds");
 
-		assert_eq!(
-			table.detected_misbehavior.get(&AuthorityId(1)).unwrap(),
-			&Misbehavior::UnauthorizedStatement(UnauthorizedStatement {
-				statement: SignedStatement {
-					statement: Statement::Candidate(Candidate(2, 100)),
-					signature: Signature(1),
-					sender: AuthorityId(1),
-				},
-			})
-		);
+		assert_eq!(summary.candidate, digest_a);
+		assert!(table.get_candidate(&digest_a).is_none());
+
+		// seeing the withdrawn candidate again doesn't bring it back.
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Candidate(candidate_a),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		}, None);
+		assert!(table.get_candidate(&digest_a).is_none());
+
+		// a superseding candidate is not a double proposal.
+		let candidate_b = Candidate(2, 999);
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Candidate(candidate_b.clone()),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		}, None);
+
+		assert!(!table.detected_misbehavior.contains_key(&AuthorityId(1)));
+		assert!(table.get_candidate(&Digest(999)).is_some());
 	}
 
 	#[test]
-	fn unauthorized_votes() {
+	fn withdrawal_refused_after_external_votes() {
 		let context = TestContext {
 			authorities: {
 				let mut map = HashMap::new();
 				map.insert(AuthorityId(1), (GroupId(2), GroupId(455)));
-				map.insert(AuthorityId(2), (GroupId(3), GroupId(222)));
+				map.insert(AuthorityId(2), (GroupId(2), GroupId(455)));
 				map
 			}
 		};
 
 		let mut table = create();
+		let candidate = Candidate(2, 100);
+		let digest = Digest(100);
 
-		let candidate_a = SignedStatement {
-			statement: Statement::Candidate(Candidate(2, 100)),
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Candidate(candidate),
 			signature: Signature(1),
 			sender: AuthorityId(1),
-		};
-		let candidate_a_digest = Digest(100);
+		}, None);
 
-		let candidate_b = SignedStatement {
-			statement: Statement::Candidate(Candidate(3, 987)),
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Valid(digest.clone()),
 			signature: Signature(2),
 			sender: AuthorityId(2),
-		};
-		let candidate_b_digest = Digest(987);
-
-		table.import_statement(&context, candidate_a, None);
-		table.import_statement(&context, candidate_b, None);
-		assert!(!table.detected_misbehavior.contains_key(&AuthorityId(1)));
-		assert!(!table.detected_misbehavior.contains_key(&AuthorityId(2)));
+		}, None);
 
-		// authority 1 votes for availability on 2's candidate.
-		let bad_availability_vote = SignedStatement {
-			statement: Statement::Available(candidate_b_digest.clone()),
+		let summary = table.import_statement(&context, SignedStatement {
+			statement: Statement::Withdraw(digest.clone()),
 			signature: Signature(1),
 			sender: AuthorityId(1),
-		};
-		table.import_statement(&context, bad_availability_vote, None);
-
-		assert_eq!(
-			table.detected_misbehavior.get(&AuthorityId(1)).unwrap(),
-			&Misbehavior::UnauthorizedStatement(UnauthorizedStatement {
-				statement: SignedStatement {
-					statement: Statement::Available(candidate_b_digest),
-					signature: Signature(1),
-					sender: AuthorityId(1),
-				},
-			})
-		);
-
-		// authority 2 votes for validity on 1's candidate.
-		let bad_validity_vote = SignedStatement {
-			statement: Statement::Valid(candidate_a_digest.clone()),
-			signature: Signature(2),
-			sender: AuthorityId(2),
-		};
-		table.import_statement(&context, bad_validity_vote, None);
+		}, None);
 
-		assert_eq!(
-			table.detected_misbehavior.get(&AuthorityId(2)).unwrap(),
-			&Misbehavior::UnauthorizedStatement(UnauthorizedStatement {
-				statement: SignedStatement {
-					statement: Statement::Valid(candidate_a_digest),
-					signature: Signature(2),
-					sender: AuthorityId(2),
-				},
-			})
-		);
+		assert!(summary.is_none());
+		assert!(table.get_candidate(&digest).is_some());
+		assert!(!table.detected_misbehavior.contains_key(&AuthorityId(1)));
 	}
 
 	#[test]
-	fn validity_double_vote_is_misbehavior() {
+	fn withdrawing_others_candidate_is_misbehavior() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				map.insert(AuthorityId(1), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(2), (GroupId(2), GroupId(455)));
+				map
+			}
+		};
+
+		let mut table = create();
+		let candidate = Candidate(2, 100);
+		let digest = Digest(100);
+
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Candidate(candidate),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		}, None);
+
+		let withdrawal = SignedStatement {
+			statement: Statement::Withdraw(digest.clone()),
+			signature: Signature(2),
+			sender: AuthorityId(2),
+		};
+		table.import_statement(&context, withdrawal.clone(), None);
+
+		assert_eq!(
+			&table.detected_misbehavior[&AuthorityId(2)][0],
+			&Misbehavior::UnauthorizedStatement(UnauthorizedStatement { statement: withdrawal })
+		);
+		assert!(table.get_candidate(&digest).is_some());
+	}
+
+	#[test]
+	fn withdrawal_by_co_issuer_is_not_misbehavior() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				for i in 1..10 {
+					map.insert(AuthorityId(i), (GroupId(2), GroupId(455)));
+				}
+				map
+			}
+		};
+
+		let digest = Digest(100);
+
+		// whichever of the issuers is found first must not matter.
+		for other in 2..10 {
+			let mut table = create();
+			for &issuer in &[1, other] {
+				table.import_statement(&context, SignedStatement {
+					statement: Statement::Candidate(Candidate(2, 100)),
+					signature: Signature(issuer),
+					sender: AuthorityId(issuer),
+				}, None);
+			}
+
+			table.import_statement(&context, SignedStatement {
+				statement: Statement::Withdraw(digest.clone()),
+				signature: Signature(1),
+				sender: AuthorityId(1),
+			}, None);
+
+			assert!(table.detected_misbehavior.is_empty());
+
+			// the other issuer has vouched for the candidate, so it stays.
+			assert!(table.get_candidate(&digest).is_some());
+		}
+	}
+
+	#[test]
+	fn forged_statements_are_dropped_unless_pre_verified() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				map.insert(AuthorityId(1), (GroupId(2), GroupId(455)));
+				map
+			}
+		};
+
+		let mut table = create();
+		let forged = SignedStatement {
+			statement: Statement::Candidate(Candidate(2, 100)),
+			signature: Signature(2),
+			sender: AuthorityId(1),
+		};
+
+		assert!(table.import_statement(&context, forged.clone(), None).is_none());
+		assert!(table.get_candidate(&Digest(100)).is_none());
+		assert_eq!(table.summary().dropped_statements, 1);
+
+		assert!(table.import_verified_statement(&context, forged, None).is_some());
+		assert!(table.get_candidate(&Digest(100)).is_some());
+	}
+
+	#[test]
+	fn exported_statements_reconstruct_includable_candidates() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				for i in 1..5 {
+					map.insert(AuthorityId(i), (GroupId(2), GroupId(455)));
+					map.insert(AuthorityId(i + 5), (GroupId(455), GroupId(2)));
+				}
+				map.insert(AuthorityId(5), (GroupId(3), GroupId(455)));
+				map
+			}
+		};
+
+		let mut table = create();
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Candidate(Candidate(2, 100)),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		}, None);
+
+		for i in 2..5 {
+			table.import_statement(&context, SignedStatement {
+				statement: Statement::Valid(Digest(100)),
+				signature: Signature(i),
+				sender: AuthorityId(i),
+			}, None);
+		}
+
+		for i in 6..10 {
+			table.import_statement(&context, SignedStatement {
+				statement: Statement::Available(Digest(100)),
+				signature: Signature(i),
+				sender: AuthorityId(i),
+			}, None);
+		}
+
+		// not includable; never exported.
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Candidate(Candidate(3, 200)),
+			signature: Signature(5),
+			sender: AuthorityId(5),
+		}, None);
+
+		assert!(table.candidate_includable(&Digest(100), &context));
+		assert!(table.export_statements(&context, |g| g == &GroupId(3)).is_empty());
+
+		let exported = table.export_statements(&context, |_| true);
+		let (validity_t, availability_t) = context.requisite_votes(&GroupId(2));
+
+		// issuance plus just enough votes to meet the thresholds.
+		assert_eq!(exported.len(), validity_t + availability_t);
+		assert_eq!(exported[0].statement, Statement::Candidate(Candidate(2, 100)));
+
+		let mut caught_up = create();
+		for statement in exported {
+			caught_up.import_statement(&context, statement, None);
+		}
+
+		assert!(caught_up.candidate_includable(&Digest(100), &context));
+		assert_eq!(caught_up.proposed_candidates(&context), vec![&Candidate(2, 100)]);
+		assert!(caught_up.get_candidate(&Digest(200)).is_none());
+	}
+
+	#[test]
+	fn multiple_offenses_are_all_recorded() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				map.insert(AuthorityId(1), (GroupId(2), GroupId(455)));
+				map
+			}
+		};
+
+		let mut table = create();
+		let candidate = |body| SignedStatement {
+			statement: Statement::Candidate(Candidate(2, body)),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		};
+
+		table.import_statement(&context, candidate(100), None);
+		table.import_statement(&context, candidate(999), None);
+		table.import_statement(&context, candidate(999), None);
+
+		// vote availability without being a guarantor.
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Available(Digest(100)),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		}, None);
+
+		assert_eq!(table.detected_misbehavior[&AuthorityId(1)].len(), 2);
+
+		let drained = table.drain_misbehavior();
+		assert_eq!(drained.len(), 2);
+		assert!(drained.iter().all(|&(ref id, _)| id == &AuthorityId(1)));
+		assert!(table.get_misbehavior().is_empty());
+	}
+
+	#[test]
+	fn submitting_candidate_from_wrong_group_is_misbehavior() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				map.insert(AuthorityId(1), (GroupId(3), GroupId(455)));
+				map
+			}
+		};
+
+		let mut table = create();
+		let statement = SignedStatement {
+			statement: Statement::Candidate(Candidate(2, 100)),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		};
+
+		table.import_statement(&context, statement, None);
+
+		assert_eq!(
+			&table.detected_misbehavior[&AuthorityId(1)][0],
+			&Misbehavior::UnauthorizedStatement(UnauthorizedStatement {
+				statement: SignedStatement {
+					statement: Statement::Candidate(Candidate(2, 100)),
+					signature: Signature(1),
+					sender: AuthorityId(1),
+				},
+			})
+		);
+	}
+
+	#[test]
+	fn unauthorized_votes() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				map.insert(AuthorityId(1), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(2), (GroupId(3), GroupId(222)));
+				map
+			}
+		};
+
+		let mut table = create();
+
+		let candidate_a = SignedStatement {
+			statement: Statement::Candidate(Candidate(2, 100)),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		};
+		let candidate_a_digest = Digest(100);
+
+		let candidate_b = SignedStatement {
+			statement: Statement::Candidate(Candidate(3, 987)),
+			signature: Signature(2),
+			sender: AuthorityId(2),
+		};
+		let candidate_b_digest = Digest(987);
+
+		table.import_statement(&context, candidate_a, None);
+		table.import_statement(&context, candidate_b, None);
+		assert!(!table.detected_misbehavior.contains_key(&AuthorityId(1)));
+		assert!(!table.detected_misbehavior.contains_key(&AuthorityId(2)));
+
+		// authority 1 votes for availability on 2's candidate.
+		let bad_availability_vote = SignedStatement {
+			statement: Statement::Available(candidate_b_digest.clone()),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		};
+		table.import_statement(&context, bad_availability_vote, None);
+
+		assert_eq!(
+			&table.detected_misbehavior[&AuthorityId(1)][0],
+			&Misbehavior::UnauthorizedStatement(UnauthorizedStatement {
+				statement: SignedStatement {
+					statement: Statement::Available(candidate_b_digest),
+					signature: Signature(1),
+					sender: AuthorityId(1),
+				},
+			})
+		);
+
+		// authority 2 votes for validity on 1's candidate.
+		let bad_validity_vote = SignedStatement {
+			statement: Statement::Valid(candidate_a_digest.clone()),
+			signature: Signature(2),
+			sender: AuthorityId(2),
+		};
+		table.import_statement(&context, bad_validity_vote, None);
+
+		assert_eq!(
+			&table.detected_misbehavior[&AuthorityId(2)][0],
+			&Misbehavior::UnauthorizedStatement(UnauthorizedStatement {
+				statement: SignedStatement {
+					statement: Statement::Valid(candidate_a_digest),
+					signature: Signature(2),
+					sender: AuthorityId(2),
+				},
+			})
+		);
+	}
+
+	#[test]
+	fn validity_double_vote_is_misbehavior() {
 		let context = TestContext {
 			authorities: {
 				let mut map = HashMap::new();
@@ -1033,7 +1887,7 @@ mod tests {
 		table.import_statement(&context, invalid_statement, None);
 
 		assert_eq!(
-			table.detected_misbehavior.get(&AuthorityId(2)).unwrap(),
+			&table.detected_misbehavior[&AuthorityId(2)][0],
 			&Misbehavior::ValidityDoubleVote(ValidityDoubleVote::ValidityAndInvalidity(
 				candidate_digest,
 				Signature(2),
@@ -1071,7 +1925,7 @@ mod tests {
 
 		table.import_statement(&context, extra_vote, None);
 		assert_eq!(
-			table.detected_misbehavior.get(&AuthorityId(1)).unwrap(),
+			&table.detected_misbehavior[&AuthorityId(1)][0],
 			&Misbehavior::ValidityDoubleVote(ValidityDoubleVote::IssuedAndValidity(
 				(Candidate(2, 100), Signature(1)),
 				(Digest(100), Signature(1)),
@@ -1081,6 +1935,7 @@ mod tests {
 
 	#[test]
 	fn candidate_can_be_included() {
+		let context = TestContext { authorities: HashMap::new() };
 		let validity_threshold = 6;
 		let availability_threshold = 34;
 
@@ -1090,25 +1945,519 @@ mod tests {
 			validity_votes: HashMap::new(),
 			availability_votes: HashMap::new(),
 			indicated_bad_by: Vec::new(),
+			seen_index: 0,
 		};
 
-		assert!(!candidate.can_be_included(validity_threshold, availability_threshold));
+		assert!(!candidate.can_be_included(&context, validity_threshold, availability_threshold));
 
 		for i in 0..validity_threshold {
 			candidate.validity_votes.insert(AuthorityId(i + 100), ValidityVote::Valid(Signature(i + 100)));
 		}
 
-		assert!(!candidate.can_be_included(validity_threshold, availability_threshold));
+		assert!(!candidate.can_be_included(&context, validity_threshold, availability_threshold));
 
 		for i in 0..availability_threshold {
 			candidate.availability_votes.insert(AuthorityId(i + 255), Signature(i + 255));
 		}
 
-		assert!(candidate.can_be_included(validity_threshold, availability_threshold));
+		assert!(candidate.can_be_included(&context, validity_threshold, availability_threshold));
 
 		candidate.indicated_bad_by.push(AuthorityId(1024));
 
-		assert!(!candidate.can_be_included(validity_threshold, availability_threshold));
+		assert!(!candidate.can_be_included(&context, validity_threshold, availability_threshold));
+	}
+
+	#[test]
+	fn issuer_counts_towards_availability_if_context_says_so() {
+		struct IssuerContext {
+			inner: TestContext,
+			issuer_guarantees: bool,
+		}
+
+		impl Context for IssuerContext {
+			type AuthorityId = AuthorityId;
+			type Digest = Digest;
+			type Candidate = Candidate;
+			type GroupId = GroupId;
+			type Signature = Signature;
+
+			fn candidate_digest(candidate: &Candidate) -> Digest {
+				TestContext::candidate_digest(candidate)
+			}
+
+			fn candidate_group(candidate: &Candidate) -> GroupId {
+				TestContext::candidate_group(candidate)
+			}
+
+			fn is_member_of(&self, authority: &AuthorityId, group: &GroupId) -> bool {
+				self.inner.is_member_of(authority, group)
+			}
+
+			fn is_availability_guarantor_of(&self, authority: &AuthorityId, group: &GroupId) -> bool {
+				self.inner.is_availability_guarantor_of(authority, group)
+			}
+
+			fn group_authorities(&self, group: &GroupId) -> (Vec<AuthorityId>, Vec<AuthorityId>) {
+				self.inner.group_authorities(group)
+			}
+
+			fn requisite_votes(&self, id: &GroupId) -> (usize, usize) {
+				self.inner.requisite_votes(id)
+			}
+
+			fn verify_statement(&self, statement: &SignedStatement<Candidate, Digest, AuthorityId, Signature>) -> bool {
+				self.inner.verify_statement(statement)
+			}
+
+			fn issuer_guarantees_availability(&self, _group: &GroupId) -> bool {
+				self.issuer_guarantees
+			}
+		}
+
+		let statements = vec![
+			SignedStatement {
+				statement: Statement::Candidate(Candidate(2, 100)),
+				signature: Signature(1),
+				sender: AuthorityId(1),
+			},
+			SignedStatement {
+				statement: Statement::Valid(Digest(100)),
+				signature: Signature(2),
+				sender: AuthorityId(2),
+			},
+			SignedStatement {
+				statement: Statement::Available(Digest(100)),
+				signature: Signature(2),
+				sender: AuthorityId(2),
+			},
+		];
+
+		let make_context = |issuer_guarantees| IssuerContext {
+			inner: TestContext {
+				authorities: {
+					let mut map = HashMap::new();
+					map.insert(AuthorityId(1), (GroupId(2), GroupId(2)));
+					map.insert(AuthorityId(2), (GroupId(2), GroupId(2)));
+					map
+				}
+			},
+			issuer_guarantees,
+		};
+
+		// by default the issuer still has to attest availability explicitly.
+		let context = make_context(false);
+		let mut table = create();
+		for statement in statements.iter().cloned() {
+			table.import_statement(&context, statement, None);
+		}
+
+		assert!(!table.candidate_includable(&Digest(100), &context));
+		assert_eq!(table.missing_voters(&Digest(100), &context).unwrap().availability, vec![AuthorityId(1)]);
+
+		let context = make_context(true);
+		let mut table = create();
+		for statement in statements.iter().cloned() {
+			table.import_statement(&context, statement, None);
+		}
+
+		assert!(table.candidate_includable(&Digest(100), &context));
+		assert!(table.missing_voters(&Digest(100), &context).unwrap().availability.is_empty());
+
+		// the issuance carries the implicit vote into another table.
+		let exported = table.export_statements(&context, |_| true);
+		assert_eq!(exported.len(), 3);
+
+		let mut other = create();
+		for statement in exported {
+			other.import_statement(&context, statement, None);
+		}
+		assert!(other.candidate_includable(&Digest(100), &context));
+	}
+
+	#[test]
+	fn weighted_votes_reach_threshold() {
+		struct WeightedContext {
+			inner: TestContext,
+			weights: HashMap<AuthorityId, usize>,
+		}
+
+		impl Context for WeightedContext {
+			type AuthorityId = AuthorityId;
+			type Digest = Digest;
+			type Candidate = Candidate;
+			type GroupId = GroupId;
+			type Signature = Signature;
+
+			fn candidate_digest(candidate: &Candidate) -> Digest {
+				TestContext::candidate_digest(candidate)
+			}
+
+			fn candidate_group(candidate: &Candidate) -> GroupId {
+				TestContext::candidate_group(candidate)
+			}
+
+			fn is_member_of(&self, authority: &AuthorityId, group: &GroupId) -> bool {
+				self.inner.is_member_of(authority, group)
+			}
+
+			fn is_availability_guarantor_of(&self, authority: &AuthorityId, group: &GroupId) -> bool {
+				self.inner.is_availability_guarantor_of(authority, group)
+			}
+
+			fn group_authorities(&self, group: &GroupId) -> (Vec<AuthorityId>, Vec<AuthorityId>) {
+				self.inner.group_authorities(group)
+			}
+
+			fn authority_weight(&self, authority: &AuthorityId) -> usize {
+				self.weights.get(authority).cloned().unwrap_or(0)
+			}
+
+			fn verify_statement(&self, statement: &SignedStatement<Candidate, Digest, AuthorityId, Signature>) -> bool {
+				self.inner.verify_statement(statement)
+			}
+
+			fn requisite_votes(&self, _id: &GroupId) -> (usize, usize) {
+				(10, 1)
+			}
+		}
+
+		let context = WeightedContext {
+			inner: TestContext {
+				authorities: {
+					let mut map = HashMap::new();
+					map.insert(AuthorityId(1), (GroupId(2), GroupId(455)));
+					map.insert(AuthorityId(2), (GroupId(2), GroupId(455)));
+					map.insert(AuthorityId(3), (GroupId(2), GroupId(455)));
+					map.insert(AuthorityId(4), (GroupId(455), GroupId(2)));
+					map
+				}
+			},
+			weights: vec![
+				(AuthorityId(1), 3),
+				(AuthorityId(2), 2),
+				(AuthorityId(3), 7),
+				(AuthorityId(4), 1),
+			].into_iter().collect(),
+		};
+
+		let mut table = create();
+		let candidate_digest = Digest(100);
+
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Candidate(Candidate(2, 100)),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		}, None);
+
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Available(candidate_digest.clone()),
+			signature: Signature(4),
+			sender: AuthorityId(4),
+		}, None);
+
+		// two of three validity votes, but only weight 5 of 10.
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Valid(candidate_digest.clone()),
+			signature: Signature(2),
+			sender: AuthorityId(2),
+		}, None);
+		assert!(!table.candidate_includable(&candidate_digest, &context));
+
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Valid(candidate_digest.clone()),
+			signature: Signature(3),
+			sender: AuthorityId(3),
+		}, None);
+		assert!(table.candidate_includable(&candidate_digest, &context));
+		assert_eq!(table.includable_count(), 1);
+	}
+
+	#[test]
+	fn selection_strategy_chooses_proposed_candidate() {
+		struct StrategyContext {
+			inner: TestContext,
+			strategy: SelectionStrategy,
+		}
+
+		impl Context for StrategyContext {
+			type AuthorityId = AuthorityId;
+			type Digest = Digest;
+			type Candidate = Candidate;
+			type GroupId = GroupId;
+			type Signature = Signature;
+
+			fn candidate_digest(candidate: &Candidate) -> Digest {
+				TestContext::candidate_digest(candidate)
+			}
+
+			fn candidate_group(candidate: &Candidate) -> GroupId {
+				TestContext::candidate_group(candidate)
+			}
+
+			fn is_member_of(&self, authority: &AuthorityId, group: &GroupId) -> bool {
+				self.inner.is_member_of(authority, group)
+			}
+
+			fn is_availability_guarantor_of(&self, authority: &AuthorityId, group: &GroupId) -> bool {
+				self.inner.is_availability_guarantor_of(authority, group)
+			}
+
+			fn group_authorities(&self, group: &GroupId) -> (Vec<AuthorityId>, Vec<AuthorityId>) {
+				self.inner.group_authorities(group)
+			}
+
+			fn verify_statement(&self, statement: &SignedStatement<Candidate, Digest, AuthorityId, Signature>) -> bool {
+				self.inner.verify_statement(statement)
+			}
+
+			fn requisite_votes(&self, _id: &GroupId) -> (usize, usize) {
+				(1, 0)
+			}
+
+			fn candidate_value(candidate: &Candidate) -> u64 {
+				candidate.1 as u64
+			}
+
+			fn selection_strategy(&self) -> SelectionStrategy {
+				self.strategy
+			}
+		}
+
+		let mut context = StrategyContext {
+			inner: TestContext {
+				authorities: (1..5).map(|i| (AuthorityId(i), (GroupId(2), GroupId(455)))).collect(),
+			},
+			strategy: SelectionStrategy::LeastByOrd,
+		};
+
+		let mut table = create();
+		for &(authority, body) in &[(1, 200), (2, 100), (3, 400), (4, 300)] {
+			table.import_statement(&context, SignedStatement {
+				statement: Statement::Candidate(Candidate(2, body)),
+				signature: Signature(authority),
+				sender: AuthorityId(authority),
+			}, None);
+		}
+
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Valid(Digest(300)),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		}, None);
+
+		assert_eq!(table.includable_count(), 1);
+
+		for &(strategy, expected) in &[
+			(SelectionStrategy::LeastByOrd, 100),
+			(SelectionStrategy::MostVotes, 300),
+			(SelectionStrategy::EarliestSeen, 200),
+			(SelectionStrategy::HighestValue, 400),
+		] {
+			context.strategy = strategy;
+			assert_eq!(table.proposed_candidates(&context), vec![&Candidate(2, expected)]);
+		}
+	}
+
+	#[test]
+	fn group_candidates_expose_voters() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				map.insert(AuthorityId(1), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(2), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(3), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(4), (GroupId(455), GroupId(2)));
+				map
+			}
+		};
+
+		let mut table = create();
+		let statements = vec![
+			(Statement::Candidate(Candidate(2, 100)), 1),
+			(Statement::Valid(Digest(100)), 2),
+			(Statement::Invalid(Digest(100)), 3),
+			(Statement::Available(Digest(100)), 4),
+		];
+
+		for (statement, sender) in statements {
+			table.import_statement(&context, SignedStatement {
+				statement,
+				signature: Signature(sender),
+				sender: AuthorityId(sender),
+			}, None);
+		}
+
+		assert_eq!(table.group_candidates(&GroupId(455)).count(), 0);
+
+		let candidates: Vec<_> = table.group_candidates(&GroupId(2)).collect();
+		assert_eq!(candidates.len(), 1);
+
+		let (digest, data) = candidates[0];
+		let mut validity_voters: Vec<_> = data.validity_voters().collect();
+		validity_voters.sort_by_key(|a| a.0);
+
+		assert_eq!(digest, &Digest(100));
+		assert_eq!(data.candidate(), &Candidate(2, 100));
+		assert_eq!(data.issuer(), Some(&AuthorityId(1)));
+		assert_eq!(validity_voters, vec![&AuthorityId(1), &AuthorityId(2)]);
+		assert_eq!(data.invalidity_voters(), &[AuthorityId(3)]);
+		assert_eq!(data.availability_voters().collect::<Vec<_>>(), vec![&AuthorityId(4)]);
+	}
+
+	#[test]
+	fn pruning_group_drops_only_its_candidates() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				map.insert(AuthorityId(1), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(2), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(3), (GroupId(455), GroupId(2)));
+				map.insert(AuthorityId(4), (GroupId(455), GroupId(2)));
+				map
+			}
+		};
+
+		let mut table = create();
+		let statements = vec![
+			(Statement::Candidate(Candidate(2, 100)), 1),
+			(Statement::Valid(Digest(100)), 2),
+			(Statement::Available(Digest(100)), 3),
+			(Statement::Available(Digest(100)), 4),
+			(Statement::Candidate(Candidate(455, 200)), 4),
+		];
+
+		for (statement, sender) in statements {
+			table.import_statement(&context, SignedStatement {
+				statement,
+				signature: Signature(sender),
+				sender: AuthorityId(sender),
+			}, None);
+		}
+
+		assert_eq!(table.includable_count(), 1);
+		assert_eq!(table.prune_group(&GroupId(2)), 1);
+		assert_eq!(table.prune_group(&GroupId(2)), 0);
+
+		assert_eq!(table.includable_count(), 0);
+		assert!(table.proposed_candidates(&context).is_empty());
+		assert!(table.get_candidate(&Digest(100)).is_none());
+		assert_eq!(table.group_candidates(&GroupId(455)).count(), 1);
+
+		// the issuer of a pruned candidate may issue again without misbehaving.
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Candidate(Candidate(2, 999)),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		}, None);
+
+		assert!(!table.detected_misbehavior.contains_key(&AuthorityId(1)));
+		assert_eq!(table.group_candidates(&GroupId(2)).count(), 1);
+
+		// withdrawals are dropped along with the group.
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Withdraw(Digest(999)),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		}, None).expect("withdrawal of own candidate succeeds");
+		assert!(table.withdrawn.contains_key(&GroupId(2)));
+
+		assert_eq!(table.prune_group(&GroupId(2)), 0);
+		assert!(table.withdrawn.is_empty());
+	}
+
+	#[test]
+	fn summary_reports_table_state() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				map.insert(AuthorityId(1), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(2), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(3), (GroupId(455), GroupId(2)));
+				map
+			}
+		};
+
+		let mut table = create();
+		let statements = vec![
+			(Statement::Candidate(Candidate(2, 100)), 1),
+			(Statement::Valid(Digest(100)), 2),
+			// duplicate.
+			(Statement::Valid(Digest(100)), 2),
+			// unknown candidate.
+			(Statement::Valid(Digest(999)), 2),
+			// not a member of group 2.
+			(Statement::Valid(Digest(100)), 3),
+			(Statement::Candidate(Candidate(455, 200)), 3),
+		];
+
+		for (statement, sender) in statements {
+			table.import_statement(&context, SignedStatement {
+				statement,
+				signature: Signature(sender),
+				sender: AuthorityId(sender),
+			}, None);
+		}
+
+		let summary = table.summary();
+		assert_eq!(summary.candidates_per_group.get(&GroupId(2)), Some(&1));
+		assert_eq!(summary.candidates_per_group.get(&GroupId(455)), Some(&1));
+		assert_eq!(summary.candidates.len(), 2);
+
+		let votes = summary.candidates.iter().find(|s| s.candidate == Digest(100)).unwrap();
+		assert_eq!(votes.validity_votes, 2);
+		assert_eq!(votes.availability_votes, 0);
+
+		assert_eq!(summary.includable_groups, 0);
+		assert_eq!(summary.dropped_statements, 2);
+		assert_eq!(summary.misbehavior_count, 1);
+		assert_eq!(summary.misbehaving_authorities, 1);
+	}
+
+	#[test]
+	fn missing_voters_shrink_as_votes_arrive() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				map.insert(AuthorityId(1), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(2), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(3), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(4), (GroupId(455), GroupId(2)));
+				map.insert(AuthorityId(5), (GroupId(455), GroupId(2)));
+				map
+			}
+		};
+
+		let mut table = create();
+		assert!(table.missing_voters(&Digest(100), &context).is_none());
+
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Candidate(Candidate(2, 100)),
+			signature: Signature(1),
+			sender: AuthorityId(1),
+		}, None);
+
+		assert_eq!(table.missing_voters(&Digest(100), &context), Some(MissingVoters {
+			validity: vec![AuthorityId(2), AuthorityId(3)],
+			availability: vec![AuthorityId(4), AuthorityId(5)],
+		}));
+
+		let statements = vec![
+			(Statement::Invalid(Digest(100)), 3),
+			(Statement::Available(Digest(100)), 5),
+		];
+
+		for (statement, sender) in statements {
+			table.import_statement(&context, SignedStatement {
+				statement,
+				signature: Signature(sender),
+				sender: AuthorityId(sender),
+			}, None);
+		}
+
+		assert_eq!(table.missing_voters(&Digest(100), &context), Some(MissingVoters {
+			validity: vec![AuthorityId(2)],
+			availability: vec![AuthorityId(4)],
+		}));
 	}
 
 	#[test]
@@ -1174,6 +2523,66 @@ mod tests {
 		assert!(table.includable_count.is_empty());
 	}
 
+	#[test]
+	fn check_proposal_reports_unbacked_candidates() {
+		let context = TestContext {
+			authorities: {
+				let mut map = HashMap::new();
+				map.insert(AuthorityId(1), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(2), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(3), (GroupId(2), GroupId(455)));
+				map.insert(AuthorityId(4), (GroupId(455), GroupId(2)));
+				map
+			}
+		};
+
+		let mut table = create();
+		let statements = vec![
+			(Statement::Candidate(Candidate(2, 100)), 1),
+			(Statement::Valid(Digest(100)), 2),
+			(Statement::Available(Digest(100)), 4),
+			(Statement::Candidate(Candidate(455, 200)), 4),
+		];
+
+		for (statement, sender) in statements {
+			table.import_statement(&context, SignedStatement {
+				statement,
+				signature: Signature(sender),
+				sender: AuthorityId(sender),
+			}, None);
+		}
+
+		assert!(table.check_proposal(&context, &[Candidate(2, 100)]).is_backed());
+
+		let proposal = [Candidate(2, 100), Candidate(455, 200), Candidate(2, 101), Candidate(7, 300)];
+		let check = table.check_proposal(&context, &proposal);
+		assert_eq!(check.unbacked, vec![
+			UnbackedCandidate {
+				index: 1,
+				digest: Digest(200),
+				reason: MissingBacking::InsufficientVotes {
+					validity_weight: 1,
+					validity_threshold: 1,
+					availability_weight: 0,
+					availability_threshold: 2,
+				},
+			},
+			UnbackedCandidate { index: 2, digest: Digest(101), reason: MissingBacking::DuplicateGroup },
+			UnbackedCandidate { index: 3, digest: Digest(300), reason: MissingBacking::Unknown },
+		]);
+
+		table.import_statement(&context, SignedStatement {
+			statement: Statement::Invalid(Digest(100)),
+			signature: Signature(3),
+			sender: AuthorityId(3),
+		}, None);
+
+		let check = table.check_proposal(&context, &[Candidate(2, 100)]);
+		assert_eq!(check.unbacked, vec![
+			UnbackedCandidate { index: 0, digest: Digest(100), reason: MissingBacking::IndicatedBad },
+		]);
+	}
+
 	#[test]
 	fn candidate_import_gives_summary() {
 		let context = TestContext {
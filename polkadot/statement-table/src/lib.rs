@@ -17,9 +17,15 @@
 extern crate substrate_primitives;
 extern crate polkadot_primitives as primitives;
 
+#[cfg(all(test, feature = "fuzz"))]
+extern crate quickcheck;
+
 pub mod generic;
 
-pub use generic::Table;
+#[cfg(all(test, feature = "fuzz"))]
+mod fuzz;
+
+pub use generic::{Table, SelectionStrategy};
 
 use primitives::parachain::{Id, CandidateReceipt, CandidateSignature as Signature};
 use primitives::{SessionKey, Hash};
@@ -36,6 +42,15 @@ pub type Misbehavior = generic::Misbehavior<CandidateReceipt, Hash, SessionKey,
 /// A summary of import of a statement.
 pub type Summary = generic::Summary<Hash, Id>;
 
+/// A snapshot of the state of the whole table.
+pub type TableSummary = generic::TableSummary<Hash, Id>;
+
+/// Authorities which have not yet voted on a candidate.
+pub type MissingVoters = generic::MissingVoters<SessionKey>;
+
+/// The result of checking a proposed set of candidates against the table.
+pub type ProposalCheck = generic::ProposalCheck<Hash>;
+
 /// Context necessary to construct a table.
 pub trait Context {
 	/// Whether a authority is a member of a group.
@@ -51,8 +66,24 @@ pub trait Context {
 		group: &Id,
 	) -> bool;
 
-	// requisite number of votes for validity and availability respectively from a group.
+	/// All members and availability guarantors of a group, respectively.
+	fn group_authorities(&self, group: &Id) -> (Vec<SessionKey>, Vec<SessionKey>);
+
+	/// The weight of an authority's votes. Defaults to 1, counting votes.
+	fn authority_weight(&self, _authority: &SessionKey) -> usize { 1 }
+
+	// requisite weight of votes for validity and availability respectively from a group.
 	fn requisite_votes(&self, group: &Id) -> (usize, usize);
+
+	/// Whether the signature on a statement is valid for its sender.
+	fn verify_statement(&self, statement: &SignedStatement) -> bool;
+
+	/// Whether issuers of candidates in a group also guarantee their availability.
+	fn issuer_guarantees_availability(&self, _group: &Id) -> bool { false }
+
+	/// How to choose which candidate of a group to propose when several are
+	/// includable. `SelectionStrategy::HighestValue` prefers the highest fees.
+	fn selection_strategy(&self) -> SelectionStrategy { SelectionStrategy::LeastByOrd }
 }
 
 impl<C: Context> generic::Context for C {
@@ -78,9 +109,33 @@ impl<C: Context> generic::Context for C {
 		Context::is_availability_guarantor_of(self, authority, group)
 	}
 
+	fn group_authorities(&self, group: &Id) -> (Vec<SessionKey>, Vec<SessionKey>) {
+		Context::group_authorities(self, group)
+	}
+
+	fn authority_weight(&self, authority: &SessionKey) -> usize {
+		Context::authority_weight(self, authority)
+	}
+
 	fn requisite_votes(&self, group: &Id) -> (usize, usize) {
 		Context::requisite_votes(self, group)
 	}
+
+	fn verify_statement(&self, statement: &SignedStatement) -> bool {
+		Context::verify_statement(self, statement)
+	}
+
+	fn issuer_guarantees_availability(&self, group: &Id) -> bool {
+		Context::issuer_guarantees_availability(self, group)
+	}
+
+	fn candidate_value(candidate: &CandidateReceipt) -> u64 {
+		candidate.commitments.fees
+	}
+
+	fn selection_strategy(&self) -> SelectionStrategy {
+		Context::selection_strategy(self)
+	}
 }
 
 /// A batch of statements to send out.
@@ -356,6 +356,23 @@ impl<A> TransactionPool<A> where
 		}
 	}
 
+	/// Re-queue the extrinsics of a block which left the best chain, verifying
+	/// them at `block`. Inherent extrinsics and those already in the pool or no
+	/// longer valid are skipped. Returns the number of extrinsics re-queued.
+	pub fn requeue(&self, block: BlockId, xts: Vec<FutureProofUncheckedExtrinsic>) -> usize {
+		xts.into_iter()
+			.filter_map(|xt| xt.using_encoded(|e| UncheckedExtrinsic::decode(&mut &e[..])))
+			.filter(|uxt| uxt.is_signed())
+			.filter(|uxt| match self.import_unchecked_extrinsic(block, uxt.clone()) {
+				Ok(_) => true,
+				Err(e) => {
+					trace!("Retracted extrinsic not re-queued: {:?}", e);
+					false
+				}
+			})
+			.count()
+	}
+
 	/// Cull old transactions from the queue.
 	pub fn cull(&self, block: BlockId) -> Result<usize> {
 		let ready = Ready::create(block, &*self.api);
@@ -411,11 +428,11 @@ mod tests {
 	use super::TransactionPool;
 	use substrate_keyring::Keyring::{self, *};
 	use codec::Slicable;
-	use polkadot_api::{PolkadotApi, BlockBuilder, Result};
-	use primitives::{AccountId, AccountIndex, Block, BlockId, Hash, Index, SessionKey, Timestamp,
-		UncheckedExtrinsic as FutureProofUncheckedExtrinsic};
-	use runtime::{RawAddress, Call, TimestampCall, BareExtrinsic, Extrinsic, UncheckedExtrinsic};
-	use primitives::parachain::{CandidateReceipt, DutyRoster, Id as ParaId};
+	use polkadot_api::{PolkadotApi, AgreementTiming, BlockBuilder, CollationInputs, Result};
+	use primitives::{AccountId, AccountIndex, Balance, Block, BlockId, BlockNumber, Hash, Heartbeat, Index, MisbehaviorReport,
+		SessionKey, Timestamp, UncheckedExtrinsic as FutureProofUncheckedExtrinsic};
+	use runtime::{RawAddress, Call, TimestampCall, BareExtrinsic, EventRecord, Extrinsic, UncheckedExtrinsic};
+	use primitives::parachain::{AgreementMisbehaviorReport, CandidateReceipt, DutyRoster, EgressLimits, EgressRoot, Id as ParaId};
 	use substrate_runtime_primitives::{MaybeUnsigned, generic};
 
 	struct TestBlockBuilder;
@@ -453,15 +470,26 @@ mod tests {
 
 		fn session_keys(&self, _at: &BlockId) -> Result<Vec<SessionKey>> { unimplemented!() }
 		fn validators(&self, _at: &BlockId) -> Result<Vec<AccountId>> { unimplemented!() }
+		fn session_index(&self, _at: &BlockId) -> Result<BlockNumber> { unimplemented!() }
+		fn session_key_owner(&self, _at: &BlockId, _key: SessionKey) -> Result<Option<AccountId>> { unimplemented!() }
 		fn random_seed(&self, _at: &BlockId) -> Result<Hash> { unimplemented!() }
 		fn duty_roster(&self, _at: &BlockId) -> Result<DutyRoster> { unimplemented!() }
 		fn timestamp(&self, _at: &BlockId) -> Result<u64> { unimplemented!() }
+		fn free_balance(&self, _at: &BlockId, _account: AccountId) -> Result<Balance> { unimplemented!() }
 		fn evaluate_block(&self, _at: &BlockId, _block: Block) -> Result<bool> { unimplemented!() }
 		fn active_parachains(&self, _at: &BlockId) -> Result<Vec<ParaId>> { unimplemented!() }
 		fn parachain_code(&self, _at: &BlockId, _parachain: ParaId) -> Result<Option<Vec<u8>>> { unimplemented!() }
 		fn parachain_head(&self, _at: &BlockId, _parachain: ParaId) -> Result<Option<Vec<u8>>> { unimplemented!() }
-		fn build_block(&self, _at: &BlockId, _timestamp: Timestamp, _new_heads: Vec<CandidateReceipt>) -> Result<Self::BlockBuilder> { unimplemented!() }
-		fn inherent_extrinsics(&self, _at: &BlockId, _timestamp: Timestamp, _new_heads: Vec<CandidateReceipt>) -> Result<Vec<Vec<u8>>> { unimplemented!() }
+		fn collation_inputs(&self, _at: &BlockId, _parachain: ParaId) -> Result<CollationInputs> { unimplemented!() }
+		fn agreement_timing(&self, _at: &BlockId) -> Result<AgreementTiming> { unimplemented!() }
+		fn egress_limits(&self, _at: &BlockId) -> Result<EgressLimits> { unimplemented!() }
+		fn egress_roots(&self, _at: &BlockId) -> Result<Vec<(ParaId, Vec<(ParaId, Vec<EgressRoot>)>)>> { unimplemented!() }
+		fn misbehavior_reported(&self, _at: &BlockId, _report: &MisbehaviorReport) -> Result<bool> { unimplemented!() }
+		fn agreement_misbehavior_reported(&self, _at: &BlockId, _report: &AgreementMisbehaviorReport) -> Result<bool> { unimplemented!() }
+		fn events(&self, _at: &BlockId) -> Result<Vec<EventRecord>> { unimplemented!() }
+		fn transaction_fee(&self, _at: &BlockId, _extrinsic: &Vec<u8>) -> Result<Balance> { unimplemented!() }
+		fn build_block(&self, _at: &BlockId, _timestamp: Timestamp, _new_heads: Vec<CandidateReceipt>, _heartbeats: Vec<Heartbeat>) -> Result<Self::BlockBuilder> { unimplemented!() }
+		fn inherent_extrinsics(&self, _at: &BlockId, _timestamp: Timestamp, _new_heads: Vec<CandidateReceipt>, _heartbeats: Vec<Heartbeat>) -> Result<Vec<Vec<u8>>> { unimplemented!() }
 
 		fn index(&self, _at: &BlockId, _account: AccountId) -> Result<Index> {
 			Ok((_account[0] as u32) + number_of(_at))
@@ -515,6 +543,28 @@ mod tests {
 		TransactionPool::new(Default::default(), Arc::new(api.clone()))
 	}
 
+	fn opaque(uxt: UncheckedExtrinsic) -> FutureProofUncheckedExtrinsic {
+		uxt.using_encoded(|e| Slicable::decode(&mut &e[..])).unwrap()
+	}
+
+	#[test]
+	fn retracted_extrinsics_are_requeued() {
+		let api = TestPolkadotApi::default();
+		let pool = pool(&api);
+		pool.import_unchecked_extrinsic(BlockId::number(0), uxt(Alice, 209, true)).unwrap();
+
+		let inherent = UncheckedExtrinsic::new(Extrinsic {
+			signed: RawAddress::Id(Default::default()),
+			index: 0,
+			function: Call::Timestamp(TimestampCall::set(0)),
+		}, MaybeUnsigned(Default::default()));
+		let retracted = vec![opaque(inherent), opaque(uxt(Alice, 209, true)), opaque(uxt(Alice, 210, true))];
+		assert_eq!(pool.requeue(BlockId::number(0), retracted), 1);
+
+		let pending: Vec<_> = pool.cull_and_get_pending(BlockId::number(0), |p| p.map(|a| (a.sender(), a.index())).collect()).unwrap();
+		assert_eq!(pending, vec![(Some(Alice.to_raw_public().into()), 209), (Some(Alice.to_raw_public().into()), 210)]);
+	}
+
 	#[test]
 	fn id_submission_should_work() {
 		let api = TestPolkadotApi::default();
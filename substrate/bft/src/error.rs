@@ -60,6 +60,12 @@ error_chain! {
 			display("Authoring for current runtime is not supported."),
 		}
 
+		/// Unable to sign with the local key.
+		Signing(e: String) {
+			description("Unable to sign with the local key"),
+			display("Unable to sign with the local key: {}", e),
+		}
+
 		/// Justification requirements not met.
 		InvalidJustification {
 			description("Invalid justification"),
@@ -165,9 +165,10 @@ pub trait Context {
 
 	/// Sign a message using the local authority ID.
 	/// In the case of a proposal message, it should sign on the hash and
-	/// the bytes of the proposal.
+	/// the bytes of the proposal. `None` if the message couldn't be signed,
+	/// in which case it isn't sent.
 	fn sign_local(&self, message: Message<Self::Candidate, Self::Digest>)
-		-> LocalizedMessage<Self::Candidate, Self::Digest, Self::AuthorityId, Self::Signature>;
+		-> Option<LocalizedMessage<Self::Candidate, Self::Digest, Self::AuthorityId, Self::Signature>>;
 
 	/// Get the proposer for a given round of consensus.
 	fn round_proposer(&self, round: usize) -> Self::AuthorityId;
@@ -244,6 +245,56 @@ impl<T> Sending<T> {
 	}
 }
 
+// how many rounds ahead of the current one messages are buffered for.
+const MAX_FUTURE_ROUNDS: usize = 16;
+
+// the most messages an honest authority sends in a single round:
+// a proposal and a prepare, commit, and advance vote.
+const MESSAGES_PER_AUTHORITY: usize = 4;
+
+// Messages for rounds ahead of the current one, held until that round is reached.
+//
+// Only rounds up to `MAX_FUTURE_ROUNDS` ahead are buffered, and the number of
+// messages buffered for each of them is bounded by the number of messages honest
+// authorities would send in it. Anything beyond that is dropped.
+#[derive(Debug)]
+struct FutureMessages<M> {
+	per_round: usize,
+	rounds: BTreeMap<usize, Vec<M>>,
+}
+
+impl<M> FutureMessages<M> {
+	fn new(nodes: usize) -> Self {
+		FutureMessages {
+			per_round: nodes.saturating_mul(MESSAGES_PER_AUTHORITY),
+			rounds: BTreeMap::new(),
+		}
+	}
+
+	// buffer a message for a round after `current_round`. returns false if it was dropped.
+	fn push(&mut self, current_round: usize, round: usize, message: M) -> bool {
+		if round <= current_round || round - current_round > MAX_FUTURE_ROUNDS {
+			return false;
+		}
+
+		let per_round = self.per_round;
+		let buffered = self.rounds.entry(round).or_insert_with(Vec::new);
+		if buffered.len() >= per_round {
+			return false;
+		}
+
+		buffered.push(message);
+		true
+	}
+
+	// take the messages buffered for a round, dropping those for all earlier rounds.
+	fn take(&mut self, round: usize) -> Vec<M> {
+		let later = self.rounds.split_off(&(round + 1));
+		let mut reached = ::std::mem::replace(&mut self.rounds, later);
+		reached.remove(&round).unwrap_or_else(Vec::new)
+	}
+}
+
 /// Error returned when the input stream concludes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct InputStreamConcluded;
@@ -300,8 +351,8 @@ enum LocalState {
 
 // This structure manages a single "view" of consensus.
 //
-// We maintain two message accumulators: one for the round we are currently in,
-// and one for a future round.
+// We maintain a message accumulator for the round we are currently in. Messages
+// for future rounds are buffered and imported when we reach that round.
 //
 // We advance the round accumulators when one of two conditions is met:
 //   - we witness consensus of advancement in the current round. in this case we
@@ -321,7 +372,7 @@ struct Strategy<C: Context> {
 	locked: Option<Locked<C::Digest, C::Signature>>,
 	notable_candidates: HashMap<C::Digest, C::Candidate>,
 	current_accumulator: Accumulator<C::Candidate, C::Digest, C::AuthorityId, C::Signature>,
-	future_accumulators: BTreeMap<usize, Accumulator<C::Candidate, C::Digest, C::AuthorityId, C::Signature>>,
+	future_messages: FutureMessages<LocalizedMessage<C::Candidate, C::Digest, C::AuthorityId, C::Signature>>,
 	local_id: C::AuthorityId,
 	misbehavior: HashMap<C::AuthorityId, Misbehavior<C::Digest, C::Signature>>,
 }
@@ -341,7 +392,7 @@ impl<C: Context> Strategy<C> {
 			nodes,
 			max_faulty,
 			current_accumulator,
-			future_accumulators: BTreeMap::new(),
+			future_messages: FutureMessages::new(nodes),
 			fetching_proposal: None,
 			evaluating_proposal: None,
 			local_state: LocalState::Start,
@@ -359,7 +410,6 @@ impl<C: Context> Strategy<C> {
 
 	fn import_message(
 		&mut self,
-		context: &C,
 		msg: LocalizedMessage<C::Candidate, C::Digest, C::AuthorityId, C::Signature>
 	) {
 		let round_number = msg.round_number();
@@ -369,17 +419,12 @@ impl<C: Context> Strategy<C> {
 		let misbehavior = if round_number == current_round {
 			self.current_accumulator.import_message(msg)
 		} else if round_number > current_round {
-			let threshold = bft_threshold(self.nodes, self.max_faulty);
-
-			let mut future_acc = self.future_accumulators.entry(round_number).or_insert_with(|| {
-				Accumulator::new(
-					round_number,
-					threshold,
-					context.round_proposer(round_number),
-				)
-			});
+			if !self.future_messages.push(current_round, round_number, msg) {
+				trace!(target: "bft", "dropping message from {:?} for round {} at round {}",
+					sender, round_number, current_round);
+			}
 
-			future_acc.import_message(msg)
+			Ok(())
 		} else {
 			Ok(())
 		};
@@ -704,20 +749,17 @@ impl<C: Context> Strategy<C> {
 			self.notable_candidates.entry(digest).or_insert_with(|| proposal.clone());
 		}
 
-		// if we jump ahead more than one round, get rid of the ones in between.
-		for irrelevant in (self.current_round() + 1)..round {
-			self.future_accumulators.remove(&irrelevant);
-		}
+		self.current_accumulator = Accumulator::new(
+			round,
+			bft_threshold(self.nodes, self.max_faulty),
+			context.round_proposer(round),
+		);
 
-		// use stored future accumulator for given round or create if it doesn't exist.
-		self.current_accumulator = match self.future_accumulators.remove(&round) {
-			Some(x) => x,
-			None => Accumulator::new(
-				round,
-				bft_threshold(self.nodes, self.max_faulty),
-				context.round_proposer(round),
-			),
-		};
+		// replay the messages we received for this round ahead of time.
+		// if we jump ahead more than one round, those for the ones in between are dropped.
+		for message in self.future_messages.take(round) {
+			self.import_message(message);
+		}
 	}
 
 	fn import_and_send_message(
@@ -726,9 +768,10 @@ impl<C: Context> Strategy<C> {
 		context: &C,
 		sending: &mut Sending<<C as TypeResolve>::Communication>
 	) {
-		let signed_message = context.sign_local(message);
-		self.import_message(context, signed_message.clone());
-		sending.push(Communication::Consensus(signed_message));
+		if let Some(signed_message) = context.sign_local(message) {
+			self.import_message(signed_message.clone());
+			sending.push(Communication::Consensus(signed_message));
+		}
 	}
 }
 
@@ -771,7 +814,7 @@ impl<C, I, O> Future for Agreement<C, I, O>
 			driving = match self.input.poll()? {
 				Async::Ready(msg) => {
 					match msg.ok_or(InputStreamConcluded)? {
-						Communication::Consensus(message) => self.strategy.import_message(&self.context, message),
+						Communication::Consensus(message) => self.strategy.import_message(message),
 						Communication::Auxiliary(lock_proof)
 							=> self.strategy.import_lock_proof(&self.context, lock_proof),
 					}
@@ -800,6 +843,11 @@ impl<C: Context, I, O> Agreement<C, I, O> {
 		&self.context
 	}
 
+	/// Get the round the agreement is currently in.
+	pub fn current_round(&self) -> usize {
+		self.strategy.current_round()
+	}
+
 	/// Drain the misbehavior vector.
 	pub fn drain_misbehavior(&mut self) -> hash_map::Drain<C::AuthorityId, Misbehavior<C::Digest, C::Signature>> {
 		self.strategy.misbehavior.drain()
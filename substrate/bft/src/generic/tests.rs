@@ -151,11 +151,11 @@ impl Context for TestContext {
 	}
 
 	fn sign_local(&self, message: Message<Candidate, Digest>)
-		-> LocalizedMessage<Candidate, Digest, AuthorityId, Signature>
+		-> Option<LocalizedMessage<Candidate, Digest, AuthorityId, Signature>>
 	{
 		let signature = Signature(message.clone(), self.local_id.clone());
 
-		match message {
+		Some(match message {
 			Message::Propose(r, proposal) => LocalizedMessage::Propose(LocalizedProposal {
 				round_number: r,
 				digest: Digest(proposal.0),
@@ -169,7 +169,7 @@ impl Context for TestContext {
 				signature,
 				sender: self.local_id.clone(),
 			}),
-		}
+		})
 	}
 
 	fn round_proposer(&self, round: usize) -> AuthorityId {
@@ -502,3 +502,67 @@ fn consensus_completes_even_when_nodes_start_with_a_delay() {
 		assert_eq!(&result.justification.digest, &results[0].justification.digest);
 	}
 }
+
+#[test]
+fn future_messages_are_bounded() {
+	// one authority: at most four messages in a round.
+	let mut buffer = FutureMessages::new(1);
+
+	for i in 0..4 {
+		assert!(buffer.push(0, 1, i));
+	}
+	assert!(!buffer.push(0, 1, 4));
+
+	assert!(!buffer.push(0, 0, 5));
+	assert!(buffer.push(0, MAX_FUTURE_ROUNDS, 6));
+	assert!(!buffer.push(0, MAX_FUTURE_ROUNDS + 1, 7));
+
+	assert!(buffer.push(0, 3, 8));
+
+	// skipping round 1 drops its messages.
+	assert!(buffer.take(2).is_empty());
+	assert_eq!(buffer.take(3), vec![8]);
+	assert_eq!(buffer.take(MAX_FUTURE_ROUNDS), vec![6]);
+	assert!(buffer.rounds.is_empty());
+}
+
+#[test]
+fn messages_for_future_rounds_are_replayed() {
+	let node_count = 4;
+	let max_faulty = 1;
+
+	let ctx = TestContext {
+		local_id: AuthorityId(0),
+		proposal: Mutex::new(0),
+		current_round: Arc::new(AtomicUsize::new(0)),
+		timer: tokio_timer::wheel().tick_duration(ROUND_DURATION).build(),
+		evaluated: Mutex::new(BTreeSet::new()),
+		node_count,
+	};
+
+	let advance_vote = |round, sender| LocalizedMessage::Vote(LocalizedVote {
+		vote: Vote::AdvanceRound(round),
+		signature: Signature(Message::Vote(Vote::AdvanceRound(round)), AuthorityId(sender)),
+		sender: AuthorityId(sender),
+	});
+
+	::futures::future::lazy(move || {
+		let mut strategy = Strategy::create(&ctx, node_count, max_faulty);
+		let mut sending = Sending::with_capacity(4);
+
+		// the other nodes have already moved on to round 1 and voted to advance that as well.
+		for sender in 1..node_count {
+			strategy.import_message(advance_vote(1, sender));
+		}
+		assert_eq!(strategy.current_round(), 0);
+
+		for sender in 1..node_count {
+			strategy.import_message(advance_vote(0, sender));
+		}
+
+		assert!(strategy.poll(&ctx, &mut sending)?.is_not_ready());
+		assert_eq!(strategy.current_round(), 2);
+
+		Ok::<_, Error>(())
+	}).wait().unwrap();
+}
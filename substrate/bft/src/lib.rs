@@ -38,6 +38,7 @@ extern crate error_chain;
 
 use std::mem;
 use std::sync::Arc;
+use std::time::Duration;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use codec::Slicable;
@@ -48,7 +49,7 @@ use runtime_primitives::bft::{Message as PrimitiveMessage, Action as PrimitiveAc
 use primitives::AuthorityId;
 
 use futures::{task, Async, Stream, Sink, Future, IntoFuture};
-use futures::sync::oneshot;
+use futures::sync::{mpsc, oneshot};
 use tokio_timer::Timer;
 use parking_lot::Mutex;
 
@@ -108,6 +109,95 @@ pub type Communication<B> = generic::Communication<B, <B as Block>::Hash, Author
 /// Misbehavior observed from BFT participants.
 pub type Misbehavior<H> = generic::Misbehavior<H, LocalizedSignature>;
 
+/// Timing of agreement rounds.
+///
+/// The timeout of round `r` is `base_timeout * backoff_factor^r`. The timeout
+/// stops growing after `max_backoff_rounds` rounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundTiming {
+	base_timeout: u64,
+	backoff_factor: u64,
+	max_backoff_rounds: usize,
+}
+
+impl Default for RoundTiming {
+	fn default() -> Self {
+		RoundTiming {
+			base_timeout: 4,
+			backoff_factor: 2,
+			max_backoff_rounds: 63,
+		}
+	}
+}
+
+impl RoundTiming {
+	/// Set the timeout of the first round, in seconds.
+	pub fn base_timeout(mut self, secs: u64) -> Self {
+		self.base_timeout = secs;
+		self
+	}
+
+	/// Set the factor each subsequent round's timeout is multiplied by.
+	pub fn backoff_factor(mut self, factor: u64) -> Self {
+		self.backoff_factor = factor;
+		self
+	}
+
+	/// Set the number of rounds after which the timeout stops growing.
+	pub fn max_backoff_rounds(mut self, rounds: usize) -> Self {
+		self.max_backoff_rounds = rounds;
+		self
+	}
+
+	/// The timeout of the given round.
+	pub fn round_timeout(&self, round: usize) -> Duration {
+		let rounds = ::std::cmp::min(round, self.max_backoff_rounds);
+		let mut timeout = self.base_timeout;
+		for _ in 0..rounds {
+			timeout = timeout.saturating_mul(self.backoff_factor);
+		}
+
+		Duration::from_secs(timeout)
+	}
+}
+
+/// Notification that an agreement has moved on to a new round without concluding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundChange<H> {
+	/// The parent hash of the agreement.
+	pub parent_hash: H,
+	/// The round the agreement moved to.
+	pub round: usize,
+	/// The timeout of the new round.
+	pub timeout: Duration,
+}
+
+/// Stream of round changes of all agreements started by a `BftService`.
+pub type RoundChangeStream<H> = mpsc::UnboundedReceiver<RoundChange<H>>;
+
+type RoundChangeSinks<H> = Arc<Mutex<Vec<mpsc::UnboundedSender<RoundChange<H>>>>>;
+
+/// Signs messages with the local authority key.
+///
+/// The key may be held outside of the node, in which case signing can fail.
+pub trait Signer: Send + Sync {
+	/// The public key of the local authority.
+	fn public(&self) -> ed25519::Public;
+
+	/// Sign a message.
+	fn sign(&self, message: &[u8]) -> Result<ed25519::Signature, Error>;
+}
+
+impl Signer for ed25519::Pair {
+	fn public(&self) -> ed25519::Public {
+		ed25519::Pair::public(self)
+	}
+
+	fn sign(&self, message: &[u8]) -> Result<ed25519::Signature, Error> {
+		Ok(ed25519::Pair::sign(self, message))
+	}
+}
+
 /// Proposer factory. Can be used to create a proposer instance.
 pub trait ProposerFactory<B: Block> {
 	/// The proposer type this creates.
@@ -117,7 +207,7 @@ pub trait ProposerFactory<B: Block> {
 
 	/// Initialize the proposal logic on top of a specific header.
 	// TODO: provide state context explicitly?
-	fn init(&self, parent_header: &B::Header, authorities: &[AuthorityId], sign_with: Arc<ed25519::Pair>) -> Result<Self::Proposer, Self::Error>;
+	fn init(&self, parent_header: &B::Header, authorities: &[AuthorityId], sign_with: Arc<Signer>) -> Result<Self::Proposer, Self::Error>;
 }
 
 /// Logic for a proposer.
@@ -144,6 +234,10 @@ pub trait Proposer<B: Block> {
 	/// Determine the proposer for a given round. This should be a deterministic function
 	/// with consistent results across all authorities.
 	fn round_proposer(&self, round_number: usize, authorities: &[AuthorityId]) -> AuthorityId;
+
+	/// The base timeout, in seconds, of the exponential round timeout for this agreement.
+	/// `None` uses the service's configured `RoundTiming`.
+	fn round_timeout_multiplier(&self) -> Option<u64> { None }
 }
 
 /// Block import trait.
@@ -160,11 +254,11 @@ pub trait Authorities<B: Block> {
 
 /// Instance of BFT agreement.
 struct BftInstance<B: Block, P> {
-	key: Arc<ed25519::Pair>,
+	key: Arc<Signer>,
 	authorities: Vec<AuthorityId>,
 	parent_hash: B::Hash,
 	timer: Timer,
-	round_timeout_multiplier: u64,
+	round_timing: RoundTiming,
 	proposer: P,
 }
 
@@ -194,8 +288,14 @@ impl<B: Block, P: Proposer<B>> generic::Context for BftInstance<B, P>
 		proposal.hash()
 	}
 
-	fn sign_local(&self, message: Message<B>) -> LocalizedMessage<B> {
-		sign_message(message, &*self.key, self.parent_hash.clone())
+	fn sign_local(&self, message: Message<B>) -> Option<LocalizedMessage<B>> {
+		match sign_message(message, &*self.key, self.parent_hash.clone()) {
+			Ok(message) => Some(message),
+			Err(e) => {
+				warn!(target: "bft", "Unable to sign message on top of {}: {}", self.parent_hash, e);
+				None
+			}
+		}
 	}
 
 	fn round_proposer(&self, round: usize) -> AuthorityId {
@@ -207,14 +307,7 @@ impl<B: Block, P: Proposer<B>> generic::Context for BftInstance<B, P>
 	}
 
 	fn begin_round_timeout(&self, round: usize) -> Self::RoundTimeout {
-		use std::time::Duration;
-
-		let round = ::std::cmp::min(63, round) as u32;
-		let timeout = 1u64.checked_shl(round)
-			.unwrap_or_else(u64::max_value)
-			.saturating_mul(self.round_timeout_multiplier);
-
-		Box::new(self.timer.sleep(Duration::from_secs(timeout))
+		Box::new(self.timer.sleep(self.round_timing.round_timeout(round))
 			.map_err(|_| Error::from(ErrorKind::FaultyTimer))
 			.map_err(Into::into))
 	}
@@ -233,6 +326,32 @@ pub struct BftFuture<B, P, I, InStream, OutSink> where
 	cancel: Arc<AtomicBool>,
 	send_task: Option<oneshot::Sender<task::Task>>,
 	import: Arc<I>,
+	round: usize,
+	round_changes: RoundChangeSinks<B::Hash>,
+}
+
+impl<B, P, I, InStream, OutSink> BftFuture<B, P, I, InStream, OutSink> where
+	B: Block + Clone + Eq,
+	B::Hash: ::std::hash::Hash,
+	P: Proposer<B>,
+	InStream: Stream<Item=Communication<B>, Error=P::Error>,
+	OutSink: Sink<SinkItem=Communication<B>, SinkError=P::Error>,
+{
+	// notify listeners if the agreement has moved on to a new round.
+	fn note_round(&mut self) {
+		let round = self.inner.current_round();
+		if round == self.round { return }
+		self.round = round;
+
+		let context = self.inner.context();
+		let change = RoundChange {
+			parent_hash: context.parent_hash.clone(),
+			round,
+			timeout: context.round_timing.round_timeout(round),
+		};
+
+		self.round_changes.lock().retain(|sink| sink.unbounded_send(change.clone()).is_ok());
+	}
 }
 
 impl<B, P, I, InStream, OutSink> Future for BftFuture<B, P, I, InStream, OutSink> where
@@ -259,9 +378,11 @@ impl<B, P, I, InStream, OutSink> Future for BftFuture<B, P, I, InStream, OutSink
 		}
 
 		// TODO: handle this error, at least by logging.
-		let committed = try_ready!(self.inner.poll().map_err(|e| {
+		let polled = self.inner.poll().map_err(|e| {
 			warn!(target: "bft", "Error in BFT agreement: {}", e);
-		}));
+		});
+		self.note_round();
+		let committed = try_ready!(polled);
 
 		// If we didn't see the proposal (very unlikely),
 		// we will get the block from the network later.
@@ -316,8 +437,9 @@ pub struct BftService<B: Block, P, I> {
 	client: Arc<I>,
 	live_agreement: Mutex<Option<(B::Hash, AgreementHandle)>>,
 	timer: Timer,
-	round_timeout_multiplier: u64,
-	key: Arc<ed25519::Pair>, // TODO: key changing over time.
+	round_timing: RoundTiming,
+	round_changes: RoundChangeSinks<B::Hash>,
+	key: Arc<Signer>, // TODO: key changing over time.
 	factory: P,
 }
 
@@ -331,17 +453,31 @@ impl<B, P, I> BftService<B, P, I>
 {
 
 	/// Create a new service instance.
-	pub fn new(client: Arc<I>, key: Arc<ed25519::Pair>, factory: P) -> BftService<B, P, I> {
+	pub fn new(client: Arc<I>, key: Arc<Signer>, factory: P) -> BftService<B, P, I> {
 		BftService {
 			client: client,
 			live_agreement: Mutex::new(None),
 			timer: Timer::default(),
-			round_timeout_multiplier: 4,
+			round_timing: RoundTiming::default(),
+			round_changes: Default::default(),
 			key: key, // TODO: key changing over time.
 			factory: factory,
 		}
 	}
 
+	/// Use the given round timing for agreements started from now on.
+	pub fn with_round_timing(mut self, round_timing: RoundTiming) -> Self {
+		self.round_timing = round_timing;
+		self
+	}
+
+	/// Get a stream of the round changes of all agreements started by this service.
+	pub fn round_change_stream(&self) -> RoundChangeStream<B::Hash> {
+		let (sink, stream) = mpsc::unbounded();
+		self.round_changes.lock().push(sink);
+		stream
+	}
+
 	/// Get the local Authority ID.
 	pub fn local_id(&self) -> AuthorityId {
 		// TODO: based on a header and some keystore.
@@ -379,10 +515,15 @@ impl<B, P, I> BftService<B, P, I>
 
 		let proposer = self.factory.init(header, &authorities, self.key.clone())?;
 
+		let round_timing = match proposer.round_timeout_multiplier() {
+			Some(base_timeout) => self.round_timing.base_timeout(base_timeout),
+			None => self.round_timing,
+		};
+
 		let bft_instance = BftInstance {
 			proposer,
 			parent_hash: hash.clone(),
-			round_timeout_multiplier: self.round_timeout_multiplier,
+			round_timing,
 			timer: self.timer.clone(),
 			key: self.key.clone(),
 			authorities: authorities,
@@ -413,6 +554,8 @@ impl<B, P, I> BftService<B, P, I>
 			cancel: cancel,
 			send_task: Some(tx),
 			import: self.client.clone(),
+			round: 0,
+			round_changes: self.round_changes.clone(),
 		}))
 	}
 
@@ -539,20 +682,20 @@ fn check_action<B: Block>(action: PrimitiveAction<B, B::Hash>, parent_hash: &B::
 }
 
 /// Sign a BFT message with the given key.
-pub fn sign_message<B: Block + Clone>(message: Message<B>, key: &ed25519::Pair, parent_hash: B::Hash) -> LocalizedMessage<B> {
+pub fn sign_message<B: Block + Clone>(message: Message<B>, key: &Signer, parent_hash: B::Hash) -> Result<LocalizedMessage<B>, Error> {
 	let signer = key.public();
 
-	let sign_action = |action: PrimitiveAction<B, B::Hash>| {
+	let sign_action = |action: PrimitiveAction<B, B::Hash>| -> Result<_, Error> {
 		let primitive = PrimitiveMessage {
 			parent: parent_hash.clone(),
 			action,
 		};
 
 		let to_sign = Slicable::encode(&primitive);
-		LocalizedSignature {
+		Ok(LocalizedSignature {
 			signer: signer.clone(),
-			signature: key.sign(&to_sign),
-		}
+			signature: key.sign(&to_sign)?,
+		})
 	};
 
 	match message {
@@ -561,14 +704,14 @@ pub fn sign_message<B: Block + Clone>(message: Message<B>, key: &ed25519::Pair,
 			let action_header = PrimitiveAction::ProposeHeader(r as u32, header_hash.clone());
 			let action_propose = PrimitiveAction::Propose(r as u32, proposal.clone());
 
-			::generic::LocalizedMessage::Propose(::generic::LocalizedProposal {
+			Ok(::generic::LocalizedMessage::Propose(::generic::LocalizedProposal {
 				round_number: r,
 				proposal,
 				digest: header_hash,
 				sender: signer.clone().into(),
-				digest_signature: sign_action(action_header),
-				full_signature: sign_action(action_propose),
-			})
+				digest_signature: sign_action(action_header)?,
+				full_signature: sign_action(action_propose)?,
+			}))
 		}
 		::generic::Message::Vote(vote) => {
 			let action = match vote {
@@ -577,11 +720,11 @@ pub fn sign_message<B: Block + Clone>(message: Message<B>, key: &ed25519::Pair,
 				::generic::Vote::AdvanceRound(r) => PrimitiveAction::AdvanceRound(r as u32),
 			};
 
-			::generic::LocalizedMessage::Vote(::generic::LocalizedVote {
+			Ok(::generic::LocalizedMessage::Vote(::generic::LocalizedVote {
 				vote: vote,
 				sender: signer.clone().into(),
-				signature: sign_action(action),
-			})
+				signature: sign_action(action)?,
+			}))
 		}
 	}
 }
@@ -642,7 +785,7 @@ mod tests {
 		type Proposer = DummyProposer;
 		type Error = Error;
 
-		fn init(&self, parent_header: &TestHeader, _authorities: &[AuthorityId], _sign_with: Arc<ed25519::Pair>) -> Result<DummyProposer, Error> {
+		fn init(&self, parent_header: &TestHeader, _authorities: &[AuthorityId], _sign_with: Arc<Signer>) -> Result<DummyProposer, Error> {
 			Ok(DummyProposer(parent_header.number + 1))
 		}
 	}
@@ -678,14 +821,15 @@ mod tests {
 			client: Arc::new(client),
 			live_agreement: Mutex::new(None),
 			timer: Timer::default(),
-			round_timeout_multiplier: 4,
-			key: Arc::new(Keyring::One.into()),
+			round_timing: RoundTiming::default(),
+			round_changes: Default::default(),
+			key: Arc::new(Keyring::One.pair()),
 			factory: DummyFactory
 		}
 	}
 
 	fn sign_vote(vote: ::generic::Vote<H256>, key: &ed25519::Pair, parent_hash: H256) -> LocalizedSignature {
-		match sign_message::<TestBlock>(vote.into(), key, parent_hash) {
+		match sign_message::<TestBlock>(vote.into(), key, parent_hash).unwrap() {
 			::generic::LocalizedMessage::Vote(vote) => vote.signature,
 			_ => panic!("signing vote leads to signed vote"),
 		}
@@ -739,6 +883,58 @@ mod tests {
 		core.turn(Some(::std::time::Duration::from_millis(100)));
 	}
 
+	#[test]
+	fn round_timeout_backs_off() {
+		let timing = RoundTiming::default()
+			.base_timeout(3)
+			.backoff_factor(2)
+			.max_backoff_rounds(4);
+
+		assert_eq!(timing.round_timeout(0), Duration::from_secs(3));
+		assert_eq!(timing.round_timeout(1), Duration::from_secs(6));
+		assert_eq!(timing.round_timeout(4), Duration::from_secs(48));
+		assert_eq!(timing.round_timeout(10), Duration::from_secs(48));
+
+		let timing = RoundTiming::default().backoff_factor(u64::max_value());
+		assert_eq!(timing.round_timeout(2), Duration::from_secs(u64::max_value()));
+	}
+
+	#[test]
+	fn round_changes_are_reported() {
+		let client = FakeClient {
+			authorities: vec![
+				Keyring::One.to_raw_public().into(),
+				Keyring::Two.to_raw_public().into(),
+				Keyring::Alice.to_raw_public().into(),
+				Keyring::Eve.to_raw_public().into(),
+			],
+			imported_heights: Mutex::new(HashSet::new()),
+		};
+
+		let mut core = Core::new().unwrap();
+
+		let service = make_service(client);
+		let round_changes = service.round_change_stream();
+
+		let first = from_block_number(2);
+		let first_hash = first.hash();
+
+		// the other authorities all vote to advance the first round.
+		let advance_votes = vec![Keyring::Two, Keyring::Alice, Keyring::Eve].into_iter().map(|keyring| {
+			let vote = ::generic::Vote::AdvanceRound(0);
+			Communication::Consensus(sign_message::<TestBlock>(vote.into(), &keyring.pair(), first_hash))
+		}).collect::<Vec<_>>();
+
+		let bft = service.build_upon(&first, stream::iter_ok(advance_votes), Output(Default::default())).unwrap();
+
+		// the agreement fails once the input concludes, but only after advancing.
+		assert!(core.run(bft.unwrap()).is_err());
+
+		let change = round_changes.wait().next().unwrap().unwrap();
+		assert_eq!(change.parent_hash, first_hash);
+		assert_eq!(change.round, 1);
+	}
+
 	#[test]
 	fn max_faulty() {
 		assert_eq!(max_faulty_of(3), 0);
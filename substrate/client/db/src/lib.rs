@@ -55,7 +55,8 @@ use runtime_primitives::BuildStorage;
 use state_machine::backend::Backend as StateBackend;
 use executor::RuntimeInfo;
 use state_machine::{CodeExecutor, TrieH256, DBValue};
-use utils::{Meta, db_err, meta_keys, number_to_db_key, open_database, read_db, read_id, read_meta};
+use utils::{Meta, db_err, meta_keys, number_to_db_key, open_database, read_aux, read_db, read_id, read_meta,
+	write_aux};
 use state_db::StateDb;
 pub use state_db::PruningMode;
 
@@ -72,6 +73,9 @@ pub struct DatabaseSettings {
 	pub path: PathBuf,
 	/// Pruning mode.
 	pub pruning: PruningMode,
+	/// Number of most recently finalized states to keep regardless of `pruning`,
+	/// so that RPC queries a few blocks back keep working.
+	pub retained_finalized_states: u32,
 }
 
 /// Create an instance of db-backed client.
@@ -211,6 +215,9 @@ impl<Block: BlockT> client::blockchain::Backend<Block> for BlockchainDb<Block> w
 pub struct BlockImportOperation<Block: BlockT> {
 	old_state: DbState,
 	updates: MemoryDB,
+	// whether the updates are the full state of the block rather than changes
+	// to its parent's.
+	storage_reset: bool,
 	pending_block: Option<PendingBlock<Block>>,
 }
 
@@ -241,6 +248,7 @@ impl<Block: BlockT> client::backend::BlockImportOperation<Block> for BlockImport
 		// TODO: wipe out existing trie.
 		let (_, update) = self.old_state.storage_root(iter.into_iter().map(|(k, v)| (k, Some(v))));
 		self.updates = update;
+		self.storage_reset = true;
 		Ok(())
 	}
 }
@@ -280,7 +288,7 @@ impl<Block: BlockT> Backend<Block> where <Block::Header as HeaderT>::Number: As<
 	pub fn new(config: DatabaseSettings, finalization_window: u64) -> Result<Self, client::error::Error> {
 		let db = open_database(&config, "full")?;
 
-		Backend::from_kvdb(db as Arc<_>, config.pruning, finalization_window)
+		Backend::from_kvdb(db as Arc<_>, config.pruning, config.retained_finalized_states, finalization_window)
 	}
 
 	#[cfg(test)]
@@ -289,13 +297,14 @@ impl<Block: BlockT> Backend<Block> where <Block::Header as HeaderT>::Number: As<
 
 		let db = Arc::new(::kvdb_memorydb::create(NUM_COLUMNS));
 
-		Backend::from_kvdb(db as Arc<_>, PruningMode::keep_blocks(0), 0).expect("failed to create test-db")
+		Backend::from_kvdb(db as Arc<_>, PruningMode::keep_blocks(0), 0, 0).expect("failed to create test-db")
 	}
 
-	fn from_kvdb(db: Arc<KeyValueDB>, pruning: PruningMode, finalization_window: u64) -> Result<Self, client::error::Error> {
+	fn from_kvdb(db: Arc<KeyValueDB>, pruning: PruningMode, retained_finalized_states: u32, finalization_window: u64) -> Result<Self, client::error::Error> {
 		let blockchain = BlockchainDb::new(db.clone())?;
 		let map_e = |e: state_db::Error<kvdb::Error>| ::client::error::Error::from(format!("State database error: {:?}", e));
-		let state_db: StateDb<Block::Hash, H256> = StateDb::new(pruning, &StateMetaDb(&*db)).map_err(map_e)?;
+		let state_db: StateDb<Block::Hash, H256> = StateDb::with_retention(pruning, retained_finalized_states, &StateMetaDb(&*db))
+			.map_err(map_e)?;
 		let storage_db = StorageDb {
 			db,
 			state_db,
@@ -338,6 +347,7 @@ impl<Block: BlockT> client::backend::Backend<Block> for Backend<Block> where
 			pending_block: None,
 			old_state: state,
 			updates: MemoryDB::default(),
+			storage_reset: false,
 		})
 	}
 
@@ -368,7 +378,13 @@ impl<Block: BlockT> client::backend::Backend<Block> for Backend<Block> where
 				}
 			}
 			let number_u64 = number.as_().into();
-			let commit = self.storage.state_db.insert_block(&hash, number_u64, &pending_block.header.parent_hash(), changeset);
+			// the full state of a block other than genesis comes from a state
+			// snapshot, with no parent state to build on.
+			let commit = if operation.storage_reset && number_u64 > 0 {
+				self.storage.state_db.reset(&hash, number_u64, changeset)
+			} else {
+				self.storage.state_db.insert_block(&hash, number_u64, &pending_block.header.parent_hash(), changeset)
+			};
 			apply_state_commit(&mut transaction, commit);
 
 			//finalize an older block
@@ -411,6 +427,14 @@ impl<Block: BlockT> client::backend::Backend<Block> for Backend<Block> where
 			DbState::with_storage(self.storage.clone(), root.into())
 		}).ok_or_else(|| client::error::ErrorKind::UnknownBlock(format!("{:?}", block)).into()))
 	}
+
+	fn insert_aux(&self, key: &[u8], value: &[u8]) -> Result<(), client::error::Error> {
+		write_aux(&*self.storage.db, key, value)
+	}
+
+	fn get_aux(&self, key: &[u8]) -> Result<Option<Vec<u8>>, client::error::Error> {
+		read_aux(&*self.storage.db, key)
+	}
 }
 
 impl<Block: BlockT> client::backend::LocalBackend<Block> for Backend<Block> where
@@ -651,4 +675,69 @@ mod tests {
 			assert!(backend.storage.db.get(::columns::STATE, &key.0[..]).unwrap().is_none());
 		}
 	}
+
+	#[test]
+	fn blocks_are_imported_on_top_of_state_snapshot() {
+		let db = Backend::<Block>::new_test();
+		let empty_header = |number, parent_hash| Header {
+			number,
+			parent_hash,
+			state_root: Default::default(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+
+		{
+			let mut op = db.begin_operation(BlockId::Hash(Default::default())).unwrap();
+			let mut header = empty_header(0, Default::default());
+			header.state_root = op.old_state.storage_root(::std::iter::empty()).0.into();
+			op.reset_storage(::std::iter::empty()).unwrap();
+			op.set_block_data(header, Some(vec![]), None, true).unwrap();
+			db.commit_operation(op).unwrap();
+		}
+
+		// the state of block 5 is imported without blocks 1 to 4.
+		let snapshot_hash = {
+			let mut op = db.begin_operation(BlockId::Hash(Default::default())).unwrap();
+			let storage = vec![
+				(vec![1, 3, 5], vec![2, 4, 6]),
+				(vec![1, 2, 3], vec![9, 9, 9]),
+			];
+			let mut header = empty_header(5, [4; 32].into());
+			header.state_root = op.old_state.storage_root(storage.iter().cloned().map(|(k, v)| (k, Some(v)))).0.into();
+			let hash = header.hash();
+
+			op.reset_storage(storage.into_iter()).unwrap();
+			op.set_block_data(header, None, None, true).unwrap();
+			db.commit_operation(op).unwrap();
+			hash
+		};
+
+		assert_eq!(db.blockchain().info().unwrap().best_number, 5);
+		assert!(db.blockchain().hash(4).unwrap().is_none());
+
+		{
+			let mut op = db.begin_operation(BlockId::Number(5)).unwrap();
+			let mut header = empty_header(6, snapshot_hash);
+			let (root, overlay) = op.old_state.storage_root(vec![(vec![1, 3, 5], None)].into_iter());
+			op.update_storage(overlay).unwrap();
+			header.state_root = root.into();
+			op.set_block_data(header, Some(vec![]), None, true).unwrap();
+			db.commit_operation(op).unwrap();
+		}
+
+		let state = db.state_at(BlockId::Number(6)).unwrap();
+		assert_eq!(state.storage(&[1, 3, 5]).unwrap(), None);
+		assert_eq!(state.storage(&[1, 2, 3]).unwrap(), Some(vec![9, 9, 9]));
+	}
+
+	#[test]
+	fn aux_data_is_stored_apart_from_meta() {
+		let db = Backend::<Block>::new_test();
+		assert_eq!(db.get_aux(b"best").unwrap(), None);
+
+		db.insert_aux(b"best", &[1, 2, 3]).unwrap();
+		assert_eq!(db.get_aux(b"best").unwrap(), Some(vec![1, 2, 3]));
+		assert!(db.storage.db.get(::columns::META, b"best").unwrap().is_none());
+	}
 }
@@ -29,7 +29,8 @@ use codec::Slicable;
 use primitives::AuthorityId;
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, As, Hashing, HashingFor, Zero};
-use utils::{meta_keys, Meta, db_err, number_to_db_key, open_database, read_db, read_id, read_meta};
+use utils::{meta_keys, Meta, db_err, number_to_db_key, open_database, read_aux, read_db, read_id, read_meta,
+	write_aux};
 use DatabaseSettings;
 
 pub(crate) mod columns {
@@ -163,6 +164,14 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 
 		Ok(())
 	}
+
+	fn insert_aux(&self, key: &[u8], value: &[u8]) -> ClientResult<()> {
+		write_aux(&*self.db, key, value)
+	}
+
+	fn get_aux(&self, key: &[u8]) -> ClientResult<Option<Vec<u8>>> {
+		read_aux(&*self.db, key)
+	}
 }
 
 #[cfg(test)]
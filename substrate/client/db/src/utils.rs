@@ -41,6 +41,8 @@ pub mod meta_keys {
 	pub const TYPE: &[u8; 4] = b"type";
 	/// Best block key.
 	pub const BEST_BLOCK: &[u8; 4] = b"best";
+	/// Prefix of the keys of auxiliary data.
+	pub const AUX_PREFIX: &[u8; 4] = b"aux:";
 }
 
 /// Database metadata.
@@ -68,6 +70,20 @@ pub fn number_to_db_key<N>(n: N) -> BlockKey where N: As<u32> {
 	]
 }
 
+/// Read auxiliary data stored in the meta column under the given key.
+pub fn read_aux(db: &KeyValueDB, key: &[u8]) -> client::error::Result<Option<Vec<u8>>> {
+	let aux_key = [&meta_keys::AUX_PREFIX[..], key].concat();
+	db.get(COLUMN_META, &aux_key).map(|value| value.map(|v| v.to_vec())).map_err(db_err)
+}
+
+/// Write auxiliary data to the meta column under the given key.
+pub fn write_aux(db: &KeyValueDB, key: &[u8], value: &[u8]) -> client::error::Result<()> {
+	let aux_key = [&meta_keys::AUX_PREFIX[..], key].concat();
+	let mut transaction = DBTransaction::new();
+	transaction.put(COLUMN_META, &aux_key, value);
+	db.write(transaction).map_err(db_err)
+}
+
 /// Maps database error to client error
 pub fn db_err(err: kvdb::Error) -> client::error::Error {
 	use std::error::Error;
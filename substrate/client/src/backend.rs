@@ -69,6 +69,10 @@ pub trait Backend<Block: BlockT>: Send + Sync {
 	fn blockchain(&self) -> &Self::Blockchain;
 	/// Returns state backend with post-state of given block.
 	fn state_at(&self, block: BlockId<Block>) -> error::Result<Self::State>;
+	/// Insert auxiliary data, which isn't part of the chain, under the given key.
+	fn insert_aux(&self, key: &[u8], value: &[u8]) -> error::Result<()>;
+	/// Get auxiliary data stored under the given key.
+	fn get_aux(&self, key: &[u8]) -> error::Result<Option<Vec<u8>>>;
 }
 
 /// Mark for all Backend implementations, that are making use of state data, stored locally.
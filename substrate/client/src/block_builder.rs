@@ -19,6 +19,7 @@
 use std::vec::Vec;
 use codec::Slicable;
 use state_machine;
+use executor::ExecutionStrategy;
 use runtime_primitives::traits::{Header as HeaderT, Hashing as HashingT, Block as BlockT, One, HashingFor};
 use runtime_primitives::generic::BlockId;
 use {backend, error, Client, CallExecutor};
@@ -33,6 +34,7 @@ pub struct BlockBuilder<B, E, Block> where
 	header: <Block as BlockT>::Header,
 	extrinsics: Vec<<Block as BlockT>::Extrinsic>,
 	executor: E,
+	strategy: ExecutionStrategy,
 	state: B::State,
 	changes: state_machine::OverlayedChanges,
 }
@@ -59,6 +61,7 @@ impl<B, E, Block> BlockBuilder<B, E, Block> where
 			.ok_or_else(|| error::ErrorKind::UnknownBlock(format!("{}", block_id)))?;
 
 		let executor = client.executor().clone();
+		let strategy = client.execution_strategies().block_construction;
 		let state = client.state_at(block_id)?;
 		let mut changes = Default::default();
 		let header = <<Block as BlockT>::Header as HeaderT>::new(
@@ -69,12 +72,13 @@ impl<B, E, Block> BlockBuilder<B, E, Block> where
 			Default::default()
 		);
 
-		executor.call_at_state(&state, &mut changes, "initialise_block", &header.encode())?;
+		executor.call_at_state(&state, &mut changes, "initialise_block", &header.encode(), strategy)?;
 
 		Ok(BlockBuilder {
 			header,
 			extrinsics: Vec::new(),
 			executor,
+			strategy,
 			state,
 			changes,
 		})
@@ -84,7 +88,7 @@ impl<B, E, Block> BlockBuilder<B, E, Block> where
 	/// can be validly executed (by executing it); if it is invalid, it'll be returned along with
 	/// the error. Otherwise, it will return a mutable reference to self (in order to chain).
 	pub fn push(&mut self, xt: <Block as BlockT>::Extrinsic) -> error::Result<()> {
-		match self.executor.call_at_state(&self.state, &mut self.changes, "apply_extrinsic", &xt.encode()) {
+		match self.executor.call_at_state(&self.state, &mut self.changes, "apply_extrinsic", &xt.encode(), self.strategy) {
 			Ok(_) => {
 				self.extrinsics.push(xt);
 				Ok(())
@@ -103,6 +107,7 @@ impl<B, E, Block> BlockBuilder<B, E, Block> where
 			&mut self.changes,
 			"finalise_block",
 			&[],
+			self.strategy,
 		)?;
 		self.header = <<Block as BlockT>::Header as Slicable>::decode(&mut &output[..])
 			.expect("Header came straight out of runtime so must be valid");
@@ -18,7 +18,7 @@ use std::sync::Arc;
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::Block as BlockT;
 use state_machine::{self, OverlayedChanges, Backend as StateBackend, CodeExecutor};
-use executor::{RuntimeVersion, RuntimeInfo};
+use executor::{ExecutionStrategy, RuntimeVersion, RuntimeInfo, WasmExecutor};
 
 use backend;
 use error;
@@ -37,15 +37,16 @@ pub trait CallExecutor<B: BlockT> {
 	/// Externalities error type.
 	type Error: state_machine::Error;
 
-	/// Execute a call to a contract on top of state in a block of given hash.
+	/// Execute a call to a contract on top of state in a block of given hash,
+	/// with the given strategy where it applies.
 	///
 	/// No changes are made.
-	fn call(&self, id: &BlockId<B>, method: &str, call_data: &[u8]) -> Result<CallResult, error::Error>;
+	fn call(&self, id: &BlockId<B>, method: &str, call_data: &[u8], strategy: ExecutionStrategy) -> Result<CallResult, error::Error>;
 
-	/// Execute a call to a contract on top of given state.
+	/// Execute a call to a contract on top of given state with the given strategy.
 	///
 	/// No changes are made.
-	fn call_at_state<S: state_machine::Backend>(&self, state: &S, overlay: &mut OverlayedChanges, method: &str, call_data: &[u8]) -> Result<(Vec<u8>, S::Transaction), error::Error>;
+	fn call_at_state<S: state_machine::Backend>(&self, state: &S, overlay: &mut OverlayedChanges, method: &str, call_data: &[u8], strategy: ExecutionStrategy) -> Result<(Vec<u8>, S::Transaction), error::Error>;
 
 	/// Execute a call to a contract on top of given state, gathering execution proof.
 	///
@@ -88,20 +89,29 @@ impl<B, E, Block> CallExecutor<Block> for LocalCallExecutor<B, E>
 {
 	type Error = E::Error;
 
-	fn call(&self, id: &BlockId<Block>, method: &str, call_data: &[u8]) -> error::Result<CallResult> {
+	fn call(&self, id: &BlockId<Block>, method: &str, call_data: &[u8], strategy: ExecutionStrategy) -> error::Result<CallResult> {
 		let mut changes = OverlayedChanges::default();
-		let (return_data, _) = self.call_at_state(&self.backend.state_at(*id)?, &mut changes, method, call_data)?;
+		let (return_data, _) = self.call_at_state(&self.backend.state_at(*id)?, &mut changes, method, call_data, strategy)?;
 		Ok(CallResult{ return_data, changes })
 	}
 
-	fn call_at_state<S: state_machine::Backend>(&self, state: &S, changes: &mut OverlayedChanges, method: &str, call_data: &[u8]) -> error::Result<(Vec<u8>, S::Transaction)> {
-		state_machine::execute(
-			state,
-			changes,
-			&self.executor,
-			method,
-			call_data,
-		).map_err(Into::into)
+	fn call_at_state<S: state_machine::Backend>(&self, state: &S, changes: &mut OverlayedChanges, method: &str, call_data: &[u8], strategy: ExecutionStrategy) -> error::Result<(Vec<u8>, S::Transaction)> {
+		match strategy {
+			ExecutionStrategy::NativeWhenPossible => state_machine::execute(
+				state,
+				changes,
+				&self.executor,
+				method,
+				call_data,
+			),
+			ExecutionStrategy::AlwaysWasm => state_machine::execute(
+				state,
+				changes,
+				&WasmExecutor,
+				method,
+				call_data,
+			),
+		}.map_err(Into::into)
 	}
 
 	fn prove_at_state<S: state_machine::Backend>(&self, state: S, changes: &mut OverlayedChanges, method: &str, call_data: &[u8]) -> Result<(Vec<u8>, Vec<Vec<u8>>), error::Error> {
@@ -17,11 +17,13 @@
 //! Substrate Client
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 use futures::sync::mpsc;
-use parking_lot::{Mutex, RwLock};
+use parking_lot::{Mutex, MutexGuard, RwLock};
 use primitives::AuthorityId;
 use runtime_primitives::{bft::Justification, generic::{BlockId, SignedBlock, Block as RuntimeBlock}};
-use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, Zero, One};
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, Hashing as HashingT, Zero, One};
 use runtime_primitives::BuildStorage;
 use primitives::storage::{StorageKey, StorageData};
 use codec::Slicable;
@@ -29,26 +31,47 @@ use state_machine::{self, Ext, OverlayedChanges, Backend as StateBackend, CodeEx
 
 use backend::{self, BlockImportOperation};
 use blockchain::{self, Info as ChainInfo, Backend as ChainBackend, HeaderBackend as ChainHeaderBackend};
-use call_executor::{CallExecutor, LocalCallExecutor};
-use executor::{RuntimeVersion, RuntimeInfo};
+use call_executor::{CallExecutor, CallResult, LocalCallExecutor};
+use executor::{ExecutionStrategy, RuntimeVersion, RuntimeInfo};
 use {error, in_mem, block_builder, runtime_io, bft, genesis};
 
 /// Type that implements `futures::Stream` of block import events.
 pub type BlockchainEventStream<Block> = mpsc::UnboundedReceiver<BlockImportNotification<Block>>;
 
+/// Type that implements `futures::Stream` of best chain reorganizations.
+pub type ChainReorgStream<Block> = mpsc::UnboundedReceiver<ChainReorgNotification<Block>>;
+
 /// Substrate Client
 pub struct Client<B, E, Block> where Block: BlockT {
 	backend: Arc<B>,
 	executor: E,
+	execution_strategies: ExecutionStrategies,
 	import_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<BlockImportNotification<Block>>>>,
+	reorg_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<ChainReorgNotification<Block>>>>,
 	import_lock: Mutex<()>,
+	// number of priority lane imports waiting for the import lock.
+	priority_imports: AtomicUsize,
 	importing_block: RwLock<Option<Block::Hash>>, // holds the block hash currently being imported. TODO: replace this with block queue
 }
 
+/// Strategies for executing the runtime, by what it is called for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionStrategies {
+	/// Executing blocks being imported.
+	pub importing: ExecutionStrategy,
+	/// Building new blocks.
+	pub block_construction: ExecutionStrategy,
+	/// Anything else, such as RPC calls.
+	pub other: ExecutionStrategy,
+}
+
 /// A source of blockchain evenets.
 pub trait BlockchainEvents<Block: BlockT> {
 	/// Get block import event stream.
 	fn import_notification_stream(&self) -> mpsc::UnboundedReceiver<BlockImportNotification<Block>>;
+
+	/// Get the stream of best chain reorganizations.
+	fn reorg_notification_stream(&self) -> ChainReorgStream<Block>;
 }
 
 /// Chain head information.
@@ -114,6 +137,18 @@ pub enum BlockOrigin {
 	File,
 }
 
+impl BlockOrigin {
+	/// Whether blocks of this origin are imported in the priority lane: blocks
+	/// authored or agreed on locally, and new blocks at the head of the chain,
+	/// don't wait behind a backlog of blocks being synced or read from a file.
+	pub fn is_priority(&self) -> bool {
+		match *self {
+			BlockOrigin::Own | BlockOrigin::ConsensusBroadcast | BlockOrigin::NetworkBroadcast => true,
+			BlockOrigin::Genesis | BlockOrigin::NetworkInitialSync | BlockOrigin::File => false,
+		}
+	}
+}
+
 /// Summary of an imported block
 #[derive(Clone, Debug)]
 pub struct BlockImportNotification<Block: BlockT> {
@@ -127,6 +162,19 @@ pub struct BlockImportNotification<Block: BlockT> {
 	pub is_new_best: bool,
 }
 
+/// The best chain switched to a block which doesn't descend from the previous best block.
+///
+/// Blocks simply extending the best chain are reported by import notifications only.
+#[derive(Clone, Debug)]
+pub struct ChainReorgNotification<Block: BlockT> {
+	/// Blocks which left the best chain, from the previous best block down to
+	/// the child of the common ancestor.
+	pub retracted: Vec<Block::Hash>,
+	/// Blocks which joined the best chain, from the child of the common ancestor
+	/// up to the new best block.
+	pub enacted: Vec<Block::Hash>,
+}
+
 /// A header paired with a justification which has already been checked.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct JustifiedHeader<Block: BlockT> {
@@ -141,6 +189,27 @@ impl<Block: BlockT> JustifiedHeader<Block> {
 	}
 }
 
+/// Check a header's justification against a set of authorities, without
+/// reading the chain. This can be called from any thread, so the justifications
+/// of many headers may be checked in parallel ahead of their import.
+pub fn check_justification_with<Block: BlockT>(
+	authorities: &[AuthorityId],
+	header: <Block as BlockT>::Header,
+	justification: ::bft::UncheckedJustification<Block::Hash>,
+) -> error::Result<JustifiedHeader<Block>> {
+	let parent_hash = header.parent_hash().clone();
+	let just = ::bft::check_justification::<Block>(authorities, parent_hash, justification)
+		.map_err(|_|
+			error::ErrorKind::BadJustification(
+				format!("{}", header.hash())
+			)
+		)?;
+	Ok(JustifiedHeader {
+		header,
+		justification: just,
+	})
+}
+
 /// Create an instance of in-memory client.
 pub fn new_in_mem<E, Block, S>(
 	executor: E,
@@ -180,12 +249,36 @@ impl<B, E, Block> Client<B, E, Block> where
 		Ok(Client {
 			backend,
 			executor,
+			execution_strategies: Default::default(),
 			import_notification_sinks: Mutex::new(Vec::new()),
+			reorg_notification_sinks: Mutex::new(Vec::new()),
 			import_lock: Mutex::new(()),
+			priority_imports: AtomicUsize::new(0),
 			importing_block: RwLock::new(None),
 		})
 	}
 
+	/// Use the given strategies for executing the runtime.
+	pub fn with_execution_strategies(mut self, strategies: ExecutionStrategies) -> Self {
+		self.execution_strategies = strategies;
+		self
+	}
+
+	/// Strategies used for executing the runtime.
+	pub fn execution_strategies(&self) -> &ExecutionStrategies {
+		&self.execution_strategies
+	}
+
+	/// Insert auxiliary data, which isn't part of the chain, under the given key.
+	pub fn insert_aux(&self, key: &[u8], value: &[u8]) -> error::Result<()> {
+		self.backend.insert_aux(key, value)
+	}
+
+	/// Get auxiliary data stored under the given key.
+	pub fn get_aux(&self, key: &[u8]) -> error::Result<Option<Vec<u8>>> {
+		self.backend.get_aux(key)
+	}
+
 	/// Get a reference to the state at a given block.
 	pub fn state_at(&self, block: &BlockId<Block>) -> error::Result<B::State> {
 		self.backend.state_at(*block)
@@ -211,7 +304,7 @@ impl<B, E, Block> Client<B, E, Block> where
 
 	/// Get the set of authorities at a given block.
 	pub fn authorities_at(&self, id: &BlockId<Block>) -> error::Result<Vec<AuthorityId>> {
-		self.executor.call(id, "authorities", &[])
+		self.executor.call(id, "authorities", &[], self.execution_strategies.other)
 			.and_then(|r| Vec::<AuthorityId>::decode(&mut &r.return_data[..])
 				.ok_or(error::ErrorKind::AuthLenInvalid.into()))
 	}
@@ -219,7 +312,7 @@ impl<B, E, Block> Client<B, E, Block> where
 	/// Get the set of authorities at a given block.
 	pub fn runtime_version_at(&self, id: &BlockId<Block>) -> error::Result<RuntimeVersion> {
 		// TODO: Post Poc-2 return an error if version is missing
-		Ok(self.executor.call(id, "version", &[])
+		Ok(self.executor.call(id, "version", &[], self.execution_strategies.other)
 			.and_then(|r| RuntimeVersion::decode(&mut &r.return_data[..])
 				.ok_or(error::ErrorKind::VersionInvalid.into()))
 			.unwrap_or_default())
@@ -230,6 +323,33 @@ impl<B, E, Block> Client<B, E, Block> where
 		&self.executor
 	}
 
+	/// Execute a call to a contract on top of state in a block of given hash,
+	/// such as for RPC.
+	///
+	/// No changes are made.
+	pub fn call(&self, id: &BlockId<Block>, method: &str, call_data: &[u8]) -> error::Result<CallResult> {
+		self.executor.call(id, method, call_data, self.execution_strategies.other)
+	}
+
+	/// Run the upgrade hooks of the given runtime code on top of the state in a
+	/// block, as if the code was set there, without making any changes.
+	///
+	/// Returns the keys the hooks changed, or `None` if they wouldn't run.
+	pub fn dry_run_upgrade(&self, id: &BlockId<Block>, code: Vec<u8>) -> error::Result<Option<Vec<StorageKey>>> {
+		let mut overlay = OverlayedChanges::default();
+		overlay.set_storage(b":code".to_vec(), Some(code));
+		let (ran, _) = self.executor.call_at_state(&self.state_at(id)?, &mut overlay, "runtime_upgrade", &[], ExecutionStrategy::AlwaysWasm)?;
+		if !bool::decode(&mut &ran[..]).ok_or(error::ErrorKind::UpgradeResultInvalid)? {
+			return Ok(None);
+		}
+
+		Ok(Some(overlay.drain()
+			.map(|(key, _)| key)
+			.filter(|key| &key[..] != b":code")
+			.map(StorageKey)
+			.collect()))
+	}
+
 	/// Execute a call to a contract on top of state in a block of given hash
 	/// AND returning execution proof.
 	///
@@ -238,6 +358,14 @@ impl<B, E, Block> Client<B, E, Block> where
 		self.state_at(id).and_then(|state| self.executor.prove_at_state(state, &mut Default::default(), method, call_data))
 	}
 
+	/// Reads storage value at a given block + key, returning read proof.
+	pub fn read_proof(&self, id: &BlockId<Block>, key: &[u8]) -> error::Result<Vec<Vec<u8>>> {
+		self.state_at(id)
+			.and_then(|state| state_machine::prove_read(state, key)
+				.map(|(_, proof)| proof)
+				.map_err(Into::into))
+	}
+
 	/// Set up the native execution environment to call into a native runtime code.
 	pub fn using_environment<F: FnOnce() -> T, T>(
 		&self, f: F
@@ -273,16 +401,7 @@ impl<B, E, Block> Client<B, E, Block> where
 	) -> error::Result<JustifiedHeader<Block>> {
 		let parent_hash = header.parent_hash().clone();
 		let authorities = self.authorities_at(&BlockId::Hash(parent_hash))?;
-		let just = ::bft::check_justification::<Block>(&authorities[..], parent_hash, justification)
-			.map_err(|_|
-				error::ErrorKind::BadJustification(
-					format!("{}", header.hash())
-				)
-			)?;
-		Ok(JustifiedHeader {
-			header,
-			justification: just,
-		})
+		check_justification_with(&authorities[..], header, justification)
 	}
 
 	/// Queue a block for import.
@@ -299,13 +418,33 @@ impl<B, E, Block> Client<B, E, Block> where
 			blockchain::BlockStatus::Unknown => return Ok(ImportResult::UnknownParent),
 		}
 		let hash = header.hash();
-		let _import_lock = self.import_lock.lock();
+		let _import_lock = self.lock_import(&origin);
 		*self.importing_block.write() = Some(hash);
 		let result = self.execute_and_import_block(origin, hash, header, justification, body);
 		*self.importing_block.write() = None;
 		result
 	}
 
+	// Take the import lock. Priority lane imports take it before any waiting
+	// backlog import, which hands the lock over while one is pending.
+	fn lock_import(&self, origin: &BlockOrigin) -> MutexGuard<()> {
+		if origin.is_priority() {
+			self.priority_imports.fetch_add(1, Ordering::SeqCst);
+			let guard = self.import_lock.lock();
+			self.priority_imports.fetch_sub(1, Ordering::SeqCst);
+			return guard;
+		}
+
+		loop {
+			let guard = self.import_lock.lock();
+			if self.priority_imports.load(Ordering::SeqCst) == 0 {
+				return guard;
+			}
+			MutexGuard::unlock_fair(guard);
+			thread::yield_now();
+		}
+	}
+
 	fn execute_and_import_block(
 		&self,
 		origin: BlockOrigin,
@@ -324,11 +463,14 @@ impl<B, E, Block> Client<B, E, Block> where
 		let storage_update = match transaction.state()? {
 			Some(transaction_state) => {
 				let mut overlay = Default::default();
+				let strategy = self.execution_strategies.importing;
+				trace!("Executing block {} with {:?}", hash, strategy);
 				let (_, storage_update) = self.executor.call_at_state(
 					transaction_state,
 					&mut overlay,
 					"execute_block",
-					&<Block as BlockT>::new(header.clone(), body.clone().unwrap_or_default()).encode()
+					&<Block as BlockT>::new(header.clone(), body.clone().unwrap_or_default()).encode(),
+					strategy,
 				)?;
 
 				Some(storage_update)
@@ -336,13 +478,17 @@ impl<B, E, Block> Client<B, E, Block> where
 			None => None,
 		};
 
-		let is_new_best = header.number() == &(self.backend.blockchain().info()?.best_number + One::one());
+		let chain_info = self.backend.blockchain().info()?;
+		let is_new_best = header.number() == &(chain_info.best_number + One::one());
 		trace!("Imported {}, (#{}), best={}, origin={:?}", hash, header.number(), is_new_best, origin);
 		transaction.set_block_data(header.clone(), body, Some(justification.uncheck().into()), is_new_best)?;
 		if let Some(storage_update) = storage_update {
 			transaction.update_storage(storage_update)?;
 		}
 		self.backend.commit_operation(transaction)?;
+		if is_new_best && parent_hash != chain_info.best_hash {
+			self.notify_reorg(chain_info.best_hash, hash)?;
+		}
 		if origin == BlockOrigin::NetworkBroadcast || origin == BlockOrigin::Own || origin == BlockOrigin::ConsensusBroadcast {
 			let notification = BlockImportNotification::<Block> {
 				hash: hash,
@@ -356,6 +502,72 @@ impl<B, E, Block> Client<B, E, Block> where
 		Ok(ImportResult::Queued)
 	}
 
+	// report the blocks retracted and enacted by a switch of the best block
+	// from `from` to `to`, walking both branches back to their common ancestor.
+	fn notify_reorg(&self, from: Block::Hash, to: Block::Hash) -> error::Result<()> {
+		let load_header = |hash: Block::Hash| -> error::Result<Block::Header> {
+			self.backend.blockchain().header(BlockId::Hash(hash))?
+				.ok_or_else(|| error::ErrorKind::UnknownBlock(format!("{}", hash)).into())
+		};
+
+		let mut retracted = Vec::new();
+		let mut enacted = Vec::new();
+		let mut from_header = load_header(from)?;
+		let mut to_header = load_header(to)?;
+		while to_header.number() > from_header.number() {
+			enacted.push(to_header.hash());
+			to_header = load_header(to_header.parent_hash().clone())?;
+		}
+		while from_header.number() > to_header.number() {
+			retracted.push(from_header.hash());
+			from_header = load_header(from_header.parent_hash().clone())?;
+		}
+		while from_header.hash() != to_header.hash() {
+			retracted.push(from_header.hash());
+			enacted.push(to_header.hash());
+			from_header = load_header(from_header.parent_hash().clone())?;
+			to_header = load_header(to_header.parent_hash().clone())?;
+		}
+		enacted.reverse();
+
+		debug!("Best chain reorganized: {} blocks retracted, {} enacted", retracted.len(), enacted.len());
+		let notification = ChainReorgNotification::<Block> { retracted, enacted };
+		self.reorg_notification_sinks.lock()
+			.retain(|sink| sink.unbounded_send(notification.clone()).is_ok());
+		Ok(())
+	}
+
+	/// Import the full state of a block whose ancestors aren't known, replacing the
+	/// state of the best block. The justification can't be checked without the
+	/// state of the parent, so the header must come from a trusted source.
+	pub fn import_state(
+		&self,
+		header: <Block as BlockT>::Header,
+		justification: ::bft::UncheckedJustification<Block::Hash>,
+		storage: Vec<(Vec<u8>, Vec<u8>)>,
+	) -> error::Result<ImportResult> {
+		let hash = header.hash();
+		let _import_lock = self.import_lock.lock();
+		match self.backend.blockchain().status(BlockId::Hash(hash))? {
+			blockchain::BlockStatus::InChain => return Ok(ImportResult::AlreadyInChain),
+			blockchain::BlockStatus::Unknown => {},
+		}
+
+		let state_root = <<<Block as BlockT>::Header as HeaderT>::Hashing as HashingT>::trie_root(
+			storage.iter().map(|&(ref k, ref v)| (k, v))
+		);
+		if &state_root != header.state_root() {
+			bail!(error::ErrorKind::InvalidStateSnapshot(format!("{}", hash)));
+		}
+
+		info!("Importing state of block #{} ({})", header.number(), hash);
+		let mut transaction = self.backend.begin_operation(BlockId::Hash(Default::default()))?;
+		transaction.reset_storage(storage.into_iter())?;
+		transaction.set_block_data(header, None, Some(justification.into()), true)?;
+		self.backend.commit_operation(transaction)?;
+		Ok(ImportResult::Queued)
+	}
+
 	/// Get blockchain info.
 	pub fn info(&self) -> error::Result<ClientInfo<Block>> {
 		let info = self.backend.blockchain().info().map_err(|e| error::Error::from_blockchain(Box::new(e)))?;
@@ -483,6 +695,13 @@ impl<B, E, Block> BlockchainEvents<Block> for Client<B, E, Block>
 		self.import_notification_sinks.lock().push(sink);
 		stream
 	}
+
+	/// Get the stream of best chain reorganizations.
+	fn reorg_notification_stream(&self) -> ChainReorgStream<Block> {
+		let (sink, stream) = mpsc::unbounded();
+		self.reorg_notification_sinks.lock().push(sink);
+		stream
+	}
 }
 
 impl<B, E, Block> ChainHead<Block> for Client<B, E, Block>
@@ -501,9 +720,10 @@ impl<B, E, Block> ChainHead<Block> for Client<B, E, Block>
 mod tests {
 	use super::*;
 	use codec::Slicable;
+	use futures::Stream;
 	use keyring::Keyring;
 	use test_client::{self, TestClient};
-	use test_client::client::BlockOrigin;
+	use test_client::client::{BlockOrigin, ExecutionStrategies, ExecutionStrategy};
 	use test_client::runtime as test_runtime;
 	use test_client::runtime::{Transfer, Extrinsic};
 
@@ -538,6 +758,32 @@ mod tests {
 		assert_eq!(client.info().unwrap().chain.best_number, 1);
 	}
 
+	#[test]
+	fn state_of_a_block_without_ancestors_is_imported() {
+		let source = test_client::new();
+		for _ in 0..2 {
+			let builder = source.new_block().unwrap();
+			source.justify_and_import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+		}
+		let header = source.best_block_header().unwrap();
+		let hash = header.hash();
+		let storage = source.state_at(&BlockId::Hash(hash)).unwrap().pairs();
+		let justification = source.justification(&BlockId::Hash(hash)).unwrap().unwrap();
+
+		let client = test_client::new();
+		let mut partial = storage.clone();
+		partial.pop();
+		assert!(client.import_state(header.clone(), justification.clone().into(), partial).is_err());
+		match client.import_state(header, justification.into(), storage).unwrap() {
+			ImportResult::Queued => {},
+			result => panic!("unexpected import result {:?}", result),
+		}
+
+		assert_eq!(client.info().unwrap().chain.best_number, 2);
+		assert_eq!(client.info().unwrap().chain.best_hash, hash);
+		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Alice.to_raw_public().into())).unwrap(), 1000);
+	}
+
 	fn sign_tx(tx: Transfer) -> Extrinsic {
 		let signature = Keyring::from_raw_public(tx.from.0.clone()).unwrap().sign(&tx.encode()).into();
 		Extrinsic { transfer: tx, signature }
@@ -562,5 +808,99 @@ mod tests {
 		assert!(client.state_at(&BlockId::Number(1)).unwrap() != client.state_at(&BlockId::Number(0)).unwrap());
 		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Alice.to_raw_public().into())).unwrap(), 958);
 		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Ferdie.to_raw_public().into())).unwrap(), 42);
+		assert_eq!(client.using_environment(test_runtime::system::events).unwrap(), vec![
+			test_runtime::Event::Transferred(Keyring::Alice.to_raw_public().into(), Keyring::Ferdie.to_raw_public().into(), 42),
+		]);
+	}
+
+	#[test]
+	fn blocks_are_built_and_imported_in_wasm() {
+		let client = test_client::new().with_execution_strategies(ExecutionStrategies {
+			importing: ExecutionStrategy::AlwaysWasm,
+			block_construction: ExecutionStrategy::AlwaysWasm,
+			other: ExecutionStrategy::AlwaysWasm,
+		});
+
+		let mut builder = client.new_block().unwrap();
+		builder.push(sign_tx(Transfer {
+			from: Keyring::Alice.to_raw_public().into(),
+			to: Keyring::Ferdie.to_raw_public().into(),
+			amount: 42,
+			nonce: 0,
+		})).unwrap();
+
+		client.justify_and_import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+
+		assert_eq!(client.info().unwrap().chain.best_number, 1);
+		assert_eq!(client.authorities_at(&BlockId::Number(1)).unwrap().len(), 3);
+		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Ferdie.to_raw_public().into())).unwrap(), 42);
+	}
+
+	#[test]
+	fn switching_to_a_fork_is_notified() {
+		let client = test_client::new();
+		let reorgs = client.reorg_notification_stream();
+		let genesis = client.info().unwrap().chain.best_hash;
+
+		let builder = client.new_block().unwrap();
+		client.justify_and_import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+		let a1 = client.info().unwrap().chain.best_hash;
+
+		// a fork of the same length doesn't take over the best chain.
+		let mut builder = client.new_block_at(&BlockId::Hash(genesis)).unwrap();
+		builder.push(sign_tx(Transfer {
+			from: Keyring::Alice.to_raw_public().into(),
+			to: Keyring::Ferdie.to_raw_public().into(),
+			amount: 42,
+			nonce: 0,
+		})).unwrap();
+		let b1 = builder.bake().unwrap();
+		let b1_hash = b1.header.hash();
+		client.justify_and_import(BlockOrigin::NetworkBroadcast, b1).unwrap();
+		assert_eq!(client.info().unwrap().chain.best_hash, a1);
+
+		let builder = client.new_block_at(&BlockId::Hash(b1_hash)).unwrap();
+		let b2 = builder.bake().unwrap();
+		let b2_hash = b2.header.hash();
+		client.justify_and_import(BlockOrigin::NetworkBroadcast, b2).unwrap();
+		assert_eq!(client.info().unwrap().chain.best_hash, b2_hash);
+
+		// extending the new best chain isn't a reorganization.
+		let builder = client.new_block().unwrap();
+		client.justify_and_import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+
+		drop(client);
+		let reorgs: Vec<_> = reorgs.wait().map(|n| n.unwrap()).collect();
+		assert_eq!(reorgs.len(), 1);
+		assert_eq!(reorgs[0].retracted, vec![a1]);
+		assert_eq!(reorgs[0].enacted, vec![b1_hash, b2_hash]);
+	}
+
+	#[test]
+	fn priority_imports_go_before_the_backlog() {
+		let client = Arc::new(test_client::new());
+		let order = Arc::new(Mutex::new(Vec::new()));
+		let guard = client.lock_import(&BlockOrigin::NetworkInitialSync);
+
+		let spawn = |origin: BlockOrigin| {
+			let client = client.clone();
+			let order = order.clone();
+			thread::spawn(move || {
+				let _guard = client.lock_import(&origin);
+				order.lock().push(origin);
+			})
+		};
+
+		// a block being synced is imported while an own block waits, then a file import is queued.
+		let own = spawn(BlockOrigin::Own);
+		while client.priority_imports.load(Ordering::SeqCst) == 0 {
+			thread::yield_now();
+		}
+		let file = spawn(BlockOrigin::File);
+
+		drop(guard);
+		own.join().unwrap();
+		file.join().unwrap();
+		assert_eq!(*order.lock(), vec![BlockOrigin::Own, BlockOrigin::File]);
 	}
 }
@@ -76,6 +76,12 @@ error_chain! {
 			display("On-chain runtime does not specify version"),
 		}
 
+		/// Could not tell whether the upgrade hooks of a runtime ran.
+		UpgradeResultInvalid {
+			description("runtime upgrade error"),
+			display("Runtime does not tell whether its upgrade hooks ran"),
+		}
+
 		/// Invalid state data.
 		AuthInvalid(i: u32) {
 			description("authority value state error"),
@@ -111,6 +117,18 @@ error_chain! {
 			description("remote fetch failed"),
 			display("Remote data fetch has been failed"),
 		}
+
+		/// Remote node has responded with data which doesn't match the request.
+		InvalidRemoteResponse(h: String) {
+			description("invalid remote response"),
+			display("Remote node has responded with invalid data: {}", h),
+		}
+
+		/// State snapshot doesn't match the state root of its block.
+		InvalidStateSnapshot(h: String) {
+			description("invalid state snapshot"),
+			display("State snapshot doesn't match the state root of block {}", h),
+		}
 	}
 }
 
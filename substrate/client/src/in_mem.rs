@@ -85,6 +85,7 @@ struct BlockchainStorage<Block: BlockT> {
 	best_hash: Block::Hash,
 	best_number: <<Block as BlockT>::Header as HeaderT>::Number,
 	genesis_hash: Block::Hash,
+	aux: HashMap<Vec<u8>, Vec<u8>>,
 }
 
 /// In-memory blockchain. Supports concurrent reads.
@@ -111,6 +112,7 @@ impl<Block: BlockT> Blockchain<Block> {
 				best_hash: Default::default(),
 				best_number: Zero::zero(),
 				genesis_hash: Default::default(),
+				aux: HashMap::new(),
 			}));
 		Blockchain {
 			storage: storage,
@@ -139,6 +141,16 @@ impl<Block: BlockT> Blockchain<Block> {
 		}
 	}
 
+	/// Insert auxiliary data under the given key.
+	pub fn insert_aux(&self, key: &[u8], value: &[u8]) {
+		self.storage.write().aux.insert(key.to_vec(), value.to_vec());
+	}
+
+	/// Get auxiliary data stored under the given key.
+	pub fn get_aux(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.storage.read().aux.get(key).cloned()
+	}
+
 	/// Compare this blockchain with another in-mem blockchain
 	pub fn equals_to(&self, other: &Self) -> bool {
 		self.canon_equals_to(other) && self.storage.read().blocks == other.storage.read().blocks
@@ -205,6 +217,15 @@ impl<Block: BlockT> light::blockchain::Storage<Block> for Blockchain<Block> {
 		self.insert(hash, header, None, None, is_new_best);
 		Ok(())
 	}
+
+	fn insert_aux(&self, key: &[u8], value: &[u8]) -> error::Result<()> {
+		Blockchain::insert_aux(self, key, value);
+		Ok(())
+	}
+
+	fn get_aux(&self, key: &[u8]) -> error::Result<Option<Vec<u8>>> {
+		Ok(Blockchain::get_aux(self, key))
+	}
 }
 
 /// In-memory operation.
@@ -309,6 +330,15 @@ impl<Block> backend::Backend<Block> for Backend<Block> where
 			None => Err(error::ErrorKind::UnknownBlock(format!("{}", block)).into()),
 		}
 	}
+
+	fn insert_aux(&self, key: &[u8], value: &[u8]) -> error::Result<()> {
+		self.blockchain.insert_aux(key, value);
+		Ok(())
+	}
+
+	fn get_aux(&self, key: &[u8]) -> error::Result<Option<Vec<u8>>> {
+		Ok(self.blockchain.get_aux(key))
+	}
 }
 
 impl<Block: BlockT> backend::LocalBackend<Block> for Backend<Block> {}
@@ -50,10 +50,12 @@ mod call_executor;
 mod client;
 
 pub use client::{
-	new_in_mem,
+	new_in_mem, check_justification_with,
 	BlockStatus, BlockOrigin, BlockchainEventStream, BlockchainEvents,
-	Client, ClientInfo, ChainHead,
+	ChainReorgNotification, ChainReorgStream,
+	Client, ClientInfo, ChainHead, ExecutionStrategies,
 	ImportResult, JustifiedHeader,
 };
 pub use blockchain::Info as ChainInfo;
 pub use call_executor::{CallResult, CallExecutor, LocalCallExecutor};
+pub use executor::ExecutionStrategy;
@@ -18,6 +18,7 @@
 //! Everything else is requested from full nodes on demand.
 
 use std::sync::{Arc, Weak};
+use futures::{Future, IntoFuture};
 
 use runtime_primitives::{bft::Justification, generic::BlockId};
 use runtime_primitives::traits::Block as BlockT;
@@ -28,7 +29,7 @@ use backend::{Backend as ClientBackend, BlockImportOperation, RemoteBackend};
 use blockchain::HeaderBackend as BlockchainHeaderBackend;
 use error::{Error as ClientError, ErrorKind as ClientErrorKind, Result as ClientResult};
 use light::blockchain::{Blockchain, Storage as BlockchainStorage};
-use light::fetcher::Fetcher;
+use light::fetcher::{Fetcher, RemoteReadRequest};
 
 /// Light client backend.
 pub struct Backend<S, F> {
@@ -93,6 +94,14 @@ impl<S, F, Block> ClientBackend<Block> for Backend<S, F> where Block: BlockT, S:
 			fetcher: self.blockchain.fetcher(),
 		})
 	}
+
+	fn insert_aux(&self, key: &[u8], value: &[u8]) -> ClientResult<()> {
+		self.blockchain.storage().insert_aux(key, value)
+	}
+
+	fn get_aux(&self, key: &[u8]) -> ClientResult<Option<Vec<u8>>> {
+		self.blockchain.storage().get_aux(key)
+	}
 }
 
 impl<S, F, Block> RemoteBackend<Block> for Backend<S, F> where Block: BlockT, S: BlockchainStorage<Block>, F: Fetcher<Block> {}
@@ -141,8 +150,13 @@ impl<Block, F> StateBackend for OnDemandState<Block, F> where Block: BlockT, F:
 	type Error = ClientError;
 	type Transaction = ();
 
-	fn storage(&self, _key: &[u8]) -> ClientResult<Option<Vec<u8>>> {
-		Err(ClientErrorKind::NotAvailableOnLightClient.into()) // TODO: fetch from remote node
+	fn storage(&self, key: &[u8]) -> ClientResult<Option<Vec<u8>>> {
+		self.fetcher.upgrade().ok_or(ClientErrorKind::NotAvailableOnLightClient)?
+			.remote_read(RemoteReadRequest {
+				block: self.block,
+				key: key.to_vec(),
+			})
+			.into_future().wait()
 	}
 
 	fn for_keys_with_prefix<A: FnMut(&[u8])>(&self, _prefix: &[u8], _action: A) {
@@ -168,20 +182,35 @@ impl<Block, F> TryIntoStateTrieBackend for OnDemandState<Block, F> where Block:
 
 #[cfg(test)]
 pub mod tests {
-	use futures::future::{ok, FutureResult};
+	use futures::future::{err, ok, FutureResult};
 	use parking_lot::Mutex;
 	use call_executor::CallResult;
-	use error::Error as ClientError;
-	use test_client::runtime::{Hash, Block};
-	use light::fetcher::{Fetcher, RemoteCallRequest};
+	use error::{Error as ClientError, ErrorKind as ClientErrorKind};
+	use test_client::runtime::{Hash, Block, Header, Extrinsic};
+	use light::fetcher::{Fetcher, RemoteCallRequest, RemoteHeaderRequest, RemoteBodyRequest, RemoteReadRequest};
 
 	pub type OkCallFetcher = Mutex<CallResult>;
 
 	impl Fetcher<Block> for OkCallFetcher {
 		type RemoteCallResult = FutureResult<CallResult, ClientError>;
+		type RemoteHeaderResult = FutureResult<Header, ClientError>;
+		type RemoteBodyResult = FutureResult<Vec<Extrinsic>, ClientError>;
+		type RemoteReadResult = FutureResult<Option<Vec<u8>>, ClientError>;
 
 		fn remote_call(&self, _request: RemoteCallRequest<Hash>) -> Self::RemoteCallResult {
 			ok((*self.lock()).clone())
 		}
+
+		fn remote_header(&self, _request: RemoteHeaderRequest<Hash>) -> Self::RemoteHeaderResult {
+			err(ClientErrorKind::NotAvailableOnLightClient.into())
+		}
+
+		fn remote_body(&self, _request: RemoteBodyRequest<Header>) -> Self::RemoteBodyResult {
+			err(ClientErrorKind::NotAvailableOnLightClient.into())
+		}
+
+		fn remote_read(&self, _request: RemoteReadRequest<Hash>) -> Self::RemoteReadResult {
+			err(ClientErrorKind::NotAvailableOnLightClient.into())
+		}
 	}
 }
@@ -32,6 +32,12 @@ use light::fetcher::Fetcher;
 pub trait Storage<Block: BlockT>: BlockchainHeaderBackend<Block> {
 	/// Store new header.
 	fn import_header(&self, is_new_best: bool, header: Block::Header) -> ClientResult<()>;
+
+	/// Insert auxiliary data under the given key.
+	fn insert_aux(&self, key: &[u8], value: &[u8]) -> ClientResult<()>;
+
+	/// Get auxiliary data stored under the given key.
+	fn get_aux(&self, key: &[u8]) -> ClientResult<Option<Vec<u8>>>;
 }
 
 /// Light client blockchain.
@@ -19,11 +19,14 @@
 use std::sync::Arc;
 use futures::IntoFuture;
 
-use runtime_primitives::traits::{As, Block as BlockT, Header as HeaderT};
-use state_machine::CodeExecutor;
+use codec::Slicable;
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{As, Block as BlockT, Header as HeaderT, Hashing, HashingFor};
+use state_machine::{CodeExecutor, read_proof_check};
 
+use blockchain::HeaderBackend as BlockchainHeaderBackend;
 use call_executor::CallResult;
-use error::{Error as ClientError, Result as ClientResult};
+use error::{Error as ClientError, ErrorKind as ClientErrorKind, Result as ClientResult};
 use light::blockchain::{Blockchain, Storage as BlockchainStorage};
 use light::call_executor::check_execution_proof;
 
@@ -38,20 +41,62 @@ pub struct RemoteCallRequest<Hash: ::std::fmt::Display> {
 	pub call_data: Vec<u8>,
 }
 
+/// Remote block header request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RemoteHeaderRequest<Hash> {
+	/// Hash of the requested block.
+	pub block: Hash,
+}
+
+/// Remote block body request. The header must be known, so that the body can
+/// be checked against its extrinsics root.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RemoteBodyRequest<Header> {
+	/// Header of the requested block.
+	pub header: Header,
+}
+
+/// Remote storage read request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RemoteReadRequest<Hash> {
+	/// Read at state of given block.
+	pub block: Hash,
+	/// Storage key to read.
+	pub key: Vec<u8>,
+}
+
 /// Light client data fetcher. Implementations of this trait must check if remote data
 /// is correct (see FetchedDataChecker) and return already checked data.
 pub trait Fetcher<Block: BlockT>: Send + Sync {
 	/// Remote call result future.
 	type RemoteCallResult: IntoFuture<Item=CallResult, Error=ClientError>;
+	/// Remote header result future.
+	type RemoteHeaderResult: IntoFuture<Item=Block::Header, Error=ClientError>;
+	/// Remote body result future.
+	type RemoteBodyResult: IntoFuture<Item=Vec<Block::Extrinsic>, Error=ClientError>;
+	/// Remote storage read result future.
+	type RemoteReadResult: IntoFuture<Item=Option<Vec<u8>>, Error=ClientError>;
 
 	/// Fetch remote call result.
 	fn remote_call(&self, request: RemoteCallRequest<Block::Hash>) -> Self::RemoteCallResult;
+	/// Fetch remote block header.
+	fn remote_header(&self, request: RemoteHeaderRequest<Block::Hash>) -> Self::RemoteHeaderResult;
+	/// Fetch remote block body.
+	fn remote_body(&self, request: RemoteBodyRequest<Block::Header>) -> Self::RemoteBodyResult;
+	/// Fetch remote storage value.
+	fn remote_read(&self, request: RemoteReadRequest<Block::Hash>) -> Self::RemoteReadResult;
 }
 
 /// Light client remote data checker.
 pub trait FetchChecker<Block: BlockT>: Send + Sync {
 	/// Check remote method execution proof.
 	fn check_execution_proof(&self, request: &RemoteCallRequest<Block::Hash>, remote_proof: Vec<Vec<u8>>) -> ClientResult<CallResult>;
+	/// Check remote header: it must be the header of the requested block.
+	fn check_header_response(&self, request: &RemoteHeaderRequest<Block::Hash>, remote_header: Option<Block::Header>) -> ClientResult<Block::Header>;
+	/// Check remote block body against the extrinsics root of its header.
+	fn check_body_response(&self, request: &RemoteBodyRequest<Block::Header>, remote_body: Option<Vec<Block::Extrinsic>>) -> ClientResult<Vec<Block::Extrinsic>>;
+	/// Check remote storage read proof against the state root of the block.
+	fn check_read_proof(&self, request: &RemoteReadRequest<Block::Hash>, remote_proof: Vec<Vec<u8>>) -> ClientResult<Option<Vec<u8>>>;
 }
 
 /// Remote data checker.
@@ -87,4 +132,78 @@ impl<S, E, F, Block> FetchChecker<Block> for LightDataChecker<S, E, F>
 	fn check_execution_proof(&self, request: &RemoteCallRequest<Block::Hash>, remote_proof: Vec<Vec<u8>>) -> ClientResult<CallResult> {
 		check_execution_proof(&*self.blockchain, &self.executor, request, remote_proof)
 	}
+
+	fn check_header_response(&self, request: &RemoteHeaderRequest<Block::Hash>, remote_header: Option<Block::Header>) -> ClientResult<Block::Header> {
+		check_header_response::<Block>(request, remote_header)
+	}
+
+	fn check_body_response(&self, request: &RemoteBodyRequest<Block::Header>, remote_body: Option<Vec<Block::Extrinsic>>) -> ClientResult<Vec<Block::Extrinsic>> {
+		check_body_response::<Block>(request, remote_body)
+	}
+
+	fn check_read_proof(&self, request: &RemoteReadRequest<Block::Hash>, remote_proof: Vec<Vec<u8>>) -> ClientResult<Option<Vec<u8>>> {
+		let local_header = self.blockchain.header(BlockId::Hash(request.block))?;
+		let local_header = local_header.ok_or_else(|| ClientErrorKind::UnknownBlock(format!("{}", request.block)))?;
+		let local_state_root = *local_header.state_root();
+		read_proof_check(local_state_root.into(), remote_proof, &request.key).map_err(Into::into)
+	}
+}
+
+fn check_header_response<Block: BlockT>(
+	request: &RemoteHeaderRequest<Block::Hash>,
+	remote_header: Option<Block::Header>,
+) -> ClientResult<Block::Header> {
+	let remote_header = remote_header.ok_or_else(|| ClientErrorKind::InvalidRemoteResponse(
+		format!("no header of block {}", request.block)))?;
+	let remote_hash = remote_header.hash();
+	if remote_hash != request.block {
+		return Err(ClientErrorKind::InvalidRemoteResponse(
+			format!("header of block {} instead of {}", remote_hash, request.block)).into());
+	}
+
+	Ok(remote_header)
+}
+
+fn check_body_response<Block: BlockT>(
+	request: &RemoteBodyRequest<Block::Header>,
+	remote_body: Option<Vec<Block::Extrinsic>>,
+) -> ClientResult<Vec<Block::Extrinsic>> {
+	let block = request.header.hash();
+	let remote_body = remote_body.ok_or_else(|| ClientErrorKind::InvalidRemoteResponse(
+		format!("no body of block {}", block)))?;
+	let extrinsics_root = HashingFor::<Block>::ordered_trie_root(remote_body.iter().map(Slicable::encode));
+	if extrinsics_root != *request.header.extrinsics_root() {
+		return Err(ClientErrorKind::InvalidRemoteResponse(
+			format!("body doesn't match the extrinsics root of block {}", block)).into());
+	}
+
+	Ok(remote_body)
+}
+
+#[cfg(test)]
+mod tests {
+	use test_client::{self, runtime::Block};
+	use super::*;
+
+	#[test]
+	fn header_and_body_responses_are_checked() {
+		let client = test_client::new();
+		let header = client.header(&BlockId::Number(0)).unwrap().unwrap();
+		let body = client.body(&BlockId::Number(0)).unwrap().unwrap();
+		let mut other_header = header.clone();
+		other_header.number = 1;
+
+		let request = RemoteHeaderRequest { block: header.hash() };
+		assert_eq!(check_header_response::<Block>(&request, Some(header.clone())).unwrap(), header);
+		assert!(check_header_response::<Block>(&request, Some(other_header)).is_err());
+		assert!(check_header_response::<Block>(&request, None).is_err());
+
+		let request = RemoteBodyRequest { header: header.clone() };
+		assert_eq!(check_body_response::<Block>(&request, Some(body.clone())).unwrap(), body);
+		assert!(check_body_response::<Block>(&request, None).is_err());
+
+		let mut other_request = request.clone();
+		other_request.header.extrinsics_root = Default::default();
+		assert!(check_body_response::<Block>(&other_request, Some(body)).is_err());
+	}
 }
@@ -66,6 +66,22 @@ pub use state_machine::Externalities;
 pub use runtime_version::RuntimeVersion;
 pub use codec::Slicable;
 
+/// How calls into the runtime are executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionStrategy {
+	/// Execute natively when the native runtime can stand in for the on-chain
+	/// one, and the on-chain Wasm otherwise.
+	NativeWhenPossible,
+	/// Always execute the on-chain Wasm.
+	AlwaysWasm,
+}
+
+impl Default for ExecutionStrategy {
+	fn default() -> Self {
+		ExecutionStrategy::NativeWhenPossible
+	}
+}
+
 /// Provides runtime information.
 pub trait RuntimeInfo {
 	/// Native runtime information if any.
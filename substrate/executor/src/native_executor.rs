@@ -88,16 +88,17 @@ impl<D: NativeExecutionDispatch + Sync + Send> CodeExecutor for NativeExecutor<D
 		method: &str,
 		data: &[u8],
 	) -> Result<Vec<u8>> {
-		if code == D::native_equivalent() {
-			// call native
-			D::dispatch(ext, method, data)
-		} else {
+		let native = code == D::native_equivalent() || {
 			let version = WasmExecutor.call(ext, code, "version", &[])?;
 			let version = RuntimeVersion::decode(&mut version.as_slice());
-			if version.map_or(false, |v| D::VERSION.can_call_with(&v)) {
-				return D::dispatch(ext, method, data)
-			}
-			// call into wasm.
+			version.map_or(false, |v| D::VERSION.can_call_with(&v))
+		};
+
+		if native {
+			trace!(target: "executor", "Executing {} natively", method);
+			D::dispatch(ext, method, data)
+		} else {
+			trace!(target: "executor", "Executing {} in Wasm", method);
 			WasmExecutor.call(ext, code, method, data)
 		}
 	}
@@ -39,8 +39,7 @@ fn check_message_sig<B: Slicable, H: Slicable>(
 	signature: &Signature,
 	from: &AuthorityId
 ) -> bool {
-	let msg: Vec<u8> = message.encode();
-	runtime_io::ed25519_verify(&signature.0, &msg, from)
+	message.using_encoded(|msg| runtime_io::ed25519_verify(&signature.0, msg, from))
 }
 
 fn prepare<B, H>(parent: H, round_number: u32, hash: H) -> Message<B, H> {
@@ -98,7 +97,7 @@ mod tests {
 			generic::Message::Vote(generic::Vote::Prepare(round as _, hash)),
 			key,
 			parent_hash
-		);
+		).unwrap();
 
 		match msg {
 			generic::LocalizedMessage::Vote(vote) => (hash, vote.signature.signature),
@@ -111,7 +110,7 @@ mod tests {
 			generic::Message::Vote(generic::Vote::Commit(round as _, hash)),
 			key,
 			parent_hash
-		);
+		).unwrap();
 
 		match msg {
 			generic::LocalizedMessage::Vote(vote) => (hash, vote.signature.signature),
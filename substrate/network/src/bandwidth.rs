@@ -0,0 +1,257 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Accounting of the bandwidth used by each peer.
+//!
+//! The bytes received from and sent to each connected peer are counted.
+//! Optional rate limits work as token buckets: a peer may use up to a burst of
+//! bytes at once, refilled at the rate over time. Packets of a peer sending
+//! faster than the inbound limit are dropped, and packets which would exceed
+//! the outbound limit of a peer aren't sent, so that a single peer can't take
+//! all of the node's bandwidth.
+//!
+//! The bytes received and sent by the node over each protocol are also
+//! summed up since it started, so that its throughput can be reported.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use parking_lot::Mutex;
+
+use network::{PeerId, ProtocolId};
+
+/// Default time of traffic at the limited rate which may be used at once, in seconds.
+pub const DEFAULT_BURST_SECS: u64 = 4;
+
+/// Limits on the bandwidth used by a single peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BandwidthConfig {
+	/// Bytes per second a peer may send. `None` is unlimited.
+	pub inbound_rate: Option<u64>,
+	/// Bytes per second which may be sent to a peer. `None` is unlimited.
+	pub outbound_rate: Option<u64>,
+	/// Seconds of traffic at the limited rate which may be used at once.
+	pub burst_secs: u64,
+}
+
+impl Default for BandwidthConfig {
+	fn default() -> Self {
+		BandwidthConfig {
+			inbound_rate: None,
+			outbound_rate: None,
+			burst_secs: DEFAULT_BURST_SECS,
+		}
+	}
+}
+
+/// Bytes exchanged with a peer since it connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PeerBandwidth {
+	/// Bytes received from the peer.
+	pub bytes_received: u64,
+	/// Bytes sent to the peer.
+	pub bytes_sent: u64,
+}
+
+/// Bytes exchanged over a protocol since the node started.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolBandwidth {
+	/// Name of the protocol.
+	pub protocol: String,
+	/// Bytes received from all peers.
+	pub bytes_received: u64,
+	/// Bytes sent to all peers.
+	pub bytes_sent: u64,
+}
+
+/// Bytes received and sent by the node over each protocol. Shared by the
+/// handlers of the protocols, which count them, and the network service.
+#[derive(Clone, Default)]
+pub struct BandwidthSinks {
+	totals: Arc<Mutex<HashMap<ProtocolId, PeerBandwidth>>>,
+}
+
+impl BandwidthSinks {
+	/// Note a packet of `len` bytes received over a protocol.
+	pub fn on_received(&self, protocol: ProtocolId, len: usize) {
+		self.totals.lock().entry(protocol).or_insert_with(Default::default).bytes_received += len as u64;
+	}
+
+	/// Note a packet of `len` bytes sent over a protocol.
+	pub fn on_sent(&self, protocol: ProtocolId, len: usize) {
+		self.totals.lock().entry(protocol).or_insert_with(Default::default).bytes_sent += len as u64;
+	}
+
+	/// Bytes exchanged over each protocol which was used, by protocol name.
+	pub fn by_protocol(&self) -> Vec<ProtocolBandwidth> {
+		let mut totals: Vec<_> = self.totals.lock().iter().map(|(id, usage)| ProtocolBandwidth {
+			protocol: String::from_utf8_lossy(&id[..]).into_owned(),
+			bytes_received: usage.bytes_received,
+			bytes_sent: usage.bytes_sent,
+		}).collect();
+		totals.sort_by(|a, b| a.protocol.cmp(&b.protocol));
+		totals
+	}
+}
+
+struct Bucket {
+	tokens: u64,
+	refilled_at: Instant,
+}
+
+impl Bucket {
+	fn new(now: Instant) -> Self {
+		Bucket { tokens: u64::max_value(), refilled_at: now }
+	}
+
+	// take `len` tokens if available, after refilling at `rate` up to the burst.
+	fn take(&mut self, len: u64, rate: u64, burst_secs: u64, now: Instant) -> bool {
+		let capacity = rate.saturating_mul(burst_secs);
+		let elapsed = now.duration_since(self.refilled_at);
+		let elapsed_ms = elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000;
+		let refill = rate.saturating_mul(elapsed_ms) / 1000;
+		self.tokens = ::std::cmp::min(self.tokens.saturating_add(refill), capacity);
+		if refill > 0 {
+			self.refilled_at = now;
+		}
+
+		if len > self.tokens {
+			return false;
+		}
+		self.tokens -= len;
+		true
+	}
+}
+
+struct Entry {
+	usage: PeerBandwidth,
+	inbound: Bucket,
+	outbound: Bucket,
+}
+
+/// Bandwidth used by connected peers.
+pub struct Bandwidth {
+	config: BandwidthConfig,
+	peers: HashMap<PeerId, Entry>,
+}
+
+impl Bandwidth {
+	/// Create with the given limits.
+	pub fn new(config: BandwidthConfig) -> Self {
+		Bandwidth {
+			config,
+			peers: HashMap::new(),
+		}
+	}
+
+	fn entry(&mut self, peer: PeerId, now: Instant) -> &mut Entry {
+		self.peers.entry(peer).or_insert_with(|| Entry {
+			usage: Default::default(),
+			inbound: Bucket::new(now),
+			outbound: Bucket::new(now),
+		})
+	}
+
+	/// Note a packet of `len` bytes received from a peer at `now`. Returns
+	/// `false` if the peer exceeds its inbound limit and the packet should be
+	/// dropped.
+	pub fn on_received(&mut self, peer: PeerId, len: usize, now: Instant) -> bool {
+		let config = self.config;
+		let entry = self.entry(peer, now);
+		entry.usage.bytes_received += len as u64;
+		match config.inbound_rate {
+			Some(rate) => entry.inbound.take(len as u64, rate, config.burst_secs, now),
+			None => true,
+		}
+	}
+
+	/// Check whether a packet of `len` bytes may be sent to a peer at `now`
+	/// without exceeding its outbound limit, counting it if so.
+	pub fn on_send(&mut self, peer: PeerId, len: usize, now: Instant) -> bool {
+		let config = self.config;
+		let entry = self.entry(peer, now);
+		let allowed = match config.outbound_rate {
+			Some(rate) => entry.outbound.take(len as u64, rate, config.burst_secs, now),
+			None => true,
+		};
+		if allowed {
+			entry.usage.bytes_sent += len as u64;
+		}
+		allowed
+	}
+
+	/// Bytes exchanged with a peer, if any.
+	pub fn usage(&self, peer: PeerId) -> Option<PeerBandwidth> {
+		self.peers.get(&peer).map(|entry| entry.usage)
+	}
+
+	/// Forget a disconnected peer.
+	pub fn remove(&mut self, peer: PeerId) {
+		self.peers.remove(&peer);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	#[test]
+	fn peers_are_limited_to_their_rate() {
+		let mut bandwidth = Bandwidth::new(BandwidthConfig {
+			inbound_rate: Some(100),
+			outbound_rate: Some(10),
+			burst_secs: 2,
+		});
+		let now = Instant::now();
+
+		// the burst may be used at once.
+		assert!(bandwidth.on_received(1, 150, now));
+		assert!(!bandwidth.on_received(1, 100, now));
+		assert!(bandwidth.on_received(2, 200, now));
+
+		// and is refilled at the rate.
+		let later = now + Duration::from_millis(500);
+		assert!(bandwidth.on_received(1, 100, later));
+		assert!(!bandwidth.on_received(1, 1, later));
+
+		assert!(bandwidth.on_send(1, 20, now));
+		assert!(!bandwidth.on_send(1, 1, now));
+
+		// dropped packets are counted as received, but not as sent.
+		assert_eq!(bandwidth.usage(1), Some(PeerBandwidth { bytes_received: 351, bytes_sent: 20 }));
+
+		bandwidth.remove(1);
+		assert_eq!(bandwidth.usage(1), None);
+		assert_eq!(bandwidth.usage(2), Some(PeerBandwidth { bytes_received: 200, bytes_sent: 0 }));
+	}
+
+	#[test]
+	fn protocols_are_counted_apart() {
+		let sinks = BandwidthSinks::default();
+		let handle = sinks.clone();
+		sinks.on_received(*b"dot", 100);
+		handle.on_sent(*b"dot", 20);
+		handle.on_received(*b"abc", 5);
+		sinks.on_received(*b"dot", 1);
+
+		assert_eq!(handle.by_protocol(), vec![
+			ProtocolBandwidth { protocol: "abc".into(), bytes_received: 5, bytes_sent: 0 },
+			ProtocolBandwidth { protocol: "dot".into(), bytes_received: 101, bytes_sent: 20 },
+		]);
+	}
+}
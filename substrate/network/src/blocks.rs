@@ -17,19 +17,66 @@
 use std::mem;
 use std::cmp;
 use std::ops::Range;
-use std::collections::{HashMap, BTreeMap};
+use std::time::{Duration, Instant};
+use std::collections::{HashMap, HashSet, BTreeMap};
 use std::collections::hash_map::Entry;
 use network::PeerId;
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
+use codec::Slicable;
 use message;
 
-const MAX_PARALLEL_DOWNLOADS: u32 = 1;
+// Maximum number of peers a range is requested from at once.
+const MAX_PARALLEL_DOWNLOADS: u32 = 2;
+
+// Time after which a range still being downloaded is requested from another peer as well.
+const SLOW_DOWNLOAD_SECS: u64 = 10;
+
+/// Default maximum number of downloaded blocks waiting to be imported.
+pub const DEFAULT_MAX_QUEUED_BLOCKS: usize = 8192;
+
+/// Default maximum size in bytes of the downloaded blocks waiting to be imported.
+pub const DEFAULT_MAX_QUEUED_BYTES: usize = 256 * 1024 * 1024;
+
+/// Limits on the downloaded blocks waiting to be imported. Once either is
+/// reached, only the blocks needed to import those already downloaded are
+/// requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueLimits {
+	/// Maximum number of blocks.
+	pub max_blocks: usize,
+	/// Maximum size of the block data in bytes.
+	pub max_bytes: usize,
+}
+
+impl Default for QueueLimits {
+	fn default() -> Self {
+		QueueLimits {
+			max_blocks: DEFAULT_MAX_QUEUED_BLOCKS,
+			max_bytes: DEFAULT_MAX_QUEUED_BYTES,
+		}
+	}
+}
+
+/// Size of the encoded data of a block.
+pub fn data_size<B: BlockT>(data: &message::BlockData<B>) -> usize {
+	let header = data.header.as_ref().map_or(0, |h| h.encode().len());
+	let body = match data.body {
+		Some(message::Body::Extrinsics(ref xts)) => xts.iter().fold(0, |size, xt| size + xt.encode().len()),
+		Some(message::Body::V1(ref xts)) => xts.iter().fold(0, |size, xt| size + xt.0.len()),
+		None => 0,
+	};
+	let justification = data.justification.as_ref().map_or(0, |j| j.encode().len());
+	header + body + justification
+}
 
 /// Block data with origin.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BlockData<B: BlockT> {
 	pub block: message::BlockData<B>,
+	/// The peer which sent the block. Only valid as long as it stays connected.
 	pub origin: PeerId,
+	/// The node ID of the peer which sent the block, if known.
+	pub origin_node: Option<String>,
 }
 
 #[derive(Debug)]
@@ -37,6 +84,8 @@ enum BlockRangeState<B: BlockT> {
 	Downloading {
 		len: u64,
 		downloading: u32,
+		/// When the range was last requested.
+		requested_at: Instant,
 	},
 	Complete(Vec<BlockData<B>>),
 }
@@ -56,6 +105,9 @@ pub struct BlockCollection<B: BlockT> {
 	/// Downloaded blocks.
 	blocks: BTreeMap<u64, BlockRangeState<B>>,
 	peer_requests: HashMap<PeerId, u64>,
+	/// Size of the data of each downloaded range, by start.
+	range_sizes: HashMap<u64, usize>,
+	queued_bytes: usize,
 }
 
 impl<B: BlockT> BlockCollection<B> where B::Header: HeaderT<Number=u64> {
@@ -64,6 +116,8 @@ impl<B: BlockT> BlockCollection<B> where B::Header: HeaderT<Number=u64> {
 		BlockCollection {
 			blocks: BTreeMap::new(),
 			peer_requests: HashMap::new(),
+			range_sizes: HashMap::new(),
+			queued_bytes: 0,
 		}
 	}
 
@@ -71,20 +125,45 @@ impl<B: BlockT> BlockCollection<B> where B::Header: HeaderT<Number=u64> {
 	pub fn clear(&mut self) {
 		self.blocks.clear();
 		self.peer_requests.clear();
+		self.range_sizes.clear();
+		self.queued_bytes = 0;
+	}
+
+	/// The number of downloaded blocks, and the size of their data in bytes.
+	pub fn queued(&self) -> (usize, usize) {
+		let blocks = self.blocks.values().fold(0, |count, range| match *range {
+			BlockRangeState::Complete(ref blocks) => count + blocks.len(),
+			BlockRangeState::Downloading { .. } => count,
+		});
+		(blocks, self.queued_bytes)
+	}
+
+	/// Whether the first block from `from` which isn't downloaded yet is being
+	/// downloaded.
+	pub fn next_in_progress(&self, from: u64) -> bool {
+		let mut next = from;
+		for (start, range) in &self.blocks {
+			if *start > next {
+				break;
+			}
+			match *range {
+				BlockRangeState::Complete(ref blocks) => next = cmp::max(next, *start + blocks.len() as u64),
+				BlockRangeState::Downloading { len, .. } => if *start + len > next {
+					return true;
+				},
+			}
+		}
+		false
 	}
 
-	/// Insert a set of blocks into collection.
-	pub fn insert(&mut self, start: u64, blocks: Vec<message::BlockData<B>>, peer_id: PeerId) {
+	/// Insert a set of blocks into collection, with the peer which sent them and its node ID.
+	pub fn insert(&mut self, start: u64, blocks: Vec<message::BlockData<B>>, peer_id: PeerId, node_id: Option<String>) {
 		if blocks.is_empty() {
 			return;
 		}
 
+		// a range requested from several peers completes with the first response.
 		match self.blocks.get(&start) {
-			Some(&BlockRangeState::Downloading { .. }) => {
-				trace!(target: "sync", "Ignored block data still marked as being downloaded: {}", start);
-				debug_assert!(false);
-				return;
-			},
 			Some(&BlockRangeState::Complete(ref existing)) if existing.len() >= blocks.len() => {
 				trace!(target: "sync", "Ignored block data already downloaded: {}", start);
 				return;
@@ -92,22 +171,41 @@ impl<B: BlockT> BlockCollection<B> where B::Header: HeaderT<Number=u64> {
 			_ => (),
 		}
 
-		self.blocks.insert(start, BlockRangeState::Complete(blocks.into_iter().map(|b| BlockData { origin: peer_id, block: b }).collect()));
+		let size = blocks.iter().fold(0, |size, b| size + data_size::<B>(b));
+		self.queued_bytes += size;
+		if let Some(replaced) = self.range_sizes.insert(start, size) {
+			self.queued_bytes -= replaced;
+		}
+
+		self.blocks.insert(start, BlockRangeState::Complete(blocks.into_iter().map(|b| BlockData {
+			origin: peer_id,
+			origin_node: node_id.clone(),
+			block: b,
+		}).collect()));
 	}
 
 	/// Returns a set of block hashes that require a header download. The returned set is marked as being downloaded.
+	///
+	/// A range which has been downloading for too long is handed out again, so
+	/// that a slow peer doesn't hold up the import of the blocks after it.
 	pub fn needed_blocks(&mut self, peer_id: PeerId, count: usize, peer_best: u64, common: u64) -> Option<Range<u64>> {
 		// First block number that we need to download
 		let first_different = common + 1;
 		let count = count as u64;
+		let now = Instant::now();
+		let slow = Duration::from_secs(SLOW_DOWNLOAD_SECS);
 		let (mut range, downloading) = {
 			let mut downloading_iter = self.blocks.iter().peekable();
 			let mut prev: Option<(&u64, &BlockRangeState<B>)> = None;
 			loop {
 				let next = downloading_iter.next();
 				break match &(prev, next) {
-					&(Some((start, &BlockRangeState::Downloading { ref len, downloading })), _) if downloading < MAX_PARALLEL_DOWNLOADS  =>
-						(*start .. *start + *len, downloading),
+					&(Some((start, &BlockRangeState::Downloading { ref len, downloading, requested_at })), _)
+						if downloading < MAX_PARALLEL_DOWNLOADS && now.duration_since(requested_at) >= slow =>
+					{
+						trace!(target: "sync", "Range {} is slow to download, requesting it from {} as well", start, peer_id);
+						(*start .. *start + *len, downloading)
+					},
 					&(Some((start, r)), Some((next_start, _))) if start + r.len() < *next_start =>
 						(*start + r.len() .. cmp::min(*next_start, *start + r.len() + count), 0), // gap
 					&(Some((start, r)), None) =>
@@ -130,7 +228,11 @@ impl<B: BlockT> BlockCollection<B> where B::Header: HeaderT<Number=u64> {
 		}
 		range.end = cmp::min(peer_best + 1, range.end);
 		self.peer_requests.insert(peer_id, range.start);
-		self.blocks.insert(range.start, BlockRangeState::Downloading{ len: range.end - range.start, downloading: downloading + 1 });
+		self.blocks.insert(range.start, BlockRangeState::Downloading {
+			len: range.end - range.start,
+			downloading: downloading + 1,
+			requested_at: now,
+		});
 		if range.end <= range.start {
 			panic!("Empty range {:?}, count={}, peer_best={}, common={}, blocks={:?}", range, count, peer_best, common, self.blocks);
 		}
@@ -141,14 +243,16 @@ impl<B: BlockT> BlockCollection<B> where B::Header: HeaderT<Number=u64> {
 	pub fn drain(&mut self, from: u64) -> Vec<BlockData<B>> {
 		let mut drained = Vec::new();
 		let mut ranges = Vec::new();
+		let mut hashes = HashSet::new();
 		{
 			let mut prev = from;
 			for (start, range_data) in &mut self.blocks {
 				match range_data {
 					&mut BlockRangeState::Complete(ref mut blocks) if *start <= prev => {
-							prev = *start + blocks.len() as u64;
-							let mut blocks = mem::replace(blocks, Vec::new());
-							drained.append(&mut blocks);
+							prev = cmp::max(prev, *start + blocks.len() as u64);
+							// overlapping ranges repeat blocks, which are drained once.
+							let blocks = mem::replace(blocks, Vec::new());
+							drained.extend(blocks.into_iter().filter(|b| hashes.insert(b.block.hash)));
 							ranges.push(*start);
 					},
 					_ => break,
@@ -157,6 +261,9 @@ impl<B: BlockT> BlockCollection<B> where B::Header: HeaderT<Number=u64> {
 		}
 		for r in ranges {
 			self.blocks.remove(&r);
+			if let Some(size) = self.range_sizes.remove(&r) {
+				self.queued_bytes -= size;
+			}
 		}
 		trace!(target: "sync", "Drained {} blocks", drained.len());
 		drained
@@ -174,10 +281,8 @@ impl<B: BlockT> BlockCollection<B> where B::Header: HeaderT<Number=u64> {
 					Some(&mut BlockRangeState::Downloading { .. })  => {
 						true
 					},
-					_ => {
-						debug_assert!(false);
-						false
-					}
+					// completed by another peer the range was requested from.
+					_ => false,
 				};
 				if remove {
 					self.blocks.remove(&start);
@@ -190,7 +295,8 @@ impl<B: BlockT> BlockCollection<B> where B::Header: HeaderT<Number=u64> {
 
 #[cfg(test)]
 mod test {
-	use super::{BlockCollection, BlockData, BlockRangeState};
+	use std::time::{Duration, Instant};
+	use super::{BlockCollection, BlockData, BlockRangeState, SLOW_DOWNLOAD_SECS};
 	use message;
 	use runtime_primitives::testing::Block as RawBlock;
 	use primitives::H256;
@@ -217,7 +323,7 @@ mod test {
 	fn create_clear() {
 		let mut bc = BlockCollection::new();
 		assert!(is_empty(&bc));
-		bc.insert(1, generate_blocks(100),  0);
+		bc.insert(1, generate_blocks(100), 0, None);
 		assert!(!is_empty(&bc));
 		bc.clear();
 		assert!(is_empty(&bc));
@@ -237,33 +343,103 @@ mod test {
 		assert_eq!(bc.needed_blocks(peer2, 40, 150, 0), Some(81 .. 121));
 
 		bc.clear_peer_download(peer1);
-		bc.insert(41, blocks[41..81].to_vec(), peer1);
+		bc.insert(41, blocks[41..81].to_vec(), peer1, Some("node1".into()));
 		assert_eq!(bc.drain(1), vec![]);
 		assert_eq!(bc.needed_blocks(peer1, 40, 150, 0), Some(121 .. 151));
 		bc.clear_peer_download(peer0);
-		bc.insert(1, blocks[1..11].to_vec(), peer0);
+		bc.insert(1, blocks[1..11].to_vec(), peer0, None);
 
 		assert_eq!(bc.needed_blocks(peer0, 40, 150, 0), Some(11 .. 41));
-		assert_eq!(bc.drain(1), blocks[1..11].iter().map(|b| BlockData { block: b.clone(), origin: 0 }).collect::<Vec<_>>());
+		assert_eq!(bc.drain(1), blocks[1..11].iter().map(|b| BlockData { block: b.clone(), origin: 0, origin_node: None }).collect::<Vec<_>>());
 
 		bc.clear_peer_download(peer0);
-		bc.insert(11, blocks[11..41].to_vec(), peer0);
+		bc.insert(11, blocks[11..41].to_vec(), peer0, None);
 
 		let drained = bc.drain(12);
-		assert_eq!(drained[..30], blocks[11..41].iter().map(|b| BlockData { block: b.clone(), origin: 0 }).collect::<Vec<_>>()[..]);
-		assert_eq!(drained[30..], blocks[41..81].iter().map(|b| BlockData { block: b.clone(), origin: 1 }).collect::<Vec<_>>()[..]);
+		assert_eq!(drained[..30], blocks[11..41].iter().map(|b| BlockData { block: b.clone(), origin: 0, origin_node: None }).collect::<Vec<_>>()[..]);
+		assert_eq!(drained[30..], blocks[41..81].iter().map(|b| BlockData { block: b.clone(), origin: 1, origin_node: Some("node1".into()) }).collect::<Vec<_>>()[..]);
 
 		bc.clear_peer_download(peer2);
 		assert_eq!(bc.needed_blocks(peer2, 40, 150, 80), Some(81 .. 121));
 		bc.clear_peer_download(peer2);
-		bc.insert(81, blocks[81..121].to_vec(), peer2);
+		bc.insert(81, blocks[81..121].to_vec(), peer2, None);
 		bc.clear_peer_download(peer1);
-		bc.insert(121, blocks[121..150].to_vec(), peer1);
+		bc.insert(121, blocks[121..150].to_vec(), peer1, None);
 
 		assert_eq!(bc.drain(80), vec![]);
 		let drained = bc.drain(81);
-		assert_eq!(drained[..40], blocks[81..121].iter().map(|b| BlockData { block: b.clone(), origin: 2 }).collect::<Vec<_>>()[..]);
-		assert_eq!(drained[40..], blocks[121..150].iter().map(|b| BlockData { block: b.clone(), origin: 1 }).collect::<Vec<_>>()[..]);
+		assert_eq!(drained[..40], blocks[81..121].iter().map(|b| BlockData { block: b.clone(), origin: 2, origin_node: None }).collect::<Vec<_>>()[..]);
+		assert_eq!(drained[40..], blocks[121..150].iter().map(|b| BlockData { block: b.clone(), origin: 1, origin_node: None }).collect::<Vec<_>>()[..]);
+	}
+
+	#[test]
+	fn overlapping_ranges_are_drained_once() {
+		let mut bc: BlockCollection<Block> = BlockCollection::new();
+		let blocks = generate_blocks(20);
+
+		bc.insert(1, blocks[1..11].to_vec(), 0, None);
+		bc.insert(6, blocks[6..16].to_vec(), 1, None);
+		bc.insert(9, blocks[9..11].to_vec(), 2, None);
+
+		let drained = bc.drain(1);
+		assert_eq!(drained.iter().map(|b| b.block.hash).collect::<Vec<_>>(),
+			blocks[1..16].iter().map(|b| b.hash).collect::<Vec<_>>());
+		assert_eq!(drained[5].origin, 0);
+		assert_eq!(drained[14].origin, 1);
+		assert_eq!(bc.queued(), (0, 0));
+	}
+
+	#[test]
+	fn tracks_queued_blocks() {
+		let mut bc: BlockCollection<Block> = BlockCollection::new();
+		let blocks: Vec<_> = generate_blocks(30).into_iter().map(|mut b| {
+			b.body = Some(message::Body::V1(vec![message::generic::V1Extrinsic(vec![0; 10])]));
+			b
+		}).collect();
+
+		assert_eq!(bc.needed_blocks(0, 10, 100, 0), Some(1 .. 11));
+		assert_eq!(bc.needed_blocks(1, 10, 100, 0), Some(11 .. 21));
+		assert!(bc.next_in_progress(1));
+
+		bc.clear_peer_download(1);
+		bc.insert(11, blocks[11..21].to_vec(), 1, None);
+		assert_eq!(bc.queued(), (10, 100));
+		assert!(bc.next_in_progress(1));
+
+		// the first range failed to download, so nothing can be drained.
+		bc.clear_peer_download(0);
+		assert!(!bc.next_in_progress(1));
+		assert_eq!(bc.needed_blocks(0, 10, 100, 0), Some(1 .. 11));
+
+		bc.clear_peer_download(0);
+		bc.insert(1, blocks[1..11].to_vec(), 0, None);
+		assert_eq!(bc.queued(), (20, 200));
+		assert!(!bc.next_in_progress(1));
+
+		assert_eq!(bc.drain(1).len(), 20);
+		assert_eq!(bc.queued(), (0, 0));
+	}
+
+	#[test]
+	fn slow_ranges_are_requested_again() {
+		let mut bc: BlockCollection<Block> = BlockCollection::new();
+		let blocks = generate_blocks(20);
+
+		assert_eq!(bc.needed_blocks(0, 10, 100, 0), Some(1 .. 11));
+		assert_eq!(bc.needed_blocks(1, 10, 100, 0), Some(11 .. 21));
+
+		// the first range is slow, so the next idle peer is asked for it too.
+		let long_ago = Instant::now() - Duration::from_secs(SLOW_DOWNLOAD_SECS + 1);
+		bc.blocks.insert(1, BlockRangeState::Downloading { len: 10, downloading: 1, requested_at: long_ago });
+		assert_eq!(bc.needed_blocks(2, 10, 100, 0), Some(1 .. 11));
+		assert_eq!(bc.needed_blocks(3, 10, 100, 0), Some(21 .. 31));
+
+		// the faster peer completes it; the late response is ignored.
+		bc.clear_peer_download(2);
+		bc.insert(1, blocks[0..10].to_vec(), 2, None);
+		bc.clear_peer_download(0);
+		bc.insert(1, blocks[0..10].to_vec(), 0, None);
+		assert_eq!(bc.drain(1).iter().map(|b| b.origin).collect::<Vec<_>>(), vec![2; 10]);
 	}
 
 	#[test]
@@ -272,8 +448,9 @@ mod test {
 		bc.blocks.insert(100, BlockRangeState::Downloading {
 			len: 128,
 			downloading: 1,
+			requested_at: Instant::now(),
 		});
-		let blocks = generate_blocks(10).into_iter().map(|b| BlockData { block: b, origin: 0 }).collect();
+		let blocks = generate_blocks(10).into_iter().map(|b| BlockData { block: b, origin: 0, origin_node: None }).collect();
 		bc.blocks.insert(114305, BlockRangeState::Complete(blocks));
 
 		assert_eq!(bc.needed_blocks(0, 128, 10000, 000), Some(1 .. 100));
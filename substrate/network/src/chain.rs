@@ -16,17 +16,27 @@
 
 //! Blockchain access trait
 
-use client::{self, Client as PolkadotClient, ImportResult, ClientInfo, BlockStatus, BlockOrigin, CallExecutor};
+use client::{self, Client as PolkadotClient, ImportResult, ClientInfo, BlockStatus, BlockOrigin, CallExecutor,
+	JustifiedHeader};
 use client::error::Error;
+use runtime_version::RuntimeVersion;
 use state_machine;
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::bft::Justification;
+use primitives::AuthorityId;
 
 pub trait Client<Block: BlockT>: Send + Sync {
 	/// Import a new block. Parent is supposed to be existing in the blockchain.
 	fn import(&self, is_best: bool, header: Block::Header, justification: Justification<Block::Hash>, body: Option<Vec<Block::Extrinsic>>) -> Result<ImportResult, Error>;
 
+	/// Import a new block whose justification has already been checked against
+	/// the authorities at its parent.
+	fn import_justified(&self, is_best: bool, header: JustifiedHeader<Block>, body: Option<Vec<Block::Extrinsic>>) -> Result<ImportResult, Error>;
+
+	/// Get the authorities at a block.
+	fn authorities(&self, id: &BlockId<Block>) -> Result<Vec<AuthorityId>, Error>;
+
 	/// Get blockchain info.
 	fn info(&self) -> Result<ClientInfo<Block>, Error>;
 
@@ -47,6 +57,24 @@ pub trait Client<Block: BlockT>: Send + Sync {
 
 	/// Get method execution proof.
 	fn execution_proof(&self, block: &Block::Hash, method: &str, data: &[u8]) -> Result<(Vec<u8>, Vec<Vec<u8>>), Error>;
+
+	/// Get storage read execution proof.
+	fn read_proof(&self, block: &Block::Hash, key: &[u8]) -> Result<Vec<Vec<u8>>, Error>;
+
+	/// Get the runtime version at a block.
+	fn runtime_version(&self, id: &BlockId<Block>) -> Result<RuntimeVersion, Error>;
+
+	/// Get all storage entries of the state at a block.
+	fn state_entries(&self, block: &Block::Hash) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error>;
+
+	/// Import the state of a trusted block to sync on top of. Its ancestors aren't needed.
+	fn import_state(&self, header: Block::Header, justification: Justification<Block::Hash>, storage: Vec<(Vec<u8>, Vec<u8>)>) -> Result<ImportResult, Error>;
+
+	/// Store auxiliary data, which isn't part of the chain, under the given key.
+	fn insert_aux(&self, key: &[u8], value: &[u8]) -> Result<(), Error>;
+
+	/// Get auxiliary data stored under the given key.
+	fn get_aux(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error>;
 }
 
 impl<B, E, Block> Client<Block> for PolkadotClient<B, E, Block> where
@@ -58,8 +86,16 @@ impl<B, E, Block> Client<Block> for PolkadotClient<B, E, Block> where
 	fn import(&self, is_best: bool, header: Block::Header, justification: Justification<Block::Hash>, body: Option<Vec<Block::Extrinsic>>) -> Result<ImportResult, Error> {
 		// TODO: defer justification check.
 		let justified_header = self.check_justification(header, justification.into())?;
+		self.import_justified(is_best, justified_header, body)
+	}
+
+	fn import_justified(&self, is_best: bool, header: JustifiedHeader<Block>, body: Option<Vec<Block::Extrinsic>>) -> Result<ImportResult, Error> {
 		let origin = if is_best { BlockOrigin::NetworkBroadcast } else { BlockOrigin::NetworkInitialSync };
-		(self as &PolkadotClient<B, E, Block>).import_block(origin, justified_header, body)
+		(self as &PolkadotClient<B, E, Block>).import_block(origin, header, body)
+	}
+
+	fn authorities(&self, id: &BlockId<Block>) -> Result<Vec<AuthorityId>, Error> {
+		(self as &PolkadotClient<B, E, Block>).authorities_at(id)
 	}
 
 	fn info(&self) -> Result<ClientInfo<Block>, Error> {
@@ -89,4 +125,29 @@ impl<B, E, Block> Client<Block> for PolkadotClient<B, E, Block> where
 	fn execution_proof(&self, block: &Block::Hash, method: &str, data: &[u8]) -> Result<(Vec<u8>, Vec<Vec<u8>>), Error> {
 		(self as &PolkadotClient<B, E, Block>).execution_proof(&BlockId::Hash(block.clone()), method, data)
 	}
+
+	fn read_proof(&self, block: &Block::Hash, key: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
+		(self as &PolkadotClient<B, E, Block>).read_proof(&BlockId::Hash(block.clone()), key)
+	}
+
+	fn runtime_version(&self, id: &BlockId<Block>) -> Result<RuntimeVersion, Error> {
+		(self as &PolkadotClient<B, E, Block>).runtime_version_at(id)
+	}
+
+	fn state_entries(&self, block: &Block::Hash) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error> {
+		use state_machine::Backend;
+		Ok((self as &PolkadotClient<B, E, Block>).state_at(&BlockId::Hash(block.clone()))?.pairs())
+	}
+
+	fn import_state(&self, header: Block::Header, justification: Justification<Block::Hash>, storage: Vec<(Vec<u8>, Vec<u8>)>) -> Result<ImportResult, Error> {
+		(self as &PolkadotClient<B, E, Block>).import_state(header, justification.into(), storage)
+	}
+
+	fn insert_aux(&self, key: &[u8], value: &[u8]) -> Result<(), Error> {
+		(self as &PolkadotClient<B, E, Block>).insert_aux(key, value)
+	}
+
+	fn get_aux(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+		(self as &PolkadotClient<B, E, Block>).get_aux(key)
+	}
 }
@@ -0,0 +1,113 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Estimate of the skew of the local clock from the clocks of peers.
+//!
+//! Peers send their time in the status message of the handshake. The offset of
+//! each peer's clock is taken when its status arrives, so it includes the
+//! latency of the message; the median over peers keeps a few wrong clocks or
+//! slow links from skewing the estimate.
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use network::PeerId;
+
+/// Number of peers whose time is needed for an estimate.
+pub const MIN_CLOCK_SAMPLES: usize = 3;
+
+/// Skew beyond which a warning is logged, in milliseconds.
+pub const MAX_CLOCK_SKEW_MS: i64 = 2000;
+
+/// Milliseconds since the Unix epoch, as sent in status messages.
+pub fn now_millis() -> u64 {
+	let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+	since_epoch.as_secs() * 1000 + since_epoch.subsec_nanos() as u64 / 1_000_000
+}
+
+/// Offsets of the clocks of connected peers from the local clock.
+#[derive(Default)]
+pub struct PeerClocks {
+	offsets: HashMap<PeerId, i64>,
+	warned: bool,
+}
+
+impl PeerClocks {
+	/// Note the time a peer sent in its status, received at local time `now`,
+	/// both in milliseconds since the Unix epoch.
+	pub fn note(&mut self, peer: PeerId, peer_time: u64, now: u64) {
+		self.offsets.insert(peer, peer_time as i64 - now as i64);
+
+		match self.skew() {
+			Some(skew) if skew.abs() > MAX_CLOCK_SKEW_MS => if !self.warned {
+				self.warned = true;
+				warn!(target: "sync", "Local clock is {}ms {} the median of {} peers. Check that it is synchronised, e.g. with NTP.",
+					skew.abs(), if skew > 0 { "behind" } else { "ahead of" }, self.offsets.len());
+			},
+			Some(_) => self.warned = false,
+			None => {},
+		}
+	}
+
+	/// Forget a disconnected peer.
+	pub fn remove(&mut self, peer: PeerId) {
+		self.offsets.remove(&peer);
+	}
+
+	/// Median offset of the peers' clocks from the local clock in milliseconds,
+	/// positive when the local clock is behind. `None` with fewer than
+	/// `MIN_CLOCK_SAMPLES` peers.
+	pub fn skew(&self) -> Option<i64> {
+		if self.offsets.len() < MIN_CLOCK_SAMPLES {
+			return None;
+		}
+
+		let mut offsets: Vec<_> = self.offsets.values().cloned().collect();
+		offsets.sort();
+		let mid = offsets.len() / 2;
+		Some(if offsets.len() % 2 == 0 {
+			(offsets[mid - 1] + offsets[mid]) / 2
+		} else {
+			offsets[mid]
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn skew_is_median_of_peers() {
+		let mut clocks = PeerClocks::default();
+		let now = 1_000_000;
+
+		clocks.note(1, now + 500, now);
+		clocks.note(2, now + 700, now);
+		assert_eq!(clocks.skew(), None);
+
+		// one peer far off doesn't move the estimate much.
+		clocks.note(3, now - 60_000, now);
+		assert_eq!(clocks.skew(), Some(500));
+
+		clocks.note(4, now + 900, now);
+		assert_eq!(clocks.skew(), Some(600));
+
+		clocks.remove(4);
+		clocks.remove(1);
+		assert_eq!(clocks.skew(), None);
+	}
+}
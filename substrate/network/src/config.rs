@@ -14,19 +14,145 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.?
 
+use std::cmp;
+use std::time::Duration;
+
 pub use service::Role;
+pub use reputation::ReputationConfig;
+pub use bandwidth::BandwidthConfig;
+pub use blocks::QueueLimits;
+pub use sync::SyncMode;
+pub use peer_set::{PeerSetConfig, PeerSlots, ConnectionLimits};
+
+/// Default time to keep importing downloaded blocks when shutting down, in seconds.
+pub const DEFAULT_SHUTDOWN_IMPORT_SECS: u64 = 30;
+
+/// Default time a peer has to answer a request before it is considered stalled, in seconds.
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 10;
+
+/// Default time a connected peer has to send its status, in seconds.
+pub const DEFAULT_HANDSHAKE_TIMEOUT_SECS: u64 = 40;
+
+/// Default time without any packet from a peer after which it is pinged, in seconds.
+pub const DEFAULT_KEEP_ALIVE_INTERVAL_SECS: u64 = 30;
+
+/// Default time a pinged peer has to answer before it is disconnected, in seconds.
+pub const DEFAULT_KEEP_ALIVE_TIMEOUT_SECS: u64 = 20;
+
+/// Default maximum number of blocks in a block response.
+pub const DEFAULT_MAX_BLOCKS_PER_RESPONSE: u32 = 128;
+
+/// Default maximum size of a block response, in bytes.
+pub const DEFAULT_MAX_RESPONSE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Limits on block responses. Both nodes tell theirs in the handshake, and the
+/// lower of each is used in both directions: responses are cut to fit, and
+/// responses which don't fit are rejected.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct ResponseLimits {
+	/// Maximum number of blocks in a response.
+	pub max_blocks: u32,
+	/// Maximum size of a response, in bytes.
+	pub max_bytes: u64,
+}
+
+impl ResponseLimits {
+	/// The limits to use with a peer which told the given ones, if any.
+	pub fn negotiate(&self, max_blocks: Option<u32>, max_bytes: Option<u64>) -> Self {
+		ResponseLimits {
+			max_blocks: max_blocks.map_or(self.max_blocks, |max| cmp::min(max, self.max_blocks)),
+			max_bytes: max_bytes.map_or(self.max_bytes, |max| cmp::min(max, self.max_bytes)),
+		}
+	}
+}
+
+impl Default for ResponseLimits {
+	fn default() -> Self {
+		ResponseLimits {
+			max_blocks: DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+			max_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+		}
+	}
+}
+
+/// How imported blocks are announced to peers.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum AnnounceMode {
+	/// Send the header of the block.
+	Header,
+	/// Send only the hash of the block; peers fetch the header if they don't
+	/// know the block. Peers which don't support hash announcements are sent
+	/// the header.
+	Hash,
+}
+
+impl Default for AnnounceMode {
+	fn default() -> Self {
+		AnnounceMode::Header
+	}
+}
 
 /// Protocol configuration
 #[derive(Clone)]
 pub struct ProtocolConfig {
 	/// Assigned roles.
 	pub roles: Role,
+	/// Reputation and bans of peers.
+	pub reputation: ReputationConfig,
+	/// Limits on the downloaded blocks waiting to be imported.
+	pub queue_limits: QueueLimits,
+	/// Time to keep importing blocks which are already downloaded when shutting
+	/// down, so that they aren't downloaded again after a restart. Zero stops
+	/// importing at once.
+	pub import_on_shutdown: Duration,
+	/// What sync downloads and imports. `None` picks the mode for the roles:
+	/// headers only for light nodes, full blocks otherwise.
+	pub sync_mode: Option<SyncMode>,
+	/// Encoded hash of a trusted block. A node at genesis downloads the state
+	/// of this block and syncs blocks from there instead of from genesis.
+	pub state_sync_target: Option<Vec<u8>>,
+	/// Numbers and encoded hashes of trusted blocks. Blocks conflicting with
+	/// them are refused and the peers which sent them banned.
+	pub checkpoints: Vec<(u64, Vec<u8>)>,
+	/// How imported blocks are announced to peers.
+	pub announce_mode: AnnounceMode,
+	/// Time a peer has to answer a request. A peer which doesn't is marked as
+	/// stalled and the request is made to other peers instead.
+	pub request_timeout: Duration,
+	/// Limits on the bandwidth used by each peer.
+	pub bandwidth: BandwidthConfig,
+	/// Reserved nodes, which are always kept connected, and connection slots
+	/// for the other peers by role.
+	pub peer_set: PeerSetConfig,
+	/// Limits on the block responses sent and accepted.
+	pub response_limits: ResponseLimits,
+	/// Time a connected peer has to send its status before it is disconnected.
+	pub handshake_timeout: Duration,
+	/// Time without any packet from a peer after which it is pinged.
+	pub keep_alive_interval: Duration,
+	/// Time a pinged peer has to answer. A peer which doesn't is disconnected
+	/// and its pending requests are made to other peers.
+	pub keep_alive_timeout: Duration,
 }
 
 impl Default for ProtocolConfig {
 	fn default() -> ProtocolConfig {
 		ProtocolConfig {
 			roles: Role::FULL,
+			reputation: Default::default(),
+			queue_limits: Default::default(),
+			import_on_shutdown: Duration::from_secs(DEFAULT_SHUTDOWN_IMPORT_SECS),
+			sync_mode: None,
+			state_sync_target: None,
+			checkpoints: Vec::new(),
+			announce_mode: AnnounceMode::Header,
+			request_timeout: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS),
+			bandwidth: Default::default(),
+			peer_set: Default::default(),
+			response_limits: Default::default(),
+			handshake_timeout: Duration::from_secs(DEFAULT_HANDSHAKE_TIMEOUT_SECS),
+			keep_alive_interval: Duration::from_secs(DEFAULT_KEEP_ALIVE_INTERVAL_SECS),
+			keep_alive_timeout: Duration::from_secs(DEFAULT_KEEP_ALIVE_TIMEOUT_SECS),
 		}
 	}
 }
@@ -14,7 +14,14 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.?
 
-//! Consensus related bits of the network service.
+//! Consensus gossip.
+//!
+//! Messages of the consensus layers are gossiped to validator peers under a
+//! topic, the hash of the relay parent agreement happens on top of. BFT
+//! messages use their parent hash as topic, other messages are opaque to the
+//! network. Every message is sent to each peer at most once. A topic expires
+//! once a block on top of its parent is imported, and any message is dropped
+//! after a fixed lifetime.
 
 use std::collections::{HashMap, HashSet};
 use futures::sync::mpsc;
@@ -33,11 +40,21 @@ struct PeerConsensus<H> {
 	known_messages: HashSet<H>,
 }
 
+struct MessageEntry<B: BlockT> {
+	hash: B::Hash,
+	// `None` for messages which aren't tied to a round, like heartbeats.
+	topic: Option<B::Hash>,
+	message: message::Message<B>,
+	timestamp: Instant,
+}
+
 /// Consensus network protocol handler. Manages statements and candidate requests.
 pub struct Consensus<B: BlockT> {
 	peers: HashMap<PeerId, PeerConsensus<B::Hash>>,
 	bft_message_sink: Option<(mpsc::UnboundedSender<message::LocalizedBftMessage<B>>, B::Hash)>,
-	messages: Vec<(B::Hash, Instant, message::Message<B>)>,
+	heartbeat_sink: Option<mpsc::UnboundedSender<message::Heartbeat>>,
+	topic_sinks: HashMap<B::Hash, mpsc::UnboundedSender<Vec<u8>>>,
+	messages: Vec<MessageEntry<B>>,
 	message_hashes: HashSet<B::Hash>,
 }
 
@@ -47,12 +64,14 @@ impl<B: BlockT> Consensus<B> where B::Header: HeaderT<Number=u64> {
 		Consensus {
 			peers: HashMap::new(),
 			bft_message_sink: None,
+			heartbeat_sink: None,
+			topic_sinks: HashMap::new(),
 			messages: Default::default(),
 			message_hashes: Default::default(),
 		}
 	}
 
-	/// Closes all notification streams.
+	/// Closes all BFT notification streams.
 	pub fn restart(&mut self) {
 		self.bft_message_sink = None;
 	}
@@ -64,9 +83,9 @@ impl<B: BlockT> Consensus<B> where B::Header: HeaderT<Number=u64> {
 			// Send out all known messages.
 			// TODO: limit by size
 			let mut known_messages = HashSet::new();
-			for &(ref hash, _, ref message) in self.messages.iter() {
-				known_messages.insert(hash.clone());
-				protocol.send_message(io, peer_id, message.clone());
+			for entry in self.messages.iter() {
+				known_messages.insert(entry.hash.clone());
+				protocol.send_message(io, peer_id, entry.message.clone());
 			}
 			self.peers.insert(peer_id, PeerConsensus {
 				known_messages,
@@ -82,51 +101,68 @@ impl<B: BlockT> Consensus<B> where B::Header: HeaderT<Number=u64> {
 		}
 	}
 
-	fn register_message(&mut self, hash: B::Hash, message: message::Message<B>) {
+	fn register_message(&mut self, hash: B::Hash, topic: Option<B::Hash>, message: message::Message<B>) {
 		if self.message_hashes.insert(hash) {
-			self.messages.push((hash, Instant::now(), message));
+			self.messages.push(MessageEntry { hash, topic, message, timestamp: Instant::now() });
 		}
 	}
 
-	pub fn on_bft_message(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId, message: message::LocalizedBftMessage<B>, hash: B::Hash) {
+	// Note that a message was received from the peer. Returns `false` if it
+	// is known already, comes from a peer which isn't a validator or belongs
+	// to a topic whose parent already has a block imported on top.
+	fn note_received(&mut self, protocol: &Protocol<B>, peer_id: PeerId, hash: B::Hash, topic: Option<&B::Hash>) -> bool {
 		if self.message_hashes.contains(&hash) {
-			trace!(target:"sync", "Ignored already known BFT message from {}", peer_id);
-			return;
+			trace!(target:"sync", "Ignored already known consensus message from {}", peer_id);
+			return false;
+		}
+
+		if let Some(topic) = topic {
+			match (protocol.chain().info(), protocol.chain().header(&BlockId::Hash(*topic))) {
+				(_, Err(e)) | (Err(e), _) => {
+					debug!(target:"sync", "Error reading blockchain: {:?}", e);
+					return false;
+				},
+				(Ok(info), Ok(Some(header))) => {
+					if header.number() < &info.chain.best_number {
+						trace!(target:"sync", "Ignored ancient consensus message from {}, topic={}", peer_id, topic);
+						return false;
+					}
+				},
+				(Ok(_), Ok(None)) => {},
+			}
 		}
 
-		match (protocol.chain().info(), protocol.chain().header(&BlockId::Hash(message.parent_hash))) {
-			(_, Err(e)) | (Err(e), _) => {
-				debug!(target:"sync", "Error reading blockchain: {:?}", e);
-				return;
+		match self.peers.get_mut(&peer_id) {
+			Some(peer) => {
+				peer.known_messages.insert(hash);
+				true
 			},
-			(Ok(info), Ok(Some(header))) => {
-				if header.number() < &info.chain.best_number {
-					trace!(target:"sync", "Ignored ancient BFT message from {}, hash={}", peer_id, message.parent_hash);
-					return;
-				}
+			None => {
+				trace!(target:"sync", "Ignored consensus message from unregistered peer {}", peer_id);
+				false
 			},
-			(Ok(_), Ok(None)) => {},
 		}
+	}
 
-		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
-			peer.known_messages.insert(hash);
-			// TODO: validate signature?
-			if let Some((sink, parent_hash)) = self.bft_message_sink.take() {
-				if message.parent_hash == parent_hash {
-					if let Err(e) = sink.unbounded_send(message.clone()) {
-						trace!(target:"sync", "Error broadcasting BFT message notification: {:?}", e);
-					} else {
-						self.bft_message_sink = Some((sink, parent_hash));
-					}
+	pub fn on_bft_message(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId, message: message::LocalizedBftMessage<B>, hash: B::Hash) {
+		if !self.note_received(protocol, peer_id, hash, Some(&message.parent_hash)) {
+			return;
+		}
+
+		// TODO: validate signature?
+		if let Some((sink, parent_hash)) = self.bft_message_sink.take() {
+			if message.parent_hash == parent_hash {
+				if let Err(e) = sink.unbounded_send(message.clone()) {
+					trace!(target:"sync", "Error broadcasting BFT message notification: {:?}", e);
+				} else {
+					self.bft_message_sink = Some((sink, parent_hash));
 				}
 			}
-		} else {
-			trace!(target:"sync", "Ignored BFT statement from unregistered peer {}", peer_id);
-			return;
 		}
 
+		let topic = message.parent_hash;
 		let message = GenericMessage::BftMessage(message);
-		self.register_message(hash.clone(), message.clone());
+		self.register_message(hash.clone(), Some(topic), message.clone());
 		// Propagate to other peers.
 		self.propagate(io, protocol, message, hash);
 	}
@@ -134,8 +170,8 @@ impl<B: BlockT> Consensus<B> where B::Header: HeaderT<Number=u64> {
 	pub fn bft_messages(&mut self, parent_hash: B::Hash) -> mpsc::UnboundedReceiver<message::LocalizedBftMessage<B>> {
 		let (sink, stream) = mpsc::unbounded();
 
-		for &(_, _, ref message) in self.messages.iter() {
-			let bft_message = match *message {
+		for entry in self.messages.iter() {
+			let bft_message = match entry.message {
 				GenericMessage::BftMessage(ref msg) => msg,
 				_ => continue,
 			};
@@ -152,9 +188,99 @@ impl<B: BlockT> Consensus<B> where B::Header: HeaderT<Number=u64> {
 	pub fn send_bft_message(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, message: message::LocalizedBftMessage<B>) {
 		// Broadcast message to all validators.
 		trace!(target:"sync", "Broadcasting BFT message {:?}", message);
+		let topic = message.parent_hash;
 		let message = GenericMessage::BftMessage(message);
 		let hash = Protocol::hash_message(&message);
-		self.register_message(hash.clone(), message.clone());
+		self.register_message(hash.clone(), Some(topic), message.clone());
+		self.propagate(io, protocol, message, hash);
+	}
+
+	pub fn on_gossip_message(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId, message: message::GossipMessage<B>, hash: B::Hash) {
+		if !self.note_received(protocol, peer_id, hash, Some(&message.topic)) {
+			return;
+		}
+
+		let topic = message.topic;
+		let delivered = match self.topic_sinks.get(&topic) {
+			Some(sink) => sink.unbounded_send(message.data.clone()).is_ok(),
+			None => true,
+		};
+		if !delivered {
+			trace!(target:"sync", "Gossip stream for topic {} closed", topic);
+			self.topic_sinks.remove(&topic);
+		}
+
+		let message = GenericMessage::Gossip(message);
+		self.register_message(hash.clone(), Some(topic), message.clone());
+		// Propagate to other peers.
+		self.propagate(io, protocol, message, hash);
+	}
+
+	/// Get a stream of the data of all messages known and received from here
+	/// on under the topic. Replaces any previous stream of the topic. The
+	/// stream ends once the topic expires.
+	pub fn gossip_messages(&mut self, topic: B::Hash) -> mpsc::UnboundedReceiver<Vec<u8>> {
+		let (sink, stream) = mpsc::unbounded();
+
+		for entry in self.messages.iter() {
+			if let GenericMessage::Gossip(ref message) = entry.message {
+				if message.topic == topic {
+					sink.unbounded_send(message.data.clone()).expect("receiving end known to be open; qed");
+				}
+			}
+		}
+
+		self.topic_sinks.insert(topic, sink);
+		stream
+	}
+
+	pub fn send_gossip(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, topic: B::Hash, data: Vec<u8>) {
+		trace!(target:"sync", "Gossiping message under topic {}", topic);
+		let message = GenericMessage::Gossip(message::generic::GossipMessage { topic, data });
+		let hash = Protocol::hash_message(&message);
+		self.register_message(hash.clone(), Some(topic), message.clone());
+		self.propagate(io, protocol, message, hash);
+	}
+
+	pub fn on_heartbeat(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId, heartbeat: message::Heartbeat, hash: B::Hash) {
+		if !self.note_received(protocol, peer_id, hash, None) {
+			return;
+		}
+
+		// signatures are checked by the receiver of the stream.
+		if let Some(sink) = self.heartbeat_sink.take() {
+			if let Err(e) = sink.unbounded_send(heartbeat.clone()) {
+				trace!(target:"sync", "Error broadcasting heartbeat notification: {:?}", e);
+			} else {
+				self.heartbeat_sink = Some(sink);
+			}
+		}
+
+		let message = GenericMessage::Heartbeat(heartbeat);
+		self.register_message(hash.clone(), None, message.clone());
+		// Propagate to other peers.
+		self.propagate(io, protocol, message, hash);
+	}
+
+	pub fn heartbeats(&mut self) -> mpsc::UnboundedReceiver<message::Heartbeat> {
+		let (sink, stream) = mpsc::unbounded();
+
+		for entry in self.messages.iter() {
+			if let GenericMessage::Heartbeat(ref heartbeat) = entry.message {
+				sink.unbounded_send(heartbeat.clone()).expect("receiving end known to be open; qed");
+			}
+		}
+
+		self.heartbeat_sink = Some(sink);
+		stream
+	}
+
+	pub fn send_heartbeat(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, heartbeat: message::Heartbeat) {
+		// Broadcast heartbeat to all validators.
+		trace!(target:"sync", "Broadcasting heartbeat");
+		let message = GenericMessage::Heartbeat(heartbeat);
+		let hash = Protocol::hash_message(&message);
+		self.register_message(hash.clone(), None, message.clone());
 		self.propagate(io, protocol, message, hash);
 	}
 
@@ -162,23 +288,26 @@ impl<B: BlockT> Consensus<B> where B::Header: HeaderT<Number=u64> {
 		self.peers.remove(&peer_id);
 	}
 
+	/// Drop expired messages. Once a block is imported, the topic of its
+	/// parent expires.
 	pub fn collect_garbage(&mut self, best_header: Option<&B::Header>) {
+		let expired_topic = best_header.map(|header| *header.parent_hash());
+		if let Some(ref topic) = expired_topic {
+			if self.topic_sinks.remove(topic).is_some() {
+				trace!(target:"sync", "Topic {} expired", topic);
+			}
+		}
+
 		let hashes = &mut self.message_hashes;
 		let before = self.messages.len();
 		let now = Instant::now();
-		self.messages.retain(|&(ref hash, timestamp, ref message)| {
-			if timestamp >= now - MESSAGE_LIFETIME &&
-				best_header.map_or(true, |header|
-					match *message {
-						GenericMessage::BftMessage(ref msg) => &msg.parent_hash != header.parent_hash(),
-						_ => true,
-					})
-			{
-				true
-			} else {
-				hashes.remove(hash);
-				false
+		self.messages.retain(|entry| {
+			let expired = entry.timestamp < now - MESSAGE_LIFETIME ||
+				(entry.topic.is_some() && entry.topic == expired_topic);
+			if expired {
+				hashes.remove(&entry.hash);
 			}
+			!expired
 		});
 		if self.messages.len() != before {
 			trace!(target:"sync", "Cleaned up {} stale messages", before - self.messages.len());
@@ -194,8 +323,9 @@ mod tests {
 	use runtime_primitives::bft::Justification;
 	use runtime_primitives::testing::{H256, Header, Block as RawBlock};
 	use std::time::Instant;
+	use futures::{Future, Stream};
 	use message::{self, generic::Message as GenericMessage};
-	use super::{Consensus, MESSAGE_LIFETIME};
+	use super::{Consensus, MessageEntry, MESSAGE_LIFETIME};
 
 	type Block = RawBlock<u64>;
 
@@ -223,8 +353,8 @@ mod tests {
 				signatures: Default::default(),
 			}),
 		});
-		consensus.messages.push((m1_hash, now, m1));
-		consensus.messages.push((m2_hash, now, m2.clone()));
+		consensus.messages.push(MessageEntry { hash: m1_hash, topic: Some(prev_hash), message: m1, timestamp: now });
+		consensus.messages.push(MessageEntry { hash: m2_hash, topic: Some(best_hash), message: m2.clone(), timestamp: now });
 		consensus.message_hashes.insert(m1_hash);
 		consensus.message_hashes.insert(m2_hash);
 
@@ -255,9 +385,28 @@ mod tests {
 
 		// make timestamp expired
 		consensus.messages.clear();
-		consensus.messages.push((m2_hash, now - MESSAGE_LIFETIME, m2));
+		consensus.messages.push(MessageEntry { hash: m2_hash, topic: Some(best_hash), message: m2, timestamp: now - MESSAGE_LIFETIME });
 		consensus.collect_garbage(None);
 		assert!(consensus.messages.is_empty());
 		assert!(consensus.message_hashes.is_empty());
 	}
+	#[test]
+	fn gossip_streams_end_when_their_topic_expires() {
+		let topic = H256::random();
+		let mut consensus = Consensus::<Block>::new();
+		let gossip = GenericMessage::Gossip(message::generic::GossipMessage { topic, data: vec![1, 2, 3] });
+		consensus.messages.push(MessageEntry { hash: H256::random(), topic: Some(topic), message: gossip, timestamp: Instant::now() });
+
+		let stream = consensus.gossip_messages(topic);
+		let header = Header {
+			parent_hash: topic,
+			number: 1,
+			state_root: H256::default(),
+			extrinsics_root: H256::default(),
+			digest: Default::default(),
+		};
+		consensus.collect_garbage(Some(&header));
+		assert!(consensus.messages.is_empty());
+		assert_eq!(stream.collect().wait().unwrap(), vec![vec![1, 2, 3]]);
+	}
 }
@@ -16,12 +16,13 @@
 
 //! Polkadot service possible errors.
 
-use network::Error as NetworkError;
+use network::{Error as NetworkError, ProtocolId};
 use client;
 
 error_chain! {
 	foreign_links {
 		Network(NetworkError) #[doc = "Devp2p error."];
+		Io(::std::io::Error) #[doc = "IO error."];
 	}
 
 	links {
@@ -29,5 +30,10 @@ error_chain! {
 	}
 
 	errors {
+		/// A protocol with the same name is already registered.
+		DuplicateProtocol(id: ProtocolId) {
+			description("Protocol name already in use"),
+			display("Protocol name already in use: {}", String::from_utf8_lossy(&id[..])),
+		}
 	}
 }
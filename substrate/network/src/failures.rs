@@ -0,0 +1,146 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Connections with peers which failed, for debugging.
+//!
+//! Every connection the protocol refuses or closes is counted by reason, as
+//! are connections dropped by the other node or the devp2p host. The latest
+//! failures are kept with the remote address, so that the peers behind them
+//! can be found. Failures of the devp2p host before a peer is reported to the
+//! protocol, such as failed dials and handshakes, aren't seen.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+
+/// Number of the latest failures kept with their remote address.
+pub const MAX_RECENT_FAILURES: usize = 32;
+
+/// Why a connection failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum FailureReason {
+	/// Refused in reserved-only mode.
+	NotReserved,
+	/// Refused for a ban.
+	Banned,
+	/// Refused for exceeding a connection limit.
+	ConnectionLimit,
+	/// No slot is free for the roles of the peer.
+	NoFreeSlot,
+	/// Different genesis, protocol version or runtime.
+	Incompatible,
+	/// The handshake wasn't done in time.
+	HandshakeTimeout,
+	/// A ping wasn't answered in time.
+	KeepAliveTimeout,
+	/// A request wasn't answered in time.
+	RequestTimeout,
+	/// The peer misbehaved.
+	Misbehavior,
+	/// Sending to the peer failed.
+	SendError,
+	/// Closed by the other node or dropped by the devp2p host.
+	Dropped,
+}
+
+/// A connection which failed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Failure {
+	/// Why it failed.
+	pub reason: FailureReason,
+	/// IP address of the other node, if known.
+	pub remote_address: Option<String>,
+}
+
+/// Number of connections which failed for a reason.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FailureCount {
+	/// The reason.
+	pub reason: FailureReason,
+	/// Number of connections.
+	pub count: u64,
+}
+
+/// Failed connections, as served over RPC.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct FailureState {
+	/// Connections which failed since the node started, by reason.
+	pub counts: Vec<FailureCount>,
+	/// The latest failures, oldest first.
+	pub recent: Vec<Failure>,
+}
+
+/// Failed connections since the node started.
+#[derive(Default)]
+pub struct ConnectionFailures {
+	counts: HashMap<FailureReason, u64>,
+	recent: VecDeque<Failure>,
+}
+
+impl ConnectionFailures {
+	/// Note a connection which failed.
+	pub fn note(&mut self, reason: FailureReason, ip: Option<IpAddr>) {
+		*self.counts.entry(reason).or_insert(0) += 1;
+		if self.recent.len() == MAX_RECENT_FAILURES {
+			self.recent.pop_front();
+		}
+		self.recent.push_back(Failure {
+			reason,
+			remote_address: ip.map(|ip| ip.to_string()),
+		});
+	}
+
+	/// Counts by reason and the latest failures.
+	pub fn state(&self) -> FailureState {
+		let mut counts: Vec<_> = self.counts.iter()
+			.map(|(reason, count)| FailureCount { reason: *reason, count: *count })
+			.collect();
+		counts.sort_by_key(|c| c.reason);
+		FailureState {
+			counts,
+			recent: self.recent.iter().cloned().collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn latest_failures_are_kept() {
+		let mut failures = ConnectionFailures::default();
+		let ip: IpAddr = "10.0.0.1".parse().unwrap();
+		failures.note(FailureReason::Dropped, Some(ip));
+		for _ in 0..MAX_RECENT_FAILURES {
+			failures.note(FailureReason::HandshakeTimeout, None);
+		}
+		failures.note(FailureReason::Banned, Some(ip));
+
+		let state = failures.state();
+		assert_eq!(state.counts, vec![
+			FailureCount { reason: FailureReason::Banned, count: 1 },
+			FailureCount { reason: FailureReason::HandshakeTimeout, count: MAX_RECENT_FAILURES as u64 },
+			FailureCount { reason: FailureReason::Dropped, count: 1 },
+		]);
+		assert_eq!(state.recent.len(), MAX_RECENT_FAILURES);
+		assert_eq!(state.recent[0].reason, FailureReason::HandshakeTimeout);
+		assert_eq!(state.recent.last(), Some(&Failure {
+			reason: FailureReason::Banned,
+			remote_address: Some("10.0.0.1".into()),
+		}));
+	}
+}
@@ -31,6 +31,10 @@ pub trait SyncIo {
 	}
 	/// Returns information on p2p session
 	fn peer_session_info(&self, peer_id: PeerId) -> Option<SessionInfo>;
+	/// Returns the node ID of a peer, which persists across sessions.
+	fn peer_node_id(&self, peer_id: PeerId) -> Option<String> {
+		self.peer_session_info(peer_id).and_then(|info| info.id).map(|id| format!("{:x}", id))
+	}
 	/// Check if the session is expired
 	fn is_expired(&self) -> bool;
 }
@@ -0,0 +1,72 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Bounded set of hashes known to a peer.
+//!
+//! Once full, the hash noted least recently is forgotten. A forgotten item
+//! may be sent to the peer again, which is cheaper than keeping every hash
+//! a long-lived peer has ever seen.
+
+use std::hash::Hash;
+use linked_hash_map::LinkedHashMap;
+
+/// Set of the most recently noted hashes, up to a capacity.
+pub struct KnownSet<H: Hash + Eq> {
+	capacity: usize,
+	items: LinkedHashMap<H, ()>,
+}
+
+impl<H: Hash + Eq> KnownSet<H> {
+	/// Create an empty set holding at most `capacity` hashes.
+	pub fn new(capacity: usize) -> Self {
+		KnownSet {
+			capacity,
+			items: LinkedHashMap::new(),
+		}
+	}
+
+	/// Note a hash. Returns `true` if it wasn't known.
+	pub fn insert(&mut self, hash: H) -> bool {
+		if self.items.get_refresh(&hash).is_some() {
+			return false;
+		}
+
+		self.items.insert(hash, ());
+		while self.items.len() > self.capacity {
+			self.items.pop_front();
+		}
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn least_recently_noted_hashes_are_forgotten() {
+		let mut set = KnownSet::new(2);
+		assert!(set.insert(1));
+		assert!(set.insert(2));
+		assert!(!set.insert(1));
+
+		// 2 is the least recently noted.
+		assert!(set.insert(3));
+		assert!(!set.insert(1));
+		assert!(set.insert(2));
+		assert!(set.insert(3));
+	}
+}
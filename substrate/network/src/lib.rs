@@ -33,6 +33,7 @@ extern crate substrate_runtime_support as runtime_support;
 extern crate substrate_runtime_primitives as runtime_primitives;
 extern crate substrate_bft;
 extern crate substrate_codec as codec;
+extern crate substrate_runtime_version as runtime_version;
 extern crate serde;
 extern crate serde_json;
 extern crate futures;
@@ -48,6 +49,16 @@ extern crate ed25519;
 
 mod service;
 mod sync;
+mod state_sync;
+mod sync_state;
+mod verification;
+mod reputation;
+mod progress;
+mod clock;
+mod bandwidth;
+mod peer_set;
+mod failures;
+mod network_state;
 mod protocol;
 mod io;
 mod message;
@@ -56,16 +67,30 @@ mod chain;
 mod blocks;
 mod consensus;
 mod on_demand;
+mod registry;
+mod notifications;
+mod known_set;
 pub mod error;
 
 #[cfg(test)] mod test;
 
-pub use service::{Service, FetchFuture, ConsensusService, BftMessageStream,
-	TransactionPool, Params, ManageNetwork, SyncProvider};
+pub use service::{Service, FetchFuture, ConsensusService, BftMessageStream, HeartbeatStream, GossipMessageStream,
+	TransactionPool, BlockAnnounceValidator, AnnounceValidity, BlockVerifier, Params, ManageNetwork, SyncProvider, NetworkChan, NetworkAction, TrySendError,
+	PeerSetHandle};
 pub use protocol::{ProtocolStatus};
+pub use reputation::{Misbehavior, PeerRecord};
 pub use sync::{Status as SyncStatus, SyncState};
 pub use network::{NonReservedPeerMode, NetworkConfiguration, ConnectionFilter, ConnectionDirection};
 pub use message::{generic as generic_message, BftMessage, LocalizedBftMessage, ConsensusVote, SignedConsensusVote, SignedConsensusMessage, SignedConsensusProposal};
 pub use error::Error;
-pub use config::{Role, ProtocolConfig};
-pub use on_demand::{OnDemand, OnDemandService, RemoteCallResponse};
+pub use config::{Role, ProtocolConfig, ReputationConfig, BandwidthConfig, QueueLimits, SyncMode, AnnounceMode,
+	PeerSetConfig, PeerSlots, ConnectionLimits, ResponseLimits, DEFAULT_HANDSHAKE_TIMEOUT_SECS,
+	DEFAULT_KEEP_ALIVE_TIMEOUT_SECS};
+pub use bandwidth::{PeerBandwidth, BandwidthSinks, ProtocolBandwidth};
+pub use network_state::{NetworkState, PeerState, DiscoveryState};
+pub use failures::{FailureReason, Failure, FailureCount, FailureState};
+pub use on_demand::{OnDemand, OnDemandService, RemoteResponse, RemoteCallResponse};
+pub use registry::{ProtocolRegistry, ApplicationProtocol, ProtocolContext};
+pub use notifications::{NotificationsProtocol, NotificationSink, NotificationEvent, NotificationStream,
+	ExecuteInProtocol};
+pub use network::{PeerId, ProtocolId};
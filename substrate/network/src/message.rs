@@ -19,7 +19,8 @@
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
 use service::Role as RoleFlags;
 
-pub use self::generic::{BlockAnnounce, RemoteCallRequest, ConsensusVote, SignedConsensusVote, FromBlock, Body};
+pub use self::generic::{BlockAnnounce, BlockHashAnnounce, RemoteCallRequest, RemoteHeaderRequest,
+	RemoteBodyRequest, RemoteReadRequest, StateRequest, ConsensusVote, SignedConsensusVote, FromBlock, Body};
 
 pub type RequestId = u64;
 
@@ -50,6 +51,9 @@ pub type LocalizedBftMessage<B> = generic::LocalizedBftMessage<
 	<B as BlockT>::Hash,
 >;
 
+/// Type alias for using the gossip message type using block type parameters.
+pub type GossipMessage<B> = generic::GossipMessage<<B as BlockT>::Hash>;
+
 /// Type alias for using the BlockData type using block type parameters.
 pub type BlockData<B> = generic::BlockData<
 	<B as BlockT>::Header,
@@ -57,6 +61,12 @@ pub type BlockData<B> = generic::BlockData<
 	<B as BlockT>::Extrinsic,
 >;
 
+/// Type alias for using the remote header response type using block type parameters.
+pub type RemoteHeaderResponse<B> = generic::RemoteHeaderResponse<<B as BlockT>::Header>;
+
+/// Type alias for using the remote body response type using block type parameters.
+pub type RemoteBodyResponse<B> = generic::RemoteBodyResponse<<B as BlockT>::Extrinsic>;
+
 /// Type alias for using the BlockResponse type using block type parameters.
 pub type BlockResponse<B> = generic::BlockResponse<
 	<B as BlockT>::Header,
@@ -85,6 +95,9 @@ pub type SignedConsensusMessage<B> = generic::SignedConsensusProposal<
 /// A set of transactions.
 pub type Transactions<E> = Vec<E>;
 
+/// An encoded liveness statement of a validator. Opaque to the network.
+pub type Heartbeat = Vec<u8>;
+
 /// Configured node role.
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum Role {
@@ -112,6 +125,16 @@ impl Role {
 		}
 		flags
 	}
+
+	/// Convert service flags to roles.
+	pub fn from_flags(flags: RoleFlags) -> Vec<Role> {
+		let mut roles = Vec::new();
+		if flags.contains(RoleFlags::FULL) { roles.push(Role::Full); }
+		if flags.contains(RoleFlags::LIGHT) { roles.push(Role::Light); }
+		if flags.contains(RoleFlags::VALIDATOR) { roles.push(Role::Validator); }
+		if flags.contains(RoleFlags::COLLATOR) { roles.push(Role::Collator); }
+		roles
+	}
 }
 
 impl From<RoleFlags> for Vec<Role> where {
@@ -157,6 +180,17 @@ pub enum Direction {
 	Descending,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+/// Response to `StateRequest`.
+pub struct StateResponse {
+	/// Id of a request this response was made for.
+	pub id: RequestId,
+	/// Storage entries in ascending order of keys.
+	pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+	/// Whether these are the last entries of the state.
+	pub complete: bool,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 /// Remote call response.
 pub struct RemoteCallResponse {
@@ -166,14 +200,24 @@ pub struct RemoteCallResponse {
 	pub proof: Vec<Vec<u8>>,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+/// Remote storage read response.
+pub struct RemoteReadResponse {
+	/// Id of a request this response was made for.
+	pub id: RequestId,
+	/// Read proof.
+	pub proof: Vec<Vec<u8>>,
+}
+
 /// Generic types.
 pub mod generic {
 	use primitives::AuthorityId;
 	use codec::Slicable;
 	use runtime_primitives::bft::Justification;
 	use ed25519;
+	use reputation::Misbehavior;
 
-	use super::{Role, BlockAttribute, RemoteCallResponse, RequestId, Transactions, Direction};
+	use super::{Role, BlockAttribute, RemoteCallResponse, RemoteReadResponse, StateResponse, RequestId, Transactions, Heartbeat, Direction};
 
 	use primitives::bytes;
 
@@ -319,6 +363,34 @@ pub mod generic {
 		RemoteCallRequest(RemoteCallRequest<Hash>),
 		/// Remote method call response.
 		RemoteCallResponse(RemoteCallResponse),
+		/// Validator liveness statement.
+		Heartbeat(Heartbeat),
+		/// Storage entries request.
+		StateRequest(StateRequest<Hash>),
+		/// Storage entries response.
+		StateResponse(StateResponse),
+		/// Sent before disconnecting a peer for misbehaving.
+		Goodbye(Misbehavior),
+		/// Block announce without the header.
+		BlockHashAnnounce(BlockHashAnnounce<Hash>),
+		/// Consensus message gossiped under a topic.
+		Gossip(GossipMessage<Hash>),
+		/// Remote block header request.
+		RemoteHeaderRequest(RemoteHeaderRequest<Hash>),
+		/// Remote block header response.
+		RemoteHeaderResponse(RemoteHeaderResponse<Header>),
+		/// Remote block body request.
+		RemoteBodyRequest(RemoteBodyRequest<Hash>),
+		/// Remote block body response.
+		RemoteBodyResponse(RemoteBodyResponse<Extrinsic>),
+		/// Remote storage read request.
+		RemoteReadRequest(RemoteReadRequest<Hash>),
+		/// Remote storage read response.
+		RemoteReadResponse(RemoteReadResponse),
+		/// Keep-alive probe, sent to peers which told they answer it.
+		Ping,
+		/// Answer to a keep-alive probe.
+		Pong,
 	}
 
 	/// Status sent on connection.
@@ -334,12 +406,34 @@ pub mod generic {
 		pub best_hash: Hash,
 		/// Genesis block hash.
 		pub genesis_hash: Hash,
+		/// Name of the runtime specification at the best block.
+		pub spec_name: String,
+		/// Version of the runtime specification at the best block.
+		pub spec_version: u32,
 		/// Signatue of `best_hash` made with validator address. Required for the validator role.
 		pub validator_signature: Option<ed25519::Signature>,
 		/// Validator address. Required for the validator role.
 		pub validator_id: Option<AuthorityId>,
 		/// Parachain id. Required for the collator role.
 		pub parachain_id: Option<u64>,
+		/// Time the status was sent, in milliseconds since the Unix epoch.
+		#[serde(default)]
+		pub time: Option<u64>,
+		/// Whether blocks may be announced by hash only.
+		#[serde(default)]
+		pub hash_announcements: bool,
+		/// Number and hash of the latest finalized block.
+		#[serde(default)]
+		pub finalized: Option<(Number, Hash)>,
+		/// Maximum number of blocks in the block responses accepted.
+		#[serde(default)]
+		pub max_blocks_per_response: Option<u32>,
+		/// Maximum size of the block responses accepted, in bytes.
+		#[serde(default)]
+		pub max_response_bytes: Option<u64>,
+		/// Whether pings are answered.
+		#[serde(default)]
+		pub keep_alive: bool,
 	}
 
 	/// Request block data from a peer.
@@ -375,6 +469,23 @@ pub mod generic {
 		pub header: H,
 	}
 
+	/// Announce a new block by its hash. The header is requested by peers
+	/// which don't know the block.
+	#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+	pub struct BlockHashAnnounce<H> {
+		/// New block hash.
+		pub hash: H,
+	}
+
+	/// Consensus message gossiped to validators. Opaque to the network.
+	#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+	pub struct GossipMessage<H> {
+		/// Topic of the message, the hash of the relay parent agreement happens on.
+		pub topic: H,
+		/// Encoded message.
+		pub data: Vec<u8>,
+	}
+
 	#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 	/// Remote call request.
 	pub struct RemoteCallRequest<H> {
@@ -387,4 +498,64 @@ pub mod generic {
 		/// Call data.
 		pub data: Vec<u8>,
 	}
+
+	#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+	/// Remote block header request.
+	pub struct RemoteHeaderRequest<H> {
+		/// Unique request id.
+		pub id: RequestId,
+		/// Hash of the block.
+		pub block: H,
+	}
+
+	#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+	/// Remote block header response.
+	pub struct RemoteHeaderResponse<Header> {
+		/// Id of a request this response was made for.
+		pub id: RequestId,
+		/// Header. None if the block is unknown to the responder.
+		pub header: Option<Header>,
+	}
+
+	#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+	/// Remote block body request.
+	pub struct RemoteBodyRequest<H> {
+		/// Unique request id.
+		pub id: RequestId,
+		/// Hash of the block.
+		pub block: H,
+	}
+
+	#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+	/// Remote block body response.
+	pub struct RemoteBodyResponse<Extrinsic> {
+		/// Id of a request this response was made for.
+		pub id: RequestId,
+		/// Block body. None if the body is unknown to the responder.
+		pub body: Option<Vec<Extrinsic>>,
+	}
+
+	#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+	/// Remote storage read request.
+	pub struct RemoteReadRequest<H> {
+		/// Unique request id.
+		pub id: RequestId,
+		/// Block at which to read the storage.
+		pub block: H,
+		/// Storage key.
+		pub key: Vec<u8>,
+	}
+
+	#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+	/// Request a chunk of the storage entries of a block's state.
+	pub struct StateRequest<H> {
+		/// Unique request id.
+		pub id: RequestId,
+		/// Block at which to read the state.
+		pub block: H,
+		/// Return entries with keys greater than this one.
+		pub start: Vec<u8>,
+		/// Maximum number of entries to return.
+		pub max: u32,
+	}
 }
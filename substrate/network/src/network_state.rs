@@ -0,0 +1,164 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State of the connectivity of the node, for debugging.
+//!
+//! Built on request by the network service from the connected peers and the
+//! discovery configuration, and serializable so that it can be served over RPC.
+
+use std::time::Duration;
+
+use network::PeerId;
+use message::Role;
+use bandwidth::ProtocolBandwidth;
+use failures::{FailureReason, FailureState, Failure};
+
+// weight of the latest round-trip in the average, as a fraction of 8.
+const LATENCY_WEIGHT: u64 = 1;
+
+/// Average round-trip time of the requests made to a peer. Recent requests
+/// weigh more, so that the average follows changes of the link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Latency {
+	average_ms: Option<u64>,
+}
+
+impl Latency {
+	/// Note the round-trip time of a request.
+	pub fn note(&mut self, elapsed: Duration) {
+		let ms = elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000;
+		self.average_ms = Some(match self.average_ms {
+			None => ms,
+			Some(average) => (average * (8 - LATENCY_WEIGHT) + ms * LATENCY_WEIGHT) / 8,
+		});
+	}
+
+	/// Average round-trip time in milliseconds, if any request was answered.
+	pub fn average_ms(&self) -> Option<u64> {
+		self.average_ms
+	}
+}
+
+/// A connected peer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerState {
+	/// Session of the peer.
+	pub peer_id: PeerId,
+	/// Public node ID, if known.
+	pub node_id: Option<String>,
+	/// Client ID of the node.
+	pub client_version: String,
+	/// Remote endpoint address.
+	pub remote_address: String,
+	/// Roles, empty until the handshake is done.
+	pub roles: Vec<Role>,
+	/// Hex-encoded best block hash, once the handshake is done.
+	pub best_hash: Option<String>,
+	/// Best block number, once the handshake is done.
+	pub best_number: Option<u64>,
+	/// Average round-trip time of requests, in milliseconds.
+	pub average_latency_ms: Option<u64>,
+	/// Whether the peer is a reserved node.
+	pub reserved: bool,
+	/// Whether the peer only serves historical blocks.
+	pub historical: bool,
+}
+
+/// How the node finds peers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveryState {
+	/// Address other nodes may connect to, if known.
+	pub external_url: Option<String>,
+	/// Whether nodes are discovered through the peers, besides the boot and reserved nodes.
+	pub enabled: bool,
+	/// Addresses of the boot nodes.
+	pub boot_nodes: Vec<String>,
+	/// Node IDs of the reserved nodes.
+	pub reserved_nodes: Vec<String>,
+	/// Whether only reserved nodes are accepted.
+	pub reserved_only: bool,
+}
+
+/// Connected peers and discovery status of the node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkState {
+	/// Connected peers, by session.
+	pub peers: Vec<PeerState>,
+	/// Bytes exchanged over each protocol since the node started.
+	pub bandwidth: Vec<ProtocolBandwidth>,
+	/// Connections which failed.
+	pub failures: FailureState,
+	/// How the node finds peers.
+	pub discovery: DiscoveryState,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json;
+
+	#[test]
+	fn latency_follows_recent_requests() {
+		let mut latency = Latency::default();
+		assert_eq!(latency.average_ms(), None);
+		latency.note(Duration::from_millis(800));
+		assert_eq!(latency.average_ms(), Some(800));
+		latency.note(Duration::from_millis(0));
+		assert_eq!(latency.average_ms(), Some(700));
+		latency.note(Duration::from_secs(1));
+		assert_eq!(latency.average_ms(), Some(737));
+	}
+
+	#[test]
+	fn network_state_serializes() {
+		let state = NetworkState {
+			peers: vec![PeerState {
+				peer_id: 1,
+				node_id: Some("abcd".into()),
+				client_version: "parity-polkadot/0.2.0".into(),
+				remote_address: "127.0.0.1:30333".into(),
+				roles: vec![Role::Full],
+				best_hash: None,
+				best_number: Some(10),
+				average_latency_ms: Some(50),
+				reserved: true,
+				historical: false,
+			}],
+			bandwidth: vec![ProtocolBandwidth { protocol: "dot".into(), bytes_received: 1024, bytes_sent: 512 }],
+			failures: FailureState {
+				counts: Vec::new(),
+				recent: vec![Failure { reason: FailureReason::HandshakeTimeout, remote_address: Some("10.0.0.1".into()) }],
+			},
+			discovery: DiscoveryState {
+				external_url: None,
+				enabled: true,
+				boot_nodes: Vec::new(),
+				reserved_nodes: vec!["abcd".into()],
+				reserved_only: false,
+			},
+		};
+		let json = serde_json::to_value(&state).unwrap();
+		assert_eq!(json["peers"][0]["averageLatencyMs"], 50);
+		assert_eq!(json["peers"][0]["roles"][0], "Full");
+		assert_eq!(json["bandwidth"][0]["bytesReceived"], 1024);
+		assert_eq!(json["failures"]["recent"][0]["reason"], "HandshakeTimeout");
+		assert_eq!(json["failures"]["recent"][0]["remoteAddress"], "10.0.0.1");
+		assert_eq!(json["discovery"]["reservedNodes"][0], "abcd");
+	}
+}
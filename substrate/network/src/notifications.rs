@@ -0,0 +1,217 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Notification protocols: an application protocol exchanging a handshake
+//! on connection and then streams of notifications with each peer.
+//!
+//! Notifications to and from a single peer are delivered in the order they
+//! were sent. All events of a protocol are yielded by a single stream, so a
+//! peer's `Opened` event always precedes its notifications and `Closed` follows them.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Weak};
+use futures::sync::mpsc;
+use parking_lot::{Mutex, RwLock};
+use network::{PeerId, ProtocolId};
+use registry::{ApplicationProtocol, ProtocolContext};
+
+const HANDSHAKE: u8 = 0;
+const NOTIFICATION: u8 = 1;
+
+/// Events of a notification protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotificationEvent {
+	/// A peer completed the handshake and may now be sent notifications.
+	Opened {
+		/// The peer.
+		peer: PeerId,
+		/// The handshake payload it sent.
+		handshake: Vec<u8>,
+	},
+	/// A previously opened peer disconnected.
+	Closed {
+		/// The peer.
+		peer: PeerId,
+	},
+	/// A notification was received from an opened peer.
+	Notification {
+		/// The peer.
+		peer: PeerId,
+		/// The notification payload.
+		data: Vec<u8>,
+	},
+}
+
+/// Stream of events of a notification protocol.
+pub type NotificationStream = mpsc::UnboundedReceiver<NotificationEvent>;
+
+/// Access to the context of registered application protocols from outside of
+/// network callbacks.
+pub trait ExecuteInProtocol: Send + Sync {
+	/// Execute a closure with the context of the given protocol.
+	fn execute_in_protocol(&self, id: ProtocolId, f: &mut FnMut(&mut ProtocolContext));
+}
+
+struct Peers {
+	// peers which we have sent our handshake, but have not received theirs.
+	handshaking: Vec<PeerId>,
+	// opened peers and their queue of outbound notifications.
+	open: HashMap<PeerId, VecDeque<Vec<u8>>>,
+	events: mpsc::UnboundedSender<NotificationEvent>,
+}
+
+impl Peers {
+	fn emit(&self, event: NotificationEvent) {
+		let _ = self.events.unbounded_send(event);
+	}
+
+	fn flush(&mut self, ctx: &mut ProtocolContext) {
+		for (peer, queue) in self.open.iter_mut() {
+			for data in queue.drain(..) {
+				ctx.send(*peer, encode(NOTIFICATION, &data));
+			}
+		}
+	}
+}
+
+fn encode(kind: u8, payload: &[u8]) -> Vec<u8> {
+	let mut packet = Vec::with_capacity(payload.len() + 1);
+	packet.push(kind);
+	packet.extend(payload);
+	packet
+}
+
+/// A notification protocol. Register with a `ProtocolRegistry` to use it.
+pub struct NotificationsProtocol {
+	id: ProtocolId,
+	handshake: Vec<u8>,
+	peers: Mutex<Peers>,
+	link: RwLock<Option<Weak<ExecuteInProtocol>>>,
+}
+
+impl NotificationsProtocol {
+	/// Create a new notification protocol with the given name, which sends the
+	/// given handshake payload to every peer on connection.
+	pub fn new(id: ProtocolId, handshake: Vec<u8>) -> (Arc<Self>, NotificationStream) {
+		let (events, stream) = mpsc::unbounded();
+		let protocol = NotificationsProtocol {
+			id,
+			handshake,
+			peers: Mutex::new(Peers {
+				handshaking: Vec::new(),
+				open: HashMap::new(),
+				events,
+			}),
+			link: RwLock::new(None),
+		};
+
+		(Arc::new(protocol), stream)
+	}
+
+	/// Set the network service to send notifications through as soon as they are
+	/// queued. Without it, notifications are only sent on the periodic tick.
+	pub fn set_service_link(&self, link: Weak<ExecuteInProtocol>) {
+		*self.link.write() = Some(link);
+	}
+
+	/// Get a sink for notifications to an opened peer. `None` if the peer
+	/// has not completed the handshake.
+	pub fn sink(protocol: &Arc<Self>, peer: PeerId) -> Option<NotificationSink> {
+		if protocol.peers.lock().open.contains_key(&peer) {
+			Some(NotificationSink { protocol: protocol.clone(), peer })
+		} else {
+			None
+		}
+	}
+
+	/// All opened peers.
+	pub fn open_peers(&self) -> Vec<PeerId> {
+		self.peers.lock().open.keys().cloned().collect()
+	}
+
+	fn flush_now(&self) {
+		let link = match self.link.read().as_ref().and_then(|l| l.upgrade()) {
+			Some(link) => link,
+			None => return,
+		};
+
+		// the queue lock is only taken inside the network context, so the
+		// network and queue locks are always acquired in the same order.
+		link.execute_in_protocol(self.id, &mut |ctx| self.peers.lock().flush(ctx));
+	}
+}
+
+impl ApplicationProtocol for NotificationsProtocol {
+	fn on_peer_connected(&self, ctx: &mut ProtocolContext, peer: PeerId) {
+		self.peers.lock().handshaking.push(peer);
+		ctx.send(peer, encode(HANDSHAKE, &self.handshake));
+	}
+
+	fn on_peer_disconnected(&self, _ctx: &mut ProtocolContext, peer: PeerId) {
+		let mut peers = self.peers.lock();
+		peers.handshaking.retain(|p| p != &peer);
+		if peers.open.remove(&peer).is_some() {
+			peers.emit(NotificationEvent::Closed { peer });
+		}
+	}
+
+	fn on_message(&self, ctx: &mut ProtocolContext, peer: PeerId, data: &[u8]) {
+		let mut peers = self.peers.lock();
+		match data.split_first() {
+			Some((&HANDSHAKE, handshake)) if peers.handshaking.contains(&peer) => {
+				peers.handshaking.retain(|p| p != &peer);
+				peers.open.insert(peer, VecDeque::new());
+				peers.emit(NotificationEvent::Opened { peer, handshake: handshake.to_vec() });
+			}
+			Some((&NOTIFICATION, notification)) if peers.open.contains_key(&peer) => {
+				peers.emit(NotificationEvent::Notification { peer, data: notification.to_vec() });
+			}
+			_ => {
+				trace!(target: "sync", "Unexpected notification protocol packet from {}", peer);
+				ctx.disable_peer(peer);
+			}
+		}
+	}
+
+	fn tick(&self, ctx: &mut ProtocolContext) {
+		self.peers.lock().flush(ctx);
+	}
+}
+
+/// Sends notifications to a single peer, in order.
+#[derive(Clone)]
+pub struct NotificationSink {
+	protocol: Arc<NotificationsProtocol>,
+	peer: PeerId,
+}
+
+impl NotificationSink {
+	/// The peer notifications are sent to.
+	pub fn peer(&self) -> PeerId {
+		self.peer
+	}
+
+	/// Queue a notification. Returns false if the peer has disconnected.
+	pub fn send(&self, data: Vec<u8>) -> bool {
+		match self.protocol.peers.lock().open.get_mut(&self.peer) {
+			Some(queue) => queue.push_back(data),
+			None => return false,
+		}
+
+		self.protocol.flush_now();
+		true
+	}
+}
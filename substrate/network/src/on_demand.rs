@@ -17,7 +17,7 @@
 //! On-demand requests service.
 
 use std::collections::VecDeque;
-use std::sync::{Arc, Weak};
+use std::sync::Arc;
 use std::time::{Instant, Duration};
 use futures::{Async, Future, Poll};
 use futures::sync::oneshot::{channel, Receiver, Sender};
@@ -25,7 +25,8 @@ use linked_hash_map::LinkedHashMap;
 use linked_hash_map::Entry;
 use parking_lot::Mutex;
 use client;
-use client::light::fetcher::{Fetcher, FetchChecker, RemoteCallRequest};
+use client::light::fetcher::{Fetcher, FetchChecker, RemoteCallRequest, RemoteHeaderRequest,
+	RemoteBodyRequest, RemoteReadRequest};
 use io::SyncIo;
 use message;
 use network::PeerId;
@@ -48,22 +49,34 @@ pub trait OnDemandService<Block: BlockT>: Send + Sync {
 
 	/// When call response is received from remote node.
 	fn on_remote_call_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteCallResponse);
+
+	/// When header response is received from remote node.
+	fn on_remote_header_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteHeaderResponse<Block>);
+
+	/// When body response is received from remote node.
+	fn on_remote_body_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteBodyResponse<Block>);
+
+	/// When read response is received from remote node.
+	fn on_remote_read_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteReadResponse);
 }
 
 /// On-demand requests service. Dispatches requests to appropriate peers.
-pub struct OnDemand<B: BlockT, E: service::ExecuteInContext<B>> {
-	core: Mutex<OnDemandCore<B, E>>,
+pub struct OnDemand<B: BlockT> {
+	core: Mutex<OnDemandCore<B>>,
 	checker: Arc<FetchChecker<B>>,
 }
 
-/// On-demand remote call response.
-pub struct RemoteCallResponse {
-	receiver: Receiver<Result<client::CallResult, client::error::Error>>,
+/// On-demand remote response. Resolves once a response has been received
+/// from one of the peers and checked.
+pub struct RemoteResponse<T> {
+	receiver: Receiver<Result<T, client::error::Error>>,
 }
 
-#[derive(Default)]
-struct OnDemandCore<B: BlockT, E: service::ExecuteInContext<B>> {
-	service: Weak<E>,
+/// On-demand remote call response.
+pub type RemoteCallResponse = RemoteResponse<client::CallResult>;
+
+struct OnDemandCore<B: BlockT> {
+	network: Option<service::NetworkChan<B>>,
 	next_request_id: u64,
 	pending_requests: VecDeque<Request<B>>,
 	active_peers: LinkedHashMap<PeerId, Request<B>>,
@@ -78,15 +91,19 @@ struct Request<Block: BlockT> {
 
 enum RequestData<Block: BlockT> {
 	RemoteCall(RemoteCallRequest<Block::Hash>, Sender<Result<client::CallResult, client::error::Error>>),
+	RemoteHeader(RemoteHeaderRequest<Block::Hash>, Sender<Result<Block::Header, client::error::Error>>),
+	RemoteBody(RemoteBodyRequest<Block::Header>, Sender<Result<Vec<Block::Extrinsic>, client::error::Error>>),
+	RemoteRead(RemoteReadRequest<Block::Hash>, Sender<Result<Option<Vec<u8>>, client::error::Error>>),
 }
 
 enum Accept<Block: BlockT> {
 	Ok,
 	CheckFailed(client::error::Error, RequestData<Block>),
+	Unexpected(RequestData<Block>),
 }
 
-impl Future for RemoteCallResponse {
-	type Item = client::CallResult;
+impl<T> Future for RemoteResponse<T> {
+	type Item = T;
 	type Error = client::error::Error;
 
 	fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
@@ -100,8 +117,7 @@ impl Future for RemoteCallResponse {
 	}
 }
 
-impl<B: BlockT, E> OnDemand<B, E> where
-	E: service::ExecuteInContext<B>,
+impl<B: BlockT> OnDemand<B> where
 	B::Header: HeaderT<Number=u64>,
 {
 	/// Creates new on-demand service.
@@ -109,7 +125,7 @@ impl<B: BlockT, E> OnDemand<B, E> where
 		OnDemand {
 			checker,
 			core: Mutex::new(OnDemandCore {
-				service: Weak::new(),
+				network: None,
 				next_request_id: 0,
 				pending_requests: VecDeque::new(),
 				active_peers: LinkedHashMap::new(),
@@ -118,9 +134,9 @@ impl<B: BlockT, E> OnDemand<B, E> where
 		}
 	}
 
-	/// Sets weak reference to network service.
-	pub fn set_service_link(&self, service: Weak<E>) {
-		self.core.lock().service = service;
+	/// Sets the channel used to send requests through the network.
+	pub fn set_network_chan(&self, network: service::NetworkChan<B>) {
+		self.core.lock().network = Some(network);
 	}
 
 	/// Schedule && dispatch all scheduled requests.
@@ -149,6 +165,13 @@ impl<B: BlockT, E> OnDemand<B, E> where
 			Accept::CheckFailed(error, retry_request_data) => {
 				trace!(target: "sync", "Failed to check remote {} response from peer {}: {}", rtype, peer, error);
 
+				io.disconnect_peer(peer);
+				core.remove_peer(peer);
+				Some(retry_request_data)
+			},
+			Accept::Unexpected(retry_request_data) => {
+				trace!(target: "sync", "Unexpected response to remote {} request from peer {}", rtype, peer);
+
 				io.disconnect_peer(peer);
 				core.remove_peer(peer);
 				Some(retry_request_data)
@@ -163,9 +186,8 @@ impl<B: BlockT, E> OnDemand<B, E> where
 	}
 }
 
-impl<B, E> OnDemandService<B> for OnDemand<B, E> where
+impl<B> OnDemandService<B> for OnDemand<B> where
 	B: BlockT,
-	E: service::ExecuteInContext<B>,
 	B::Header: HeaderT<Number=u64>,
 {
 	fn on_connect(&self, peer: PeerId, role: service::Role) {
@@ -203,27 +225,89 @@ impl<B, E> OnDemandService<B> for OnDemand<B, E> where
 				},
 				Err(error) => Accept::CheckFailed(error, RequestData::RemoteCall(request, sender)),
 			},
+			data => Accept::Unexpected(data),
+		})
+	}
+
+	fn on_remote_header_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteHeaderResponse<B>) {
+		self.accept_response("header", io, peer, response.id, |request| match request.data {
+			RequestData::RemoteHeader(request, sender) => match self.checker.check_header_response(&request, response.header) {
+				Ok(response) => {
+					// we do not bother if receiver has been dropped already
+					let _ = sender.send(Ok(response));
+					Accept::Ok
+				},
+				Err(error) => Accept::CheckFailed(error, RequestData::RemoteHeader(request, sender)),
+			},
+			data => Accept::Unexpected(data),
+		})
+	}
+
+	fn on_remote_body_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteBodyResponse<B>) {
+		self.accept_response("body", io, peer, response.id, |request| match request.data {
+			RequestData::RemoteBody(request, sender) => match self.checker.check_body_response(&request, response.body) {
+				Ok(response) => {
+					// we do not bother if receiver has been dropped already
+					let _ = sender.send(Ok(response));
+					Accept::Ok
+				},
+				Err(error) => Accept::CheckFailed(error, RequestData::RemoteBody(request, sender)),
+			},
+			data => Accept::Unexpected(data),
+		})
+	}
+
+	fn on_remote_read_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteReadResponse) {
+		self.accept_response("read", io, peer, response.id, |request| match request.data {
+			RequestData::RemoteRead(request, sender) => match self.checker.check_read_proof(&request, response.proof) {
+				Ok(response) => {
+					// we do not bother if receiver has been dropped already
+					let _ = sender.send(Ok(response));
+					Accept::Ok
+				},
+				Err(error) => Accept::CheckFailed(error, RequestData::RemoteRead(request, sender)),
+			},
+			data => Accept::Unexpected(data),
 		})
 	}
 }
 
-impl<B, E> Fetcher<B> for OnDemand<B, E> where
+impl<B> Fetcher<B> for OnDemand<B> where
 	B: BlockT,
-	E: service::ExecuteInContext<B>,
 	B::Header: HeaderT<Number=u64>,
 {
 	type RemoteCallResult = RemoteCallResponse;
+	type RemoteHeaderResult = RemoteResponse<B::Header>;
+	type RemoteBodyResult = RemoteResponse<Vec<B::Extrinsic>>;
+	type RemoteReadResult = RemoteResponse<Option<Vec<u8>>>;
 
 	fn remote_call(&self, request: RemoteCallRequest<B::Hash>) -> Self::RemoteCallResult {
 		let (sender, receiver) = channel();
 		self.schedule_request(RequestData::RemoteCall(request, sender),
-			RemoteCallResponse { receiver })
+			RemoteResponse { receiver })
+	}
+
+	fn remote_header(&self, request: RemoteHeaderRequest<B::Hash>) -> Self::RemoteHeaderResult {
+		let (sender, receiver) = channel();
+		self.schedule_request(RequestData::RemoteHeader(request, sender),
+			RemoteResponse { receiver })
+	}
+
+	fn remote_body(&self, request: RemoteBodyRequest<B::Header>) -> Self::RemoteBodyResult {
+		let (sender, receiver) = channel();
+		self.schedule_request(RequestData::RemoteBody(request, sender),
+			RemoteResponse { receiver })
+	}
+
+	fn remote_read(&self, request: RemoteReadRequest<B::Hash>) -> Self::RemoteReadResult {
+		let (sender, receiver) = channel();
+		self.schedule_request(RequestData::RemoteRead(request, sender),
+			RemoteResponse { receiver })
 	}
 }
 
-impl<B, E> OnDemandCore<B, E> where
+impl<B> OnDemandCore<B> where
 	B: BlockT,
-	E: service::ExecuteInContext<B> ,
 	B::Header: HeaderT<Number=u64>
 {
 	pub fn add_peer(&mut self, peer: PeerId) {
@@ -281,8 +365,8 @@ impl<B, E> OnDemandCore<B, E> where
 	}
 
 	pub fn dispatch(&mut self) {
-		let service = match self.service.upgrade() {
-			Some(service) => service,
+		let network = match self.network {
+			Some(ref network) => network.clone(),
 			None => return,
 		};
 
@@ -293,12 +377,28 @@ impl<B, E> OnDemandCore<B, E> where
 			};
 
 			let mut request = self.pending_requests.pop_front().expect("checked in loop condition; qed");
+			let action = service::NetworkAction::SendMessage(peer, request.message());
+			match network.try_send(action) {
+				Ok(()) => (),
+				Err(service::TrySendError::Full(_)) => {
+					// network is busy. keep the request to be dispatched again
+					// when peers are next maintained or a request completes.
+					trace!(target: "sync", "Network action queue is full; delaying remote request {}", request.id);
+					self.idle_peers.push_front(peer);
+					self.pending_requests.push_front(request);
+					return;
+				},
+				Err(service::TrySendError::Closed(_)) => {
+					// network is gone. dropping the request resolves its
+					// response with an error rather than leaving the caller waiting.
+					trace!(target: "sync", "Network has shut down; failing remote request {}", request.id);
+					self.idle_peers.push_front(peer);
+					continue;
+				},
+			}
+
 			request.timestamp = Instant::now();
 			trace!(target: "sync", "Dispatching remote request {} to peer {}", request.id, peer);
-
-			service.execute_in_context(|ctx, protocol| {
-				protocol.send_message(ctx, peer, request.message())
-			});
 			self.active_peers.insert(peer, request);
 		}
 	}
@@ -313,6 +413,19 @@ impl<Block: BlockT> Request<Block> {
 				method: data.method.clone(),
 				data: data.call_data.clone(),
 			}),
+			RequestData::RemoteHeader(ref data, _) => message::generic::Message::RemoteHeaderRequest(message::RemoteHeaderRequest {
+				id: self.id,
+				block: data.block,
+			}),
+			RequestData::RemoteBody(ref data, _) => message::generic::Message::RemoteBodyRequest(message::RemoteBodyRequest {
+				id: self.id,
+				block: data.header.hash(),
+			}),
+			RequestData::RemoteRead(ref data, _) => message::generic::Message::RemoteReadRequest(message::RemoteReadRequest {
+				id: self.id,
+				block: data.block,
+				key: data.key.clone(),
+			}),
 		}
 	}
 }
@@ -325,23 +438,19 @@ mod tests {
 	use futures::Future;
 	use parking_lot::RwLock;
 	use client;
-	use client::light::fetcher::{Fetcher, FetchChecker, RemoteCallRequest};
-	use io::NetSyncIo;
+	use client::light::fetcher::{Fetcher, FetchChecker, RemoteCallRequest, RemoteHeaderRequest,
+		RemoteBodyRequest, RemoteReadRequest};
+	use futures::Stream;
 	use message;
 	use network::PeerId;
-	use protocol::Protocol;
-	use service::{Role, ExecuteInContext};
+	use service::{Role, NetworkChan, NetworkAction, Event, ActionReceiver};
 	use test::TestIo;
 	use super::{REQUEST_TIMEOUT, OnDemand, OnDemandService};
-	use test_client::runtime::{Block, Hash};
+	use runtime_primitives::traits::Header as HeaderT;
+	use test_client::runtime::{Block, Extrinsic, Hash, Header};
 
-	struct DummyExecutor;
 	struct DummyFetchChecker { ok: bool }
 
-	impl ExecuteInContext<Block> for DummyExecutor {
-		fn execute_in_context<F: Fn(&mut NetSyncIo, &Protocol<Block>)>(&self, _closure: F) {}
-	}
-
 	impl FetchChecker<Block> for DummyFetchChecker {
 		fn check_execution_proof(&self, _request: &RemoteCallRequest<Hash>, _remote_proof: Vec<Vec<u8>>) -> client::error::Result<client::CallResult> {
 			match self.ok {
@@ -352,21 +461,42 @@ mod tests {
 				false => Err(client::error::ErrorKind::Backend("Test error".into()).into()),
 			}
 		}
+
+		fn check_header_response(&self, _request: &RemoteHeaderRequest<Hash>, remote_header: Option<Header>) -> client::error::Result<Header> {
+			match (self.ok, remote_header) {
+				(true, Some(header)) => Ok(header),
+				_ => Err(client::error::ErrorKind::Backend("Test error".into()).into()),
+			}
+		}
+
+		fn check_body_response(&self, _request: &RemoteBodyRequest<Header>, remote_body: Option<Vec<Extrinsic>>) -> client::error::Result<Vec<Extrinsic>> {
+			match (self.ok, remote_body) {
+				(true, Some(body)) => Ok(body),
+				_ => Err(client::error::ErrorKind::Backend("Test error".into()).into()),
+			}
+		}
+
+		fn check_read_proof(&self, _request: &RemoteReadRequest<Hash>, _remote_proof: Vec<Vec<u8>>) -> client::error::Result<Option<Vec<u8>>> {
+			match self.ok {
+				true => Ok(Some(vec![42])),
+				false => Err(client::error::ErrorKind::Backend("Test error".into()).into()),
+			}
+		}
 	}
 
-	fn dummy(ok: bool) -> (Arc<DummyExecutor>, Arc<OnDemand<Block, DummyExecutor>>) {
-		let executor = Arc::new(DummyExecutor);
+	fn dummy(ok: bool) -> (ActionReceiver<Block>, Arc<OnDemand<Block>>) {
+		let (network, actions) = NetworkChan::new();
 		let service = Arc::new(OnDemand::new(Arc::new(DummyFetchChecker { ok })));
-		service.set_service_link(Arc::downgrade(&executor));
-		(executor, service)
+		service.set_network_chan(network);
+		(actions, service)
 	}
 
-	fn total_peers(on_demand: &OnDemand<Block, DummyExecutor>) -> usize {
+	fn total_peers(on_demand: &OnDemand<Block>) -> usize {
 		let core = on_demand.core.lock();
 		core.idle_peers.len() + core.active_peers.len()
 	}
 
-	fn receive_call_response(on_demand: &OnDemand<Block, DummyExecutor>, network: &mut TestIo, peer: PeerId, id: message::RequestId) {
+	fn receive_call_response(on_demand: &OnDemand<Block>, network: &mut TestIo, peer: PeerId, id: message::RequestId) {
 		on_demand.on_remote_call_response(network, peer, message::RemoteCallResponse {
 			id: id,
 			proof: vec![vec![2]],
@@ -383,6 +513,58 @@ mod tests {
 		assert_eq!(vec![1, 2, 3], on_demand.core.lock().idle_peers.iter().cloned().collect::<Vec<_>>());
 	}
 
+	#[test]
+	fn requests_are_queued_as_network_actions() {
+		let (actions, on_demand) = dummy(true);
+		on_demand.on_connect(0, Role::FULL);
+		on_demand.remote_call(RemoteCallRequest { block: Default::default(), method: "test".into(), call_data: vec![] });
+
+		match actions.wait().next() {
+			Some(Ok(Event::Action(NetworkAction::SendMessage(0, message::generic::Message::RemoteCallRequest(request))))) =>
+				assert_eq!(request.method, "test"),
+			_ => panic!("expected remote call request to peer 0"),
+		}
+	}
+
+	#[test]
+	fn requests_fail_while_network_unavailable() {
+		let (actions, on_demand) = dummy(true);
+		drop(actions);
+
+		on_demand.on_connect(0, Role::FULL);
+		let response = on_demand.remote_call(RemoteCallRequest { block: Default::default(), method: "test".into(), call_data: vec![] });
+		assert!(response.wait().is_err());
+		assert_eq!(vec![0], on_demand.core.lock().idle_peers.iter().cloned().collect::<Vec<_>>());
+		assert!(on_demand.core.lock().active_peers.is_empty());
+		assert!(on_demand.core.lock().pending_requests.is_empty());
+	}
+
+	#[test]
+	fn requests_wait_while_network_queue_is_full() {
+		let (network, actions) = NetworkChan::new();
+		let on_demand = Arc::new(OnDemand::new(Arc::new(DummyFetchChecker { ok: true })));
+		on_demand.set_network_chan(network.clone());
+		let mut queued = 0;
+		while network.try_send(NetworkAction::PropagateTransactions).is_ok() {
+			queued += 1;
+		}
+
+		on_demand.on_connect(0, Role::FULL);
+		on_demand.remote_call(RemoteCallRequest { block: Default::default(), method: "test".into(), call_data: vec![] });
+		assert_eq!(on_demand.core.lock().pending_requests.len(), 1);
+		assert_eq!(vec![0], on_demand.core.lock().idle_peers.iter().cloned().collect::<Vec<_>>());
+
+		// once the network makes room, the request is dispatched on the next attempt.
+		let mut actions = actions.wait();
+		for _ in 0..queued {
+			actions.next();
+		}
+		let queue = RwLock::new(VecDeque::new());
+		on_demand.maintain_peers(&mut TestIo::new(&queue, None));
+		assert!(on_demand.core.lock().pending_requests.is_empty());
+		assert_eq!(on_demand.core.lock().active_peers.len(), 1);
+	}
+
 	#[test]
 	fn disconnects_from_idle_peer() {
 		let (_, on_demand) = dummy(true);
@@ -467,4 +649,58 @@ mod tests {
 		receive_call_response(&*on_demand, &mut network, 0, 0);
 		thread.join().unwrap();
 	}
+
+	#[test]
+	fn disconnects_from_peer_on_response_to_other_request() {
+		let (_x, on_demand) = dummy(true);
+		let queue = RwLock::new(VecDeque::new());
+		let mut network = TestIo::new(&queue, None);
+		on_demand.on_connect(0, Role::FULL);
+
+		on_demand.remote_read(RemoteReadRequest { block: Default::default(), key: b":key".to_vec() });
+		receive_call_response(&*on_demand, &mut network, 0, 0);
+		assert!(network.to_disconnect.contains(&0));
+		assert_eq!(on_demand.core.lock().pending_requests.len(), 1);
+	}
+
+	#[test]
+	fn retries_header_request_with_another_peer() {
+		let (_x, on_demand) = dummy(true);
+		let queue = RwLock::new(VecDeque::new());
+		let mut network = TestIo::new(&queue, None);
+		on_demand.on_connect(0, Role::FULL);
+		on_demand.on_connect(1, Role::FULL);
+
+		let header = Header::new(1, Default::default(), Default::default(), Default::default(), Default::default());
+		let response = on_demand.remote_header(RemoteHeaderRequest { block: Default::default() });
+		let expected = header.clone();
+		let thread = ::std::thread::spawn(move || {
+			assert_eq!(response.wait().unwrap(), expected);
+		});
+
+		// peer 0 doesn't know the header, so the request is retried with peer 1.
+		on_demand.on_remote_header_response(&mut network, 0, message::generic::RemoteHeaderResponse { id: 0, header: None });
+		assert!(network.to_disconnect.contains(&0));
+		assert_eq!(vec![1], on_demand.core.lock().active_peers.keys().cloned().collect::<Vec<_>>());
+
+		on_demand.on_remote_header_response(&mut network, 1, message::generic::RemoteHeaderResponse { id: 1, header: Some(header) });
+		thread.join().unwrap();
+		assert!(!network.to_disconnect.contains(&1));
+	}
+
+	#[test]
+	fn receives_remote_read_response() {
+		let (_x, on_demand) = dummy(true);
+		let queue = RwLock::new(VecDeque::new());
+		let mut network = TestIo::new(&queue, None);
+		on_demand.on_connect(0, Role::FULL);
+
+		let response = on_demand.remote_read(RemoteReadRequest { block: Default::default(), key: b":key".to_vec() });
+		let thread = ::std::thread::spawn(move || {
+			assert_eq!(response.wait().unwrap(), Some(vec![42]));
+		});
+
+		on_demand.on_remote_read_response(&mut network, 0, message::RemoteReadResponse { id: 0, proof: vec![vec![2]] });
+		thread.join().unwrap();
+	}
 }
\ No newline at end of file
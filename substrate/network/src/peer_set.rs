@@ -0,0 +1,326 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reserved peers and connection slots by role.
+//!
+//! Reserved nodes are always accepted and are never disconnected to make room
+//! for other peers, nor refused for a ban. Every other peer takes a slot of
+//! its role once the handshake tells its roles: full nodes (validators
+//! included), light clients and collators each have their own number of
+//! slots, so that a crowd of light clients can't take every connection of a
+//! node. In reserved-only mode no other peer is accepted at all.
+//!
+//! Before the handshake, connections of peers which aren't reserved are also
+//! limited in number: those opened by other nodes, those from a single IP
+//! address and those still handshaking. A flood of connections then can't
+//! exhaust the resources of a node.
+
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use parking_lot::RwLock;
+
+use network::{PeerId, SessionInfo};
+use service::Role;
+
+/// Connection slots for peers which aren't reserved. `None` is unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PeerSlots {
+	/// Slots for full nodes and validators.
+	pub full: Option<usize>,
+	/// Slots for light clients.
+	pub light: Option<usize>,
+	/// Slots for collators.
+	pub collator: Option<usize>,
+}
+
+// class of slots taken by a peer with the given roles.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SlotClass {
+	Full,
+	Light,
+	Collator,
+}
+
+impl SlotClass {
+	fn of(roles: Role) -> Self {
+		if roles.contains(Role::COLLATOR) {
+			SlotClass::Collator
+		} else if roles.contains(Role::LIGHT) {
+			SlotClass::Light
+		} else {
+			SlotClass::Full
+		}
+	}
+}
+
+impl PeerSlots {
+	fn of(&self, class: SlotClass) -> Option<usize> {
+		match class {
+			SlotClass::Full => self.full,
+			SlotClass::Light => self.light,
+			SlotClass::Collator => self.collator,
+		}
+	}
+}
+
+/// Limits on the connections of peers which aren't reserved, checked when
+/// they connect. `None` is unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConnectionLimits {
+	/// Connections opened by other nodes.
+	pub max_incoming: Option<usize>,
+	/// Connections with a single IP address.
+	pub max_per_ip: Option<usize>,
+	/// Connections whose handshake isn't done.
+	pub max_handshaking: Option<usize>,
+}
+
+/// An open connection, as far as the limits are concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Connection {
+	/// Whether the other node opened it.
+	pub incoming: bool,
+	/// IP address of the other node, if known.
+	pub ip: Option<IpAddr>,
+}
+
+impl Connection {
+	/// The connection of a session.
+	pub fn of(info: &SessionInfo) -> Self {
+		Connection {
+			incoming: !info.originated,
+			ip: info.remote_address.parse::<SocketAddr>().ok().map(|address| address.ip()),
+		}
+	}
+}
+
+impl ConnectionLimits {
+	/// Check a new connection against the connections open already, of which
+	/// `handshaking` haven't finished the handshake. Returns the limit it
+	/// exceeds, if any.
+	pub fn check<'a, I: IntoIterator<Item=&'a Connection>>(&self, new: &Connection, open: I, handshaking: usize) -> Result<(), &'static str> {
+		if self.max_handshaking.map_or(false, |max| handshaking >= max) {
+			return Err("too many pending handshakes");
+		}
+
+		let (mut incoming, mut same_ip) = (0, 0);
+		for connection in open {
+			if connection.incoming {
+				incoming += 1;
+			}
+			if new.ip.is_some() && connection.ip == new.ip {
+				same_ip += 1;
+			}
+		}
+		if new.incoming && self.max_incoming.map_or(false, |max| incoming >= max) {
+			return Err("too many incoming connections");
+		}
+		if new.ip.is_some() && self.max_per_ip.map_or(false, |max| same_ip >= max) {
+			return Err("too many connections from the same address");
+		}
+		Ok(())
+	}
+}
+
+/// Reserved peers, slots and connection limits.
+#[derive(Debug, Clone, Default)]
+pub struct PeerSetConfig {
+	/// Addresses of the reserved nodes, as `enode://<node id>@<address>`.
+	pub reserved_nodes: Vec<String>,
+	/// Accept reserved nodes only.
+	pub reserved_only: bool,
+	/// Connection slots for the other peers.
+	pub slots: PeerSlots,
+	/// Limits on the connections of the other peers.
+	pub limits: ConnectionLimits,
+}
+
+/// Node ID of an `enode://` address, as reported for connected peers.
+pub fn node_id_of(address: &str) -> String {
+	let address = address.trim_left_matches("enode://");
+	address.split('@').next().unwrap_or(address).to_lowercase()
+}
+
+struct State {
+	reserved: HashSet<String>,
+	reserved_only: bool,
+	slots: PeerSlots,
+	limits: ConnectionLimits,
+}
+
+/// Reserved nodes and slots, shared by the protocol and the network service.
+/// Changes apply to the peers connected at the next protocol tick.
+#[derive(Clone)]
+pub struct PeerSet {
+	state: Arc<RwLock<State>>,
+}
+
+impl PeerSet {
+	/// Create with the given configuration.
+	pub fn new(config: &PeerSetConfig) -> Self {
+		PeerSet {
+			state: Arc::new(RwLock::new(State {
+				reserved: config.reserved_nodes.iter().map(|a| node_id_of(a)).collect(),
+				reserved_only: config.reserved_only,
+				slots: config.slots,
+				limits: config.limits,
+			})),
+		}
+	}
+
+	/// Whether the node with the given ID is reserved.
+	pub fn is_reserved(&self, node_id: &str) -> bool {
+		self.state.read().reserved.contains(node_id)
+	}
+
+	/// Node IDs of the reserved nodes.
+	pub fn reserved_nodes(&self) -> Vec<String> {
+		let mut reserved: Vec<_> = self.state.read().reserved.iter().cloned().collect();
+		reserved.sort();
+		reserved
+	}
+
+	/// Reserve the node at the given address.
+	pub fn add_reserved(&self, address: &str) {
+		self.state.write().reserved.insert(node_id_of(address));
+	}
+
+	/// Remove the reservation of the node at the given address.
+	pub fn remove_reserved(&self, address: &str) {
+		self.state.write().reserved.remove(&node_id_of(address));
+	}
+
+	/// Whether only reserved nodes are accepted.
+	pub fn reserved_only(&self) -> bool {
+		self.state.read().reserved_only
+	}
+
+	/// Accept reserved nodes only, or any node.
+	pub fn set_reserved_only(&self, reserved_only: bool) {
+		self.state.write().reserved_only = reserved_only;
+	}
+
+	/// Connection slots for peers which aren't reserved.
+	pub fn slots(&self) -> PeerSlots {
+		self.state.read().slots
+	}
+
+	/// Change the connection slots for peers which aren't reserved.
+	pub fn set_slots(&self, slots: PeerSlots) {
+		self.state.write().slots = slots;
+	}
+
+	/// Limits on the connections of peers which aren't reserved.
+	pub fn limits(&self) -> ConnectionLimits {
+		self.state.read().limits
+	}
+
+	/// Change the limits on connections. Open connections are kept.
+	pub fn set_limits(&self, limits: ConnectionLimits) {
+		self.state.write().limits = limits;
+	}
+
+	/// Whether a node which just connected may start the handshake.
+	pub fn accepts(&self, node_id: Option<&str>) -> bool {
+		let state = self.state.read();
+		!state.reserved_only || node_id.map_or(false, |id| state.reserved.contains(id))
+	}
+
+	/// Whether a peer which isn't reserved may take a slot, given the peers
+	/// which aren't reserved and already hold one.
+	pub fn has_free_slot<'a, I: IntoIterator<Item=&'a Role>>(&self, roles: Role, connected: I) -> bool {
+		let state = self.state.read();
+		if state.reserved_only {
+			return false;
+		}
+		let class = SlotClass::of(roles);
+		match state.slots.of(class) {
+			None => true,
+			Some(slots) => connected.into_iter().filter(|r| SlotClass::of(**r) == class).count() < slots,
+		}
+	}
+
+	/// Peers which aren't reserved to disconnect to respect the reserved-only
+	/// mode and the slots. Peers earlier in `connected` keep their slots.
+	pub fn excess(&self, connected: &[(PeerId, Role)]) -> Vec<PeerId> {
+		let state = self.state.read();
+		if state.reserved_only {
+			return connected.iter().map(|&(id, _)| id).collect();
+		}
+
+		let mut taken = Vec::new();
+		connected.iter().filter_map(|&(id, roles)| {
+			let class = SlotClass::of(roles);
+			taken.push(class);
+			match state.slots.of(class) {
+				Some(slots) if taken.iter().filter(|c| **c == class).count() > slots => Some(id),
+				_ => None,
+			}
+		}).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const NODE: &str = "enode://ABCD@127.0.0.1:30333";
+
+	#[test]
+	fn reserved_nodes_skip_the_slots() {
+		let peer_set = PeerSet::new(&PeerSetConfig {
+			reserved_nodes: vec![NODE.into()],
+			reserved_only: false,
+			slots: PeerSlots { full: Some(1), light: Some(0), collator: None },
+			limits: Default::default(),
+		});
+		assert!(peer_set.is_reserved("abcd"));
+		assert!(peer_set.accepts(None));
+
+		assert!(peer_set.has_free_slot(Role::FULL, &[Role::LIGHT]));
+		assert!(!peer_set.has_free_slot(Role::VALIDATOR, &[Role::FULL]));
+		assert!(!peer_set.has_free_slot(Role::LIGHT, &[]));
+		assert!(peer_set.has_free_slot(Role::COLLATOR | Role::FULL, &[Role::FULL, Role::COLLATOR]));
+
+		let connected = [(1, Role::FULL), (2, Role::LIGHT), (3, Role::COLLATOR), (4, Role::VALIDATOR)];
+		assert_eq!(peer_set.excess(&connected), vec![2, 4]);
+
+		peer_set.set_reserved_only(true);
+		assert!(peer_set.accepts(Some("abcd")));
+		assert!(!peer_set.accepts(Some("ef01")));
+		assert!(!peer_set.accepts(None));
+		assert!(!peer_set.has_free_slot(Role::COLLATOR, &[]));
+		assert_eq!(peer_set.excess(&connected), vec![1, 2, 3, 4]);
+
+		peer_set.remove_reserved(NODE);
+		assert!(!peer_set.accepts(Some("abcd")));
+	}
+
+	#[test]
+	fn connections_over_the_limits_are_refused() {
+		let limits = ConnectionLimits { max_incoming: Some(2), max_per_ip: Some(1), max_handshaking: Some(3) };
+		let connection = |incoming, ip: &str| Connection { incoming, ip: ip.parse().ok() };
+		let open = [connection(true, "10.0.0.1"), connection(false, "10.0.0.2"), connection(true, "unknown")];
+
+		assert_eq!(limits.check(&connection(true, "10.0.0.3"), &open, 0), Err("too many incoming connections"));
+		assert_eq!(limits.check(&connection(false, "10.0.0.3"), &open, 0), Ok(()));
+		assert_eq!(limits.check(&connection(false, "10.0.0.2"), &open, 0), Err("too many connections from the same address"));
+		assert_eq!(limits.check(&connection(false, "unknown"), &open, 0), Ok(()));
+		assert_eq!(limits.check(&connection(false, "10.0.0.3"), &open, 3), Err("too many pending handshakes"));
+		assert_eq!(ConnectionLimits::default().check(&connection(true, "10.0.0.1"), &open, 100), Ok(()));
+	}
+}
@@ -0,0 +1,114 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Rate of block import during sync.
+//!
+//! Rates are measured over a sliding window, so that they follow changes in
+//! the speed of sync rather than averaging over its whole history.
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Duration over which import rates are measured.
+pub const RATE_WINDOW_SECS: u64 = 30;
+
+/// Blocks imported over the last few seconds.
+pub struct ImportProgress {
+	window: Duration,
+	started: Instant,
+	// time, number of blocks and bytes of each batch imported within the window.
+	batches: VecDeque<(Instant, usize, usize)>,
+}
+
+impl ImportProgress {
+	/// Start measuring at `now`.
+	pub fn new(now: Instant) -> Self {
+		ImportProgress {
+			window: Duration::from_secs(RATE_WINDOW_SECS),
+			started: now,
+			batches: VecDeque::new(),
+		}
+	}
+
+	/// Note a batch of imported blocks.
+	pub fn note_imported(&mut self, blocks: usize, bytes: usize, now: Instant) {
+		if blocks > 0 {
+			self.batches.push_back((now, blocks, bytes));
+		}
+		self.prune(now);
+	}
+
+	/// Blocks and bytes imported per second within the window up to `now`.
+	pub fn rates(&self, now: Instant) -> (f64, f64) {
+		let elapsed = cmp::min(self.window, now.duration_since(self.started));
+		let secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000.0;
+		if secs == 0.0 {
+			return (0.0, 0.0);
+		}
+
+		let (blocks, bytes) = self.batches.iter()
+			.filter(|&&(at, _, _)| now.duration_since(at) < self.window)
+			.fold((0, 0), |(blocks, bytes), &(_, b, s)| (blocks + b, bytes + s));
+		(blocks as f64 / secs, bytes as f64 / secs)
+	}
+
+	/// Time to import `remaining` blocks at the rate within the window up to `now`.
+	pub fn eta(&self, remaining: u64, now: Instant) -> Option<Duration> {
+		let (blocks_per_second, _) = self.rates(now);
+		if blocks_per_second > 0.0 {
+			Some(Duration::from_secs((remaining as f64 / blocks_per_second).ceil() as u64))
+		} else {
+			None
+		}
+	}
+
+	fn prune(&mut self, now: Instant) {
+		while self.batches.front().map_or(false, |&(at, _, _)| now.duration_since(at) >= self.window) {
+			self.batches.pop_front();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rates_follow_the_window() {
+		let start = Instant::now();
+		let at = |secs| start + Duration::from_secs(secs);
+		let mut progress = ImportProgress::new(start);
+
+		assert_eq!(progress.rates(start), (0.0, 0.0));
+		assert_eq!(progress.eta(100, at(1)), None);
+
+		// measured over the time since the start until the window is full.
+		progress.note_imported(10, 1000, at(1));
+		progress.note_imported(10, 1000, at(2));
+		assert_eq!(progress.rates(at(5)), (4.0, 400.0));
+		assert_eq!(progress.eta(100, at(5)), Some(Duration::from_secs(25)));
+
+		progress.note_imported(50, 2000, at(RATE_WINDOW_SECS));
+		assert_eq!(progress.rates(at(RATE_WINDOW_SECS + 1)), (2.0, 100.0));
+
+		// older batches drop out of the window.
+		progress.note_imported(0, 0, at(RATE_WINDOW_SECS + 2));
+		assert_eq!(progress.batches.len(), 1);
+		assert_eq!(progress.rates(at(2 * RATE_WINDOW_SECS)), (0.0, 0.0));
+		assert_eq!(progress.eta(100, at(2 * RATE_WINDOW_SECS)), None);
+	}
+}
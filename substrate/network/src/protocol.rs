@@ -16,30 +16,54 @@
 
 use std::collections::{HashMap, HashSet};
 use std::{mem, cmp};
+use std::net::IpAddr;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time;
 use parking_lot::{RwLock, Mutex};
 use serde_json;
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, Hashing, HashingFor};
 use runtime_primitives::generic::BlockId;
+use runtime_primitives::bft::Justification;
 use network::PeerId;
+use runtime_version::RuntimeVersion;
+use codec::Slicable;
+use primitives::hexdisplay::HexDisplay;
 
 use message::{self, Message};
 use message::generic::Message as GenericMessage;
-use sync::{ChainSync, Status as SyncStatus, SyncState};
+use sync::{ChainSync, Status as SyncStatus, SyncState, SyncMode};
+use sync_state::{SyncSnapshot, SYNC_STATE_KEY};
 use consensus::Consensus;
-use service::{Role, TransactionPool, BftMessageStream};
-use config::ProtocolConfig;
+use service::{Role, TransactionPool, BlockAnnounceValidator, AnnounceValidity, BlockVerifier, BftMessageStream, HeartbeatStream,
+	GossipMessageStream, DOT_PROTOCOL_ID};
+use config::{ProtocolConfig, AnnounceMode, ResponseLimits};
 use chain::Client;
 use on_demand::OnDemandService;
+use reputation::{self, Misbehavior, PeerReputation, PeerRecord};
+use clock::{self, PeerClocks};
+use bandwidth::{Bandwidth, BandwidthSinks, PeerBandwidth};
+use peer_set::{PeerSet, Connection};
+use failures::{ConnectionFailures, FailureReason, FailureState};
+use network_state::Latency;
 use io::SyncIo;
+use known_set::KnownSet;
 use error;
 
 const REQUEST_TIMEOUT_SEC: u64 = 40;
-const PROTOCOL_VERSION: u32 = 0;
+/// Maximum number of transaction hashes remembered per peer.
+const MAX_KNOWN_TRANSACTIONS: usize = 10240;
+/// Maximum number of transactions sent in a single message.
+const MAX_TRANSACTIONS_PER_MESSAGE: usize = 256;
+/// Maximum number of transactions sent to a peer per propagation. The rest are
+/// sent on the next propagation.
+const MAX_TRANSACTIONS_PER_PROPAGATION: usize = 1024;
+const PROTOCOL_VERSION: u32 = 1;
 
-// Maximum allowed entries in `BlockResponse`
-const MAX_BLOCK_DATA_RESPONSE: u32 = 128;
+// Room left in a `BlockResponse` for the message around the blocks, in bytes.
+const RESPONSE_ENVELOPE_BYTES: u64 = 64;
+// Maximum allowed entries in `StateResponse`
+const MAX_STATE_DATA_RESPONSE: u32 = 1024;
 
 // Lock must always be taken in order declared here.
 pub struct Protocol<B: BlockT> {
@@ -53,7 +77,32 @@ pub struct Protocol<B: BlockT> {
 	peers: RwLock<HashMap<PeerId, Peer<B>>>,
 	// Connected peers pending Status message.
 	handshaking_peers: RwLock<HashMap<PeerId, time::Instant>>,
+	// Open connections, handshaking or not, checked against the connection limits.
+	connections: RwLock<HashMap<PeerId, Connection>>,
 	transaction_pool: Arc<TransactionPool<B>>,
+	// Checks of announced headers, before their blocks are downloaded.
+	block_announce_validator: Option<Arc<BlockAnnounceValidator<B>>>,
+	// Checks of downloaded blocks, before they are imported.
+	block_verifier: Option<Arc<BlockVerifier<B>>>,
+	// Reputation of peers by node ID, shared by sync and the handshake.
+	reputation: PeerReputation,
+	// Time after which sync stops importing, while shutting down.
+	import_deadline: Mutex<Option<time::Instant>>,
+	// Whether downloaded blocks are kept unimported until import resumes.
+	import_paused: AtomicBool,
+	// Offsets of the clocks of connected peers.
+	clocks: Mutex<PeerClocks>,
+	// Bytes exchanged with connected peers and their rate limits.
+	bandwidth: Mutex<Bandwidth>,
+	bandwidth_sinks: BandwidthSinks,
+	// Peers whose connection the protocol is closing, not to count them as dropped.
+	closing: Mutex<HashSet<PeerId>>,
+	// Connections which failed since the node started.
+	failures: Mutex<ConnectionFailures>,
+	// Reserved nodes and connection slots, shared with the service.
+	peer_set: PeerSet,
+	// Hashes of trusted blocks by number.
+	checkpoints: HashMap<u64, B::Hash>,
 }
 
 /// Syncing status and statistics
@@ -77,16 +126,42 @@ struct Peer<B: BlockT> {
 	best_hash: B::Hash,
 	/// Peer best block number
 	best_number: <B::Header as HeaderT>::Number,
+	/// Peer latest finalized block number and hash, if reported
+	finalized: Option<(<B::Header as HeaderT>::Number, B::Hash)>,
+	/// Runtime spec name and version reported in the handshake
+	spec: (String, u32),
+	/// Whether the peer runs another spec version and is only used to serve history
+	historical: bool,
 	/// Pending block request if any
 	block_request: Option<message::BlockRequest<B>>,
+	/// Pending state request if any
+	state_request: Option<message::StateRequest<B::Hash>>,
 	/// Request timestamp
 	request_timestamp: Option<time::Instant>,
-	/// Holds a set of transactions known to this peer.
-	known_transactions: HashSet<B::Hash>,
+	/// Whether the pending request wasn't answered in time
+	stalled: bool,
+	/// Number of requests in a row which weren't answered in time
+	stalls: u32,
+	/// Holds a set of the transactions most recently known to this peer.
+	known_transactions: KnownSet<B::Hash>,
 	/// Holds a set of blocks known to this peer.
 	known_blocks: HashSet<B::Hash>,
 	/// Request counter,
 	next_request_id: message::RequestId,
+	/// Whether the peer accepts blocks announced by hash only
+	hash_announcements: bool,
+	/// Whether the peer is a reserved node, which doesn't take a slot
+	reserved: bool,
+	/// Average round-trip time of requests
+	latency: Latency,
+	/// Limits on the block responses exchanged with the peer
+	response_limits: ResponseLimits,
+	/// Whether the peer answers pings
+	keep_alive: bool,
+	/// Time of the latest packet from the peer
+	last_received: time::Instant,
+	/// Time the unanswered ping was sent, if any
+	ping_sent: Option<time::Instant>,
 }
 
 #[derive(Debug)]
@@ -99,6 +174,21 @@ pub struct PeerInfo<B: BlockT> {
 	pub best_hash: B::Hash,
 	/// Peer best block number
 	pub best_number: <B::Header as HeaderT>::Number,
+	/// Peer latest finalized block number and hash, if reported
+	pub finalized: Option<(<B::Header as HeaderT>::Number, B::Hash)>,
+	/// Runtime spec name reported by the peer
+	pub spec_name: String,
+	/// Runtime spec version reported by the peer
+	pub spec_version: u32,
+	/// Whether the peer runs a different runtime spec version. Such peers
+	/// serve block requests but are not synced from or gossiped with.
+	pub historical: bool,
+	/// Bytes exchanged with the peer since it connected.
+	pub bandwidth: PeerBandwidth,
+	/// Average round-trip time of requests to the peer, in milliseconds.
+	pub average_latency_ms: Option<u64>,
+	/// Whether the peer is a reserved node.
+	pub reserved: bool,
 }
 
 impl<B: BlockT> Protocol<B> where
@@ -109,10 +199,33 @@ impl<B: BlockT> Protocol<B> where
 		config: ProtocolConfig,
 		chain: Arc<Client<B>>,
 		on_demand: Option<Arc<OnDemandService<B>>>,
-		transaction_pool: Arc<TransactionPool<B>>
+		transaction_pool: Arc<TransactionPool<B>>,
+		block_announce_validator: Option<Arc<BlockAnnounceValidator<B>>>,
+		block_verifier: Option<Arc<BlockVerifier<B>>>,
 	) -> error::Result<Self>  {
 		let info = chain.info()?;
-		let sync = ChainSync::new(config.roles, &info);
+		let sync_mode = config.sync_mode.unwrap_or_else(|| SyncMode::for_role(config.roles));
+		let state_target = config.state_sync_target.as_ref().and_then(|t| Slicable::decode(&mut &t[..]));
+		let snapshot = match chain.get_aux(SYNC_STATE_KEY) {
+			Ok(data) => data.and_then(|data| SyncSnapshot::decode(&data)),
+			Err(e) => {
+				debug!(target: "sync", "Error reading sync state: {:?}", e);
+				None
+			},
+		};
+		let sync = ChainSync::new(sync_mode, &info, config.queue_limits, state_target, snapshot);
+		let reputation = PeerReputation::new(config.reputation);
+		let bandwidth = Bandwidth::new(config.bandwidth);
+		let peer_set = PeerSet::new(&config.peer_set);
+		let checkpoints = config.checkpoints.iter().filter_map(|&(number, ref hash)| {
+			match Slicable::decode(&mut &hash[..]) {
+				Some(hash) => Some((number, hash)),
+				None => {
+					warn!(target: "sync", "Ignoring invalid checkpoint hash of block #{}", number);
+					None
+				}
+			}
+		}).collect();
 		let protocol = Protocol {
 			config: config,
 			chain: chain,
@@ -122,7 +235,20 @@ impl<B: BlockT> Protocol<B> where
 			consensus: Mutex::new(Consensus::new()),
 			peers: RwLock::new(HashMap::new()),
 			handshaking_peers: RwLock::new(HashMap::new()),
+			connections: RwLock::new(HashMap::new()),
 			transaction_pool: transaction_pool,
+			block_announce_validator: block_announce_validator,
+			block_verifier: block_verifier,
+			reputation: reputation,
+			import_deadline: Mutex::new(None),
+			import_paused: AtomicBool::new(false),
+			clocks: Mutex::new(PeerClocks::default()),
+			bandwidth: Mutex::new(bandwidth),
+			bandwidth_sinks: BandwidthSinks::default(),
+			closing: Mutex::new(HashSet::new()),
+			failures: Mutex::new(ConnectionFailures::default()),
+			peer_set: peer_set,
+			checkpoints: checkpoints,
 		};
 		Ok(protocol)
 	}
@@ -134,16 +260,29 @@ impl<B: BlockT> Protocol<B> where
 		ProtocolStatus {
 			sync: sync.status(),
 			num_peers: peers.values().count(),
-			num_active_peers: peers.values().filter(|p| p.block_request.is_some()).count(),
+			num_active_peers: peers.values().filter(|p| p.block_request.is_some() || p.state_request.is_some()).count(),
 		}
 	}
 
 	pub fn handle_packet(&self, io: &mut SyncIo, peer_id: PeerId, data: &[u8]) {
+		self.bandwidth_sinks.on_received(DOT_PROTOCOL_ID, data.len());
+		if !self.bandwidth.lock().on_received(peer_id, data.len(), time::Instant::now()) {
+			debug!(target: "sync", "Peer {} exceeds its inbound bandwidth limit", peer_id);
+			self.disable_peer_for(io, peer_id, Misbehavior::Spam);
+			return;
+		}
+
+		// any packet shows the connection is alive.
+		if let Some(ref mut peer) = self.peers.write().get_mut(&peer_id) {
+			peer.last_received = time::Instant::now();
+			peer.ping_sent = None;
+		}
+
 		let message: Message<B> = match serde_json::from_slice(data) {
 			Ok(m) => m,
 			Err(e) => {
 				debug!("Invalid packet from {}: {}", peer_id, e);
-				io.disable_peer(peer_id);
+				self.disable_peer_for(io, peer_id, Misbehavior::ProtocolViolation);
 				return;
 			}
 		};
@@ -152,20 +291,26 @@ impl<B: BlockT> Protocol<B> where
 			GenericMessage::Status(s) => self.on_status_message(io, peer_id, s),
 			GenericMessage::BlockRequest(r) => self.on_block_request(io, peer_id, r),
 			GenericMessage::BlockResponse(r) => {
-				let request = {
+				let (request, limits) = {
 					let mut peers = self.peers.write();
 					if let Some(ref mut peer) = peers.get_mut(&peer_id) {
-						peer.request_timestamp = None;
+						if let Some(sent) = peer.request_timestamp.take() {
+							peer.latency.note(sent.elapsed());
+						}
+						if !mem::replace(&mut peer.stalled, false) {
+							peer.stalls = 0;
+						}
 						match mem::replace(&mut peer.block_request, None) {
-							Some(r) => r,
+							Some(request) => (request, peer.response_limits),
 							None => {
 								debug!("Unexpected response packet from {}", peer_id);
-								io.disable_peer(peer_id);
+								self.disable_peer_for(io, peer_id, Misbehavior::ProtocolViolation);
 								return;
 							}
 						}
 					} else {
 						debug!("Unexpected packet from {}", peer_id);
+						self.note_failure(io, peer_id, FailureReason::Misbehavior);
 						io.disable_peer(peer_id);
 						return;
 					}
@@ -174,15 +319,69 @@ impl<B: BlockT> Protocol<B> where
 					trace!(target: "sync", "Ignoring mismatched response packet from {} (expected {} got {})", peer_id, request.id, r.id);
 					return;
 				}
+				let max_blocks = cmp::min(request.max.unwrap_or(limits.max_blocks), limits.max_blocks) as usize;
+				if r.blocks.len() > max_blocks || data.len() as u64 > limits.max_bytes {
+					debug!(target: "sync", "Oversize response from {}: {} blocks in {} bytes", peer_id, r.blocks.len(), data.len());
+					self.disable_peer_for(io, peer_id, Misbehavior::ProtocolViolation);
+					return;
+				}
 				self.on_block_response(io, peer_id, request, r);
 			},
 			GenericMessage::BlockAnnounce(announce) => {
 				self.on_block_announce(io, peer_id, announce);
 			},
+			GenericMessage::BlockHashAnnounce(announce) => {
+				self.on_block_hash_announce(io, peer_id, announce);
+			},
 			GenericMessage::BftMessage(m) => self.on_bft_message(io, peer_id, m, HashingFor::<B>::hash(data)),
 			GenericMessage::Transactions(m) => self.on_transactions(io, peer_id, m),
 			GenericMessage::RemoteCallRequest(request) => self.on_remote_call_request(io, peer_id, request),
 			GenericMessage::RemoteCallResponse(response) => self.on_remote_call_response(io, peer_id, response),
+			GenericMessage::RemoteHeaderRequest(request) => self.on_remote_header_request(io, peer_id, request),
+			GenericMessage::RemoteHeaderResponse(response) => self.on_remote_header_response(io, peer_id, response),
+			GenericMessage::RemoteBodyRequest(request) => self.on_remote_body_request(io, peer_id, request),
+			GenericMessage::RemoteBodyResponse(response) => self.on_remote_body_response(io, peer_id, response),
+			GenericMessage::RemoteReadRequest(request) => self.on_remote_read_request(io, peer_id, request),
+			GenericMessage::RemoteReadResponse(response) => self.on_remote_read_response(io, peer_id, response),
+			GenericMessage::Heartbeat(h) => self.on_heartbeat(io, peer_id, h, HashingFor::<B>::hash(data)),
+			GenericMessage::Gossip(m) => self.on_gossip_message(io, peer_id, m, HashingFor::<B>::hash(data)),
+			GenericMessage::StateRequest(request) => self.on_state_request(io, peer_id, request),
+			GenericMessage::StateResponse(r) => {
+				let request = {
+					let mut peers = self.peers.write();
+					if let Some(ref mut peer) = peers.get_mut(&peer_id) {
+						if let Some(sent) = peer.request_timestamp.take() {
+							peer.latency.note(sent.elapsed());
+						}
+						if !mem::replace(&mut peer.stalled, false) {
+							peer.stalls = 0;
+						}
+						match mem::replace(&mut peer.state_request, None) {
+							Some(r) => r,
+							None => {
+								debug!("Unexpected state response packet from {}", peer_id);
+								self.disable_peer_for(io, peer_id, Misbehavior::ProtocolViolation);
+								return;
+							}
+						}
+					} else {
+						debug!("Unexpected packet from {}", peer_id);
+						self.note_failure(io, peer_id, FailureReason::Misbehavior);
+						io.disable_peer(peer_id);
+						return;
+					}
+				};
+				if request.id != r.id {
+					trace!(target: "sync", "Ignoring mismatched state response packet from {} (expected {} got {})", peer_id, request.id, r.id);
+					return;
+				}
+				self.on_state_response(io, peer_id, r);
+			},
+			GenericMessage::Goodbye(misbehavior) => {
+				info!(target: "sync", "Disconnected by {} ({}) for {}", peer_id, io.peer_info(peer_id), misbehavior);
+			},
+			GenericMessage::Ping => self.send_message(io, peer_id, GenericMessage::Pong),
+			GenericMessage::Pong => {},
 		}
 	}
 
@@ -197,11 +396,26 @@ impl<B: BlockT> Protocol<B> where
 					peer.request_timestamp = Some(time::Instant::now());
 				}
 			},
+			&mut GenericMessage::StateRequest(ref mut r) => {
+				let mut peers = self.peers.write();
+				if let Some(ref mut peer) = peers.get_mut(&peer_id) {
+					r.id = peer.next_request_id;
+					peer.next_request_id = peer.next_request_id + 1;
+					peer.state_request = Some(r.clone());
+					peer.request_timestamp = Some(time::Instant::now());
+				}
+			},
 			_ => (),
 		}
 		let data = serde_json::to_vec(&message).expect("Serializer is infallible; qed");
+		if !self.bandwidth.lock().on_send(peer_id, data.len(), time::Instant::now()) {
+			trace!(target: "sync", "Not sending {} bytes to {}: outbound bandwidth limit reached", data.len(), peer_id);
+			return;
+		}
+		self.bandwidth_sinks.on_sent(DOT_PROTOCOL_ID, data.len());
 		if let Err(e) = io.send(peer_id, data) {
 			debug!(target:"sync", "Error sending message: {:?}", e);
+			self.note_failure(io, peer_id, FailureReason::SendError);
 			io.disconnect_peer(peer_id);
 		}
 	}
@@ -211,9 +425,124 @@ impl<B: BlockT> Protocol<B> where
 		HashingFor::<B>::hash(&data)
 	}
 
+	/// Change the reputation of a peer. Peers whose reputation drops too low
+	/// are banned, and disabled when they connect until the ban expires.
+	pub fn report_peer(&self, io: &mut SyncIo, peer_id: PeerId, change: i32) {
+		if let Some(node_id) = io.peer_node_id(peer_id) {
+			self.report_node(&node_id, change);
+		}
+	}
+
+	/// Punish the node which sent some data, and disable the peer it was
+	/// received from. Peer IDs are reused once peers disconnect, so where the
+	/// node ID is known the peer is only disabled if it still belongs to the node.
+	pub fn punish_origin(&self, io: &mut SyncIo, origin: PeerId, origin_node: Option<&str>, misbehavior: Misbehavior) {
+		let node_id = match origin_node {
+			Some(node_id) => node_id,
+			None => {
+				self.say_goodbye(io, origin, misbehavior);
+				return;
+			}
+		};
+
+		self.report_misbehavior(node_id, misbehavior);
+		if io.peer_node_id(origin).as_ref().map(String::as_str) == Some(node_id) {
+			self.say_goodbye(io, origin, misbehavior);
+		} else {
+			debug!(target: "sync", "Peer {} no longer belongs to punished node {}", origin, node_id);
+		}
+	}
+
+	/// Punish a peer for misbehaving, telling it why it is disabled.
+	pub fn disable_peer_for(&self, io: &mut SyncIo, peer_id: PeerId, misbehavior: Misbehavior) {
+		if let Some(node_id) = io.peer_node_id(peer_id) {
+			self.report_misbehavior(&node_id, misbehavior);
+		}
+		self.say_goodbye(io, peer_id, misbehavior);
+	}
+
+	/// Nodes which have misbehaved, with their reputation and last misbehavior.
+	pub fn misbehaving_peers(&self) -> Vec<PeerRecord> {
+		self.reputation.misbehaving(time::Instant::now())
+	}
+
+	fn say_goodbye(&self, io: &mut SyncIo, peer_id: PeerId, misbehavior: Misbehavior) {
+		debug!(target: "sync", "Disabling {} for {}", peer_id, misbehavior);
+		self.send_message(io, peer_id, GenericMessage::Goodbye(misbehavior));
+		self.note_failure(io, peer_id, FailureReason::Misbehavior);
+		io.disable_peer(peer_id);
+	}
+
+	// remote address of a peer, while it's connected.
+	fn remote_ip(&self, io: &mut SyncIo, peer_id: PeerId) -> Option<IpAddr> {
+		match self.connections.read().get(&peer_id) {
+			Some(connection) => connection.ip,
+			None => io.peer_session_info(peer_id).and_then(|info| Connection::of(&info).ip),
+		}
+	}
+
+	// note why the protocol closes the connection of a peer.
+	fn note_failure(&self, io: &mut SyncIo, peer_id: PeerId, reason: FailureReason) {
+		let ip = self.remote_ip(io, peer_id);
+		debug!(target: "sync", "Closing connection with {} ({:?}): {:?}", peer_id, ip, reason);
+		self.closing.lock().insert(peer_id);
+		self.failures.lock().note(reason, ip);
+	}
+
+	/// Connections which failed since the node started.
+	pub fn failures(&self) -> FailureState {
+		self.failures.lock().state()
+	}
+
+	fn report_node(&self, node_id: &str, change: i32) {
+		if self.reputation.report(node_id, change, time::Instant::now()) {
+			info!(target: "sync", "Banned node {}", node_id);
+		}
+	}
+
+	fn report_misbehavior(&self, node_id: &str, misbehavior: Misbehavior) {
+		if self.reputation.report_misbehavior(node_id, misbehavior, time::Instant::now()) {
+			info!(target: "sync", "Banned node {} for {}", node_id, misbehavior);
+		}
+	}
+
 	/// Called when a new peer is connected
 	pub fn on_peer_connected(&self, io: &mut SyncIo, peer_id: PeerId) {
 		trace!(target: "sync", "Connected {}: {}", peer_id, io.peer_info(peer_id));
+		// peer IDs are reused, so this is a new session.
+		self.closing.lock().remove(&peer_id);
+		let node_id = io.peer_node_id(peer_id);
+		if !self.peer_set.accepts(node_id.as_ref().map(String::as_str)) {
+			debug!(target: "sync", "Refusing {}: only reserved nodes are accepted", peer_id);
+			self.note_failure(io, peer_id, FailureReason::NotReserved);
+			io.disconnect_peer(peer_id);
+			return;
+		}
+		let reserved = node_id.as_ref().map_or(false, |id| self.peer_set.is_reserved(id));
+		if let Some(node_id) = node_id {
+			// reserved nodes are kept connected whatever their reputation.
+			if !reserved && self.reputation.is_banned(&node_id, time::Instant::now()) {
+				debug!(target: "sync", "Refusing banned peer {} ({})", peer_id, node_id);
+				self.note_failure(io, peer_id, FailureReason::Banned);
+				io.disable_peer(peer_id);
+				return;
+			}
+		}
+		if let Some(connection) = io.peer_session_info(peer_id).map(|info| Connection::of(&info)) {
+			// reserved nodes aren't limited, but count against the limits of others.
+			let handshaking = self.handshaking_peers.read().len();
+			let mut connections = self.connections.write();
+			if !reserved {
+				if let Err(limit) = self.peer_set.limits().check(&connection, connections.values(), handshaking) {
+					debug!(target: "sync", "Refusing {}: {}", peer_id, limit);
+					drop(connections);
+					self.note_failure(io, peer_id, FailureReason::ConnectionLimit);
+					io.disconnect_peer(peer_id);
+					return;
+				}
+			}
+			connections.insert(peer_id, connection);
+		}
 		self.handshaking_peers.write().insert(peer_id, time::Instant::now());
 		self.send_status(io, peer_id);
 	}
@@ -221,13 +550,25 @@ impl<B: BlockT> Protocol<B> where
 	/// Called by peer when it is disconnecting
 	pub fn on_peer_disconnected(&self, io: &mut SyncIo, peer: PeerId) {
 		trace!(target: "sync", "Disconnecting {}: {}", peer, io.peer_info(peer));
-		let removed = {
+		let (removed, dropped) = {
 			let mut peers = self.peers.write();
 			let mut handshaking_peers = self.handshaking_peers.write();
-			handshaking_peers.remove(&peer);
-			peers.remove(&peer).is_some()
+			let handshaking = handshaking_peers.remove(&peer).is_some();
+			let connection = self.connections.write().remove(&peer);
+			let removed = peers.remove(&peer).is_some();
+			let known = removed || handshaking || connection.is_some();
+			(removed, if known { Some(connection.and_then(|c| c.ip)) } else { None })
 		};
+		// connections closed by the protocol are counted already.
+		if !self.closing.lock().remove(&peer) {
+			if let Some(ip) = dropped {
+				debug!(target: "sync", "Connection with {} ({:?}) dropped", peer, ip);
+				self.failures.lock().note(FailureReason::Dropped, ip);
+			}
+		}
+		self.bandwidth.lock().remove(peer);
 		if removed {
+			self.clocks.lock().remove(peer);
 			self.consensus.lock().peer_disconnected(io, self, peer);
 			self.sync.write().peer_disconnected(io, self, peer);
 			self.on_demand.as_ref().map(|s| s.on_disconnect(peer));
@@ -241,7 +582,9 @@ impl<B: BlockT> Protocol<B> where
 			message::FromBlock::Hash(h) => BlockId::Hash(h),
 			message::FromBlock::Number(n) => BlockId::Number(n),
 		};
-		let max = cmp::min(request.max.unwrap_or(u32::max_value()), MAX_BLOCK_DATA_RESPONSE) as usize;
+		let limits = self.response_limits(peer);
+		let max = cmp::min(request.max.unwrap_or(u32::max_value()), limits.max_blocks) as usize;
+		let mut size = RESPONSE_ENVELOPE_BYTES;
 		// TODO: receipts, etc.
 		let (mut get_header, mut get_body, mut get_justification) = (false, false, false);
 		for a in request.fields {
@@ -267,6 +610,12 @@ impl<B: BlockT> Protocol<B> where
 				message_queue: None,
 				justification: if get_justification { self.chain.justification(&BlockId::Hash(hash)).unwrap_or(None) } else { None },
 			};
+			// blocks are separated by a comma in the response.
+			size += serde_json::to_vec(&block_data).map_or(u64::max_value(), |data| data.len() as u64) + 1;
+			if size > limits.max_bytes {
+				trace!(target: "sync", "BlockResponse to {} is cut to {} blocks to fit in {} bytes", peer, blocks.len(), limits.max_bytes);
+				break;
+			}
 			blocks.push(block_data);
 			match request.direction {
 				message::Direction::Ascending => id = BlockId::Number(number + 1),
@@ -292,6 +641,38 @@ impl<B: BlockT> Protocol<B> where
 		self.sync.write().on_block_data(io, self, peer, request, response);
 	}
 
+	fn on_state_request(&self, io: &mut SyncIo, peer: PeerId, request: message::StateRequest<B::Hash>) {
+		trace!(target: "sync", "StateRequest {} from {}: block {} max {}", request.id, peer, request.block, request.max);
+		let max = cmp::min(request.max, MAX_STATE_DATA_RESPONSE) as usize;
+		let (entries, complete) = match self.chain.state_entries(&request.block) {
+			Ok(entries) => {
+				let mut entries: Vec<_> = entries.into_iter()
+					.filter(|&(ref key, _)| request.start.is_empty() || *key > request.start)
+					.collect();
+				entries.sort();
+				let complete = entries.len() <= max;
+				entries.truncate(max);
+				(entries, complete)
+			},
+			Err(e) => {
+				trace!(target: "sync", "StateRequest {} from {} for block {} failed with: {}", request.id, peer, request.block, e);
+				(Vec::new(), false)
+			}
+		};
+
+		trace!(target: "sync", "Sending StateResponse with {} entries", entries.len());
+		self.send_message(io, peer, GenericMessage::StateResponse(message::StateResponse {
+			id: request.id,
+			entries,
+			complete,
+		}))
+	}
+
+	fn on_state_response(&self, io: &mut SyncIo, peer: PeerId, response: message::StateResponse) {
+		trace!(target: "sync", "StateResponse {} from {} with {} entries", response.id, peer, response.entries.len());
+		self.sync.write().on_state_data(io, self, peer, response);
+	}
+
 	fn on_bft_message(&self, io: &mut SyncIo, peer: PeerId, message: message::LocalizedBftMessage<B>, hash: B::Hash) {
 		trace!(target: "sync", "BFT message from {}: {:?}", peer, message);
 		self.consensus.lock().on_bft_message(io, self, peer, message, hash);
@@ -307,24 +688,106 @@ impl<B: BlockT> Protocol<B> where
 		self.consensus.lock().bft_messages(parent_hash)
 	}
 
+	fn on_gossip_message(&self, io: &mut SyncIo, peer: PeerId, message: message::GossipMessage<B>, hash: B::Hash) {
+		trace!(target: "sync", "Gossip message from {} under topic {}", peer, message.topic);
+		self.consensus.lock().on_gossip_message(io, self, peer, message, hash);
+	}
+
+	/// See `ConsensusService` trait.
+	pub fn send_gossip(&self, io: &mut SyncIo, topic: B::Hash, data: Vec<u8>) {
+		self.consensus.lock().send_gossip(io, self, topic, data)
+	}
+
+	/// See `ConsensusService` trait.
+	pub fn gossip_messages(&self, topic: B::Hash) -> GossipMessageStream {
+		self.consensus.lock().gossip_messages(topic)
+	}
+
+	fn on_heartbeat(&self, io: &mut SyncIo, peer: PeerId, heartbeat: message::Heartbeat, hash: B::Hash) {
+		trace!(target: "sync", "Heartbeat from {}", peer);
+		self.consensus.lock().on_heartbeat(io, self, peer, heartbeat, hash);
+	}
+
+	/// See `ConsensusService` trait.
+	pub fn send_heartbeat(&self, io: &mut SyncIo, heartbeat: message::Heartbeat) {
+		self.consensus.lock().send_heartbeat(io, self, heartbeat)
+	}
+
+	/// See `ConsensusService` trait.
+	pub fn heartbeats(&self) -> HeartbeatStream {
+		self.consensus.lock().heartbeats()
+	}
+
 	/// Perform time based maintenance.
 	pub fn tick(&self, io: &mut SyncIo) {
 		self.maintain_peers(io);
+		self.sync.write().tick(io, self);
 		self.on_demand.as_ref().map(|s| s.maintain_peers(io));
 		self.consensus.lock().collect_garbage(None);
+		self.reputation.collect_garbage(time::Instant::now());
 	}
 
 	fn maintain_peers(&self, io: &mut SyncIo) {
 		let tick = time::Instant::now();
 		let mut aborting = Vec::new();
+		let mut stalled = Vec::new();
+		let mut idle = Vec::new();
+		{
+			let mut peers = self.peers.write();
+			for (peer_id, peer) in peers.iter_mut() {
+				let late = peer.request_timestamp.map_or(false, |t| tick - t >= self.config.request_timeout);
+				if late && !peer.stalled {
+					peer.stalled = true;
+					peer.stalls += 1;
+					stalled.push((*peer_id, peer.stalls));
+				}
+
+				if !peer.keep_alive {
+					continue;
+				}
+				// packets may be handled after `tick` was taken, so no instant is subtracted from it.
+				match peer.ping_sent {
+					Some(sent) if sent + self.config.keep_alive_timeout <= tick => {
+						debug!(target: "sync", "Peer {} didn't answer a ping, disconnecting", peer_id);
+						self.note_failure(io, *peer_id, FailureReason::KeepAliveTimeout);
+						io.disconnect_peer(*peer_id);
+						aborting.push(*peer_id);
+					},
+					None if peer.last_received + self.config.keep_alive_interval <= tick => {
+						peer.ping_sent = Some(tick);
+						idle.push(*peer_id);
+					},
+					_ => {},
+				}
+			}
+		}
+		for peer_id in idle {
+			trace!(target: "sync", "Pinging idle peer {}", peer_id);
+			self.send_message(io, peer_id, GenericMessage::Ping);
+		}
+		for (peer_id, stalls) in stalled {
+			trace!(target: "sync", "Request to {} stalled ({} in a row)", peer_id, stalls);
+			if stalls > 1 {
+				self.report_peer(io, peer_id, reputation::STALLED);
+			}
+			self.sync.write().on_peer_stalled(io, self, peer_id);
+		}
 		{
 			let peers = self.peers.read();
 			let handshaking_peers = self.handshaking_peers.read();
 			for (peer_id, timestamp) in peers.iter()
-				.filter_map(|(id, peer)| peer.request_timestamp.as_ref().map(|r| (id, r)))
-				.chain(handshaking_peers.iter()) {
+				.filter_map(|(id, peer)| peer.request_timestamp.as_ref().map(|r| (id, r))) {
 				if (tick - *timestamp).as_secs() > REQUEST_TIMEOUT_SEC {
 					trace!(target: "sync", "Timeout {}", peer_id);
+					self.note_failure(io, *peer_id, FailureReason::RequestTimeout);
+					io.disconnect_peer(*peer_id);
+					aborting.push(*peer_id);
+				}
+			}
+			for (peer_id, connected) in handshaking_peers.iter() {
+				if *connected + self.config.handshake_timeout <= tick {
+					trace!(target: "sync", "Handshake timeout {}", peer_id);
+					self.note_failure(io, *peer_id, FailureReason::HandshakeTimeout);
 					io.disconnect_peer(*peer_id);
 					aborting.push(*peer_id);
 				}
@@ -333,6 +796,36 @@ impl<B: BlockT> Protocol<B> where
 		for p in aborting {
 			self.on_peer_disconnected(io, p);
 		}
+
+		// reserved nodes and slots may have changed since peers connected.
+		let excess = {
+			let mut peers = self.peers.write();
+			let mut connected = Vec::new();
+			for (peer_id, peer) in peers.iter_mut() {
+				peer.reserved = io.peer_node_id(*peer_id).map_or(false, |id| self.peer_set.is_reserved(&id));
+				if !peer.reserved {
+					connected.push((*peer_id, peer.roles));
+				}
+			}
+			connected.sort_by_key(|&(peer_id, _)| peer_id);
+			self.peer_set.excess(&connected)
+		};
+		for peer_id in excess {
+			debug!(target: "sync", "Disconnecting {} to free its slot", peer_id);
+			self.note_failure(io, peer_id, FailureReason::NoFreeSlot);
+			io.disconnect_peer(peer_id);
+			self.on_peer_disconnected(io, peer_id);
+		}
+	}
+
+	/// Reserved nodes and connection slots.
+	pub fn peer_set(&self) -> PeerSet {
+		self.peer_set.clone()
+	}
+
+	/// Counters of the bytes exchanged over each protocol.
+	pub fn bandwidth_sinks(&self) -> BandwidthSinks {
+		self.bandwidth_sinks.clone()
 	}
 
 	pub fn peer_info(&self, peer: PeerId) -> Option<PeerInfo<B>> {
@@ -342,10 +835,38 @@ impl<B: BlockT> Protocol<B> where
 				protocol_version: p.protocol_version,
 				best_hash: p.best_hash,
 				best_number: p.best_number,
+				finalized: p.finalized,
+				spec_name: p.spec.0.clone(),
+				spec_version: p.spec.1,
+				historical: p.historical,
+				bandwidth: self.bandwidth.lock().usage(peer).unwrap_or_default(),
+				average_latency_ms: p.latency.average_ms(),
+				reserved: p.reserved,
 			}
 		})
 	}
 
+	// limits on the block responses exchanged with a peer.
+	fn response_limits(&self, peer: PeerId) -> ResponseLimits {
+		self.peers.read().get(&peer).map_or(self.config.response_limits, |p| p.response_limits)
+	}
+
+	/// Maximum number of blocks to request from a peer at once.
+	pub fn max_blocks_per_request(&self, peer: PeerId) -> usize {
+		self.response_limits(peer).max_blocks as usize
+	}
+
+	fn runtime_version(&self) -> Option<RuntimeVersion> {
+		let info = self.chain.info().ok()?;
+		match self.chain.runtime_version(&BlockId::Hash(info.chain.best_hash)) {
+			Ok(version) => Some(version),
+			Err(e) => {
+				debug!(target: "sync", "Error reading runtime version: {:?}", e);
+				None
+			}
+		}
+	}
+
 	/// Called by peer to report status
 	fn on_status_message(&self, io: &mut SyncIo, peer_id: PeerId, status: message::Status<B>) {
 		trace!(target: "sync", "New peer {} {:?}", peer_id, status);
@@ -354,7 +875,8 @@ impl<B: BlockT> Protocol<B> where
 			return;
 		}
 
-		{
+		let local_version = self.runtime_version();
+		let historical = {
 			let mut peers = self.peers.write();
 			let mut handshaking_peers = self.handshaking_peers.write();
 			if peers.contains_key(&peer_id) {
@@ -362,30 +884,78 @@ impl<B: BlockT> Protocol<B> where
 				return;
 			}
 			if status.genesis_hash != self.genesis_hash {
+				self.note_failure(io, peer_id, FailureReason::Incompatible);
 				io.disable_peer(peer_id);
 				trace!(target: "sync", "Peer {} genesis hash mismatch (ours: {}, theirs: {})", peer_id, self.genesis_hash, status.genesis_hash);
 				return;
 			}
 			if status.version != PROTOCOL_VERSION {
+				self.note_failure(io, peer_id, FailureReason::Incompatible);
 				io.disable_peer(peer_id);
 				trace!(target: "sync", "Peer {} unsupported eth protocol ({})", peer_id, status.version);
 				return;
 			}
 
+			let mut historical = false;
+			if let Some(ref local) = local_version {
+				if status.spec_name != *local.spec_name {
+					self.note_failure(io, peer_id, FailureReason::Incompatible);
+					io.disable_peer(peer_id);
+					info!(target: "sync", "Peer {} runs incompatible runtime {} (ours: {})", peer_id, status.spec_name, local.spec_name);
+					return;
+				}
+				if status.spec_version != local.spec_version {
+					info!(target: "sync", "Peer {} runs {} spec version {} (ours: {}), using it for historical blocks only",
+						peer_id, status.spec_name, status.spec_version, local.spec_version);
+					historical = true;
+				}
+			}
+
+			let roles = message::Role::as_flags(&status.roles);
+			let reserved = io.peer_node_id(peer_id).map_or(false, |id| self.peer_set.is_reserved(&id));
+			if !reserved && !self.peer_set.has_free_slot(roles, peers.values().filter(|p| !p.reserved).map(|p| &p.roles)) {
+				debug!(target: "sync", "No free slot for peer {} with roles {:?}", peer_id, roles);
+				handshaking_peers.remove(&peer_id);
+				self.note_failure(io, peer_id, FailureReason::NoFreeSlot);
+				io.disconnect_peer(peer_id);
+				return;
+			}
+
 			let peer = Peer {
 				protocol_version: status.version,
-				roles: message::Role::as_flags(&status.roles),
+				roles: roles,
 				best_hash: status.best_hash,
 				best_number: status.best_number,
+				finalized: status.finalized,
+				spec: (status.spec_name.clone(), status.spec_version),
+				historical,
 				block_request: None,
+				state_request: None,
 				request_timestamp: None,
-				known_transactions: HashSet::new(),
+				stalled: false,
+				stalls: 0,
+				known_transactions: KnownSet::new(MAX_KNOWN_TRANSACTIONS),
 				known_blocks: HashSet::new(),
 				next_request_id: 0,
+				hash_announcements: status.hash_announcements,
+				reserved: reserved,
+				latency: Latency::default(),
+				response_limits: self.config.response_limits.negotiate(status.max_blocks_per_response, status.max_response_bytes),
+				keep_alive: status.keep_alive,
+				last_received: time::Instant::now(),
+				ping_sent: None,
 			};
 			peers.insert(peer_id.clone(), peer);
 			handshaking_peers.remove(&peer_id);
+			if let Some(time) = status.time {
+				self.clocks.lock().note(peer_id, time, clock::now_millis());
+			}
 			debug!(target: "sync", "Connected {} {}", peer_id, io.peer_info(peer_id));
+			historical
+		};
+
+		if historical {
+			return;
 		}
 
 		self.sync.write().new_peer(io, self, peer_id);
@@ -424,11 +994,12 @@ impl<B: BlockT> Protocol<B> where
 
 		let mut propagated_to = HashMap::new();
 		let mut peers = self.peers.write();
-		for (peer_id, ref mut peer) in peers.iter_mut() {
+		for (peer_id, ref mut peer) in peers.iter_mut().filter(|&(_, ref p)| !p.historical) {
 			let (hashes, to_send): (Vec<_>, Vec<_>) = transactions
 				.iter()
 				.cloned()
 				.filter(|&(hash, _)| peer.known_transactions.insert(hash))
+				.take(MAX_TRANSACTIONS_PER_PROPAGATION)
 				.unzip();
 
 			if !to_send.is_empty() {
@@ -443,7 +1014,9 @@ impl<B: BlockT> Protocol<B> where
 					}
 				}
 				trace!(target: "sync", "Sending {} transactions to {}", to_send.len(), peer_id);
-				self.send_message(io, *peer_id, GenericMessage::Transactions(to_send));
+				for batch in to_send.chunks(MAX_TRANSACTIONS_PER_MESSAGE) {
+					self.send_message(io, *peer_id, GenericMessage::Transactions(batch.to_vec()));
+				}
 			}
 		}
 		self.transaction_pool.on_broadcasted(propagated_to);
@@ -451,28 +1024,73 @@ impl<B: BlockT> Protocol<B> where
 
 	/// Send Status message
 	fn send_status(&self, io: &mut SyncIo, peer_id: PeerId) {
-		if let Ok(info) = self.chain.info() {
+		if let (Ok(info), Some(version)) = (self.chain.info(), self.runtime_version()) {
 			let status = message::generic::Status {
 				version: PROTOCOL_VERSION,
 				genesis_hash: info.chain.genesis_hash,
+				spec_name: version.spec_name.to_string(),
+				spec_version: version.spec_version,
 				roles: self.config.roles.into(),
 				best_number: info.chain.best_number,
 				best_hash: info.chain.best_hash,
 				validator_signature: None,
 				validator_id: None,
 				parachain_id: None,
+				time: Some(clock::now_millis()),
+				hash_announcements: true,
+				// blocks are only imported with a justification, so the best block is final.
+				finalized: Some((info.chain.best_number, info.chain.best_hash)),
+				max_blocks_per_response: Some(self.config.response_limits.max_blocks),
+				max_response_bytes: Some(self.config.response_limits.max_bytes),
+				keep_alive: true,
 			};
 			self.send_message(io, peer_id, GenericMessage::Status(status))
 		}
 	}
 
+	/// Median offset of the clocks of connected peers from the local clock in
+	/// milliseconds, positive when the local clock is behind. `None` until
+	/// enough peers have sent their time.
+	pub fn clock_skew(&self) -> Option<i64> {
+		self.clocks.lock().skew()
+	}
+
+	/// Whether sync should stop importing blocks because the protocol is shutting down.
+	pub fn is_import_stopped(&self) -> bool {
+		self.import_deadline.lock().map_or(false, |deadline| time::Instant::now() >= deadline)
+	}
+
+	/// Whether sync keeps downloaded blocks without importing them.
+	pub fn is_import_paused(&self) -> bool {
+		self.import_paused.load(Ordering::Acquire)
+	}
+
+	/// Stop importing downloaded blocks, keeping them until `resume_import`.
+	/// An import in progress stops after the block being imported. Blocks are
+	/// still downloaded until the queue limits are reached.
+	pub fn pause_import(&self) {
+		self.import_paused.store(true, Ordering::Release);
+	}
+
+	/// Import the blocks downloaded while paused, and import as usual from then on.
+	pub fn resume_import(&self, io: &mut SyncIo) {
+		self.import_paused.store(false, Ordering::Release);
+		self.sync.write().resume_import(io, self);
+	}
+
 	pub fn abort(&self) {
+		// an import in progress holds the sync lock; let it finish within the configured time.
+		*self.import_deadline.lock() = Some(time::Instant::now() + self.config.import_on_shutdown);
 		let mut sync = self.sync.write();
+		*self.import_deadline.lock() = None;
+
 		let mut peers = self.peers.write();
 		let mut handshaking_peers = self.handshaking_peers.write();
+		sync.persist(&*self.chain);
 		sync.clear();
 		peers.clear();
 		handshaking_peers.clear();
+		self.connections.write().clear();
 		self.consensus.lock().restart();
 	}
 
@@ -482,12 +1100,77 @@ impl<B: BlockT> Protocol<B> where
 		{
 			let mut peers = self.peers.write();
 			if let Some(ref mut peer) = peers.get_mut(&peer_id) {
+				if peer.historical {
+					trace!(target: "sync", "Ignoring announcement from historical peer {}", peer_id);
+					return;
+				}
 				peer.known_blocks.insert(hash.clone());
 			}
 		}
+		if !self.validate_announce(io, peer_id, &hash, &header) {
+			return;
+		}
 		self.sync.write().on_block_announce(io, self, peer_id, hash, &header);
 	}
 
+	/// Validate the header of a block announced by a peer, punishing the peer
+	/// if the announcement is bogus. Returns `true` if the block may be downloaded.
+	pub fn validate_announce(&self, io: &mut SyncIo, peer_id: PeerId, hash: &B::Hash, header: &B::Header) -> bool {
+		if self.conflicts_with_checkpoint(header.number().clone(), hash) {
+			debug!(target: "sync", "Block {} announced by {} conflicts with a checkpoint", hash, peer_id);
+			self.disable_peer_for(io, peer_id, Misbehavior::ConflictingCheckpoint);
+			return false;
+		}
+
+		let validator = match self.block_announce_validator {
+			Some(ref validator) => validator,
+			None => return true,
+		};
+
+		match validator.validate(header) {
+			AnnounceValidity::Valid => true,
+			AnnounceValidity::Ignore => {
+				trace!(target: "sync", "Ignoring block announce from {}: {}", peer_id, hash);
+				false
+			},
+			AnnounceValidity::Invalid => {
+				debug!(target: "sync", "Invalid block announce from {}: {}", peer_id, hash);
+				self.disable_peer_for(io, peer_id, Misbehavior::BadBlock);
+				false
+			},
+		}
+	}
+
+	/// Check a downloaded block with the chain-specific verifier, if any.
+	pub fn verify_block(&self, header: &B::Header, justification: &Justification<B::Hash>, body: Option<&[B::Extrinsic]>)
+		-> Result<(), String>
+	{
+		match self.block_verifier {
+			Some(ref verifier) => verifier.verify(header, justification, body),
+			None => Ok(()),
+		}
+	}
+
+	/// Whether a block has the number of a checkpoint, but another hash.
+	pub fn conflicts_with_checkpoint(&self, number: u64, hash: &B::Hash) -> bool {
+		self.checkpoints.get(&number).map_or(false, |checkpoint| checkpoint != hash)
+	}
+
+	pub fn on_block_hash_announce(&self, io: &mut SyncIo, peer_id: PeerId, announce: message::BlockHashAnnounce<B::Hash>) {
+		let hash = announce.hash;
+		{
+			let mut peers = self.peers.write();
+			if let Some(ref mut peer) = peers.get_mut(&peer_id) {
+				if peer.historical {
+					trace!(target: "sync", "Ignoring announcement from historical peer {}", peer_id);
+					return;
+				}
+				peer.known_blocks.insert(hash.clone());
+			}
+		}
+		self.sync.write().on_block_hash_announce(io, self, peer_id, hash);
+	}
+
 	pub fn on_block_imported(&self, io: &mut SyncIo, hash: B::Hash, header: &B::Header) {
 		self.sync.write().update_chain_info(&header);
 
@@ -499,12 +1182,16 @@ impl<B: BlockT> Protocol<B> where
 		// send out block announcements
 		let mut peers = self.peers.write();
 
+		let announce_hashes = self.config.announce_mode == AnnounceMode::Hash;
 		for (peer_id, ref mut peer) in peers.iter_mut() {
 			if peer.known_blocks.insert(hash.clone()) {
 				trace!(target: "sync", "Announcing block {:?} to {}", hash, peer_id);
-				self.send_message(io, *peer_id, GenericMessage::BlockAnnounce(message::BlockAnnounce {
-					header: header.clone()
-				}));
+				let message = if announce_hashes && peer.hash_announcements {
+					GenericMessage::BlockHashAnnounce(message::BlockHashAnnounce { hash })
+				} else {
+					GenericMessage::BlockAnnounce(message::BlockAnnounce { header: header.clone() })
+				};
+				self.send_message(io, *peer_id, message);
 			}
 		}
 
@@ -532,6 +1219,70 @@ impl<B: BlockT> Protocol<B> where
 		self.on_demand.as_ref().map(|s| s.on_remote_call_response(io, peer_id, response));
 	}
 
+	fn on_remote_header_request(&self, io: &mut SyncIo, peer_id: PeerId, request: message::RemoteHeaderRequest<B::Hash>) {
+		trace!(target: "sync", "Remote header request {} from {} ({})", request.id, peer_id, request.block);
+		let header = match self.chain.header(&BlockId::Hash(request.block)) {
+			Ok(header) => header,
+			Err(error) => {
+				trace!(target: "sync", "Remote header request {} from {} ({}) failed with: {}",
+					request.id, peer_id, request.block, error);
+				None
+			},
+		};
+
+		self.send_message(io, peer_id, GenericMessage::RemoteHeaderResponse(message::generic::RemoteHeaderResponse {
+			id: request.id, header,
+		}));
+	}
+
+	fn on_remote_header_response(&self, io: &mut SyncIo, peer_id: PeerId, response: message::RemoteHeaderResponse<B>) {
+		trace!(target: "sync", "Remote header response {} from {}", response.id, peer_id);
+		self.on_demand.as_ref().map(|s| s.on_remote_header_response(io, peer_id, response));
+	}
+
+	fn on_remote_body_request(&self, io: &mut SyncIo, peer_id: PeerId, request: message::RemoteBodyRequest<B::Hash>) {
+		trace!(target: "sync", "Remote body request {} from {} ({})", request.id, peer_id, request.block);
+		let body = match self.chain.body(&BlockId::Hash(request.block)) {
+			Ok(body) => body,
+			Err(error) => {
+				trace!(target: "sync", "Remote body request {} from {} ({}) failed with: {}",
+					request.id, peer_id, request.block, error);
+				None
+			},
+		};
+
+		self.send_message(io, peer_id, GenericMessage::RemoteBodyResponse(message::generic::RemoteBodyResponse {
+			id: request.id, body,
+		}));
+	}
+
+	fn on_remote_body_response(&self, io: &mut SyncIo, peer_id: PeerId, response: message::RemoteBodyResponse<B>) {
+		trace!(target: "sync", "Remote body response {} from {}", response.id, peer_id);
+		self.on_demand.as_ref().map(|s| s.on_remote_body_response(io, peer_id, response));
+	}
+
+	fn on_remote_read_request(&self, io: &mut SyncIo, peer_id: PeerId, request: message::RemoteReadRequest<B::Hash>) {
+		trace!(target: "sync", "Remote read request {} from {} ({} at {})",
+			request.id, peer_id, HexDisplay::from(&request.key), request.block);
+		let proof = match self.chain.read_proof(&request.block, &request.key) {
+			Ok(proof) => proof,
+			Err(error) => {
+				trace!(target: "sync", "Remote read request {} from {} ({} at {}) failed with: {}",
+					request.id, peer_id, HexDisplay::from(&request.key), request.block, error);
+				Default::default()
+			},
+		};
+
+		self.send_message(io, peer_id, GenericMessage::RemoteReadResponse(message::RemoteReadResponse {
+			id: request.id, proof,
+		}));
+	}
+
+	fn on_remote_read_response(&self, io: &mut SyncIo, peer_id: PeerId, response: message::RemoteReadResponse) {
+		trace!(target: "sync", "Remote read response {} from {}", response.id, peer_id);
+		self.on_demand.as_ref().map(|s| s.on_remote_read_response(io, peer_id, response));
+	}
+
 	pub fn chain(&self) -> &Client<B> {
 		&*self.chain
 	}
@@ -0,0 +1,187 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Registry of application protocols run alongside the core protocol.
+//!
+//! Each registered protocol gets its own devp2p capability and sees only its
+//! own packets. Messages are passed to handlers as raw bytes, leaving the
+//! choice of codec to the protocol.
+
+use std::sync::Arc;
+use std::time::Duration;
+use network::{NetworkProtocolHandler, NetworkContext, PeerId, ProtocolId};
+use core_io::TimerToken;
+use io::{SyncIo, NetSyncIo};
+use service::DOT_PROTOCOL_ID;
+use error::{Error, ErrorKind};
+use notifications::{NotificationsProtocol, NotificationStream};
+use bandwidth::BandwidthSinks;
+
+const TICK_TOKEN: TimerToken = 0;
+const TICK_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Number of packet types used by each application protocol.
+pub(crate) const PACKET_COUNT: u8 = 1;
+
+/// Interface for an application protocol to interact with its peers.
+pub trait ProtocolContext {
+	/// Send a message to a peer.
+	fn send(&mut self, peer: PeerId, data: Vec<u8>);
+	/// Disconnect a peer and prevent it from connecting again.
+	fn disable_peer(&mut self, peer: PeerId);
+	/// Disconnect a peer.
+	fn disconnect_peer(&mut self, peer: PeerId);
+}
+
+impl<T: SyncIo + ?Sized> ProtocolContext for T {
+	fn send(&mut self, peer: PeerId, data: Vec<u8>) {
+		if let Err(e) = SyncIo::send(self, peer, data) {
+			trace!(target: "sync", "Error sending application protocol message to {}: {:?}", peer, e);
+		}
+	}
+
+	fn disable_peer(&mut self, peer: PeerId) {
+		SyncIo::disable_peer(self, peer)
+	}
+
+	fn disconnect_peer(&mut self, peer: PeerId) {
+		SyncIo::disconnect_peer(self, peer)
+	}
+}
+
+/// Context which counts the bytes sent over an application protocol.
+pub(crate) struct MeteredContext<'a> {
+	inner: &'a mut ProtocolContext,
+	id: ProtocolId,
+	sinks: &'a BandwidthSinks,
+}
+
+impl<'a> MeteredContext<'a> {
+	pub(crate) fn new(inner: &'a mut ProtocolContext, id: ProtocolId, sinks: &'a BandwidthSinks) -> Self {
+		MeteredContext { inner, id, sinks }
+	}
+}
+
+impl<'a> ProtocolContext for MeteredContext<'a> {
+	fn send(&mut self, peer: PeerId, data: Vec<u8>) {
+		self.sinks.on_sent(self.id, data.len());
+		self.inner.send(peer, data)
+	}
+
+	fn disable_peer(&mut self, peer: PeerId) {
+		self.inner.disable_peer(peer)
+	}
+
+	fn disconnect_peer(&mut self, peer: PeerId) {
+		self.inner.disconnect_peer(peer)
+	}
+}
+
+/// Handler for an application protocol.
+pub trait ApplicationProtocol: Send + Sync {
+	/// Called when a peer supporting the protocol connects.
+	fn on_peer_connected(&self, _ctx: &mut ProtocolContext, _peer: PeerId) { }
+	/// Called when a peer supporting the protocol disconnects.
+	fn on_peer_disconnected(&self, _ctx: &mut ProtocolContext, _peer: PeerId) { }
+	/// Called when a message is received from a peer.
+	fn on_message(&self, ctx: &mut ProtocolContext, peer: PeerId, data: &[u8]);
+	/// Called periodically.
+	fn tick(&self, _ctx: &mut ProtocolContext) { }
+}
+
+/// Application protocols to register with the network service on start.
+#[derive(Default)]
+pub struct ProtocolRegistry {
+	protocols: Vec<(ProtocolId, Arc<ApplicationProtocol>)>,
+}
+
+impl ProtocolRegistry {
+	/// Create an empty registry.
+	pub fn new() -> Self {
+		ProtocolRegistry::default()
+	}
+
+	/// Register a handler for messages of the given protocol.
+	///
+	/// Fails if the name is taken by the core protocol or another registered one.
+	pub fn register(&mut self, id: ProtocolId, handler: Arc<ApplicationProtocol>) -> Result<(), Error> {
+		if id == DOT_PROTOCOL_ID || self.protocols.iter().any(|&(ref other, _)| other == &id) {
+			bail!(ErrorKind::DuplicateProtocol(id));
+		}
+
+		self.protocols.push((id, handler));
+		Ok(())
+	}
+
+	/// Register a notification protocol with the given name and handshake payload.
+	pub fn register_notifications(&mut self, id: ProtocolId, handshake: Vec<u8>)
+		-> Result<(Arc<NotificationsProtocol>, NotificationStream), Error>
+	{
+		let (protocol, stream) = NotificationsProtocol::new(id, handshake);
+		self.register(id, protocol.clone())?;
+		Ok((protocol, stream))
+	}
+
+	/// Names of all registered protocols.
+	pub fn protocol_ids(&self) -> Vec<ProtocolId> {
+		self.protocols.iter().map(|&(id, _)| id).collect()
+	}
+
+	pub(crate) fn into_handlers(self, sinks: &BandwidthSinks) -> Vec<(ProtocolId, Arc<ApplicationProtocolHandler>)> {
+		self.protocols.into_iter()
+			.map(|(id, handler)| (id, Arc::new(ApplicationProtocolHandler { id, handler, sinks: sinks.clone() })))
+			.collect()
+	}
+}
+
+/// devp2p handler for an application protocol.
+pub(crate) struct ApplicationProtocolHandler {
+	id: ProtocolId,
+	handler: Arc<ApplicationProtocol>,
+	sinks: BandwidthSinks,
+}
+
+impl ApplicationProtocolHandler {
+	fn with_context<F: FnOnce(&mut ProtocolContext)>(&self, io: &NetworkContext, f: F) {
+		f(&mut MeteredContext::new(&mut NetSyncIo::new(io), self.id, &self.sinks))
+	}
+}
+
+impl NetworkProtocolHandler for ApplicationProtocolHandler {
+	fn initialize(&self, io: &NetworkContext) {
+		io.register_timer(TICK_TOKEN, TICK_TIMEOUT)
+			.expect("Error registering application protocol timer");
+	}
+
+	fn read(&self, io: &NetworkContext, peer: &PeerId, _packet_id: u8, data: &[u8]) {
+		self.sinks.on_received(self.id, data.len());
+		self.with_context(io, |ctx| self.handler.on_message(ctx, *peer, data));
+	}
+
+	fn connected(&self, io: &NetworkContext, peer: &PeerId) {
+		self.with_context(io, |ctx| self.handler.on_peer_connected(ctx, *peer));
+	}
+
+	fn disconnected(&self, io: &NetworkContext, peer: &PeerId) {
+		self.with_context(io, |ctx| self.handler.on_peer_disconnected(ctx, *peer));
+	}
+
+	fn timeout(&self, io: &NetworkContext, timer: TimerToken) {
+		if timer == TICK_TOKEN {
+			self.with_context(io, |ctx| self.handler.tick(ctx));
+		}
+	}
+}
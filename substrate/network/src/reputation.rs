@@ -0,0 +1,300 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reputation of peers, and bans of peers with a bad one.
+//!
+//! Peers are tracked by node ID rather than by session, so that a peer which
+//! is disconnected for misbehaving doesn't start afresh when it reconnects.
+//! Useful responses raise the reputation of a peer, up to a limit, and bad
+//! blocks lower it. Once it falls to the ban threshold, the peer is banned for a
+//! while and refused when it connects. A ban doesn't restore the reputation, so
+//! a peer which misbehaves again after being let back in is banned again.
+//!
+//! The last misbehavior of every peer is kept alongside its reputation, so that
+//! operators can see why peers were disconnected or banned.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Reputation change for a response which advanced the sync.
+pub const USEFUL_RESPONSE: i32 = 1;
+
+/// Reputation change for a block which failed import.
+pub const BAD_BLOCK: i32 = -50;
+
+/// Reputation change for a response which lacked requested data.
+pub const MISSING_DATA: i32 = -20;
+
+/// Reputation change for a message with an invalid signature.
+pub const INVALID_SIGNATURE: i32 = -50;
+
+/// Reputation change for sending more messages than allowed.
+pub const SPAM: i32 = -10;
+
+/// Reputation change for a block conflicting with a checkpoint, which bans
+/// the peer at once.
+pub const CONFLICTING_CHECKPOINT: i32 = i32::min_value();
+
+/// Reputation change for a request which wasn't answered in time, after
+/// another which wasn't either.
+pub const STALLED: i32 = -10;
+
+/// Default reputation at or below which a peer is banned.
+pub const DEFAULT_BAN_THRESHOLD: i32 = -100;
+
+/// Default highest reputation a peer may have.
+pub const DEFAULT_MAX_REPUTATION: i32 = 100;
+
+/// Default duration of a ban in seconds.
+pub const DEFAULT_BAN_DURATION_SECS: u64 = 600;
+
+/// Configuration of peer reputation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReputationConfig {
+	/// Reputation at or below which a peer is banned.
+	pub ban_threshold: i32,
+	/// Highest reputation a peer may have, so that a long history of useful
+	/// responses can't make up for any amount of bad blocks.
+	pub max_reputation: i32,
+	/// How long a peer is banned for.
+	pub ban_duration: Duration,
+}
+
+impl Default for ReputationConfig {
+	fn default() -> Self {
+		ReputationConfig {
+			ban_threshold: DEFAULT_BAN_THRESHOLD,
+			max_reputation: DEFAULT_MAX_REPUTATION,
+			ban_duration: Duration::from_secs(DEFAULT_BAN_DURATION_SECS),
+		}
+	}
+}
+
+/// Ways in which a peer may misbehave. Sent to the peer when it is
+/// disconnected for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Misbehavior {
+	/// Sent a block which failed import.
+	BadBlock,
+	/// Gossiped a message with an invalid signature.
+	InvalidSignature,
+	/// Sent an unexpected or malformed message, or a response lacking
+	/// requested data.
+	ProtocolViolation,
+	/// Sent more messages than allowed.
+	Spam,
+	/// Sent a block on a chain conflicting with a checkpoint.
+	ConflictingCheckpoint,
+}
+
+impl Misbehavior {
+	/// The reputation change for the misbehavior.
+	pub fn reputation_change(&self) -> i32 {
+		match *self {
+			Misbehavior::BadBlock => BAD_BLOCK,
+			Misbehavior::InvalidSignature => INVALID_SIGNATURE,
+			Misbehavior::ProtocolViolation => MISSING_DATA,
+			Misbehavior::Spam => SPAM,
+			Misbehavior::ConflictingCheckpoint => CONFLICTING_CHECKPOINT,
+		}
+	}
+}
+
+impl fmt::Display for Misbehavior {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Misbehavior::BadBlock => write!(f, "bad block"),
+			Misbehavior::InvalidSignature => write!(f, "invalid signature"),
+			Misbehavior::ProtocolViolation => write!(f, "protocol violation"),
+			Misbehavior::Spam => write!(f, "spam"),
+			Misbehavior::ConflictingCheckpoint => write!(f, "block conflicting with a checkpoint"),
+		}
+	}
+}
+
+/// What is known about a node, for inspection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerRecord {
+	/// Node ID.
+	pub node_id: String,
+	/// Current reputation.
+	pub reputation: i32,
+	/// Whether the node is banned.
+	pub banned: bool,
+	/// The last misbehavior of the node, if any.
+	pub last_misbehavior: Option<Misbehavior>,
+}
+
+#[derive(Default)]
+struct Entry {
+	reputation: i32,
+	banned_until: Option<Instant>,
+	last_misbehavior: Option<Misbehavior>,
+}
+
+impl Entry {
+	fn is_banned(&self, now: Instant) -> bool {
+		self.banned_until.map_or(false, |until| now < until)
+	}
+}
+
+/// Reputations and bans of peers, by node ID.
+pub struct PeerReputation {
+	config: ReputationConfig,
+	peers: Mutex<HashMap<String, Entry>>,
+}
+
+impl PeerReputation {
+	/// Create with the given configuration.
+	pub fn new(config: ReputationConfig) -> Self {
+		PeerReputation {
+			config,
+			peers: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Change the reputation of a node. Returns `true` if the node is banned
+	/// as a result.
+	pub fn report(&self, node: &str, change: i32, now: Instant) -> bool {
+		let mut peers = self.peers.lock();
+		let entry = peers.entry(node.to_owned()).or_insert_with(Entry::default);
+
+		entry.reputation = ::std::cmp::min(entry.reputation.saturating_add(change), self.config.max_reputation);
+		if change < 0 && entry.reputation <= self.config.ban_threshold && !entry.is_banned(now) {
+			entry.banned_until = Some(now + self.config.ban_duration);
+			return true;
+		}
+
+		false
+	}
+
+	/// Lower the reputation of a node for misbehaving, and remember the
+	/// misbehavior. Returns `true` if the node is banned as a result.
+	pub fn report_misbehavior(&self, node: &str, misbehavior: Misbehavior, now: Instant) -> bool {
+		self.peers.lock().entry(node.to_owned()).or_insert_with(Entry::default).last_misbehavior = Some(misbehavior);
+		self.report(node, misbehavior.reputation_change(), now)
+	}
+
+	/// Records of all known nodes which have misbehaved, ordered by node ID.
+	pub fn misbehaving(&self, now: Instant) -> Vec<PeerRecord> {
+		let mut records: Vec<_> = self.peers.lock().iter()
+			.filter(|&(_, e)| e.last_misbehavior.is_some())
+			.map(|(node_id, e)| PeerRecord {
+				node_id: node_id.clone(),
+				reputation: e.reputation,
+				banned: e.is_banned(now),
+				last_misbehavior: e.last_misbehavior,
+			})
+			.collect();
+		records.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+		records
+	}
+
+	/// Whether a node is banned at `now`.
+	pub fn is_banned(&self, node: &str, now: Instant) -> bool {
+		self.peers.lock().get(node).map_or(false, |e| e.is_banned(now))
+	}
+
+	/// Forget nodes whose ban has expired and which have no reputation either way.
+	pub fn collect_garbage(&self, now: Instant) {
+		self.peers.lock().retain(|_, e| e.reputation != 0 || e.is_banned(now));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn reputation_of(reputation: &PeerReputation, node: &str) -> i32 {
+		reputation.peers.lock().get(node).map_or(0, |e| e.reputation)
+	}
+
+	#[test]
+	fn bad_blocks_lead_to_timed_bans() {
+		let reputation = PeerReputation::new(ReputationConfig {
+			ban_threshold: -100,
+			max_reputation: 10,
+			ban_duration: Duration::from_secs(10),
+		});
+		let start = Instant::now();
+		let at = |secs| start + Duration::from_secs(secs);
+
+		assert!(!reputation.report("a", BAD_BLOCK, start));
+		assert!(!reputation.is_banned("a", start));
+		assert!(reputation.report("a", BAD_BLOCK, start));
+		assert!(reputation.is_banned("a", at(9)));
+		assert!(!reputation.is_banned("b", at(9)));
+
+		// still banned, not banned anew.
+		assert!(!reputation.report("a", BAD_BLOCK, at(5)));
+
+		// the ban expires, but the reputation stays.
+		assert!(!reputation.is_banned("a", at(10)));
+		assert!(reputation.report("a", MISSING_DATA, at(10)));
+		assert!(reputation.is_banned("a", at(19)));
+	}
+
+	#[test]
+	fn useful_responses_are_capped() {
+		let reputation = PeerReputation::new(ReputationConfig {
+			ban_threshold: -100,
+			max_reputation: 10,
+			ban_duration: Duration::from_secs(10),
+		});
+		let now = Instant::now();
+
+		for _ in 0..1000 {
+			reputation.report("a", USEFUL_RESPONSE, now);
+		}
+		assert_eq!(reputation_of(&reputation, "a"), 10);
+
+		// a good history delays the ban, but doesn't prevent it.
+		assert!(!reputation.report("a", BAD_BLOCK, now));
+		assert!(!reputation.report("a", BAD_BLOCK, now));
+		assert!(reputation.report("a", BAD_BLOCK, now));
+
+		reputation.report("b", USEFUL_RESPONSE, now);
+		reputation.report("b", -USEFUL_RESPONSE, now);
+		reputation.collect_garbage(now);
+		assert!(reputation.is_banned("a", now));
+		assert_eq!(reputation_of(&reputation, "b"), 0);
+		assert_eq!(reputation.peers.lock().len(), 1);
+	}
+
+	#[test]
+	fn misbehavior_is_recorded() {
+		let reputation = PeerReputation::new(ReputationConfig {
+			ban_threshold: -60,
+			max_reputation: 10,
+			ban_duration: Duration::from_secs(10),
+		});
+		let now = Instant::now();
+
+		reputation.report("a", USEFUL_RESPONSE, now);
+		assert!(!reputation.report_misbehavior("b", Misbehavior::Spam, now));
+		assert!(reputation.report_misbehavior("b", Misbehavior::InvalidSignature, now));
+
+		assert_eq!(reputation.misbehaving(now), vec![PeerRecord {
+			node_id: "b".into(),
+			reputation: SPAM + INVALID_SIGNATURE,
+			banned: true,
+			last_misbehavior: Some(Misbehavior::InvalidSignature),
+		}]);
+	}
+}
@@ -15,10 +15,13 @@
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.?
 
 use std::collections::HashMap;
-use std::sync::Arc;
-use std::io;
+use std::sync::{Arc, Weak};
+use std::{io, thread};
 use std::time::Duration;
+use futures::{Future, Sink, Stream};
+use futures::stream;
 use futures::sync::{oneshot, mpsc};
+use parking_lot::Mutex;
 use network::{NetworkProtocolHandler, NetworkContext, PeerId, ProtocolId,
 NetworkConfiguration , NonReservedPeerMode, ErrorKind};
 use network_devp2p::{NetworkService};
@@ -28,9 +31,18 @@ use protocol::{Protocol, ProtocolStatus, PeerInfo as ProtocolPeerInfo};
 use config::{ProtocolConfig};
 use error::Error;
 use chain::Client;
-use message::LocalizedBftMessage;
+use message::{Message, LocalizedBftMessage, Heartbeat};
 use on_demand::OnDemandService;
+use registry::{self, ProtocolRegistry, ProtocolContext, ApplicationProtocolHandler, MeteredContext};
+use bandwidth::BandwidthSinks;
+use notifications::ExecuteInProtocol;
+use reputation::PeerRecord;
+use peer_set::{PeerSet, PeerSlots, ConnectionLimits};
+use network_state::{NetworkState, PeerState, DiscoveryState};
+use message::Role as NodeRole;
+use primitives::hexdisplay::HexDisplay;
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
+use runtime_primitives::bft::Justification;
 
 /// Polkadot devp2p protocol id
 pub const DOT_PROTOCOL_ID: ProtocolId = *b"dot";
@@ -41,6 +53,10 @@ const V0_PACKET_COUNT: u8 = 1;
 pub type FetchFuture = oneshot::Receiver<Vec<u8>>;
 /// Type that represents bft messages stream.
 pub type BftMessageStream<B> = mpsc::UnboundedReceiver<LocalizedBftMessage<B>>;
+/// Type that represents a stream of encoded heartbeats.
+pub type HeartbeatStream = mpsc::UnboundedReceiver<Heartbeat>;
+/// Type that represents a stream of the data of gossip messages under a topic.
+pub type GossipMessageStream = mpsc::UnboundedReceiver<Vec<u8>>;
 
 const TICK_TOKEN: TimerToken = 0;
 const TICK_TIMEOUT: Duration = Duration::from_millis(1000);
@@ -48,6 +64,10 @@ const TICK_TIMEOUT: Duration = Duration::from_millis(1000);
 const PROPAGATE_TOKEN: TimerToken = 1;
 const PROPAGATE_TIMEOUT: Duration = Duration::from_millis(5000);
 
+/// Number of actions which may be queued for the network before actions which
+/// can be dropped or retried are refused.
+const ACTION_QUEUE_SIZE: usize = 1024;
+
 bitflags! {
 	/// Node roles bitmask.
 	pub struct Role: u32 {
@@ -72,6 +92,8 @@ pub trait SyncProvider<B: BlockT>: Send + Sync {
 	fn peers(&self) -> Vec<PeerInfo<B>>;
 	/// Get this node id if available.
 	fn node_id(&self) -> Option<String>;
+	/// Get the nodes which have misbehaved, with their last misbehavior.
+	fn misbehaving_peers(&self) -> Vec<PeerRecord>;
 }
 
 /// Transaction pool interface
@@ -84,6 +106,34 @@ pub trait TransactionPool<B: BlockT>: Send + Sync {
 	fn on_broadcasted(&self, propagations: HashMap<B::Hash, Vec<String>>);
 }
 
+/// Outcome of the validation of a block announcement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnounceValidity {
+	/// The announced block may be downloaded.
+	Valid,
+	/// The announced block isn't worth downloading, but the peer didn't misbehave.
+	Ignore,
+	/// The announcement is bogus. The peer is punished for it.
+	Invalid,
+}
+
+/// Cheap checks of the headers of announced blocks, done before the blocks
+/// are requested from the announcing peer.
+pub trait BlockAnnounceValidator<B: BlockT>: Send + Sync {
+	/// Validate the header of an announced block.
+	fn validate(&self, header: &B::Header) -> AnnounceValidity;
+}
+
+/// Chain-specific checks of downloaded blocks, done before they are imported.
+/// A block which fails them isn't imported and the peer which sent it is
+/// punished.
+pub trait BlockVerifier<B: BlockT>: Send + Sync {
+	/// Verify a block with its justification, and its body unless only headers
+	/// are synced. Returns why the block is refused, if it is.
+	fn verify(&self, header: &B::Header, justification: &Justification<B::Hash>, body: Option<&[B::Extrinsic]>)
+		-> Result<(), String>;
+}
+
 /// ConsensusService
 pub trait ConsensusService<B: BlockT>: Send + Sync {
 	/// Maintain connectivity to given addresses.
@@ -94,12 +144,125 @@ pub trait ConsensusService<B: BlockT>: Send + Sync {
 	fn bft_messages(&self, parent_hash: B::Hash) -> BftMessageStream<B>;
 	/// Send out a BFT message.
 	fn send_bft_message(&self, message: LocalizedBftMessage<B>);
+
+	/// Get a stream of all heartbeats known and received from here on.
+	fn heartbeats(&self) -> HeartbeatStream;
+	/// Send out an encoded heartbeat.
+	fn send_heartbeat(&self, heartbeat: Heartbeat);
+
+	/// Get a stream of the data of all gossip messages known and received from
+	/// here on under the topic. The stream ends once the topic expires.
+	fn gossip_messages(&self, topic: B::Hash) -> GossipMessageStream;
+	/// Gossip encoded data to validators under the topic.
+	fn send_gossip(&self, topic: B::Hash, data: Vec<u8>);
+
+	/// Median offset of the clocks of connected peers from the local clock in
+	/// milliseconds, positive when the local clock is behind. `None` until
+	/// enough peers have sent their time.
+	fn clock_skew(&self) -> Option<i64>;
+}
+
+/// Actions to be processed by the network event loop.
+pub enum NetworkAction<B: BlockT> {
+	/// A new block was imported by the client.
+	BlockImported(B::Hash, B::Header),
+	/// Propagate transactions from the pool.
+	PropagateTransactions,
+	/// Send a message to a peer.
+	SendMessage(PeerId, Message<B>),
+	/// Send out a BFT message.
+	BftMessage(LocalizedBftMessage<B>),
+	/// Send out an encoded heartbeat.
+	Heartbeat(Heartbeat),
+	/// Gossip encoded data under a topic.
+	Gossip(B::Hash, Vec<u8>),
+	/// Import the blocks downloaded while import was paused.
+	ResumeImport,
 }
 
-/// Service able to execute closure in the network context.
-pub trait ExecuteInContext<B: BlockT>: Send + Sync {
-	/// Execute closure in network context.
-	fn execute_in_context<F: Fn(&mut NetSyncIo, &Protocol<B>)>(&self, closure: F);
+pub(crate) enum Event<B: BlockT> {
+	Action(NetworkAction<B>),
+	Stop,
+}
+
+/// Receiving end of the network action queues. Control events and bounded
+/// actions are interleaved.
+pub(crate) type ActionReceiver<B> = stream::Select<mpsc::UnboundedReceiver<Event<B>>, mpsc::Receiver<Event<B>>>;
+
+/// Reason an action couldn't be queued with `NetworkChan::try_send`.
+pub enum TrySendError<B: BlockT> {
+	/// The queue is full. The action may be retried later.
+	Full(NetworkAction<B>),
+	/// The network has shut down.
+	Closed(NetworkAction<B>),
+}
+
+/// Handle for queueing actions on the network event loop.
+///
+/// Queueing never touches the network or protocol locks and never blocks, so it
+/// is safe to do from any thread, including while holding other locks.
+///
+/// Consensus and sync actions and the stop signal go through an unbounded
+/// control queue, as they must not be dropped. Other actions go through a
+/// bounded queue, and are refused or wait for room while it is full.
+pub struct NetworkChan<B: BlockT> {
+	control: mpsc::UnboundedSender<Event<B>>,
+	sender: Arc<Mutex<mpsc::Sender<Event<B>>>>,
+}
+
+impl<B: BlockT> Clone for NetworkChan<B> {
+	fn clone(&self) -> Self {
+		NetworkChan { control: self.control.clone(), sender: self.sender.clone() }
+	}
+}
+
+impl<B: BlockT> NetworkChan<B> {
+	/// Create a new channel and the receiving end to be processed by the event loop.
+	pub(crate) fn new() -> (Self, ActionReceiver<B>) {
+		let (control, control_receiver) = mpsc::unbounded();
+		let (sender, receiver) = mpsc::channel(ACTION_QUEUE_SIZE);
+		let chan = NetworkChan { control, sender: Arc::new(Mutex::new(sender)) };
+		(chan, control_receiver.select(receiver))
+	}
+
+	/// Queue an action without waiting. If the queue is full or the network
+	/// has shut down, the action is handed back.
+	pub fn try_send(&self, action: NetworkAction<B>) -> Result<(), TrySendError<B>> {
+		self.sender.lock().try_send(Event::Action(action)).map_err(|e| {
+			let full = e.is_full();
+			match (e.into_inner(), full) {
+				(Event::Action(action), true) => TrySendError::Full(action),
+				(Event::Action(action), false) => TrySendError::Closed(action),
+				(Event::Stop, _) => unreachable!("only actions are sent through `try_send`; qed"),
+			}
+		})
+	}
+
+	/// Queue an action, resolving once there is room in the queue. Fails if the
+	/// network has shut down.
+	pub fn send(&self, action: NetworkAction<B>) -> Box<Future<Item=(), Error=()> + Send> {
+		let sender = self.sender.lock().clone();
+		Box::new(sender.send(Event::Action(action)).map(|_| ()).map_err(|_| ()))
+	}
+
+	fn stop(&self) {
+		let _ = self.control.unbounded_send(Event::Stop);
+	}
+
+	// queue an action on the control queue. consensus and sync actions are
+	// never dropped; they only fail once the network has shut down.
+	fn dispatch(&self, action: NetworkAction<B>) {
+		if self.control.unbounded_send(Event::Action(action)).is_err() {
+			debug!(target: "sync", "Network has shut down; dropping action");
+		}
+	}
+
+	// queue an action which is repeated periodically, dropping it if the queue is full.
+	fn dispatch_or_drop(&self, action: NetworkAction<B>) {
+		if self.try_send(action).is_err() {
+			trace!(target: "sync", "Network action queue is full or closed; dropping action");
+		}
+	}
 }
 
 /// devp2p Protocol handler
@@ -136,42 +299,144 @@ pub struct Params<B: BlockT> {
 	pub on_demand: Option<Arc<OnDemandService<B>>>,
 	/// Transaction pool.
 	pub transaction_pool: Arc<TransactionPool<B>>,
+	/// Validator of block announcements. All announcements are valid if `None`.
+	pub block_announce_validator: Option<Arc<BlockAnnounceValidator<B>>>,
+	/// Verifier of downloaded blocks. Blocks are only checked by the client if `None`.
+	pub block_verifier: Option<Arc<BlockVerifier<B>>>,
+	/// Application protocols to run alongside the core protocol.
+	pub application_protocols: ProtocolRegistry,
 }
 
 /// Polkadot network service. Handles network IO and manages connectivity.
 pub struct Service<B: BlockT + 'static> where B::Header: HeaderT<Number=u64> {
 	/// Network service
-	network: NetworkService,
+	network: Arc<NetworkService>,
+	/// Actions queued for the network event loop.
+	actions: NetworkChan<B>,
 	/// Devp2p protocol handler
 	handler: Arc<ProtocolHandler<B>>,
+	/// Devp2p handlers for registered application protocols.
+	application_handlers: Vec<(ProtocolId, Arc<ApplicationProtocolHandler>)>,
+	/// Addresses of the boot nodes.
+	boot_nodes: Vec<String>,
+	/// Whether nodes are discovered through the peers.
+	discovery_enabled: bool,
 }
 
 impl<B: BlockT + 'static> Service<B> where B::Header: HeaderT<Number=u64> {
 	/// Creates and register protocol with the network service
 	pub fn new(params: Params<B>) -> Result<Arc<Service<B>>, Error> {
-		let service = NetworkService::new(params.network_config.clone(), None)?;
+		let mut network_config = params.network_config.clone();
+		network_config.reserved_nodes.extend(params.config.peer_set.reserved_nodes.iter().cloned());
+		if params.config.peer_set.reserved_only {
+			network_config.non_reserved_mode = NonReservedPeerMode::Deny;
+		}
+		let service = Arc::new(NetworkService::new(network_config, None)?);
+		let (actions, action_receiver) = NetworkChan::new();
+		let protocol = Protocol::new(params.config, params.chain, params.on_demand, params.transaction_pool,
+			params.block_announce_validator, params.block_verifier)?;
+		let bandwidth_sinks = protocol.bandwidth_sinks();
 		let sync = Arc::new(Service {
 			network: service,
-			handler: Arc::new(ProtocolHandler {
-				protocol: Protocol::new(params.config, params.chain, params.on_demand, params.transaction_pool)?,
-			}),
+			actions,
+			handler: Arc::new(ProtocolHandler { protocol }),
+			application_handlers: params.application_protocols.into_handlers(&bandwidth_sinks),
+			boot_nodes: params.network_config.boot_nodes.clone(),
+			discovery_enabled: params.network_config.discovery_enabled,
 		});
 
+		let network = Arc::downgrade(&sync.network);
+		let handler = Arc::downgrade(&sync.handler);
+		thread::Builder::new()
+			.name("network-actions".into())
+			.spawn(move || process_actions(network, handler, action_receiver))?;
+
 		Ok(sync)
 	}
 
+	/// Get a handle for queueing actions on the network event loop.
+	pub fn network_chan(&self) -> NetworkChan<B> {
+		self.actions.clone()
+	}
+
+	/// Get the connected peers and the discovery status, for debugging
+	/// connectivity.
+	pub fn network_state(&self) -> NetworkState {
+		let peer_set = self.handler.protocol.peer_set();
+		let peers = self.network.with_context_eval(DOT_PROTOCOL_ID, |ctx| {
+			self.network.connected_peers().into_iter().filter_map(|peer_id| {
+				let session_info = ctx.session_info(peer_id)?;
+				let dot_info = self.handler.protocol.peer_info(peer_id);
+				let node_id = session_info.id.map(|id| format!("{:x}", id));
+				Some(PeerState {
+					peer_id,
+					reserved: node_id.as_ref().map_or(false, |id| peer_set.is_reserved(id)),
+					node_id,
+					client_version: session_info.client_version,
+					remote_address: session_info.remote_address,
+					roles: dot_info.as_ref().map_or_else(Vec::new, |info| NodeRole::from_flags(info.roles)),
+					best_hash: dot_info.as_ref().map(|info| format!("0x{}", HexDisplay::from(&info.best_hash.as_ref()))),
+					best_number: dot_info.as_ref().map(|info| info.best_number),
+					average_latency_ms: dot_info.as_ref().and_then(|info| info.average_latency_ms),
+					historical: dot_info.as_ref().map_or(false, |info| info.historical),
+				})
+			}).collect()
+		}).unwrap_or_else(Vec::new);
+
+		NetworkState {
+			peers,
+			bandwidth: self.handler.protocol.bandwidth_sinks().by_protocol(),
+			failures: self.handler.protocol.failures(),
+			discovery: DiscoveryState {
+				external_url: self.network.external_url(),
+				enabled: self.discovery_enabled,
+				boot_nodes: self.boot_nodes.clone(),
+				reserved_nodes: peer_set.reserved_nodes(),
+				reserved_only: peer_set.reserved_only(),
+			},
+		}
+	}
+
+	/// Get the counters of the bytes exchanged over each protocol.
+	pub fn bandwidth(&self) -> BandwidthSinks {
+		self.handler.protocol.bandwidth_sinks()
+	}
+
+	/// Get a handle for changing the reserved nodes and connection slots.
+	pub fn peer_set(&self) -> PeerSetHandle {
+		PeerSetHandle {
+			network: self.network.clone(),
+			peer_set: self.handler.protocol.peer_set(),
+		}
+	}
+
 	/// Called when a new block is imported by the client.
 	pub fn on_block_imported(&self, hash: B::Hash, header: &B::Header) {
-		self.network.with_context(DOT_PROTOCOL_ID, |context| {
-			self.handler.protocol.on_block_imported(&mut NetSyncIo::new(context), hash, header)
-		});
+		self.actions.dispatch(NetworkAction::BlockImported(hash, header.clone()));
 	}
 
 	/// Called when new transactons are imported by the client.
 	pub fn trigger_repropagate(&self) {
-		self.network.with_context(DOT_PROTOCOL_ID, |context| {
-			self.handler.protocol.propagate_transactions(&mut NetSyncIo::new(context));
-		});
+		self.actions.dispatch_or_drop(NetworkAction::PropagateTransactions);
+	}
+
+	/// Stop importing downloaded blocks, e.g. while the database is busy with
+	/// heavy work. Downloaded blocks are kept, and downloading stops once the
+	/// queue limits are reached.
+	pub fn pause_import(&self) {
+		self.handler.protocol.pause_import();
+	}
+
+	/// Import the blocks downloaded while import was paused, and carry on syncing.
+	pub fn resume_import(&self) {
+		self.actions.dispatch(NetworkAction::ResumeImport);
+	}
+
+	/// Execute a closure with the context of a registered application protocol.
+	/// Does nothing if the network isn't running.
+	pub fn with_application_protocol<F: FnOnce(&mut ProtocolContext)>(&self, id: ProtocolId, f: F) {
+		let sinks = self.handler.protocol.bandwidth_sinks();
+		self.network.with_context(id, |context| f(&mut MeteredContext::new(&mut NetSyncIo::new(context), id, &sinks)));
 	}
 
 	fn start(&self) {
@@ -183,6 +448,11 @@ impl<B: BlockT + 'static> Service<B> where B::Header: HeaderT<Number=u64> {
 		};
 		self.network.register_protocol(self.handler.clone(), DOT_PROTOCOL_ID, &[(0, V0_PACKET_COUNT)])
 			.unwrap_or_else(|e| warn!("Error registering polkadot protocol: {:?}", e));
+
+		for &(id, ref handler) in &self.application_handlers {
+			self.network.register_protocol(handler.clone(), id, &[(0, registry::PACKET_COUNT)])
+				.unwrap_or_else(|e| warn!("Error registering protocol {}: {:?}", String::from_utf8_lossy(&id[..]), e));
+		}
 	}
 
 	fn stop(&self) {
@@ -191,17 +461,41 @@ impl<B: BlockT + 'static> Service<B> where B::Header: HeaderT<Number=u64> {
 	}
 }
 
+// process queued actions until the service is stopped or dropped.
+fn process_actions<B: BlockT + 'static>(
+	network: Weak<NetworkService>,
+	handler: Weak<ProtocolHandler<B>>,
+	actions: ActionReceiver<B>,
+) where B::Header: HeaderT<Number=u64> {
+	for event in actions.wait() {
+		let action = match event {
+			Ok(Event::Action(action)) => action,
+			Ok(Event::Stop) | Err(()) => break,
+		};
+
+		let (network, handler) = match (network.upgrade(), handler.upgrade()) {
+			(Some(network), Some(handler)) => (network, handler),
+			_ => break,
+		};
+
+		network.with_context(DOT_PROTOCOL_ID, |context| {
+			handler.handle_action(&mut NetSyncIo::new(context), action)
+		});
+	}
+
+	trace!(target: "sync", "Network action loop finished");
+}
+
 impl<B: BlockT + 'static> Drop for Service<B> where B::Header: HeaderT<Number=u64> {
 	fn drop(&mut self) {
+		self.actions.stop();
 		self.stop();
 	}
 }
 
-impl<B: BlockT + 'static> ExecuteInContext<B> for Service<B> where B::Header: HeaderT<Number=u64> {
-	fn execute_in_context<F: Fn(&mut NetSyncIo, &Protocol<B>)>(&self, closure: F) {
-		self.network.with_context(DOT_PROTOCOL_ID, |context| {
-			closure(&mut NetSyncIo::new(context), &self.handler.protocol)
-		});
+impl<B: BlockT + 'static> ExecuteInProtocol for Service<B> where B::Header: HeaderT<Number=u64> {
+	fn execute_in_protocol(&self, id: ProtocolId, f: &mut FnMut(&mut ProtocolContext)) {
+		self.with_application_protocol(id, |ctx| f(ctx));
 	}
 }
 
@@ -237,6 +531,10 @@ impl<B: BlockT + 'static> SyncProvider<B> for Service<B> where B::Header: Header
 	fn node_id(&self) -> Option<String> {
 		self.network.external_url()
 	}
+
+	fn misbehaving_peers(&self) -> Vec<PeerRecord> {
+		self.handler.protocol.misbehaving_peers()
+	}
 }
 
 /// ConsensusService
@@ -250,9 +548,41 @@ impl<B: BlockT + 'static> ConsensusService<B> for Service<B> where B::Header: He
 	}
 
 	fn send_bft_message(&self, message: LocalizedBftMessage<B>) {
-		self.network.with_context(DOT_PROTOCOL_ID, |context| {
-			self.handler.protocol.send_bft_message(&mut NetSyncIo::new(context), message);
-		});
+		self.actions.dispatch(NetworkAction::BftMessage(message));
+	}
+
+	fn heartbeats(&self) -> HeartbeatStream {
+		self.handler.protocol.heartbeats()
+	}
+
+	fn send_heartbeat(&self, heartbeat: Heartbeat) {
+		self.actions.dispatch(NetworkAction::Heartbeat(heartbeat));
+	}
+
+	fn gossip_messages(&self, topic: B::Hash) -> GossipMessageStream {
+		self.handler.protocol.gossip_messages(topic)
+	}
+
+	fn send_gossip(&self, topic: B::Hash, data: Vec<u8>) {
+		self.actions.dispatch(NetworkAction::Gossip(topic, data));
+	}
+
+	fn clock_skew(&self) -> Option<i64> {
+		self.handler.protocol.clock_skew()
+	}
+}
+
+impl<B: BlockT + 'static> ProtocolHandler<B> where B::Header: HeaderT<Number=u64> {
+	fn handle_action(&self, io: &mut NetSyncIo, action: NetworkAction<B>) {
+		match action {
+			NetworkAction::BlockImported(hash, header) => self.protocol.on_block_imported(io, hash, &header),
+			NetworkAction::PropagateTransactions => self.protocol.propagate_transactions(io),
+			NetworkAction::SendMessage(peer, message) => self.protocol.send_message(io, peer, message),
+			NetworkAction::BftMessage(message) => self.protocol.send_bft_message(io, message),
+			NetworkAction::Heartbeat(heartbeat) => self.protocol.send_heartbeat(io, heartbeat),
+			NetworkAction::Gossip(topic, data) => self.protocol.send_gossip(io, topic, data),
+			NetworkAction::ResumeImport => self.protocol.resume_import(io),
+		}
 	}
 }
 
@@ -303,21 +633,82 @@ pub trait ManageNetwork: Send + Sync {
 }
 
 
+/// Handle for changing the reserved nodes and connection slots at runtime.
+/// Connected peers which no longer fit are disconnected at the next tick.
+#[derive(Clone)]
+pub struct PeerSetHandle {
+	network: Arc<NetworkService>,
+	peer_set: PeerSet,
+}
+
+impl PeerSetHandle {
+	/// Reserve the node at an `enode://` address, connecting to it and
+	/// keeping it connected.
+	pub fn add_reserved_peer(&self, peer: &str) -> Result<(), String> {
+		self.network.add_reserved_peer(peer).map_err(|e| format!("{:?}", e))?;
+		self.peer_set.add_reserved(peer);
+		Ok(())
+	}
+
+	/// Remove the reservation of the node at an `enode://` address.
+	pub fn remove_reserved_peer(&self, peer: &str) -> Result<(), String> {
+		self.network.remove_reserved_peer(peer).map_err(|e| format!("{:?}", e))?;
+		self.peer_set.remove_reserved(peer);
+		Ok(())
+	}
+
+	/// Accept reserved nodes only, or any node.
+	pub fn set_reserved_only(&self, reserved_only: bool) {
+		self.peer_set.set_reserved_only(reserved_only);
+		self.network.set_non_reserved_mode(if reserved_only {
+			NonReservedPeerMode::Deny
+		} else {
+			NonReservedPeerMode::Accept
+		});
+	}
+
+	/// Whether only reserved nodes are accepted.
+	pub fn is_reserved_only(&self) -> bool {
+		self.peer_set.reserved_only()
+	}
+
+	/// Change the connection slots for peers which aren't reserved.
+	pub fn set_slots(&self, slots: PeerSlots) {
+		self.peer_set.set_slots(slots);
+	}
+
+	/// Connection slots for peers which aren't reserved.
+	pub fn slots(&self) -> PeerSlots {
+		self.peer_set.slots()
+	}
+
+	/// Change the limits on the connections of peers which aren't reserved.
+	/// They apply to the peers connecting from then on.
+	pub fn set_limits(&self, limits: ConnectionLimits) {
+		self.peer_set.set_limits(limits);
+	}
+
+	/// Limits on the connections of peers which aren't reserved.
+	pub fn limits(&self) -> ConnectionLimits {
+		self.peer_set.limits()
+	}
+}
+
 impl<B: BlockT + 'static> ManageNetwork for Service<B> where B::Header: HeaderT<Number=u64> {
 	fn accept_unreserved_peers(&self) {
-		self.network.set_non_reserved_mode(NonReservedPeerMode::Accept);
+		self.peer_set().set_reserved_only(false);
 	}
 
 	fn deny_unreserved_peers(&self) {
-		self.network.set_non_reserved_mode(NonReservedPeerMode::Deny);
+		self.peer_set().set_reserved_only(true);
 	}
 
 	fn remove_reserved_peer(&self, peer: String) -> Result<(), String> {
-		self.network.remove_reserved_peer(&peer).map_err(|e| format!("{:?}", e))
+		self.peer_set().remove_reserved_peer(&peer)
 	}
 
 	fn add_reserved_peer(&self, peer: String) -> Result<(), String> {
-		self.network.add_reserved_peer(&peer).map_err(|e| format!("{:?}", e))
+		self.peer_set().add_reserved_peer(&peer)
 	}
 
 	fn start_network(&self) {
@@ -0,0 +1,213 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Download of the state of a trusted block, so that a new node can sync on
+//! top of it without downloading and executing the blocks before it.
+//!
+//! The header and justification of the block are fetched first, then the
+//! storage entries in chunks of ascending keys, one request at a time. The
+//! client checks the entries against the state root of the header on import.
+
+use std::collections::{HashMap, HashSet};
+use network::PeerId;
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
+use runtime_primitives::bft::Justification;
+use message::{self, generic::Message as GenericMessage, Message};
+
+// Maximum storage entries to request in a single packet.
+const MAX_STATE_ENTRIES_TO_REQUEST: u32 = 1024;
+
+/// Download of the state of a block.
+pub struct StateSync<B: BlockT> {
+	target: B::Hash,
+	header: Option<(B::Header, Justification<B::Hash>)>,
+	entries: Vec<(Vec<u8>, Vec<u8>)>,
+	complete: bool,
+	// peers which provided entries, with their node IDs.
+	sources: HashMap<PeerId, Option<String>>,
+	// peers which failed to provide what was asked of them.
+	unable: HashSet<PeerId>,
+}
+
+impl<B: BlockT> StateSync<B> where
+	B::Header: HeaderT<Number=u64>,
+{
+	/// Start downloading the state of the block with the given hash.
+	pub fn new(target: B::Hash) -> Self {
+		StateSync {
+			target,
+			header: None,
+			entries: Vec::new(),
+			complete: false,
+			sources: HashMap::new(),
+			unable: HashSet::new(),
+		}
+	}
+
+	/// Hash of the block whose state is downloaded.
+	pub fn target(&self) -> &B::Hash {
+		&self.target
+	}
+
+	/// Number of the block whose state is downloaded, once its header is known.
+	pub fn number(&self) -> Option<u64> {
+		self.header.as_ref().map(|&(ref header, _)| *header.number())
+	}
+
+	/// Whether the peer may be asked for the next piece of data.
+	pub fn can_request_from(&self, peer_id: PeerId, best_number: u64) -> bool {
+		!self.unable.contains(&peer_id) && self.number().map_or(true, |n| best_number >= n)
+	}
+
+	/// Whether all entries are downloaded.
+	pub fn is_complete(&self) -> bool {
+		self.complete
+	}
+
+	/// The next request to make.
+	pub fn next_request(&self) -> Message<B> {
+		if self.header.is_none() {
+			return GenericMessage::BlockRequest(message::generic::BlockRequest {
+				id: 0,
+				fields: vec![message::BlockAttribute::Header, message::BlockAttribute::Justification],
+				from: message::FromBlock::Hash(self.target),
+				to: None,
+				direction: message::Direction::Ascending,
+				max: Some(1),
+			});
+		}
+
+		GenericMessage::StateRequest(message::generic::StateRequest {
+			id: 0,
+			block: self.target,
+			start: self.entries.last().map(|&(ref key, _)| key.clone()).unwrap_or_default(),
+			max: MAX_STATE_ENTRIES_TO_REQUEST,
+		})
+	}
+
+	/// Handle the response to a header request. Returns `false` if the peer
+	/// didn't provide the header with its justification.
+	pub fn on_header(&mut self, peer_id: PeerId, blocks: Vec<message::BlockData<B>>) -> bool {
+		let target = self.target;
+		let block = blocks.into_iter().find(|b| b.hash == target);
+		match block.map(|b| (b.header, b.justification)) {
+			Some((Some(header), Some(justification))) => {
+				if header.hash() != target {
+					self.unable.insert(peer_id);
+					return false;
+				}
+				self.header = Some((header, justification));
+				true
+			},
+			_ => {
+				self.unable.insert(peer_id);
+				false
+			}
+		}
+	}
+
+	/// Handle a chunk of storage entries. Returns `false` if the peer didn't
+	/// provide any or provided them out of order.
+	pub fn on_entries(&mut self, peer_id: PeerId, peer_node: Option<String>, response: message::StateResponse) -> bool {
+		let in_order = {
+			let mut last = self.entries.last().map(|&(ref key, _)| key);
+			response.entries.iter().all(|&(ref key, _)| {
+				let next = last.map_or(true, |last| key > last);
+				last = Some(key);
+				next
+			})
+		};
+
+		if !in_order || (response.entries.is_empty() && !response.complete) {
+			self.unable.insert(peer_id);
+			return false;
+		}
+
+		self.entries.extend(response.entries);
+		self.complete = response.complete;
+		self.sources.insert(peer_id, peer_node);
+		true
+	}
+
+	/// Peers which provided entries, with their node IDs.
+	pub fn sources(&self) -> &HashMap<PeerId, Option<String>> {
+		&self.sources
+	}
+
+	/// The header, justification and storage entries of the block, once complete.
+	pub fn into_state(self) -> Option<(B::Header, Justification<B::Hash>, Vec<(Vec<u8>, Vec<u8>)>)> {
+		match (self.complete, self.header) {
+			(true, Some((header, justification))) => Some((header, justification, self.entries)),
+			_ => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use test_client::runtime::{Block, Header};
+	use message::StateResponse;
+
+	fn state_sync() -> StateSync<Block> {
+		let header = Header {
+			parent_hash: Default::default(),
+			number: 5,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Default::default(),
+		};
+		let mut sync = StateSync::new(header.hash());
+		let justification = Justification {
+			round_number: 0,
+			hash: header.hash(),
+			signatures: Vec::new(),
+		};
+		sync.header = Some((header, justification));
+		sync
+	}
+
+	fn response(keys: &[u8], complete: bool) -> StateResponse {
+		StateResponse {
+			id: 0,
+			entries: keys.iter().map(|k| (vec![*k], vec![*k])).collect(),
+			complete,
+		}
+	}
+
+	#[test]
+	fn entries_are_requested_after_the_last_key() {
+		let mut sync = state_sync();
+		assert!(sync.on_entries(0, None, response(&[1, 2], false)));
+		match sync.next_request() {
+			GenericMessage::StateRequest(r) => assert_eq!(r.start, vec![2]),
+			_ => panic!("entries are requested once the header is known"),
+		}
+
+		// out of order or no progress
+		assert!(!sync.on_entries(1, None, response(&[2, 3], false)));
+		assert!(!sync.on_entries(2, None, response(&[], false)));
+		assert!(!sync.can_request_from(1, 10));
+		assert!(sync.can_request_from(3, 10));
+		assert!(!sync.can_request_from(3, 4));
+
+		assert!(sync.on_entries(3, None, response(&[3], true)));
+		assert!(sync.is_complete());
+		assert_eq!(sync.sources().len(), 2);
+		let (_, _, entries) = sync.into_state().unwrap();
+		assert_eq!(entries.len(), 3);
+	}
+}
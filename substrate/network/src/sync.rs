@@ -14,26 +14,40 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.?
 
-use std::collections::HashMap;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 use io::SyncIo;
 use protocol::Protocol;
 use network::PeerId;
-use client::{ImportResult, BlockStatus, ClientInfo};
-use blocks::{self, BlockCollection};
+use client::{ImportResult, BlockStatus, ClientInfo, JustifiedHeader};
+use blocks::{self, BlockCollection, QueueLimits};
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
 use runtime_primitives::generic::BlockId;
 use message::{self, generic::Message as GenericMessage};
 use service::Role;
+use verification::{self, VERIFICATION_THREADS};
+use reputation::{self, Misbehavior};
+use progress::ImportProgress;
+use state_sync::StateSync;
+use sync_state::{SyncSnapshot, PeerQuality, SYNC_STATE_KEY};
+use chain::Client;
+use primitives::AuthorityId;
 
-// Maximum blocks to request in a single packet.
-const MAX_BLOCKS_TO_REQUEST: usize = 128;
+// Interval between writes of the sync state to the database.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(30);
 
 struct PeerSync<B: BlockT> {
 	pub common_hash: B::Hash,
 	pub common_number: <B::Header as HeaderT>::Number,
 	pub best_hash: B::Hash,
 	pub best_number: <B::Header as HeaderT>::Number,
+	// latest block the peer reported final, if any.
+	pub finalized_number: Option<<B::Header as HeaderT>::Number>,
 	pub state: PeerSyncState<B>,
+	// block announced by hash while the peer was busy, whose header is yet to be requested.
+	pub announced: Option<B::Hash>,
+	pub node_id: Option<String>,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -42,6 +56,40 @@ enum PeerSyncState<B: BlockT> {
 	Available,
 	DownloadingNew(<B::Header as HeaderT>::Number),
 	DownloadingStale(B::Hash),
+	DownloadingJustification(B::Hash),
+	DownloadingState,
+	DownloadingAnnounced(B::Hash),
+	// didn't answer the last request in time; the answer is dropped if it comes.
+	Stalled,
+}
+
+// A downloaded block which came without its justification, kept until a peer
+// provides it.
+struct PendingJustification<B: BlockT> {
+	block: blocks::BlockData<B>,
+	// peers asked for the justification, including the one the block came from.
+	requested_from: HashSet<PeerId>,
+}
+
+/// What sync downloads and imports.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SyncMode {
+	/// Headers, justifications and bodies of blocks.
+	Full,
+	/// Headers and justifications only; bodies are not downloaded.
+	Headers,
+}
+
+impl SyncMode {
+	/// The mode for a node with the given roles: nodes which execute blocks
+	/// need their bodies, light nodes only follow headers.
+	pub fn for_role(role: Role) -> Self {
+		if role.intersects(Role::FULL | Role::VALIDATOR | Role::COLLATOR) {
+			SyncMode::Full
+		} else {
+			SyncMode::Headers
+		}
+	}
 }
 
 /// Relay chain sync strategy.
@@ -49,9 +97,20 @@ pub struct ChainSync<B: BlockT> {
 	genesis_hash: B::Hash,
 	peers: HashMap<PeerId, PeerSync<B>>,
 	blocks: BlockCollection<B>,
+	queue_limits: QueueLimits,
+	progress: ImportProgress,
 	best_queued_number: u64,
 	best_queued_hash: B::Hash,
+	mode: SyncMode,
 	required_block_attributes: Vec<message::BlockAttribute>,
+	pending_justification: Option<PendingJustification<B>>,
+	state_sync: Option<StateSync<B>>,
+	// best block seen before the last restart, until peers report theirs.
+	restored_target: Option<u64>,
+	peer_quality: HashMap<String, PeerQuality>,
+	persisted_at: Instant,
+	// downloaded blocks outside of the collection, left unimported while import is paused.
+	paused_blocks: Vec<blocks::BlockData<B>>,
 }
 
 /// Reported sync state.
@@ -70,33 +129,126 @@ pub struct Status<B: BlockT> {
 	pub state: SyncState,
 	/// Target sync block number.
 	pub best_seen_block: Option<<B::Header as HeaderT>::Number>,
+	/// Number of downloaded blocks waiting to be imported.
+	pub queued_blocks: usize,
+	/// Whether the downloaded blocks have reached the queue limits.
+	pub queue_saturated: bool,
+	/// Blocks imported per second over the last few seconds.
+	pub blocks_per_second: f64,
+	/// Bytes of block data imported per second over the last few seconds.
+	pub bytes_per_second: f64,
+	/// Estimated time to import up to the best block seen, if any are being imported.
+	pub eta: Option<Duration>,
 }
 
 impl<B: BlockT> ChainSync<B> where
 	B::Header: HeaderT<Number=u64>,
 {
-	/// Create a new instance.
-	pub fn new(role: Role, info: &ClientInfo<B>) -> Self {
+	/// Create a new instance. A node at genesis first downloads the state of
+	/// `state_target`, if given, and syncs blocks from there. Progress stored
+	/// before a restart is resumed from `snapshot`.
+	pub fn new(
+		mode: SyncMode,
+		info: &ClientInfo<B>,
+		queue_limits: QueueLimits,
+		state_target: Option<B::Hash>,
+		snapshot: Option<SyncSnapshot>,
+	) -> Self {
 		let mut required_block_attributes = vec![
 			message::BlockAttribute::Header,
 			message::BlockAttribute::Justification
 		];
-		if role.intersects(Role::FULL | Role::VALIDATOR | Role::COLLATOR) {
+		if mode == SyncMode::Full {
 			required_block_attributes.push(message::BlockAttribute::Body);
 		}
 
+		let best_number = info.best_queued_number.unwrap_or(info.chain.best_number);
+		let state_sync = match state_target {
+			Some(target) if best_number == 0 => {
+				info!(target: "sync", "Downloading the state of block {} before syncing", target);
+				Some(StateSync::new(target))
+			},
+			_ => None,
+		};
+
+		let snapshot = snapshot.unwrap_or_default();
+		if snapshot.best_imported > info.chain.best_number {
+			warn!(target: "sync", "Best block #{} is behind #{} imported before the restart", info.chain.best_number, snapshot.best_imported);
+		}
+		let restored_target = snapshot.target.and_then(|target| if target > best_number { Some(target) } else { None });
+		if let Some(target) = restored_target {
+			info!(target: "sync", "Resuming sync from #{} towards #{}", best_number, target);
+		}
+
 		ChainSync {
 			genesis_hash: info.chain.genesis_hash,
 			peers: HashMap::new(),
 			blocks: BlockCollection::new(),
+			queue_limits: queue_limits,
+			progress: ImportProgress::new(Instant::now()),
 			best_queued_hash: info.best_queued_hash.unwrap_or(info.chain.best_hash),
 			best_queued_number: info.best_queued_number.unwrap_or(info.chain.best_number),
+			mode: mode,
 			required_block_attributes: required_block_attributes,
+			pending_justification: None,
+			state_sync: state_sync,
+			restored_target: restored_target,
+			peer_quality: snapshot.peers,
+			persisted_at: Instant::now(),
+			paused_blocks: Vec::new(),
 		}
 	}
 
+	/// What is downloaded and imported.
+	pub fn mode(&self) -> SyncMode {
+		self.mode
+	}
+
 	fn best_seen_block(&self) -> Option<u64> {
-		self.peers.values().max_by_key(|p| p.best_number).map(|p| p.best_number)
+		self.peers.values().max_by_key(|p| p.best_number).map(|p| p.best_number).or(self.restored_target)
+	}
+
+	// highest block reported final by a peer. Until it is imported, blocks are
+	// only downloaded up to what their peers report final, so that catch-up
+	// doesn't follow a longer chain which isn't final.
+	fn finalized_target(&self) -> Option<u64> {
+		self.peers.values().filter_map(|p| p.finalized_number).max()
+	}
+
+	fn peer_score(&self, peer: &PeerSync<B>) -> i64 {
+		peer.node_id.as_ref().and_then(|node| self.peer_quality.get(node)).map_or(0, PeerQuality::score)
+	}
+
+	/// Store the sync progress, so that it's resumed after a restart.
+	pub fn persist(&mut self, chain: &Client<B>) {
+		let best_imported = match chain.info() {
+			Ok(info) => info.chain.best_number,
+			Err(_) => self.best_queued_number,
+		};
+		let mut snapshot = SyncSnapshot {
+			best_imported,
+			target: self.best_seen_block(),
+			peers: self.peer_quality.clone(),
+		};
+		snapshot.prune_peers();
+
+		if let Err(e) = chain.insert_aux(SYNC_STATE_KEY, &snapshot.encode()) {
+			debug!(target: "sync", "Error storing sync state: {:?}", e);
+		}
+		self.peer_quality = snapshot.peers;
+		self.persisted_at = Instant::now();
+	}
+
+	// number and size of the downloaded blocks waiting to be imported.
+	fn queued(&self) -> (usize, usize) {
+		let (blocks, bytes) = self.blocks.queued();
+		let paused_bytes: usize = self.paused_blocks.iter().map(|b| blocks::data_size(&b.block)).sum();
+		(blocks + self.paused_blocks.len(), bytes + paused_bytes)
+	}
+
+	fn is_saturated(&self) -> bool {
+		let (blocks, bytes) = self.queued();
+		blocks >= self.queue_limits.max_blocks || bytes >= self.queue_limits.max_bytes
 	}
 
 	/// Returns sync status
@@ -106,14 +258,26 @@ impl<B: BlockT> ChainSync<B> where
 			&Some(n) if n > self.best_queued_number && n - self.best_queued_number > 5 => SyncState::Downloading,
 			_ => SyncState::Idle,
 		};
+		let now = Instant::now();
+		let (blocks_per_second, bytes_per_second) = self.progress.rates(now);
+		let eta = match best_seen {
+			Some(n) if n > self.best_queued_number => self.progress.eta(n - self.best_queued_number, now),
+			_ => None,
+		};
 		Status {
 			state: state,
 			best_seen_block: best_seen,
+			queued_blocks: self.queued().0,
+			queue_saturated: self.is_saturated(),
+			blocks_per_second: blocks_per_second,
+			bytes_per_second: bytes_per_second,
+			eta: eta,
 		}
 	}
 
 	/// Handle new connected peer.
 	pub fn new_peer(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId) {
+		let node_id = io.peer_node_id(peer_id);
 		if let Some(info) = protocol.peer_info(peer_id) {
 			match (protocol.chain().block_status(&BlockId::Hash(info.best_hash)), info.best_number) {
 				(Err(e), _) => {
@@ -137,7 +301,10 @@ impl<B: BlockT> ChainSync<B> where
 							common_number: 0,
 							best_hash: info.best_hash,
 							best_number: info.best_number,
+							finalized_number: info.finalized.map(|(number, _)| number),
 							state: PeerSyncState::AncestorSearch(our_best),
+							announced: None,
+							node_id: node_id,
 						});
 						Self::request_ancestry(io, protocol, peer_id, our_best)
 					} else {
@@ -148,7 +315,10 @@ impl<B: BlockT> ChainSync<B> where
 							common_number: 0,
 							best_hash: info.best_hash,
 							best_number: info.best_number,
+							finalized_number: info.finalized.map(|(number, _)| number),
 							state: PeerSyncState::Available,
+							announced: None,
+							node_id: node_id,
 						});
 						self.download_new(io, protocol, peer_id)
 					}
@@ -160,7 +330,10 @@ impl<B: BlockT> ChainSync<B> where
 						common_number: info.best_number,
 						best_hash: info.best_hash,
 						best_number: info.best_number,
+						finalized_number: info.finalized.map(|(number, _)| number),
 						state: PeerSyncState::Available,
+						announced: None,
+						node_id: node_id,
 					});
 				}
 			}
@@ -169,23 +342,57 @@ impl<B: BlockT> ChainSync<B> where
 
 	pub fn on_block_data(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId, _request: message::BlockRequest<B>, response: message::BlockResponse<B>) {
 		let count = response.blocks.len();
-		let mut imported: usize = 0;
+		let node_id = io.peer_node_id(peer_id);
+		if self.peers.get(&peer_id).map_or(false, |p| p.state == PeerSyncState::DownloadingState) {
+			self.on_state_header(io, protocol, peer_id, response.blocks);
+			return;
+		}
+
+		let mut announced = None;
 		let new_blocks = if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
 			match peer.state {
 				PeerSyncState::DownloadingNew(start_block) => {
 					self.blocks.clear_peer_download(peer_id);
 					peer.state = PeerSyncState::Available;
 
-					self.blocks.insert(start_block, response.blocks, peer_id);
-					self.blocks.drain(self.best_queued_number + 1)
+					if let Some(ref node) = node_id {
+						self.peer_quality.entry(node.clone()).or_insert_with(PeerQuality::default).blocks += count as u64;
+					}
+					self.blocks.insert(start_block, response.blocks, peer_id, node_id);
+					// while import is paused, the blocks wait in the collection.
+					if protocol.is_import_paused() {
+						Vec::new()
+					} else {
+						self.blocks.drain(self.best_queued_number + 1)
+					}
 				},
 				PeerSyncState::DownloadingStale(_) => {
 					peer.state = PeerSyncState::Available;
 					response.blocks.into_iter().map(|b| blocks::BlockData {
 						origin: peer_id,
+						origin_node: node_id.clone(),
 						block: b
 					}).collect()
 				},
+				PeerSyncState::DownloadingJustification(hash) => {
+					peer.state = PeerSyncState::Available;
+					let justification = response.blocks.into_iter()
+						.find(|b| b.hash == hash)
+						.and_then(|b| b.justification);
+					match (justification, self.pending_justification.take()) {
+						(Some(justification), Some(mut pending)) if pending.block.block.hash == hash => {
+							trace!(target: "sync", "Got justification for block {} from {}", hash, peer_id);
+							pending.block.block.justification = Some(justification);
+							vec![pending.block]
+						},
+						(_, pending) => {
+							// another peer is asked when downloading below.
+							trace!(target: "sync", "No justification for block {} from {}", hash, peer_id);
+							self.pending_justification = pending;
+							Vec::new()
+						}
+					}
+				},
 				PeerSyncState::AncestorSearch(n) => {
 					match response.blocks.get(0) {
 						Some(ref block) => {
@@ -226,17 +433,91 @@ impl<B: BlockT> ChainSync<B> where
 						}
 					}
 				},
-				PeerSyncState::Available => Vec::new(),
+				PeerSyncState::DownloadingAnnounced(hash) => {
+					peer.state = PeerSyncState::Available;
+					announced = response.blocks.into_iter()
+						.find(|b| b.hash == hash)
+						.and_then(|b| b.header)
+						.and_then(|header| if header.hash() == hash { Some((hash, header)) } else { None });
+					if announced.is_none() {
+						trace!(target: "sync", "Header of announced block {} was not provided by {}", hash, peer_id);
+					}
+					Vec::new()
+				},
+				PeerSyncState::Stalled => {
+					trace!(target: "sync", "Dropping late response from {}", peer_id);
+					peer.state = PeerSyncState::Available;
+					Vec::new()
+				},
+				PeerSyncState::Available | PeerSyncState::DownloadingState => Vec::new(),
 			}
 		} else {
 			vec![]
 		};
 
+		// continue as if the header was announced.
+		if let Some((hash, header)) = announced {
+			if protocol.validate_announce(io, peer_id, &hash, &header) {
+				self.on_block_announce(io, protocol, peer_id, hash, &header);
+			}
+		}
+
+		let (imported, imported_bytes) = match self.import_blocks(io, protocol, new_blocks) {
+			Some(imported) => imported,
+			None => return,
+		};
+		trace!(target: "sync", "Imported {} of {}", imported, count);
+		self.progress.note_imported(imported, imported_bytes, Instant::now());
+		if imported > 0 {
+			protocol.report_peer(io, peer_id, reputation::USEFUL_RESPONSE);
+		}
+		self.maintain_sync(io, protocol);
+	}
+
+	/// Import the blocks downloaded while import was paused, and carry on.
+	pub fn resume_import(&mut self, io: &mut SyncIo, protocol: &Protocol<B>) {
+		let mut blocks = ::std::mem::replace(&mut self.paused_blocks, Vec::new());
+		blocks.extend(self.blocks.drain(self.best_queued_number + 1));
+		blocks.sort_by_key(|b| b.block.header.as_ref().map(|h| *h.number()));
+		debug!(target: "sync", "Resuming import of {} downloaded blocks", blocks.len());
+
+		if let Some((imported, imported_bytes)) = self.import_blocks(io, protocol, blocks) {
+			self.progress.note_imported(imported, imported_bytes, Instant::now());
+			self.maintain_sync(io, protocol);
+		}
+	}
+
+	// Import downloaded blocks, in ascending order. Returns the number of
+	// blocks imported and their size, or `None` if sync was restarted or is
+	// shutting down. Blocks left when import is paused are kept until it resumes.
+	fn import_blocks(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, new_blocks: Vec<blocks::BlockData<B>>) -> Option<(usize, usize)> {
+		let mut imported: usize = 0;
+		let mut imported_bytes: usize = 0;
+		let new_blocks = Self::drop_known(protocol, new_blocks);
 		let best_seen = self.best_seen_block();
+		let (verified_with, verified) = Self::verify_justifications(protocol, &new_blocks);
+		let mut verified = verified.into_iter();
+
 		// Blocks in the response/drain should be in ascending order.
-		for block in new_blocks {
+		let total = new_blocks.len();
+		let mut new_blocks = new_blocks.into_iter().enumerate();
+		while let Some((i, block)) = new_blocks.next() {
+			if protocol.is_import_stopped() {
+				debug!(target: "sync", "Shutting down, leaving {} downloaded blocks unimported", total - i);
+				return None;
+			}
+			if protocol.is_import_paused() {
+				debug!(target: "sync", "Import paused, keeping {} downloaded blocks", total - i);
+				self.paused_blocks.push(block);
+				self.paused_blocks.extend(new_blocks.map(|(_, block)| block));
+				return Some((imported, imported_bytes));
+			}
+
+			let size = blocks::data_size(&block.block);
 			let origin = block.origin;
+			let origin_node = block.origin_node;
 			let block = block.block;
+			let justified = verified.next().and_then(|v| v);
 			match (block.header, block.justification) {
 				(Some(header), Some(justification)) => {
 					let number = header.number().clone();
@@ -244,6 +525,13 @@ impl<B: BlockT> ChainSync<B> where
 					let parent = header.parent_hash().clone();
 					let is_best = best_seen.as_ref().map_or(false, |n| number >= *n);
 
+					if protocol.conflicts_with_checkpoint(number, &hash) {
+						debug!(target: "sync", "Block {}: {:?} conflicts with a checkpoint", number, hash);
+						protocol.punish_origin(io, origin, origin_node.as_ref().map(String::as_str), Misbehavior::ConflictingCheckpoint);
+						self.restart(io, protocol);
+						return None;
+					}
+
 					// check whether the block is known before importing.
 					match protocol.chain().block_status(&BlockId::Hash(hash)) {
 						Ok(BlockStatus::InChain) => continue,
@@ -251,16 +539,32 @@ impl<B: BlockT> ChainSync<B> where
 						Err(e) => {
 							debug!(target: "sync", "Error importing block {}: {:?}: {:?}", number, hash, e);
 							self.restart(io, protocol);
-							return;
+							return None;
 						}
 					}
 
-					let result = protocol.chain().import(
-						is_best,
-						header,
-						justification,
-						block.body.map(|b| b.to_extrinsics()),
-					);
+					let body = block.body.map(|b| b.to_extrinsics());
+					if let Err(reason) = protocol.verify_block(&header, &justification, body.as_ref().map(|b| &b[..])) {
+						debug!(target: "sync", "Block {}: {:?} refused: {}", number, hash, reason);
+						protocol.punish_origin(io, origin, origin_node.as_ref().map(String::as_str), Misbehavior::BadBlock);
+						self.restart(io, protocol);
+						return None;
+					}
+
+					// the justification was checked ahead of time against the authorities
+					// at the start of the batch, which only holds if they are unchanged.
+					let justified = if justified.is_some()
+						&& protocol.chain().authorities(&BlockId::Hash(parent)).ok().as_ref() == Some(&verified_with)
+					{
+						justified
+					} else {
+						None
+					};
+
+					let result = match justified {
+						Some(justified) => protocol.chain().import_justified(is_best, justified, body),
+						None => protocol.chain().import(is_best, header, justification, body),
+					};
 					match result {
 						Ok(ImportResult::AlreadyInChain) => {
 							trace!(target: "sync", "Block already in chain {}: {:?}", number, hash);
@@ -274,44 +578,234 @@ impl<B: BlockT> ChainSync<B> where
 							trace!(target: "sync", "Block queued {}: {:?}", number, hash);
 							self.block_imported(&hash, number);
 							imported = imported + 1;
+							imported_bytes = imported_bytes + size;
 						},
 						Ok(ImportResult::UnknownParent) => {
 							debug!(target: "sync", "Block with unknown parent {}: {:?}, parent: {:?}", number, hash, parent);
 							self.restart(io, protocol);
-							return;
+							return None;
 						},
 						Ok(ImportResult::KnownBad) => {
 							debug!(target: "sync", "Bad block {}: {:?}", number, hash);
-							io.disable_peer(origin); //TODO: use persistent ID
+							protocol.punish_origin(io, origin, origin_node.as_ref().map(String::as_str), Misbehavior::BadBlock);
 							self.restart(io, protocol);
-							return;
+							return None;
 						}
 						Err(e) => {
 							debug!(target: "sync", "Error importing block {}: {:?}: {:?}", number, hash, e);
 							self.restart(io, protocol);
-							return;
+							return None;
 						}
 					}
 				},
 				(None, _) => {
 					debug!(target: "sync", "Header {} was not provided by {} ", block.hash, origin);
-					io.disable_peer(origin); //TODO: use persistent ID
-					return;
+					protocol.punish_origin(io, origin, origin_node.as_ref().map(String::as_str), Misbehavior::ProtocolViolation);
+					return None;
 				},
-				(_, None) => {
+				(Some(header), None) => {
+					// keep the block and ask other peers for the justification alone.
 					debug!(target: "sync", "Justification set for block {} was not provided by {} ", block.hash, origin);
-					io.disable_peer(origin); //TODO: use persistent ID
+					protocol.punish_origin(io, origin, origin_node.as_ref().map(String::as_str), Misbehavior::ProtocolViolation);
+					self.pending_justification = Some(PendingJustification {
+						block: blocks::BlockData {
+							block: message::generic::BlockData {
+								hash: block.hash,
+								header: Some(header),
+								body: block.body,
+								receipt: block.receipt,
+								message_queue: block.message_queue,
+								justification: None,
+							},
+							origin,
+							origin_node,
+						},
+						requested_from: Some(origin).into_iter().collect(),
+					});
+					self.request_justification(io, protocol);
+					return None;
+				}
+			}
+		}
+		Some((imported, imported_bytes))
+	}
+
+	fn on_state_header(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId, blocks: Vec<message::BlockData<B>>) {
+		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
+			peer.state = PeerSyncState::Available;
+		}
+		if let Some(ref mut state_sync) = self.state_sync {
+			if state_sync.on_header(peer_id, blocks) {
+				trace!(target: "sync", "Got header of state sync target {} from {}", state_sync.target(), peer_id);
+			} else {
+				trace!(target: "sync", "No header of state sync target {} from {}", state_sync.target(), peer_id);
+			}
+		}
+		self.maintain_sync(io, protocol);
+	}
+
+	/// Handle a chunk of storage entries of the block whose state is downloaded.
+	pub fn on_state_data(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId, response: message::StateResponse) {
+		let late = match self.peers.get_mut(&peer_id) {
+			Some(peer) => match peer.state {
+				PeerSyncState::DownloadingState => {
+					peer.state = PeerSyncState::Available;
+					false
+				},
+				PeerSyncState::Stalled => {
+					trace!(target: "sync", "Dropping late state response from {}", peer_id);
+					peer.state = PeerSyncState::Available;
+					true
+				},
+				_ => return,
+			},
+			None => return,
+		};
+		if late {
+			self.maintain_sync(io, protocol);
+			return;
+		}
+
+		let complete = match self.state_sync {
+			Some(ref mut state_sync) => {
+				let count = response.entries.len();
+				if state_sync.on_entries(peer_id, io.peer_node_id(peer_id), response) {
+					trace!(target: "sync", "Got {} state entries from {}", count, peer_id);
+					protocol.report_peer(io, peer_id, reputation::USEFUL_RESPONSE);
+				} else {
+					debug!(target: "sync", "State entries were not provided by {}", peer_id);
+				}
+				state_sync.is_complete()
+			},
+			None => return,
+		};
+
+		if complete {
+			self.import_state(io, protocol);
+		}
+		self.maintain_sync(io, protocol);
+	}
+
+	// import the downloaded state and continue syncing blocks on top of it. if
+	// the state doesn't match the header, its sources are punished and the
+	// download starts over.
+	fn import_state(&mut self, io: &mut SyncIo, protocol: &Protocol<B>) {
+		let state_sync = match self.state_sync.take() {
+			Some(state_sync) => state_sync,
+			None => return,
+		};
+		let target = *state_sync.target();
+		let sources = state_sync.sources().clone();
+		let (header, justification, entries) = match state_sync.into_state() {
+			Some(state) => state,
+			None => return,
+		};
+
+		let number = *header.number();
+		match protocol.chain().import_state(header, justification, entries) {
+			Ok(_) => {
+				info!(target: "sync", "Imported the state of block #{} ({}), syncing blocks from there", number, target);
+				self.block_imported(&target, number);
+				// peers behind the imported block have nothing to offer.
+				for peer in self.peers.values_mut() {
+					if peer.best_number < number {
+						peer.common_number = peer.best_number;
+						peer.common_hash = peer.best_hash;
+					}
+				}
+			},
+			Err(e) => {
+				debug!(target: "sync", "Error importing the state of block {}: {:?}", target, e);
+				for (peer_id, node_id) in sources {
+					protocol.punish_origin(io, peer_id, node_id.as_ref().map(String::as_str), Misbehavior::BadBlock);
+				}
+				self.state_sync = Some(StateSync::new(target));
+			}
+		}
+	}
+
+	// Ask a peer for the next piece of the state being downloaded, unless
+	// another peer is already asked.
+	fn download_state(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId) {
+		if self.peers.values().any(|p| p.state == PeerSyncState::DownloadingState) {
+			return;
+		}
+
+		let request = match (self.state_sync.as_ref(), self.peers.get_mut(&peer_id)) {
+			(Some(state_sync), Some(peer)) => {
+				if peer.state != PeerSyncState::Available || !state_sync.can_request_from(peer_id, peer.best_number) {
 					return;
 				}
+				trace!(target: "sync", "Requesting state of block {} from {}", state_sync.target(), peer_id);
+				peer.state = PeerSyncState::DownloadingState;
+				state_sync.next_request()
+			},
+			_ => return,
+		};
+		protocol.send_message(io, peer_id, request);
+	}
+
+	// drop repeated blocks and blocks which are already imported, before they
+	// are verified. errors reading the status are left for the import to report.
+	fn drop_known(protocol: &Protocol<B>, blocks: Vec<blocks::BlockData<B>>) -> Vec<blocks::BlockData<B>> {
+		let total = blocks.len();
+		let mut hashes = HashSet::new();
+		let blocks: Vec<_> = blocks.into_iter().filter(|b| {
+			hashes.insert(b.block.hash) && match protocol.chain().block_status(&BlockId::Hash(b.block.hash)) {
+				Ok(BlockStatus::InChain) | Ok(BlockStatus::Queued) => false,
+				_ => true,
 			}
+		}).collect();
+		if blocks.len() < total {
+			trace!(target: "sync", "Dropped {} known or repeated blocks of {}", total - blocks.len(), total);
 		}
-		trace!(target: "sync", "Imported {} of {}", imported, count);
+		blocks
+	}
+
+	// check the justifications of a batch of new blocks in parallel against the
+	// authorities at the parent of the first, returning the authorities as well.
+	// blocks without a result are checked on import instead.
+	fn verify_justifications(protocol: &Protocol<B>, blocks: &[blocks::BlockData<B>])
+		-> (Vec<AuthorityId>, Vec<Option<JustifiedHeader<B>>>)
+	{
+		let parent = match blocks.first().and_then(|b| b.block.header.as_ref()) {
+			Some(header) => header.parent_hash().clone(),
+			None => return (Vec::new(), Vec::new()),
+		};
+
+		let authorities = match protocol.chain().authorities(&BlockId::Hash(parent)) {
+			Ok(authorities) => authorities,
+			Err(e) => {
+				trace!(target: "sync", "Unable to get authorities at {}, verifying blocks on import: {:?}", parent, e);
+				return (Vec::new(), Vec::new());
+			}
+		};
+
+		let headers = blocks.iter()
+			.map(|b| (b.block.header.clone(), b.block.justification.clone()))
+			.take_while(|&(ref h, ref j)| h.is_some() && j.is_some())
+			.map(|(h, j)| (h.expect("checked above; qed"), j.expect("checked above; qed")))
+			.collect();
+
+		let verified = verification::verify_batch(VERIFICATION_THREADS, authorities.clone(), headers);
+		(authorities, verified)
+	}
+
+	/// Perform time based maintenance: ranges which are slow to download are
+	/// handed to idle peers.
+	pub fn tick(&mut self, io: &mut SyncIo, protocol: &Protocol<B>) {
 		self.maintain_sync(io, protocol);
+		if self.persisted_at.elapsed() >= PERSIST_INTERVAL {
+			self.persist(protocol.chain());
+		}
 	}
 
 	fn maintain_sync(&mut self, io: &mut SyncIo, protocol: &Protocol<B>) {
-		let peers: Vec<PeerId> = self.peers.keys().map(|p| *p).collect();
-		for peer in peers {
+		// peers which served blocks well, before or after a restart, are asked first.
+		let mut peers: Vec<(i64, PeerId)> = self.peers.iter().map(|(id, p)| (self.peer_score(p), *id)).collect();
+		peers.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+		for (_, peer) in peers {
+			self.request_announced(io, protocol, peer);
 			self.download_new(io, protocol, peer);
 		}
 	}
@@ -368,11 +862,70 @@ impl<B: BlockT> ChainSync<B> where
 		}
 	}
 
+	/// Handle a block announced by hash only: the header is requested from
+	/// the peer, and the block handled as if the header was announced.
+	pub fn on_block_hash_announce(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId, hash: B::Hash) {
+		if self.is_known_or_already_downloading(protocol, &hash) {
+			trace!(target: "sync", "Known block announce from {}: {}", peer_id, hash);
+			return;
+		}
+
+		match self.peers.get_mut(&peer_id) {
+			Some(peer) => peer.announced = Some(hash),
+			None => return,
+		}
+		self.request_announced(io, protocol, peer_id);
+	}
+
+	// Request the header of the block last announced by hash by a peer, once the peer is available.
+	fn request_announced(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId) {
+		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
+			if peer.state != PeerSyncState::Available {
+				return;
+			}
+			if let Some(hash) = peer.announced.take() {
+				trace!(target: "sync", "Requesting header of block {} announced by {}", hash, peer_id);
+				let request = message::generic::BlockRequest {
+					id: 0,
+					fields: vec![message::BlockAttribute::Header],
+					from: message::FromBlock::Hash(hash),
+					to: None,
+					direction: message::Direction::Ascending,
+					max: Some(1),
+				};
+				peer.state = PeerSyncState::DownloadingAnnounced(hash);
+				protocol.send_message(io, peer_id, GenericMessage::BlockRequest(request));
+			}
+		}
+	}
+
 	fn is_known_or_already_downloading(&self, protocol: &Protocol<B>, hash: &B::Hash) -> bool {
 		self.peers.iter().any(|(_, p)| p.state == PeerSyncState::DownloadingStale(*hash))
 			|| protocol.chain().block_status(&BlockId::Hash(*hash)).ok().map_or(false, |s| s != BlockStatus::Unknown)
 	}
 
+	/// Handle a peer which didn't answer a request in time: what was requested
+	/// from it is requested from other peers.
+	pub fn on_peer_stalled(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId) {
+		let node_id = match self.peers.get_mut(&peer_id) {
+			Some(peer) => match peer.state {
+				PeerSyncState::AncestorSearch(_) | PeerSyncState::Available | PeerSyncState::Stalled => return,
+				_ => {
+					peer.state = PeerSyncState::Stalled;
+					peer.node_id.clone()
+				},
+			},
+			None => return,
+		};
+		if let Some(node) = node_id {
+			self.peer_quality.entry(node).or_insert_with(PeerQuality::default).stalls += 1;
+		}
+
+		trace!(target: "sync", "Requesting from other peers what {} didn't provide in time", peer_id);
+		self.blocks.clear_peer_download(peer_id);
+		self.maintain_sync(io, protocol);
+	}
+
 	pub fn peer_disconnected(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId) {
 		self.blocks.clear_peer_download(peer_id);
 		self.peers.remove(&peer_id);
@@ -381,6 +934,7 @@ impl<B: BlockT> ChainSync<B> where
 
 	pub fn restart(&mut self, io: &mut SyncIo, protocol: &Protocol<B>) {
 		self.blocks.clear();
+		self.paused_blocks.clear();
 		let ids: Vec<PeerId> = self.peers.keys().map(|p| *p).collect();
 		for id in ids {
 			self.new_peer(io, protocol, id);
@@ -400,7 +954,66 @@ impl<B: BlockT> ChainSync<B> where
 
 	pub fn clear(&mut self) {
 		self.blocks.clear();
+		self.paused_blocks.clear();
 		self.peers.clear();
+		self.pending_justification = None;
+	}
+
+	// Ask a peer which hasn't been asked yet for the missing justification of a
+	// downloaded block. The block is kept until it is imported or another block
+	// misses its justification.
+	fn request_justification(&mut self, io: &mut SyncIo, protocol: &Protocol<B>) {
+		let (hash, number) = match self.pending_justification {
+			Some(ref pending) => (
+				pending.block.block.hash,
+				pending.block.block.header.as_ref().map_or(0, |h| *h.number()),
+			),
+			None => return,
+		};
+
+		let in_progress = PeerSyncState::DownloadingJustification(hash);
+		if self.peers.values().any(|p| p.state == in_progress) {
+			return;
+		}
+
+		if protocol.chain().block_status(&BlockId::Hash(hash)).ok() == Some(BlockStatus::InChain) {
+			self.pending_justification = None;
+			return;
+		}
+
+		let peer_id = {
+			let requested_from = &self.pending_justification.as_ref().expect("checked above; qed").requested_from;
+			self.peers.iter()
+				.filter(|&(id, p)| p.state == PeerSyncState::Available && p.best_number >= number && !requested_from.contains(id))
+				.map(|(id, _)| *id)
+				.next()
+		};
+
+		let peer_id = match peer_id {
+			Some(peer_id) => peer_id,
+			None => {
+				trace!(target: "sync", "No peer left to ask for the justification of block {}", hash);
+				return;
+			}
+		};
+
+		trace!(target: "sync", "Requesting justification for block {} from {}", hash, peer_id);
+		if let Some(ref mut pending) = self.pending_justification {
+			pending.requested_from.insert(peer_id);
+		}
+		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
+			peer.state = in_progress;
+		}
+
+		let request = message::generic::BlockRequest {
+			id: 0,
+			fields: vec![message::BlockAttribute::Justification],
+			from: message::FromBlock::Hash(hash),
+			to: None,
+			direction: message::Direction::Ascending,
+			max: Some(1),
+		};
+		protocol.send_message(io, peer_id, GenericMessage::BlockRequest(request));
 	}
 
 	// Download old block.
@@ -426,11 +1039,32 @@ impl<B: BlockT> ChainSync<B> where
 
 	// Issue a request for a peer to download new blocks, if any are available
 	fn download_new(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId) {
+		// blocks are synced on top of the state being downloaded.
+		if self.state_sync.is_some() {
+			self.download_state(io, protocol, peer_id);
+			return;
+		}
+
+		// a missing justification holds up import, so it comes first.
+		self.request_justification(io, protocol);
+
+		// once the queue is full, only fill the gap that keeps it from draining.
+		let hold_back = self.is_saturated() && self.blocks.next_in_progress(self.best_queued_number + 1);
+		let best_queued_number = self.best_queued_number;
+		let max_blocks = protocol.max_blocks_per_request(peer_id);
+		let catch_up_target = self.finalized_target().and_then(|target| if target > best_queued_number { Some(target) } else { None });
 		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
 			trace!(target: "sync", "Considering new block download from {}, common block is {}, best is {:?}", peer_id, peer.common_number, peer.best_number);
 			match peer.state {
+				PeerSyncState::Available if hold_back => {
+					trace!(target: "sync", "Import queue is full, not requesting blocks from {}", peer_id);
+				},
 				PeerSyncState::Available => {
-					if let Some(range) = self.blocks.needed_blocks(peer_id, MAX_BLOCKS_TO_REQUEST, peer.best_number, peer.common_number) {
+					let best_number = match catch_up_target {
+						Some(target) => cmp::min(peer.best_number, peer.finalized_number.unwrap_or(target)),
+						None => peer.best_number,
+					};
+					if let Some(range) = self.blocks.needed_blocks(peer_id, max_blocks, best_number, peer.common_number) {
 						trace!(target: "sync", "Requesting blocks from {}, ({} to {})", peer_id, range.start, range.end);
 						let request = message::generic::BlockRequest {
 							id: 0,
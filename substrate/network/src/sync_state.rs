@@ -0,0 +1,112 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Sync progress kept across restarts.
+//!
+//! The number of the best imported block, the best block seen on the network
+//! and the quality of the peers which served blocks are stored in the client
+//! database as auxiliary data. A restarted node knows how far behind it is
+//! before any peer has reported its best block, and requests ranges from the
+//! peers which served it well before the others.
+
+use std::collections::HashMap;
+use serde_json;
+
+/// Key of the sync state in the auxiliary data of the client.
+pub const SYNC_STATE_KEY: &[u8] = b"sync_state";
+
+/// Maximum number of peers whose quality is kept.
+pub const MAX_PEER_QUALITY_ENTRIES: usize = 64;
+
+// blocks which a stalled request is worth when ranking peers.
+const STALL_PENALTY: i64 = 128;
+
+/// Blocks served by a peer and requests it didn't answer in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct PeerQuality {
+	/// Number of new blocks received from the peer.
+	pub blocks: u64,
+	/// Number of requests which stalled.
+	pub stalls: u32,
+}
+
+impl PeerQuality {
+	/// Rank of the peer. Peers with a higher score are asked for blocks first.
+	pub fn score(&self) -> i64 {
+		self.blocks as i64 - self.stalls as i64 * STALL_PENALTY
+	}
+}
+
+/// Sync progress as stored in the client database.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SyncSnapshot {
+	/// Number of the best imported block.
+	pub best_imported: u64,
+	/// Best block number seen on the network, if any.
+	pub target: Option<u64>,
+	/// Quality of peers by node ID.
+	pub peers: HashMap<String, PeerQuality>,
+}
+
+impl SyncSnapshot {
+	/// Decode a stored snapshot. Returns `None` if the data is invalid.
+	pub fn decode(data: &[u8]) -> Option<Self> {
+		serde_json::from_slice(data).ok()
+	}
+
+	/// Encode for storage.
+	pub fn encode(&self) -> Vec<u8> {
+		serde_json::to_vec(self).expect("snapshot contains only strings and numbers; qed")
+	}
+
+	/// Keep the quality of the best `MAX_PEER_QUALITY_ENTRIES` peers only.
+	pub fn prune_peers(&mut self) {
+		if self.peers.len() <= MAX_PEER_QUALITY_ENTRIES {
+			return;
+		}
+
+		let mut peers: Vec<_> = self.peers.drain().collect();
+		peers.sort_by(|a, b| b.1.score().cmp(&a.1.score()).then_with(|| a.0.cmp(&b.0)));
+		peers.truncate(MAX_PEER_QUALITY_ENTRIES);
+		self.peers = peers.into_iter().collect();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn only_the_best_peers_are_kept() {
+		let mut snapshot = SyncSnapshot {
+			best_imported: 10,
+			target: Some(100),
+			peers: (0..MAX_PEER_QUALITY_ENTRIES as u64 + 2)
+				.map(|i| (format!("node{}", i), PeerQuality { blocks: i, stalls: 0 }))
+				.collect(),
+		};
+		snapshot.peers.insert("staller".into(), PeerQuality { blocks: 1000, stalls: 10 });
+
+		snapshot.prune_peers();
+		assert_eq!(snapshot.peers.len(), MAX_PEER_QUALITY_ENTRIES);
+		assert!(!snapshot.peers.contains_key("staller"));
+		assert!(!snapshot.peers.contains_key("node0"));
+		assert!(snapshot.peers.contains_key("node65"));
+
+		assert_eq!(SyncSnapshot::decode(&snapshot.encode()), Some(snapshot));
+		assert_eq!(SyncSnapshot::decode(b"garbage"), None);
+	}
+}
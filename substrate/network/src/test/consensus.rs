@@ -51,3 +51,25 @@ fn bft_messages_include_those_sent_before_asking_for_stream() {
 
 	assert_eq!(stream.wait().next(), Some(Ok(localized)));
 }
+
+#[test]
+fn heartbeats_include_those_sent_before_asking_for_stream() {
+	let mut config = ::config::ProtocolConfig::default();
+	config.roles = ::service::Role::VALIDATOR | ::service::Role::FULL;
+
+	let mut net = TestNet::new_with_config(2, config);
+	net.sync(); // necessary for handshaking
+
+	let peer = net.peer(0);
+	let mut io = TestIo::new(&peer.queue, None);
+	let heartbeat = vec![1, 2, 3];
+
+	let message: Message<Block> = generic::Message::Heartbeat(heartbeat.clone());
+
+	let as_bytes = ::serde_json::to_vec(&message).unwrap();
+	peer.sync.handle_packet(&mut io, 1, &as_bytes[..]);
+
+	let stream = peer.sync.heartbeats();
+
+	assert_eq!(stream.wait().next(), Some(Ok(heartbeat)));
+}
@@ -15,6 +15,8 @@
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
 mod consensus;
+mod notifications;
+mod registry;
 mod sync;
 
 use std::collections::{VecDeque, HashSet, HashMap};
@@ -28,7 +30,7 @@ use runtime_primitives::generic::BlockId;
 use io::SyncIo;
 use protocol::Protocol;
 use config::ProtocolConfig;
-use service::TransactionPool;
+use service::{TransactionPool, BlockAnnounceValidator, BlockVerifier};
 use network::{PeerId, SessionInfo, Error as NetworkError};
 use keyring::Keyring;
 use codec::Slicable;
@@ -232,9 +234,26 @@ impl TestNet {
 	}
 
 	pub fn add_peer(&mut self, config: &ProtocolConfig) {
+		self.add_peer_with_validator(config, None);
+	}
+
+	pub fn add_peer_with_validator(&mut self, config: &ProtocolConfig, validator: Option<Arc<BlockAnnounceValidator<Block>>>) {
+		self.add_peer_with_checks(config, validator, None);
+	}
+
+	pub fn add_peer_with_verifier(&mut self, config: &ProtocolConfig, verifier: Arc<BlockVerifier<Block>>) {
+		self.add_peer_with_checks(config, None, Some(verifier));
+	}
+
+	fn add_peer_with_checks(
+		&mut self,
+		config: &ProtocolConfig,
+		validator: Option<Arc<BlockAnnounceValidator<Block>>>,
+		verifier: Option<Arc<BlockVerifier<Block>>>,
+	) {
 		let client = Arc::new(test_client::new());
 		let tx_pool = Arc::new(EmptyTransactionPool);
-		let sync = Protocol::new(config.clone(), client.clone(), None, tx_pool).unwrap();
+		let sync = Protocol::new(config.clone(), client.clone(), None, tx_pool, validator, verifier).unwrap();
 		self.peers.push(Arc::new(Peer {
 			sync: sync,
 			client: client,
@@ -0,0 +1,74 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::VecDeque;
+use futures::{Future, Stream};
+use parking_lot::RwLock;
+use notifications::{NotificationsProtocol, NotificationEvent};
+use registry::ApplicationProtocol;
+use super::TestIo;
+
+#[test]
+fn notifications_are_delivered_in_order_after_handshake() {
+	let queue = RwLock::new(VecDeque::new());
+	let (protocol, events) = NotificationsProtocol::new(*b"ntf", b"hello".to_vec());
+
+	{
+		let mut io = TestIo::new(&queue, Some(1));
+		protocol.on_peer_connected(&mut io, 1);
+		assert!(NotificationsProtocol::sink(&protocol, 1).is_none());
+
+		protocol.on_message(&mut io, 1, &[0, 7]);
+		protocol.on_message(&mut io, 1, &[1, 1]);
+		protocol.on_message(&mut io, 1, &[1, 2]);
+
+		let sink = NotificationsProtocol::sink(&protocol, 1).unwrap();
+		assert!(sink.send(vec![3]));
+		assert!(sink.send(vec![4]));
+		protocol.tick(&mut io);
+
+		protocol.on_peer_disconnected(&mut io, 1);
+		assert!(!sink.send(vec![5]));
+	}
+
+	let sent: Vec<_> = queue.read().iter().map(|p| (p.recipient, p.data.clone())).collect();
+	assert_eq!(sent, vec![
+		(1, b"\0hello".to_vec()),
+		(1, vec![1, 3]),
+		(1, vec![1, 4]),
+	]);
+
+	drop(protocol);
+	assert_eq!(events.collect().wait().unwrap(), vec![
+		NotificationEvent::Opened { peer: 1, handshake: vec![7] },
+		NotificationEvent::Notification { peer: 1, data: vec![1] },
+		NotificationEvent::Notification { peer: 1, data: vec![2] },
+		NotificationEvent::Closed { peer: 1 },
+	]);
+}
+
+#[test]
+fn notification_before_handshake_disables_peer() {
+	let queue = RwLock::new(VecDeque::new());
+	let (protocol, _events) = NotificationsProtocol::new(*b"ntf", Vec::new());
+
+	let mut io = TestIo::new(&queue, Some(1));
+	protocol.on_peer_connected(&mut io, 1);
+	protocol.on_message(&mut io, 1, &[1, 1]);
+
+	assert!(io.to_disconnect.contains(&1));
+	assert!(protocol.open_peers().is_empty());
+}
@@ -0,0 +1,61 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use parking_lot::RwLock;
+use network::PeerId;
+use registry::{ProtocolRegistry, ApplicationProtocol, ProtocolContext};
+use service::DOT_PROTOCOL_ID;
+use super::TestIo;
+
+struct Echo;
+
+impl ApplicationProtocol for Echo {
+	fn on_message(&self, ctx: &mut ProtocolContext, peer: PeerId, data: &[u8]) {
+		if data.is_empty() {
+			ctx.disconnect_peer(peer);
+		} else {
+			ctx.send(peer, data.to_vec());
+		}
+	}
+}
+
+#[test]
+fn protocol_names_must_be_unique() {
+	let mut registry = ProtocolRegistry::new();
+	assert!(registry.register(*b"ech", Arc::new(Echo)).is_ok());
+	assert!(registry.register(*b"ech", Arc::new(Echo)).is_err());
+	assert!(registry.register(DOT_PROTOCOL_ID, Arc::new(Echo)).is_err());
+	assert!(registry.register(*b"ecv", Arc::new(Echo)).is_ok());
+	assert_eq!(registry.protocol_ids(), vec![*b"ech", *b"ecv"]);
+}
+
+#[test]
+fn application_protocol_talks_through_context() {
+	let queue = RwLock::new(VecDeque::new());
+	{
+		let mut io = TestIo::new(&queue, Some(1));
+		Echo.on_message(&mut io, 1, b"hello");
+		Echo.on_message(&mut io, 2, b"");
+		assert!(io.to_disconnect.contains(&2));
+	}
+
+	let packets = queue.read();
+	assert_eq!(packets.len(), 1);
+	assert_eq!(packets[0].recipient, 1);
+	assert_eq!(packets[0].data, b"hello".to_vec());
+}
@@ -14,10 +14,13 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::time::Duration;
 use client::backend::Backend;
 use client::blockchain::HeaderBackend as BlockchainHeaderBackend;
+use state_machine::Backend as StateBackend;
 use sync::SyncState;
-use {Role};
+use runtime_primitives::bft::Justification;
+use {Role, SyncMode, AnnounceMode, BlockAnnounceValidator, AnnounceValidity, BlockVerifier, ResponseLimits};
 use super::*;
 
 #[test]
@@ -123,3 +126,432 @@ fn blocks_are_not_announced_by_light_nodes() {
 	assert_eq!(net.peer(1).client.backend().blockchain().info().unwrap().best_number, 1);
 	assert_eq!(net.peer(2).client.backend().blockchain().info().unwrap().best_number, 0);
 }
+
+#[test]
+fn header_sync_skips_bodies() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(0);
+
+	// full peer0 only follows headers of full peer1
+	let mut headers_config = ProtocolConfig::default();
+	headers_config.sync_mode = Some(SyncMode::Headers);
+	net.add_peer(&headers_config);
+	net.add_peer(&ProtocolConfig::default());
+
+	net.peer(1).push_blocks(2, false);
+	net.sync();
+
+	let headers_client = &net.peer(0).client;
+	assert_eq!(headers_client.backend().blockchain().info().unwrap().best_number, 2);
+	assert!(headers_client.justification(&BlockId::Number(2)).unwrap().is_some());
+	assert!(headers_client.body(&BlockId::Number(2)).unwrap().is_none());
+	assert!(net.peer(1).client.body(&BlockId::Number(2)).unwrap().is_some());
+}
+
+#[test]
+fn blocks_announced_by_hash_are_fetched() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(0);
+
+	// peer1 announces its blocks by hash only
+	let mut hash_config = ProtocolConfig::default();
+	hash_config.announce_mode = AnnounceMode::Hash;
+	net.add_peer(&ProtocolConfig::default());
+	net.add_peer(&hash_config);
+	net.sync();
+
+	net.peer(1).push_blocks(1, false);
+	net.peer(1).start();
+	{
+		let queue = net.peer(1).queue.read();
+		let announce: ::message::Message<Block> = ::serde_json::from_slice(&queue.front().unwrap().data).unwrap();
+		match announce {
+			::message::generic::Message::BlockHashAnnounce(_) => (),
+			other => panic!("expected an announcement by hash, got {:?}", other),
+		}
+	}
+	net.sync();
+
+	assert_eq!(net.peer(0).client.backend().blockchain().info().unwrap().best_number, 1);
+}
+
+struct FixedValidator(AnnounceValidity);
+
+impl BlockAnnounceValidator<Block> for FixedValidator {
+	fn validate(&self, _header: &<Block as BlockT>::Header) -> AnnounceValidity {
+		self.0
+	}
+}
+
+#[test]
+fn announcements_are_validated_before_download() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(0);
+
+	// peer0 ignores every announcement, peer1 considers them all bogus
+	net.add_peer_with_validator(&ProtocolConfig::default(), Some(Arc::new(FixedValidator(AnnounceValidity::Ignore))));
+	net.add_peer_with_validator(&ProtocolConfig::default(), Some(Arc::new(FixedValidator(AnnounceValidity::Invalid))));
+	net.add_peer(&ProtocolConfig::default());
+	net.sync();
+
+	net.peer(2).push_blocks(1, false);
+	net.peer(2).start();
+	net.sync();
+
+	assert_eq!(net.peer(0).client.backend().blockchain().info().unwrap().best_number, 0);
+	assert_eq!(net.peer(1).client.backend().blockchain().info().unwrap().best_number, 0);
+
+	// only peer1 dropped the announcing peer
+	assert!(!net.disconnect_events.is_empty());
+	assert!(net.disconnect_events.iter().all(|&(from, _)| from == 2));
+}
+
+// refuses blocks from a number on.
+struct RefuseFrom(u64);
+
+impl BlockVerifier<Block> for RefuseFrom {
+	fn verify(&self, header: &<Block as BlockT>::Header, _: &Justification<Hash>, body: Option<&[Extrinsic]>)
+		-> Result<(), String>
+	{
+		assert!(body.is_some());
+		if header.number >= self.0 {
+			Err(format!("block #{} is too high", header.number))
+		} else {
+			Ok(())
+		}
+	}
+}
+
+#[test]
+fn downloaded_blocks_are_verified_before_import() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(1);
+	net.peer(0).push_blocks(10, false);
+	net.add_peer_with_verifier(&ProtocolConfig::default(), Arc::new(RefuseFrom(4)));
+	net.sync();
+
+	assert_eq!(net.peer(1).client.backend().blockchain().info().unwrap().best_number, 3);
+	assert!(net.disconnect_events.contains(&(0, 0)));
+}
+
+#[test]
+fn requests_which_stall_are_made_again() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(0);
+
+	// peer0 considers any request stalled on the next tick
+	let mut impatient_config = ProtocolConfig::default();
+	impatient_config.request_timeout = Duration::from_secs(0);
+	net.add_peer(&impatient_config);
+	net.add_peer(&ProtocolConfig::default());
+	net.peer(1).push_blocks(10, false);
+
+	net.start();
+	while net.peer(0).sync.status().num_active_peers == 0 {
+		net.sync_step();
+	}
+	net.peer(0).sync.tick(&mut TestIo::new(&net.peer(0).queue, None));
+
+	// the late response is dropped without punishing peer1, and the blocks requested again.
+	net.sync();
+	assert!(net.disconnect_events.is_empty());
+	assert_eq!(net.peer(0).client.backend().blockchain().info().unwrap().best_number, 10);
+}
+
+#[test]
+fn state_sync_starts_from_trusted_block() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(1);
+	net.peer(0).push_blocks(10, false);
+	let target = net.peer(0).client.block_hash(5).unwrap().unwrap();
+
+	// peer1 downloads the state of block 5 and only syncs blocks after it
+	let mut state_config = ProtocolConfig::default();
+	state_config.state_sync_target = Some(target.encode());
+	net.add_peer(&state_config);
+	net.sync();
+
+	let client = &net.peer(1).client;
+	assert_eq!(client.backend().blockchain().info().unwrap().best_number, 10);
+	assert!(client.header(&BlockId::Hash(target)).unwrap().is_some());
+	assert!(client.header(&BlockId::Number(4)).unwrap().is_none());
+	assert_eq!(client.state_at(&BlockId::Number(10)).unwrap().pairs(),
+		net.peer(0).client.state_at(&BlockId::Number(10)).unwrap().pairs());
+}
+
+#[test]
+fn peers_with_other_runtime_are_refused_or_historical() {
+	::env_logger::init().ok();
+	let net = TestNet::new(1);
+	let peer = net.peer(0);
+	let version = peer.client.runtime_version_at(&BlockId::Number(0)).unwrap();
+
+	let status = |peer_id: PeerId, spec_name: String, spec_version: u32| {
+		let status = ::message::generic::Status {
+			version: 1,
+			roles: vec![::message::Role::Full],
+			best_number: 0,
+			best_hash: peer.genesis_hash(),
+			genesis_hash: peer.genesis_hash(),
+			spec_name,
+			spec_version,
+			validator_signature: None,
+			validator_id: None,
+			parachain_id: None,
+			time: None,
+			hash_announcements: false,
+			finalized: None,
+			max_blocks_per_response: None,
+			max_response_bytes: None,
+			keep_alive: false,
+		};
+		let data = ::serde_json::to_vec(&::message::generic::Message::Status(status)).unwrap();
+
+		peer.on_connect(peer_id);
+		peer.receive_message(peer_id, TestPacket { data, recipient: 0 })
+	};
+
+	// a newer spec version is kept for history only.
+	assert!(status(1, version.spec_name.to_string(), version.spec_version + 1).is_empty());
+	assert!(peer.sync.peer_info(1).unwrap().historical);
+
+	assert!(status(2, version.spec_name.to_string(), version.spec_version).is_empty());
+	assert!(!peer.sync.peer_info(2).unwrap().historical);
+
+	// another runtime altogether is refused.
+	assert!(status(3, "other".into(), version.spec_version).contains(&3));
+	assert!(peer.sync.peer_info(3).is_none());
+}
+
+#[test]
+fn catch_up_follows_finalized_blocks() {
+	::env_logger::init().ok();
+	let net = TestNet::new(1);
+	let peer = net.peer(0);
+	let version = peer.client.runtime_version_at(&BlockId::Number(0)).unwrap();
+
+	// connect a peer and return the number of blocks first requested from it.
+	let connect = |peer_id: PeerId, best_number: u64, finalized: Option<u64>| {
+		let status = ::message::generic::Status {
+			version: 1,
+			roles: vec![::message::Role::Full],
+			best_number,
+			best_hash: [peer_id as u8; 32].into(),
+			genesis_hash: peer.genesis_hash(),
+			spec_name: version.spec_name.to_string(),
+			spec_version: version.spec_version,
+			validator_signature: None,
+			validator_id: None,
+			parachain_id: None,
+			time: None,
+			hash_announcements: false,
+			finalized: finalized.map(|number| (number, [peer_id as u8; 32].into())),
+			max_blocks_per_response: None,
+			max_response_bytes: None,
+			keep_alive: false,
+		};
+		let data = ::serde_json::to_vec(&::message::generic::Message::Status(status)).unwrap();
+
+		peer.on_connect(peer_id);
+		assert!(peer.receive_message(peer_id, TestPacket { data, recipient: 0 }).is_empty());
+		let mut max = None;
+		while let Some(packet) = peer.pending_message() {
+			let message: ::serde_json::Value = ::serde_json::from_slice(&packet.data).unwrap();
+			if packet.recipient == peer_id && message.get("BlockRequest").is_some() {
+				max = message["BlockRequest"]["max"].as_u64();
+			}
+		}
+		max
+	};
+
+	// a long chain of which only a few blocks are final is only followed up to those.
+	assert_eq!(connect(1, 1000, Some(5)), Some(5));
+	// a peer which doesn't report its finalized block is followed up to the highest one reported.
+	assert_eq!(connect(2, 1000, None), None);
+	assert_eq!(connect(3, 50, Some(50)), Some(45));
+}
+
+#[test]
+fn responses_fit_the_limits_of_both_peers() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(1);
+	net.peer(0).push_blocks(20, false);
+
+	let mut config = ProtocolConfig::default();
+	config.response_limits = ResponseLimits { max_blocks: 10, max_bytes: 4 * 1024 };
+	net.add_peer(&config);
+	net.sync();
+
+	// a response over the limits would have got the serving peer disabled.
+	assert!(net.peer(1).sync.peer_info(0).is_some());
+	assert_eq!(net.peer(1).client.backend().blockchain().info().unwrap().best_number, 20);
+}
+
+#[test]
+fn paused_import_keeps_downloaded_blocks() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(2);
+	net.peer(0).push_blocks(10, false);
+	net.peer(1).sync.pause_import();
+	net.sync();
+
+	let best_number = || net.peer(1).client.backend().blockchain().info().unwrap().best_number;
+	assert_eq!(best_number(), 0);
+	assert_eq!(net.peer(1).sync.status().sync.queued_blocks, 10);
+
+	net.peer(1).sync.resume_import(&mut TestIo::new(&net.peer(1).queue, None));
+	assert_eq!(best_number(), 10);
+	assert_eq!(net.peer(1).sync.status().sync.queued_blocks, 0);
+}
+
+// status of a peer at the genesis block of `peer`.
+fn genesis_status(peer: &Peer) -> ::message::generic::Status<Hash, u64> {
+	let version = peer.client.runtime_version_at(&BlockId::Number(0)).unwrap();
+	::message::generic::Status {
+		version: 1,
+		roles: vec![::message::Role::Full],
+		best_number: 0,
+		best_hash: peer.genesis_hash(),
+		genesis_hash: peer.genesis_hash(),
+		spec_name: version.spec_name.to_string(),
+		spec_version: version.spec_version,
+		validator_signature: None,
+		validator_id: None,
+		parachain_id: None,
+		time: None,
+		hash_announcements: false,
+		finalized: None,
+		max_blocks_per_response: None,
+		max_response_bytes: None,
+		keep_alive: false,
+	}
+}
+
+#[test]
+fn peers_which_dont_answer_pings_are_disconnected() {
+	::env_logger::init().ok();
+
+	// peers are pinged on every tick, and have until the next one to answer.
+	let mut config = ProtocolConfig::default();
+	config.keep_alive_interval = Duration::from_secs(0);
+	config.keep_alive_timeout = Duration::from_secs(0);
+	let net = TestNet::new_with_config(1, config);
+	let peer = net.peer(0);
+
+	let connect = |peer_id: PeerId, keep_alive: bool| {
+		let mut status = genesis_status(peer);
+		status.keep_alive = keep_alive;
+		let data = ::serde_json::to_vec(&::message::generic::Message::Status(status)).unwrap();
+		peer.on_connect(peer_id);
+		assert!(peer.receive_message(peer_id, TestPacket { data, recipient: 0 }).is_empty());
+	};
+	let pinged = || {
+		let mut pinged = Vec::new();
+		while let Some(packet) = peer.pending_message() {
+			let message: ::serde_json::Value = ::serde_json::from_slice(&packet.data).unwrap();
+			if message == "Ping" {
+				pinged.push(packet.recipient);
+			}
+		}
+		pinged.sort();
+		pinged
+	};
+
+	connect(1, true);
+	connect(2, true);
+	connect(3, false);
+	pinged();
+
+	// peers which don't tell they answer pings aren't pinged.
+	peer.sync_step();
+	assert_eq!(pinged(), vec![1, 2]);
+
+	assert!(peer.receive_message(2, TestPacket { data: b"\"Pong\"".to_vec(), recipient: 0 }).is_empty());
+
+	peer.sync_step();
+	assert!(peer.sync.peer_info(1).is_none());
+	assert!(peer.sync.peer_info(2).is_some());
+	assert!(peer.sync.peer_info(3).is_some());
+	assert_eq!(pinged(), vec![2]);
+}
+
+#[test]
+fn justifications_are_verified_in_parallel() {
+	use verification::verify_batch;
+
+	let net = TestNet::new(1);
+	net.peer(0).push_blocks(10, false);
+
+	let client = &net.peer(0).client;
+	let headers: Vec<_> = (1..11).map(|n| {
+		let id = BlockId::Number(n);
+		(client.header(&id).unwrap().unwrap(), client.justification(&id).unwrap().unwrap())
+	}).collect();
+	let authorities = client.authorities_at(&BlockId::Number(0)).unwrap();
+
+	let verified = verify_batch::<Block>(4, authorities.clone(), headers.clone());
+	assert_eq!(verified.len(), 10);
+	assert!(verified.iter().all(Option::is_some));
+
+	let mut tampered = headers;
+	tampered[3].1.signatures.clear();
+	let verified = verify_batch::<Block>(3, authorities, tampered);
+	assert!(verified[3].is_none());
+	assert_eq!(verified.iter().filter(|v| v.is_some()).count(), 9);
+}
+
+#[test]
+fn sync_target_survives_restart() {
+	let mut net = TestNet::new(2);
+	net.peer(1).push_blocks(100, false);
+	net.sync_steps(2);
+	assert_eq!(net.peer(0).sync.status().sync.best_seen_block, Some(100));
+
+	// the progress is stored on shutdown, and known before any peer connects.
+	net.restart_peer(0);
+	let client = net.peer(0).client.clone();
+	let restarted = Protocol::new(ProtocolConfig::default(), client, None, Arc::new(EmptyTransactionPool), None, None).unwrap();
+	assert_eq!(restarted.status().sync.best_seen_block, Some(100));
+	assert_eq!(restarted.status().sync.state, SyncState::Downloading);
+}
+
+#[test]
+fn chains_conflicting_with_a_checkpoint_are_refused() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(1);
+	net.peer(0).push_blocks(10, false);
+
+	// the checkpoint is on another chain, with transactions in its blocks.
+	let other = TestNet::new(1);
+	other.peer(0).push_blocks(10, true);
+	let checkpoint = other.peer(0).client.block_hash(5).unwrap().unwrap();
+
+	let mut config = ProtocolConfig::default();
+	config.checkpoints = vec![(5, checkpoint.encode())];
+	net.add_peer(&config);
+	net.sync();
+
+	assert_eq!(net.peer(1).client.backend().blockchain().info().unwrap().best_number, 4);
+	assert!(net.disconnect_events.contains(&(0, 0)));
+}
+
+#[test]
+fn peers_which_dont_send_their_status_in_time_are_disconnected() {
+	::env_logger::init().ok();
+	let mut config = ProtocolConfig::default();
+	config.handshake_timeout = Duration::from_secs(0);
+	let mut net = TestNet::new(1);
+	net.add_peer(&config);
+
+	let data = ::serde_json::to_vec(&::message::generic::Message::Status(genesis_status(net.peer(0)))).unwrap();
+	for i in 0..2 {
+		let peer = net.peer(i);
+		peer.on_connect(5);
+		peer.sync_step();
+		peer.receive_message(5, TestPacket { data: data.clone(), recipient: 0 });
+	}
+
+	// the status came after the next tick, which is too late for peer1.
+	assert!(net.peer(0).sync.peer_info(5).is_some());
+	assert!(net.peer(1).sync.peer_info(5).is_none());
+}
@@ -0,0 +1,89 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Parallel verification of block justifications.
+//!
+//! Checking the signatures of a justification is the most expensive part of
+//! importing a block during major sync, and doesn't depend on the blocks before
+//! it having been imported, as long as the authority set is known. Batches of
+//! downloaded blocks are checked against the authorities at the parent of the
+//! first block by a number of worker threads, and then imported in order.
+
+use std::sync::Arc;
+use std::thread;
+
+use client::{self, JustifiedHeader};
+use primitives::AuthorityId;
+use runtime_primitives::bft::Justification;
+use runtime_primitives::traits::Block as BlockT;
+
+/// Number of threads verifying a batch of justifications.
+pub const VERIFICATION_THREADS: usize = 4;
+
+/// Check the justifications of a batch of headers against a single set of
+/// authorities, using up to `threads` worker threads.
+///
+/// Results are in the order of the headers. A header whose justification
+/// doesn't check out against `authorities` has no result; it may still be valid
+/// if the authority set changed within the batch.
+pub fn verify_batch<B: BlockT>(
+	threads: usize,
+	authorities: Vec<AuthorityId>,
+	headers: Vec<(B::Header, Justification<B::Hash>)>,
+) -> Vec<Option<JustifiedHeader<B>>> {
+	let count = headers.len();
+	let threads = ::std::cmp::min(threads, count);
+	if threads <= 1 {
+		return headers.into_iter().map(|(h, j)| verify::<B>(&authorities, h, j)).collect();
+	}
+
+	let authorities = Arc::new(authorities);
+	let chunk_size = (count + threads - 1) / threads;
+
+	let mut workers = Vec::with_capacity(threads);
+	let mut headers = headers.into_iter();
+	for _ in 0..threads {
+		let chunk: Vec<_> = headers.by_ref().take(chunk_size).collect();
+		let chunk_len = chunk.len();
+		let authorities = authorities.clone();
+
+		let worker = thread::Builder::new()
+			.name("Justification verification".into())
+			.spawn(move || chunk.into_iter().map(|(h, j)| verify::<B>(&authorities, h, j)).collect::<Vec<_>>());
+
+		workers.push((worker, chunk_len));
+	}
+
+	let mut verified = Vec::with_capacity(count);
+	for (worker, chunk_len) in workers {
+		match worker.map_err(|e| e.to_string()).and_then(|w| w.join().map_err(|_| "worker panicked".into())) {
+			Ok(results) => verified.extend(results),
+			Err(e) => {
+				// leave the chunk to be verified on import.
+				warn!(target: "sync", "Error verifying justifications: {}", e);
+				verified.extend((0..chunk_len).map(|_| None));
+			}
+		}
+	}
+
+	verified
+}
+
+fn verify<B: BlockT>(authorities: &[AuthorityId], header: B::Header, justification: Justification<B::Hash>)
+	-> Option<JustifiedHeader<B>>
+{
+	client::check_justification_with::<B>(authorities, header, justification.into()).ok()
+}
@@ -29,7 +29,10 @@ extern crate substrate_runtime_primitives;
 #[macro_use]
 extern crate log;
 
+pub mod relay;
+
 use std::io;
+use substrate_runtime_primitives::generic::SignedBlock;
 use substrate_runtime_primitives::traits::Block as BlockT;
 
 type Metadata = apis::metadata::Metadata;
@@ -44,7 +47,7 @@ pub fn rpc_handler<Block: BlockT, S, C, A, Y>(
 ) -> RpcHandler where
 	Block: 'static,
 	S: apis::state::StateApi<Block::Hash>,
-	C: apis::chain::ChainApi<Block::Hash, Block::Header, Metadata=Metadata>,
+	C: apis::chain::ChainApi<Block::Hash, Block::Header, SignedBlock<Block::Header, Block::Extrinsic, Block::Hash>, Metadata=Metadata>,
 	A: apis::author::AuthorApi<Block::Hash, Block::Extrinsic>,
 	Y: apis::system::SystemApi,
 {
@@ -56,6 +59,15 @@ pub fn rpc_handler<Block: BlockT, S, C, A, Y>(
 	io
 }
 
+/// Construct rpc `IoHandler` serving only the relay API.
+pub fn relay_handler<R, Header, AccountId, Balance, ParaId>(relay: R) -> RpcHandler where
+	R: apis::relay::RelayApi<Header, AccountId, Balance, ParaId>,
+{
+	let mut io = pubsub::PubSubHandler::default();
+	io.extend_with(relay.to_delegate());
+	io
+}
+
 /// Start HTTP server listening on given address.
 pub fn start_http(
 	addr: &std::net::SocketAddr,
@@ -0,0 +1,171 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Caching front for the relay API.
+//!
+//! Front-ends tend to poll the same few values, so answers are reused for a
+//! short while instead of hitting the client on every request.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use apis::relay::{Bytes, RelayApi};
+use apis::relay::error::Result;
+
+/// Default time an answer is reused for, in seconds.
+pub const DEFAULT_CACHE_SECS: u64 = 3;
+
+// Maximum number of answers kept per method.
+const MAX_CACHED_ENTRIES: usize = 1024;
+
+struct TtlCache<K, V> {
+	ttl: Duration,
+	entries: Mutex<HashMap<K, (Instant, V)>>,
+}
+
+impl<K: Hash + Eq, V: Clone> TtlCache<K, V> {
+	fn new(ttl: Duration) -> Self {
+		TtlCache {
+			ttl,
+			entries: Mutex::new(HashMap::new()),
+		}
+	}
+
+	// Get a fresh answer for `key`, fetching it if there is none.
+	fn get_or_fetch<F: FnOnce() -> Result<V>>(&self, key: K, fetch: F) -> Result<V> {
+		let now = Instant::now();
+		if let Some(&(at, ref value)) = self.entries.lock().expect("cache lock is never poisoned; qed").get(&key) {
+			if now.duration_since(at) < self.ttl {
+				return Ok(value.clone());
+			}
+		}
+
+		// not holding the lock while fetching; concurrent misses fetch twice.
+		let value = fetch()?;
+		let mut entries = self.entries.lock().expect("cache lock is never poisoned; qed");
+		if entries.len() >= MAX_CACHED_ENTRIES {
+			let ttl = self.ttl;
+			entries.retain(|_, &mut (at, _)| now.duration_since(at) < ttl);
+			if entries.len() >= MAX_CACHED_ENTRIES {
+				entries.clear();
+			}
+		}
+		entries.insert(key, (now, value.clone()));
+		Ok(value)
+	}
+}
+
+/// Relay API reusing the answers of another for a while.
+pub struct CachedRelay<T, Header, AccountId, Balance, ParaId> {
+	inner: T,
+	best_head: TtlCache<(), Header>,
+	finalized_head: TtlCache<(), Header>,
+	balances: TtlCache<AccountId, Balance>,
+	parachain_heads: TtlCache<ParaId, Option<Bytes>>,
+}
+
+impl<T, Header, AccountId, Balance, ParaId> CachedRelay<T, Header, AccountId, Balance, ParaId> where
+	AccountId: Hash + Eq,
+	ParaId: Hash + Eq,
+	Header: Clone,
+	Balance: Clone,
+{
+	/// Wrap `inner`, reusing its answers for `ttl`.
+	pub fn new(inner: T, ttl: Duration) -> Self {
+		CachedRelay {
+			inner,
+			best_head: TtlCache::new(ttl),
+			finalized_head: TtlCache::new(ttl),
+			balances: TtlCache::new(ttl),
+			parachain_heads: TtlCache::new(ttl),
+		}
+	}
+}
+
+impl<T, Header, AccountId, Balance, ParaId> RelayApi<Header, AccountId, Balance, ParaId> for CachedRelay<T, Header, AccountId, Balance, ParaId> where
+	T: RelayApi<Header, AccountId, Balance, ParaId>,
+	Header: Clone + Send + Sync + 'static,
+	AccountId: Hash + Eq + Clone + Send + Sync + 'static,
+	Balance: Clone + Send + Sync + 'static,
+	ParaId: Hash + Eq + Clone + Send + Sync + 'static,
+{
+	fn best_head(&self) -> Result<Header> {
+		self.best_head.get_or_fetch((), || self.inner.best_head())
+	}
+
+	fn finalized_head(&self) -> Result<Header> {
+		self.finalized_head.get_or_fetch((), || self.inner.finalized_head())
+	}
+
+	fn balance(&self, account: AccountId) -> Result<Balance> {
+		self.balances.get_or_fetch(account.clone(), || self.inner.balance(account))
+	}
+
+	fn parachain_head(&self, para_id: ParaId) -> Result<Option<Bytes>> {
+		self.parachain_heads.get_or_fetch(para_id.clone(), || self.inner.parachain_head(para_id))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use apis::relay::error::ErrorKind;
+
+	struct Counting {
+		balance_calls: AtomicUsize,
+	}
+
+	impl RelayApi<u64, u8, u128, u32> for Counting {
+		fn best_head(&self) -> Result<u64> {
+			Ok(1)
+		}
+
+		fn finalized_head(&self) -> Result<u64> {
+			Ok(1)
+		}
+
+		fn balance(&self, account: u8) -> Result<u128> {
+			self.balance_calls.fetch_add(1, Ordering::SeqCst);
+			Ok(account as u128)
+		}
+
+		fn parachain_head(&self, _para_id: u32) -> Result<Option<Bytes>> {
+			Err(ErrorKind::Unavailable("no parachains".into()).into())
+		}
+	}
+
+	#[test]
+	fn answers_are_reused_until_they_expire() {
+		let relay = CachedRelay::new(Counting { balance_calls: AtomicUsize::new(0) }, Duration::from_millis(200));
+		let calls = |relay: &CachedRelay<Counting, _, _, _, _>| relay.inner.balance_calls.load(Ordering::SeqCst);
+
+		assert_eq!(relay.balance(1).unwrap(), 1);
+		assert_eq!(relay.balance(1).unwrap(), 1);
+		assert_eq!(relay.balance(2).unwrap(), 2);
+		assert_eq!(calls(&relay), 2);
+
+		::std::thread::sleep(Duration::from_millis(250));
+		assert_eq!(relay.balance(1).unwrap(), 1);
+		assert_eq!(calls(&relay), 3);
+
+		// errors aren't cached.
+		assert!(relay.parachain_head(1).is_err());
+		assert!(relay.parachain_heads.entries.lock().unwrap().is_empty());
+	}
+}
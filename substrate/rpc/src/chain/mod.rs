@@ -19,7 +19,7 @@
 use std::sync::Arc;
 
 use runtime_primitives::traits::Block as BlockT;
-use runtime_primitives::generic::BlockId;
+use runtime_primitives::generic::{BlockId, SignedBlock};
 use client::{self, Client, BlockchainEvents};
 use state_machine;
 
@@ -39,13 +39,17 @@ use self::error::{Result, ResultExt};
 
 build_rpc_trait! {
 	/// Polkadot blockchain API
-	pub trait ChainApi<Hash, Header> {
+	pub trait ChainApi<Hash, Header, SignedBlock> {
 		type Metadata;
 
 		/// Get header of a relay chain block.
 		#[rpc(name = "chain_getHeader")]
 		fn header(&self, Hash) -> Result<Option<Header>>;
 
+		/// Get header, body and justification of a relay chain block.
+		#[rpc(name = "chain_getBlock")]
+		fn block(&self, Hash) -> Result<Option<SignedBlock>>;
+
 		/// Get hash of the head.
 		#[rpc(name = "chain_getHead")]
 		fn head(&self) -> Result<Hash>;
@@ -80,7 +84,7 @@ impl<B, E, Block: BlockT> Chain<B, E, Block> {
 	}
 }
 
-impl<B, E, Block> ChainApi<Block::Hash, Block::Header> for Chain<B, E, Block> where
+impl<B, E, Block> ChainApi<Block::Hash, Block::Header, SignedBlock<Block::Header, Block::Extrinsic, Block::Hash>> for Chain<B, E, Block> where
 	Block: BlockT + 'static,
 	B: client::backend::Backend<Block> + Send + Sync + 'static,
 	E: client::CallExecutor<Block> + Send + Sync + 'static,
@@ -92,6 +96,10 @@ impl<B, E, Block> ChainApi<Block::Hash, Block::Header> for Chain<B, E, Block> wh
 		self.client.header(&BlockId::Hash(hash)).chain_err(|| "Blockchain error")
 	}
 
+	fn block(&self, hash: Block::Hash) -> Result<Option<SignedBlock<Block::Header, Block::Extrinsic, Block::Hash>>> {
+		self.client.block(&BlockId::Hash(hash)).chain_err(|| "Blockchain error")
+	}
+
 	fn head(&self) -> Result<Block::Hash> {
 		Ok(self.client.info().chain_err(|| "Blockchain error")?.chain.best_hash)
 	}
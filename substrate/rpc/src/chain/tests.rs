@@ -46,6 +46,31 @@ fn should_return_header() {
 	);
 }
 
+#[test]
+fn should_return_a_block() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let remote = core.remote();
+
+	let api = Chain {
+		client: Arc::new(test_client::new()),
+		subscriptions: Subscriptions::new(remote),
+	};
+
+	let block = api.client.new_block().unwrap().bake().unwrap();
+	let block_hash = block.hash();
+	api.client.justify_and_import(BlockOrigin::Own, block).unwrap();
+
+	assert_matches!(
+		api.block(block_hash),
+		Ok(Some(ref x)) if x.block.header.number == 1 && x.block.extrinsics.is_empty()
+	);
+
+	assert_matches!(
+		api.block(5.into()),
+		Ok(None)
+	);
+}
+
 #[test]
 fn should_notify_about_latest_block() {
 	let mut core = ::tokio_core::reactor::Core::new().unwrap();
@@ -0,0 +1,45 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Substrate consensus introspection API.
+//!
+//! Meant for debugging why candidates are or aren't making it into blocks.
+
+pub mod error;
+
+#[cfg(test)]
+mod tests;
+
+use self::error::Result;
+
+build_rpc_trait! {
+	/// Consensus introspection RPC API.
+	pub trait ConsensusApi<GroupId, DutyRoster, GroupState, Summary> {
+		/// Get the duty roster of the ongoing agreement, if any.
+		#[rpc(name = "consensus_dutyRoster")]
+		fn duty_roster(&self) -> Result<Option<DutyRoster>>;
+
+		/// Get the membership, statements and vote tallies of a group in the
+		/// ongoing agreement, if any.
+		#[rpc(name = "consensus_groupState")]
+		fn group_state(&self, GroupId) -> Result<Option<GroupState>>;
+
+		/// Get a summary of candidates, votes and misbehavior across all groups
+		/// in the ongoing agreement, if any.
+		#[rpc(name = "consensus_tableSummary")]
+		fn table_summary(&self) -> Result<Option<Summary>>;
+	}
+}
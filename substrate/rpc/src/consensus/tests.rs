@@ -0,0 +1,58 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use super::error::*;
+
+struct NoAgreement;
+
+impl ConsensusApi<u32, Vec<u32>, String, usize> for NoAgreement {
+	fn duty_roster(&self) -> Result<Option<Vec<u32>>> {
+		Ok(None)
+	}
+
+	fn group_state(&self, _group: u32) -> Result<Option<String>> {
+		Err(ErrorKind::Unimplemented.into())
+	}
+
+	fn table_summary(&self) -> Result<Option<usize>> {
+		Ok(None)
+	}
+}
+
+#[test]
+fn duty_roster_works() {
+	assert_eq!(
+		ConsensusApi::duty_roster(&NoAgreement).unwrap(),
+		None
+	);
+}
+
+#[test]
+fn table_summary_works() {
+	assert_eq!(
+		ConsensusApi::table_summary(&NoAgreement).unwrap(),
+		None
+	);
+}
+
+#[test]
+fn group_state_reports_errors() {
+	assert_matches!(
+		ConsensusApi::group_state(&NoAgreement, 1),
+		Err(Error(ErrorKind::Unimplemented, _))
+	);
+}
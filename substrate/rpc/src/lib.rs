@@ -46,6 +46,9 @@ mod subscriptions;
 
 pub mod author;
 pub mod chain;
+pub mod consensus;
 pub mod metadata;
+pub mod network;
+pub mod relay;
 pub mod state;
 pub mod system;
@@ -0,0 +1,36 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Substrate network introspection API.
+//!
+//! Meant for debugging connectivity issues.
+
+pub mod error;
+
+#[cfg(test)]
+mod tests;
+
+use self::error::Result;
+
+build_rpc_trait! {
+	/// Network introspection RPC API.
+	pub trait NetworkApi<NetworkState> {
+		/// Get the connected peers with their roles, best blocks and latency,
+		/// and how the node discovers peers.
+		#[rpc(name = "network_state")]
+		fn network_state(&self) -> Result<NetworkState>;
+	}
+}
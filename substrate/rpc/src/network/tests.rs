@@ -0,0 +1,50 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use super::error::*;
+
+struct NoNetwork;
+
+impl NetworkApi<Vec<String>> for NoNetwork {
+	fn network_state(&self) -> Result<Vec<String>> {
+		Err(ErrorKind::Unimplemented.into())
+	}
+}
+
+struct Peers;
+
+impl NetworkApi<Vec<String>> for Peers {
+	fn network_state(&self) -> Result<Vec<String>> {
+		Ok(vec!["abcd".into()])
+	}
+}
+
+#[test]
+fn network_state_works() {
+	assert_eq!(
+		NetworkApi::network_state(&Peers).unwrap(),
+		vec!["abcd".to_owned()]
+	);
+}
+
+#[test]
+fn network_state_reports_errors() {
+	assert_matches!(
+		NetworkApi::network_state(&NoNetwork),
+		Err(Error(ErrorKind::Unimplemented, _))
+	);
+}
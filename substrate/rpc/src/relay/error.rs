@@ -0,0 +1,42 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Relay RPC module errors.
+
+use rpc;
+
+error_chain! {
+	errors {
+		/// The requested data can't be provided.
+		Unavailable(reason: String) {
+			description("data unavailable"),
+			display("Data unavailable: {}", reason),
+		}
+	}
+}
+
+impl From<Error> for rpc::Error {
+	fn from(e: Error) -> Self {
+		match e {
+			Error(ErrorKind::Unavailable(reason), _) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(-1),
+				message: format!("Data unavailable: {}", reason),
+				data: None,
+			},
+			_ => rpc::Error::internal_error(),
+		}
+	}
+}
@@ -0,0 +1,50 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Simplified chain state API for front-ends.
+//!
+//! Answers the few questions a dapp front-end asks of a trusted node, with
+//! results which may be cached for a short while.
+
+pub mod error;
+
+#[cfg(test)]
+mod tests;
+
+pub use primitives::Bytes;
+
+use self::error::Result;
+
+build_rpc_trait! {
+	/// Simplified chain state API.
+	pub trait RelayApi<Header, AccountId, Balance, ParaId> {
+		/// Get the header of the best block.
+		#[rpc(name = "relay_bestHead")]
+		fn best_head(&self) -> Result<Header>;
+
+		/// Get the header of the latest finalized block.
+		#[rpc(name = "relay_finalizedHead")]
+		fn finalized_head(&self) -> Result<Header>;
+
+		/// Get the free balance of an account at the best block.
+		#[rpc(name = "relay_balance")]
+		fn balance(&self, AccountId) -> Result<Balance>;
+
+		/// Get the head data of a parachain at the best block, if it's active.
+		#[rpc(name = "relay_parachainHead")]
+		fn parachain_head(&self, ParaId) -> Result<Option<Bytes>>;
+	}
+}
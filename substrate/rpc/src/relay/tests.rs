@@ -0,0 +1,51 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use super::error::*;
+
+struct Genesis;
+
+impl RelayApi<u64, u8, u128, u32> for Genesis {
+	fn best_head(&self) -> Result<u64> {
+		Ok(0)
+	}
+
+	fn finalized_head(&self) -> Result<u64> {
+		Ok(0)
+	}
+
+	fn balance(&self, account: u8) -> Result<u128> {
+		Ok(account as u128 * 10)
+	}
+
+	fn parachain_head(&self, _para_id: u32) -> Result<Option<Bytes>> {
+		Err(ErrorKind::Unavailable("no parachains".into()).into())
+	}
+}
+
+#[test]
+fn balance_works() {
+	assert_eq!(RelayApi::balance(&Genesis, 2).unwrap(), 20);
+}
+
+#[test]
+fn parachain_head_reports_errors() {
+	assert_matches!(
+		RelayApi::parachain_head(&Genesis, 1),
+		Err(Error(ErrorKind::Unavailable(_), _))
+	);
+}
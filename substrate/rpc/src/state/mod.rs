@@ -27,6 +27,7 @@ use client::{self, Client, CallExecutor};
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::Block as BlockT;
 use primitives::storage::{StorageKey, StorageData};
+use primitives::Bytes;
 use primitives::hexdisplay::HexDisplay;
 use state_machine;
 
@@ -66,6 +67,11 @@ build_rpc_trait! {
 		/// Call a contract at the best block.
 		#[rpc(name = "state_call")]
 		fn call(&self, String, Vec<u8>) -> Result<Vec<u8>>;
+
+		/// Dry-run the upgrade hooks of the given runtime code at the best block.
+		/// Returns the storage keys they would change, or `None` if they wouldn't run.
+		#[rpc(name = "state_tryRuntimeUpgrade")]
+		fn try_runtime_upgrade(&self, Bytes) -> Result<Option<Vec<StorageKey>>>;
 	}
 }
 
@@ -82,7 +88,7 @@ impl<B, E, Block> StateApi<Block::Hash> for Arc<Client<B, E, Block>> where
 
 	fn call_at(&self, method: String, data: Vec<u8>, block: Block::Hash) -> Result<Vec<u8>> {
 		trace!(target: "rpc", "Calling runtime at {:?} for method {} ({})", block, method, HexDisplay::from(&data));
-		Ok(self.as_ref().executor().call(&BlockId::Hash(block), &method, &data)?.return_data)
+		Ok(self.as_ref().call(&BlockId::Hash(block), &method, &data)?.return_data)
 	}
 
 	fn storage_hash_at(&self, key: StorageKey, block: Block::Hash) -> Result<Block::Hash> {
@@ -109,4 +115,10 @@ impl<B, E, Block> StateApi<Block::Hash> for Arc<Client<B, E, Block>> where
 	fn call(&self, method: String, data: Vec<u8>) -> Result<Vec<u8>> {
 		self.call_at(method, data, self.as_ref().info()?.chain.best_hash)
 	}
+
+	fn try_runtime_upgrade(&self, code: Bytes) -> Result<Option<Vec<StorageKey>>> {
+		let best = self.as_ref().info()?.chain.best_hash;
+		trace!(target: "rpc", "Dry-running runtime upgrade at {:?}", best);
+		Ok(self.as_ref().dry_run_upgrade(&BlockId::Hash(best), code.0)?)
+	}
 }
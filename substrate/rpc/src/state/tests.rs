@@ -39,3 +39,14 @@ fn should_call_contract() {
 		Err(Error(ErrorKind::Client(client::error::ErrorKind::Execution(_)), _))
 	)
 }
+
+#[test]
+fn should_fail_dry_running_upgrade_without_hooks() {
+	let client = Arc::new(test_client::new());
+	let code = client.code_at(&BlockId::Hash(client.genesis_hash())).unwrap();
+
+	assert_matches!(
+		StateApi::try_runtime_upgrade(&client, Bytes(code)),
+		Err(Error(ErrorKind::Client(client::error::ErrorKind::Execution(_)), _))
+	)
+}
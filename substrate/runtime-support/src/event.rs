@@ -0,0 +1,51 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Events of the runtime.
+//!
+//! Modules declare an `Event<T>` type of their own, which the runtime collects
+//! into an overarching event type with `impl_outer_event!`. That type is the
+//! `Event` of the system module, which keeps the events deposited in a block.
+
+/// Implement the overarching event type of a runtime, over the `Event<T>` types
+/// of the given modules. Each module's event is encoded after the given index.
+#[macro_export]
+macro_rules! impl_outer_event {
+	(
+		$(#[$attr:meta])*
+		pub enum $name:ident for $concrete:ident {
+			$(
+				$module:ident = $id:expr,
+			)*
+		}
+	) => {
+		$(#[$attr])*
+		#[allow(non_camel_case_types)]
+		pub enum $name {
+			$(
+				$module ( $module::Event<$concrete> ),
+			)*
+		}
+		impl_outer_dispatch_common! { $name, $($module = $id,)* }
+		$(
+			impl From<$module::Event<$concrete>> for $name {
+				fn from(x: $module::Event<$concrete>) -> Self {
+					$name::$module(x)
+				}
+			}
+		)*
+	}
+}
@@ -31,10 +31,13 @@ pub use self::storage::generator::Storage as GenericStorage;
 
 pub mod dispatch;
 pub mod storage;
+pub mod upgrade;
+mod event;
 mod hashable;
 
 pub use self::storage::{StorageVec, StorageList, StorageValue, StorageMap};
 pub use self::hashable::Hashable;
+pub use self::upgrade::OnRuntimeUpgrade;
 pub use self::dispatch::{Parameter, Dispatchable, Callable, AuxDispatchable, AuxCallable, IsSubType, IsAuxSubType};
 pub use runtime_io::print;
 
@@ -0,0 +1,96 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hooks run when the runtime is upgraded.
+//!
+//! The spec version the hooks last ran for is kept in storage, so each bump of
+//! the spec version runs them exactly once, at the start of the first block
+//! executed by the new runtime. Hooks are told the spec version they migrate
+//! from. Chains which predate the hooks count as spec version 0; a chain which
+//! starts at a later version should put it under `LAST_UPGRADE_KEY` in its
+//! genesis storage.
+
+use storage::unhashed;
+
+/// Storage key of the spec version the upgrade hooks last ran for.
+pub const LAST_UPGRADE_KEY: &'static [u8] = b":last_upgrade";
+
+/// Something which migrates its storage when the runtime is upgraded.
+pub trait OnRuntimeUpgrade {
+	/// Migrate storage from the layout of the given spec version.
+	fn on_runtime_upgrade(from_spec_version: u32);
+}
+
+impl OnRuntimeUpgrade for () {
+	fn on_runtime_upgrade(_from_spec_version: u32) {}
+}
+
+impl<A: OnRuntimeUpgrade, B: OnRuntimeUpgrade> OnRuntimeUpgrade for (A, B) {
+	fn on_runtime_upgrade(from_spec_version: u32) {
+		A::on_runtime_upgrade(from_spec_version);
+		B::on_runtime_upgrade(from_spec_version);
+	}
+}
+
+/// The spec version the upgrade hooks last ran for.
+pub fn last_upgrade() -> u32 {
+	unhashed::get_or(LAST_UPGRADE_KEY, 0)
+}
+
+/// Run the hooks of `U` unless they already ran for `spec_version` or a later
+/// one. Returns whether they ran.
+pub fn apply<U: OnRuntimeUpgrade>(spec_version: u32) -> bool {
+	let last = last_upgrade();
+	if last >= spec_version {
+		return false;
+	}
+
+	U::on_runtime_upgrade(last);
+	unhashed::put(LAST_UPGRADE_KEY, &spec_version);
+	true
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use runtime_io::{TestExternalities, with_externalities};
+
+	const MIGRATED_FROM: &'static [u8] = b":test:migrated_from";
+
+	struct Migration;
+	impl OnRuntimeUpgrade for Migration {
+		fn on_runtime_upgrade(from_spec_version: u32) {
+			let mut migrated: Vec<u32> = unhashed::get_or_default(MIGRATED_FROM);
+			migrated.push(from_spec_version);
+			unhashed::put(MIGRATED_FROM, &migrated);
+		}
+	}
+
+	#[test]
+	fn hooks_run_once_per_spec_version() {
+		let mut t = TestExternalities::new();
+		with_externalities(&mut t, || {
+			assert!(!apply::<Migration>(0));
+			assert!(apply::<Migration>(1));
+			assert!(!apply::<Migration>(1));
+			assert!(apply::<(Migration, ())>(3));
+			assert!(!apply::<Migration>(2));
+
+			assert_eq!(last_upgrade(), 3);
+			assert_eq!(unhashed::get::<Vec<u32>>(MIGRATED_FROM), Some(vec![0, 1]));
+		});
+	}
+}
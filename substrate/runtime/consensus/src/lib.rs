@@ -37,13 +37,15 @@ extern crate substrate_runtime_primitives as primitives;
 extern crate substrate_codec as codec;
 extern crate substrate_runtime_system as system;
 extern crate substrate_primitives;
+extern crate substrate_misbehavior_check as misbehavior_check;
 
 use rstd::prelude::*;
-use runtime_support::{storage, Parameter};
+use runtime_support::{storage, Parameter, StorageMap};
 use runtime_support::dispatch::Result;
 use runtime_support::storage::unhashed::StorageVec;
 use primitives::traits::{RefInto, MaybeSerializeDebug, MaybeEmpty};
 use primitives::bft::MisbehaviorReport;
+use substrate_primitives::AuthorityId;
 
 pub const AUTHORITY_AT: &'static [u8] = b":auth:";
 pub const AUTHORITY_COUNT: &'static [u8] = b":auth:len";
@@ -58,9 +60,21 @@ pub const CODE: &'static [u8] = b":code";
 
 pub type KeyValue = (Vec<u8>, Vec<u8>);
 
+/// Handler for proven misbehavior of an authority, e.g. to punish its owner.
+pub trait OnMisbehaviorReported {
+	/// The authority with the given key has been proven to misbehave. An error
+	/// rejects the report, leaving the offence unrecorded.
+	fn on_misbehavior_reported(target: &AuthorityId) -> Result;
+}
+
+impl OnMisbehaviorReported for () {
+	fn on_misbehavior_reported(_: &AuthorityId) -> Result { Ok(()) }
+}
+
 pub trait Trait: system::Trait {
 	type PublicAux: RefInto<Self::AccountId> + MaybeEmpty;		// MaybeEmpty is for Timestamp's usage.
 	type SessionKey: Parameter + Default + MaybeSerializeDebug;
+	type OnMisbehaviorReported: OnMisbehaviorReported;
 }
 
 decl_module! {
@@ -78,6 +92,13 @@ decl_module! {
 	}
 }
 
+decl_storage! {
+	trait Store for Module<T: Trait>;
+
+	// Offences which have been reported, keyed by `MisbehaviorReport::offence`.
+	pub ReportedOffences get(offence_reported): b"con:rep:" => default map [ Vec<u8> => bool ];
+}
+
 impl<T: Trait> Module<T> {
 	/// Get the current set of authorities. These are the session keys.
 	pub fn authorities() -> Vec<T::SessionKey> {
@@ -98,9 +119,25 @@ impl<T: Trait> Module<T> {
 		Ok(())
 	}
 
-	/// Report some misbehaviour.
-	fn report_misbehavior(_aux: &T::PublicAux, _report: MisbehaviorReport<T::Hash, T::BlockNumber>) -> Result {
-		// TODO.
+	/// Whether the offence proven by a misbehavior report has already been reported.
+	pub fn is_reported(report: &MisbehaviorReport<T::Hash, T::BlockNumber>) -> bool {
+		Self::offence_reported(report.offence())
+	}
+
+	/// Report some misbehaviour, proven by two conflicting messages signed by its target.
+	/// The offence may be reported once, and is handed to `T::OnMisbehaviorReported`
+	/// to be punished.
+	fn report_misbehavior(_aux: &T::PublicAux, report: MisbehaviorReport<T::Hash, T::BlockNumber>) -> Result {
+		ensure!(
+			misbehavior_check::evaluate_misbehavior::<(), _>(&report.target, report.parent_hash, &report.misbehavior),
+			"misbehavior report not proven by its evidence"
+		);
+
+		let offence = report.offence();
+		ensure!(!Self::offence_reported(&offence), "offence already reported");
+
+		T::OnMisbehaviorReported::on_misbehavior_reported(&report.target)?;
+		<ReportedOffences<T>>::insert(offence, true);
 		Ok(())
 	}
 
@@ -639,8 +639,10 @@ mod tests {
 	impl consensus::Trait for Test {
 		type PublicAux = <Self as HasPublicAux>::PublicAux;
 		type SessionKey = u64;
+		type OnMisbehaviorReported = ();
 	}
 	impl system::Trait for Test {
+		const MAX_BLOCK_WEIGHT: u32 = 1024 * 1024;
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = H256;
@@ -648,6 +650,7 @@ mod tests {
 		type Digest = Digest;
 		type AccountId = u64;
 		type Header = Header;
+		type Event = ();
 	}
 	impl session::Trait for Test {
 		type ConvertAccountIdToSessionKey = Identity;
@@ -657,6 +660,7 @@ mod tests {
 		type Balance = u64;
 		type DetermineContractAddress = staking::DummyContractAddressFor;
 		type AccountIndex = u64;
+		type Event = ();
 	}
 	impl democracy::Trait for Test {
 		type Proposal = Proposal;
@@ -65,6 +65,7 @@ mod internal {
 		Stale,
 		Future,
 		CantPay,
+		FullBlock,
 	}
 
 	pub enum ApplyOutcome {
@@ -79,7 +80,8 @@ pub struct Executive<
 	Lookup,
 	Payment,
 	Finalisation,
->(PhantomData<(System, Block, Lookup, Payment, Finalisation)>);
+	Upgrade,
+>(PhantomData<(System, Block, Lookup, Payment, Finalisation, Upgrade)>);
 
 impl<
 	System: system::Trait,
@@ -87,13 +89,16 @@ impl<
 	Lookup: AuxLookup<Source=<Block::Extrinsic as Checkable>::Address, Target=System::AccountId>,
 	Payment: MakePayment<System::AccountId>,
 	Finalisation: Executable,
-> Executive<System, Block, Lookup, Payment, Finalisation> where
+	Upgrade: Executable,
+> Executive<System, Block, Lookup, Payment, Finalisation, Upgrade> where
 	Block::Extrinsic: Checkable<AccountId=System::AccountId> + Slicable,
 	<Block::Extrinsic as Checkable>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId>
 {
-	/// Start the execution of a particular block.
+	/// Start the execution of a particular block, running any storage
+	/// migrations of a newly upgraded runtime first.
 	pub fn initialise_block(header: &System::Header) {
 		<system::Module<System>>::initialise(header.number(), header.parent_hash(), header.extrinsics_root());
+		Upgrade::execute();
 	}
 
 	fn initial_checks(block: &Block) {
@@ -124,6 +129,7 @@ impl<
 		extrinsics.into_iter().for_each(Self::apply_extrinsic_no_note);
 
 		// post-transactional book-keeping.
+		<system::Module<System>>::note_finished_extrinsics();
 		Finalisation::execute();
 
 		// any final checks
@@ -133,6 +139,7 @@ impl<
 	/// Finalise the block - it is up the caller to ensure that all header fields are valid
 	/// except state-root.
 	pub fn finalise_block() -> System::Header {
+		<system::Module<System>>::note_finished_extrinsics();
 		Finalisation::execute();
 
 		// setup extrinsics
@@ -154,6 +161,7 @@ impl<
 			Err(internal::ApplyError::BadSignature(_)) => Err(ApplyError::BadSignature),
 			Err(internal::ApplyError::Stale) => Err(ApplyError::Stale),
 			Err(internal::ApplyError::Future) => Err(ApplyError::Future),
+			Err(internal::ApplyError::FullBlock) => Err(ApplyError::FullBlock),
 		}
 	}
 
@@ -166,11 +174,18 @@ impl<
 			Err(internal::ApplyError::CantPay) => panic!("All extrinsics should have sender able to pay their fees"),
 			Err(internal::ApplyError::BadSignature(_)) => panic!("All extrinsics should be properly signed"),
 			Err(internal::ApplyError::Stale) | Err(internal::ApplyError::Future) => panic!("All extrinsics should have the correct nonce"),
+			Err(internal::ApplyError::FullBlock) => panic!("All extrinsics should fit in the block"),
 		}
 	}
 
 	/// Actually apply an extrinsic given its `encoded_len`; this doesn't note its hash.
 	fn apply_extrinsic_no_note_with_len(uxt: Block::Extrinsic, encoded_len: usize) -> result::Result<internal::ApplyOutcome, internal::ApplyError> {
+		// check the block has room for the extrinsic, whether it pays fees or not.
+		let weight = <system::Module<System>>::block_weight().saturating_add(encoded_len as u32);
+		if weight > System::MAX_BLOCK_WEIGHT {
+			return Err(internal::ApplyError::FullBlock)
+		}
+
 		// Verify the signature is good.
 		let xt = uxt.check(Lookup::lookup).map_err(internal::ApplyError::BadSignature)?;
 
@@ -194,6 +209,7 @@ impl<
 		let r = xt.apply();
 
 		<system::ExtrinsicIndex<System>>::put(<system::ExtrinsicIndex<System>>::get() + 1u32);
+		<system::BlockWeight<System>>::put(weight);
 
 		r.map(|_| internal::ApplyOutcome::Success).or_else(|e| Ok(internal::ApplyOutcome::Fail(e)))
 	}
@@ -240,8 +256,10 @@ mod tests {
 	impl consensus::Trait for Test {
 		type PublicAux = <Self as HasPublicAux>::PublicAux;
 		type SessionKey = u64;
+		type OnMisbehaviorReported = ();
 	}
 	impl system::Trait for Test {
+		const MAX_BLOCK_WEIGHT: u32 = 1024 * 1024;
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = substrate_primitives::H256;
@@ -249,6 +267,7 @@ mod tests {
 		type Digest = Digest;
 		type AccountId = u64;
 		type Header = Header;
+		type Event = ();
 	}
 	impl session::Trait for Test {
 		type ConvertAccountIdToSessionKey = Identity;
@@ -258,6 +277,7 @@ mod tests {
 		type Balance = u64;
 		type DetermineContractAddress = staking::DummyContractAddressFor;
 		type AccountIndex = u64;
+		type Event = ();
 	}
 	impl timestamp::Trait for Test {
 		const TIMESTAMP_SET_POSITION: u32 = 0;
@@ -265,7 +285,7 @@ mod tests {
 	}
 
 	type TestXt = primitives::testing::TestXt<Call<Test>>;
-	type Executive = super::Executive<Test, Block<TestXt>, NullLookup, staking::Module<Test>, (session::Module<Test>, staking::Module<Test>)>;
+	type Executive = super::Executive<Test, Block<TestXt>, NullLookup, staking::Module<Test>, (session::Module<Test>, staking::Module<Test>), ()>;
 
 	#[test]
 	fn staking_balance_transfer_dispatch_works() {
@@ -296,6 +316,27 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn full_block_rejects_extrinsic() {
+		let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+		t.extend(staking::GenesisConfig::<Test> {
+			balances: vec![(1, 111)],
+			..Default::default()
+		}.build_storage().unwrap());
+		let xt = primitives::testing::TestXt((1, 0, Call::transfer(2.into(), 69)));
+		let encoded_len = xt.encode().len() as u32;
+		with_externalities(&mut t, || {
+			Executive::initialise_block(&Header::new(1, H256::default(), H256::default(), [69u8; 32].into(), Digest::default()));
+			<system::BlockWeight<Test>>::put(<Test as system::Trait>::MAX_BLOCK_WEIGHT - encoded_len + 1);
+			assert_eq!(Executive::apply_extrinsic(xt.clone()), Err(ApplyError::FullBlock));
+			assert_eq!(<staking::Module<Test>>::voting_balance(&1), 111);
+
+			<system::BlockWeight<Test>>::put(<Test as system::Trait>::MAX_BLOCK_WEIGHT - encoded_len);
+			assert_eq!(Executive::apply_extrinsic(xt), Ok(ApplyOutcome::Success));
+			assert_eq!(<system::Module<Test>>::block_weight(), <Test as system::Trait>::MAX_BLOCK_WEIGHT);
+		});
+	}
+
 	fn new_test_ext() -> runtime_io::TestExternalities {
 		let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap();
 		t.extend(consensus::GenesisConfig::<Test>::default().build_storage().unwrap());
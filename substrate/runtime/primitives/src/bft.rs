@@ -213,6 +213,27 @@ pub struct MisbehaviorReport<Hash, Number> {
 	pub misbehavior: MisbehaviorKind<Hash>,
 }
 
+impl<Hash: Slicable, Number> MisbehaviorReport<Hash, Number> {
+	/// An encoding of the offence proven by this report: who misbehaved, in
+	/// which way, and in which round on top of which parent. All reports of the
+	/// same offence share it, whichever pair of conflicting messages they carry.
+	pub fn offence(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		self.parent_hash.using_encoded(|s| v.extend(s));
+		self.target.using_encoded(|s| v.extend(s));
+
+		let (code, round) = match self.misbehavior {
+			MisbehaviorKind::BftDoublePrepare(round, _, _) => (MisbehaviorCode::BftDoublePrepare, round),
+			MisbehaviorKind::BftDoubleCommit(round, _, _) => (MisbehaviorCode::BftDoubleCommit, round),
+		};
+
+		(code as i8).using_encoded(|s| v.extend(s));
+		round.using_encoded(|s| v.extend(s));
+
+		v
+	}
+}
+
 impl<Hash: Slicable, Number: Slicable> Slicable for MisbehaviorReport<Hash, Number> {
 	fn encode(&self) -> Vec<u8> {
 		let mut v = Vec::new();
@@ -308,4 +329,29 @@ mod test {
 		let encoded = report.encode();
 		assert_eq!(MisbehaviorReport::<H256, u64>::decode(&mut &encoded[..]).unwrap(), report);
 	}
+
+	#[test]
+	fn offence_ignores_evidence() {
+		let report = |round, sig| MisbehaviorReport::<H256, u64> {
+			parent_hash: [0; 32].into(),
+			parent_number: 999,
+			target: [1; 32].into(),
+			misbehavior: MisbehaviorKind::BftDoublePrepare(
+				round,
+				([2; 32].into(), [sig; 64].into()),
+				([4; 32].into(), [5; 64].into()),
+			),
+		};
+
+		assert_eq!(report(1, 3).offence(), report(1, 6).offence());
+		assert!(report(1, 3).offence() != report(2, 3).offence());
+
+		let mut commit = report(1, 3);
+		commit.misbehavior = MisbehaviorKind::BftDoubleCommit(
+			1,
+			([2; 32].into(), [3; 64].into()),
+			([4; 32].into(), [5; 64].into()),
+		);
+		assert!(commit.offence() != report(1, 3).offence());
+	}
 }
@@ -128,6 +128,8 @@ pub enum ApplyError {
 	Future = 2,
 	/// Sending account had too low a balance.
 	CantPay = 3,
+	/// The block has no room left for the extrinsic.
+	FullBlock = 4,
 }
 impl codec::Slicable for ApplyError {
 	fn decode<I: codec::Input>(input: &mut I) -> Option<Self> {
@@ -136,6 +138,7 @@ impl codec::Slicable for ApplyError {
 			x if x == ApplyError::Stale as u8 => Some(ApplyError::Stale),
 			x if x == ApplyError::Future as u8 => Some(ApplyError::Future),
 			x if x == ApplyError::CantPay as u8 => Some(ApplyError::CantPay),
+			x if x == ApplyError::FullBlock as u8 => Some(ApplyError::FullBlock),
 			_ => None,
 		}
 	}
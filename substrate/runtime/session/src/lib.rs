@@ -60,6 +60,13 @@ impl<T> OnSessionChange<T> for () {
 	fn on_session_change(_: bool, _: T) {}
 }
 
+impl<T: Clone, A: OnSessionChange<T>, B: OnSessionChange<T>> OnSessionChange<T> for (A, B) {
+	fn on_session_change(normal_rotation: bool, time_elapsed: T) {
+		A::on_session_change(normal_rotation, time_elapsed.clone());
+		B::on_session_change(normal_rotation, time_elapsed);
+	}
+}
+
 pub trait Trait: timestamp::Trait {
 	type ConvertAccountIdToSessionKey: Convert<Self::AccountId, Self::SessionKey>;
 	type OnSessionChange: OnSessionChange<Self::Moment>;
@@ -97,6 +104,8 @@ decl_storage! {
 	LastLengthChange: b"ses:llc" => T::BlockNumber;
 	// The next key for a given validator.
 	NextKeyFor: b"ses:nxt:" => map [ T::AccountId => T::SessionKey ];
+	// The validator which currently holds a given session key.
+	pub KeyOwner get(key_owner): b"ses:own:" => map [ T::SessionKey => T::AccountId ];
 	// The next session length.
 	NextSessionLength: b"ses:nln" => T::BlockNumber;
 }
@@ -139,10 +148,17 @@ impl<T: Trait> Module<T> {
 	/// Called by `staking::next_era()` only. `next_session` should be called after this in order to
 	/// update the session keys to the next validator set.
 	pub fn set_validators(new: &[T::AccountId]) {
+		for key in <consensus::Module<T>>::authorities() {
+			<KeyOwner<T>>::remove(key);
+		}
+
+		let keys = new.iter().cloned().map(T::ConvertAccountIdToSessionKey::convert).collect::<Vec<_>>();
+		for (key, validator) in keys.iter().zip(new) {
+			<KeyOwner<T>>::insert(key, validator);
+		}
+
 		<Validators<T>>::put(&new.to_vec());			// TODO: optimise.
-		<consensus::Module<T>>::set_authorities(
-			&new.iter().cloned().map(T::ConvertAccountIdToSessionKey::convert).collect::<Vec<_>>()
-		);
+		<consensus::Module<T>>::set_authorities(&keys);
 	}
 
 	/// Hook to be called after transaction processing.
@@ -177,8 +193,16 @@ impl<T: Trait> Module<T> {
 		T::OnSessionChange::on_session_change(normal_rotation, time_elapsed);
 
 		// Update any changes in session keys.
+		let authorities = <consensus::Module<T>>::authorities();
 		Self::validators().iter().enumerate().for_each(|(i, v)| {
 			if let Some(n) = <NextKeyFor<T>>::take(v) {
+				if let Some(old) = authorities.get(i) {
+					if Self::key_owner(old).as_ref() == Some(v) {
+						<KeyOwner<T>>::remove(old);
+					}
+				}
+
+				<KeyOwner<T>>::insert(&n, v);
 				<consensus::Module<T>>::set_authority(i as u32, &n);
 			}
 		});
@@ -248,13 +272,21 @@ impl<T: Trait> primitives::BuildStorage for GenesisConfig<T>
 		use runtime_io::twox_128;
 		use codec::Slicable;
 		use primitives::traits::As;
-		Ok(map![
+		let mut r: runtime_io::TestExternalities = map![
 			twox_128(<SessionLength<T>>::key()).to_vec() => self.session_length.encode(),
 			twox_128(<CurrentIndex<T>>::key()).to_vec() => T::BlockNumber::sa(0).encode(),
 			twox_128(<CurrentStart<T>>::key()).to_vec() => T::Moment::zero().encode(),
 			twox_128(<Validators<T>>::key()).to_vec() => self.validators.encode(),
 			twox_128(<BrokenPercentLate<T>>::key()).to_vec() => self.broken_percent_late.encode()
-		])
+		];
+
+		// the genesis authorities are expected to be the validators' default keys.
+		for validator in self.validators {
+			let key = T::ConvertAccountIdToSessionKey::convert(validator.clone());
+			r.insert(twox_128(&<KeyOwner<T>>::key_for(key)).to_vec(), validator.encode());
+		}
+
+		Ok(r)
 	}
 }
 
@@ -275,8 +307,10 @@ mod tests {
 	impl consensus::Trait for Test {
 		type PublicAux = <Self as HasPublicAux>::PublicAux;
 		type SessionKey = u64;
+		type OnMisbehaviorReported = ();
 	}
 	impl system::Trait for Test {
+		const MAX_BLOCK_WEIGHT: u32 = 1024 * 1024;
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = H256;
@@ -284,6 +318,7 @@ mod tests {
 		type Digest = Digest;
 		type AccountId = u64;
 		type Header = Header;
+		type Event = ();
 	}
 	impl timestamp::Trait for Test {
 		const TIMESTAMP_SET_POSITION: u32 = 0;
@@ -432,4 +467,26 @@ mod tests {
 			assert_eq!(Consensus::authorities(), vec![1, 5, 3]);
 		});
 	}
+
+	#[test]
+	fn key_owners_follow_key_changes() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(Session::key_owner(2), Some(2));
+
+			System::set_block_number(1);
+			assert_ok!(Session::set_key(&2, 5));
+			assert_eq!(Session::key_owner(5), None);
+
+			System::set_block_number(2);
+			Session::check_rotate_session();
+			assert_eq!(Session::key_owner(5), Some(2));
+			assert_eq!(Session::key_owner(2), None);
+			assert_eq!(Session::key_owner(1), Some(1));
+
+			Session::set_validators(&[3, 4]);
+			assert_eq!(Session::key_owner(4), Some(4));
+			assert_eq!(Session::key_owner(1), None);
+			assert_eq!(Session::key_owner(5), None);
+		});
+	}
 }
@@ -128,6 +128,74 @@ pub trait Trait: system::Trait + session::Trait {
 	/// Type used for storing an account's index; implies the maximum number of accounts the system
 	/// can hold.
 	type AccountIndex: Parameter + Member + Slicable + SimpleArithmetic + As<u8> + As<u16> + As<u32> + As<u64> + As<usize> + Copy;
+	/// The overarching event type, which the events of this module convert into.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+/// An event of this module.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+pub enum RawEvent<Balance, AccountId> {
+	/// Some balance was transferred: sender, destination and value.
+	Transfer(AccountId, AccountId, Balance),
+	/// An account declared the desire to stake.
+	Staked(AccountId),
+	/// An account retracted the desire to stake.
+	Unstaked(AccountId),
+	/// An account nominated another: nominator and target.
+	Nominated(AccountId, AccountId),
+	/// An account retracted its nomination.
+	Unnominated(AccountId),
+}
+
+/// The event of this module for a runtime.
+pub type Event<T> = RawEvent<<T as Trait>::Balance, <T as system::Trait>::AccountId>;
+
+impl<Balance, AccountId> From<RawEvent<Balance, AccountId>> for () {
+	fn from(_: RawEvent<Balance, AccountId>) {}
+}
+
+impl<Balance: Slicable, AccountId: Slicable> Slicable for RawEvent<Balance, AccountId> {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		match *self {
+			RawEvent::Transfer(ref from, ref to, ref value) => {
+				v.push(0);
+				from.using_encoded(|s| v.extend(s));
+				to.using_encoded(|s| v.extend(s));
+				value.using_encoded(|s| v.extend(s));
+			}
+			RawEvent::Staked(ref who) => {
+				v.push(1);
+				who.using_encoded(|s| v.extend(s));
+			}
+			RawEvent::Unstaked(ref who) => {
+				v.push(2);
+				who.using_encoded(|s| v.extend(s));
+			}
+			RawEvent::Nominated(ref who, ref target) => {
+				v.push(3);
+				who.using_encoded(|s| v.extend(s));
+				target.using_encoded(|s| v.extend(s));
+			}
+			RawEvent::Unnominated(ref who) => {
+				v.push(4);
+				who.using_encoded(|s| v.extend(s));
+			}
+		}
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(match input.read_byte()? {
+			0 => RawEvent::Transfer(Slicable::decode(input)?, Slicable::decode(input)?, Slicable::decode(input)?),
+			1 => RawEvent::Staked(Slicable::decode(input)?),
+			2 => RawEvent::Unstaked(Slicable::decode(input)?),
+			3 => RawEvent::Nominated(Slicable::decode(input)?, Slicable::decode(input)?),
+			4 => RawEvent::Unnominated(Slicable::decode(input)?),
+			_ => return None,
+		})
+	}
 }
 
 decl_module! {
@@ -267,6 +335,11 @@ impl<T: Trait> Module<T> {
 		Self::free_balance(who) + Self::reserved_balance(who)
 	}
 
+	/// The fee charged for a signed transaction of the given encoded length.
+	pub fn transaction_fee(encoded_len: usize) -> T::Balance {
+		Self::transaction_base_fee() + Self::transaction_byte_fee() * <T::Balance as As<u64>>::sa(encoded_len as u64)
+	}
+
 	/// Some result as `slash(who, value)` (but without the side-effects) assuming there are no
 	/// balance changes in the meantime and only the reserved balance is not taken into account.
 	pub fn can_slash(who: &T::AccountId, value: T::Balance) -> bool {
@@ -310,6 +383,7 @@ impl<T: Trait> Module<T> {
 		// commit anything that made it this far to storage
 		if let Some(commit) = Self::effect_transfer(aux.ref_into(), &dest, value, &DirectAccountDb)? {
 			<AccountDb<T>>::merge(&mut DirectAccountDb, commit);
+			Self::deposit_event(RawEvent::Transfer(aux.ref_into().clone(), dest, value));
 		}
 		Ok(())
 	}
@@ -326,6 +400,7 @@ impl<T: Trait> Module<T> {
 		intentions.push(aux.clone());
 		<Intentions<T>>::put(intentions);
 		<Bondage<T>>::insert(aux, T::BlockNumber::max_value());
+		Self::deposit_event(RawEvent::Staked(aux.clone()));
 		Ok(())
 	}
 
@@ -343,6 +418,7 @@ impl<T: Trait> Module<T> {
 		intentions.swap_remove(position);
 		<Intentions<T>>::put(intentions);
 		<Bondage<T>>::insert(aux.ref_into(), Self::current_era() + Self::bonding_duration());
+		Self::deposit_event(RawEvent::Unstaked(aux.clone()));
 		Ok(())
 	}
 
@@ -364,6 +440,7 @@ impl<T: Trait> Module<T> {
 		// Update bondage
 		<Bondage<T>>::insert(aux.ref_into(), T::BlockNumber::max_value());
 
+		Self::deposit_event(RawEvent::Nominated(aux.clone(), target));
 		Ok(())
 	}
 
@@ -391,6 +468,7 @@ impl<T: Trait> Module<T> {
 
 		// update bondage
 		<Bondage<T>>::insert(aux.ref_into(), Self::current_era() + Self::bonding_duration());
+		Self::deposit_event(RawEvent::Unnominated(source.clone()));
 		Ok(())
 	}
 
@@ -848,6 +926,11 @@ impl<T: Trait> Module<T> {
 			None
 		})
 	}
+
+	/// Deposit one of this module's events.
+	fn deposit_event(event: Event<T>) {
+		<system::Module<T>>::deposit_event(<T as Trait>::Event::from(event).into());
+	}
 }
 
 impl<T: Trait> Executable for Module<T> {
@@ -875,7 +958,7 @@ impl<T: Trait> AuxLookup for Module<T> {
 impl<T: Trait> MakePayment<T::AccountId> for Module<T> {
 	fn make_payment(transactor: &T::AccountId, encoded_len: usize) -> Result {
 		let b = Self::free_balance(transactor);
-		let transaction_fee = Self::transaction_base_fee() + Self::transaction_byte_fee() * <T::Balance as As<u64>>::sa(encoded_len as u64);
+		let transaction_fee = Self::transaction_fee(encoded_len);
 		if b < transaction_fee {
 			return Err("not enough funds for transaction fee");
 		}
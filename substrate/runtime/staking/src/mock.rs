@@ -23,7 +23,7 @@ use primitives::traits::{HasPublicAux, Identity};
 use primitives::testing::{Digest, Header};
 use substrate_primitives::H256;
 use runtime_io;
-use {GenesisConfig, Module, Trait, consensus, session, system, timestamp};
+use {GenesisConfig, Module, RawEvent, Trait, consensus, session, system, timestamp};
 use super::DummyContractAddressFor;
 
 // Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
@@ -35,8 +35,10 @@ impl HasPublicAux for Test {
 impl consensus::Trait for Test {
 	type PublicAux = <Self as HasPublicAux>::PublicAux;
 	type SessionKey = u64;
+	type OnMisbehaviorReported = ();
 }
 impl system::Trait for Test {
+	const MAX_BLOCK_WEIGHT: u32 = 1024 * 1024;
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
@@ -44,6 +46,7 @@ impl system::Trait for Test {
 	type Digest = Digest;
 	type AccountId = u64;
 	type Header = Header;
+	type Event = RawEvent<u64, u64>;
 }
 impl session::Trait for Test {
 	type ConvertAccountIdToSessionKey = Identity;
@@ -57,6 +60,7 @@ impl Trait for Test {
 	type Balance = u64;
 	type DetermineContractAddress = DummyContractAddressFor;
 	type AccountIndex = u64;
+	type Event = RawEvent<u64, u64>;
 }
 
 pub fn new_test_ext(ext_deposit: u64, session_length: u64, sessions_per_era: u64, current_era: u64, monied: bool, reward: u64) -> runtime_io::TestExternalities {
@@ -425,6 +425,23 @@ fn staking_balance_transfer_works() {
 		assert_ok!(Staking::transfer(&1, 2.into(), 69));
 		assert_eq!(Staking::voting_balance(&1), 42);
 		assert_eq!(Staking::voting_balance(&2), 69);
+		assert_eq!(System::events(), vec![system::EventRecord {
+			phase: system::Phase::ApplyExtrinsic(0),
+			event: RawEvent::Transfer(1, 2, 69),
+		}]);
+	});
+}
+
+#[test]
+fn transaction_fee_should_be_paid() {
+	with_externalities(&mut new_test_ext(0, 1, 3, 1, false, 0), || {
+		<TransactionBaseFee<Test>>::put(10);
+		<TransactionByteFee<Test>>::put(2);
+		assert_eq!(Staking::transaction_fee(5), 20);
+		<FreeBalance<Test>>::insert(1, 30);
+		assert_ok!(Staking::make_payment(&1, 5));
+		assert_eq!(Staking::free_balance(&1), 10);
+		assert_noop!(Staking::make_payment(&1, 5), "not enough funds for transaction fee");
 	});
 }
 
@@ -14,8 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate Demo.  If not, see <http://www.gnu.org/licenses/>.
 
-//! System manager: Handles lowest level stuff like depositing logs, basic set up and take down of
-//! temporary storage entries, access to old block hashes.
+//! System manager: Handles lowest level stuff like depositing logs and events, basic set up and
+//! take down of temporary storage entries, access to old block hashes.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -42,11 +42,10 @@ use primitives::traits::{self, CheckEqual, SimpleArithmetic, SimpleBitOps, Zero,
 	Hashing, Member, MaybeDisplay};
 use runtime_support::{StorageValue, StorageMap, Parameter};
 use safe_mix::TripletMix;
+use codec::{Slicable, Input};
 
 #[cfg(any(feature = "std", test))]
 use rstd::marker::PhantomData;
-#[cfg(any(feature = "std", test))]
-use codec::Slicable;
 
 #[cfg(any(feature = "std", test))]
 use runtime_io::{twox_128, TestExternalities};
@@ -63,6 +62,9 @@ pub fn extrinsics_data_root<H: Hashing>(xts: Vec<Vec<u8>>) -> H::Output {
 }
 
 pub trait Trait: Eq + Clone {
+	// the weight of the extrinsics a block may hold, each weighing its encoded length.
+	const MAX_BLOCK_WEIGHT: u32;
+
 	type Index: Parameter + Member + Default + MaybeDisplay + SimpleArithmetic + Copy;
 	type BlockNumber: Parameter + Member + MaybeDisplay + SimpleArithmetic + Default + Bounded + Copy + rstd::hash::Hash;
 	type Hash: Parameter + Member + MaybeDisplay + SimpleBitOps + Default + Copy + CheckEqual + rstd::hash::Hash + AsRef<[u8]>;
@@ -75,6 +77,64 @@ pub trait Trait: Eq + Clone {
 		Hash = Self::Hash,
 		Digest = Self::Digest
 	>;
+	type Event: Parameter + Member;
+}
+
+/// The part of a block's execution an event was deposited in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub enum Phase {
+	/// Applying the extrinsic with the given index.
+	ApplyExtrinsic(u32),
+	/// Finalising the block, after all extrinsics were applied.
+	Finalization,
+}
+
+impl Slicable for Phase {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		match *self {
+			Phase::ApplyExtrinsic(index) => {
+				v.push(0);
+				index.using_encoded(|s| v.extend(s));
+			}
+			Phase::Finalization => v.push(1),
+		}
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => u32::decode(input).map(Phase::ApplyExtrinsic),
+			1 => Some(Phase::Finalization),
+			_ => None,
+		}
+	}
+}
+
+/// An event deposited in a block, with the phase it was deposited in.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct EventRecord<E> {
+	/// The phase of the block's execution.
+	pub phase: Phase,
+	/// The event.
+	pub event: E,
+}
+
+impl<E: Slicable> Slicable for EventRecord<E> {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.phase.encode();
+		self.event.using_encoded(|s| v.extend(s));
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(EventRecord {
+			phase: Slicable::decode(input)?,
+			event: Slicable::decode(input)?,
+		})
+	}
 }
 
 decl_module! {
@@ -89,12 +149,18 @@ decl_storage! {
 
 	pub ExtrinsicIndex get(extrinsic_index): b"sys:xti" => required u32;
 	pub ExtrinsicData get(extrinsic_data): b"sys:xtd" => required map [ u32 => Vec<u8> ];
+	// The weight of the extrinsics applied in the current block.
+	pub BlockWeight get(block_weight): b"sys:block_weight" => default u32;
 	RandomSeed get(random_seed): b"sys:rnd" => required T::Hash;
 	// The current block number being processed. Set by `execute_block`.
 	Number get(block_number): b"sys:num" => required T::BlockNumber;
 	ParentHash get(parent_hash): b"sys:pha" => required T::Hash;
 	ExtrinsicsRoot get(extrinsics_root): b"sys:txr" => required T::Hash;
 	Digest get(digest): b"sys:dig" => default T::Digest;
+	// The events deposited in the current block. Kept until the next block starts.
+	pub Events get(events): b"sys:events" => default Vec<EventRecord<T::Event>>;
+	// The number of extrinsics in the block, once they were all applied.
+	ExtrinsicCount: b"sys:extrinsic_count" => u32;
 }
 
 impl<T: Trait> Module<T> {
@@ -107,12 +173,15 @@ impl<T: Trait> Module<T> {
 		<ExtrinsicsRoot<T>>::put(txs_root);
 		<RandomSeed<T>>::put(Self::calculate_random());
 		<ExtrinsicIndex<T>>::put(0);
+		<Events<T>>::kill();
 	}
 
 	/// Remove temporary "environment" entries in storage.
 	pub fn finalise() -> T::Header {
 		<RandomSeed<T>>::kill();
 		<ExtrinsicIndex<T>>::kill();
+		<ExtrinsicCount<T>>::kill();
+		<BlockWeight<T>>::kill();
 
 		let number = <Number<T>>::take();
 		let parent_hash = <ParentHash<T>>::take();
@@ -129,6 +198,20 @@ impl<T: Trait> Module<T> {
 		<Digest<T>>::put(l);
 	}
 
+	/// Deposits an event in the current block, in the phase of its execution.
+	pub fn deposit_event(event: T::Event) {
+		let phase = if <ExtrinsicCount<T>>::exists() {
+			Phase::Finalization
+		} else {
+			// no extrinsic index is set outside of block execution, as in tests.
+			Phase::ApplyExtrinsic(runtime_support::storage::get_or_default(<ExtrinsicIndex<T>>::key()))
+		};
+
+		let mut events = Self::events();
+		events.push(EventRecord { phase, event });
+		<Events<T>>::put(events);
+	}
+
 	/// Calculate the current block's random seed.
 	fn calculate_random() -> T::Hash {
 		assert!(Self::block_number() > Zero::zero(), "Block number may never be zero");
@@ -184,6 +267,12 @@ impl<T: Trait> Module<T> {
 		<ExtrinsicData<T>>::insert(Self::extrinsic_index(), encoded_xt);
 	}
 
+	/// Note that all extrinsics of the block were applied, so that events from now on are
+	/// deposited in its finalisation.
+	pub fn note_finished_extrinsics() {
+		<ExtrinsicCount<T>>::put(Self::extrinsic_index());
+	}
+
 	/// Remove all extrinsics data and save the extrinsics trie root.
 	pub fn derive_extrinsics() {
 		let extrinsics = (0..Self::extrinsic_index()).map(<ExtrinsicData<T>>::take).collect();
@@ -152,6 +152,7 @@ mod tests {
 		type PublicAux = u64;
 	}
 	impl system::Trait for Test {
+		const MAX_BLOCK_WEIGHT: u32 = 1024 * 1024;
 		type Index = u64;
 		type BlockNumber = u64;
 		type Hash = H256;
@@ -159,10 +160,12 @@ mod tests {
 		type Digest = Digest;
 		type AccountId = u64;
 		type Header = Header;
+		type Event = ();
 	}
 	impl consensus::Trait for Test {
 		type PublicAux = u64;
 		type SessionKey = u64;
+		type OnMisbehaviorReported = ();
 	}
 	impl Trait for Test {
 		const TIMESTAMP_SET_POSITION: u32 = 0;
@@ -33,9 +33,13 @@ extern crate substrate_runtime_std as rstd;
 extern crate substrate_runtime_support as runtime_support;
 
 extern crate substrate_codec as codec;
+extern crate substrate_runtime_primitives as primitives;
 
 use rstd::prelude::*;
+use rstd::marker::PhantomData;
 use codec::Slicable;
+use primitives::traits::Executable;
+use runtime_support::OnRuntimeUpgrade;
 #[cfg(feature = "std")]
 use std::borrow::Cow;
 
@@ -138,6 +142,102 @@ impl Slicable for RuntimeVersion {
 	}
 }
 
+/// Name of the custom section of a Wasm runtime holding its encoded `BuildInfo`.
+pub const BUILD_INFO_SECTION: &'static str = "runtime_build_info";
+
+/// How a runtime was built. Recorded at compile time, so that anyone can check
+/// a runtime was built from the source it claims by building that source with
+/// the same compiler and features, and comparing the code.
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq, Serialize, Deserialize))]
+pub struct BuildInfo {
+	/// Version of the compiler, as reported by `rustc --version`.
+	pub rustc_version: VersionString,
+	/// Cargo features enabled, comma-separated.
+	pub features: VersionString,
+	/// Git tree hash of the source built, suffixed with `-dirty` if there were
+	/// uncommitted changes.
+	pub source_hash: VersionString,
+}
+
+impl Slicable for BuildInfo {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		v.extend(codec::encode_slice(self.rustc_version.as_bytes()));
+		v.extend(codec::encode_slice(self.features.as_bytes()));
+		v.extend(codec::encode_slice(self.source_hash.as_bytes()));
+		v
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn decode<I: codec::Input>(_value: &mut I) -> Option<Self> {
+		unreachable!()
+	}
+
+	#[cfg(feature = "std")]
+	fn decode<I: codec::Input>(value: &mut I) -> Option<Self> {
+		Some(BuildInfo {
+			rustc_version: Cow::Owned(String::from_utf8_lossy(&Vec::decode(value)?).into()),
+			features: Cow::Owned(String::from_utf8_lossy(&Vec::decode(value)?).into()),
+			source_hash: Cow::Owned(String::from_utf8_lossy(&Vec::decode(value)?).into()),
+		})
+	}
+}
+
+/// Read the `BuildInfo` from the custom section of a Wasm runtime, without
+/// executing it.
+#[cfg(feature = "std")]
+pub fn wasm_build_info(code: &[u8]) -> Option<BuildInfo> {
+	let mut section = wasm_custom_section(code, BUILD_INFO_SECTION)?;
+	BuildInfo::decode(&mut section)
+}
+
+// read an unsigned LEB128 integer.
+#[cfg(feature = "std")]
+fn read_leb128(input: &mut &[u8]) -> Option<usize> {
+	let mut result = 0usize;
+	for shift in 0..5 {
+		let (&byte, rest) = input.split_first()?;
+		*input = rest;
+		result |= ((byte & 0x7f) as usize) << (shift * 7);
+		if byte & 0x80 == 0 {
+			return Some(result);
+		}
+	}
+	None
+}
+
+// the contents of the first custom section of a Wasm module with the given name.
+#[cfg(feature = "std")]
+fn wasm_custom_section<'a>(code: &'a [u8], name: &str) -> Option<&'a [u8]> {
+	const HEADER: &'static [u8] = b"\0asm\x01\0\0\0";
+	if !code.starts_with(HEADER) {
+		return None;
+	}
+
+	let mut input = &code[HEADER.len()..];
+	while let Some((&id, rest)) = input.split_first() {
+		input = rest;
+		let size = read_leb128(&mut input)?;
+		if size > input.len() {
+			return None;
+		}
+
+		let (mut section, rest) = input.split_at(size);
+		input = rest;
+		if id != 0 {
+			continue;
+		}
+
+		let name_len = read_leb128(&mut section)?;
+		if name_len <= section.len() && &section[..name_len] == name.as_bytes() {
+			return Some(&section[name_len..]);
+		}
+	}
+
+	None
+}
+
 pub trait Trait {
 	const VERSION: RuntimeVersion;
 }
@@ -152,3 +252,70 @@ impl<T: Trait> Module<T> {
 		T::VERSION.clone()
 	}
 }
+
+/// Runs the upgrade hooks `U` when the spec version of `T` was bumped since
+/// they last ran. Meant to be executed at the start of every block.
+pub struct Upgrade<T, U>(PhantomData<(T, U)>);
+
+impl<T: Trait, U: OnRuntimeUpgrade> Upgrade<T, U> {
+	/// Run the hooks if the spec version was bumped. Returns whether they ran.
+	pub fn apply() -> bool {
+		runtime_support::upgrade::apply::<U>(T::VERSION.spec_version)
+	}
+}
+
+impl<T: Trait, U: OnRuntimeUpgrade> Executable for Upgrade<T, U> {
+	fn execute() {
+		Self::apply();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn build_info() -> BuildInfo {
+		BuildInfo {
+			rustc_version: ver_str!("rustc 1.28.0-nightly"),
+			features: ver_str!(""),
+			source_hash: ver_str!("4b825dc642cb6eb9a060e54bf8d69288fbe4904"),
+		}
+	}
+
+	fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+		let mut v = vec![id, contents.len() as u8];
+		v.extend_from_slice(contents);
+		v
+	}
+
+	fn custom_section(name: &str, contents: &[u8]) -> Vec<u8> {
+		let mut v = vec![name.len() as u8];
+		v.extend_from_slice(name.as_bytes());
+		v.extend_from_slice(contents);
+		section(0, &v)
+	}
+
+	#[test]
+	fn reads_build_info_from_custom_section() {
+		let mut code = b"\0asm\x01\0\0\0".to_vec();
+		code.extend(section(1, &[0x01, 0x60, 0x00, 0x00]));
+		code.extend(custom_section("name", &[1, 2, 3]));
+		code.extend(custom_section(BUILD_INFO_SECTION, &build_info().encode()));
+
+		assert_eq!(wasm_build_info(&code), Some(build_info()));
+	}
+
+	#[test]
+	fn no_build_info_in_malformed_code() {
+		let mut code = b"\0asm\x01\0\0\0".to_vec();
+		code.extend(section(1, &[0x01, 0x60, 0x00, 0x00]));
+		assert_eq!(wasm_build_info(&code), None);
+
+		let mut truncated = b"\0asm\x01\0\0\0".to_vec();
+		truncated.extend(custom_section(BUILD_INFO_SECTION, &build_info().encode()));
+		truncated.pop();
+		assert_eq!(wasm_build_info(&truncated), None);
+
+		assert_eq!(wasm_build_info(&build_info().encode()), None);
+	}
+}
@@ -29,6 +29,10 @@
 //! See `RefWindow` for pruning algorithm details. `StateDb` prunes on each finalization until pruning
 //! constraints are satisfied.
 //!
+//! A retention window of the most recently finalized states can be set independently of the
+//! pruning constraints. States within it are never pruned, so queries a few blocks back keep
+//! working even with aggressive pruning.
+//!
 
 #[macro_use] extern crate log;
 extern crate parking_lot;
@@ -149,11 +153,12 @@ struct StateDbSync<BlockHash: Hash, Key: Hash> {
 	unfinalized: UnfinalizedOverlay<BlockHash, Key>,
 	pruning: Option<RefWindow<BlockHash, Key>>,
 	pinned: HashSet<BlockHash>,
+	retained_finalized: u64,
 }
 
 impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
-	pub fn new<D: MetaDb>(mode: PruningMode, db: &D) -> Result<StateDbSync<BlockHash, Key>, Error<D::Error>> {
-		trace!("StateDb settings: {:?}", mode);
+	pub fn new<D: MetaDb>(mode: PruningMode, retained_finalized: u32, db: &D) -> Result<StateDbSync<BlockHash, Key>, Error<D::Error>> {
+		trace!("StateDb settings: {:?}, retaining {} finalized states", mode, retained_finalized);
 		let unfinalized: UnfinalizedOverlay<BlockHash, Key> = UnfinalizedOverlay::new(db)?;
 		let pruning: Option<RefWindow<BlockHash, Key>> = match mode {
 			PruningMode::Constrained(Constraints {
@@ -168,6 +173,7 @@ impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 			unfinalized,
 			pruning: pruning,
 			pinned: Default::default(),
+			retained_finalized: retained_finalized as u64,
 		})
 	}
 
@@ -193,7 +199,19 @@ impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 		}
 	}
 
+	pub fn reset(&mut self, hash: &BlockHash, number: u64, mut changeset: ChangeSet<Key>) -> CommitSet<Key> {
+		changeset.deleted.clear();
+		let mut commit = self.unfinalized.reset(hash, number);
+		commit.data = changeset;
+		commit
+	}
+
 	pub fn finalize_block(&mut self, hash: &BlockHash) -> CommitSet<Key> {
+		// a block whose state was written in full is final already.
+		if self.unfinalized.last_finalized_hash() == Some(hash) {
+			return CommitSet::default();
+		}
+
 		let mut commit = match self.mode {
 			PruningMode::ArchiveAll => {
 				CommitSet::default()
@@ -221,6 +239,11 @@ impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 					break;
 				}
 
+				// the retention window is kept regardless of other constraints.
+				if pruning.window_size() <= self.retained_finalized {
+					break;
+				}
+
 				if constraints.max_mem.map_or(false, |m| pruning.mem_used() > m) {
 					break;
 				}
@@ -260,8 +283,14 @@ pub struct StateDb<BlockHash: Hash, Key: Hash> {
 impl<BlockHash: Hash, Key: Hash> StateDb<BlockHash, Key> {
 	/// Creates a new instance. Does not expect any metadata in the database.
 	pub fn new<D: MetaDb>(mode: PruningMode, db: &D) -> Result<StateDb<BlockHash, Key>, Error<D::Error>> {
+		Self::with_retention(mode, 0, db)
+	}
+
+	/// Creates a new instance which never prunes the `retained_finalized` most
+	/// recently finalized states, whatever the pruning mode's constraints.
+	pub fn with_retention<D: MetaDb>(mode: PruningMode, retained_finalized: u32, db: &D) -> Result<StateDb<BlockHash, Key>, Error<D::Error>> {
 		Ok(StateDb {
-			db: RwLock::new(StateDbSync::new(mode, db)?)
+			db: RwLock::new(StateDbSync::new(mode, retained_finalized, db)?)
 		})
 	}
 
@@ -270,6 +299,12 @@ impl<BlockHash: Hash, Key: Hash> StateDb<BlockHash, Key> {
 		self.db.write().insert_block(hash, number, parent_hash, changeset)
 	}
 
+	/// Write the full state of a block, discarding all unfinalized blocks. The
+	/// block is final and new blocks are built on top of it.
+	pub fn reset(&self, hash: &BlockHash, number: u64, changeset: ChangeSet<Key>) -> CommitSet<Key> {
+		self.db.write().reset(hash, number, changeset)
+	}
+
 	/// Finalize a previously inserted block.
 	pub fn finalize_block(&self, hash: &BlockHash) -> CommitSet<Key> {
 		self.db.write().finalize_block(hash)
@@ -298,8 +333,12 @@ mod tests {
 	use test::{make_db, make_changeset, TestDb};
 
 	fn make_test_db(settings: PruningMode) -> (TestDb, StateDb<H256, H256>) {
+		make_test_db_with_retention(settings, 0)
+	}
+
+	fn make_test_db_with_retention(settings: PruningMode, retained: u32) -> (TestDb, StateDb<H256, H256>) {
 		let mut db = make_db(&[91, 921, 922, 93, 94]);
-		let state_db = StateDb::new(settings, &db).unwrap();
+		let state_db = StateDb::with_retention(settings, retained, &db).unwrap();
 
 		db.commit(&state_db.insert_block(&H256::from(1), 1, &H256::from(0), make_changeset(&[1], &[91])));
 		db.commit(&state_db.insert_block(&H256::from(21), 2, &H256::from(1), make_changeset(&[21], &[921, 1])));
@@ -351,4 +390,22 @@ mod tests {
 		}));
 		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
 	}
+
+	#[test]
+	fn retention_window_overrides_pruning() {
+		let (db, _) = make_test_db_with_retention(PruningMode::Constrained(Constraints {
+			max_blocks: Some(0),
+			max_mem: None,
+		}), 2);
+		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
+	}
+
+	#[test]
+	fn smaller_retention_window_keeps_pruning_window() {
+		let (db, _) = make_test_db_with_retention(PruningMode::Constrained(Constraints {
+			max_blocks: Some(2),
+			max_mem: None,
+		}), 1);
+		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
+	}
 }
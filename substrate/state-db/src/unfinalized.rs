@@ -234,6 +234,25 @@ impl<BlockHash: Hash, Key: Hash> UnfinalizedOverlay<BlockHash, Key> {
 		commit
 	}
 
+	/// Discard all unfinalized blocks and continue from a block whose state is
+	/// written in full rather than as changes to its parent's.
+	pub fn reset(&mut self, hash: &BlockHash, number: u64) -> CommitSet<Key> {
+		let mut commit = CommitSet::default();
+		for level in self.levels.drain(..) {
+			commit.meta.deleted.extend(level.into_iter().map(|overlay| overlay.journal_key));
+		}
+		self.parents.clear();
+		let last_finalized = (hash.clone(), number);
+		commit.meta.inserted.push((to_meta_key(LAST_FINALIZED, &()), last_finalized.encode()));
+		self.last_finalized = Some(last_finalized);
+		commit
+	}
+
+	/// Hash of the last finalized block, if any.
+	pub fn last_finalized_hash(&self) -> Option<&BlockHash> {
+		self.last_finalized.as_ref().map(|&(ref hash, _)| hash)
+	}
+
 	/// Get a value from the node overlay. This searches in every existing changeset.
 	pub fn get(&self, key: &Key) -> Option<DBValue> {
 		for level in self.levels.iter() {
@@ -356,6 +375,28 @@ mod tests {
 		assert_eq!(overlay.last_finalized, overlay2.last_finalized);
 	}
 
+	#[test]
+	fn reset_discards_unfinalized_blocks() {
+		let h1 = H256::random();
+		let h2 = H256::random();
+		let h3 = H256::random();
+		let mut db = make_db(&[1, 2]);
+		let mut overlay = UnfinalizedOverlay::<H256, H256>::new(&db).unwrap();
+		db.commit(&overlay.insert(&h1, 1, &H256::default(), make_changeset(&[3], &[2])));
+		db.commit(&overlay.insert(&h2, 2, &h1, make_changeset(&[4], &[])));
+
+		db.commit(&overlay.reset(&h3, 10));
+		assert!(!contains(&overlay, 3));
+		assert_eq!(overlay.last_finalized_hash(), Some(&h3));
+		assert_eq!(db.meta.len(), 1);
+
+		// blocks are inserted on top of the reset one.
+		db.commit(&overlay.insert(&H256::random(), 11, &h3, make_changeset(&[5], &[])));
+		let overlay2 = UnfinalizedOverlay::<H256, H256>::new(&db).unwrap();
+		assert_eq!(overlay.levels, overlay2.levels);
+		assert_eq!(overlay.last_finalized, overlay2.last_finalized);
+	}
+
 	#[test]
 	fn insert_finalize_two() {
 		let h1 = H256::random();
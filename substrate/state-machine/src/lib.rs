@@ -46,6 +46,7 @@ mod trie_backend;
 pub use testing::TestExternalities;
 pub use ext::Ext;
 pub use backend::Backend;
+pub use proving_backend::ProvingBackend;
 pub use trie_backend::{TryIntoTrieBackend, TrieBackend, TrieH256, Storage, DBValue};
 
 /// The overlayed changes to state to be queried on top of the backend.
@@ -68,7 +69,8 @@ impl OverlayedChanges {
 			.map(|x| x.as_ref().map(AsRef::as_ref))
 	}
 
-	fn set_storage(&mut self, key: Vec<u8>, val: Option<Vec<u8>>) {
+	/// Set or clear a storage entry prospectively.
+	pub fn set_storage(&mut self, key: Vec<u8>, val: Option<Vec<u8>>) {
 		self.prospective.insert(key, val);
 	}
 
@@ -224,6 +226,7 @@ pub fn prove_execution<B: TryIntoTrieBackend, Exec: CodeExecutor>(
 		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<Error>)?;
 	let proving_backend = proving_backend::ProvingBackend::new(trie_backend);
 	let (result, transaction) = execute(&proving_backend, overlay, exec, method, call_data)?;
+	trace!(target: "state", "Proof of {} execution is {} bytes", method, proving_backend.proof_size());
 	let proof = proving_backend.extract_proof();
 	Ok((result, proof, transaction))
 }
@@ -242,6 +245,31 @@ pub fn execution_proof_check<Exec: CodeExecutor>(
 	execute(&backend, overlay, exec, method, call_data)
 }
 
+/// Read a value from the given state backend, together with the proof of the read:
+/// the storage trie nodes touched on the way to the value.
+pub fn prove_read<B: TryIntoTrieBackend>(
+	backend: B,
+	key: &[u8],
+) -> Result<(Option<Vec<u8>>, Vec<Vec<u8>>), Box<Error>>
+{
+	let trie_backend = backend.try_into_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<Error>)?;
+	let proving_backend = proving_backend::ProvingBackend::new(trie_backend);
+	let result = proving_backend.storage(key).map_err(|e| Box::new(e) as Box<Error>)?;
+	Ok((result, proving_backend.extract_proof()))
+}
+
+/// Check storage read proof, generated by `prove_read` call.
+pub fn read_proof_check(
+	root: [u8; 32],
+	proof: Vec<Vec<u8>>,
+	key: &[u8],
+) -> Result<Option<Vec<u8>>, Box<Error>>
+{
+	let backend = proving_backend::create_proof_check_backend(root.into(), proof)?;
+	backend.storage(key).map_err(|e| Box::new(e) as Box<Error>)
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -345,4 +373,20 @@ mod tests {
 		assert_eq!(remote_result, vec![66]);
 		assert_eq!(remote_result, local_result);
 	}
+
+	#[test]
+	fn prove_read_and_proof_check_works() {
+		// fetch read proof from 'remote' full node
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let (remote_value, remote_proof) = prove_read(remote_backend, b"value2").unwrap();
+
+		// check proof locally
+		let local_value = read_proof_check(remote_root, remote_proof.clone(), b"value2").unwrap();
+		assert_eq!(remote_value, Some(vec![24]));
+		assert_eq!(local_value, remote_value);
+
+		// the proof doesn't hold against another root
+		assert!(read_proof_check([1; 32], remote_proof, b"value2").is_err());
+	}
 }
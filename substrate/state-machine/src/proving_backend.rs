@@ -17,6 +17,7 @@
 //! Proving state machine backend.
 
 use std::cell::RefCell;
+use std::collections::HashMap;
 use ethereum_types::H256 as TrieH256;
 use hashdb::HashDB;
 use memorydb::MemoryDB;
@@ -24,11 +25,30 @@ use patricia_trie::{TrieDB, TrieError, Trie, Recorder};
 use trie_backend::{TrieBackend, Ephemeral};
 use {Error, ExecutionError, Backend, TryIntoTrieBackend};
 
+// Trie nodes touched so far, each recorded once, and their total size.
+struct ProofRecorder {
+	recorder: Recorder,
+	nodes: HashMap<TrieH256, Vec<u8>>,
+	size: usize,
+}
+
+impl ProofRecorder {
+	// move the nodes recorded by the last lookup into the proof.
+	fn note_lookup(&mut self) {
+		for record in self.recorder.drain() {
+			if !self.nodes.contains_key(&record.hash) {
+				self.size += record.data.len();
+				self.nodes.insert(record.hash, record.data.to_vec());
+			}
+		}
+	}
+}
+
 /// Patricia trie-based backend which also tracks all touched storage trie values.
 /// These can be sent to remote node and used as a proof of execution.
 pub struct ProvingBackend {
 	backend: TrieBackend,
-	proof_recorder: RefCell<Recorder>,
+	proof_recorder: RefCell<ProofRecorder>,
 }
 
 impl ProvingBackend {
@@ -36,17 +56,29 @@ impl ProvingBackend {
 	pub fn new(backend: TrieBackend) -> Self {
 		ProvingBackend {
 			backend,
-			proof_recorder: RefCell::new(Recorder::new()),
+			proof_recorder: RefCell::new(ProofRecorder {
+				recorder: Recorder::new(),
+				nodes: HashMap::new(),
+				size: 0,
+			}),
 		}
 	}
 
+	/// Size in bytes of the proof gathered so far. Each touched trie node is
+	/// counted once, however often it is read.
+	pub fn proof_size(&self) -> usize {
+		self.proof_recorder.borrow().size
+	}
+
 	/// Consume the backend, extracting the gathered proof in lexicographical order
 	/// by value.
 	pub fn extract_proof(self) -> Vec<Vec<u8>> {
-		self.proof_recorder.into_inner().drain()
+		let mut proof: Vec<_> = self.proof_recorder.into_inner().nodes
 			.into_iter()
-			.map(|n| n.data.to_vec())
-			.collect()
+			.map(|(_, data)| data)
+			.collect();
+		proof.sort();
+		proof
 	}
 }
 
@@ -65,8 +97,10 @@ impl Backend for ProvingBackend {
 
 		let mut proof_recorder = self.proof_recorder.try_borrow_mut()
 			.expect("only fails when already borrowed; storage() is non-reentrant; qed");
-		TrieDB::new(&eph, &self.backend.root()).map_err(map_e)?
-			.get_with(key, &mut *proof_recorder).map(|x| x.map(|val| val.to_vec())).map_err(map_e)
+		let value = TrieDB::new(&eph, &self.backend.root()).map_err(map_e)?
+			.get_with(key, &mut proof_recorder.recorder).map(|x| x.map(|val| val.to_vec())).map_err(map_e);
+		proof_recorder.note_lookup();
+		value
 	}
 
 	fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], f: F) {
@@ -127,6 +161,20 @@ mod tests {
 		assert!(!backend.extract_proof().is_empty());
 	}
 
+	#[test]
+	fn proof_size_counts_each_node_once() {
+		let backend = test_proving();
+		assert_eq!(backend.proof_size(), 0);
+
+		backend.storage(b"key").unwrap();
+		let size = backend.proof_size();
+		assert!(size > 0);
+
+		backend.storage(b"key").unwrap();
+		assert_eq!(backend.proof_size(), size);
+		assert_eq!(backend.extract_proof().iter().map(|n| n.len()).sum::<usize>(), size);
+	}
+
 	#[test]
 	fn proof_is_invalid_when_does_not_contains_root() {
 		assert!(create_proof_check_backend(1.into(), vec![]).is_err());
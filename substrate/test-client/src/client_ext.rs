@@ -63,9 +63,9 @@ impl TestClient for Client<Backend, Executor, runtime::Block> {
 fn fake_justify(header: &runtime::Header) -> bft::UncheckedJustification<runtime::Hash> {
 	let hash = header.hash();
 	let authorities = vec![
-		Keyring::Alice.into(),
-		Keyring::Bob.into(),
-		Keyring::Charlie.into(),
+		Keyring::Alice.pair(),
+		Keyring::Bob.pair(),
+		Keyring::Charlie.pair(),
 	];
 
 	bft::UncheckedJustification {
@@ -75,7 +75,7 @@ fn fake_justify(header: &runtime::Header) -> bft::UncheckedJustification<runtime
 				bft::generic::Vote::Commit(1, hash).into(),
 				key,
 				header.parent_hash
-			);
+			).expect("local keys always sign; qed");
 
 			match msg {
 				bft::generic::LocalizedMessage::Vote(vote) => vote.signature,
@@ -96,6 +96,36 @@ impl Slicable for Transfer {
 	}
 }
 
+/// Events deposited by the test-runtime, kept in storage until the next block.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub enum Event {
+	/// Some balance was transferred: sender, destination and amount.
+	Transferred(AccountId, AccountId, u64),
+}
+
+impl Slicable for Event {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		match *self {
+			Event::Transferred(ref from, ref to, ref amount) => {
+				v.push(0);
+				from.using_encoded(|s| v.extend(s));
+				to.using_encoded(|s| v.extend(s));
+				amount.using_encoded(|s| v.extend(s));
+			}
+		}
+		v
+	}
+
+	fn decode<I: ::codec::Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => Slicable::decode(input).map(|(from, to, amount)| Event::Transferred(from, to, amount)),
+			_ => None,
+		}
+	}
+}
+
 /// Extrinsic for test-runtime.
 #[derive(Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
@@ -168,6 +198,7 @@ pub mod api {
 		initialise_block => |header| system::initialise_block(header),
 		execute_block => |block| system::execute_block(block),
 		apply_extrinsic => |utx| system::execute_transaction(utx),
-		finalise_block => |()| system::finalise_block()
+		finalise_block => |()| system::finalise_block(),
+		events => |()| system::events()
 	);
 }
@@ -22,7 +22,7 @@ use runtime_io::{storage_root, enumerated_trie_root};
 use runtime_support::storage::{self, StorageValue, StorageMap};
 use runtime_primitives::traits::{Hashing, BlakeTwo256};
 use codec::{KeyedVec, Slicable};
-use super::{AccountId, BlockNumber, Extrinsic, H256 as Hash, Block, Header};
+use super::{AccountId, BlockNumber, Event, Extrinsic, H256 as Hash, Block, Header};
 
 const NONCE_OF: &[u8] = b"nonce:";
 const BALANCE_OF: &[u8] = b"balance:";
@@ -35,6 +35,8 @@ storage_items! {
 	// The current block number being processed. Set by `execute_block`.
 	Number: b"sys:num" => required BlockNumber;
 	ParentHash: b"sys:pha" => required Hash;
+	// The events deposited in the current block. Kept until the next block starts.
+	Events: b"sys:events" => default Vec<Event>;
 }
 
 pub fn balance_of(who: AccountId) -> u64 {
@@ -45,6 +47,11 @@ pub fn nonce_of(who: AccountId) -> u64 {
 	storage::get_or(&who.to_keyed_vec(NONCE_OF), 0)
 }
 
+/// Get the events deposited in the latest block.
+pub fn events() -> Vec<Event> {
+	<Events>::get()
+}
+
 /// Get authorities ar given block.
 pub fn authorities() -> Vec<::primitives::AuthorityId> {
 	let len: u32 = storage::unhashed::get(AUTHORITY_COUNT).expect("There are always authorities in test-runtime");
@@ -58,6 +65,7 @@ pub fn initialise_block(header: Header) {
 	<Number>::put(&header.number);
 	<ParentHash>::put(&header.parent_hash);
 	<ExtrinsicIndex>::put(0);
+	<Events>::kill();
 }
 
 /// Actually execute all transitioning for `block`.
@@ -71,6 +79,9 @@ pub fn execute_block(block: Block) {
 	info_expect_equal_hash(&header.extrinsics_root, &txs_root);
 	assert!(header.extrinsics_root == txs_root, "Transaction trie root must be valid.");
 
+	// events of the previous block are replaced.
+	<Events>::kill();
+
 	// execute transactions
 	block.extrinsics.iter().for_each(execute_transaction_backend);
 
@@ -138,6 +149,10 @@ fn execute_transaction_backend(utx: &Extrinsic) {
 	let to_balance: u64 = storage::get_or(&to_balance_key, 0);
 	storage::put(&from_balance_key, &(from_balance - tx.amount));
 	storage::put(&to_balance_key, &(to_balance + tx.amount));
+
+	let mut events = <Events>::get();
+	events.push(Event::Transferred(tx.from, tx.to, tx.amount));
+	<Events>::put(&events);
 }
 
 #[cfg(feature = "std")]
@@ -164,7 +179,7 @@ mod tests {
 	use runtime_io::{with_externalities, twox_128, TestExternalities};
 	use codec::{Joiner, KeyedVec};
 	use keyring::Keyring;
-	use ::{Header, Digest, Extrinsic, Transfer};
+	use ::{Header, Digest, Event, Extrinsic, Transfer};
 
 	fn new_test_ext() -> TestExternalities {
 		map![
@@ -217,7 +232,7 @@ mod tests {
 			header: Header {
 				parent_hash: [69u8; 32].into(),
 				number: 1,
-				state_root: hex!("0dd8210adaf581464cc68555814a787ed491f8c608d0a0dbbf2208a6d44190b1").into(),
+				state_root: hex!("2f17a1539265c3c44a05d1fa64a15356a339f578473864e61d26ba1165e97a88").into(),
 				extrinsics_root: hex!("951508f2cc0071500a74765ab0fb2f280fdcdd329d5f989dda675010adee99d6").into(),
 				digest: Digest { logs: vec![], },
 			},
@@ -236,13 +251,16 @@ mod tests {
 
 			assert_eq!(balance_of(Keyring::Alice.to_raw_public().into()), 42);
 			assert_eq!(balance_of(Keyring::Bob.to_raw_public().into()), 69);
+			assert_eq!(events(), vec![
+				Event::Transferred(Keyring::Alice.to_raw_public().into(), Keyring::Bob.to_raw_public().into(), 69),
+			]);
 		});
 
 		let b = Block {
 			header: Header {
 				parent_hash: b.header.hash(),
 				number: 2,
-				state_root: hex!("c93f2fd494c386fa32ee76b6198a7ccf5db12c02c3a79755fd2d4646ec2bf8d7").into(),
+				state_root: hex!("485e23afdf6a2ff0f2b1e5fde8f89aae0cbc8a11f66f5917f0cf5cc5fd2a2e30").into(),
 				extrinsics_root: hex!("3563642676d7e042c894eedc579ba2d6eeedf9a6c66d9d557599effc9f674372").into(),
 				digest: Digest { logs: vec![], },
 			},
@@ -268,6 +286,10 @@ mod tests {
 			assert_eq!(balance_of(Keyring::Alice.to_raw_public().into()), 0);
 			assert_eq!(balance_of(Keyring::Bob.to_raw_public().into()), 42);
 			assert_eq!(balance_of(Keyring::Charlie.to_raw_public().into()), 69);
+			assert_eq!(events(), vec![
+				Event::Transferred(Keyring::Bob.to_raw_public().into(), Keyring::Alice.to_raw_public().into(), 27),
+				Event::Transferred(Keyring::Alice.to_raw_public().into(), Keyring::Charlie.to_raw_public().into(), 69),
+			]);
 		});
 	}
 }